@@ -0,0 +1,89 @@
+use image::RgbImage;
+
+use crate::filters::{self, Filter};
+
+// 基本调整参数，不修改原图，只作用于显示和导出
+// 亮度、对比度、饱和度的范围为 -100..=100，gamma 为 0.2..=3.0
+// 模糊半径为 0..=20，锐化强度为 0..=100
+// 处理顺序：自动色阶 → 基本调整 → 模糊 → 锐化 → 滤镜
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adjustments {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    pub gamma: f32,
+    pub blur: f32,
+    pub sharpen: f32,
+    pub filter: Option<Filter>,
+    pub auto_levels: bool, // 按直方图自动拉伸每个通道
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Adjustments {
+            brightness: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+            gamma: 1.0,
+            blur: 0.0,
+            sharpen: 0.0,
+            filter: None,
+            auto_levels: false,
+        }
+    }
+}
+
+impl Adjustments {
+    pub fn is_identity(&self) -> bool {
+        *self == Adjustments::default()
+    }
+
+    // 亮度、对比度和 gamma 只与单个通道的值有关，合并成一张查找表
+    fn lookup_table(&self) -> [u8; 256] {
+        let offset = self.brightness / 100.0 * 128.0;
+        let contrast = ((self.contrast + 100.0) / 100.0).powi(2);
+        let inv_gamma = 1.0 / self.gamma;
+        let mut table = [0u8; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let v = (i as f32 - 128.0) * contrast + 128.0 + offset;
+            let v = (v.clamp(0.0, 255.0) / 255.0).powf(inv_gamma) * 255.0;
+            *entry = v.round() as u8;
+        }
+        table
+    }
+
+    /// 返回应用调整后的新图片
+    pub fn apply(&self, img: &RgbImage) -> RgbImage {
+        let table = self.lookup_table();
+        // 自动色阶和基本调整都是逐通道映射，合并成每个通道一张表
+        let tables = if self.auto_levels {
+            filters::auto_levels_tables(img).map(|levels| levels.map(|v| table[v as usize]))
+        } else {
+            [table; 3]
+        };
+        let saturation = 1.0 + self.saturation / 100.0;
+        let mut out = img.clone();
+        for pixel in out.pixels_mut() {
+            let [r, g, b] = [0, 1, 2].map(|c| tables[c][pixel[c] as usize] as f32);
+            if saturation != 1.0 {
+                // 以亮度为中心拉伸或压缩色彩
+                let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+                pixel.0 = [r, g, b]
+                    .map(|c| (luma + (c - luma) * saturation).round().clamp(0.0, 255.0) as u8);
+            } else {
+                pixel.0 = [r as u8, g as u8, b as u8];
+            }
+        }
+        if self.blur > 0.0 {
+            out = filters::gaussian_blur(&out, self.blur);
+        }
+        if self.sharpen > 0.0 {
+            // 强度 100 对应 2 倍的细节增强
+            out = filters::unsharp_mask(&out, self.sharpen / 50.0, 2.0);
+        }
+        if let Some(filter) = self.filter {
+            filter.apply(&mut out);
+        }
+        out
+    }
+}
@@ -0,0 +1,516 @@
+use iced::Point;
+
+use crate::codes::{Bits, CodeKind, DetectedCode};
+
+// 在图片高度上均匀取这么多行扫描
+const SCAN_ROWS: usize = 60;
+
+// EAN/UPC 左侧数字的 L 编码（空、条、空、条的模块宽度），G 编码为其反序，右侧 R 编码宽度与 L 相同
+const DIGIT_PATTERNS: [[usize; 4]; 10] = [
+    [3, 2, 1, 1],
+    [2, 2, 2, 1],
+    [2, 1, 2, 2],
+    [1, 4, 1, 1],
+    [1, 1, 3, 2],
+    [1, 2, 3, 1],
+    [1, 1, 1, 4],
+    [1, 3, 1, 2],
+    [1, 2, 1, 3],
+    [3, 1, 1, 2],
+];
+
+// EAN-13 左侧六个数字中 G 编码的位置决定第一个数字
+const FIRST_DIGIT_PARITY: [u8; 10] = [0x00, 0x0B, 0x0D, 0x0E, 0x13, 0x19, 0x1C, 0x15, 0x16, 0x1A];
+
+// Code 128 的 106 个符号（条、空交替的模块宽度），之后是终止符
+const CODE128_PATTERNS: [[usize; 6]; 106] = [
+    [2, 1, 2, 2, 2, 2],
+    [2, 2, 2, 1, 2, 2],
+    [2, 2, 2, 2, 2, 1],
+    [1, 2, 1, 2, 2, 3],
+    [1, 2, 1, 3, 2, 2],
+    [1, 3, 1, 2, 2, 2],
+    [1, 2, 2, 2, 1, 3],
+    [1, 2, 2, 3, 1, 2],
+    [1, 3, 2, 2, 1, 2],
+    [2, 2, 1, 2, 1, 3],
+    [2, 2, 1, 3, 1, 2],
+    [2, 3, 1, 2, 1, 2],
+    [1, 1, 2, 2, 3, 2],
+    [1, 2, 2, 1, 3, 2],
+    [1, 2, 2, 2, 3, 1],
+    [1, 1, 3, 2, 2, 2],
+    [1, 2, 3, 1, 2, 2],
+    [1, 2, 3, 2, 2, 1],
+    [2, 2, 3, 2, 1, 1],
+    [2, 2, 1, 1, 3, 2],
+    [2, 2, 1, 2, 3, 1],
+    [2, 1, 3, 2, 1, 2],
+    [2, 2, 3, 1, 1, 2],
+    [3, 1, 2, 1, 3, 1],
+    [3, 1, 1, 2, 2, 2],
+    [3, 2, 1, 1, 2, 2],
+    [3, 2, 1, 2, 2, 1],
+    [3, 1, 2, 2, 1, 2],
+    [3, 2, 2, 1, 1, 2],
+    [3, 2, 2, 2, 1, 1],
+    [2, 1, 2, 1, 2, 3],
+    [2, 1, 2, 3, 2, 1],
+    [2, 3, 2, 1, 2, 1],
+    [1, 1, 1, 3, 2, 3],
+    [1, 3, 1, 1, 2, 3],
+    [1, 3, 1, 3, 2, 1],
+    [1, 1, 2, 3, 1, 3],
+    [1, 3, 2, 1, 1, 3],
+    [1, 3, 2, 3, 1, 1],
+    [2, 1, 1, 3, 1, 3],
+    [2, 3, 1, 1, 1, 3],
+    [2, 3, 1, 3, 1, 1],
+    [1, 1, 2, 1, 3, 3],
+    [1, 1, 2, 3, 3, 1],
+    [1, 3, 2, 1, 3, 1],
+    [1, 1, 3, 1, 2, 3],
+    [1, 1, 3, 3, 2, 1],
+    [1, 3, 3, 1, 2, 1],
+    [3, 1, 3, 1, 2, 1],
+    [2, 1, 1, 3, 3, 1],
+    [2, 3, 1, 1, 3, 1],
+    [2, 1, 3, 1, 1, 3],
+    [2, 1, 3, 3, 1, 1],
+    [2, 1, 3, 1, 3, 1],
+    [3, 1, 1, 1, 2, 3],
+    [3, 1, 1, 3, 2, 1],
+    [3, 3, 1, 1, 2, 1],
+    [3, 1, 2, 1, 1, 3],
+    [3, 1, 2, 3, 1, 1],
+    [3, 3, 2, 1, 1, 1],
+    [3, 1, 4, 1, 1, 1],
+    [2, 2, 1, 4, 1, 1],
+    [4, 3, 1, 1, 1, 1],
+    [1, 1, 1, 2, 2, 4],
+    [1, 1, 1, 4, 2, 2],
+    [1, 2, 1, 1, 2, 4],
+    [1, 2, 1, 4, 2, 1],
+    [1, 4, 1, 1, 2, 2],
+    [1, 4, 1, 2, 2, 1],
+    [1, 1, 2, 2, 1, 4],
+    [1, 1, 2, 4, 1, 2],
+    [1, 2, 2, 1, 1, 4],
+    [1, 2, 2, 4, 1, 1],
+    [1, 4, 2, 1, 1, 2],
+    [1, 4, 2, 2, 1, 1],
+    [2, 4, 1, 2, 1, 1],
+    [2, 2, 1, 1, 1, 4],
+    [4, 1, 3, 1, 1, 1],
+    [2, 4, 1, 1, 1, 2],
+    [1, 3, 4, 1, 1, 1],
+    [1, 1, 1, 2, 4, 2],
+    [1, 2, 1, 1, 4, 2],
+    [1, 2, 1, 2, 4, 1],
+    [1, 1, 4, 2, 1, 2],
+    [1, 2, 4, 1, 1, 2],
+    [1, 2, 4, 2, 1, 1],
+    [4, 1, 1, 2, 1, 2],
+    [4, 2, 1, 1, 1, 2],
+    [4, 2, 1, 2, 1, 1],
+    [2, 1, 2, 1, 4, 1],
+    [2, 1, 4, 1, 2, 1],
+    [4, 1, 2, 1, 2, 1],
+    [1, 1, 1, 1, 4, 3],
+    [1, 1, 1, 3, 4, 1],
+    [1, 3, 1, 1, 4, 1],
+    [1, 1, 4, 1, 1, 3],
+    [1, 1, 4, 3, 1, 1],
+    [4, 1, 1, 1, 1, 3],
+    [4, 1, 1, 3, 1, 1],
+    [1, 1, 3, 1, 4, 1],
+    [1, 1, 4, 1, 3, 1],
+    [3, 1, 1, 1, 4, 1],
+    [4, 1, 1, 1, 3, 1],
+    [2, 1, 1, 4, 1, 2],
+    [2, 1, 1, 2, 1, 4],
+    [2, 1, 1, 2, 3, 2],
+];
+const CODE128_STOP: [usize; 7] = [2, 3, 3, 1, 1, 1, 2];
+
+// Code 128 的特殊符号
+const CODE_C: usize = 99;
+const CODE_B: usize = 100;
+const CODE_A: usize = 101;
+const FNC1: usize = 102;
+const SHIFT: usize = 98;
+const START_A: usize = 103;
+
+// 一行中识别出的条形码
+struct Hit {
+    kind: CodeKind,
+    text: String,
+    left: usize,
+    right: usize,
+}
+
+/// 逐行扫描一维条形码（EAN-13、UPC-A、EAN-8 和 Code 128），正反两个方向都尝试。
+/// 至少在两行中识别出相同内容才算找到，位置为这些行覆盖的矩形
+pub fn detect(bits: &Bits) -> Vec<DetectedCode> {
+    let step = (bits.height / SCAN_ROWS).max(1);
+    let mut found: Vec<(Hit, usize, usize, usize)> = Vec::new(); // 结果、首行、末行、行数
+    for y in (step / 2..bits.height).step_by(step) {
+        let row: Vec<bool> = (0..bits.width).map(|x| bits.get(x, y)).collect();
+        let reversed: Vec<bool> = row.iter().rev().copied().collect();
+        let mut hits = decode_row(&row);
+        hits.extend(decode_row(&reversed).into_iter().map(|hit| Hit {
+            left: bits.width - hit.right,
+            right: bits.width - hit.left,
+            ..hit
+        }));
+        for hit in hits {
+            match found
+                .iter_mut()
+                .find(|(other, ..)| other.kind == hit.kind && other.text == hit.text)
+            {
+                Some((other, _, last, count)) => {
+                    other.left = other.left.min(hit.left);
+                    other.right = other.right.max(hit.right);
+                    *last = y;
+                    *count += 1;
+                }
+                None => found.push((hit, y, y, 1)),
+            }
+        }
+    }
+    found
+        .into_iter()
+        .filter(|&(_, _, _, count)| count >= 2)
+        .map(|(hit, first, last, _)| {
+            let (left, right) = (hit.left as f32, hit.right as f32);
+            let (top, bottom) = (first as f32, (last + 1) as f32);
+            DetectedCode {
+                kind: hit.kind,
+                text: hit.text,
+                corners: [
+                    Point::new(left, top),
+                    Point::new(right, top),
+                    Point::new(right, bottom),
+                    Point::new(left, bottom),
+                ],
+            }
+        })
+        .collect()
+}
+
+// 把一行像素转为交替的空、条宽度，第一段总是空（可能为 0）
+fn runs(row: &[bool]) -> Vec<usize> {
+    let mut runs = vec![0];
+    let mut dark = false;
+    for &pixel in row {
+        if pixel != dark {
+            runs.push(0);
+            dark = pixel;
+        }
+        *runs.last_mut().unwrap() += 1;
+    }
+    runs
+}
+
+fn decode_row(row: &[bool]) -> Vec<Hit> {
+    let runs = runs(row);
+    let mut offsets = Vec::with_capacity(runs.len() + 1);
+    let mut x = 0;
+    for &run in &runs {
+        offsets.push(x);
+        x += run;
+    }
+    offsets.push(x);
+
+    let mut hits = Vec::new();
+    let mut i = 0;
+    // 偶数位置是空，从每段空之后的条开始尝试
+    while i + 1 < runs.len() {
+        let decoded = decode_ean(&runs, i + 1, 13)
+            .or_else(|| decode_ean(&runs, i + 1, 8))
+            .or_else(|| decode_code128(&runs, i + 1));
+        match decoded {
+            Some((kind, text, end)) => {
+                hits.push(Hit {
+                    kind,
+                    text,
+                    left: offsets[i + 1],
+                    right: offsets[end],
+                });
+                i = end - end % 2;
+            }
+            None => i += 2,
+        }
+    }
+    hits
+}
+
+// 各段宽度与标准模块宽度的差异，单段差异超过 max_individual 个模块时返回 None
+fn variance(runs: &[usize], pattern: &[usize], max_individual: f32) -> Option<f32> {
+    let total: usize = runs.iter().sum();
+    let modules: usize = pattern.iter().sum();
+    if total < modules {
+        return None;
+    }
+    let unit = total as f32 / modules as f32;
+    let mut sum = 0.0;
+    for (&run, &expected) in runs.iter().zip(pattern) {
+        let difference = (run as f32 - expected as f32 * unit).abs();
+        if difference > max_individual * unit {
+            return None;
+        }
+        sum += difference;
+    }
+    Some(sum / total as f32)
+}
+
+// 最匹配的符号及其差异
+fn best_match<const N: usize>(
+    runs: &[usize],
+    patterns: &[[usize; N]],
+    max_average: f32,
+) -> Option<usize> {
+    patterns
+        .iter()
+        .enumerate()
+        .filter_map(|(index, pattern)| Some((variance(runs, pattern, 0.7)?, index)))
+        .filter(|&(variance, _)| variance < max_average)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, index)| index)
+}
+
+// 从 start 开始解码 EAN-13（digits 为 13）或 EAN-8，返回码制、内容和结束后的位置
+fn decode_ean(runs: &[usize], start: usize, digits: usize) -> Option<(CodeKind, String, usize)> {
+    let half = digits / 2;
+    let len = 3 + half * 4 + 5 + half * 4 + 3;
+    let end = start + len;
+    if end > runs.len() {
+        return None;
+    }
+    let symbol = &runs[start..end];
+    let modules = 3 + half * 7 + 5 + half * 7 + 3;
+    let unit = symbol.iter().sum::<usize>() as f32 / modules as f32;
+    // 两侧至少三个模块宽的空白
+    let quiet_after = runs.get(end).copied().unwrap_or(0);
+    if (runs[start - 1] as f32) < unit * 3.0
+        || (end < runs.len() && (quiet_after as f32) < unit * 3.0)
+    {
+        return None;
+    }
+    let middle = 3 + half * 4;
+    let guards_ok = [(0, 3), (middle, 5), (len - 3, 3)]
+        .iter()
+        .all(|&(at, count)| {
+            variance(&symbol[at..at + count], &[1, 1, 1, 1, 1][..count], 0.7)
+                .is_some_and(|v| v < 0.48)
+        });
+    if !guards_ok {
+        return None;
+    }
+
+    let mut values = Vec::with_capacity(digits);
+    let mut parity = 0u8;
+    let reversed = DIGIT_PATTERNS.map(|[a, b, c, d]| [d, c, b, a]);
+    for (k, chunk) in symbol[3..middle].chunks(4).enumerate() {
+        let l = best_match(chunk, &DIGIT_PATTERNS, 0.48);
+        // EAN-8 左侧只用 L 编码
+        let g = if digits == 13 {
+            best_match(chunk, &reversed, 0.48)
+        } else {
+            None
+        };
+        let (l_var, g_var) = (
+            l.and_then(|d| variance(chunk, &DIGIT_PATTERNS[d], 0.7)),
+            g.and_then(|d| variance(chunk, &reversed[d], 0.7)),
+        );
+        match (l, g) {
+            (Some(d), Some(_)) if l_var <= g_var => values.push(d),
+            (_, Some(d)) => {
+                values.push(d);
+                parity |= 1 << (half - 1 - k);
+            }
+            (Some(d), None) => values.push(d),
+            (None, None) => return None,
+        }
+    }
+    for chunk in symbol[middle + 5..len - 3].chunks(4) {
+        values.push(best_match(chunk, &DIGIT_PATTERNS, 0.48)?);
+    }
+
+    if digits == 13 {
+        let first = FIRST_DIGIT_PARITY.iter().position(|&p| p == parity)?;
+        values.insert(0, first);
+    }
+    let count = values.len();
+    let sum: usize = values[..count - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| if (count - 1 - i) % 2 == 1 { d * 3 } else { d })
+        .sum();
+    if (10 - sum % 10) % 10 != values[count - 1] {
+        return None;
+    }
+
+    let text: String = values.iter().map(|d| char::from(b'0' + *d as u8)).collect();
+    let kind = match digits {
+        8 => CodeKind::Ean8,
+        // 以 0 开头的 EAN-13 就是 UPC-A
+        _ if values[0] == 0 => return Some((CodeKind::UpcA, text[1..].to_string(), end)),
+        _ => CodeKind::Ean13,
+    };
+    Some((kind, text, end))
+}
+
+// 从 start 开始解码 Code 128，返回码制、内容和结束后的位置
+fn decode_code128(runs: &[usize], start: usize) -> Option<(CodeKind, String, usize)> {
+    if start + 6 > runs.len() {
+        return None;
+    }
+    let first = best_match(&runs[start..start + 6], &CODE128_PATTERNS, 0.25)?;
+    if first < START_A {
+        return None;
+    }
+    let unit = runs[start..start + 6].iter().sum::<usize>() as f32 / 11.0;
+    if (runs[start - 1] as f32) < unit * 5.0 {
+        return None;
+    }
+
+    let mut symbols = vec![first];
+    let mut at = start + 6;
+    loop {
+        if at + 7 <= runs.len()
+            && variance(&runs[at..at + 7], &CODE128_STOP, 0.7).is_some_and(|v| v < 0.25)
+        {
+            at += 7;
+            break;
+        }
+        if at + 6 > runs.len() {
+            return None;
+        }
+        symbols.push(best_match(&runs[at..at + 6], &CODE128_PATTERNS, 0.25)?);
+        at += 6;
+    }
+    // 起始符、至少一个数据符号和校验符
+    if symbols.len() < 3 {
+        return None;
+    }
+    let check = symbols.pop()?;
+    let sum = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| value * i.max(1))
+        .sum::<usize>();
+    if sum % 103 != check {
+        return None;
+    }
+
+    let mut text = String::new();
+    // 当前字符集：0 为 A，1 为 B，2 为 C
+    let mut set = first - START_A;
+    let mut shifted = false;
+    for &value in &symbols[1..] {
+        let current = if shifted { 1 - set } else { set };
+        shifted = false;
+        match (current, value) {
+            (_, START_A..) => return None,
+            (_, FNC1) | (_, 96) | (_, 97) => {}
+            (2, 0..=99) => text.push_str(&format!("{:02}", value)),
+            (2, CODE_B) => set = 1,
+            (2, CODE_A) => set = 0,
+            (_, SHIFT) => shifted = true,
+            (_, CODE_C) => set = 2,
+            (0, CODE_B) | (1, CODE_A) => set = 1 - current,
+            // A、B 中另一个是 FNC4，扩展 ASCII 较少使用，忽略
+            (_, CODE_B) | (_, CODE_A) => {}
+            (0, 64..) => text.push(char::from(value as u8 - 64)),
+            _ => text.push(char::from(value as u8 + 32)),
+        }
+    }
+    Some((CodeKind::Code128, text, at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes;
+    use image::{Rgb, RgbImage};
+
+    // 按标准编码画出 EAN-13 的模块序列，true 为条
+    fn ean13_modules(digits: &str) -> Vec<bool> {
+        let digits: Vec<usize> = digits.bytes().map(|b| (b - b'0') as usize).collect();
+        let push = |modules: &mut Vec<bool>, widths: [usize; 4], first_dark: bool| {
+            for (i, width) in widths.into_iter().enumerate() {
+                modules.extend(std::iter::repeat_n(first_dark == (i % 2 == 0), width));
+            }
+        };
+        let mut modules = vec![true, false, true];
+        let parity = FIRST_DIGIT_PARITY[digits[0]];
+        for (k, &d) in digits[1..7].iter().enumerate() {
+            let [a, b, c, e] = DIGIT_PATTERNS[d];
+            let widths = if parity >> (5 - k) & 1 == 1 {
+                [e, c, b, a]
+            } else {
+                [a, b, c, e]
+            };
+            push(&mut modules, widths, false);
+        }
+        modules.extend([false, true, false, true, false]);
+        for &d in &digits[7..] {
+            push(&mut modules, DIGIT_PATTERNS[d], true);
+        }
+        modules.extend([true, false, true]);
+        modules
+    }
+
+    // 每个模块 3 像素宽，两侧留 10 个模块的空白
+    fn render(modules: &[bool]) -> RgbImage {
+        let width = (modules.len() as u32 + 20) * 3;
+        RgbImage::from_fn(width, 60, |x, _| {
+            let dark = (x / 3)
+                .checked_sub(10)
+                .and_then(|i| modules.get(i as usize))
+                .is_some_and(|&dark| dark);
+            if dark {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        })
+    }
+
+    fn decoded(img: &RgbImage) -> Vec<(CodeKind, String)> {
+        codes::scan(img)
+            .into_iter()
+            .map(|code| (code.kind, code.text))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_ean13_and_upca() {
+        let img = render(&ean13_modules("4006381333931"));
+        assert_eq!(
+            decoded(&img),
+            [(CodeKind::Ean13, "4006381333931".to_string())]
+        );
+        // 左右翻转后从反方向扫描也能识别
+        let flipped = image::imageops::flip_horizontal(&img);
+        assert_eq!(
+            decoded(&flipped),
+            [(CodeKind::Ean13, "4006381333931".to_string())]
+        );
+        let img = render(&ean13_modules("0036000291452"));
+        assert_eq!(
+            decoded(&img),
+            [(CodeKind::UpcA, "036000291452".to_string())]
+        );
+    }
+
+    #[test]
+    fn wrong_check_digit_is_rejected() {
+        let img = render(&ean13_modules("4006381333932"));
+        assert!(decoded(&img).is_empty());
+        // 截断的条码
+        let modules = ean13_modules("4006381333931");
+        assert!(decoded(&render(&modules[..60])).is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+use iced::futures::{SinkExt, Stream};
+use image::{DynamicImage, RgbImage, RgbaImage};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::convert;
+use crate::export::{self, EncodeOptions, ExportFormat};
+use crate::i18n::{tr, trf};
+use crate::pipeline::ResamplingType;
+
+// 批处理的作用范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchScope {
+    Selection,
+    Folder,
+}
+
+// 批处理对话框中的选项，输入框保存原始文本
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub resize_enabled: bool,
+    pub max_width: String,
+    pub max_height: String,
+    pub resampling: ResamplingType,
+    pub watermark: Option<PathBuf>, // 水印图片（放在右下角），None 表示不加水印
+    pub convert_enabled: bool,
+    pub encode: EncodeOptions,
+    pub rename_enabled: bool,
+    pub rename_pattern: String, // 支持 {name}（原文件名）和 {n}（序号）
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            resize_enabled: false,
+            max_width: "1920".to_string(),
+            max_height: "1920".to_string(),
+            resampling: ResamplingType::Lanczos3,
+            watermark: None,
+            convert_enabled: false,
+            encode: EncodeOptions::default(),
+            rename_enabled: false,
+            rename_pattern: "{name}_{n}".to_string(),
+            output_dir: None,
+        }
+    }
+}
+
+impl BatchOptions {
+    /// 校验选项并生成执行计划
+    pub fn plan(&self) -> Result<BatchPlan, String> {
+        let resize = if self.resize_enabled {
+            let width = self.max_width.trim().parse::<u32>().ok().filter(|&w| w > 0);
+            let height = self
+                .max_height
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&h| h > 0);
+            match (width, height) {
+                (Some(width), Some(height)) => Some((width, height)),
+                _ => return Err(tr("Invalid maximum size").to_string()),
+            }
+        } else {
+            None
+        };
+        let rename = if self.rename_enabled {
+            let pattern = self.rename_pattern.trim();
+            if pattern.is_empty() || pattern.contains(['/', '\\']) {
+                return Err(tr("Invalid rename pattern").to_string());
+            }
+            Some(pattern.to_string())
+        } else {
+            None
+        };
+        let plan = BatchPlan {
+            resize,
+            resampling: self.resampling,
+            watermark: self.watermark.clone(),
+            encode: self.convert_enabled.then_some(self.encode),
+            rename,
+            output_dir: self.output_dir.clone(),
+        };
+        if plan.is_empty() {
+            return Err(tr("Choose at least one operation").to_string());
+        }
+        Ok(plan)
+    }
+}
+
+// 校验后的批处理计划
+#[derive(Debug, Clone)]
+pub struct BatchPlan {
+    resize: Option<(u32, u32)>,
+    resampling: ResamplingType,
+    watermark: Option<PathBuf>,
+    encode: Option<EncodeOptions>,
+    rename: Option<String>,
+    output_dir: Option<PathBuf>,
+}
+
+impl BatchPlan {
+    fn is_empty(&self) -> bool {
+        self.resize.is_none()
+            && self.watermark.is_none()
+            && self.encode.is_none()
+            && self.rename.is_none()
+            && self.output_dir.is_none()
+    }
+
+    // 是否需要解码并重新编码图片
+    fn needs_pixels(&self) -> bool {
+        self.resize.is_some() || self.watermark.is_some() || self.encode.is_some()
+    }
+
+    /// 只改名且不指定输出目录时，直接在原位置重命名文件
+    pub fn renames_in_place(&self) -> bool {
+        !self.needs_pixels() && self.output_dir.is_none()
+    }
+
+    // 显示在任务列表中的描述
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((width, height)) = self.resize {
+            parts.push(trf("Resize ≤ {}×{}", &[&width, &height]));
+        }
+        if self.watermark.is_some() {
+            parts.push(tr("Watermark").to_string());
+        }
+        if let Some(encode) = self.encode {
+            parts.push(trf("Convert to {}", &[&encode.format.name()]));
+        }
+        if self.rename.is_some() {
+            parts.push(tr("Rename").to_string());
+        }
+        if parts.is_empty() {
+            parts.push(tr("Copy").to_string());
+        }
+        parts.join(" + ")
+    }
+}
+
+// 单张图片的处理状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemStatus {
+    Pending,
+    Running,
+    Done(PathBuf),
+    Failed(String),
+    Cancelled,
+}
+
+// 队列中的一个批处理任务
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    pub id: u64,
+    pub plan: BatchPlan,
+    pub items: Vec<(PathBuf, ItemStatus)>,
+    pub cancel: Arc<AtomicBool>,
+    pub started: bool,
+    pub finished: bool,
+}
+
+impl BatchJob {
+    pub fn new(id: u64, plan: BatchPlan, sources: Vec<PathBuf>) -> Self {
+        BatchJob {
+            id,
+            plan,
+            items: sources
+                .into_iter()
+                .map(|p| (p, ItemStatus::Pending))
+                .collect(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            started: false,
+            finished: false,
+        }
+    }
+
+    // 已处理完（成功、失败或取消）的数量
+    pub fn completed(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|(_, status)| !matches!(status, ItemStatus::Pending | ItemStatus::Running))
+            .count()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&PathBuf, &String)> {
+        self.items.iter().filter_map(|(path, status)| match status {
+            ItemStatus::Failed(e) => Some((path, e)),
+            _ => None,
+        })
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+// 任务执行过程中发送的事件
+#[derive(Debug, Clone)]
+pub enum BatchEvent {
+    Item(usize, ItemStatus),
+    Finished,
+}
+
+/// 依次处理任务中的每张图片，取消后剩余的图片标记为 Cancelled
+pub fn run_job(job: &BatchJob) -> impl Stream<Item = BatchEvent> + use<> {
+    let plan = job.plan.clone();
+    let sources: Vec<PathBuf> = job.items.iter().map(|(p, _)| p.clone()).collect();
+    let cancel = job.cancel.clone();
+    iced::stream::channel(4, move |mut output| async move {
+        let watermark = match &plan.watermark {
+            Some(path) => match image::open(path) {
+                Ok(img) => Some(Arc::new(img.to_rgba8())),
+                Err(e) => {
+                    let error = format!("Failed to load watermark: {}", e);
+                    for index in 0..sources.len() {
+                        let status = ItemStatus::Failed(error.clone());
+                        let _ = output.send(BatchEvent::Item(index, status)).await;
+                    }
+                    let _ = output.send(BatchEvent::Finished).await;
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        for (index, src) in sources.into_iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                let _ = output
+                    .send(BatchEvent::Item(index, ItemStatus::Cancelled))
+                    .await;
+                continue;
+            }
+            let _ = output
+                .send(BatchEvent::Item(index, ItemStatus::Running))
+                .await;
+
+            let plan = plan.clone();
+            let watermark = watermark.clone();
+            let status = tokio::task::spawn_blocking(move || {
+                process_item(&src, index, &plan, watermark.as_deref())
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r)
+            .map_or_else(ItemStatus::Failed, ItemStatus::Done);
+            let _ = output.send(BatchEvent::Item(index, status)).await;
+        }
+        let _ = output.send(BatchEvent::Finished).await;
+    })
+}
+
+fn process_item(
+    src: &Path,
+    index: usize,
+    plan: &BatchPlan,
+    watermark: Option<&RgbaImage>,
+) -> Result<PathBuf, String> {
+    let dir = plan
+        .output_dir
+        .clone()
+        .or_else(|| src.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+    let stem = match &plan.rename {
+        Some(pattern) => pattern
+            .replace("{name}", &stem)
+            .replace("{n}", &format!("{:03}", index + 1)),
+        None => stem.into_owned(),
+    };
+    let src_extension = src
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    // 只改名或复制，不重新编码
+    if !plan.needs_pixels() {
+        let dst = convert::unique_path(&dir, &stem, &src_extension);
+        if plan.output_dir.is_none() {
+            fs::rename(src, &dst).map_err(|e| e.to_string())?;
+        } else {
+            fs::copy(src, &dst).map_err(|e| e.to_string())?;
+        }
+        return Ok(dst);
+    }
+
+    let (img, _) = crate::pipeline::decode_image(src, true).map_err(|e| e.to_string())?;
+    let mut img = img.to_rgb8();
+    if let Some((max_width, max_height)) = plan.resize {
+        img = fit_within(&img, max_width, max_height, plan.resampling)?;
+    }
+    if let Some(watermark) = watermark {
+        apply_watermark(&mut img, watermark);
+    }
+
+    // 未指定转换格式时沿用原格式，原格式不支持写入时保存为 PNG
+    let encode = plan.encode.unwrap_or(EncodeOptions {
+        format: ExportFormat::from_extension(&src_extension).unwrap_or(ExportFormat::Png),
+        ..Default::default()
+    });
+    // 写到原图旁边且没有改名时，加后缀避免和原图混淆
+    let stem = if plan.output_dir.is_none() && plan.rename.is_none() {
+        format!("{}_edited", stem)
+    } else {
+        stem
+    };
+    let dst = convert::unique_path(&dir, &stem, encode.format.extension());
+    export::write_image(&DynamicImage::ImageRgb8(img), &encode, &dst).map_err(|e| e.to_string())?;
+    Ok(dst)
+}
+
+// 等比缩小到 max_width x max_height 以内，不放大
+fn fit_within(
+    img: &RgbImage,
+    max_width: u32,
+    max_height: u32,
+    resampling: ResamplingType,
+) -> Result<RgbImage, String> {
+    let (width, height) = img.dimensions();
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    if scale >= 1.0 {
+        return Ok(img.clone());
+    }
+    let new_width = ((width as f64 * scale).round() as u32).max(1);
+    let new_height = ((height as f64 * scale).round() as u32).max(1);
+    export::resize_image(img, new_width, new_height, resampling)
+}
+
+// 水印宽度为图片宽度的 1/5，贴在右下角并按透明度混合
+fn apply_watermark(img: &mut RgbImage, watermark: &RgbaImage) {
+    let target_width = (img.width() / 5).max(1);
+    let scale = target_width as f32 / watermark.width() as f32;
+    let target_height = ((watermark.height() as f32 * scale).round() as u32).max(1);
+    let mark = image::imageops::resize(
+        watermark,
+        target_width,
+        target_height,
+        image::imageops::FilterType::Triangle,
+    );
+
+    let margin = img.width() / 40;
+    let x0 = img.width().saturating_sub(mark.width() + margin);
+    let y0 = img.height().saturating_sub(mark.height() + margin);
+    for (x, y, pixel) in mark.enumerate_pixels() {
+        let (tx, ty) = (x0 + x, y0 + y);
+        if tx >= img.width() || ty >= img.height() {
+            continue;
+        }
+        let alpha = pixel[3] as f32 / 255.0;
+        let target = img.get_pixel_mut(tx, ty);
+        for c in 0..3 {
+            target[c] = (pixel[c] as f32 * alpha + target[c] as f32 * (1.0 - alpha)).round() as u8;
+        }
+    }
+}
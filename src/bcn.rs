@@ -0,0 +1,440 @@
+use image::RgbaImage;
+
+/// GPU 纹理的块压缩格式，每个 4×4 像素块压缩为 8 或 16 字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFormat {
+    Bc1, // DXT1，RGB 加 1 位透明
+    Bc2, // DXT3，4 位显式透明
+    Bc3, // DXT5，插值透明
+    Bc4, // 单通道，显示为灰度
+    Bc5, // 双通道（常用于法线贴图），显示在红色和绿色通道
+    Bc7,
+}
+
+impl BlockFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            BlockFormat::Bc1 => "BC1",
+            BlockFormat::Bc2 => "BC2",
+            BlockFormat::Bc3 => "BC3",
+            BlockFormat::Bc4 => "BC4",
+            BlockFormat::Bc5 => "BC5",
+            BlockFormat::Bc7 => "BC7",
+        }
+    }
+
+    pub fn block_size(self) -> usize {
+        match self {
+            BlockFormat::Bc1 | BlockFormat::Bc4 => 8,
+            _ => 16,
+        }
+    }
+
+    /// 指定尺寸的一层纹理压缩后的字节数
+    pub fn level_size(self, width: u32, height: u32) -> usize {
+        width.div_ceil(4) as usize * height.div_ceil(4) as usize * self.block_size()
+    }
+}
+
+/// 解压一层纹理，块按从左到右、从上到下排列，右边和下边超出图片的像素丢弃
+pub fn decode(format: BlockFormat, data: &[u8], width: u32, height: u32) -> Option<RgbaImage> {
+    let size = format.block_size();
+    if data.len() < format.level_size(width, height) {
+        return None;
+    }
+    let mut img = RgbaImage::new(width, height);
+    let blocks_x = width.div_ceil(4);
+    for (index, block) in data.chunks_exact(size).enumerate() {
+        let (bx, by) = (index as u32 % blocks_x * 4, index as u32 / blocks_x * 4);
+        if by >= height {
+            break;
+        }
+        let pixels = match format {
+            BlockFormat::Bc1 => color_block(block, false),
+            BlockFormat::Bc2 => bc2_block(block),
+            BlockFormat::Bc3 => bc3_block(block),
+            BlockFormat::Bc4 => single_channel(block).map(|v| [v, v, v, 255]),
+            BlockFormat::Bc5 => {
+                let (red, green) = (single_channel(&block[..8]), single_channel(&block[8..]));
+                std::array::from_fn(|i| [red[i], green[i], 0, 255])
+            }
+            BlockFormat::Bc7 => bc7_block(block),
+        };
+        for (i, pixel) in pixels.into_iter().enumerate() {
+            let (x, y) = (bx + i as u32 % 4, by + i as u32 / 4);
+            if x < width && y < height {
+                img.put_pixel(x, y, image::Rgba(pixel));
+            }
+        }
+    }
+    Some(img)
+}
+
+fn expand_565(color: u16) -> [u8; 4] {
+    let (r, g, b) = (
+        (color >> 11) as u8 & 31,
+        (color >> 5) as u8 & 63,
+        color as u8 & 31,
+    );
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+}
+
+// BC1 的颜色块，BC2 和 BC3 中的颜色块总是使用四色模式
+fn color_block(block: &[u8], four_color: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (expand_565(c0), expand_565(c1));
+    let mix = |wa: u16, wb: u16, total: u16| -> [u8; 4] {
+        std::array::from_fn(|i| ((a[i] as u16 * wa + b[i] as u16 * wb) / total) as u8)
+    };
+    let palette = if four_color || c0 > c1 {
+        [a, b, mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [a, b, mix(1, 1, 2), [0, 0, 0, 0]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[(indices >> (i * 2)) as usize & 3])
+}
+
+fn bc2_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    let mut pixels = color_block(&block[8..], true);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        pixel[3] = ((alpha >> (i * 4)) & 15) as u8 * 17;
+    }
+    pixels
+}
+
+fn bc3_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let alpha = single_channel(&block[..8]);
+    let mut pixels = color_block(&block[8..], true);
+    for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+        pixel[3] = alpha;
+    }
+    pixels
+}
+
+// BC3 的透明块和 BC4/BC5 的通道：两个端点加 16 个 3 位索引
+fn single_channel(block: &[u8]) -> [u8; 16] {
+    let (a, b) = (block[0] as u16, block[1] as u16);
+    let palette: [u8; 8] = if a > b {
+        std::array::from_fn(|i| match i {
+            0 => a as u8,
+            1 => b as u8,
+            i => (((8 - i as u16) * a + (i as u16 - 1) * b) / 7) as u8,
+        })
+    } else {
+        std::array::from_fn(|i| match i {
+            0 => a as u8,
+            1 => b as u8,
+            6 => 0,
+            7 => 255,
+            i => (((6 - i as u16) * a + (i as u16 - 1) * b) / 5) as u8,
+        })
+    };
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|i| palette[(indices >> (i * 3)) as usize & 7])
+}
+
+// BC7 各模式的参数
+struct Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool, // 每个端点一个 P 位
+    shared_pbits: bool,   // 同一子集的两个端点共用一个 P 位
+    index_bits: u32,
+    index_bits2: u32, // 模式 4、5 中第二组索引的位数
+}
+
+const fn mode(
+    subsets: usize,
+    [
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+    ]: [u32; 5],
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index_bits2: u32,
+) -> Mode {
+    Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        index_bits2,
+    }
+}
+
+const MODES: [Mode; 8] = [
+    mode(3, [4, 0, 0, 4, 0], true, false, 3, 0),
+    mode(2, [6, 0, 0, 6, 0], false, true, 3, 0),
+    mode(3, [6, 0, 0, 5, 0], false, false, 2, 0),
+    mode(2, [6, 0, 0, 7, 0], true, false, 2, 0),
+    mode(1, [0, 2, 1, 5, 6], false, false, 2, 3),
+    mode(1, [0, 2, 0, 7, 8], false, false, 2, 2),
+    mode(1, [0, 0, 0, 7, 7], true, false, 4, 0),
+    mode(2, [6, 0, 0, 5, 5], true, false, 2, 0),
+];
+
+// 两个子集时每种划分中各像素所属的子集，每个十六进制数字依次为一行四个像素
+const PARTITIONS2: [u16; 64] = [
+    0x3333, 0x1111, 0x7777, 0x1337, 0x0113, 0x3777, 0x1377, 0x0137, 0x0013, 0x37ff, 0x017f, 0x0017,
+    0x17ff, 0x00ff, 0x0fff, 0x000f, 0x08ef, 0x7100, 0x008e, 0x7310, 0x3100, 0x08ce, 0x008c, 0x7331,
+    0x3110, 0x088c, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0x5555, 0x0f0f, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x6969, 0x5aa5, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0x3cc3, 0x6699, 0x0660,
+    0x4e40, 0x2720, 0x0272, 0x04e4, 0x6c93, 0x36c9, 0x639c, 0x39c6, 0x6cc9, 0x6339, 0x7e81, 0x18e7,
+    0x0f33, 0x33f0, 0x22ee, 0x4477,
+];
+
+// 三个子集时每种划分中各像素所属的子集，按行排列
+const PARTITIONS3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+// 各子集的锚点像素，锚点的索引省略最高位
+const ANCHORS2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+const ANCHORS3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+const ANCHORS3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+const WEIGHTS2: [u16; 4] = [0, 21, 43, 64];
+const WEIGHTS3: [u16; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS4: [u16; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+// 从低位开始按位读取 128 位的块
+struct Bits(u128);
+
+impl Bits {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.0 & ((1u128 << count) - 1)) as u32;
+        self.0 >>= count;
+        value
+    }
+}
+
+fn subset_of(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => (PARTITIONS2[partition] >> (15 - pixel)) as usize & 1,
+        3 => PARTITIONS3[partition][pixel] as usize,
+        _ => 0,
+    }
+}
+
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    pixel == 0
+        || match subsets {
+            2 => pixel == ANCHORS2[partition] as usize,
+            3 => {
+                pixel == ANCHORS3_SECOND[partition] as usize
+                    || pixel == ANCHORS3_THIRD[partition] as usize
+            }
+            _ => false,
+        }
+}
+
+fn interpolate(a: u8, b: u8, index: u32, bits: u32) -> u8 {
+    let weight = match bits {
+        2 => WEIGHTS2[index as usize],
+        3 => WEIGHTS3[index as usize],
+        _ => WEIGHTS4[index as usize],
+    };
+    ((a as u16 * (64 - weight) + b as u16 * weight + 32) >> 6) as u8
+}
+
+// 把量化后的端点扩展到 8 位
+fn unquantize(value: u32, bits: u32) -> u8 {
+    let value = value << (8 - bits);
+    (value | value >> bits) as u8
+}
+
+fn bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut bits = Bits(u128::from_le_bytes(block.try_into().unwrap()));
+    let number = block[0].trailing_zeros() as usize;
+    // 保留的模式解码为全透明
+    let Some(mode) = MODES.get(number) else {
+        return [[0; 4]; 16];
+    };
+    bits.read(number as u32 + 1);
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    // 端点按通道、子集、端点的顺序排列
+    let endpoints = mode.subsets * 2;
+    let mut colors = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in colors.iter_mut().take(endpoints) {
+            endpoint[channel] = bits.read(mode.color_bits);
+        }
+    }
+    for endpoint in colors.iter_mut().take(endpoints) {
+        endpoint[3] = bits.read(mode.alpha_bits);
+    }
+
+    let (mut color_bits, mut alpha_bits) = (mode.color_bits, mode.alpha_bits);
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let pbits: Vec<u32> = if mode.endpoint_pbits {
+            (0..endpoints).map(|_| bits.read(1)).collect()
+        } else {
+            (0..mode.subsets)
+                .flat_map(|_| {
+                    let bit = bits.read(1);
+                    [bit, bit]
+                })
+                .collect()
+        };
+        for (endpoint, pbit) in colors.iter_mut().zip(pbits) {
+            for (channel, value) in endpoint.iter_mut().enumerate() {
+                if channel < 3 || mode.alpha_bits > 0 {
+                    *value = *value << 1 | pbit;
+                }
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+    let endpoints: [[u8; 4]; 6] = colors.map(|endpoint| {
+        [
+            unquantize(endpoint[0], color_bits),
+            unquantize(endpoint[1], color_bits),
+            unquantize(endpoint[2], color_bits),
+            if alpha_bits > 0 {
+                unquantize(endpoint[3], alpha_bits)
+            } else {
+                255
+            },
+        ]
+    });
+
+    let indices: [u32; 16] = std::array::from_fn(|pixel| {
+        let anchor = is_anchor(mode.subsets, partition, pixel);
+        bits.read(mode.index_bits - anchor as u32)
+    });
+    let indices2: [u32; 16] = std::array::from_fn(|pixel| {
+        if mode.index_bits2 == 0 {
+            0
+        } else {
+            bits.read(mode.index_bits2 - (pixel == 0) as u32)
+        }
+    });
+
+    std::array::from_fn(|pixel| {
+        let subset = subset_of(mode.subsets, partition, pixel);
+        let (a, b) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        // 模式 4、5 中颜色和透明使用不同的索引，由索引选择位决定哪一组给颜色
+        let ((color_index, color_bits), (alpha_index, alpha_bits)) = if mode.index_bits2 == 0 {
+            let primary = (indices[pixel], mode.index_bits);
+            (primary, primary)
+        } else {
+            let primary = (indices[pixel], mode.index_bits);
+            let secondary = (indices2[pixel], mode.index_bits2);
+            if index_selection == 0 {
+                (primary, secondary)
+            } else {
+                (secondary, primary)
+            }
+        };
+        let mut color: [u8; 4] = std::array::from_fn(|channel| {
+            if channel < 3 {
+                interpolate(a[channel], b[channel], color_index, color_bits)
+            } else {
+                interpolate(a[3], b[3], alpha_index, alpha_bits)
+            }
+        });
+        if rotation > 0 {
+            color.swap(3, rotation as usize - 1);
+        }
+        color
+    })
+}
@@ -0,0 +1,71 @@
+use iced::Vector;
+use iced::widget::image::Handle;
+use image::RgbImage;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::pipeline::{self, ResamplingType};
+
+/// 闪烁比较：在图片区域原地交替显示两张图片，用来找出两个版本之间细微的差别
+#[derive(Debug, Clone)]
+pub struct Blink {
+    pub paths: [PathBuf; 2],
+    pub images: Option<[Arc<RgbImage>; 2]>, // 解码完成前为空
+    pub frames: Option<[Handle; 2]>,        // 按当前的缩放和平移生成的画面
+    pub showing: usize,                     // 正在显示哪一张
+    pub paused: bool,
+}
+
+impl Blink {
+    pub fn new(paths: [PathBuf; 2]) -> Self {
+        Blink {
+            paths,
+            images: None,
+            frames: None,
+            showing: 0,
+            paused: false,
+        }
+    }
+
+    /// 正在显示的画面，解码完成前为空
+    pub fn frame(&self) -> Option<&Handle> {
+        self.frames.as_ref().map(|frames| &frames[self.showing])
+    }
+
+    pub fn toggle(&mut self) {
+        self.showing = 1 - self.showing;
+    }
+}
+
+/// 按原尺寸解码两张图片，不使用缩小解码，细节才能对比
+pub fn decode(paths: [PathBuf; 2], apply_orientation: bool) -> Result<[Arc<RgbImage>; 2], String> {
+    let [first, second] = paths.map(|path| {
+        pipeline::decode_image(&path, apply_orientation)
+            .map(|(img, _)| Arc::new(img.to_rgb8()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    });
+    Ok([first?, second?])
+}
+
+/// 按缩放条值和平移量生成两张图片的画面，与查看单张图片时的视图一致
+pub fn render(
+    images: &[Arc<RgbImage>; 2],
+    slider_value: u8,
+    offset: Vector,
+    resampling: ResamplingType,
+) -> [Handle; 2] {
+    let scale = slider_value as f32 / 50.0;
+    images.each_ref().map(|img| {
+        if slider_value == 50 && offset == Vector::ZERO {
+            return pipeline::rgb_handle(img);
+        }
+        match pipeline::crop_and_scale(img, scale, offset, resampling) {
+            Ok(bytes) => Handle::from_bytes(bytes),
+            Err(e) => {
+                error!("Failed to scale image: {}", e);
+                pipeline::rgb_handle(img)
+            }
+        }
+    })
+}
@@ -1,26 +1,17 @@
 use iced::{
-    Background, Border, Color, Shadow, Theme, Vector, border, color,
+    Background, Border, Color, Shadow, Theme, Vector,
     widget::button::{Status, Style},
 };
 
+use crate::palette;
+
 pub fn default(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, text_color) = match status {
-        Status::Active => (
-            Some(Background::Color(Color::from_rgb8(248, 249, 250))),
-            Color::from_rgb8(33, 37, 41),
-        ),
-        Status::Hovered => (
-            Some(Background::Color(Color::from_rgb8(233, 236, 239))),
-            Color::from_rgb8(33, 37, 41),
-        ),
-        Status::Pressed => (
-            Some(Background::Color(Color::from_rgb8(222, 226, 230))),
-            Color::from_rgb8(33, 37, 41),
-        ),
-        Status::Disabled => (
-            Some(Background::Color(Color::from_rgb8(248, 249, 250))),
-            Color::from_rgb8(108, 117, 125),
-        ),
+        Status::Active => (Some(Background::Color(p.surface_alt)), p.text),
+        Status::Hovered => (Some(Background::Color(p.hover)), p.text),
+        Status::Pressed => (Some(Background::Color(p.pressed)), p.text),
+        Status::Disabled => (Some(Background::Color(p.surface_alt)), p.muted),
     };
 
     Style {
@@ -29,34 +20,23 @@ pub fn default(theme: &Theme, status: Status) -> Style {
         border: Border {
             radius: 6.0.into(),
             width: 1.0,
-            color: Color::from_rgb8(222, 226, 230),
+            color: p.border,
         },
         shadow: Shadow {
             offset: Vector::new(0.0, 1.0),
-            blur_radius: 2.0.into(),
-            color: Color::from_rgba8(0, 0, 0, 0.1),
+            blur_radius: 2.0,
+            color: p.shadow,
         },
     }
 }
 
-pub fn primary(_theme: &Theme, status: Status) -> Style {
+pub fn primary(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, text_color) = match status {
-        Status::Active => (
-            Some(Background::Color(Color::from_rgb8(13, 110, 253))),
-            Color::WHITE,
-        ),
-        Status::Hovered => (
-            Some(Background::Color(Color::from_rgb8(0, 86, 179))),
-            Color::WHITE,
-        ),
-        Status::Pressed => (
-            Some(Background::Color(Color::from_rgb8(10, 88, 202))),
-            Color::WHITE,
-        ),
-        Status::Disabled => (
-            Some(Background::Color(Color::from_rgb8(108, 117, 125))),
-            Color::from_rgb8(248, 249, 250),
-        ),
+        Status::Active => (Some(Background::Color(p.accent)), Color::WHITE),
+        Status::Hovered => (Some(Background::Color(p.accent_hover)), Color::WHITE),
+        Status::Pressed => (Some(Background::Color(p.accent_pressed)), Color::WHITE),
+        Status::Disabled => (Some(Background::Color(p.muted)), p.surface_alt),
     };
 
     Style {
@@ -69,30 +49,22 @@ pub fn primary(_theme: &Theme, status: Status) -> Style {
         },
         shadow: Shadow {
             offset: Vector::new(0.0, 2.0),
-            blur_radius: 4.0.into(),
-            color: Color::from_rgba8(13, 110, 253, 0.25),
+            blur_radius: 4.0,
+            color: Color {
+                a: 0.25,
+                ..p.accent
+            },
         },
     }
 }
 
-pub fn transparent(_theme: &Theme, status: Status) -> Style {
+pub fn transparent(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, text_color) = match status {
-        Status::Active => (
-            Some(Background::Color(Color::TRANSPARENT)),
-            Color::from_rgb8(33, 37, 41),
-        ),
-        Status::Hovered => (
-            Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.05))),
-            Color::from_rgb8(33, 37, 41),
-        ),
-        Status::Pressed => (
-            Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.1))),
-            Color::from_rgb8(33, 37, 41),
-        ),
-        Status::Disabled => (
-            Some(Background::Color(Color::TRANSPARENT)),
-            Color::from_rgb8(108, 117, 125),
-        ),
+        Status::Active => (Some(Background::Color(Color::TRANSPARENT)), p.text),
+        Status::Hovered => (Some(Background::Color(Color { a: 0.05, ..p.text })), p.text),
+        Status::Pressed => (Some(Background::Color(Color { a: 0.1, ..p.text })), p.text),
+        Status::Disabled => (Some(Background::Color(Color::TRANSPARENT)), p.muted),
     };
 
     Style {
@@ -107,29 +79,33 @@ pub fn transparent(_theme: &Theme, status: Status) -> Style {
     }
 }
 
-pub fn highlighted(_theme: &Theme, status: Status) -> Style {
+pub fn highlighted(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, border_color) = match status {
         Status::Active => (
-            Some(Background::Color(Color::from_rgba8(13, 110, 253, 0.1))),
-            Color::from_rgb8(13, 110, 253),
+            Some(Background::Color(Color { a: 0.1, ..p.accent })),
+            p.accent,
         ),
         Status::Hovered => (
-            Some(Background::Color(Color::from_rgba8(13, 110, 253, 0.15))),
-            Color::from_rgb8(13, 110, 253),
+            Some(Background::Color(Color {
+                a: 0.15,
+                ..p.accent
+            })),
+            p.accent,
         ),
         Status::Pressed => (
-            Some(Background::Color(Color::from_rgba8(13, 110, 253, 0.2))),
-            Color::from_rgb8(10, 88, 202),
+            Some(Background::Color(Color { a: 0.2, ..p.accent })),
+            p.accent_pressed,
         ),
         Status::Disabled => (
-            Some(Background::Color(Color::from_rgba8(108, 117, 125, 0.1))),
-            Color::from_rgb8(108, 117, 125),
+            Some(Background::Color(Color { a: 0.1, ..p.muted })),
+            p.muted,
         ),
     };
 
     Style {
         background,
-        text_color: Color::from_rgb8(33, 37, 41),
+        text_color: p.text,
         border: Border {
             radius: 6.0.into(),
             width: 2.0,
@@ -137,30 +113,31 @@ pub fn highlighted(_theme: &Theme, status: Status) -> Style {
         },
         shadow: Shadow {
             offset: Vector::new(0.0, 1.0),
-            blur_radius: 3.0.into(),
-            color: Color::from_rgba8(13, 110, 253, 0.2),
+            blur_radius: 3.0,
+            color: Color { a: 0.2, ..p.accent },
         },
     }
 }
 
-pub fn sidebar_item(_theme: &Theme, status: Status) -> Style {
+pub fn sidebar_item(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, text_color) = match status {
-        Status::Active => (
-            Some(Background::Color(Color::TRANSPARENT)),
-            Color::from_rgb8(52, 58, 64),
-        ),
+        Status::Active => (Some(Background::Color(Color::TRANSPARENT)), p.heading),
         Status::Hovered => (
-            Some(Background::Color(Color::from_rgba8(13, 110, 253, 0.08))),
-            Color::from_rgb8(13, 110, 253),
+            Some(Background::Color(Color {
+                a: 0.08,
+                ..p.accent
+            })),
+            p.accent,
         ),
         Status::Pressed => (
-            Some(Background::Color(Color::from_rgba8(13, 110, 253, 0.12))),
-            Color::from_rgb8(10, 88, 202),
-        ),
-        Status::Disabled => (
-            Some(Background::Color(Color::TRANSPARENT)),
-            Color::from_rgb8(173, 181, 189),
+            Some(Background::Color(Color {
+                a: 0.12,
+                ..p.accent
+            })),
+            p.accent_pressed,
         ),
+        Status::Disabled => (Some(Background::Color(Color::TRANSPARENT)), p.disabled),
     };
 
     Style {
@@ -175,45 +152,49 @@ pub fn sidebar_item(_theme: &Theme, status: Status) -> Style {
     }
 }
 
-pub fn thumbnail(_theme: &Theme, status: Status) -> Style {
+pub fn thumbnail(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, border_color, shadow) = match status {
         Status::Active => (
-            Some(Background::Color(Color::WHITE)),
-            Color::from_rgb8(222, 226, 230),
+            Some(Background::Color(p.surface)),
+            p.border,
             Shadow {
                 offset: Vector::new(0.0, 1.0),
-                blur_radius: 3.0.into(),
-                color: Color::from_rgba8(0, 0, 0, 0.1),
+                blur_radius: 3.0,
+                color: p.shadow,
             },
         ),
         Status::Hovered => (
-            Some(Background::Color(Color::WHITE)),
-            Color::from_rgb8(13, 110, 253),
+            Some(Background::Color(p.surface)),
+            p.accent,
             Shadow {
                 offset: Vector::new(0.0, 2.0),
-                blur_radius: 6.0.into(),
-                color: Color::from_rgba8(13, 110, 253, 0.25),
+                blur_radius: 6.0,
+                color: Color {
+                    a: 0.25,
+                    ..p.accent
+                },
             },
         ),
         Status::Pressed => (
-            Some(Background::Color(Color::from_rgb8(248, 249, 250))),
-            Color::from_rgb8(10, 88, 202),
+            Some(Background::Color(p.surface_alt)),
+            p.accent_pressed,
             Shadow {
                 offset: Vector::new(0.0, 1.0),
-                blur_radius: 2.0.into(),
-                color: Color::from_rgba8(0, 0, 0, 0.15),
+                blur_radius: 2.0,
+                color: p.shadow,
             },
         ),
         Status::Disabled => (
-            Some(Background::Color(Color::from_rgb8(248, 249, 250))),
-            Color::from_rgb8(222, 226, 230),
+            Some(Background::Color(p.surface_alt)),
+            p.border,
             Shadow::default(),
         ),
     };
 
     Style {
         background,
-        text_color: Color::from_rgb8(33, 37, 41),
+        text_color: p.text,
         border: Border {
             radius: 8.0.into(),
             width: 2.0,
@@ -223,30 +204,48 @@ pub fn thumbnail(_theme: &Theme, status: Status) -> Style {
     }
 }
 
-pub fn thumbnail_selected(_theme: &Theme, status: Status) -> Style {
+pub fn thumbnail_selected(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
     let (background, shadow) = match status {
         Status::Active | Status::Hovered | Status::Pressed => (
-            Some(Background::Color(Color::WHITE)),
+            Some(Background::Color(p.surface)),
             Shadow {
                 offset: Vector::new(0.0, 4.0),
-                blur_radius: 12.0.into(),
-                color: Color::from_rgba8(13, 110, 253, 0.4),
+                blur_radius: 12.0,
+                color: Color { a: 0.4, ..p.accent },
             },
         ),
-        Status::Disabled => (
-            Some(Background::Color(Color::from_rgb8(248, 249, 250))),
-            Shadow::default(),
-        ),
+        Status::Disabled => (Some(Background::Color(p.surface_alt)), Shadow::default()),
     };
 
     Style {
         background,
-        text_color: Color::from_rgb8(33, 37, 41),
+        text_color: p.text,
         border: Border {
             radius: 8.0.into(),
             width: 3.0,
-            color: Color::from_rgb8(13, 110, 253),
+            color: p.accent,
         },
         shadow,
     }
 }
+
+// 多选中的缩略图：橙色边框，与当前图片区分
+pub fn thumbnail_marked(theme: &Theme, status: Status) -> Style {
+    let p = palette::of(theme);
+    let background = match status {
+        Status::Disabled => Some(Background::Color(p.surface_alt)),
+        _ => Some(Background::Color(p.surface)),
+    };
+
+    Style {
+        background,
+        text_color: p.text,
+        border: Border {
+            radius: 8.0.into(),
+            width: 3.0,
+            color: p.marked,
+        },
+        shadow: Shadow::default(),
+    }
+}
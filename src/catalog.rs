@@ -0,0 +1,597 @@
+use iced::futures::{SinkExt, Stream};
+use md5::{Digest, Md5};
+use rusqlite::{Connection, params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use crate::config;
+use crate::exif_info;
+use crate::i18n::tr;
+use crate::metadata_search::{self, MetadataQuery};
+use crate::name_search::NamePattern;
+use crate::formats;
+use crate::similar;
+use crate::xmp_iptc;
+
+// 目录树中图片目录的虚拟路径，智能相册为 __LIBRARY__/<相册标识>
+pub const LIBRARY_ROOT: &str = "__LIBRARY__";
+
+// 每处理这么多张图片提交一次并报告进度，索引过程中已经可以搜到前面的图片
+const COMMIT_INTERVAL: usize = 200;
+
+// 数据库结构的版本，记录在 PRAGMA user_version 中；1 加入了感知哈希 phash
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS images (
+        path TEXT PRIMARY KEY,
+        folder TEXT NOT NULL,
+        name TEXT NOT NULL,
+        width INTEGER,
+        height INTEGER,
+        size INTEGER NOT NULL,
+        mtime INTEGER NOT NULL,
+        hash TEXT NOT NULL,
+        camera TEXT,
+        taken TEXT,
+        iso INTEGER,
+        focal REAL,
+        tags TEXT NOT NULL DEFAULT '',
+        rating INTEGER,
+        phash INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS images_name ON images (name);
+    CREATE INDEX IF NOT EXISTS images_hash ON images (hash);
+";
+
+/// 按条件从图片目录中选出的智能相册
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartAlbum {
+    All,
+    TopRated,
+    Untagged,
+    Duplicates,
+}
+
+impl SmartAlbum {
+    pub const ALL: [SmartAlbum; 4] = [
+        SmartAlbum::All,
+        SmartAlbum::TopRated,
+        SmartAlbum::Untagged,
+        SmartAlbum::Duplicates,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SmartAlbum::All => tr("All images"),
+            SmartAlbum::TopRated => tr("Rated 4+"),
+            SmartAlbum::Untagged => tr("Untagged"),
+            SmartAlbum::Duplicates => tr("Duplicates"),
+        }
+    }
+
+    fn id(self) -> &'static str {
+        match self {
+            SmartAlbum::All => "all",
+            SmartAlbum::TopRated => "top-rated",
+            SmartAlbum::Untagged => "untagged",
+            SmartAlbum::Duplicates => "duplicates",
+        }
+    }
+
+    // 查询条件和排序
+    fn query(self) -> &'static str {
+        match self {
+            SmartAlbum::All => "1 ORDER BY COALESCE(taken, '') DESC, mtime DESC",
+            SmartAlbum::TopRated => "rating >= 4 ORDER BY rating DESC, mtime DESC",
+            SmartAlbum::Untagged => "tags = '' ORDER BY mtime DESC",
+            SmartAlbum::Duplicates => {
+                "hash IN (SELECT hash FROM images GROUP BY hash HAVING COUNT(*) > 1) \
+                 ORDER BY hash, path"
+            }
+        }
+    }
+
+    /// 目录树中的虚拟路径
+    pub fn path(self) -> PathBuf {
+        Path::new(LIBRARY_ROOT).join(self.id())
+    }
+
+    pub fn from_path(path: &Path) -> Option<SmartAlbum> {
+        let id = path.strip_prefix(LIBRARY_ROOT).ok()?.to_str()?;
+        SmartAlbum::ALL.into_iter().find(|album| album.id() == id)
+    }
+}
+
+pub fn is_library(path: &Path) -> bool {
+    path.starts_with(LIBRARY_ROOT)
+}
+
+/// “最近添加”中列出多少天内修改过的图片
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AddedWithin {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl AddedWithin {
+    pub const ALL: [AddedWithin; 3] = [AddedWithin::Day, AddedWithin::Week, AddedWithin::Month];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AddedWithin::Day => tr("1 day"),
+            AddedWithin::Week => tr("7 days"),
+            AddedWithin::Month => tr("30 days"),
+        }
+    }
+
+    // 起始时间（Unix 秒）
+    fn since(self) -> i64 {
+        let days = match self {
+            AddedWithin::Day => 1,
+            AddedWithin::Week => 7,
+            AddedWithin::Month => 30,
+        };
+        chrono::Utc::now().timestamp() - days * 24 * 60 * 60
+    }
+}
+
+/// 索引过程中发送的事件
+#[derive(Debug, Clone)]
+pub enum IndexEvent {
+    Progress(usize, usize),          // 已处理的图片数、总数
+    Finished(Result<usize, String>), // 新增或更新的图片数
+}
+
+/// 索引了选定文件夹的 SQLite 图片目录，用于整个图片库的搜索和智能相册
+pub struct Catalog {
+    conn: Connection,
+}
+
+impl Catalog {
+    pub fn open(path: &Path) -> Result<Catalog, String> {
+        Ok(Catalog {
+            conn: open_connection(path)?,
+        })
+    }
+
+    /// 已索引的图片数量
+    pub fn count(&self) -> usize {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM images", [], |row| row.get(0))
+            .unwrap_or(0)
+    }
+
+    pub fn album(&self, album: SmartAlbum) -> Result<Vec<PathBuf>, String> {
+        self.paths(
+            &format!("SELECT path FROM images WHERE {}", album.query()),
+            Vec::new(),
+        )
+    }
+
+    /// 修改时间不早于 since 的图片，最新的在前
+    fn added_since(&self, since: i64) -> Result<Vec<(PathBuf, i64)>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT path, mtime FROM images WHERE mtime >= ? ORDER BY mtime DESC")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([since], |row| {
+                Ok((PathBuf::from(row.get::<_, String>(0)?), row.get(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(rows.flatten().collect())
+    }
+
+    /// 在整个图片目录中按元数据条件搜索
+    pub fn search(&self, query: &MetadataQuery) -> Result<Vec<PathBuf>, String> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        let camera = query.camera.trim();
+        if !camera.is_empty() {
+            conditions.push("camera LIKE ? ESCAPE '\\'".to_string());
+            values.push(format!("%{}%", escape_like(camera)).into());
+        }
+        for (column, min, max) in [
+            ("iso", &query.iso_min, &query.iso_max),
+            ("focal", &query.focal_min, &query.focal_max),
+        ] {
+            if let Some(min) = metadata_search::parse_bound(min) {
+                conditions.push(format!("{} >= ?", column));
+                values.push(min.into());
+            }
+            if let Some(max) = metadata_search::parse_bound(max) {
+                conditions.push(format!("{} <= ?", column));
+                values.push(max.into());
+            }
+        }
+        // 关键词按行保存，前后都有换行，整行匹配
+        let keyword = query.keyword.trim();
+        if !keyword.is_empty() {
+            conditions.push("tags LIKE ? ESCAPE '\\'".to_string());
+            values.push(format!("%\n{}\n%", escape_like(keyword)).into());
+        }
+        if conditions.is_empty() {
+            conditions.push("1".to_string());
+        }
+        let sql = format!(
+            "SELECT path FROM images WHERE {} ORDER BY folder, name",
+            conditions.join(" AND ")
+        );
+        self.paths(&sql, values)
+    }
+
+    /// 文件名符合条件的图片
+    pub fn find_names(&self, pattern: &NamePattern) -> Result<Vec<PathBuf>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT path, name FROM images ORDER BY folder, name")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(rows
+            .flatten()
+            .filter(|(_, name)| pattern.matches(name))
+            .map(|(path, _)| PathBuf::from(path))
+            .collect())
+    }
+
+    /// 已索引图片的感知哈希
+    pub fn phash(&self, path: &Path) -> Option<u64> {
+        self.conn
+            .query_row(
+                "SELECT phash FROM images WHERE path = ?1",
+                [path.to_str()?],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .ok()
+            .flatten()
+            .map(|hash| hash as u64)
+    }
+
+    /// 感知哈希与 hash 相差不超过 max_distance 位的图片，按差异从小到大排列
+    pub fn similar(&self, hash: u64, max_distance: u32) -> Result<Vec<PathBuf>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT path, phash FROM images WHERE phash IS NOT NULL")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut matches: Vec<(u32, String)> = rows
+            .flatten()
+            .map(|(path, other)| ((hash ^ other as u64).count_ones(), path))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        matches.sort();
+        Ok(matches
+            .into_iter()
+            .map(|(_, path)| PathBuf::from(path))
+            .collect())
+    }
+
+    /// 所有图片的拍摄时间和修改时间
+    pub fn dates(&self) -> Result<Vec<(PathBuf, Option<String>, i64)>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT path, taken, mtime FROM images")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    PathBuf::from(row.get::<_, String>(0)?),
+                    row.get(1)?,
+                    row.get(2)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(rows.flatten().collect())
+    }
+
+    fn paths(
+        &self,
+        sql: &str,
+        values: Vec<rusqlite::types::Value>,
+    ) -> Result<Vec<PathBuf>, String> {
+        let mut statement = self.conn.prepare(sql).map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        Ok(rows.flatten().map(PathBuf::from).collect())
+    }
+}
+
+/// 图片目录数据库的位置
+pub fn catalog_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("catalog.sqlite"))
+}
+
+fn open_connection(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    // WAL 模式下后台索引写入时界面仍然可以查询
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+    let version: i32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    // 版本 0 的图片目录没有感知哈希这一列，下次索引时为 phash 为空的图片补上
+    if version < 1 && conn.prepare("SELECT phash FROM images LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE images ADD COLUMN phash INTEGER;")
+            .map_err(|e| e.to_string())?;
+    }
+    if version < SCHEMA_VERSION {
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(conn)
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// 在后台线程中索引文件夹（包括子文件夹）：跳过大小和修改时间都没变的图片，
+/// 删除已经不存在或不在这些文件夹中的记录
+pub fn index(folders: Vec<PathBuf>, db: PathBuf) -> impl Stream<Item = IndexEvent> {
+    iced::stream::channel(4, move |mut output| async move {
+        let (sender, mut receiver) = mpsc::channel(4);
+        let worker = tokio::task::spawn_blocking(move || index_folders(&folders, &db, &sender));
+        while let Some(progress) = receiver.recv().await {
+            let _ = output.send(progress).await;
+        }
+        let result = worker.await.map_err(|e| e.to_string()).and_then(|r| r);
+        let _ = output.send(IndexEvent::Finished(result)).await;
+    })
+}
+
+fn index_folders(
+    folders: &[PathBuf],
+    db: &Path,
+    sender: &mpsc::Sender<IndexEvent>,
+) -> Result<usize, String> {
+    let mut conn = open_connection(db)?;
+    let mut files = Vec::new();
+    for folder in folders {
+        walk(folder, &mut files);
+    }
+    let total = files.len();
+
+    let known: HashMap<String, (i64, i64)> = {
+        let mut statement = conn
+            .prepare("SELECT path, size, mtime FROM images")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| e.to_string())?;
+        rows.flatten().collect()
+    };
+    // 没有感知哈希的图片：旧版本索引的，或者上次无法解码的
+    let missing_phash: HashSet<String> = {
+        let mut statement = conn
+            .prepare("SELECT path FROM images WHERE phash IS NULL")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.flatten().collect()
+    };
+
+    let mut seen = HashSet::new();
+    let mut updated = 0;
+    for (chunk_index, chunk) in files.chunks(COMMIT_INTERVAL).enumerate() {
+        // 接收端已经关闭（应用退出）时停止
+        let done = chunk_index * COMMIT_INTERVAL;
+        if sender
+            .blocking_send(IndexEvent::Progress(done, total))
+            .is_err()
+        {
+            return Ok(updated);
+        }
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for path in chunk {
+            let (Some(key), Ok(meta)) = (path.to_str(), fs::metadata(path)) else {
+                continue;
+            };
+            let size = meta.len() as i64;
+            let mtime = modified(&meta);
+            seen.insert(key.to_string());
+            if known.get(key) == Some(&(size, mtime)) {
+                // 文件没有变化时只补上感知哈希
+                if missing_phash.contains(key)
+                    && let Some(hash) = similar::hash_file(path)
+                {
+                    tx.execute(
+                        "UPDATE images SET phash = ?2 WHERE path = ?1",
+                        params![key, hash as i64],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    updated += 1;
+                }
+                continue;
+            }
+            let Ok(bytes) = fs::read(path) else {
+                continue;
+            };
+            let (width, height) =
+                image::image_dimensions(path).map_or((None, None), |(w, h)| (Some(w), Some(h)));
+            let exif = exif_info::read_exif(path);
+            let descriptive = xmp_iptc::read_descriptive_metadata(path);
+            let camera = exif.as_ref().and_then(|e| {
+                let name = [e.camera_make.as_deref(), e.camera_model.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (!name.is_empty()).then_some(name)
+            });
+            let tags = descriptive
+                .as_ref()
+                .filter(|d| !d.keywords.is_empty())
+                .map(|d| format!("\n{}\n", d.keywords.join("\n")))
+                .unwrap_or_default();
+            tx.execute(
+                "INSERT OR REPLACE INTO images
+                    (path, folder, name, width, height, size, mtime, hash,
+                     camera, taken, iso, focal, tags, rating, phash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                params![
+                    key,
+                    path.parent().and_then(|p| p.to_str()).unwrap_or_default(),
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or_default(),
+                    width,
+                    height,
+                    size,
+                    mtime,
+                    hex::encode(Md5::digest(&bytes)),
+                    camera,
+                    exif.as_ref().and_then(|e| e.date_time.clone()),
+                    exif.as_ref().and_then(|e| e.iso),
+                    exif.as_ref().and_then(|e| e.focal_length_mm),
+                    tags,
+                    descriptive.and_then(|d| d.rating),
+                    similar::hash_file(path).map(|hash| hash as i64),
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            updated += 1;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for path in known.keys().filter(|path| !seen.contains(*path)) {
+        tx.execute("DELETE FROM images WHERE path = ?1", params![path])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    let _ = sender.blocking_send(IndexEvent::Progress(total, total));
+    Ok(updated)
+}
+
+/// 在后台查找最近添加的图片：图片目录中的图片，加上 folders 中（不包括子文件夹）的图片，
+/// 按修改时间从新到旧排列
+pub async fn recently_added(
+    db: Option<PathBuf>,
+    folders: Vec<PathBuf>,
+    within: AddedWithin,
+) -> Result<Vec<PathBuf>, String> {
+    tokio::task::spawn_blocking(move || {
+        let since = within.since();
+        let mut images = match db {
+            Some(db) => Catalog::open(&db)?.added_since(since)?,
+            None => Vec::new(),
+        };
+        let mut seen: HashSet<PathBuf> = images.iter().map(|(path, _)| path.clone()).collect();
+        for folder in &folders {
+            let Ok(entries) = fs::read_dir(folder) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !formats::is_image(&path) || seen.contains(&path) {
+                    continue;
+                }
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                let mtime = modified(&meta);
+                if meta.is_file() && mtime >= since {
+                    seen.insert(path.clone());
+                    images.push((path, mtime));
+                }
+            }
+        }
+        images.sort_by_key(|(_, mtime)| std::cmp::Reverse(*mtime));
+        Ok(images.into_iter().map(|(path, _)| path).collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 文件的修改时间（Unix 秒）
+fn modified(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// 递归列出文件夹中的图片，跳过隐藏目录，不跟随符号链接
+pub fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                walk(&path, files);
+            }
+        } else if file_type.is_file() && formats::is_image(&path) {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_catalogs_without_phash() {
+        let path = std::env::temp_dir().join(format!("catalog-{}.sqlite", std::process::id()));
+        let _ = fs::remove_file(&path);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE images (
+                    path TEXT PRIMARY KEY, folder TEXT NOT NULL, name TEXT NOT NULL,
+                    width INTEGER, height INTEGER, size INTEGER NOT NULL, mtime INTEGER NOT NULL,
+                    hash TEXT NOT NULL, camera TEXT, taken TEXT, iso INTEGER, focal REAL,
+                    tags TEXT NOT NULL DEFAULT '', rating INTEGER
+                 );
+                 INSERT INTO images (path, folder, name, size, mtime, hash)
+                 VALUES ('/photos/a.jpg', '/photos', 'a.jpg', 10, 20, 'md5');",
+            )
+            .unwrap();
+
+        let conn = open_connection(&path).unwrap();
+        let version: i32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        // 迁移不改动原来的记录，只是 phash 为空
+        let (size, phash): (i64, Option<i64>) = conn
+            .query_row("SELECT size, phash FROM images", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((size, phash), (10, None));
+        // 再次打开时不再迁移
+        drop(conn);
+        assert!(open_connection(&path).is_ok());
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
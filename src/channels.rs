@@ -0,0 +1,80 @@
+use image::{Rgb, RgbImage};
+use std::path::Path;
+
+use crate::i18n::tr;
+use crate::pipeline;
+
+/// 只显示图片的一个通道，用于检查蒙版和各通道的内容，不影响保存和导出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channel {
+    #[default]
+    All,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Luminance,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 6] = [
+        Channel::All,
+        Channel::Red,
+        Channel::Green,
+        Channel::Blue,
+        Channel::Alpha,
+        Channel::Luminance,
+    ];
+
+    // 按钮上的缩写，不翻译
+    pub fn label(self) -> &'static str {
+        match self {
+            Channel::All => "RGB",
+            Channel::Red => "R",
+            Channel::Green => "G",
+            Channel::Blue => "B",
+            Channel::Alpha => "A",
+            Channel::Luminance => "L",
+        }
+    }
+
+    // 鼠标悬停时显示的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::All => tr("All channels"),
+            Channel::Red => tr("Red channel"),
+            Channel::Green => tr("Green channel"),
+            Channel::Blue => tr("Blue channel"),
+            Channel::Alpha => tr("Alpha channel"),
+            Channel::Luminance => tr("Luminance"),
+        }
+    }
+}
+
+/// 把一个颜色通道或亮度显示为灰度图；All 和 Alpha 返回 None，Alpha 需要从文件中读取
+pub fn isolate(img: &RgbImage, channel: Channel) -> Option<RgbImage> {
+    let value: fn(&[u8; 3]) -> u8 = match channel {
+        Channel::Red => |p| p[0],
+        Channel::Green => |p| p[1],
+        Channel::Blue => |p| p[2],
+        // Rec. 709 的亮度系数
+        Channel::Luminance => {
+            |p| (0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32).round() as u8
+        }
+        Channel::All | Channel::Alpha => return None,
+    };
+    Some(RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let v = value(&img.get_pixel(x, y).0);
+        Rgb([v, v, v])
+    }))
+}
+
+/// 读取图片文件的透明通道并显示为灰度图，白色为不透明；没有透明通道的图片全部为白色
+pub fn alpha(path: &Path, apply_orientation: bool) -> Result<RgbImage, String> {
+    let (img, _) = pipeline::decode_image(path, apply_orientation).map_err(|e| e.to_string())?;
+    let rgba = img.to_rgba8();
+    Ok(RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let a = rgba.get_pixel(x, y)[3];
+        Rgb([a, a, a])
+    }))
+}
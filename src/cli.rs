@@ -0,0 +1,95 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::config::SortOrder;
+use crate::remote_control::Command;
+
+/// 命令行参数，在窗口打开之前解析；帮助文字用英文，不经过翻译
+#[derive(Debug, Parser)]
+#[command(version, about = "Image browser")]
+pub struct Args {
+    // 启动时打开的图片或文件夹；从文件管理器打开图片时由系统传入
+    #[arg(value_parser = parse_path, help = "Image or folder to open (a path or file:// URL)")]
+    pub path: Option<PathBuf>,
+
+    #[arg(long, help = "Start in fullscreen mode")]
+    pub fullscreen: bool,
+
+    // 每张图片显示的秒数
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Start a slideshow, showing each image for SECONDS"
+    )]
+    pub slideshow: Option<u64>,
+
+    // 只影响本次运行，不写入配置
+    #[arg(
+        long,
+        value_name = "ORDER",
+        value_parser = parse_sort,
+        help = "Sort the library by name, date or size"
+    )]
+    pub sort: Option<SortOrder>,
+
+    // 默认把路径交给已经运行的实例
+    #[arg(long, help = "Open a separate window even if one is already running")]
+    pub new_instance: bool,
+
+    // 例如绑定到媒体键：image-browser --remote next
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        value_parser = Command::parse,
+        help = "Send a command (next, previous, pause-slideshow, stop-slideshow, fullscreen) to the running instance and exit"
+    )]
+    pub remote: Option<Command>,
+}
+
+// 有的文件管理器传入 file:// 链接而不是路径，转换为本地路径
+fn parse_path(text: &str) -> Result<PathBuf, String> {
+    let Some(rest) = text.strip_prefix("file://") else {
+        return Ok(PathBuf::from(text));
+    };
+    // 链接中的主机名只能为空或 localhost
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !rest.starts_with('/') {
+        return Err(format!("unsupported file URL '{}'", text));
+    }
+    let path = percent_decode(rest).ok_or_else(|| format!("invalid file URL '{}'", text))?;
+    // Windows 的 file:///C:/a.jpg 去掉盘符前的斜杠
+    if cfg!(windows) && path.as_bytes().get(2) == Some(&b':') {
+        return Ok(PathBuf::from(&path[1..]));
+    }
+    Ok(PathBuf::from(path))
+}
+
+// 解码 %XX 转义，结果不是 UTF-8 时返回 None
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn parse_sort(text: &str) -> Result<SortOrder, String> {
+    match text.to_lowercase().as_str() {
+        "name" => Ok(SortOrder::Name),
+        "date" | "modified" => Ok(SortOrder::Modified),
+        "size" => Ok(SortOrder::Size),
+        _ => Err(format!(
+            "unknown sort order '{}', expected name, date or size",
+            text
+        )),
+    }
+}
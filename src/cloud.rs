@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use crate::cloud_storage::{self, CloudConfig, CloudStorage, ObjectListing};
+use crate::file_tree::{Access, FileTreeEntry};
+use crate::formats;
+
+// 目录树中云端根节点的虚拟路径，子节点为 __CLOUD__/<对象键>
+pub const CLOUD_ROOT: &str = "__CLOUD__";
+
+/// 等待确认的云端操作
+#[derive(Debug, Clone)]
+pub enum RemoteEdit {
+    Delete(String),
+    Rename { key: String, target: String }, // target 为新的完整对象键，可以移到其他文件夹
+}
+
+/// 目录树中的云端根节点
+pub fn root_entry() -> FileTreeEntry {
+    FileTreeEntry::Directory {
+        name: "Cloud".to_string(),
+        path: PathBuf::from(CLOUD_ROOT),
+        children: Vec::new(),
+        expanded: false,
+        children_loaded: false,
+        link: None,
+        access: Access::Readable,
+    }
+}
+
+/// 虚拟路径对应的对象键（文件夹以 / 结尾），不是云端路径时返回 None
+pub fn object_key(path: &Path) -> Option<String> {
+    let rest = path.strip_prefix(CLOUD_ROOT).ok()?;
+    let parts: Vec<_> = rest
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// 虚拟路径对应的文件夹前缀：根节点为空，其他文件夹以 / 结尾
+pub fn folder_prefix(path: &Path) -> String {
+    let mut prefix = object_key(path).unwrap_or_default();
+    if !prefix.is_empty() {
+        prefix.push('/');
+    }
+    prefix
+}
+
+/// 对象所在文件夹的前缀
+pub fn parent_prefix(key: &str) -> &str {
+    key.rfind('/').map_or("", |end| &key[..=end])
+}
+
+pub fn is_remote(path: &Path) -> bool {
+    path.starts_with(CLOUD_ROOT)
+}
+
+/// 对象键（或以 / 结尾的文件夹前缀）对应的虚拟路径
+pub fn remote_path(key: &str) -> PathBuf {
+    let mut path = PathBuf::from(CLOUD_ROOT);
+    path.extend(key.split('/').filter(|part| !part.is_empty()));
+    path
+}
+
+/// 对象在本地缓存中的位置
+pub fn cache_path(config: &CloudConfig, key: &str) -> Option<PathBuf> {
+    let mut path = config.cache_dir()?;
+    path.extend(
+        key.split('/')
+            .filter(|part| !part.is_empty() && *part != ".."),
+    );
+    Some(path)
+}
+
+pub fn is_image(key: &str) -> bool {
+    formats::is_image(Path::new(key))
+}
+
+/// 列出文件夹中的对象，prefix 为空时列出存储桶根目录
+pub async fn list(config: CloudConfig, prefix: String) -> Result<ObjectListing, String> {
+    let storage = cloud_storage::connect(&config)?;
+    storage.list_objects(prefix).await
+}
+
+pub async fn delete(config: CloudConfig, key: String) -> Result<(), String> {
+    let storage = cloud_storage::connect(&config)?;
+    storage.delete_object(key).await
+}
+
+/// 重命名或移动对象：先复制为新的键，成功后再删除原对象
+pub async fn rename(config: CloudConfig, from: String, to: String) -> Result<(), String> {
+    let storage = cloud_storage::connect(&config)?;
+    storage.copy_object(from.clone(), to).await?;
+    storage.delete_object(from).await
+}
+
+/// 目录树中显示的子文件夹和图片
+pub fn tree_children(listing: &ObjectListing) -> Vec<FileTreeEntry> {
+    let folders = listing.folders.iter().map(|prefix| {
+        let name = prefix
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(prefix);
+        FileTreeEntry::Directory {
+            name: name.to_string(),
+            path: remote_path(prefix),
+            children: Vec::new(),
+            expanded: false,
+            children_loaded: false,
+            link: None,
+            access: Access::Readable,
+        }
+    });
+    let files = listing
+        .objects
+        .iter()
+        .filter(|object| is_image(&object.key))
+        .map(|object| FileTreeEntry::File {
+            name: object
+                .key
+                .rsplit('/')
+                .next()
+                .unwrap_or(&object.key)
+                .to_string(),
+            path: remote_path(&object.key),
+        });
+    folders.chain(files).collect()
+}
@@ -0,0 +1,424 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::cos_client::TecentCosUtil;
+use crate::i18n::tr;
+use crate::oss_client::OssClient;
+use crate::s3_client::S3Client;
+use crate::throttle;
+
+// 腾讯云 COS 的访问配置从环境变量读取
+const ENV_COS_SECRET_ID: &str = "COS_SECRET_ID";
+const ENV_COS_SECRET_KEY: &str = "COS_SECRET_KEY";
+const ENV_COS_REGION: &str = "COS_REGION";
+const ENV_COS_BUCKET: &str = "COS_BUCKET";
+
+/// 与具体云服务无关的对象存储接口
+#[allow(async_fn_in_trait)]
+pub trait CloudStorage {
+    /// 在 expires 秒内有效、不需要密钥就能下载对象的链接
+    fn presigned_url(&self, key: &str, expires: u64) -> Result<String, String>;
+
+    /// 把本地文件上传为 key
+    async fn upload_object(&self, path: PathBuf, key: String) -> Result<(), String>;
+
+    /// 列出 prefix 下一层的对象和“文件夹”
+    async fn list_objects(&self, prefix: String) -> Result<ObjectListing, String>;
+
+    /// 对象的大小（字节）
+    async fn object_size(&self, key: String) -> Result<u64, String>;
+
+    /// 下载对象中 start..=end 范围内的字节
+    async fn download_range(&self, key: String, start: u64, end: u64) -> Result<Vec<u8>, String>;
+
+    /// 对象的 ETag（不带引号），对象不存在时返回 None
+    async fn object_etag(&self, key: String) -> Result<Option<String>, String>;
+
+    /// 把 source 复制为 key
+    async fn copy_object(&self, source: String, key: String) -> Result<(), String>;
+
+    /// 删除对象
+    async fn delete_object(&self, key: String) -> Result<(), String>;
+}
+
+/// 存储桶中的一个对象
+#[derive(Debug, Clone)]
+pub struct RemoteObject {
+    pub key: String,
+    pub size: u64,
+    pub etag: String,  // 不带引号，内容变化时改变
+    pub modified: i64, // 最后修改时间（Unix 秒）
+}
+
+/// 某个前缀下的一层对象，folders 是下一级文件夹的前缀（以 / 结尾）
+#[derive(Debug, Clone, Default)]
+pub struct ObjectListing {
+    pub folders: Vec<String>,
+    pub objects: Vec<RemoteObject>,
+}
+
+/// 云存储服务商
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Provider {
+    #[default]
+    Cos,
+    Oss,
+    S3,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 3] = [Provider::Cos, Provider::Oss, Provider::S3];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Cos => tr("Tencent COS"),
+            Provider::Oss => tr("Aliyun OSS"),
+            Provider::S3 => tr("S3 compatible"),
+        }
+    }
+
+    // 本地缓存目录名
+    fn id(self) -> &'static str {
+        match self {
+            Provider::Cos => "cos",
+            Provider::Oss => "oss",
+            Provider::S3 => "s3",
+        }
+    }
+}
+
+/// S3 兼容服务（AWS、MinIO、Backblaze B2 等）的连接参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct S3Config {
+    pub endpoint: String, // 例如 https://s3.us-east-1.amazonaws.com
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub path_style: bool, // 使用 endpoint/bucket/key 形式的地址（MinIO 等需要）
+}
+
+/// 分享链接的有效期
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShareExpiry {
+    Hour,
+    #[default]
+    Day,
+    Week,
+}
+
+impl ShareExpiry {
+    pub const ALL: [ShareExpiry; 3] = [ShareExpiry::Hour, ShareExpiry::Day, ShareExpiry::Week];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShareExpiry::Hour => tr("1 hour"),
+            ShareExpiry::Day => tr("1 day"),
+            ShareExpiry::Week => tr("7 days"),
+        }
+    }
+
+    pub fn seconds(self) -> u64 {
+        match self {
+            ShareExpiry::Hour => 3600,
+            ShareExpiry::Day => 24 * 3600,
+            ShareExpiry::Week => 7 * 24 * 3600,
+        }
+    }
+}
+
+/// 阿里云 OSS 的连接参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OssConfig {
+    pub endpoint: String, // 例如 oss-cn-hangzhou.aliyuncs.com
+    pub bucket: String,
+    pub access_key_id: String,
+    pub access_key_secret: String,
+}
+
+/// 设置中选择的云存储
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudConfig {
+    pub provider: Provider,
+    pub oss: OssConfig,
+    pub s3: S3Config,
+}
+
+impl CloudConfig {
+    pub fn is_configured(&self) -> bool {
+        match self.provider {
+            Provider::Cos => cos_credentials().is_some(),
+            Provider::Oss => {
+                let oss = &self.oss;
+                [
+                    &oss.endpoint,
+                    &oss.bucket,
+                    &oss.access_key_id,
+                    &oss.access_key_secret,
+                ]
+                .iter()
+                .all(|value| !value.trim().is_empty())
+            }
+            Provider::S3 => {
+                let s3 = &self.s3;
+                [
+                    &s3.endpoint,
+                    &s3.region,
+                    &s3.bucket,
+                    &s3.access_key,
+                    &s3.secret_key,
+                ]
+                .iter()
+                .all(|value| !value.trim().is_empty())
+            }
+        }
+    }
+
+    /// 区分服务商和存储桶的标识，例如 s3/photos
+    pub fn bucket_id(&self) -> Option<String> {
+        let bucket = match self.provider {
+            Provider::Cos => cos_credentials()?[3].clone(),
+            Provider::Oss => self.oss.bucket.clone(),
+            Provider::S3 => self.s3.bucket.clone(),
+        };
+        Some(format!("{}/{}", self.provider.id(), bucket))
+    }
+
+    /// 下载的对象在本地缓存中的根目录，每个服务商和存储桶分开
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        let mut dir = cache_root()?;
+        dir.extend(self.bucket_id()?.split('/'));
+        Some(dir)
+    }
+}
+
+/// 所有存储桶的本地缓存所在的目录
+pub fn cache_root() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("image-browser"))
+}
+
+/// 按设置连接的云存储
+pub enum Storage {
+    Cos(TecentCosUtil),
+    Oss(OssClient),
+    S3(S3Client),
+}
+
+pub fn connect(config: &CloudConfig) -> Result<Storage, String> {
+    if !config.is_configured() {
+        return Err("Cloud storage is not configured".to_string());
+    }
+    match config.provider {
+        Provider::Cos => {
+            let [secret_id, secret_key, region, bucket] =
+                cos_credentials().ok_or_else(|| "Cloud storage is not configured".to_string())?;
+            Ok(Storage::Cos(TecentCosUtil::new(
+                secret_id, secret_key, region, bucket,
+            )))
+        }
+        Provider::Oss => OssClient::new(config.oss.clone()).map(Storage::Oss),
+        Provider::S3 => S3Client::new(config.s3.clone()).map(Storage::S3),
+    }
+}
+
+impl CloudStorage for Storage {
+    fn presigned_url(&self, key: &str, expires: u64) -> Result<String, String> {
+        match self {
+            Storage::Cos(client) => client.presigned_url(key, expires),
+            Storage::Oss(client) => client.presigned_url(key, expires),
+            Storage::S3(client) => client.presigned_url(key, expires),
+        }
+    }
+
+    async fn upload_object(&self, path: PathBuf, key: String) -> Result<(), String> {
+        match self {
+            Storage::Cos(client) => client.upload_object(path, key).await,
+            Storage::Oss(client) => client.upload_object(path, key).await,
+            Storage::S3(client) => client.upload_object(path, key).await,
+        }
+    }
+
+    async fn list_objects(&self, prefix: String) -> Result<ObjectListing, String> {
+        match self {
+            Storage::Cos(client) => client.list_objects(prefix).await,
+            Storage::Oss(client) => client.list_objects(prefix).await,
+            Storage::S3(client) => client.list_objects(prefix).await,
+        }
+    }
+
+    async fn object_size(&self, key: String) -> Result<u64, String> {
+        match self {
+            Storage::Cos(client) => client.object_size(key).await,
+            Storage::Oss(client) => client.object_size(key).await,
+            Storage::S3(client) => client.object_size(key).await,
+        }
+    }
+
+    async fn download_range(&self, key: String, start: u64, end: u64) -> Result<Vec<u8>, String> {
+        let bytes = match self {
+            Storage::Cos(client) => client.download_range(key, start, end).await,
+            Storage::Oss(client) => client.download_range(key, start, end).await,
+            Storage::S3(client) => client.download_range(key, start, end).await,
+        }?;
+        // 按下载限速等待后再交给调用方，下一块请求随之推迟
+        throttle::downloaded(bytes.len()).await;
+        Ok(bytes)
+    }
+
+    async fn object_etag(&self, key: String) -> Result<Option<String>, String> {
+        match self {
+            Storage::Cos(client) => client.object_etag(key).await,
+            Storage::Oss(client) => client.object_etag(key).await,
+            Storage::S3(client) => client.object_etag(key).await,
+        }
+    }
+
+    async fn copy_object(&self, source: String, key: String) -> Result<(), String> {
+        match self {
+            Storage::Cos(client) => client.copy_object(source, key).await,
+            Storage::Oss(client) => client.copy_object(source, key).await,
+            Storage::S3(client) => client.copy_object(source, key).await,
+        }
+    }
+
+    async fn delete_object(&self, key: String) -> Result<(), String> {
+        match self {
+            Storage::Cos(client) => client.delete_object(key).await,
+            Storage::Oss(client) => client.delete_object(key).await,
+            Storage::S3(client) => client.delete_object(key).await,
+        }
+    }
+}
+
+// secret id、secret key、地域和存储桶，缺少任何一项时返回 None
+fn cos_credentials() -> Option<[String; 4]> {
+    let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+    Some([
+        var(ENV_COS_SECRET_ID)?,
+        var(ENV_COS_SECRET_KEY)?,
+        var(ENV_COS_REGION)?,
+        var(ENV_COS_BUCKET)?,
+    ])
+}
+
+/// HTTP 请求失败时的错误：状态码和响应体 <Message> 中的说明
+pub fn http_error(status: u16, body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    let message = xml_values(&text, "Message")
+        .first()
+        .map(|message| unescape_xml(message))
+        .unwrap_or_default();
+    format!("HTTP {}: {}", status, message)
+}
+
+/// HEAD 请求返回的 ETag，404 表示对象不存在
+pub fn etag_from_head(
+    status: u16,
+    headers: &reqwest::header::HeaderMap,
+    body: &[u8],
+) -> Result<Option<String>, String> {
+    match status {
+        404 => Ok(None),
+        200..=299 => Ok(headers
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.trim_matches('"').to_string())),
+        _ => Err(http_error(status, body)),
+    }
+}
+
+/// 上传后的对象键：文件名
+pub fn upload_key(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "default_name".to_string())
+}
+
+/// 根据文件扩展名猜测上传时的 Content-Type
+pub fn content_type(key: &str) -> &'static str {
+    let ext = key.rsplit('.').next().unwrap_or_default().to_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+// 依次取出 <tag> 和 </tag> 之间的内容
+pub fn xml_values<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else { break };
+        values.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+pub fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// 解析 ListObjects 返回的一页结果，返回下一页的标记（没有更多时为 None）
+pub fn parse_listing(
+    xml: &str,
+    prefix: &str,
+    listing: &mut ObjectListing,
+    next_marker_tag: &str,
+) -> Option<String> {
+    for common in xml_values(xml, "CommonPrefixes") {
+        listing
+            .folders
+            .extend(xml_values(common, "Prefix").into_iter().map(unescape_xml));
+    }
+    for contents in xml_values(xml, "Contents") {
+        let Some(key) = xml_values(contents, "Key")
+            .first()
+            .map(|key| unescape_xml(key))
+        else {
+            continue;
+        };
+        // 以 / 结尾的空对象是控制台创建的文件夹本身
+        if key == prefix || key.ends_with('/') {
+            continue;
+        }
+        let size = xml_values(contents, "Size")
+            .first()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(0);
+        let etag = xml_values(contents, "ETag")
+            .first()
+            .map(|etag| unescape_xml(etag).trim_matches('"').to_string())
+            .unwrap_or_default();
+        let modified = xml_values(contents, "LastModified")
+            .first()
+            .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+            .map_or(0, |time| time.timestamp());
+        listing.objects.push(RemoteObject {
+            key,
+            size,
+            etag,
+            modified,
+        });
+    }
+    let truncated = xml_values(xml, "IsTruncated").first() == Some(&"true");
+    xml_values(xml, next_marker_tag)
+        .first()
+        .filter(|next| truncated && !next.is_empty())
+        .map(|next| unescape_xml(next))
+}
@@ -0,0 +1,244 @@
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Stroke};
+use iced::widget::image::Handle;
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+use image::{GrayImage, RgbImage};
+use std::path::PathBuf;
+
+use crate::barcodes;
+use crate::messages::Message;
+use crate::qr;
+
+// 超过这个边长的图片先缩小再识别
+const MAX_SCAN_SIZE: u32 = 2000;
+
+/// 能识别的码制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeKind {
+    Qr,
+    Ean13,
+    UpcA,
+    Ean8,
+    Code128,
+}
+
+impl CodeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            CodeKind::Qr => "QR Code",
+            CodeKind::Ean13 => "EAN-13",
+            CodeKind::UpcA => "UPC-A",
+            CodeKind::Ean8 => "EAN-8",
+            CodeKind::Code128 => "Code 128",
+        }
+    }
+}
+
+/// 识别出的二维码或条形码
+#[derive(Debug, Clone)]
+pub struct DetectedCode {
+    pub kind: CodeKind,
+    pub text: String,
+    pub corners: [Point; 4], // 原图坐标，顺时针排列
+}
+
+impl DetectedCode {
+    /// 内容是网址时可以直接打开
+    pub fn is_link(&self) -> bool {
+        let text = self.text.trim().to_lowercase();
+        text.starts_with("http://") || text.starts_with("https://")
+    }
+}
+
+/// 当前图片的识别结果，codes 为 None 时正在识别
+#[derive(Debug, Clone)]
+pub struct CodeScan {
+    pub path: PathBuf,
+    pub codes: Option<Vec<DetectedCode>>,
+}
+
+/// 二值化后的图片，true 为深色
+pub struct Bits {
+    pub width: usize,
+    pub height: usize,
+    data: Vec<bool>,
+}
+
+impl Bits {
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.data[y * self.width + x]
+    }
+
+    /// 按浮点坐标取所在的像素，超出图片时为浅色
+    pub fn get_f(&self, x: f32, y: f32) -> bool {
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return false;
+        }
+        self.get(x as usize, y as usize)
+    }
+
+    // 全局阈值（Otsu 方法），适合光照均匀的截图和生成的图片
+    fn global(gray: &GrayImage) -> Bits {
+        let mut histogram = [0u64; 256];
+        gray.pixels().for_each(|p| histogram[p.0[0] as usize] += 1);
+        let total: u64 = histogram.iter().sum();
+        let sum: u64 = histogram
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| i as u64 * n)
+            .sum();
+        let (mut background, mut background_sum) = (0u64, 0u64);
+        let (mut best, mut threshold) = (0.0, 128u8);
+        for (i, &n) in histogram.iter().enumerate() {
+            background += n;
+            background_sum += i as u64 * n;
+            if background == 0 || background == total {
+                continue;
+            }
+            let foreground = total - background;
+            let mean_b = background_sum as f64 / background as f64;
+            let mean_f = (sum - background_sum) as f64 / foreground as f64;
+            let variance = background as f64 * foreground as f64 * (mean_b - mean_f).powi(2);
+            if variance > best {
+                best = variance;
+                threshold = i as u8;
+            }
+        }
+        Bits {
+            width: gray.width() as usize,
+            height: gray.height() as usize,
+            data: gray.pixels().map(|p| p.0[0] <= threshold).collect(),
+        }
+    }
+
+    // 局部阈值：比周围平均亮度暗 15% 以上的像素为深色，适合拍摄的照片
+    fn adaptive(gray: &GrayImage) -> Bits {
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+        let mut integral = vec![0u64; (width + 1) * (height + 1)];
+        for y in 0..height {
+            let mut row = 0u64;
+            for x in 0..width {
+                row += gray.get_pixel(x as u32, y as u32).0[0] as u64;
+                integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+            }
+        }
+        let half = (width.max(height) / 16).max(8);
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let (top, bottom) = (y.saturating_sub(half), (y + half + 1).min(height));
+            for x in 0..width {
+                let (left, right) = (x.saturating_sub(half), (x + half + 1).min(width));
+                let area = ((bottom - top) * (right - left)) as u64;
+                let sum = integral[bottom * (width + 1) + right]
+                    + integral[top * (width + 1) + left]
+                    - integral[top * (width + 1) + right]
+                    - integral[bottom * (width + 1) + left];
+                let value = gray.get_pixel(x as u32, y as u32).0[0] as u64;
+                data.push(value * area * 100 < sum * 85);
+            }
+        }
+        Bits {
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+/// 识别图片中的二维码和条形码，同样的内容只保留一个
+pub fn scan(img: &RgbImage) -> Vec<DetectedCode> {
+    let mut gray = image::imageops::grayscale(img);
+    let mut scale = 1.0;
+    let longest = gray.width().max(gray.height());
+    if longest > MAX_SCAN_SIZE {
+        scale = longest as f32 / MAX_SCAN_SIZE as f32;
+        let (w, h) = (
+            (gray.width() as f32 / scale).round().max(1.0) as u32,
+            (gray.height() as f32 / scale).round().max(1.0) as u32,
+        );
+        gray = image::imageops::resize(&gray, w, h, image::imageops::FilterType::Triangle);
+    }
+
+    let mut codes: Vec<DetectedCode> = Vec::new();
+    for bits in [Bits::global(&gray), Bits::adaptive(&gray)] {
+        let found = qr::detect(&bits)
+            .into_iter()
+            .map(|code| DetectedCode {
+                kind: CodeKind::Qr,
+                text: code.text,
+                corners: code.corners,
+            })
+            .chain(barcodes::detect(&bits));
+        for mut code in found {
+            if codes
+                .iter()
+                .any(|other| other.kind == code.kind && other.text == code.text)
+            {
+                continue;
+            }
+            code.corners = code
+                .corners
+                .map(|point| Point::new(point.x * scale, point.y * scale));
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+/// 在画布上显示图片并标出识别到的码的位置和序号
+pub struct CodeOverlay<'a> {
+    pub handle: &'a Handle,
+    pub image_size: (u32, u32),
+    pub codes: &'a [DetectedCode],
+}
+
+impl canvas::Program<Message> for CodeOverlay<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (w, h) = (self.image_size.0 as f32, self.image_size.1 as f32);
+        let scale = (bounds.width / w).min(bounds.height / h);
+        let size = Size::new(w * scale, h * scale);
+        let origin = Point::new(
+            (bounds.width - size.width) / 2.0,
+            (bounds.height - size.height) / 2.0,
+        );
+        frame.draw_image(Rectangle::new(origin, size), self.handle);
+
+        let color = Color::from_rgb8(0x2e, 0xcc, 0x71);
+        let to_canvas =
+            |point: Point| Point::new(origin.x + point.x * scale, origin.y + point.y * scale);
+        for (index, code) in self.codes.iter().enumerate() {
+            let corners = code.corners.map(to_canvas);
+            let outline = canvas::Path::new(|builder| {
+                builder.move_to(corners[0]);
+                corners[1..]
+                    .iter()
+                    .for_each(|&point| builder.line_to(point));
+                builder.close();
+            });
+            frame.fill(&outline, Color { a: 0.2, ..color });
+            frame.stroke(
+                &outline,
+                Stroke::default().with_width(3.0).with_color(color),
+            );
+            frame.fill_text(canvas::Text {
+                content: (index + 1).to_string(),
+                position: Point::new(corners[0].x + 4.0, corners[0].y + 2.0),
+                color: Color::WHITE,
+                size: 18.into(),
+                ..canvas::Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,265 @@
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Stroke, event};
+use iced::widget::image::Handle;
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::messages::Message;
+
+/// 最多保留的最近取色数
+pub const HISTORY_SIZE: usize = 16;
+
+// 放大镜显示光标周围多少个像素（边长，奇数），每个像素画多大
+const LOUPE_PIXELS: i64 = 11;
+const LOUPE_CELL: f32 = 8.0;
+
+/// 复制颜色时使用的写法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorFormat {
+    #[default]
+    Hex, // #1A2B3C
+    Rgb, // rgb(26, 43, 60)
+}
+
+impl ColorFormat {
+    pub const ALL: [ColorFormat; 2] = [ColorFormat::Hex, ColorFormat::Rgb];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorFormat::Hex => "HEX",
+            ColorFormat::Rgb => "RGB",
+        }
+    }
+
+    pub fn format(self, color: [u8; 3]) -> String {
+        match self {
+            ColorFormat::Hex => to_hex(color),
+            ColorFormat::Rgb => format!("rgb({}, {}, {})", color[0], color[1], color[2]),
+        }
+    }
+}
+
+pub fn to_hex(color: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+}
+
+/// 解析 #RRGGBB，配置文件中的取色历史用这种写法保存
+pub fn parse_hex(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// 把颜色写为调色板文件，格式由扩展名决定：.gpl（GIMP）或 .ase（Adobe）
+pub fn export(colors: &[[u8; 3]], dst: &Path) -> Result<(), String> {
+    let extension = dst
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let bytes = match extension.as_str() {
+        "ase" => ase(colors),
+        _ => gpl(colors).into_bytes(),
+    };
+    std::fs::write(dst, bytes).map_err(|e| e.to_string())
+}
+
+// GIMP 调色板：文本文件，每行一个颜色
+fn gpl(colors: &[[u8; 3]]) -> String {
+    let mut content = String::from("GIMP Palette\nName: Picked colors\nColumns: 8\n#\n");
+    for &color in colors {
+        content.push_str(&format!(
+            "{:3} {:3} {:3}\t{}\n",
+            color[0],
+            color[1],
+            color[2],
+            to_hex(color)
+        ));
+    }
+    content
+}
+
+// Adobe Swatch Exchange：大端序，文件头后每个颜色一个块，名称为 UTF-16 并以 0 结尾
+fn ase(colors: &[[u8; 3]]) -> Vec<u8> {
+    let mut bytes = b"ASEF".to_vec();
+    bytes.extend(1u16.to_be_bytes()); // 版本 1.0
+    bytes.extend(0u16.to_be_bytes());
+    bytes.extend((colors.len() as u32).to_be_bytes());
+    for &color in colors {
+        let name: Vec<u16> = to_hex(color).encode_utf16().chain([0]).collect();
+        let mut block = Vec::new();
+        block.extend((name.len() as u16).to_be_bytes());
+        name.iter()
+            .for_each(|unit| block.extend(unit.to_be_bytes()));
+        block.extend(b"RGB ");
+        for channel in color {
+            block.extend((channel as f32 / 255.0).to_be_bytes());
+        }
+        block.extend(2u16.to_be_bytes()); // 普通色（不是专色或全局色）
+        bytes.extend(1u16.to_be_bytes()); // 颜色块
+        bytes.extend((block.len() as u32).to_be_bytes());
+        bytes.extend(block);
+    }
+    bytes
+}
+
+/// 取色模式下显示图片，光标旁显示放大的像素，点击取色
+pub struct ColorPickerOverlay<'a> {
+    pub handle: &'a Handle,
+    pub image: &'a RgbImage,
+}
+
+impl ColorPickerOverlay<'_> {
+    // 图片按 Contain 方式放入画布后的位置和缩放比例
+    fn fit(&self, bounds: Size) -> (Rectangle, f32) {
+        let (w, h) = (self.image.width() as f32, self.image.height() as f32);
+        let scale = (bounds.width / w).min(bounds.height / h);
+        let size = Size::new(w * scale, h * scale);
+        let origin = Point::new(
+            (bounds.width - size.width) / 2.0,
+            (bounds.height - size.height) / 2.0,
+        );
+        (Rectangle::new(origin, size), scale)
+    }
+
+    // 画布坐标处的像素位置，不在图片上时为 None
+    fn pixel_at(&self, position: Point, bounds: Size) -> Option<(u32, u32)> {
+        let (fit, scale) = self.fit(bounds);
+        if !fit.contains(position) {
+            return None;
+        }
+        let x = ((position.x - fit.x) / scale) as u32;
+        let y = ((position.y - fit.y) / scale) as u32;
+        Some((
+            x.min(self.image.width() - 1),
+            y.min(self.image.height() - 1),
+        ))
+    }
+
+    fn color_at(&self, position: Point, bounds: Size) -> Option<[u8; 3]> {
+        let (x, y) = self.pixel_at(position, bounds)?;
+        Some(self.image.get_pixel(x, y).0)
+    }
+}
+
+impl canvas::Program<Message> for ColorPickerOverlay<'_> {
+    // 光标下的颜色，变化时才通知工具栏
+    type State = Option<[u8; 3]>;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match event {
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let color = cursor
+                    .position_in(bounds)
+                    .and_then(|position| self.color_at(position, bounds.size()));
+                if color == *state {
+                    return (event::Status::Ignored, None);
+                }
+                *state = color;
+                (event::Status::Ignored, Some(Message::ColorHovered(color)))
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(color) = cursor
+                    .position_in(bounds)
+                    .and_then(|position| self.color_at(position, bounds.size()))
+                else {
+                    return (event::Status::Ignored, None);
+                };
+                (event::Status::Captured, Some(Message::ColorPicked(color)))
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (fit, _) = self.fit(bounds.size());
+        frame.draw_image(fit, self.handle);
+
+        // 放大镜：光标右下方显示周围的像素，中间的像素加框
+        let Some(position) = cursor.position_in(bounds) else {
+            return vec![frame.into_geometry()];
+        };
+        let Some((x, y)) = self.pixel_at(position, bounds.size()) else {
+            return vec![frame.into_geometry()];
+        };
+        let side = LOUPE_PIXELS as f32 * LOUPE_CELL;
+        let mut origin = Point::new(position.x + 20.0, position.y + 20.0);
+        // 靠近右边或下边时放到光标的另一侧
+        if origin.x + side > bounds.width {
+            origin.x = position.x - 20.0 - side;
+        }
+        if origin.y + side > bounds.height {
+            origin.y = position.y - 20.0 - side;
+        }
+        let half = LOUPE_PIXELS / 2;
+        for row in 0..LOUPE_PIXELS {
+            for column in 0..LOUPE_PIXELS {
+                let (px, py) = (x as i64 + column - half, y as i64 + row - half);
+                let color = if px < 0
+                    || py < 0
+                    || px >= self.image.width() as i64
+                    || py >= self.image.height() as i64
+                {
+                    Color::from_rgb8(0x80, 0x80, 0x80)
+                } else {
+                    let [r, g, b] = self.image.get_pixel(px as u32, py as u32).0;
+                    Color::from_rgb8(r, g, b)
+                };
+                frame.fill_rectangle(
+                    Point::new(
+                        origin.x + column as f32 * LOUPE_CELL,
+                        origin.y + row as f32 * LOUPE_CELL,
+                    ),
+                    Size::new(LOUPE_CELL, LOUPE_CELL),
+                    color,
+                );
+            }
+        }
+        let center = Point::new(
+            origin.x + half as f32 * LOUPE_CELL,
+            origin.y + half as f32 * LOUPE_CELL,
+        );
+        frame.stroke(
+            &canvas::Path::rectangle(center, Size::new(LOUPE_CELL, LOUPE_CELL)),
+            Stroke::default().with_width(1.5).with_color(Color::WHITE),
+        );
+        frame.stroke(
+            &canvas::Path::rectangle(origin, Size::new(side, side)),
+            Stroke::default().with_width(2.0).with_color(Color::WHITE),
+        );
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
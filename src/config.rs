@@ -0,0 +1,282 @@
+use iced::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::color_picker::ColorFormat;
+use crate::i18n::{Language, tr};
+use crate::palette::Palette;
+use crate::pipeline::ResamplingType;
+use crate::{keymap, memory, settings};
+
+// 缩略图边长的范围（像素）
+pub const THUMBNAIL_SIZE_MIN: u32 = 48;
+pub const THUMBNAIL_SIZE_MAX: u32 = 256;
+
+// 设置对话框中可选的缩略图边长（像素）
+pub const THUMBNAIL_SIZES: [u32; 4] = [64, 80, 120, 160];
+
+// 设置对话框中可选的内存上限（MB）
+pub const MEMORY_BUDGETS: [usize; 4] = [512, 1024, 2048, 4096];
+
+// 闪烁比较时可选的切换间隔（毫秒）
+pub const BLINK_INTERVALS: [u64; 5] = [150, 250, 500, 1000, 2000];
+
+/// 图片库中图片的排列顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Name,
+    Modified, // 最新修改的在前
+    Size,     // 最大的在前
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 3] = [SortOrder::Name, SortOrder::Modified, SortOrder::Size];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Name => tr("Name"),
+            SortOrder::Modified => tr("Date modified"),
+            SortOrder::Size => tr("Size"),
+        }
+    }
+
+    /// 按这个顺序排列图片
+    pub fn sort(self, images: &mut [PathBuf]) {
+        match self {
+            SortOrder::Name => images.sort_by_cached_key(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+                    .unwrap_or_default()
+            }),
+            SortOrder::Modified => images.sort_by_cached_key(|path| {
+                std::cmp::Reverse(fs::metadata(path).and_then(|meta| meta.modified()).ok())
+            }),
+            SortOrder::Size => images.sort_by_cached_key(|path| {
+                std::cmp::Reverse(fs::metadata(path).map_or(0, |meta| meta.len()))
+            }),
+        }
+    }
+}
+
+/// 图片区域的背景色，Auto 时随主题变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backdrop {
+    #[default]
+    Auto,
+    Gray,
+    Dark,
+    Black,
+}
+
+impl Backdrop {
+    pub const ALL: [Backdrop; 4] = [
+        Backdrop::Auto,
+        Backdrop::Gray,
+        Backdrop::Dark,
+        Backdrop::Black,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backdrop::Auto => tr("Theme"),
+            Backdrop::Gray => tr("Gray"),
+            Backdrop::Dark => tr("Dark"),
+            Backdrop::Black => tr("Black"),
+        }
+    }
+
+    pub fn color(self, palette: &Palette) -> Color {
+        match self {
+            Backdrop::Auto => palette.canvas,
+            Backdrop::Gray => Color::from_rgb8(128, 128, 128),
+            Backdrop::Dark => Color::from_rgb8(33, 37, 41),
+            Backdrop::Black => Color::BLACK,
+        }
+    }
+}
+
+/// 图片下方的缩略图栏：展开、只显示标题栏或隐藏
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailStrip {
+    #[default]
+    Shown,
+    Collapsed,
+    Hidden,
+}
+
+/// 界面主题，System 时跟随系统
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    #[default]
+    Light,
+    Dark,
+    System,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::Light, ThemeMode::Dark, ThemeMode::System];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeMode::Light => tr("Light"),
+            ThemeMode::Dark => tr("Dark"),
+            ThemeMode::System => tr("System"),
+        }
+    }
+}
+
+/// 主按钮、选中项和滑块使用的强调色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Accent {
+    #[default]
+    Blue,
+    Purple,
+    Pink,
+    Orange,
+    Green,
+    Teal,
+}
+
+impl Accent {
+    pub const ALL: [Accent; 6] = [
+        Accent::Blue,
+        Accent::Purple,
+        Accent::Pink,
+        Accent::Orange,
+        Accent::Green,
+        Accent::Teal,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Accent::Blue => tr("Blue"),
+            Accent::Purple => tr("Purple"),
+            Accent::Pink => tr("Pink"),
+            Accent::Orange => tr("Orange"),
+            Accent::Green => tr("Green"),
+            Accent::Teal => tr("Teal"),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Accent::Blue => Color::from_rgb8(13, 110, 253),
+            Accent::Purple => Color::from_rgb8(111, 66, 193),
+            Accent::Pink => Color::from_rgb8(214, 51, 132),
+            Accent::Orange => Color::from_rgb8(232, 89, 12),
+            Accent::Green => Color::from_rgb8(25, 135, 84),
+            Accent::Teal => Color::from_rgb8(12, 133, 153),
+        }
+    }
+}
+
+/// 配置目录中的 config.toml，保存界面偏好；云存储凭据等仍保存在 settings.json 中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub resampling: ResamplingType,      // 默认的缩放算法
+    pub sort: SortOrder,                 // 图片库的排列顺序
+    pub thumbnail_size: u32,             // 缩略图边长（像素）
+    pub thumbnail_strip: ThumbnailStrip, // 缩略图栏的显示方式
+    pub backdrop: Backdrop,              // 图片区域的背景色
+    pub memory_budget: usize,            // 图片和缓存合计的内存上限（MB）
+    pub blink_interval: u64,             // 闪烁比较时两张图片切换的间隔（毫秒）
+    pub ocr_languages: String,           // 文字识别的 Tesseract 语言，多个用 + 连接
+    pub picked_colors: Vec<String>,      // 最近用取色器选取的颜色（#RRGGBB），最新的在前
+    pub color_format: ColorFormat,       // 复制颜色时的写法
+    pub start_folder: Option<PathBuf>,   // 目录树中显示的文件夹，为空时使用主目录
+    pub settings_file: Option<PathBuf>,  // 使用其他位置的 settings.json（例如共享的云存储设置）
+    pub pinned_folders: Vec<PathBuf>,    // 固定在目录树根部的文件夹
+    pub show_hidden: bool,               // 目录树和图片库中显示隐藏的文件和文件夹
+    pub disabled_plugins: Vec<String>,   // 停用的插件（动态库的文件名，不含扩展名）
+    pub theme: ThemeMode,
+    pub accent: Accent,
+    pub language: Language,
+    pub keys: BTreeMap<String, String>, // 动作名称 → 快捷键，例如 save = "Ctrl+S"
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            resampling: ResamplingType::Lanczos3,
+            sort: SortOrder::default(),
+            thumbnail_size: 80,
+            thumbnail_strip: ThumbnailStrip::default(),
+            backdrop: Backdrop::default(),
+            memory_budget: memory::DEFAULT_BUDGET >> 20,
+            blink_interval: 500,
+            ocr_languages: "eng".to_string(),
+            picked_colors: Vec::new(),
+            color_format: ColorFormat::default(),
+            start_folder: None,
+            settings_file: None,
+            pinned_folders: Vec::new(),
+            show_hidden: false,
+            disabled_plugins: Vec::new(),
+            theme: ThemeMode::default(),
+            accent: Accent::default(),
+            language: Language::default(),
+            keys: keymap::default_keys(),
+        }
+    }
+}
+
+impl Config {
+    /// 读取配置文件；文件不存在时写入默认配置，方便用户找到并修改
+    pub fn load_or_create(path: &Path) -> Result<Config, Box<dyn Error>> {
+        if !path.is_file() {
+            let config = Config::default();
+            config.save_to_file(path)?;
+            return Ok(config);
+        }
+        let content = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&content)?;
+        config.thumbnail_size = config
+            .thumbnail_size
+            .clamp(THUMBNAIL_SIZE_MIN, THUMBNAIL_SIZE_MAX);
+        config.blink_interval = config.blink_interval.clamp(
+            BLINK_INTERVALS[0],
+            BLINK_INTERVALS[BLINK_INTERVALS.len() - 1],
+        );
+        Ok(config)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 内存上限（字节）
+    pub fn memory_budget_bytes(&self) -> usize {
+        self.memory_budget.max(MEMORY_BUDGETS[0]) << 20
+    }
+
+    /// 设置文件的位置：配置中指定的文件，否则为数据目录中的 settings.json
+    pub fn settings_path(&self) -> Option<PathBuf> {
+        self.settings_file.clone().or_else(settings::settings_path)
+    }
+}
+
+/// 配置文件的位置
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("image-browser").join("config.toml"))
+}
+
+/// 应用的数据目录，图片目录数据库、设置、插件等都放在这里，不存在时创建
+pub fn data_dir() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("image-browser");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
@@ -0,0 +1,384 @@
+use iced::advanced::graphics::text::cosmic_text::{
+    Attrs, Buffer, Color, Family, Metrics, Shaping, SwashCache,
+};
+use iced::advanced::graphics::text::font_system;
+use iced::futures::{SinkExt, Stream};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, Rgb, RgbImage};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::file_tree::file_name;
+use crate::i18n::tr;
+use crate::pipeline::{self, ResamplingType};
+use crate::{convert, export};
+
+// 对话框中可选的格子边长（像素）
+pub const CELL_SIZES: [u32; 4] = [160, 240, 320, 480];
+
+// PDF 页面按 150 DPI 换算为点
+const PDF_DPI: f32 = 150.0;
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const CELL_BACKGROUND: Rgb<u8> = Rgb([242, 242, 242]);
+
+/// 联系表的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SheetFormat {
+    #[default]
+    Png,
+    Pdf, // 每页一张 JPEG
+}
+
+impl SheetFormat {
+    pub const ALL: [SheetFormat; 2] = [SheetFormat::Png, SheetFormat::Pdf];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SheetFormat::Png => "PNG",
+            SheetFormat::Pdf => "PDF",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SheetFormat::Png => "png",
+            SheetFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// 联系表的版式：每页 columns × rows 个格子，图片多时分成多页
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetOptions {
+    pub columns: u32,
+    pub rows: u32,
+    pub cell_size: u32, // 缩略图区域的边长（像素）
+    pub labels: bool,   // 缩略图下方显示文件名
+    pub format: SheetFormat,
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        SheetOptions {
+            columns: 5,
+            rows: 6,
+            cell_size: 240,
+            labels: true,
+            format: SheetFormat::default(),
+        }
+    }
+}
+
+impl SheetOptions {
+    /// 图片数量对应的页数
+    pub fn pages(&self, count: usize) -> usize {
+        count.div_ceil((self.columns * self.rows) as usize)
+    }
+
+    // 格子之间和页面四周的留白
+    fn gap(&self) -> u32 {
+        (self.cell_size / 12).max(8)
+    }
+
+    fn label_size(&self) -> f32 {
+        (self.cell_size as f32 / 14.0).clamp(11.0, 20.0)
+    }
+
+    // 一个格子的高度：缩略图加上文件名
+    fn cell_height(&self) -> u32 {
+        if self.labels {
+            self.cell_size + (self.label_size() * 1.8).ceil() as u32
+        } else {
+            self.cell_size
+        }
+    }
+}
+
+/// 生成进度：每放入一张缩略图发送一次 Placed
+#[derive(Debug, Clone)]
+pub enum SheetProgress {
+    Placed,
+    Finished(Result<Vec<PathBuf>, String>), // 写出的文件
+}
+
+/// 把图片的缩略图和文件名排在一页或多页上，写入 output；
+/// PNG 有多页时依次写到 output 旁边的 名称_1.png、名称_2.png ……
+pub fn export(
+    sources: Vec<PathBuf>,
+    options: SheetOptions,
+    output: PathBuf,
+) -> impl Stream<Item = SheetProgress> {
+    iced::stream::channel(4, move |mut sender| async move {
+        let per_page = (options.columns * options.rows) as usize;
+        let mut pages = Vec::new();
+        for chunk in sources.chunks(per_page.max(1)) {
+            let mut page = new_page(&options, chunk.len());
+            let mut cache = SwashCache::new();
+            for (index, path) in chunk.iter().enumerate() {
+                let source = path.clone();
+                let thumbnail =
+                    tokio::task::spawn_blocking(move || thumbnail(&source, options.cell_size))
+                        .await
+                        .ok()
+                        .flatten();
+                place(&mut page, &options, index, thumbnail.as_ref());
+                if options.labels {
+                    draw_label(&mut page, &options, index, &file_name(path), &mut cache);
+                }
+                let _ = sender.send(SheetProgress::Placed).await;
+            }
+            pages.push(page);
+        }
+        let result = tokio::task::spawn_blocking(move || write_pages(&pages, &options, &output))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+        let _ = sender.send(SheetProgress::Finished(result)).await;
+    })
+}
+
+// 放 count 张图片的空白页面，最后一页只保留用到的行
+fn new_page(options: &SheetOptions, count: usize) -> RgbImage {
+    let gap = options.gap();
+    let rows = (count as u32).div_ceil(options.columns).max(1);
+    let width = gap + options.columns * (options.cell_size + gap);
+    let height = gap + rows * (options.cell_height() + gap);
+    RgbImage::from_pixel(width, height, BACKGROUND)
+}
+
+// 第 index 个格子左上角的位置
+fn cell_origin(options: &SheetOptions, index: usize) -> (u32, u32) {
+    let gap = options.gap();
+    let (column, row) = (
+        index as u32 % options.columns,
+        index as u32 / options.columns,
+    );
+    (
+        gap + column * (options.cell_size + gap),
+        gap + row * (options.cell_height() + gap),
+    )
+}
+
+// 解码并等比缩小到 size × size 以内，无法解码时返回 None
+fn thumbnail(path: &Path, size: u32) -> Option<RgbImage> {
+    let decoded = pipeline::decode_for_display(path, true).ok()?;
+    let img = decoded.image.as_ref();
+    let (width, height) = export::fit_within(img.dimensions(), size);
+    export::resize_image(img, width, height, ResamplingType::Lanczos3).ok()
+}
+
+// 把缩略图居中放进格子；没有缩略图时只留下灰色的格子
+fn place(page: &mut RgbImage, options: &SheetOptions, index: usize, thumbnail: Option<&RgbImage>) {
+    let (x, y) = cell_origin(options, index);
+    let size = options.cell_size;
+    for dy in 0..size {
+        for dx in 0..size {
+            page.put_pixel(x + dx, y + dy, CELL_BACKGROUND);
+        }
+    }
+    if let Some(thumbnail) = thumbnail {
+        image::imageops::replace(
+            page,
+            thumbnail,
+            (x + (size - thumbnail.width()) / 2) as i64,
+            (y + (size - thumbnail.height()) / 2) as i64,
+        );
+    }
+}
+
+// 在格子下方居中写文件名，太长时省略中间部分
+fn draw_label(
+    page: &mut RgbImage,
+    options: &SheetOptions,
+    index: usize,
+    name: &str,
+    cache: &mut SwashCache,
+) {
+    let (x, y) = cell_origin(options, index);
+    let size = options.label_size();
+    let max_width = options.cell_size as f32;
+    let mut font_system = font_system().write().unwrap();
+    let font_system = font_system.raw();
+    let mut buffer = Buffer::new(font_system, Metrics::new(size, size * 1.25));
+    buffer.set_size(font_system, None, None);
+
+    let chars: Vec<char> = name.chars().collect();
+    let mut keep = chars.len();
+    let width = loop {
+        let text = if keep == chars.len() {
+            name.to_string()
+        } else {
+            let head: String = chars[..keep.div_ceil(2)].iter().collect();
+            let tail: String = chars[chars.len() - keep / 2..].iter().collect();
+            format!("{}…{}", head, tail)
+        };
+        buffer.set_text(
+            font_system,
+            &text,
+            Attrs::new().family(Family::SansSerif),
+            Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(font_system, false);
+        let width = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max);
+        if width <= max_width || keep == 0 {
+            break width;
+        }
+        keep -= 1;
+    };
+
+    let left = x as i32 + ((max_width - width) / 2.0).max(0.0) as i32;
+    let top = (y + options.cell_size) as i32 + (size * 0.4) as i32;
+    buffer.draw(
+        font_system,
+        cache,
+        Color::rgb(64, 64, 64),
+        |gx, gy, w, h, color| {
+            let alpha = color.a() as u32;
+            for py in gy..gy + h as i32 {
+                for px in gx..gx + w as i32 {
+                    let (px, py) = (left + px, top + py);
+                    if px < 0 || py < 0 || px >= page.width() as i32 || py >= page.height() as i32 {
+                        continue;
+                    }
+                    let pixel = page.get_pixel_mut(px as u32, py as u32);
+                    for (channel, value) in
+                        pixel.0.iter_mut().zip([color.r(), color.g(), color.b()])
+                    {
+                        *channel =
+                            ((value as u32 * alpha + *channel as u32 * (255 - alpha)) / 255) as u8;
+                    }
+                }
+            }
+        },
+    );
+}
+
+fn write_pages(
+    pages: &[RgbImage],
+    options: &SheetOptions,
+    output: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    match options.format {
+        SheetFormat::Png if pages.len() == 1 => {
+            pages[0].save(output).map_err(|e| e.to_string())?;
+            Ok(vec![output.to_path_buf()])
+        }
+        SheetFormat::Png => {
+            let dir = output.parent().unwrap_or(Path::new("."));
+            let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+            pages
+                .iter()
+                .enumerate()
+                .map(|(index, page)| {
+                    let path = convert::unique_path(dir, &format!("{}_{}", stem, index + 1), "png");
+                    page.save(&path).map_err(|e| e.to_string())?;
+                    Ok(path)
+                })
+                .collect()
+        }
+        SheetFormat::Pdf => {
+            write_pdf(pages, output).map_err(|e| e.to_string())?;
+            Ok(vec![output.to_path_buf()])
+        }
+    }
+}
+
+// 最简单的 PDF：每页放一张 JPEG（DCTDecode），页面大小与图片一致
+fn write_pdf(pages: &[RgbImage], output: &Path) -> std::io::Result<()> {
+    let mut pdf: Vec<u8> = Vec::new();
+    let mut offsets = Vec::new();
+    pdf.extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+
+    // 对象编号：1 目录，2 页面树，之后每页依次为页面、内容、图片
+    let page_ids: Vec<usize> = (0..pages.len()).map(|index| 3 + index * 3).collect();
+    let mut object = |pdf: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", offsets.len()).as_bytes());
+        pdf.extend_from_slice(body);
+        pdf.extend_from_slice(b"\nendobj\n");
+    };
+    object(&mut pdf, b"<< /Type /Catalog /Pages 2 0 R >>");
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    object(
+        &mut pdf,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .as_bytes(),
+    );
+    for (page, id) in pages.iter().zip(&page_ids) {
+        let (width, height) = (
+            page.width() as f32 * 72.0 / PDF_DPI,
+            page.height() as f32 * 72.0 / PDF_DPI,
+        );
+        object(
+            &mut pdf,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                width,
+                height,
+                id + 2,
+                id + 1
+            )
+            .as_bytes(),
+        );
+        let content = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q", width, height);
+        object(
+            &mut pdf,
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            )
+            .as_bytes(),
+        );
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(page.clone())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, 90))
+            .map_err(std::io::Error::other)?;
+        let mut body = format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            page.width(),
+            page.height(),
+            jpeg.len()
+        )
+        .into_bytes();
+        body.extend_from_slice(&jpeg);
+        body.extend_from_slice(b"\nendstream");
+        object(&mut pdf, &body);
+    }
+
+    let xref = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes(),
+    );
+    for offset in &offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            offsets.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    writer.write_all(&pdf)?;
+    writer.flush()
+}
+
+/// 默认的文件名，例如 “Contact sheet.pdf”
+pub fn default_file_name(format: SheetFormat) -> String {
+    format!("{}.{}", tr("Contact sheet"), format.extension())
+}
@@ -0,0 +1,292 @@
+use iced::advanced::widget::{Operation, Tree, tree};
+use iced::advanced::{Clipboard, Layout, Overlay, Shell, Widget, layout, mouse, overlay, renderer};
+use iced::keyboard::{self, key::Named};
+use iced::{Element, Event, Length, Point, Rectangle, Renderer, Size, Theme, Vector, event};
+
+/// 右键时在鼠标位置弹出菜单的容器，iced 没有自带菜单
+/// 菜单只在打开时才生成；点击菜单项、点击菜单外部或按 Escape 时关闭
+pub struct ContextMenu<'a, Message> {
+    underlay: Element<'a, Message>,
+    menu: Box<dyn Fn() -> Element<'a, Message> + 'a>,
+    built: Option<Element<'a, Message>>, // 打开时生成的菜单，overlay 借用它
+}
+
+// 菜单打开时左上角在容器中的位置
+#[derive(Debug, Default)]
+struct State {
+    open: Option<Point>,
+}
+
+/// 给 underlay 加上右键菜单，menu 在菜单打开时生成菜单内容
+pub fn context_menu<'a, Message>(
+    underlay: impl Into<Element<'a, Message>>,
+    menu: impl Fn() -> Element<'a, Message> + 'a,
+) -> ContextMenu<'a, Message> {
+    ContextMenu {
+        underlay: underlay.into(),
+        menu: Box::new(menu),
+        built: None,
+    }
+}
+
+impl<'a, Message> Widget<Message, Theme, Renderer> for ContextMenu<'a, Message> {
+    fn size(&self) -> Size<Length> {
+        self.underlay.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.underlay
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.underlay.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    // 第二个子节点是菜单的状态，菜单生成时才同步
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.underlay), Tree::empty()]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        if tree.children.len() == 2 {
+            tree.children[0].diff(&self.underlay);
+        } else {
+            tree.children = self.children();
+        }
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        self.underlay
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) = event
+            && let Some(position) = cursor.position_over(layout.bounds())
+        {
+            tree.state.downcast_mut::<State>().open = Some(position);
+            shell.invalidate_layout();
+            return event::Status::Captured;
+        }
+        self.underlay.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.underlay.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        let state = tree.state.downcast_mut::<State>();
+        let (underlay_tree, menu_tree) = tree.children.split_at_mut(1);
+        let Some(position) = state.open else {
+            self.built = None;
+            return self.underlay.as_widget_mut().overlay(
+                &mut underlay_tree[0],
+                layout,
+                renderer,
+                translation,
+            );
+        };
+        let menu = self.built.insert((self.menu)());
+        menu_tree[0].diff(&*menu);
+        Some(overlay::Element::new(Box::new(MenuOverlay {
+            menu,
+            tree: &mut menu_tree[0],
+            state,
+            position: position + translation,
+        })))
+    }
+}
+
+impl<'a, Message: 'a> From<ContextMenu<'a, Message>> for Element<'a, Message> {
+    fn from(menu: ContextMenu<'a, Message>) -> Self {
+        Element::new(menu)
+    }
+}
+
+// 浮在界面上的菜单
+struct MenuOverlay<'a, 'b, Message> {
+    menu: &'a mut Element<'b, Message>,
+    tree: &'a mut Tree,
+    state: &'a mut State,
+    position: Point,
+}
+
+impl<Message> MenuOverlay<'_, '_, Message> {
+    fn close(&mut self, shell: &mut Shell<'_, Message>) {
+        self.state.open = None;
+        shell.invalidate_layout();
+    }
+}
+
+impl<Message> Overlay<Message, Theme, Renderer> for MenuOverlay<'_, '_, Message> {
+    fn layout(&mut self, renderer: &Renderer, bounds: Size) -> layout::Node {
+        let node = self.menu.as_widget().layout(
+            self.tree,
+            renderer,
+            &layout::Limits::new(Size::ZERO, bounds),
+        );
+        // 超出窗口时翻到鼠标的左边或上边
+        let size = node.size();
+        let x = if self.position.x + size.width > bounds.width {
+            (self.position.x - size.width).max(0.0)
+        } else {
+            self.position.x
+        };
+        let y = if self.position.y + size.height > bounds.height {
+            (self.position.y - size.height).max(0.0)
+        } else {
+            self.position.y
+        };
+        node.move_to(Point::new(x, y))
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        self.menu.as_widget().draw(
+            self.tree,
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            &layout.bounds(),
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let over = cursor.is_over(layout.bounds());
+        match &event {
+            Event::Mouse(mouse::Event::ButtonPressed(_)) if !over => {
+                self.close(shell);
+                return event::Status::Captured;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(Named::Escape),
+                ..
+            }) => {
+                self.close(shell);
+                return event::Status::Captured;
+            }
+            _ => {}
+        }
+        let status = self.menu.as_widget_mut().on_event(
+            self.tree,
+            event.clone(),
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            &layout.bounds(),
+        );
+        // 菜单项在松开鼠标时触发，之后关闭菜单
+        if over && matches!(event, Event::Mouse(mouse::Event::ButtonReleased(_))) {
+            self.close(shell);
+        }
+        status
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.menu
+            .as_widget()
+            .mouse_interaction(self.tree, layout, cursor, viewport, renderer)
+    }
+}
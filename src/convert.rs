@@ -0,0 +1,67 @@
+use iced::futures::{SinkExt, Stream};
+use std::path::{Path, PathBuf};
+
+use crate::export::{self, EncodeOptions};
+
+// 格式转换设置，output_dir 为 None 时写到原图所在目录
+#[derive(Debug, Clone, Default)]
+pub struct ConvertSettings {
+    pub encode: EncodeOptions,
+    pub output_dir: Option<PathBuf>,
+}
+
+// 转换进度：每张图片完成时发送一次 Converted
+#[derive(Debug, Clone)]
+pub enum ConvertProgress {
+    Converted(PathBuf, Result<PathBuf, String>),
+    Finished,
+}
+
+/// 计算转换后的文件路径，不会覆盖原图或已存在的文件
+pub fn output_path(src: &Path, settings: &ConvertSettings) -> PathBuf {
+    let dir = settings
+        .output_dir
+        .clone()
+        .or_else(|| src.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+    unique_path(&dir, &stem, settings.encode.format.extension())
+}
+
+/// 返回 dir 中不存在的文件名，重名时追加 _1、_2 ……
+pub fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut candidate = dir.join(format!("{}.{}", stem, extension));
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{}_{}.{}", stem, n, extension));
+        n += 1;
+    }
+    candidate
+}
+
+/// 解码 src 并按设置重新编码，返回输出文件路径
+pub fn convert_file(src: &Path, settings: &ConvertSettings) -> Result<PathBuf, String> {
+    let (img, _) = crate::pipeline::decode_image(src, true).map_err(|e| e.to_string())?;
+    let dst = output_path(src, settings);
+    export::write_image(&img, &settings.encode, &dst).map_err(|e| e.to_string())?;
+    Ok(dst)
+}
+
+/// 依次转换多张图片，通过返回的 Stream 报告每张的结果
+pub fn convert_all(
+    sources: Vec<PathBuf>,
+    settings: ConvertSettings,
+) -> impl Stream<Item = ConvertProgress> {
+    iced::stream::channel(4, move |mut output| async move {
+        for src in sources {
+            let settings = settings.clone();
+            let path = src.clone();
+            let result = tokio::task::spawn_blocking(move || convert_file(&path, &settings))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r);
+            let _ = output.send(ConvertProgress::Converted(src, result)).await;
+        }
+        let _ = output.send(ConvertProgress::Finished).await;
+    })
+}
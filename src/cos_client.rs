@@ -1,15 +1,13 @@
 use std::path::PathBuf;
 use qcos::client::Client;
 use qcos::objects::{mime};
-use qcos::request::ErrNo;
+use qcos::request::{ErrNo, Response};
 
-pub trait CosFunction {
-    fn create_cos_client(secret_id: String, secret_key: String, region: String, bucket: String) -> Result<Self, Box<dyn std::error::Error>> where Self: Sized;
+use tracing::{debug, warn};
 
-    async fn upload_object(&self, path: PathBuf) -> Result<(), Box<dyn std::error::Error>>;
-
-    async fn download_object(&self, key: String, name: String) -> Result<(), Box<dyn std::error::Error>>;
-}
+use crate::cloud_storage::{self, CloudStorage, ObjectListing};
+use crate::s3_client::uri_encode;
+use crate::throttle;
 
 #[derive(Debug, Clone)]
 pub struct TecentCosUtil {
@@ -17,37 +15,146 @@ pub struct TecentCosUtil {
     bucket: String
 }
 
-impl CosFunction for TecentCosUtil {
-    fn create_cos_client(secret_id: String, secret_key: String, region: String, bucket: String) -> Result<Self, Box<dyn std::error::Error>> {
+impl TecentCosUtil {
+    pub fn new(secret_id: String, secret_key: String, region: String, bucket: String) -> Self {
         let client = Client::new(secret_id, secret_key, bucket.clone(), region);
-        Ok(
-            TecentCosUtil {client: client, bucket: bucket}
-        )
-    }
-
-    async fn upload_object(&self, path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-        let object_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("default_name");
-        let suffix = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-        let mime_type = match suffix {
-            "jpg" | "jpeg" => mime::IMAGE_JPEG,
-            "png" => mime::IMAGE_PNG,
-            "gif" => mime::IMAGE_GIF,
-            _ => mime::APPLICATION_OCTET_STREAM, // Default to binary stream
-        };
+        TecentCosUtil {client, bucket}
+    }
+}
+
+impl CloudStorage for TecentCosUtil {
+    fn presigned_url(&self, key: &str, expires: u64) -> Result<String, String> {
+        let expires = u32::try_from(expires).unwrap_or(u32::MAX);
+        Ok(self.client.get_presigned_download_url(key, expires))
+    }
+
+    async fn upload_object(&self, path: PathBuf, key: String) -> Result<(), String> {
+        let mime_type = cloud_storage::content_type(&key).parse().unwrap_or(mime::APPLICATION_OCTET_STREAM);
         //let mut acl_header = AclHeader::new();
         // acl_header.insert_object_x_cos_acl(ObjectAcl::BucketOwnerFullControl);
-        println!("Uploading object: {} with MIME type: {}", object_name, mime_type);
-        let res = self.client.put_object(&path, object_name, Some(mime_type), None).await;
-        if res.error_no == ErrNo::SUCCESS {
-            println!("success");
+        debug!(bucket = %self.bucket, key = %key, "Uploading object");
+        let body = tokio::fs::read(&path).await.map_err(|e| e.to_string())?;
+        // 自己发送签名后的 PUT 请求，请求体才能按上传限速分块发送
+        let url_path = self.client.get_path_from_object_key(&key);
+        let url = self.client.get_full_url_from_path(&url_path);
+        let mut headers = self.client.get_common_headers();
+        headers.insert(
+            "content-type",
+            mime_type
+                .as_ref()
+                .parse()
+                .map_err(|_| "Invalid content type".to_string())?,
+        );
+        headers.insert("content-length", body.len().into());
+        let headers =
+            self.client
+                .get_headers_with_auth("put", &url_path, None, Some(headers), None);
+        let response = reqwest::Client::new()
+            .put(url)
+            .headers(headers)
+            .body(throttle::upload_body(body))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
         } else {
-            println!("[{}]: {} {:?}", res.error_no, res.error_message, String::from_utf8(res.result));
+            // 错误响应中可能带有待签名的字符串，只记录状态码
+            warn!(bucket = %self.bucket, key = %key, %status, "Upload failed");
+            let body = response.bytes().await.unwrap_or_default();
+            Err(cloud_storage::http_error(status.as_u16(), &body))
         }
-        Ok(())
     }
 
-    async fn download_object(&self, key: String, name: String) -> Result<(), Box<dyn std::error::Error>> {
-        self.client.get_object(&key, &name, None).await;
+    async fn list_objects(&self, prefix: String) -> Result<ObjectListing, String> {
+        let mut listing = ObjectListing::default();
+        let mut marker = String::new();
+        // 每次最多返回 1000 个对象，被截断时从 NextMarker 继续
+        loop {
+            let res = self
+                .client
+                .list_objects(&prefix, "/", "", &marker, 1000)
+                .await;
+            let xml = String::from_utf8(check(res)?).map_err(|e| e.to_string())?;
+            match cloud_storage::parse_listing(&xml, &prefix, &mut listing, "NextMarker") {
+                Some(next) => marker = next,
+                None => break,
+            }
+        }
+        Ok(listing)
+    }
+
+    async fn object_size(&self, key: String) -> Result<u64, String> {
+        let size = self.client.get_object_size(&key).await;
+        if size < 0 {
+            return Err(format!("Object not found: {}", key));
+        }
+        Ok(size as u64)
+    }
+
+    async fn download_range(&self, key: String, start: u64, end: u64) -> Result<Vec<u8>, String> {
+        let res = self
+            .client
+            .get_object_binary_range(&key, start as usize, Some(end as usize))
+            .await;
+        check(res)
+    }
+
+    async fn object_etag(&self, key: String) -> Result<Option<String>, String> {
+        let url_path = self.client.get_path_from_object_key(&key);
+        let url = self.client.get_full_url_from_path(&url_path);
+        let headers = self
+            .client
+            .get_headers_with_auth("head", &url_path, None, None, None);
+        let response = reqwest::Client::new()
+            .head(url)
+            .headers(headers)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        cloud_storage::etag_from_head(response.status().as_u16(), response.headers(), &[])
+    }
+
+    async fn copy_object(&self, source: String, key: String) -> Result<(), String> {
+        // 复制来源为 <存储桶域名>/<对象键>
+        let url_path = self.client.get_path_from_object_key(&key);
+        let url = self.client.get_full_url_from_path(&url_path);
+        let mut headers = self.client.get_common_headers();
+        let copy_source = format!("{}/{}", self.client.get_host(), uri_encode(&source, false));
+        headers.insert(
+            "x-cos-copy-source",
+            copy_source
+                .parse()
+                .map_err(|_| "Invalid object key".to_string())?,
+        );
+        let headers =
+            self.client
+                .get_headers_with_auth("put", &url_path, None, Some(headers), None);
+        let response = reqwest::Client::new()
+            .put(url)
+            .headers(headers)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.bytes().await.unwrap_or_default();
+            return Err(cloud_storage::http_error(status.as_u16(), &body));
+        }
         Ok(())
     }
+
+    async fn delete_object(&self, key: String) -> Result<(), String> {
+        check(self.client.delete_object(&key).await).map(|_| ())
+    }
+}
+
+// 请求失败时返回错误码和错误信息
+fn check(res: Response) -> Result<Vec<u8>, String> {
+    if res.error_no == ErrNo::SUCCESS {
+        Ok(res.result)
+    } else {
+        Err(format!("[{}]: {}", res.error_no, res.error_message))
+    }
 }
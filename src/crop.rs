@@ -0,0 +1,298 @@
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Stroke, event};
+use iced::widget::image::Handle;
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+use image::{ImageResult, RgbImage};
+use std::path::Path;
+
+use crate::export;
+use crate::i18n::tr;
+use crate::messages::Message;
+
+// 裁剪比例预设
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AspectRatio {
+    Free,
+    Square,
+    ThreeTwo,
+    SixteenNine,
+}
+
+impl AspectRatio {
+    pub fn name(self) -> &'static str {
+        match self {
+            AspectRatio::Free => tr("Free"),
+            AspectRatio::Square => "1:1",
+            AspectRatio::ThreeTwo => "3:2",
+            AspectRatio::SixteenNine => "16:9",
+        }
+    }
+
+    // 宽高比，自由裁剪时为 None
+    pub fn ratio(self) -> Option<f32> {
+        match self {
+            AspectRatio::Free => None,
+            AspectRatio::Square => Some(1.0),
+            AspectRatio::ThreeTwo => Some(3.0 / 2.0),
+            AspectRatio::SixteenNine => Some(16.0 / 9.0),
+        }
+    }
+
+    pub fn all() -> Vec<AspectRatio> {
+        vec![
+            AspectRatio::Free,
+            AspectRatio::Square,
+            AspectRatio::ThreeTwo,
+            AspectRatio::SixteenNine,
+        ]
+    }
+}
+
+// 裁剪区域，单位为原图像素
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CropRect {
+    // 图片按 factor 缩放后对应的区域
+    pub fn scaled(self, factor: f32) -> CropRect {
+        CropRect {
+            x: self.x * factor,
+            y: self.y * factor,
+            width: self.width * factor,
+            height: self.height * factor,
+        }
+    }
+
+    // 取整后的像素区域，保证至少 1x1 且不超出图片
+    pub fn to_pixels(self, image_width: u32, image_height: u32) -> (u32, u32, u32, u32) {
+        let x = (self.x.round().max(0.0) as u32).min(image_width.saturating_sub(1));
+        let y = (self.y.round().max(0.0) as u32).min(image_height.saturating_sub(1));
+        let width = (self.width.round() as u32).clamp(1, image_width - x);
+        let height = (self.height.round() as u32).clamp(1, image_height - y);
+        (x, y, width, height)
+    }
+}
+
+/// 按裁剪区域截取图片并保存，格式由 dst 的扩展名决定
+pub fn save_cropped(img: &RgbImage, rect: CropRect, dst: &Path) -> ImageResult<()> {
+    let (x, y, width, height) = rect.to_pixels(img.width(), img.height());
+    let cropped = image::imageops::crop_imm(img, x, y, width, height).to_image();
+    export::save_image(&cropped, dst)
+}
+
+/// 在画布上显示图片并拖拽出裁剪框
+pub struct CropOverlay<'a> {
+    pub handle: &'a Handle,
+    pub image_size: (u32, u32),
+    pub selection: Option<CropRect>,
+    pub aspect: AspectRatio,
+}
+
+impl CropOverlay<'_> {
+    // 图片按 Contain 方式放入画布后的位置和缩放比例
+    fn fit(&self, bounds: Size) -> (Rectangle, f32) {
+        let (w, h) = (self.image_size.0 as f32, self.image_size.1 as f32);
+        let scale = (bounds.width / w).min(bounds.height / h);
+        let size = Size::new(w * scale, h * scale);
+        let origin = Point::new(
+            (bounds.width - size.width) / 2.0,
+            (bounds.height - size.height) / 2.0,
+        );
+        (Rectangle::new(origin, size), scale)
+    }
+
+    // 画布坐标转换为原图坐标（限制在图片范围内）
+    fn to_image(&self, position: Point, bounds: Size) -> Point {
+        let (fit, scale) = self.fit(bounds);
+        Point::new(
+            ((position.x - fit.x) / scale).clamp(0.0, self.image_size.0 as f32),
+            ((position.y - fit.y) / scale).clamp(0.0, self.image_size.1 as f32),
+        )
+    }
+
+    // 根据拖拽起点和当前位置计算裁剪框，固定比例时保持宽高比
+    fn selection_between(&self, anchor: Point, current: Point) -> CropRect {
+        let (image_w, image_h) = (self.image_size.0 as f32, self.image_size.1 as f32);
+        let dir_x = if current.x >= anchor.x { 1.0 } else { -1.0 };
+        let dir_y = if current.y >= anchor.y { 1.0 } else { -1.0 };
+        let mut width = (current.x - anchor.x).abs();
+        let mut height = (current.y - anchor.y).abs();
+
+        if let Some(ratio) = self.aspect.ratio() {
+            if width / ratio > height {
+                height = width / ratio;
+            } else {
+                width = height * ratio;
+            }
+            // 超出图片边界时按比例缩小
+            let max_w = if dir_x > 0.0 {
+                image_w - anchor.x
+            } else {
+                anchor.x
+            };
+            let max_h = if dir_y > 0.0 {
+                image_h - anchor.y
+            } else {
+                anchor.y
+            };
+            if width > max_w {
+                width = max_w;
+                height = width / ratio;
+            }
+            if height > max_h {
+                height = max_h;
+                width = height * ratio;
+            }
+        }
+
+        CropRect {
+            x: if dir_x > 0.0 {
+                anchor.x
+            } else {
+                anchor.x - width
+            },
+            y: if dir_y > 0.0 {
+                anchor.y
+            } else {
+                anchor.y - height
+            },
+            width,
+            height,
+        }
+    }
+}
+
+impl canvas::Program<Message> for CropOverlay<'_> {
+    // 拖拽起点（原图坐标）
+    type State = Option<Point>;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                *state = Some(self.to_image(position, bounds.size()));
+                (
+                    event::Status::Captured,
+                    Some(Message::CropSelectionChanged(None)),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let (Some(anchor), Some(position)) =
+                    (*state, cursor.position_from(bounds.position()))
+                else {
+                    return (event::Status::Ignored, None);
+                };
+                let current = self.to_image(position, bounds.size());
+                let selection = self.selection_between(anchor, current);
+                (
+                    event::Status::Captured,
+                    Some(Message::CropSelectionChanged(Some(selection))),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.take().is_some() {
+                    (event::Status::Captured, None)
+                } else {
+                    (event::Status::Ignored, None)
+                }
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (fit, scale) = self.fit(bounds.size());
+        frame.draw_image(fit, self.handle);
+
+        if let Some(selection) = self.selection {
+            let rect = Rectangle {
+                x: fit.x + selection.x * scale,
+                y: fit.y + selection.y * scale,
+                width: selection.width * scale,
+                height: selection.height * scale,
+            };
+
+            // 裁剪框外的区域变暗
+            let shade = Color::from_rgba8(0, 0, 0, 0.5);
+            frame.fill_rectangle(
+                Point::new(fit.x, fit.y),
+                Size::new(fit.width, rect.y - fit.y),
+                shade,
+            );
+            frame.fill_rectangle(
+                Point::new(fit.x, rect.y + rect.height),
+                Size::new(fit.width, fit.y + fit.height - rect.y - rect.height),
+                shade,
+            );
+            frame.fill_rectangle(
+                Point::new(fit.x, rect.y),
+                Size::new(rect.x - fit.x, rect.height),
+                shade,
+            );
+            frame.fill_rectangle(
+                Point::new(rect.x + rect.width, rect.y),
+                Size::new(fit.x + fit.width - rect.x - rect.width, rect.height),
+                shade,
+            );
+
+            // 三分线
+            let guide = Stroke::default()
+                .with_width(1.0)
+                .with_color(Color::from_rgba8(255, 255, 255, 0.5));
+            for i in 1..3 {
+                let t = i as f32 / 3.0;
+                let x = rect.x + rect.width * t;
+                let y = rect.y + rect.height * t;
+                frame.stroke(
+                    &canvas::Path::line(Point::new(x, rect.y), Point::new(x, rect.y + rect.height)),
+                    guide,
+                );
+                frame.stroke(
+                    &canvas::Path::line(Point::new(rect.x, y), Point::new(rect.x + rect.width, y)),
+                    guide,
+                );
+            }
+
+            frame.stroke(
+                &canvas::Path::rectangle(rect.position(), rect.size()),
+                Stroke::default().with_width(2.0).with_color(Color::WHITE),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
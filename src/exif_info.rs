@@ -0,0 +1,183 @@
+use exif::{In, Reader, Tag, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// 图片的 EXIF 信息（只保留界面需要展示的字段）
+#[derive(Debug, Clone, Default)]
+pub struct ExifInfo {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub date_time: Option<String>,
+    pub exposure_time: Option<String>,
+    pub f_number: Option<String>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<String>,
+    pub focal_length_mm: Option<f64>,
+    pub gps: Option<GpsLocation>,
+}
+
+// GPS 坐标，经纬度均为十进制度数（南纬、西经为负数）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+impl GpsLocation {
+    pub fn display(&self) -> String {
+        let lat_ref = if self.latitude >= 0.0 { 'N' } else { 'S' };
+        let lon_ref = if self.longitude >= 0.0 { 'E' } else { 'W' };
+        format!(
+            "{:.6}° {}, {:.6}° {}",
+            self.latitude.abs(),
+            lat_ref,
+            self.longitude.abs(),
+            lon_ref
+        )
+    }
+
+    // 在线地图链接（OpenStreetMap，无需 API key）
+    pub fn map_url(&self) -> String {
+        format!(
+            "https://www.openstreetmap.org/?mlat={lat:.6}&mlon={lon:.6}#map=15/{lat:.6}/{lon:.6}",
+            lat = self.latitude,
+            lon = self.longitude
+        )
+    }
+}
+
+impl ExifInfo {
+    pub fn is_empty(&self) -> bool {
+        self.camera_make.is_none()
+            && self.camera_model.is_none()
+            && self.date_time.is_none()
+            && self.exposure_time.is_none()
+            && self.f_number.is_none()
+            && self.iso.is_none()
+            && self.focal_length.is_none()
+            && self.gps.is_none()
+    }
+}
+
+/// 读取图片的 EXIF 信息，文件不存在或没有 EXIF 时返回 None
+pub fn read_exif(path: &Path) -> Option<ExifInfo> {
+    let file = File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let ascii = |tag: Tag| -> Option<String> {
+        let field = exif.get_field(tag, In::PRIMARY)?;
+        match &field.value {
+            Value::Ascii(values) => values
+                .first()
+                .map(|v| String::from_utf8_lossy(v).trim().to_string())
+                .filter(|s| !s.is_empty()),
+            _ => None,
+        }
+    };
+    let display = |tag: Tag| -> Option<String> {
+        exif.get_field(tag, In::PRIMARY)
+            .map(|field| field.display_value().with_unit(&exif).to_string())
+    };
+
+    let info = ExifInfo {
+        camera_make: ascii(Tag::Make),
+        camera_model: ascii(Tag::Model),
+        date_time: ascii(Tag::DateTimeOriginal).or_else(|| ascii(Tag::DateTime)),
+        exposure_time: display(Tag::ExposureTime),
+        f_number: display(Tag::FNumber),
+        iso: exif
+            .get_field(Tag::PhotographicSensitivity, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0)),
+        focal_length: display(Tag::FocalLength),
+        focal_length_mm: exif.get_field(Tag::FocalLength, In::PRIMARY).and_then(
+            |field| match &field.value {
+                Value::Rational(v) => v.first().map(|r| r.to_f64()),
+                _ => None,
+            },
+        ),
+        gps: read_gps(&exif),
+    };
+    Some(info)
+}
+
+fn read_gps(exif: &exif::Exif) -> Option<GpsLocation> {
+    let latitude = dms_to_degrees(exif.get_field(Tag::GPSLatitude, In::PRIMARY)?)?;
+    let longitude = dms_to_degrees(exif.get_field(Tag::GPSLongitude, In::PRIMARY)?)?;
+    let lat_ref = gps_ref(exif, Tag::GPSLatitudeRef).unwrap_or('N');
+    let lon_ref = gps_ref(exif, Tag::GPSLongitudeRef).unwrap_or('E');
+
+    let altitude = exif
+        .get_field(Tag::GPSAltitude, In::PRIMARY)
+        .and_then(|field| match &field.value {
+            Value::Rational(v) => v.first().map(|r| r.to_f64()),
+            _ => None,
+        })
+        .map(|alt| {
+            // GPSAltitudeRef 为 1 表示海平面以下
+            let below_sea_level = exif
+                .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+                == Some(1);
+            if below_sea_level { -alt } else { alt }
+        });
+
+    Some(GpsLocation {
+        latitude: if lat_ref == 'S' { -latitude } else { latitude },
+        longitude: if lon_ref == 'W' {
+            -longitude
+        } else {
+            longitude
+        },
+        altitude,
+    })
+}
+
+// 度/分/秒 三个有理数转换为十进制度数
+fn dms_to_degrees(field: &exif::Field) -> Option<f64> {
+    match &field.value {
+        Value::Rational(v) if v.len() >= 3 => {
+            Some(v[0].to_f64() + v[1].to_f64() / 60.0 + v[2].to_f64() / 3600.0)
+        }
+        _ => None,
+    }
+}
+
+fn gps_ref(exif: &exif::Exif, tag: Tag) -> Option<char> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first()?.first().map(|&b| b as char),
+        _ => None,
+    }
+}
+
+/// 读取 JPEG 中内嵌的 EXIF 缩略图，返回 (缩略图 JPEG 数据, EXIF 方向值)
+/// 只读取文件头部的 EXIF 段，比完整解码原图快得多
+pub fn read_embedded_thumbnail(path: &Path) -> Option<(Vec<u8>, u32)> {
+    let file = File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let offset = exif
+        .get_field(Tag::JPEGInterchangeFormat, In::THUMBNAIL)?
+        .value
+        .get_uint(0)? as usize;
+    let length = exif
+        .get_field(Tag::JPEGInterchangeFormatLength, In::THUMBNAIL)?
+        .value
+        .get_uint(0)? as usize;
+    let data = exif.buf().get(offset..offset.checked_add(length)?)?;
+    // 必须是完整的 JPEG 数据（SOI 开头）
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let orientation = exif
+        .get_field(Tag::Orientation, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .unwrap_or(1);
+    Some((data.to_vec(), orientation))
+}
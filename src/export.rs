@@ -0,0 +1,406 @@
+use iced::futures::{SinkExt, Stream};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::qoi::QoiEncoder;
+use image::{DynamicImage, ImageBuffer, ImageFormat, ImageResult, Rgb, RgbImage};
+use rgb::FromSlice;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::i18n::tr;
+use crate::metadata::{self, MetadataPolicy};
+use crate::perf::{self, Stage};
+use crate::pipeline::ResamplingType;
+
+// 输出尺寸的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeUnit {
+    Pixels,
+    Percent,
+}
+
+impl SizeUnit {
+    pub fn name(self) -> &'static str {
+        match self {
+            SizeUnit::Pixels => "px",
+            SizeUnit::Percent => "%",
+        }
+    }
+}
+
+// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Qoi, // 无损，编解码很快，常用于游戏资源
+}
+
+impl ExportFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "PNG",
+            ExportFormat::Jpeg => "JPEG",
+            ExportFormat::WebP => "WebP",
+            ExportFormat::Qoi => "QOI",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::WebP => "webp",
+            ExportFormat::Qoi => "qoi",
+        }
+    }
+
+    pub fn all() -> Vec<ExportFormat> {
+        vec![
+            ExportFormat::Png,
+            ExportFormat::Jpeg,
+            ExportFormat::WebP,
+            ExportFormat::Qoi,
+        ]
+    }
+
+    // 浏览器能显示的格式，用于网页相册
+    pub fn for_web() -> Vec<ExportFormat> {
+        vec![ExportFormat::Png, ExportFormat::Jpeg, ExportFormat::WebP]
+    }
+
+    // 根据扩展名推断格式，不支持的格式返回 None
+    pub fn from_extension(extension: &str) -> Option<ExportFormat> {
+        match extension.to_lowercase().as_str() {
+            "png" => Some(ExportFormat::Png),
+            "jpg" | "jpeg" => Some(ExportFormat::Jpeg),
+            "webp" => Some(ExportFormat::WebP),
+            "qoi" => Some(ExportFormat::Qoi),
+            _ => None,
+        }
+    }
+}
+
+// PNG 压缩级别：越高文件越小，编码越慢
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PngCompression {
+    Fast,
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub fn name(self) -> &'static str {
+        match self {
+            PngCompression::Fast => tr("Fast"),
+            PngCompression::Default => tr("Default"),
+            PngCompression::Best => tr("Best"),
+        }
+    }
+
+    pub fn all() -> Vec<PngCompression> {
+        vec![
+            PngCompression::Fast,
+            PngCompression::Default,
+            PngCompression::Best,
+        ]
+    }
+
+    fn to_compression_type(self) -> CompressionType {
+        match self {
+            PngCompression::Fast => CompressionType::Fast,
+            PngCompression::Default => CompressionType::Default,
+            PngCompression::Best => CompressionType::Best,
+        }
+    }
+}
+
+// 编码参数：质量用于 JPEG 和有损 WebP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub format: ExportFormat,
+    pub quality: u8,
+    pub png_compression: PngCompression,
+    pub webp_lossless: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            format: ExportFormat::Jpeg,
+            quality: 90,
+            png_compression: PngCompression::Default,
+            webp_lossless: false,
+        }
+    }
+}
+
+impl EncodeOptions {
+    // 当前格式是否使用质量参数
+    pub fn has_quality(&self) -> bool {
+        match self.format {
+            ExportFormat::Png | ExportFormat::Qoi => false,
+            ExportFormat::Jpeg => true,
+            ExportFormat::WebP => !self.webp_lossless,
+        }
+    }
+}
+
+// 导出对话框中的设置，宽高保存输入框中的原始文本
+#[derive(Debug, Clone)]
+pub struct ExportSettings {
+    pub width: String,
+    pub height: String,
+    pub unit: SizeUnit,
+    pub keep_aspect: bool,
+    pub resampling: ResamplingType,
+    pub encode: EncodeOptions,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            width: "100".to_string(),
+            height: "100".to_string(),
+            unit: SizeUnit::Percent,
+            keep_aspect: true,
+            resampling: ResamplingType::Lanczos3,
+            encode: EncodeOptions::default(),
+        }
+    }
+}
+
+impl ExportSettings {
+    // 修改宽度，保持比例时同步计算高度
+    pub fn set_width(&mut self, value: String, source: (u32, u32)) {
+        if self.keep_aspect
+            && let Ok(width) = value.trim().parse::<f64>()
+        {
+            self.height = match self.unit {
+                SizeUnit::Pixels => {
+                    format!("{}", (width * source.1 as f64 / source.0 as f64).round())
+                }
+                SizeUnit::Percent => value.trim().to_string(),
+            };
+        }
+        self.width = value;
+    }
+
+    // 修改高度，保持比例时同步计算宽度
+    pub fn set_height(&mut self, value: String, source: (u32, u32)) {
+        if self.keep_aspect
+            && let Ok(height) = value.trim().parse::<f64>()
+        {
+            self.width = match self.unit {
+                SizeUnit::Pixels => {
+                    format!("{}", (height * source.0 as f64 / source.1 as f64).round())
+                }
+                SizeUnit::Percent => value.trim().to_string(),
+            };
+        }
+        self.height = value;
+    }
+
+    // 切换单位时把当前尺寸换算到新单位
+    pub fn set_unit(&mut self, unit: SizeUnit, source: (u32, u32)) {
+        if unit == self.unit {
+            return;
+        }
+        if let Some((width, height)) = self.target_size(source) {
+            match unit {
+                SizeUnit::Pixels => {
+                    self.width = width.to_string();
+                    self.height = height.to_string();
+                }
+                SizeUnit::Percent => {
+                    self.width = format!("{:.0}", width as f64 * 100.0 / source.0 as f64);
+                    self.height = format!("{:.0}", height as f64 * 100.0 / source.1 as f64);
+                }
+            }
+        }
+        self.unit = unit;
+    }
+
+    /// 根据原图尺寸计算输出尺寸，输入无效时返回 None
+    pub fn target_size(&self, source: (u32, u32)) -> Option<(u32, u32)> {
+        let width = self.width.trim().parse::<f64>().ok()?;
+        let height = self.height.trim().parse::<f64>().ok()?;
+        let (width, height) = match self.unit {
+            SizeUnit::Pixels => (width, height),
+            SizeUnit::Percent => (
+                source.0 as f64 * width / 100.0,
+                source.1 as f64 * height / 100.0,
+            ),
+        };
+        let (width, height) = (width.round(), height.round());
+        // 限制最大边长，避免误输入导致分配过大的内存
+        if width < 1.0 || height < 1.0 || width > 32768.0 || height > 32768.0 {
+            return None;
+        }
+        Some((width as u32, height as u32))
+    }
+}
+
+// 导出进度，Finished 中为输出文件或错误信息
+#[derive(Debug, Clone)]
+pub enum ExportProgress {
+    Progress(f32),
+    Finished(Result<PathBuf, String>),
+}
+
+/// 在后台缩放并编码图片，通过返回的 Stream 报告进度
+pub fn export_resized(
+    img: Arc<RgbImage>,
+    settings: ExportSettings,
+    metadata: MetadataPolicy,
+    dst: PathBuf,
+) -> impl Stream<Item = ExportProgress> {
+    iced::stream::channel(4, move |mut output| async move {
+        let source = img.dimensions();
+        let Some((width, height)) = settings.target_size(source) else {
+            let _ = output
+                .send(ExportProgress::Finished(Err(
+                    "Invalid output size".to_string()
+                )))
+                .await;
+            return;
+        };
+        let _ = output.send(ExportProgress::Progress(0.1)).await;
+
+        // 缩放和编码都比较耗时，放到阻塞线程池中执行
+        let resampling = settings.resampling;
+        let resized = tokio::task::spawn_blocking(move || {
+            if (width, height) == source {
+                Ok(Arc::unwrap_or_clone(img))
+            } else {
+                resize_image(&img, width, height, resampling)
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        let resized = match resized {
+            Ok(resized) => resized,
+            Err(e) => {
+                let _ = output.send(ExportProgress::Finished(Err(e))).await;
+                return;
+            }
+        };
+        let _ = output.send(ExportProgress::Progress(0.6)).await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            write_image(&DynamicImage::ImageRgb8(resized), &settings.encode, &dst)
+                .and_then(|()| {
+                    // 重新编码的文件不带原图的元数据，只需要写入修改的字段
+                    match &metadata {
+                        MetadataPolicy::Edit(edits) if !edits.is_empty() => {
+                            metadata::export_with_metadata(&dst, &dst, &metadata)
+                        }
+                        _ => Ok(()),
+                    }
+                })
+                .map(|()| dst)
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        let _ = output.send(ExportProgress::Progress(1.0)).await;
+        let _ = output.send(ExportProgress::Finished(result)).await;
+    })
+}
+
+/// 等比缩小到 max x max 以内的尺寸，不放大
+pub fn fit_within((width, height): (u32, u32), max: u32) -> (u32, u32) {
+    let scale = (max as f64 / width.max(height).max(1) as f64).min(1.0);
+    (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
+
+/// 使用指定的缩放算法把图片缩放到 width x height
+pub fn resize_image(
+    img: &RgbImage,
+    width: u32,
+    height: u32,
+    resampling: ResamplingType,
+) -> Result<RgbImage, String> {
+    let mut dst = vec![0; width as usize * height as usize * 3];
+    let mut resizer = resize::new(
+        img.width() as usize,
+        img.height() as usize,
+        width as usize,
+        height as usize,
+        resize::Pixel::RGB8,
+        resampling.to_resize_type(),
+    )
+    .map_err(|e| e.to_string())?;
+    resizer
+        .resize(img.as_raw().as_rgb(), dst.as_rgb_mut())
+        .map_err(|e| e.to_string())?;
+    ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(width, height, dst)
+        .ok_or_else(|| "Invalid output buffer".to_string())
+}
+
+/// 按编码参数把图片写入 dst（不检查扩展名是否与格式一致）
+pub fn write_image(
+    img: &DynamicImage,
+    options: &EncodeOptions,
+    dst: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _timer = perf::Timer::start(Stage::Encode);
+    let mut writer = BufWriter::new(File::create(dst)?);
+    match options.format {
+        ExportFormat::Png => {
+            let encoder = PngEncoder::new_with_quality(
+                &mut writer,
+                options.png_compression.to_compression_type(),
+                FilterType::Adaptive,
+            );
+            img.write_with_encoder(encoder)?;
+        }
+        // JPEG 不支持透明通道
+        ExportFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut writer, options.quality);
+            DynamicImage::ImageRgb8(img.to_rgb8()).write_with_encoder(encoder)?;
+        }
+        ExportFormat::WebP => {
+            let rgba = img.to_rgba8();
+            let encoder = webp::Encoder::from_rgba(rgba.as_raw(), rgba.width(), rgba.height());
+            let data = if options.webp_lossless {
+                encoder.encode_lossless()
+            } else {
+                encoder.encode(options.quality as f32)
+            };
+            writer.write_all(&data)?;
+        }
+        // QOI 只支持 RGB 和 RGBA 的 8 位像素
+        ExportFormat::Qoi => {
+            let encoder = QoiEncoder::new(&mut writer);
+            if img.color().has_alpha() {
+                DynamicImage::ImageRgba8(img.to_rgba8()).write_with_encoder(encoder)?;
+            } else {
+                DynamicImage::ImageRgb8(img.to_rgb8()).write_with_encoder(encoder)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 保存编辑后的图片，格式由 dst 的扩展名决定
+pub fn save_image(img: &RgbImage, dst: &Path) -> ImageResult<()> {
+    let _timer = perf::Timer::start(Stage::Encode);
+    match ImageFormat::from_path(dst)? {
+        // 默认的 JPEG 质量（75）偏低，保存编辑结果使用更高的质量
+        ImageFormat::Jpeg => {
+            let mut writer = BufWriter::new(File::create(dst)?);
+            img.write_with_encoder(JpegEncoder::new_with_quality(&mut writer, 95))
+        }
+        _ => img.save(dst),
+    }
+}
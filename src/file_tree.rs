@@ -0,0 +1,223 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::catalog::SmartAlbum;
+use crate::i18n::tr;
+use crate::smart_directory::RecentItem;
+use crate::{catalog, cloud, scan, smart_directory};
+
+#[derive(Debug, Clone)]
+pub enum FileTreeEntry {
+    Directory {
+        name: String,
+        path: PathBuf,
+        children: Vec<FileTreeEntry>,
+        expanded: bool,
+        children_loaded: bool, // 是否已加载子节点
+        link: Option<PathBuf>, // 符号链接指向的实际目录
+        access: Access,
+    },
+    File {
+        name: String,
+        path: PathBuf,
+    },
+}
+
+/// 目录能否读取和展开
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Readable,
+    Denied, // 没有读取权限
+    Loop,   // 指向自身或上级目录的符号链接，展开后会无限嵌套
+}
+
+/// 输入名称的对话框：重命名本地图片或新建文件夹
+#[derive(Debug, Clone)]
+pub enum NamePrompt {
+    Rename { path: PathBuf, name: String },
+    NewFolder { parent: PathBuf, name: String },
+}
+
+impl FileTreeEntry {
+    pub fn default(path: PathBuf) -> Self {
+        if path.is_dir() {
+            let link = fs::symlink_metadata(&path)
+                .is_ok_and(|metadata| metadata.file_type().is_symlink())
+                .then(|| fs::canonicalize(&path).ok())
+                .flatten();
+            let access = directory_access(&path, link.as_deref());
+            FileTreeEntry::Directory {
+                name: file_name(&path),
+                path,
+                children: Vec::new(),
+                expanded: false,
+                children_loaded: false,
+                link,
+                access,
+            }
+        } else {
+            FileTreeEntry::File {
+                name: file_name(&path),
+                path,
+            }
+        }
+    }
+
+    pub fn is_directory(&self) -> bool {
+        match self {
+            FileTreeEntry::Directory { .. } => true,
+            FileTreeEntry::File { .. } => false,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            FileTreeEntry::Directory { path, .. } => path,
+            FileTreeEntry::File { path, .. } => path,
+        }
+    }
+}
+
+// 链接到路径上任何一级上级目录时为循环；只检查权限，不读取目录内容
+fn directory_access(path: &Path, link: Option<&Path>) -> Access {
+    if let Some(target) = link
+        && path
+            .ancestors()
+            .skip(1)
+            .any(|ancestor| fs::canonicalize(ancestor).is_ok_and(|a| a == target))
+    {
+        return Access::Loop;
+    }
+    match fs::read_dir(path) {
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Access::Denied,
+        _ => Access::Readable,
+    }
+}
+
+// 目录树中的智能文件夹，子项为浏览记录中的图片
+pub fn smart_folder<'a>(
+    name: &str,
+    path: &str,
+    items: impl IntoIterator<Item = &'a RecentItem>,
+) -> FileTreeEntry {
+    FileTreeEntry::Directory {
+        name: name.to_string(),
+        path: PathBuf::from(path),
+        children: items.into_iter().map(smart_folder_child).collect(),
+        expanded: false,
+        children_loaded: false,
+        link: None,
+        access: Access::Readable,
+    }
+}
+
+// 智能文件夹和图片目录在目录树中显示的名称，其他文件夹返回 None
+pub fn smart_folder_name(path: &Path) -> Option<&'static str> {
+    if let Some(album) = SmartAlbum::from_path(path) {
+        return Some(album.name());
+    }
+    match path.to_str()? {
+        smart_directory::RECENTS => Some(tr("Recents")),
+        smart_directory::MOST_VIEWED => Some(tr("Most viewed")),
+        smart_directory::RECENTLY_ADDED => Some(tr("Recently added")),
+        catalog::LIBRARY_ROOT => Some(tr("Library")),
+        _ => None,
+    }
+}
+
+pub fn smart_folder_child(item: &RecentItem) -> FileTreeEntry {
+    FileTreeEntry::File {
+        name: item.name(),
+        path: item.path().clone(),
+    }
+}
+
+// 文件或文件夹的名称，根目录这样没有名称的路径显示完整路径
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub fn find_entry_by_path<'a>(
+    entry: &'a mut FileTreeEntry,
+    path: &PathBuf,
+) -> Option<&'a mut FileTreeEntry> {
+    if entry.path() == path {
+        return Some(entry);
+    }
+
+    if let FileTreeEntry::Directory { children, .. } = entry {
+        for child in children.iter_mut() {
+            if let Some(found) = find_entry_by_path(child, path) {
+                return Some(found);
+            }
+        }
+    }
+
+    None
+}
+
+pub fn load_directory_children(
+    root_entry: &mut FileTreeEntry,
+    target_path: PathBuf,
+    show_hidden: bool,
+) {
+    if let Some(FileTreeEntry::Directory { children, .. }) =
+        find_entry_by_path(root_entry, &target_path)
+    {
+        *children = scan::read_children(&target_path, show_hidden);
+    }
+}
+
+/// 重新读取已加载的本地文件夹的子项，保留子文件夹的展开状态
+pub fn reload_children(entry: &mut FileTreeEntry, show_hidden: bool) {
+    let FileTreeEntry::Directory {
+        path,
+        children,
+        children_loaded: true,
+        ..
+    } = entry
+    else {
+        return;
+    };
+    if smart_directory::is_smart_folder(path) || catalog::is_library(path) || cloud::is_remote(path)
+    {
+        return;
+    }
+    replace_children(children, scan::read_children(path, show_hidden));
+    for child in children.iter_mut() {
+        reload_children(child, show_hidden);
+    }
+}
+
+/// 换成重新读取的子项，仍然存在的子文件夹保留展开状态和已加载的子项
+pub fn replace_children(children: &mut Vec<FileTreeEntry>, fresh: Vec<FileTreeEntry>) {
+    let mut old = std::mem::replace(children, fresh);
+    for child in children.iter_mut() {
+        let Some(position) = old.iter().position(|o| o.path() == child.path()) else {
+            continue;
+        };
+        if let (
+            FileTreeEntry::Directory {
+                expanded,
+                children,
+                children_loaded,
+                ..
+            },
+            FileTreeEntry::Directory {
+                expanded: old_expanded,
+                children: old_children,
+                children_loaded: old_loaded,
+                ..
+            },
+        ) = (&mut *child, old.swap_remove(position))
+        {
+            *expanded = old_expanded;
+            *children = old_children;
+            *children_loaded = old_loaded;
+        }
+    }
+}
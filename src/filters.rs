@@ -0,0 +1,127 @@
+use image::RgbImage;
+use image::imageops;
+
+use crate::i18n::tr;
+use crate::plugins::{self, PluginFilter};
+
+// 一键滤镜，内置的逐像素处理，或由插件提供
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Grayscale,
+    Sepia,
+    Invert,
+    Plugin(PluginFilter),
+}
+
+impl Filter {
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::Grayscale => tr("Grayscale"),
+            Filter::Sepia => tr("Sepia"),
+            Filter::Invert => tr("Invert"),
+            Filter::Plugin(filter) => filter.name(),
+        }
+    }
+
+    // 内置滤镜之后是已启用插件的滤镜
+    pub fn all() -> Vec<Filter> {
+        let mut filters = vec![Filter::Grayscale, Filter::Sepia, Filter::Invert];
+        filters.extend(plugins::filters().into_iter().map(Filter::Plugin));
+        filters
+    }
+
+    /// 在原地对图片应用滤镜
+    pub fn apply(self, img: &mut RgbImage) {
+        let pixel_fn: fn([u8; 3]) -> [u8; 3] = match self {
+            Filter::Grayscale => grayscale,
+            Filter::Sepia => sepia,
+            Filter::Invert => |c| c.map(|c| 255 - c),
+            Filter::Plugin(filter) => return filter.apply(img),
+        };
+        for pixel in img.pixels_mut() {
+            pixel.0 = pixel_fn(pixel.0);
+        }
+    }
+}
+
+fn grayscale([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as u8;
+    [luma; 3]
+}
+
+// 常用的棕褐色变换矩阵
+fn sepia([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    [
+        0.393 * r + 0.769 * g + 0.189 * b,
+        0.349 * r + 0.686 * g + 0.168 * b,
+        0.272 * r + 0.534 * g + 0.131 * b,
+    ]
+    .map(|c| c.round().min(255.0) as u8)
+}
+
+// 自动色阶时每端忽略的像素比例，避免个别噪点决定拉伸范围
+const AUTO_LEVELS_CLIP: f32 = 0.005;
+
+/// 自动色阶：按每个通道的直方图把有效范围拉伸到 0..=255，返回各通道的查找表
+pub fn auto_levels_tables(img: &RgbImage) -> [[u8; 256]; 3] {
+    let mut histograms = [[0u32; 256]; 3];
+    for pixel in img.pixels() {
+        for (histogram, &c) in histograms.iter_mut().zip(pixel.0.iter()) {
+            histogram[c as usize] += 1;
+        }
+    }
+    let clip = (img.width() as f32 * img.height() as f32 * AUTO_LEVELS_CLIP) as u32;
+    histograms.map(|histogram| {
+        let (low, high) = histogram_bounds(&histogram, clip);
+        let mut table = [0u8; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = if high <= low {
+                i as u8
+            } else {
+                let v = (i as f32 - low as f32) * 255.0 / (high - low) as f32;
+                v.round().clamp(0.0, 255.0) as u8
+            };
+        }
+        table
+    })
+}
+
+// 两端各去掉 clip 个像素后的最小值和最大值
+fn histogram_bounds(histogram: &[u32; 256], clip: u32) -> (usize, usize) {
+    let mut count = 0;
+    let low = histogram
+        .iter()
+        .position(|&n| {
+            count += n;
+            count > clip
+        })
+        .unwrap_or(0);
+    count = 0;
+    let high = histogram
+        .iter()
+        .rposition(|&n| {
+            count += n;
+            count > clip
+        })
+        .unwrap_or(255);
+    (low, high)
+}
+
+/// 高斯模糊，sigma 越大越模糊（使用快速的近似算法）
+pub fn gaussian_blur(img: &RgbImage, sigma: f32) -> RgbImage {
+    imageops::fast_blur(img, sigma)
+}
+
+/// USM 锐化：原图加上原图与模糊图之差的 amount 倍
+pub fn unsharp_mask(img: &RgbImage, amount: f32, sigma: f32) -> RgbImage {
+    let blurred = imageops::fast_blur(img, sigma);
+    let mut out = img.clone();
+    for (pixel, blurred) in out.pixels_mut().zip(blurred.pixels()) {
+        for (c, b) in pixel.0.iter_mut().zip(blurred.0) {
+            let v = *c as f32 + (*c as f32 - b as f32) * amount;
+            *c = v.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    out
+}
@@ -0,0 +1,22 @@
+use std::path::Path;
+
+use crate::{psd, textures};
+
+/// 可以解码、在目录树中显示并加入图片库的扩展名（小写）。与 Cargo.toml 中启用的 image 格式一致，
+/// 另加由 textures 解码的 DDS 和 KTX 纹理以及由 psd 解码的 Photoshop 文件
+pub const IMAGE_EXTENSIONS: [&str; 21] = [
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "tga", "pbm", "pgm", "ppm", "pnm", "pam",
+    "tif", "tiff", "qoi", "dds", "ktx", "ktx2", "psd", "psb",
+];
+
+/// 按扩展名判断是否是支持的图片，不区分大小写
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 由本程序自己解码的格式，iced 无法按路径显示，只能使用解码后的像素
+pub fn decoded_by_app(path: &Path) -> bool {
+    textures::is_texture(path) || psd::is_psd(path)
+}
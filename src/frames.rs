@@ -0,0 +1,81 @@
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageResult, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::export::{self, EncodeOptions, ExportFormat};
+
+/// 动画图片的各帧，在图片区域下方用滑块逐帧查看
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub path: PathBuf,
+    pub frames: Vec<Arc<RgbaImage>>, // 已按前面的帧合成的完整画面
+    pub current: usize,              // 正在显示的帧
+}
+
+/// 可能含有动画的格式：GIF、APNG 和动画 WebP
+pub fn may_be_animated(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "gif" | "png" | "webp"))
+}
+
+/// 解码动画的所有帧，只有一帧的图片返回空
+pub fn decode(path: &Path) -> ImageResult<Vec<Arc<RgbaImage>>> {
+    let reader = BufReader::new(File::open(path)?);
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let frames = match extension.as_str() {
+        "gif" => GifDecoder::new(reader)?.into_frames(),
+        "png" => {
+            let decoder = PngDecoder::new(reader)?;
+            if !decoder.is_apng()? {
+                return Ok(Vec::new());
+            }
+            decoder.apng()?.into_frames()
+        }
+        "webp" => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Ok(Vec::new());
+            }
+            decoder.into_frames()
+        }
+        _ => return Ok(Vec::new()),
+    };
+    let frames = frames
+        .map(|frame| frame.map(|frame| Arc::new(frame.into_buffer())))
+        .collect::<ImageResult<Vec<_>>>()?;
+    Ok(if frames.len() > 1 { frames } else { Vec::new() })
+}
+
+/// 把一帧写为 PNG，保留透明通道
+pub fn write_frame(frame: &RgbaImage, dst: &Path) -> Result<(), String> {
+    let options = EncodeOptions {
+        format: ExportFormat::Png,
+        ..EncodeOptions::default()
+    };
+    export::write_image(&DynamicImage::ImageRgba8(frame.clone()), &options, dst)
+        .map_err(|e| e.to_string())
+}
+
+/// 把所有帧写入 dir，文件名为原文件名加四位帧序号（从 1 开始），如 name_0001.png
+pub fn write_all(frames: &[Arc<RgbaImage>], source: &Path, dir: &Path) -> Result<(), String> {
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    for (index, frame) in frames.iter().enumerate() {
+        write_frame(frame, &dir.join(frame_name(&stem, index)))?;
+    }
+    Ok(())
+}
+
+/// 导出一帧时的默认文件名
+pub fn frame_name(stem: &str, index: usize) -> String {
+    format!("{}_{:04}.png", stem, index + 1)
+}
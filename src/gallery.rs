@@ -0,0 +1,339 @@
+use iced::futures::{SinkExt, Stream};
+use image::{DynamicImage, RgbImage};
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::export::{self, EncodeOptions};
+use crate::file_tree::file_name;
+use crate::pipeline::{self, ResamplingType};
+use crate::{formats, xmp_iptc};
+
+// 向导中可选的大图最长边和缩略图边长（像素）
+pub const IMAGE_SIZES: [u32; 4] = [1280, 1920, 2560, 3840];
+pub const THUMBNAIL_SIZES: [u32; 3] = [160, 240, 320];
+
+const IMAGES_DIR: &str = "images";
+const THUMBNAILS_DIR: &str = "thumbnails";
+
+/// 向导的两个步骤：先选内容，再选格式和输出位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GalleryStep {
+    Content,
+    Output,
+}
+
+/// 网页相册的选项
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalleryOptions {
+    pub title: String,       // 页面标题，默认为文件夹名称
+    pub image_size: u32,     // 大图的最长边
+    pub thumbnail_size: u32, // 缩略图的最长边
+    pub captions: bool,      // 显示元数据中的标题和描述
+    pub encode: EncodeOptions,
+}
+
+impl GalleryOptions {
+    pub fn new(title: String) -> Self {
+        GalleryOptions {
+            title,
+            image_size: 1920,
+            thumbnail_size: 240,
+            captions: true,
+            encode: EncodeOptions {
+                quality: 85,
+                ..EncodeOptions::default()
+            },
+        }
+    }
+}
+
+/// 打开着的相册向导
+#[derive(Debug, Clone)]
+pub struct GalleryWizard {
+    pub folder: PathBuf, // 导出哪个文件夹中的图片
+    pub count: usize,    // 文件夹中的图片数量
+    pub step: GalleryStep,
+    pub options: GalleryOptions,
+    pub output: Option<PathBuf>,          // 输出文件夹，选择之前不能导出
+    pub progress: Option<(usize, usize)>, // 导出进度（已完成, 总数）
+    pub failures: Vec<(PathBuf, String)>, // 导出失败的图片及原因
+    pub index: Option<PathBuf>,           // 导出完成后的 index.html
+    pub error: Option<String>,            // 写入页面失败的原因
+}
+
+impl GalleryWizard {
+    pub fn new(folder: PathBuf) -> Self {
+        let options = GalleryOptions::new(file_name(&folder));
+        GalleryWizard {
+            count: images(&folder).len(),
+            folder,
+            step: GalleryStep::Content,
+            options,
+            output: None,
+            progress: None,
+            failures: Vec::new(),
+            index: None,
+            error: None,
+        }
+    }
+}
+
+/// 导出进度
+#[derive(Debug, Clone)]
+pub enum GalleryProgress {
+    Started(usize), // 文件夹中的图片数量
+    Exported(PathBuf, Result<(), String>),
+    Finished(Result<PathBuf, String>), // 生成的 index.html
+}
+
+// 页面上的一张图片
+struct Entry {
+    file: String, // images 和 thumbnails 中的文件名
+    size: (u32, u32),
+    thumbnail_size: (u32, u32),
+    name: String,
+    title: Option<String>,
+    description: Option<String>,
+}
+
+/// 文件夹中的图片，按文件名排序
+pub fn images(folder: &Path) -> Vec<PathBuf> {
+    let mut images: Vec<PathBuf> = fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && formats::is_image(path))
+                .collect()
+        })
+        .unwrap_or_default();
+    images.sort();
+    images
+}
+
+/// 把文件夹中的图片导出为静态网页相册：output 中的 index.html、images 和 thumbnails，
+/// 不依赖脚本和外部资源。重新导出到同一位置时覆盖上一次的文件
+pub fn export(
+    folder: PathBuf,
+    options: GalleryOptions,
+    output: PathBuf,
+) -> impl Stream<Item = GalleryProgress> {
+    iced::stream::channel(4, move |mut sender| async move {
+        let sources = images(&folder);
+        let _ = sender.send(GalleryProgress::Started(sources.len())).await;
+        let created = fs::create_dir_all(output.join(IMAGES_DIR))
+            .and_then(|()| fs::create_dir_all(output.join(THUMBNAILS_DIR)));
+        if let Err(e) = created {
+            let _ = sender
+                .send(GalleryProgress::Finished(Err(e.to_string())))
+                .await;
+            return;
+        }
+
+        let extension = options.encode.format.extension();
+        let mut used = HashSet::new();
+        let mut entries = Vec::new();
+        for source in sources {
+            let file = output_name(&source, extension, &mut used);
+            let (src, opts, dir) = (source.clone(), options.clone(), output.clone());
+            let result = tokio::task::spawn_blocking(move || export_image(&src, &opts, &dir, file))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result);
+            let result = result.map(|entry| entries.push(entry));
+            let _ = sender.send(GalleryProgress::Exported(source, result)).await;
+        }
+
+        let index = output.join("index.html");
+        let result = fs::write(&index, index_page(&options, &entries))
+            .map(|()| index)
+            .map_err(|e| e.to_string());
+        let _ = sender.send(GalleryProgress::Finished(result)).await;
+    })
+}
+
+// 输出文件名沿用原文件名，同名不同格式的图片追加 _1、_2 ……
+fn output_name(source: &Path, extension: &str, used: &mut HashSet<String>) -> String {
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}.{}", stem, extension);
+    let mut n = 1;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{}_{}.{}", stem, n, extension);
+        n += 1;
+    }
+    name
+}
+
+// 解码一次，依次缩小为大图和缩略图并按编码参数写出
+fn export_image(
+    source: &Path,
+    options: &GalleryOptions,
+    output: &Path,
+    file: String,
+) -> Result<Entry, String> {
+    let (decoded, _) = pipeline::decode_image(source, true).map_err(|e| e.to_string())?;
+    let img = decoded.to_rgb8();
+    let size = export::fit_within(img.dimensions(), options.image_size);
+    let large = resized(img, size)?;
+    let thumbnail_size = export::fit_within(size, options.thumbnail_size);
+    let thumbnail = export::resize_image(
+        &large,
+        thumbnail_size.0,
+        thumbnail_size.1,
+        ResamplingType::Lanczos3,
+    )?;
+    for (img, dir) in [(large, IMAGES_DIR), (thumbnail, THUMBNAILS_DIR)] {
+        export::write_image(
+            &DynamicImage::ImageRgb8(img),
+            &options.encode,
+            &output.join(dir).join(&file),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let metadata = options
+        .captions
+        .then(|| xmp_iptc::read_descriptive_metadata(source))
+        .flatten()
+        .unwrap_or_default();
+    Ok(Entry {
+        file,
+        size,
+        thumbnail_size,
+        name: file_name(source),
+        title: metadata.title,
+        description: metadata.description,
+    })
+}
+
+fn resized(img: RgbImage, (width, height): (u32, u32)) -> Result<RgbImage, String> {
+    if img.dimensions() == (width, height) {
+        Ok(img)
+    } else {
+        export::resize_image(&img, width, height, ResamplingType::Lanczos3)
+    }
+}
+
+// 缩略图网格加上用 :target 实现的大图浏览，每张大图有上一张/下一张链接
+fn index_page(options: &GalleryOptions, entries: &[Entry]) -> String {
+    let title = escape(&options.title);
+    let cell = options.thumbnail_size;
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="generator" content="Tolores">
+<title>{title}</title>
+<style>
+body {{ margin: 0; padding: 24px; font-family: system-ui, sans-serif; background: #f4f4f4; color: #222; }}
+h1 {{ margin: 0 0 20px; font-size: 24px; font-weight: 600; }}
+.grid {{ display: grid; grid-template-columns: repeat(auto-fill, minmax({cell}px, 1fr)); gap: 16px; }}
+figure {{ margin: 0; padding: 8px; background: #fff; border-radius: 8px; box-shadow: 0 1px 3px rgba(0, 0, 0, .12); }}
+figure > a {{ display: flex; align-items: center; justify-content: center; height: {cell}px; }}
+figure img {{ max-width: 100%; max-height: 100%; }}
+figcaption {{ margin-top: 6px; font-size: 13px; overflow-wrap: anywhere; }}
+figcaption small {{ display: block; color: #666; }}
+.view {{ display: none; position: fixed; inset: 0; flex-direction: column; align-items: center; justify-content: center; background: rgba(0, 0, 0, .92); }}
+.view:target {{ display: flex; }}
+.view img {{ max-width: 92vw; max-height: 84vh; }}
+.view p {{ margin: 12px; color: #ddd; text-align: center; }}
+.view p small {{ display: block; color: #aaa; }}
+.view a {{ position: absolute; padding: 16px; color: #fff; font-size: 40px; text-decoration: none; }}
+.close {{ top: 0; right: 8px; }}
+.prev {{ top: 45%; left: 8px; }}
+.next {{ top: 45%; right: 8px; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div class="grid">
+"#
+    );
+
+    for (index, entry) in entries.iter().enumerate() {
+        let file = url_escape(&entry.file);
+        let alt = escape(entry.title.as_deref().unwrap_or(&entry.name));
+        let _ = write!(
+            html,
+            r##"<figure id="g{index}"><a href="#v{index}"><img src="{THUMBNAILS_DIR}/{file}" width="{}" height="{}" alt="{alt}" loading="lazy"></a>"##,
+            entry.thumbnail_size.0, entry.thumbnail_size.1,
+        );
+        if options.captions {
+            let _ = write!(html, "<figcaption>{}</figcaption>", caption(entry));
+        }
+        html.push_str("</figure>\n");
+    }
+    html.push_str("</div>\n");
+
+    for (index, entry) in entries.iter().enumerate() {
+        let file = url_escape(&entry.file);
+        let alt = escape(entry.title.as_deref().unwrap_or(&entry.name));
+        let _ = write!(
+            html,
+            r##"<div class="view" id="v{index}"><a class="close" href="#g{index}">×</a>"##
+        );
+        if index > 0 {
+            let _ = write!(html, r##"<a class="prev" href="#v{}">‹</a>"##, index - 1);
+        }
+        let _ = write!(
+            html,
+            r#"<img src="{IMAGES_DIR}/{file}" width="{}" height="{}" alt="{alt}" loading="lazy">"#,
+            entry.size.0, entry.size.1,
+        );
+        if options.captions {
+            let _ = write!(html, "<p>{}</p>", caption(entry));
+        }
+        if index + 1 < entries.len() {
+            let _ = write!(html, r##"<a class="next" href="#v{}">›</a>"##, index + 1);
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+// 标题（没有时为文件名）和描述
+fn caption(entry: &Entry) -> String {
+    let mut caption = escape(entry.title.as_deref().unwrap_or(&entry.name));
+    if let Some(description) = &entry.description {
+        let _ = write!(caption, "<small>{}</small>", escape(description));
+    }
+    caption
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 文件名中的空格、# 和非 ASCII 字符在链接中需要百分号编码
+fn url_escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                escaped.push(byte as char)
+            }
+            byte => {
+                let _ = write!(escaped, "%{:02X}", byte);
+            }
+        }
+    }
+    escaped
+}
@@ -0,0 +1,137 @@
+use iced::keyboard::key::Named;
+use iced::mouse::ScrollDelta;
+use iced::touch::{self, Finger};
+use iced::{Point, Vector, keyboard};
+use std::collections::HashMap;
+
+// 按行滚动时每行对应的平移距离（像素）
+const LINE_HEIGHT: f32 = 40.0;
+
+// Ctrl+滚动时滚动多少像素缩放 e 倍
+const ZOOM_DISTANCE: f32 = 400.0;
+
+// 滚轮切换图片时，触控板按像素滚动多少算作一格
+const WHEEL_STEP_PIXELS: f32 = 120.0;
+
+// 方向键每次平移的距离（像素）
+const KEY_PAN_STEP: f32 = 50.0;
+
+/// 一次手势对视图的改变
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gesture {
+    pub zoom: f32,   // 缩放倍率，1.0 表示不变
+    pub pan: Vector, // 平移量（像素）
+}
+
+/// 双指滚动平移图片；Ctrl+滚动缩放，Windows 和多数 Linux 桌面把触控板的捏合报告为 Ctrl+滚轮
+pub fn from_scroll(delta: ScrollDelta, modifiers: keyboard::Modifiers) -> Gesture {
+    let (x, y) = match delta {
+        ScrollDelta::Lines { x, y } => (x * LINE_HEIGHT, y * LINE_HEIGHT),
+        ScrollDelta::Pixels { x, y } => (x, y),
+    };
+    if modifiers.command() {
+        Gesture {
+            zoom: (y / ZOOM_DISTANCE).exp(),
+            pan: Vector::ZERO,
+        }
+    } else if modifiers.shift() && x == 0.0 {
+        // 只有竖直滚轮的鼠标按住 Shift 横向平移
+        Gesture {
+            zoom: 1.0,
+            pan: Vector::new(y, 0.0),
+        }
+    } else {
+        Gesture {
+            zoom: 1.0,
+            pan: Vector::new(x, y),
+        }
+    }
+}
+
+/// 方向键对应的平移量：按右方向键查看图片右边的部分，图片向左移动
+pub fn arrow_pan(key: &keyboard::Key) -> Option<Vector> {
+    let keyboard::Key::Named(named) = key else {
+        return None;
+    };
+    let (x, y) = match named {
+        Named::ArrowLeft => (1.0, 0.0),
+        Named::ArrowRight => (-1.0, 0.0),
+        Named::ArrowUp => (0.0, 1.0),
+        Named::ArrowDown => (0.0, -1.0),
+        _ => return None,
+    };
+    Some(Vector::new(x, y) * KEY_PAN_STEP)
+}
+
+/// 按住空格或中键时临时启用的手型工具
+#[derive(Debug, Clone, Copy)]
+pub struct TemporaryPan {
+    pub middle_button: bool,        // 由中键启用，松开中键时结束
+    pub hand_tool_was_active: bool, // 结束后恢复原来的工具
+    pub moved: bool,                // 是否拖动过，中键单击而没有拖动时恢复默认视图
+}
+
+/// 滚轮切换图片时累计的滚动量，触控板的小幅滚动累计到一格才切换
+#[derive(Debug, Default)]
+pub struct WheelSteps {
+    accumulated: f32,
+}
+
+impl WheelSteps {
+    /// 向上滚动一格返回 1（上一张），向下返回 -1（下一张），不足一格返回 0
+    pub fn update(&mut self, delta: ScrollDelta) -> i32 {
+        self.accumulated += match delta {
+            ScrollDelta::Lines { y, .. } => y,
+            ScrollDelta::Pixels { y, .. } => y / WHEEL_STEP_PIXELS,
+        };
+        if self.accumulated.abs() < 1.0 {
+            return 0;
+        }
+        let step = self.accumulated.signum() as i32;
+        self.accumulated = 0.0;
+        step
+    }
+}
+
+/// 触摸屏上按下的手指，两指移动时换算为捏合缩放和平移
+#[derive(Debug, Default)]
+pub struct TouchTracker {
+    fingers: HashMap<Finger, Point>,
+}
+
+impl TouchTracker {
+    pub fn update(&mut self, event: touch::Event) -> Option<Gesture> {
+        match event {
+            touch::Event::FingerPressed { id, position } => {
+                self.fingers.insert(id, position);
+                None
+            }
+            touch::Event::FingerMoved { id, position } => {
+                let before = self.pair();
+                *self.fingers.get_mut(&id)? = position;
+                let ((distance, center), (new_distance, new_center)) = (before?, self.pair()?);
+                (distance > 0.0).then(|| Gesture {
+                    zoom: new_distance / distance,
+                    pan: new_center - center,
+                })
+            }
+            touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. } => {
+                self.fingers.remove(&id);
+                None
+            }
+        }
+    }
+
+    // 恰好两指时两指间的距离和中点
+    fn pair(&self) -> Option<(f32, Point)> {
+        let mut fingers = self.fingers.values();
+        let (a, b) = (fingers.next()?, fingers.next()?);
+        if fingers.next().is_some() {
+            return None;
+        }
+        Some((
+            a.distance(*b),
+            Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0),
+        ))
+    }
+}
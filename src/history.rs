@@ -0,0 +1,135 @@
+use image::RgbImage;
+use std::time::{Duration, Instant};
+
+use crate::adjustments::Adjustments;
+use crate::crop::CropRect;
+use crate::straighten;
+
+// 连续拖动滑块产生的调整在这个时间内合并为一步
+const ADJUST_MERGE_WINDOW: Duration = Duration::from_secs(1);
+
+// 旋转方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Left,
+    Right,
+}
+
+// 可撤销的编辑操作，只保存参数，需要时从原图重新计算，不保存图片副本
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditOp {
+    Crop(CropRect), // 裁剪区域基于上一步编辑后的图片
+    Rotate(Rotation),
+    Straighten(f32), // 任意角度旋转并自动裁边，单位为度
+    Adjust {
+        before: Adjustments,
+        after: Adjustments,
+    },
+}
+
+impl EditOp {
+    // 裁剪和旋转会改变像素尺寸，需要从原图重放
+    pub fn is_geometry(&self) -> bool {
+        !matches!(self, EditOp::Adjust { .. })
+    }
+}
+
+// 编辑历史，撤销的操作移到 redo 栈，新的操作会清空 redo 栈
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo: Vec<EditOp>,
+    redo: Vec<EditOp>,
+    last_adjust: Option<Instant>,
+    geometry_revision: u64, // 几何编辑每次变化时加一，用于丢弃过期的后台计算结果
+}
+
+impl EditHistory {
+    pub fn push(&mut self, op: EditOp) {
+        if let EditOp::Adjust { before, after } = op
+            && before == after
+        {
+            return;
+        }
+        self.redo.clear();
+        if let EditOp::Adjust { after, .. } = op {
+            let now = Instant::now();
+            let recent = self
+                .last_adjust
+                .is_some_and(|last| now.duration_since(last) < ADJUST_MERGE_WINDOW);
+            self.last_adjust = Some(now);
+            if recent && let Some(EditOp::Adjust { before, after: top }) = self.undo.last_mut() {
+                *top = after;
+                // 调回了原来的值，这一步不再需要
+                if *before == after {
+                    self.undo.pop();
+                }
+                return;
+            }
+        } else {
+            self.last_adjust = None;
+            self.geometry_revision += 1;
+        }
+        self.undo.push(op);
+    }
+
+    /// 撤销最近的操作，返回被撤销的操作
+    pub fn undo(&mut self) -> Option<EditOp> {
+        let op = self.undo.pop()?;
+        self.finish_step(op);
+        self.redo.push(op);
+        Some(op)
+    }
+
+    /// 重做最近撤销的操作
+    pub fn redo(&mut self) -> Option<EditOp> {
+        let op = self.redo.pop()?;
+        self.finish_step(op);
+        self.undo.push(op);
+        Some(op)
+    }
+
+    fn finish_step(&mut self, op: EditOp) {
+        self.last_adjust = None;
+        if op.is_geometry() {
+            self.geometry_revision += 1;
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = EditHistory {
+            geometry_revision: self.geometry_revision + 1,
+            ..Default::default()
+        };
+    }
+
+    pub fn geometry_revision(&self) -> u64 {
+        self.geometry_revision
+    }
+
+    /// 按顺序把裁剪和旋转应用到原图，没有几何编辑时返回 None
+    pub fn apply_geometry(&self, original: &RgbImage) -> Option<RgbImage> {
+        let mut edited: Option<RgbImage> = None;
+        for op in &self.undo {
+            let img = edited.as_ref().unwrap_or(original);
+            edited = match *op {
+                EditOp::Crop(rect) => {
+                    let (x, y, width, height) = rect.to_pixels(img.width(), img.height());
+                    Some(image::imageops::crop_imm(img, x, y, width, height).to_image())
+                }
+                EditOp::Rotate(Rotation::Left) => Some(image::imageops::rotate270(img)),
+                EditOp::Rotate(Rotation::Right) => Some(image::imageops::rotate90(img)),
+                EditOp::Straighten(degrees) => Some(straighten::straighten(img, degrees)),
+                EditOp::Adjust { .. } => continue,
+            };
+        }
+        edited
+    }
+}
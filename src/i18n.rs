@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+// 中文翻译，gettext 的 .po 格式，msgid 为界面中的英文原文
+const ZH_CN: &str = include_str!("../locales/zh_CN.po");
+
+static CHINESE: LazyLock<HashMap<String, String>> = LazyLock::new(|| parse_po(ZH_CN));
+
+// 当前是否显示中文
+static USE_CHINESE: AtomicBool = AtomicBool::new(false);
+
+/// 界面语言，System 时按系统的区域设置选择
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    System,
+    English,
+    Chinese,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::System, Language::English, Language::Chinese];
+
+    // 语言名称用各自的语言显示，不翻译
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::System => tr("System"),
+            Language::English => "English",
+            Language::Chinese => "中文",
+        }
+    }
+}
+
+/// 切换界面语言，之后 tr 返回对应的翻译
+pub fn set_language(language: Language) {
+    let chinese = match language {
+        Language::System => sys_locale::get_locale().is_some_and(|locale| locale.starts_with("zh")),
+        Language::English => false,
+        Language::Chinese => true,
+    };
+    USE_CHINESE.store(chinese, Ordering::Relaxed);
+}
+
+/// 翻译界面文字，没有翻译时返回英文原文
+pub fn tr(msgid: &'static str) -> &'static str {
+    if !USE_CHINESE.load(Ordering::Relaxed) {
+        return msgid;
+    }
+    match CHINESE.get(msgid) {
+        Some(msgstr) if !msgstr.is_empty() => msgstr,
+        _ => msgid,
+    }
+}
+
+/// 翻译带参数的文字，译文中的 {} 依次替换为 args
+pub fn trf(msgid: &'static str, args: &[&dyn Display]) -> String {
+    let mut parts = tr(msgid).split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        if let Some(arg) = args.get(index) {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+// 解析 .po 文件中的 msgid / msgstr，字符串可以像 gettext 工具生成的那样分成多行 "..."
+fn parse_po(content: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    // 当前条目的关键字和字符串
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // 续行接在上一个关键字的字符串后面
+        if line.starts_with('"') {
+            match fields.last_mut() {
+                Some((_, value)) => value.push_str(&unquote(line)),
+                None => warn!("Ignored string outside of an entry on line {}", index + 1),
+            }
+            continue;
+        }
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        // msgstr 之后的 msgid 或 msgctxt 开始新的条目
+        if matches!(keyword, "msgid" | "msgctxt")
+            && fields.iter().any(|(name, _)| name.starts_with("msgstr"))
+        {
+            add_entry(&mut messages, std::mem::take(&mut fields));
+        }
+        fields.push((keyword, unquote(rest)));
+    }
+    add_entry(&mut messages, fields);
+    messages
+}
+
+// 把一个条目加入翻译；带上下文或复数形式的条目不支持，记录警告后跳过
+fn add_entry(messages: &mut HashMap<String, String>, fields: Vec<(&str, String)>) {
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(keyword, _)| *keyword == name)
+            .map(|(_, value)| value.clone())
+    };
+    let Some(msgid) = field("msgid") else {
+        if !fields.is_empty() {
+            warn!("Skipped translation entry without msgid: {:?}", fields);
+        }
+        return;
+    };
+    // 空的 msgid 是文件头
+    if msgid.is_empty() {
+        return;
+    }
+    match field("msgstr") {
+        Some(msgstr) if fields.len() == 2 => {
+            messages.insert(msgid, msgstr);
+        }
+        _ => warn!("Skipped unsupported translation entry {:?}", msgid),
+    }
+}
+
+// 去掉引号并还原转义字符
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    let text = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text);
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_and_multi_line_entries() {
+        let po = r#"# comment
+msgid ""
+msgstr ""
+"Language: zh_CN\n"
+
+msgid "Open"
+msgstr "打开"
+
+msgid ""
+"Delete {} from disk? "
+"This cannot be undone."
+msgstr ""
+"从磁盘删除 {}？"
+"此操作无法撤销。"
+
+msgid "Say \"hi\"\n"
+msgstr "说\"你好\"\n"
+"#;
+        let messages = parse_po(po);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages["Open"], "打开");
+        assert_eq!(
+            messages["Delete {} from disk? This cannot be undone."],
+            "从磁盘删除 {}？此操作无法撤销。"
+        );
+        assert_eq!(messages["Say \"hi\"\n"], "说\"你好\"\n");
+    }
+
+    #[test]
+    fn skips_context_and_plural_entries() {
+        let po = r#"msgctxt "menu"
+msgid "File"
+msgstr "文件"
+
+msgid "{} image"
+msgid_plural "{} images"
+msgstr[0] "{} 张图片"
+
+msgid "Next"
+msgstr "下一张"
+"#;
+        let messages = parse_po(po);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages["Next"], "下一张");
+    }
+
+    #[test]
+    fn bundled_translations_parse() {
+        let messages = parse_po(ZH_CN);
+        assert_eq!(messages["Open"], "打开");
+        assert_eq!(
+            messages.len(),
+            ZH_CN
+                .lines()
+                .filter(|line| line.starts_with("msgid "))
+                .count()
+                - 1
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use image::RgbImage;
+use image::metadata::Orientation;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::debug;
+
+use crate::textures::TextureInfo;
+
+// 默认最多缓存约 512 MB 的解码结果
+pub const DEFAULT_BUDGET: usize = 512 * 1024 * 1024;
+
+/// 解码得到的图片
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub image: Arc<RgbImage>, // 与正在显示的图片共享像素
+    pub orientation: Orientation,
+    pub full_size: Option<(u32, u32)>, // 按缩小的尺寸解码时为原图尺寸
+    pub texture: Option<TextureInfo>,  // GPU 纹理的格式和层数
+    pub psd_layers: Vec<String>,       // PSD 的图层名称，从上到下
+}
+
+struct Entry {
+    path: PathBuf,
+    raw_orientation: bool, // 解码时是否忽略了EXIF方向
+    modified: Option<SystemTime>,
+    decoded: Decoded,
+}
+
+impl Entry {
+    fn bytes(&self) -> usize {
+        self.decoded.image.as_raw().len()
+    }
+}
+
+/// 按路径缓存最近解码的图片，超出内存预算时淘汰最久未使用的
+pub struct DecodedCache {
+    entries: Vec<Entry>, // 末尾为最近使用
+    budget: usize,
+    used: usize,
+}
+
+impl DecodedCache {
+    pub fn new(budget: usize) -> Self {
+        DecodedCache {
+            entries: Vec::new(),
+            budget,
+            used: 0,
+        }
+    }
+
+    /// 取出缓存的图片并标记为最近使用，文件在缓存后被修改过时视为未命中
+    pub fn get(&mut self, path: &Path, raw_orientation: bool) -> Option<Decoded> {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.path == path && e.raw_orientation == raw_orientation)?;
+        if self.entries[index].modified != modified_time(path) {
+            let entry = self.entries.remove(index);
+            self.used -= entry.bytes();
+            return None;
+        }
+        let entry = self.entries.remove(index);
+        let result = entry.decoded.clone();
+        self.entries.push(entry);
+        Some(result)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, raw_orientation: bool, decoded: Decoded) {
+        self.remove(&path);
+        let entry = Entry {
+            modified: modified_time(&path),
+            path,
+            raw_orientation,
+            decoded,
+        };
+        // 单张超过预算的图片不缓存
+        if entry.bytes() > self.budget {
+            return;
+        }
+        self.used += entry.bytes();
+        self.entries.push(entry);
+        self.shrink_to(self.budget);
+    }
+
+    /// 淘汰最久未使用的图片，直到占用不超过 limit 字节
+    pub fn shrink_to(&mut self, limit: usize) {
+        while self.used > limit && !self.entries.is_empty() {
+            let entry = self.entries.remove(0);
+            self.used -= entry.bytes();
+            debug!(
+                path = %entry.path.display(),
+                used = self.used,
+                limit,
+                "Evicted decoded image"
+            );
+        }
+    }
+
+    /// 这张图片的像素是否也被缓存持有
+    pub fn holds(&self, image: &Arc<RgbImage>) -> bool {
+        self.entries
+            .iter()
+            .any(|e| Arc::ptr_eq(&e.decoded.image, image))
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.entries.retain(|e| e.path != path);
+        self.used = self.entries.iter().map(Entry::bytes).sum();
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,48 @@
+use image::{DynamicImage, GrayImage, RgbImage};
+use jpeg_decoder::{Decoder, PixelFormat};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// 浏览时图片长边的目标尺寸，大约是常见屏幕的长边
+pub const DISPLAY_TARGET: u32 = 2560;
+
+/// 长边超过目标尺寸两倍的 JPEG 利用 DCT 缩放（1/2、1/4、1/8）直接解码出较小的图片，
+/// 返回缩小后的图片和原图尺寸；其他情况返回 None，由调用方按原尺寸解码
+pub fn decode_reduced(path: &Path, target: u32) -> Option<(DynamicImage, (u32, u32))> {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    if ext != "jpg" && ext != "jpeg" {
+        return None;
+    }
+
+    let mut decoder = Decoder::new(BufReader::new(File::open(path).ok()?));
+    decoder.read_info().ok()?;
+    let info = decoder.info()?;
+    let (width, height) = (info.width as u32, info.height as u32);
+    let long_edge = width.max(height);
+    if long_edge < target * 2 {
+        return None;
+    }
+
+    // 解码器会选择不小于请求尺寸的最小缩放比例
+    let ratio = target as f32 / long_edge as f32;
+    let requested = |size: u32| ((size as f32 * ratio).ceil() as u16).max(1);
+    let (scaled_w, scaled_h) = decoder.scale(requested(width), requested(height)).ok()?;
+    let pixels = decoder.decode().ok()?;
+    let (scaled_w, scaled_h) = (scaled_w as u32, scaled_h as u32);
+    let img = match info.pixel_format {
+        PixelFormat::RGB24 => {
+            DynamicImage::ImageRgb8(RgbImage::from_raw(scaled_w, scaled_h, pixels)?)
+        }
+        PixelFormat::L8 => {
+            DynamicImage::ImageLuma8(GrayImage::from_raw(scaled_w, scaled_h, pixels)?)
+        }
+        // CMYK 和 16 位灰度交给 image 按原尺寸解码
+        PixelFormat::CMYK32 | PixelFormat::L16 => return None,
+    };
+    Some((img, (width, height)))
+}
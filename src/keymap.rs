@@ -0,0 +1,445 @@
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::warn;
+
+use crate::i18n::tr;
+
+/// 可以绑定快捷键的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Next,
+    Previous,
+    Delete,
+    RotateLeft,
+    RotateRight,
+    Fullscreen,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    Save,
+    SaveAs,
+    Find,
+    Undo,
+    Redo,
+    Cancel,
+    PerfHud,
+    Diagnostics,
+    NewWindow,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    Thumbnails,
+    HoldToPan, // 按住时临时启用手型工具
+    ActualSize,
+    LockView, // 切换图片时保持缩放和平移
+    Blink,
+    ScanCodes, // 识别二维码和条形码
+    ExtractText,
+    FindSimilar, // 在图片目录中查找相似的图片
+    Map,
+    Timeline,
+    PickColor,
+    Refresh,
+    Pick, // 挑片：选中、排除、清除标记
+    Reject,
+    Unflag,
+}
+
+impl Action {
+    pub const ALL: [Action; 37] = [
+        Action::Next,
+        Action::Previous,
+        Action::Delete,
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::Fullscreen,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomReset,
+        Action::Save,
+        Action::SaveAs,
+        Action::Find,
+        Action::Undo,
+        Action::Redo,
+        Action::Cancel,
+        Action::PerfHud,
+        Action::Diagnostics,
+        Action::NewWindow,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::Thumbnails,
+        Action::HoldToPan,
+        Action::ActualSize,
+        Action::LockView,
+        Action::Blink,
+        Action::ScanCodes,
+        Action::ExtractText,
+        Action::FindSimilar,
+        Action::Map,
+        Action::Timeline,
+        Action::PickColor,
+        Action::Refresh,
+        Action::Pick,
+        Action::Reject,
+        Action::Unflag,
+    ];
+
+    /// 配置文件 [keys] 中的名称
+    pub fn id(self) -> &'static str {
+        match self {
+            Action::Next => "next",
+            Action::Previous => "previous",
+            Action::Delete => "delete",
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::Fullscreen => "fullscreen",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ZoomReset => "zoom_reset",
+            Action::Save => "save",
+            Action::SaveAs => "save_as",
+            Action::Find => "find",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Cancel => "cancel",
+            Action::PerfHud => "perf_hud",
+            Action::Diagnostics => "diagnostics",
+            Action::NewWindow => "new_window",
+            Action::NewTab => "new_tab",
+            Action::CloseTab => "close_tab",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::Thumbnails => "thumbnails",
+            Action::HoldToPan => "hold_to_pan",
+            Action::ActualSize => "actual_size",
+            Action::LockView => "lock_view",
+            Action::Blink => "blink",
+            Action::ScanCodes => "scan_codes",
+            Action::ExtractText => "extract_text",
+            Action::FindSimilar => "find_similar",
+            Action::Map => "map",
+            Action::Timeline => "timeline",
+            Action::PickColor => "pick_color",
+            Action::Refresh => "refresh",
+            Action::Pick => "pick",
+            Action::Reject => "reject",
+            Action::Unflag => "unflag",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Next => tr("Next image"),
+            Action::Previous => tr("Previous image"),
+            Action::Delete => tr("Delete image"),
+            Action::RotateLeft => tr("Rotate left"),
+            Action::RotateRight => tr("Rotate right"),
+            Action::Fullscreen => tr("Fullscreen"),
+            Action::ZoomIn => tr("Zoom in"),
+            Action::ZoomOut => tr("Zoom out"),
+            Action::ZoomReset => tr("Reset zoom"),
+            Action::Save => tr("Save"),
+            Action::SaveAs => tr("Save as…"),
+            Action::Find => tr("Find files by name"),
+            Action::Undo => tr("Undo"),
+            Action::Redo => tr("Redo"),
+            Action::Cancel => tr("Close panel or exit mode"),
+            Action::PerfHud => tr("Performance panel"),
+            Action::Diagnostics => tr("Diagnostics log"),
+            Action::NewWindow => tr("Open in new window"),
+            Action::NewTab => tr("New tab"),
+            Action::CloseTab => tr("Close tab"),
+            Action::NextTab => tr("Next tab"),
+            Action::PreviousTab => tr("Previous tab"),
+            Action::Thumbnails => tr("Show or hide thumbnails"),
+            Action::HoldToPan => tr("Hold to pan"),
+            Action::ActualSize => tr("Actual size"),
+            Action::LockView => tr("Lock zoom and position"),
+            Action::Blink => tr("Blink compare"),
+            Action::ScanCodes => tr("Scan codes"),
+            Action::ExtractText => tr("Extract text"),
+            Action::FindSimilar => tr("Find similar"),
+            Action::Map => tr("Map"),
+            Action::Timeline => tr("Timeline"),
+            Action::PickColor => tr("Pick color"),
+            Action::Refresh => tr("Refresh folder"),
+            Action::Pick => tr("Pick"),
+            Action::Reject => tr("Reject"),
+            Action::Unflag => tr("Clear flag"),
+        }
+    }
+
+    // 默认的快捷键，多个时用逗号分隔
+    fn default_keys(self) -> &'static str {
+        match self {
+            Action::Next => "Right",
+            Action::Previous => "Left",
+            Action::Delete => "Delete",
+            Action::RotateLeft => "Ctrl+L",
+            Action::RotateRight => "Ctrl+R",
+            Action::Fullscreen => "F, F11",
+            Action::ZoomIn => "+, =",
+            Action::ZoomOut => "-",
+            Action::ZoomReset => "Ctrl+0",
+            Action::Save => "Ctrl+S",
+            Action::SaveAs => "Ctrl+Shift+S",
+            Action::Find => "Ctrl+F",
+            Action::Undo => "Ctrl+Z",
+            Action::Redo => "Ctrl+Shift+Z, Ctrl+Y",
+            Action::Cancel => "Escape",
+            Action::PerfHud => "F12",
+            Action::Diagnostics => "Ctrl+Shift+L",
+            Action::NewWindow => "Ctrl+N",
+            Action::NewTab => "Ctrl+T",
+            Action::CloseTab => "Ctrl+W",
+            Action::NextTab => "Ctrl+Tab",
+            Action::PreviousTab => "Ctrl+Shift+Tab",
+            Action::Thumbnails => "Ctrl+B",
+            Action::HoldToPan => "Space",
+            Action::ActualSize => "Ctrl+1",
+            Action::LockView => "L",
+            Action::Blink => "B",
+            Action::ScanCodes => "Q",
+            Action::ExtractText => "Ctrl+Shift+C",
+            Action::FindSimilar => "Ctrl+Shift+F",
+            Action::Map => "M",
+            Action::Timeline => "T",
+            Action::PickColor => "I",
+            Action::Refresh => "F5",
+            Action::Pick => "P",
+            Action::Reject => "X",
+            Action::Unflag => "U",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.id() == id)
+    }
+}
+
+// 组合键中的按键
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyName {
+    Named(Named),
+    Char(String), // 已经转为小写
+}
+
+/// 一个组合键，例如 Ctrl+Shift+S；Ctrl 在 macOS 上对应 Cmd
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCombo {
+    key: KeyName,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+// 配置中可以使用的按键名称
+const NAMED_KEYS: [(&str, Named); 15] = [
+    ("Left", Named::ArrowLeft),
+    ("Right", Named::ArrowRight),
+    ("Up", Named::ArrowUp),
+    ("Down", Named::ArrowDown),
+    ("Delete", Named::Delete),
+    ("Backspace", Named::Backspace),
+    ("Escape", Named::Escape),
+    ("Enter", Named::Enter),
+    ("Space", Named::Space),
+    ("Tab", Named::Tab),
+    ("Home", Named::Home),
+    ("End", Named::End),
+    ("PageUp", Named::PageUp),
+    ("PageDown", Named::PageDown),
+    ("Insert", Named::Insert),
+];
+
+// F1 到 F12
+const FUNCTION_KEYS: [Named; 12] = [
+    Named::F1,
+    Named::F2,
+    Named::F3,
+    Named::F4,
+    Named::F5,
+    Named::F6,
+    Named::F7,
+    Named::F8,
+    Named::F9,
+    Named::F10,
+    Named::F11,
+    Named::F12,
+];
+
+impl KeyCombo {
+    /// 解析 "Ctrl+Shift+S"、"Right"、"+" 这样的写法，名称不区分大小写
+    pub fn parse(text: &str) -> Option<KeyCombo> {
+        let text = text.trim();
+        // 最后一个 + 之后是按键；以 + 结尾时按键就是 +
+        let (modifiers, key) = match text.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (rest.strip_suffix('+').unwrap_or(rest), "+")
+            }
+            _ => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut combo = KeyCombo {
+            key: parse_key(key.trim())?,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        };
+        for modifier in modifiers
+            .split('+')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+        {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" => combo.ctrl = true,
+                "shift" => combo.shift = true,
+                "alt" | "option" => combo.alt = true,
+                _ => return None,
+            }
+        }
+        Some(combo)
+    }
+
+    fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        let (key_matches, is_symbol) = match (&self.key, key.as_ref()) {
+            (KeyName::Named(named), Key::Named(pressed)) => (*named == pressed, false),
+            (KeyName::Char(c), Key::Character(pressed)) => (
+                c.eq_ignore_ascii_case(pressed),
+                !c.chars().all(char::is_alphanumeric),
+            ),
+            _ => (false, false),
+        };
+        // + 等符号在很多键盘上要按 Shift，没有指定 Shift 时不检查
+        key_matches
+            && self.ctrl == modifiers.command()
+            && self.alt == modifiers.alt()
+            && (self.shift == modifiers.shift() || (is_symbol && !self.shift))
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        match &self.key {
+            KeyName::Named(named) => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, key)| key == named)
+                    .map(|(name, _)| name.to_string())
+                    .or_else(|| {
+                        let index = FUNCTION_KEYS.iter().position(|key| key == named)?;
+                        Some(format!("F{}", index + 1))
+                    })
+                    .unwrap_or_default();
+                write!(f, "{}", name)
+            }
+            KeyName::Char(c) => write!(f, "{}", c.to_uppercase()),
+        }
+    }
+}
+
+fn parse_key(text: &str) -> Option<KeyName> {
+    if let Some((_, named)) = NAMED_KEYS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+    {
+        return Some(KeyName::Named(*named));
+    }
+    if text.eq_ignore_ascii_case("esc") {
+        return Some(KeyName::Named(Named::Escape));
+    }
+    if let Some(index) = text
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| (1..=FUNCTION_KEYS.len()).contains(n))
+    {
+        return Some(KeyName::Named(FUNCTION_KEYS[index - 1]));
+    }
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyName::Char(c.to_lowercase().collect())),
+        _ => None,
+    }
+}
+
+/// 动作和快捷键的对应关系：默认值加上配置文件 [keys] 中的修改
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyCombo>)>,
+}
+
+impl Keymap {
+    /// 按配置生成，无法识别的动作或按键打印警告后忽略；值为空字符串时取消该动作的快捷键
+    pub fn from_config(keys: &BTreeMap<String, String>) -> Keymap {
+        let mut bindings: Vec<_> = Action::ALL
+            .into_iter()
+            .map(|action| (action, parse_combos(action.default_keys())))
+            .collect();
+        for (id, value) in keys {
+            let Some(action) = Action::from_id(id) else {
+                warn!("Unknown action in [keys]: {}", id);
+                continue;
+            };
+            let combos = parse_combos(value);
+            if combos.len() != value.split(',').filter(|k| !k.trim().is_empty()).count() {
+                warn!("Invalid shortcut for {}: {}", id, value);
+            }
+            if let Some((_, existing)) = bindings.iter_mut().find(|(a, _)| *a == action) {
+                *existing = combos;
+            }
+        }
+        Keymap { bindings }
+    }
+
+    /// 按下的组合键对应的动作
+    pub fn action(&self, key: &Key, modifiers: Modifiers) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, combos)| combos.iter().any(|combo| combo.matches(key, modifiers)))
+            .map(|(action, _)| *action)
+    }
+
+    /// 设置中显示的快捷键列表
+    pub fn describe(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, combos)| {
+                combos
+                    .iter()
+                    .map(KeyCombo::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    }
+}
+
+// 逗号分隔的多个组合键，逗号本身不能作为按键
+fn parse_combos(text: &str) -> Vec<KeyCombo> {
+    text.split(',')
+        .filter(|k| !k.trim().is_empty())
+        .filter_map(KeyCombo::parse)
+        .collect()
+}
+
+/// 写入新配置文件的默认快捷键，方便用户直接修改
+pub fn default_keys() -> BTreeMap<String, String> {
+    Action::ALL
+        .into_iter()
+        .map(|action| (action.id().to_string(), action.default_keys().to_string()))
+        .collect()
+}
@@ -0,0 +1,104 @@
+//! 图片浏览器：目录树、缩略图、缩放和编辑，以及云存储同步
+//! 解码、缩略图和缩放流程在 pipeline、thumbnails 和 image_cache 中，不依赖界面，可以单独使用
+
+mod adjustments;
+mod batch;
+mod barcodes;
+mod bcn;
+mod blink;
+mod button_style;
+mod catalog;
+mod channels;
+mod cli;
+mod cloud;
+mod cloud_storage;
+mod codes;
+mod color_picker;
+mod config;
+mod contact_sheet;
+mod context_menu;
+mod convert;
+pub mod cos_client;
+mod crop;
+mod exif_info;
+mod export;
+mod file_tree;
+mod filters;
+mod formats;
+mod frames;
+mod gallery;
+mod gestures;
+mod history;
+mod i18n;
+pub mod image_cache;
+mod jpeg_scale;
+mod keymap;
+mod logging;
+mod map;
+mod memory;
+mod messages;
+mod metadata;
+mod metadata_search;
+mod name_search;
+mod ocr;
+mod oss_client;
+mod palette;
+mod perf;
+pub mod pipeline;
+mod plugins;
+mod psd;
+mod qr;
+mod remote_cache;
+mod remote_control;
+mod s3_client;
+mod scan;
+mod script;
+mod settings;
+mod share;
+mod similar;
+mod smart_directory;
+mod state;
+mod straighten;
+mod sync;
+mod tabs;
+mod textures;
+mod throttle;
+mod timeline;
+pub mod thumbnails;
+mod toast;
+mod transfer_queue;
+mod upload_manifest;
+mod view;
+mod viewer;
+mod wallpaper;
+mod xmp_iptc;
+
+use clap::Parser;
+
+use cli::Args;
+use state::State;
+
+/// 解析命令行参数后启动图片浏览器；主窗口和查看窗口由 State 打开，主窗口关闭时退出
+pub fn run() -> iced::Result {
+    let args = Args::parse();
+    logging::init();
+    // 只把控制命令交给正在运行的实例，不打开窗口
+    if let Some(command) = &args.remote {
+        if let Err(e) = remote_control::send(command) {
+            eprintln!("Failed to send '{}': {}", command.line(), e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // 已经有实例在运行时通过控制接口把路径交给它，并由它切换到前台
+    if !args.new_instance
+        && let Some(path) = &args.path
+        && remote_control::send(&remote_control::Command::Open(path.clone())).is_ok()
+    {
+        return Ok(());
+    }
+    iced::daemon(State::title, State::update, State::view)
+        .subscription(State::subscription)
+        .theme(State::theme)
+        .run_with(move || State::new(args))
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+// 诊断面板中保留的日志条数
+const CAPACITY: usize = 500;
+
+/// 一条日志事件
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String, // 消息和附带的字段
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<5} {}: {}",
+            self.time.format("%H:%M:%S%.3f"),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+// 后台线程也会写日志，统一放在全局的队列中
+static ENTRIES: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+fn entries() -> MutexGuard<'static, VecDeque<LogEntry>> {
+    ENTRIES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 同时输出到 stderr 和诊断面板；本程序记录 debug 及以上，依赖库只记录警告和错误
+pub fn init() {
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG)
+        .with_default(Level::WARN);
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(RecentLayer)
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+}
+
+/// 最近的日志，旧的在前
+pub fn recent() -> Vec<LogEntry> {
+    entries().iter().cloned().collect()
+}
+
+pub fn clear() {
+    entries().clear();
+}
+
+// 把事件记到全局队列中，超过容量时丢弃最旧的
+struct RecentLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let entry = LogEntry {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+        };
+        let mut entries = entries();
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+// 消息放在最前面，其他字段按 name=value 追加在后面
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.message
+                .push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.insert_str(0, value);
+        } else {
+            self.message
+                .push_str(&format!(" {}={}", field.name(), value));
+        }
+    }
+}
@@ -0,0 +1,482 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Stroke, event};
+use iced::widget::image::Handle;
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme, Vector};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+use crate::cloud_storage;
+use crate::exif_info;
+use crate::messages::Message;
+
+// 地图面板中画布的尺寸
+pub const WIDTH: f32 = 480.0;
+pub const HEIGHT: f32 = 360.0;
+
+const TILE_SIZE: f64 = 256.0;
+const MIN_ZOOM: u8 = 1;
+const MAX_ZOOM: u8 = 18;
+// 打开地图时最多放大到这一级，只有一张照片时也能看到周围的地方
+const MAX_FIT_ZOOM: u8 = 15;
+// 屏幕上距离小于这个值的标记合并为一组
+const CLUSTER_RADIUS: f32 = 36.0;
+// 内存中最多保留的瓦片数，超过时丢掉其他缩放级别的瓦片
+const MAX_TILES: usize = 256;
+const TILE_URL: &str = "https://tile.openstreetmap.org";
+// 移动超过这个距离才算拖动，否则是点击
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// 有 GPS 位置的图片，坐标为 Web 墨卡托投影下的世界坐标（0 到 1）
+#[derive(Debug, Clone)]
+pub struct GeoPhoto {
+    pub path: PathBuf,
+    x: f64,
+    y: f64,
+}
+
+impl GeoPhoto {
+    fn new(path: PathBuf, latitude: f64, longitude: f64) -> GeoPhoto {
+        let latitude = latitude.clamp(-85.05, 85.05).to_radians();
+        GeoPhoto {
+            path,
+            x: (longitude + 180.0) / 360.0,
+            y: (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0,
+        }
+    }
+}
+
+/// 一块地图瓦片
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    pub zoom: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+// 屏幕上的一个标记，包含一张或多张距离很近的图片
+struct Cluster {
+    position: Point,
+    photos: Vec<usize>,
+}
+
+impl Cluster {
+    fn radius(&self) -> f32 {
+        if self.photos.len() == 1 {
+            7.0
+        } else {
+            11.0 + (self.photos.len() as f32).log10() * 5.0
+        }
+    }
+}
+
+/// 地图面板的状态
+#[derive(Debug, Clone)]
+pub struct MapView {
+    pub photos: Option<Vec<GeoPhoto>>, // None 时正在读取图片的位置
+    center: (f64, f64),                // 画布中心的世界坐标
+    zoom: u8,
+    tiles: HashMap<Tile, Option<Handle>>, // 值为 None 时正在下载或下载失败
+}
+
+impl MapView {
+    pub fn new() -> MapView {
+        MapView {
+            photos: None,
+            center: (0.5, 0.5),
+            zoom: MIN_ZOOM,
+            tiles: HashMap::new(),
+        }
+    }
+
+    /// 显示读取到的图片位置，缩放到刚好能看到所有图片
+    pub fn set_photos(&mut self, photos: Vec<GeoPhoto>) {
+        if !photos.is_empty() {
+            let (mut left, mut top, mut right, mut bottom) = (1.0f64, 1.0f64, 0.0f64, 0.0f64);
+            for photo in &photos {
+                left = left.min(photo.x);
+                right = right.max(photo.x);
+                top = top.min(photo.y);
+                bottom = bottom.max(photo.y);
+            }
+            self.center = ((left + right) / 2.0, (top + bottom) / 2.0);
+            // 四周留出标记的位置
+            let (width, height) = (WIDTH as f64 - 64.0, HEIGHT as f64 - 64.0);
+            self.zoom = (MIN_ZOOM..=MAX_FIT_ZOOM)
+                .rev()
+                .find(|&zoom| {
+                    let scale = world_size(zoom);
+                    (right - left) * scale <= width && (bottom - top) * scale <= height
+                })
+                .unwrap_or(MIN_ZOOM);
+        }
+        self.photos = Some(photos);
+    }
+
+    /// 拖动地图
+    pub fn pan(&mut self, delta: Vector) {
+        let scale = world_size(self.zoom);
+        self.center = (
+            (self.center.0 - delta.x as f64 / scale).clamp(0.0, 1.0),
+            (self.center.1 - delta.y as f64 / scale).clamp(0.0, 1.0),
+        );
+    }
+
+    /// 放大或缩小 steps 级，position（画布坐标）处的地点保持不动
+    pub fn zoom_at(&mut self, steps: i32, position: Point) {
+        let zoom = (self.zoom as i32 + steps).clamp(MIN_ZOOM as i32, MAX_ZOOM as i32) as u8;
+        let offset = (
+            (position.x - WIDTH / 2.0) as f64,
+            (position.y - HEIGHT / 2.0) as f64,
+        );
+        let old = world_size(self.zoom);
+        let anchor = (
+            self.center.0 + offset.0 / old,
+            self.center.1 + offset.1 / old,
+        );
+        let new = world_size(zoom);
+        self.center = (
+            (anchor.0 - offset.0 / new).clamp(0.0, 1.0),
+            (anchor.1 - offset.1 / new).clamp(0.0, 1.0),
+        );
+        self.zoom = zoom;
+    }
+
+    /// 当前能看到但还没有开始下载的瓦片，返回后记为正在下载
+    pub fn missing_tiles(&mut self) -> Vec<Tile> {
+        if self.tiles.len() > MAX_TILES {
+            let zoom = self.zoom;
+            self.tiles.retain(|tile, _| tile.zoom == zoom);
+        }
+        let missing: Vec<Tile> = self
+            .visible_tiles()
+            .into_iter()
+            .filter(|tile| !self.tiles.contains_key(tile))
+            .collect();
+        for tile in &missing {
+            self.tiles.insert(*tile, None);
+        }
+        missing
+    }
+
+    /// 瓦片下载完成，下载失败的瓦片留空，不再重试
+    pub fn tile_loaded(&mut self, tile: Tile, handle: Option<Handle>) {
+        if let Some(slot) = self.tiles.get_mut(&tile) {
+            *slot = handle;
+        }
+    }
+
+    fn visible_tiles(&self) -> Vec<Tile> {
+        let scale = world_size(self.zoom);
+        let count = 1u32 << self.zoom;
+        let left = self.center.0 * scale - WIDTH as f64 / 2.0;
+        let top = self.center.1 * scale - HEIGHT as f64 / 2.0;
+        let range = |start: f64, length: f32| {
+            let first = (start / TILE_SIZE).floor().max(0.0) as u32;
+            let last = ((start + length as f64) / TILE_SIZE).floor().max(0.0) as u32;
+            first.min(count - 1)..=last.min(count - 1)
+        };
+        let mut tiles = Vec::new();
+        for y in range(top, HEIGHT) {
+            for x in range(left, WIDTH) {
+                tiles.push(Tile {
+                    zoom: self.zoom,
+                    x,
+                    y,
+                });
+            }
+        }
+        tiles
+    }
+
+    // 世界坐标转换为画布坐标
+    fn to_screen(&self, x: f64, y: f64) -> Point {
+        let scale = world_size(self.zoom);
+        Point::new(
+            ((x - self.center.0) * scale) as f32 + WIDTH / 2.0,
+            ((y - self.center.1) * scale) as f32 + HEIGHT / 2.0,
+        )
+    }
+
+    // 画布中能看到的标记，距离近的合并为一组
+    fn clusters(&self) -> Vec<Cluster> {
+        let visible =
+            Rectangle::new(Point::ORIGIN, Size::new(WIDTH, HEIGHT)).expand(CLUSTER_RADIUS);
+        let photos = self.photos.as_deref().unwrap_or_default();
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for (index, photo) in photos.iter().enumerate() {
+            let position = self.to_screen(photo.x, photo.y);
+            if !visible.contains(position) {
+                continue;
+            }
+            match clusters
+                .iter_mut()
+                .find(|cluster| cluster.position.distance(position) < CLUSTER_RADIUS)
+            {
+                Some(cluster) => cluster.photos.push(index),
+                None => clusters.push(Cluster {
+                    position,
+                    photos: vec![index],
+                }),
+            }
+        }
+        // 组的位置放在所有图片的中间
+        for cluster in &mut clusters {
+            if cluster.photos.len() > 1 {
+                let (sum_x, sum_y) = cluster
+                    .photos
+                    .iter()
+                    .map(|&index| &photos[index])
+                    .fold((0.0, 0.0), |(x, y), photo| (x + photo.x, y + photo.y));
+                let count = cluster.photos.len() as f64;
+                cluster.position = self.to_screen(sum_x / count, sum_y / count);
+            }
+        }
+        clusters
+    }
+
+    // 画布坐标处的标记
+    fn cluster_at(&self, position: Point) -> Option<Cluster> {
+        self.clusters()
+            .into_iter()
+            .find(|cluster| cluster.position.distance(position) <= cluster.radius() + 2.0)
+    }
+}
+
+// 某一缩放级别下整个世界的像素宽度
+fn world_size(zoom: u8) -> f64 {
+    TILE_SIZE * (1u64 << zoom) as f64
+}
+
+/// 在后台读取图片的 GPS 位置，没有位置的图片不包括在内
+pub async fn locate(paths: Vec<PathBuf>) -> Vec<GeoPhoto> {
+    tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let gps = exif_info::read_exif(&path)?.gps?;
+                Some(GeoPhoto::new(path, gps.latitude, gps.longitude))
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// 下载 OpenStreetMap 瓦片，下载过的瓦片缓存在磁盘上
+pub async fn fetch_tile(tile: Tile) -> Result<Handle, String> {
+    let name = format!("{}/{}/{}.png", tile.zoom, tile.x, tile.y);
+    let cached = cloud_storage::cache_root().map(|root| root.join("map-tiles").join(&name));
+    if let Some(path) = &cached
+        && let Ok(bytes) = tokio::fs::read(path).await
+    {
+        return Ok(Handle::from_bytes(bytes));
+    }
+    // OpenStreetMap 的使用政策要求请求中带有能识别应用的 User-Agent
+    let response = reqwest::Client::new()
+        .get(format!("{}/{}", TILE_URL, name))
+        .header(
+            reqwest::header::USER_AGENT,
+            concat!("image-browser/", env!("CARGO_PKG_VERSION")),
+        )
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?.to_vec();
+    if let Some(path) = cached {
+        if let Some(dir) = path.parent() {
+            let _ = tokio::fs::create_dir_all(dir).await;
+        }
+        let _ = tokio::fs::write(&path, &bytes).await;
+    }
+    Ok(Handle::from_bytes(bytes))
+}
+
+/// 拖动地图时记录的位置
+#[derive(Debug, Default)]
+pub struct Drag {
+    pressed: Option<Point>, // 按下的位置
+    last: Point,            // 上一次移动到的位置
+    moved: bool,            // 已经超过点击的范围，松开时不再当作点击
+}
+
+/// 显示地图瓦片和图片位置的画布：拖动平移，滚轮缩放，点击标记打开图片或放大该组
+pub struct MapCanvas<'a> {
+    pub map: &'a MapView,
+    pub current: Option<&'a Path>,
+}
+
+impl canvas::Program<Message> for MapCanvas<'_> {
+    type State = Drag;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                *state = Drag {
+                    pressed: Some(position),
+                    last: position,
+                    moved: false,
+                };
+                (event::Status::Captured, None)
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let (Some(pressed), Some(position)) =
+                    (state.pressed, cursor.position_from(bounds.position()))
+                else {
+                    return (event::Status::Ignored, None);
+                };
+                if !state.moved && pressed.distance(position) < DRAG_THRESHOLD {
+                    return (event::Status::Captured, None);
+                }
+                state.moved = true;
+                let delta = position - state.last;
+                state.last = position;
+                (event::Status::Captured, Some(Message::MapPanned(delta)))
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let Some(pressed) = state.pressed.take() else {
+                    return (event::Status::Ignored, None);
+                };
+                if state.moved {
+                    return (event::Status::Captured, None);
+                }
+                // 点击单张图片的标记打开图片，点击一组时放大
+                let photos = self.map.photos.as_deref().unwrap_or_default();
+                let message =
+                    self.map
+                        .cluster_at(pressed)
+                        .map(|cluster| match cluster.photos.as_slice() {
+                            [index] => Message::PickImage(photos[*index].path.clone()),
+                            _ => Message::MapZoomed(2, cluster.position),
+                        });
+                (event::Status::Captured, message)
+            }
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } | mouse::ScrollDelta::Pixels { y, .. } => y,
+                };
+                if y == 0.0 {
+                    return (event::Status::Captured, None);
+                }
+                let steps = if y > 0.0 { 1 } else { -1 };
+                (
+                    event::Status::Captured,
+                    Some(Message::MapZoomed(steps, position)),
+                )
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(
+            Point::ORIGIN,
+            bounds.size(),
+            Color::from_rgb8(0xdd, 0xdd, 0xdd),
+        );
+
+        let size = Size::new(TILE_SIZE as f32, TILE_SIZE as f32);
+        let count = (1u64 << self.map.zoom) as f64;
+        for tile in self.map.visible_tiles() {
+            if let Some(Some(handle)) = self.map.tiles.get(&tile) {
+                let origin = self
+                    .map
+                    .to_screen(tile.x as f64 / count, tile.y as f64 / count);
+                frame.draw_image(Rectangle::new(origin, size), handle);
+            }
+        }
+
+        let current = self.map.photos.iter().flatten().position(|photo| {
+            self.current
+                .is_some_and(|current| current == photo.path.as_path())
+        });
+        for cluster in self.map.clusters() {
+            let selected = current.is_some_and(|current| cluster.photos.contains(&current));
+            let color = if selected {
+                Color::from_rgb8(0x34, 0x98, 0xdb)
+            } else if cluster.photos.len() == 1 {
+                Color::from_rgb8(0xe7, 0x4c, 0x3c)
+            } else {
+                Color::from_rgb8(0xe6, 0x7e, 0x22)
+            };
+            let circle = canvas::Path::circle(cluster.position, cluster.radius());
+            frame.fill(&circle, color);
+            frame.stroke(
+                &circle,
+                Stroke::default().with_width(2.0).with_color(Color::WHITE),
+            );
+            if cluster.photos.len() > 1 {
+                frame.fill_text(canvas::Text {
+                    content: cluster.photos.len().to_string(),
+                    position: cluster.position,
+                    color: Color::WHITE,
+                    size: 12.into(),
+                    horizontal_alignment: Horizontal::Center,
+                    vertical_alignment: Vertical::Center,
+                    ..canvas::Text::default()
+                });
+            }
+        }
+
+        // 瓦片的使用条款要求注明数据来源
+        let attribution = "© OpenStreetMap contributors";
+        frame.fill_rectangle(
+            Point::new(bounds.width - 168.0, bounds.height - 16.0),
+            Size::new(168.0, 16.0),
+            Color::from_rgba8(255, 255, 255, 0.7),
+        );
+        frame.fill_text(canvas::Text {
+            content: attribution.to_string(),
+            position: Point::new(bounds.width - 4.0, bounds.height - 8.0),
+            color: Color::from_rgb8(0x33, 0x33, 0x33),
+            size: 10.into(),
+            horizontal_alignment: Horizontal::Right,
+            vertical_alignment: Vertical::Center,
+            ..canvas::Text::default()
+        });
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if state.moved && state.pressed.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        match cursor.position_in(bounds) {
+            Some(position) if self.map.cluster_at(position).is_some() => {
+                mouse::Interaction::Pointer
+            }
+            Some(_) => mouse::Interaction::Grab,
+            None => mouse::Interaction::default(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use iced::widget::image::Handle;
+
+// 默认的内存上限：当前图片、缩放缓冲区、解码缓存和缩略图合计约 1 GB
+pub const DEFAULT_BUDGET: usize = 1024 * 1024 * 1024;
+
+/// 各部分大致占用的内存（字节）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryUsage {
+    pub current: usize,    // 当前图片及其编辑结果
+    pub buffers: usize,    // 缩放预览和最终渲染的字节缓冲区
+    pub decoded: usize,    // 最近解码图片的缓存
+    pub thumbnails: usize, // 缩略图缓存
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.current + self.buffers + self.decoded + self.thumbnails
+    }
+}
+
+/// 像素数据保存在内存中的图片句柄占用的字节数，按路径加载的句柄不计
+pub fn handle_bytes(handle: &Handle) -> usize {
+    match handle {
+        Handle::Rgba { pixels, .. } => pixels.len(),
+        Handle::Bytes(_, bytes) => bytes.len(),
+        Handle::Path(..) => 0,
+    }
+}
+
+/// 把字节数格式化为 KB/MB/GB
+pub fn format_bytes(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= KB * KB * KB {
+        format!("{:.1} GB", bytes / (KB * KB * KB))
+    } else if bytes >= KB * KB {
+        format!("{:.0} MB", bytes / (KB * KB))
+    } else {
+        format!("{:.0} KB", bytes / KB)
+    }
+}
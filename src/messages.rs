@@ -0,0 +1,477 @@
+use iced::widget::image::Handle;
+use iced::widget::text_editor;
+use iced::{Point, Vector, keyboard, window};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::adjustments::Adjustments;
+use crate::batch::{BatchEvent, BatchOptions, BatchScope};
+use crate::catalog::{AddedWithin, IndexEvent};
+use crate::channels::Channel;
+use crate::cloud::RemoteEdit;
+use crate::cloud_storage::{ObjectListing, OssConfig, Provider, S3Config, ShareExpiry};
+use crate::codes::DetectedCode;
+use crate::color_picker::ColorFormat;
+use crate::config::{Accent, Backdrop, SortOrder, ThemeMode, ThumbnailStrip};
+use crate::contact_sheet::{SheetOptions, SheetProgress};
+use crate::convert::ConvertProgress;
+use crate::crop::{AspectRatio, CropRect};
+use crate::exif_info::ExifInfo;
+use crate::export::{EncodeOptions, ExportProgress, SizeUnit};
+use crate::gallery::{GalleryOptions, GalleryProgress, GalleryStep};
+use crate::history::Rotation;
+use crate::i18n::{Language, tr};
+use crate::image_cache::Decoded;
+use crate::map::{GeoPhoto, Tile};
+use crate::metadata_search::{ImageMetadata, MetadataQuery};
+use crate::pipeline::ResamplingType;
+use crate::remote_control::Command;
+use crate::plugins::Exporter;
+use crate::remote_cache::CacheLimit;
+use crate::scan::{Rescan, ScanEvent};
+use crate::share::Outcome;
+use crate::script::ScriptOutput;
+use crate::sync::{ConflictPolicy, SyncEvent};
+use crate::thumbnails::FileInfo;
+use crate::timeline::{Month, TimelineSource};
+use crate::transfer_queue::TransferEvent;
+use crate::xmp_iptc::{DescriptiveMetadata, Flag};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectImage,
+    NoOp,
+    ExpandDirectory(PathBuf),
+    PickImage(PathBuf),
+    PickNextImage,
+    PickPreviousImage,
+    OpenResamplingBar,
+    SliderChanged(u8),
+    SliderReleased,                        // 新增：滑块释放事件
+    ResamplingTypeChanged(ResamplingType), // 新增：缩放算法改变
+    ImageResized(Vec<u8>, bool, u64),      // 用于接收缩放后的图片字节，bool表示是否是高质量渲染
+    PreviewResize(u64),                    // 拖动停顿后生成预览
+    LoadImage(PathBuf),                    // 用于加载图片
+    SpinnerTick,                           // 加载动画的下一帧
+    TogglePerfHud,                         // 显示/隐藏性能面板（F12）
+    PerfHudTick,                           // 刷新性能面板
+    ResetPerfStats,                        // 清空性能统计
+    LoadThumbnail(PathBuf),                // 用于加载缩略图
+    // 缩略图加载完成，附带图片尺寸和文件大小
+    ThumbnailLoaded(PathBuf, Handle, Option<FileInfo>, u64),
+    FinalizeDragging(u64),                 // 新增：完成拖动，执行高质量渲染
+    ToggleHandTool,                        // 切换手型工具
+    MousePressed(iced::mouse::Event),      // 鼠标按下事件
+    MouseReleased(iced::mouse::Event),     // 鼠标释放事件
+    MouseMoved(iced::Point),               // 鼠标移动事件
+    ToggleFullscreen,                      // 切换全屏模式
+    EscPressed,                            // ESC按键事件
+    UploadToCloud(PathBuf),                // 上传到云端
+    UploadSelected,                        // 上传选中的图片（没有多选时为当前图片）
+    ToggleSettingsDialog,                  // 打开/关闭设置对话框
+    AutoUploadToggled(bool),               // 开启/关闭自动上传
+    AddAutoUploadFolder,                   // 选择一个自动上传的文件夹
+    RemoveAutoUploadFolder(PathBuf),       // 不再自动上传这个文件夹
+    CloudProviderChanged(Provider),        // 选择云存储服务商
+    OssConfigChanged(OssConfig),           // 修改 OSS 连接参数
+    S3ConfigChanged(S3Config),             // 修改 S3 连接参数
+    ToggleInfoPanel,                       // 切换信息面板
+    OpenUrl(String),                       // 用系统默认程序打开链接
+    FilterByKeyword(String),               // 按关键词筛选图片库
+    ToggleSearchPanel,                     // 切换元数据搜索面板
+    SearchCameraChanged(String),           // 修改相机型号条件
+    SearchIsoMinChanged(String),           // 修改ISO下限
+    SearchIsoMaxChanged(String),           // 修改ISO上限
+    SearchFocalMinChanged(String),         // 修改焦距下限
+    SearchFocalMaxChanged(String),         // 修改焦距上限
+    SearchKeywordChanged(String),          // 修改关键词条件
+    ApplySearch,                           // 执行元数据搜索
+    SearchMetadataLoaded(MetadataQuery, Vec<(PathBuf, ImageMetadata)>), // 搜索所需的元数据读取完成
+    ClearSearch,                           // 取消元数据搜索
+    StripMetadataToggled(bool),            // 切换导出时是否去除元数据
+    MetadataArtistChanged(String),         // 修改作者
+    MetadataCopyrightChanged(String),      // 修改版权
+    MetadataDateChanged(String),           // 修改拍摄时间
+    ExportCopy,                            // 按元数据设置导出当前图片副本
+    CopyExported(Result<PathBuf, String>), // 副本导出完成，或失败的错误信息
+    ToggleRawOrientation,                  // 切换是否按EXIF方向显示
+    ToggleCropMode,                        // 进入/退出裁剪模式
+    CropAspectChanged(AspectRatio),        // 修改裁剪比例
+    CropSelectionChanged(Option<CropRect>), // 拖拽裁剪框
+    SaveCropAs,                            // 裁剪结果另存为新文件
+    OverwriteWithCrop,                     // 裁剪结果覆盖原文件（需确认）
+    ImageSaved(PathBuf),                   // 裁剪或编辑结果保存完成
+    SaveFailed(PathBuf, String),           // 裁剪或编辑结果保存失败
+    ToggleExportDialog,                    // 打开/关闭导出对话框
+    ExportWidthChanged(String),            // 修改导出宽度
+    ExportHeightChanged(String),           // 修改导出高度
+    ExportUnitChanged(SizeUnit),           // 切换像素/百分比
+    ExportKeepAspectToggled(bool),         // 切换是否保持比例
+    ExportResamplingChanged(ResamplingType), // 修改导出缩放算法
+    ExportEncodeChanged(EncodeOptions),    // 修改导出格式和质量
+    StartExport,                           // 选择文件并开始导出
+    ExportProgressed(ExportProgress),      // 导出进度更新
+    ModifiersChanged(keyboard::Modifiers), // 修饰键变化
+    ClearSelection,                        // 取消缩略图多选
+    ToggleConvertDialog,                   // 打开/关闭格式转换对话框
+    ConvertEncodeChanged(EncodeOptions),   // 修改转换格式和质量
+    ChooseConvertFolder,                   // 选择转换输出目录
+    ConvertNextToOriginals,                // 输出到原图所在目录
+    StartConvert,                          // 开始转换选中的图片
+    ConvertProgressed(ConvertProgress),    // 转换进度更新
+    ToggleAdjustPanel,                     // 打开/关闭调整面板
+    AdjustmentsChanged(Adjustments),       // 拖动调整滑块
+    AdjustmentsApplied(Adjustments, u64, Option<image::RgbImage>), // 后台调整计算完成
+    ResetAdjustments,                      // 恢复默认调整
+    ToggleBatchDialog,                     // 打开/关闭批处理对话框
+    BatchOptionsChanged(BatchOptions),     // 修改批处理选项
+    BatchScopeChanged(BatchScope),         // 切换批处理范围
+    BatchEncodeChanged(EncodeOptions),     // 修改批处理的输出格式
+    ChooseBatchWatermark,                  // 选择水印图片
+    RemoveBatchWatermark,                  // 不加水印
+    ChooseBatchFolder,                     // 选择批处理输出目录
+    BatchNextToOriginals,                  // 批处理输出到原图所在目录
+    QueueBatchJob,                         // 把当前选项加入任务队列
+    BatchJobEvent(u64, BatchEvent),        // 批处理任务进度
+    CancelBatchJob(u64),                   // 取消批处理任务
+    ToggleJobsPanel,                       // 打开/关闭任务面板
+    ClearFinishedJobs,                     // 清除已完成的任务
+    ToggleTransfersPanel,                  // 打开/关闭传输面板
+    PauseTransfer(u64),                    // 暂停一项传输
+    ResumeTransfer(u64),                   // 继续暂停的传输
+    CancelTransfer(u64),                   // 取消一项传输
+    RetryTransfer(u64),                    // 重试失败或取消的传输
+    ClearFinishedTransfers,                // 清除已结束的传输
+    ApplyCrop,                             // 把裁剪框作为一步编辑应用到图片
+    Rotate(Rotation),                      // 旋转 90 度
+    ToggleStraightenMode,                  // 进入/退出拉直模式
+    StraightenAngleChanged(f32),           // 拖动拉直角度滑块
+    ApplyStraighten,                       // 按当前角度拉直并裁掉空白边
+    Undo,                                  // 撤销上一步编辑
+    Redo,                                  // 重做撤销的编辑
+    Save,                                  // 编辑结果覆盖当前文件（需确认）
+    SaveAs,                                // 编辑结果另存为新文件
+    SavedAs(PathBuf),                      // 另存完成，切换到新文件
+    // 一批缩略图生成完成
+    ThumbnailsLoaded(u64, Vec<(PathBuf, Handle, Option<FileInfo>)>),
+    // 后台解码完成：像素和EXIF方向，或错误信息，带上解码时是否忽略了EXIF方向
+    ImageDecoded(PathBuf, bool, Result<Decoded, String>),
+    // 后台目录扫描的一批结果或结束
+    DirectoryScanned(u64, ScanEvent),
+    // 上传到云端完成，或失败的错误信息
+    UploadFinished(PathBuf, Result<(), String>),
+    // 提示到时间后自动关闭
+    DismissToast(u64),
+    // 云端文件夹的对象列表
+    CloudListed(PathBuf, Result<ObjectListing, String>),
+    // 传输队列中一项的进度或结果
+    TransferProgress(u64, TransferEvent),
+    // 添加一个与存储桶同步的文件夹
+    AddSyncFolder,
+    // 取消这个文件夹的同步
+    RemoveSyncFolder(PathBuf),
+    // 修改同步冲突时的处理方式
+    SyncConflictChanged(ConflictPolicy),
+    // 修改定时同步的间隔（分钟）
+    SyncIntervalChanged(u64),
+    // 立即同步全部文件夹（定时同步也发送这条消息）
+    SyncNow,
+    // 同步的进度或结果
+    SyncProgress(SyncEvent),
+    // 检查是否有到了重试时间的上传
+    RetryTransfersTick,
+    // 生成对象的预签名下载链接并复制到剪贴板
+    CopyShareLink(String),
+    // 修改分享链接的有效期
+    ShareExpiryChanged(ShareExpiry),
+    // 确认后删除云端对象
+    DeleteRemoteObject(String),
+    // 打开云端对象的重命名/移动对话框
+    RenameRemoteObject(String),
+    // 修改重命名的目标对象键
+    RemoteRenameChanged(String),
+    ConfirmRemoteEdit,
+    CancelRemoteEdit,
+    // 云端删除或重命名的结果
+    RemoteEdited(RemoteEdit, Result<(), String>),
+    // 修改上传限速（KB/s）
+    UploadLimitChanged(u64),
+    // 修改下载限速（KB/s）
+    DownloadLimitChanged(u64),
+    // 修改远程缓存的大小上限
+    CacheLimitChanged(CacheLimit),
+    // 删除全部远程缓存
+    ClearRemoteCache,
+    // 在智能文件夹中右键点击浏览记录，打开或关闭它的菜单（智能文件夹, 图片）
+    ToggleRecentMenu(PathBuf, PathBuf),
+    // 从浏览记录中移除
+    RemoveRecent(PathBuf),
+    // 固定到 Recents 顶部或取消固定
+    TogglePinRecent(PathBuf),
+    // 在系统文件管理器中打开图片所在的文件夹
+    OpenContainingFolder(PathBuf),
+    // 复制图片的完整路径
+    CopyPath(PathBuf),
+    // 清空 Recents（固定的记录保留）
+    ClearRecents,
+    // 选择加入图片目录的文件夹
+    AddCatalogFolder,
+    RemoveCatalogFolder(PathBuf),
+    // 在后台重新索引图片目录中的文件夹
+    ReindexCatalog,
+    // 索引的进度或结果
+    CatalogIndexed(IndexEvent),
+    // 切换搜索范围：整个图片目录或当前图片库
+    SearchLibraryToggled(bool),
+    // 打开或关闭文件名搜索栏
+    ToggleFindBar,
+    FindQueryChanged(String),
+    FindRegexToggled(bool),
+    // 在所有索引的文件夹中按文件名搜索
+    Find,
+    // 文件名搜索的结果（搜索条件, 匹配的图片）
+    FindFinished(String, Result<Vec<PathBuf>, String>),
+    // 关闭搜索结果，恢复原来的图片库
+    ClearFindResults,
+    // 修改“最近添加”的时间范围
+    RecentlyAddedChanged(AddedWithin),
+    // 最近添加的图片查找完成
+    RecentlyAddedLoaded(Result<Vec<PathBuf>, String>),
+    // 是否记住每张图片的缩放和平移
+    RememberViewToggled(bool),
+    // 切换设置对话框的分页
+    SettingsPageChanged(SettingsPage),
+    // 修改图片区域的背景色
+    BackdropChanged(Backdrop),
+    // 修改缩略图大小
+    ThumbnailSizeChanged(u32),
+    // 修改文件夹中图片的排列顺序
+    SortOrderChanged(SortOrder),
+    // 修改内存上限（MB）
+    MemoryBudgetChanged(usize),
+    // 选择目录树中显示的起始文件夹，None 时恢复为主目录
+    PickStartFolder,
+    ResetStartFolder,
+    // 选择浅色、深色或跟随系统的主题
+    ThemeChanged(ThemeMode),
+    // 顶栏中的按钮：在浅色和深色之间切换
+    ToggleTheme,
+    // 修改主按钮、选中项和滑块使用的强调色
+    AccentChanged(Accent),
+    // 切换界面语言
+    LanguageChanged(Language),
+    // 在某个窗口中按下的键，由快捷键表决定执行的动作
+    KeyPressed(window::Id, keyboard::Key, keyboard::Modifiers),
+    // 删除当前图片（需确认）
+    DeleteImage,
+    // 诊断面板：最近的日志
+    ToggleLogPanel,
+    LogTick,
+    CopyLog,
+    ClearLog,
+    // 幻灯片放映切换到下一张
+    SlideshowTick,
+    // 之后启动的实例转发过来的路径
+    OpenFromOtherInstance(PathBuf),
+    // 在新的查看窗口中打开图片或文件夹
+    OpenInNewWindow(PathBuf),
+    // 查看窗口中切换图片
+    ViewerNext(window::Id),
+    ViewerPrevious(window::Id),
+    // 查看窗口中打开的文件夹的扫描结果
+    ViewerScanned(window::Id, ScanEvent),
+    // 窗口已关闭，主窗口关闭时退出程序
+    WindowClosed(window::Id),
+    // 标签页：新建（复制当前标签页）、关闭、切换
+    NewTab,
+    CloseTab(usize),
+    SelectTab(usize),
+    // 展开、折叠或隐藏缩略图栏
+    ThumbnailStripChanged(ThumbnailStrip),
+    // 右键菜单：设为壁纸、从图片库移除、重命名、新建文件夹、固定和刷新文件夹
+    SetWallpaper(PathBuf),
+    WallpaperSet(Result<(), String>),
+    RemoveFromLibrary(PathBuf),
+    RenameFile(PathBuf),
+    FileRenamed(PathBuf, PathBuf, Result<(), String>),
+    NewFolder(PathBuf),
+    NamePromptChanged(String),
+    ConfirmNamePrompt,
+    CancelNamePrompt,
+    TogglePinFolder(PathBuf),
+    RefreshFolder(PathBuf),
+    // 插件：启用或停用、重新扫描插件目录、打开插件目录、交给插件导出当前图片
+    PluginToggled(String, bool),
+    ReloadPlugins,
+    OpenPluginsFolder,
+    PluginExport(Exporter),
+    PluginExported(&'static str, Result<(), String>),
+    // 脚本：打开/关闭对话框、编辑、运行、运行结束、打开和保存脚本文件
+    ToggleScriptDialog,
+    ScriptEdited(text_editor::Action),
+    RunScript,
+    ScriptFinished(ScriptOutput),
+    OpenScript,
+    SaveScript,
+    // 用系统的分享界面分享图片，不支持时上传到云存储并复制分享链接
+    ShareImage(PathBuf),
+    ShareFinished(PathBuf, Result<Outcome, String>),
+    // 通过 DBus 或命名管道收到的控制命令
+    RemoteCommand(Command),
+    // 联系表：打开/关闭对话框、修改版式、选择保存位置并生成、生成进度
+    ToggleContactSheetDialog,
+    ContactSheetChanged(SheetOptions),
+    ExportContactSheet,
+    ContactSheetProgressed(SheetProgress),
+    // 网页相册向导：打开、关闭、切换步骤、修改选项、选择输出文件夹、开始导出、导出进度、在浏览器中打开
+    OpenGalleryWizard(PathBuf),
+    CloseGalleryWizard,
+    GalleryStepChanged(GalleryStep),
+    GalleryOptionsChanged(GalleryOptions),
+    GalleryEncodeChanged(EncodeOptions),
+    ChooseGalleryFolder,
+    ExportGallery,
+    GalleryProgressed(GalleryProgress),
+    OpenGallery,
+    // 触控板和触摸屏手势：图片上的滚动、触摸事件、手势或按键停下后高质量渲染
+    ViewerScrolled(iced::mouse::ScrollDelta),
+    Touched(iced::touch::Event),
+    ViewSettled(u64),
+    // 设置：滚轮切换图片
+    WheelNavigationToggled(bool),
+    // 图片上的左键单击（双击切换全屏）和中键单击（恢复默认的缩放和平移）
+    ViewerPressed,
+    ViewerMiddlePressed,
+    // 临时平移：松开按住的按键或中键
+    KeyReleased(keyboard::Key, keyboard::Modifiers),
+    EndTemporaryPan,
+    // 缩放到 100% 前测得的图片查看区域
+    ViewerMeasured(Option<iced::Rectangle>),
+    // 锁定视图后切换图片时保持缩放和平移
+    ViewLockToggled(bool),
+    // 只显示一个通道
+    ChannelChanged(Channel),
+    // 闪烁比较：在选中的两张图片之间交替显示
+    ToggleBlink,
+    BlinkDecoded([PathBuf; 2], Result<[Arc<image::RgbImage>; 2], String>),
+    BlinkTick,
+    PauseBlink,
+    BlinkIntervalChanged(u64),
+    // 显示 DDS 或 KTX 纹理的另一层，在后台解码完成后替换显示的图片
+    MipLevelChanged(usize),
+    MipLevelDecoded(PathBuf, usize, Result<Arc<image::RgbImage>, String>),
+    // 动画图片：逐帧查看和导出为 PNG
+    FramesDecoded(PathBuf, Result<Vec<Arc<image::RgbaImage>>, String>),
+    FrameSelected(usize),
+    ExportFrame,
+    ExportAllFrames,
+    FramesExported(Result<PathBuf, String>),
+    // 识别图片中的二维码和条形码，复制或打开识别出的内容
+    ScanCodes,
+    CodesScanned(PathBuf, Vec<DetectedCode>),
+    CopyCode(String),
+    // 文字识别：复制当前图片中的文字，设置中选择识别的语言
+    ExtractText,
+    TextExtracted(Result<String, String>),
+    OcrLanguagesListed(Result<Vec<String>, String>),
+    OcrLanguageToggled(String),
+    // 在图片目录中查找与当前图片相似的图片，按相似程度排列显示
+    FindSimilar,
+    SimilarFound(PathBuf, Result<Vec<PathBuf>, String>),
+    // 地图面板：显示当前文件夹中有 GPS 位置的图片
+    ToggleMap,
+    MapLocated(Vec<GeoPhoto>),
+    MapPanned(Vector),
+    MapZoomed(i32, Point), // 放大的级数（负数为缩小）、保持不动的画布坐标
+    MapTileLoaded(Tile, Result<Handle, String>),
+    // 时间线：按拍摄日期分组浏览图片库或整个图片目录
+    ToggleTimeline,
+    TimelineSourceChanged(TimelineSource),
+    TimelineBuilt(TimelineSource, Result<Vec<Month>, String>),
+    TimelineMonthSelected(usize),
+    TimelineImagePicked(PathBuf),
+    // 取色器：点击图片复制颜色，保留最近的颜色并可导出为调色板
+    ToggleColorPicker,
+    ColorHovered(Option<[u8; 3]>),
+    ColorPicked([u8; 3]),
+    CopyColor([u8; 3]),
+    ColorFormatChanged(ColorFormat),
+    ClearPickedColors,
+    ExportPalette,
+    // 缩略图大小滑块
+    ThumbnailSizeDragged(u32),
+    ThumbnailSizeReleased,
+    // 缩略图悬停预览
+    ThumbnailHovered(PathBuf),
+    ThumbnailUnhovered(PathBuf),
+    HoverPreviewDelayed(PathBuf),
+    HoverPreviewLoaded(PathBuf, Option<Handle>),
+    // 多选工具栏中的批量操作
+    DeleteSelected,
+    MoveSelected,
+    SelectionMoved(Vec<(PathBuf, Result<PathBuf, String>)>),
+    ExportSelection,
+    BatchTagChanged(String),
+    TagSelected,
+    TagsWritten(String, Vec<(PathBuf, Result<(), String>)>),
+    // 切换是否显示隐藏的文件和文件夹
+    ShowHiddenToggled(bool),
+    // 重新读取文件夹，与目录树和图片库合并
+    RefreshCollection,
+    FolderRescanned(PathBuf, Result<Rescan, String>),
+    CheckFolder,
+    // 挑片：标记选中或排除，只显示有标记的图片，处理排除的图片
+    SetFlag(Option<Flag>),
+    ShowFlagged(Flag),
+    FlaggedFound(Flag, Vec<PathBuf>),
+    MoveRejects,
+    DeleteRejects,
+    RejectsFound(RejectAction, Vec<PathBuf>),
+    // 后台读取的当前图片信息
+    ExifInfoLoaded(PathBuf, Option<ExifInfo>),
+    DescriptiveMetadataLoaded(PathBuf, Option<DescriptiveMetadata>),
+    PluginMetadataLoaded(PathBuf, Vec<(String, String)>),
+    // 挑片标记在后台写入侧车文件之后
+    FlagsWritten(Option<Flag>, Vec<(PathBuf, Result<(), String>)>),
+    // 缩小解码的图片在后台按原尺寸重新解码，带上请求的代数
+    FullResolutionDecoded(PathBuf, u64, Result<Decoded, String>),
+    // 在后台解码的透明通道，带上解码时是否忽略了EXIF方向
+    AlphaDecoded(PathBuf, bool, Result<image::RgbImage, String>),
+}
+
+// 在后台找出排除的图片之后执行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectAction {
+    Move,
+    Delete,
+}
+
+// 设置对话框的分页
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsPage {
+    General,
+    Cloud,
+    Sync,
+    Library,
+    Plugins,
+}
+
+impl SettingsPage {
+    pub const ALL: [SettingsPage; 5] = [
+        SettingsPage::General,
+        SettingsPage::Cloud,
+        SettingsPage::Sync,
+        SettingsPage::Library,
+        SettingsPage::Plugins,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SettingsPage::General => tr("General"),
+            SettingsPage::Cloud => tr("Cloud"),
+            SettingsPage::Sync => tr("Sync"),
+            SettingsPage::Library => tr("Library"),
+            SettingsPage::Plugins => tr("Plugins"),
+        }
+    }
+}
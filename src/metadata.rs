@@ -0,0 +1,296 @@
+use exif::experimental::Writer;
+use exif::{Field, In, Reader, Tag, Value};
+use img_parts::jpeg::Jpeg;
+use img_parts::png::Png;
+use img_parts::webp::WebP;
+use img_parts::{Bytes, DynImage, ImageEXIF};
+use std::error::Error;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+// 导出或上传时可修改的基本字段，空字符串表示保留原值
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataEdits {
+    pub artist: String,
+    pub copyright: String,
+    pub date_time: String, // 拍摄时间，格式为 "YYYY:MM:DD HH:MM:SS"
+}
+
+impl MetadataEdits {
+    pub fn is_empty(&self) -> bool {
+        self.artist.trim().is_empty()
+            && self.copyright.trim().is_empty()
+            && self.date_time.trim().is_empty()
+    }
+}
+
+// 导出或上传文件时如何处理元数据
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum MetadataPolicy {
+    #[default]
+    Keep,
+    StripAll,            // 去除全部元数据（隐私保护）
+    Edit(MetadataEdits), // 修改部分 EXIF 字段，其余保留
+}
+
+/// 复制 src 到 dst，同时按照 policy 处理元数据（只改写元数据段，不重新编码像素）
+pub fn export_with_metadata(
+    src: &Path,
+    dst: &Path,
+    policy: &MetadataPolicy,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = fs::read(src)?;
+    let output = apply_metadata_policy(bytes, policy)?;
+    fs::write(dst, output)?;
+    Ok(())
+}
+
+/// 按照 policy 处理一张已编码图片的元数据，支持 JPEG、PNG 和 WebP
+/// 其他格式只在 Keep 时原样返回
+pub fn apply_metadata_policy(
+    bytes: Vec<u8>,
+    policy: &MetadataPolicy,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    match policy {
+        MetadataPolicy::Keep => Ok(bytes),
+        MetadataPolicy::StripAll => strip_all(bytes),
+        MetadataPolicy::Edit(edits) if edits.is_empty() => Ok(bytes),
+        MetadataPolicy::Edit(edits) => edit_fields(bytes, edits),
+    }
+}
+
+fn strip_all(bytes: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let image = DynImage::from_bytes(Bytes::from(bytes))?.ok_or("unsupported image format")?;
+    let stripped: DynImage = match image {
+        DynImage::Jpeg(mut jpeg) => {
+            strip_jpeg(&mut jpeg);
+            jpeg.into()
+        }
+        DynImage::Png(mut png) => {
+            strip_png(&mut png);
+            png.into()
+        }
+        DynImage::WebP(mut webp) => {
+            strip_webp(&mut webp);
+            webp.into()
+        }
+    };
+    Ok(stripped.encoder().bytes().to_vec())
+}
+
+// 保留 ICC 色彩配置（APP2），去掉 EXIF/XMP（APP1）、IPTC（APP13）和注释段
+fn strip_jpeg(jpeg: &mut Jpeg) {
+    jpeg.segments_mut().retain(|segment| {
+        let marker = segment.marker();
+        !(marker == 0xE1 || marker == 0xED || marker == 0xFE)
+    });
+}
+
+fn strip_png(png: &mut Png) {
+    for kind in [*b"eXIf", *b"tEXt", *b"zTXt", *b"iTXt", *b"tIME"] {
+        png.remove_chunks_by_type(kind);
+    }
+}
+
+fn strip_webp(webp: &mut WebP) {
+    webp.set_exif(None);
+    webp.remove_chunks_by_id(*b"XMP ");
+}
+
+fn edit_fields(bytes: Vec<u8>, edits: &MetadataEdits) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut image = DynImage::from_bytes(Bytes::from(bytes))?.ok_or("unsupported image format")?;
+
+    // 读取已有的 EXIF，只保留主图像 IFD 中的字段（缩略图 IFD 会被丢弃）
+    let existing = image
+        .exif()
+        .and_then(|raw| Reader::new().read_raw(raw.to_vec()).ok());
+    let little_endian = existing.as_ref().is_none_or(|exif| exif.little_endian());
+
+    let ascii = |tag: Tag, value: &str| Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![value.trim().as_bytes().to_vec()]),
+    };
+    let mut edited = Vec::new();
+    if !edits.artist.trim().is_empty() {
+        edited.push(ascii(Tag::Artist, &edits.artist));
+    }
+    if !edits.copyright.trim().is_empty() {
+        edited.push(ascii(Tag::Copyright, &edits.copyright));
+    }
+    if !edits.date_time.trim().is_empty() {
+        edited.push(ascii(Tag::DateTimeOriginal, &edits.date_time));
+        edited.push(ascii(Tag::DateTime, &edits.date_time));
+    }
+
+    let mut writer = Writer::new();
+    if let Some(exif) = &existing {
+        for field in exif.fields() {
+            if field.ifd_num == In::PRIMARY && !edited.iter().any(|e| e.tag == field.tag) {
+                writer.push_field(field);
+            }
+        }
+    }
+    for field in &edited {
+        writer.push_field(field);
+    }
+
+    let mut buf = Cursor::new(Vec::new());
+    writer.write(&mut buf, little_endian)?;
+    image.set_exif(Some(Bytes::from(buf.into_inner())));
+    Ok(image.encoder().bytes().to_vec())
+}
+
+/// 校验拍摄时间格式是否为 EXIF 要求的 "YYYY:MM:DD HH:MM:SS"
+pub fn is_valid_exif_date(value: &str) -> bool {
+    chrono::NaiveDateTime::parse_from_str(value.trim(), "%Y:%m:%d %H:%M:%S").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::Exif;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::codecs::png::PngEncoder;
+    use image::{ExtendedColorType, ImageEncoder, RgbImage};
+    use img_parts::ImageICC;
+    use img_parts::jpeg::JpegSegment;
+    use img_parts::png::PngChunk;
+
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn exif_bytes(fields: &[Field], little_endian: bool) -> Bytes {
+        let mut writer = Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut buf = Cursor::new(Vec::new());
+        writer.write(&mut buf, little_endian).unwrap();
+        Bytes::from(buf.into_inner())
+    }
+
+    fn read_exif(bytes: &[u8]) -> Exif {
+        let image = DynImage::from_bytes(Bytes::copy_from_slice(bytes))
+            .unwrap()
+            .unwrap();
+        Reader::new()
+            .read_raw(image.exif().unwrap().to_vec())
+            .unwrap()
+    }
+
+    fn ascii_value(exif: &Exif, tag: Tag) -> String {
+        match &exif.get_field(tag, In::PRIMARY).unwrap().value {
+            Value::Ascii(values) => String::from_utf8(values[0].clone()).unwrap(),
+            value => panic!("unexpected value {:?}", value),
+        }
+    }
+
+    // 带有 EXIF、XMP、IPTC、注释和 ICC 色彩配置的 JPEG
+    fn sample_jpeg(exif: Bytes) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        JpegEncoder::new(&mut encoded)
+            .write_image(RgbImage::new(8, 8).as_raw(), 8, 8, ExtendedColorType::Rgb8)
+            .unwrap();
+        let mut jpeg = Jpeg::from_bytes(Bytes::from(encoded)).unwrap();
+        jpeg.set_exif(Some(exif));
+        jpeg.set_icc_profile(Some(Bytes::from_static(b"icc profile")));
+        let segments = jpeg.segments_mut();
+        segments.insert(
+            1,
+            JpegSegment::new_with_contents(
+                0xE1,
+                Bytes::from_static(b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>"),
+            ),
+        );
+        segments.insert(
+            1,
+            JpegSegment::new_with_contents(0xED, Bytes::from_static(b"Photoshop 3.0\0iptc")),
+        );
+        segments.insert(
+            1,
+            JpegSegment::new_with_contents(0xFE, Bytes::from_static(b"comment")),
+        );
+        jpeg.encoder().bytes().to_vec()
+    }
+
+    #[test]
+    fn strip_all_removes_jpeg_metadata_but_keeps_icc() {
+        let exif = exif_bytes(&[ascii(Tag::Artist, "Someone")], true);
+        let sample = sample_jpeg(exif);
+        let jpeg = Jpeg::from_bytes(Bytes::from(sample.clone())).unwrap();
+        assert_eq!(jpeg.segments_by_marker(0xE1).count(), 2);
+        let stripped = apply_metadata_policy(sample, &MetadataPolicy::StripAll).unwrap();
+
+        let jpeg = Jpeg::from_bytes(Bytes::from(stripped.clone())).unwrap();
+        for marker in [0xE1, 0xED, 0xFE] {
+            assert!(
+                jpeg.segment_by_marker(marker).is_none(),
+                "{:#X} kept",
+                marker
+            );
+        }
+        assert_eq!(jpeg.icc_profile(), Some(Bytes::from_static(b"icc profile")));
+        assert!(image::load_from_memory(&stripped).is_ok());
+    }
+
+    #[test]
+    fn strip_all_removes_png_chunks() {
+        let mut encoded = Vec::new();
+        PngEncoder::new(&mut encoded)
+            .write_image(RgbImage::new(8, 8).as_raw(), 8, 8, ExtendedColorType::Rgb8)
+            .unwrap();
+        let mut png = Png::from_bytes(Bytes::from(encoded)).unwrap();
+        png.set_exif(Some(exif_bytes(&[ascii(Tag::Artist, "Someone")], true)));
+        let end = png.chunks().len() - 1;
+        png.chunks_mut().insert(
+            end,
+            PngChunk::new(*b"tEXt", Bytes::from_static(b"Comment\0secret")),
+        );
+
+        let stripped =
+            apply_metadata_policy(png.encoder().bytes().to_vec(), &MetadataPolicy::StripAll)
+                .unwrap();
+        let png = Png::from_bytes(Bytes::from(stripped.clone())).unwrap();
+        assert!(png.chunk_by_type(*b"eXIf").is_none());
+        assert!(png.chunk_by_type(*b"tEXt").is_none());
+        assert!(image::load_from_memory(&stripped).is_ok());
+    }
+
+    #[test]
+    fn edit_replaces_fields_and_keeps_the_rest() {
+        // 大端字节序的原始 EXIF
+        let exif = exif_bytes(
+            &[
+                ascii(Tag::Make, "Canon"),
+                ascii(Tag::Artist, "Old artist"),
+                ascii(Tag::Copyright, "Old copyright"),
+                ascii(Tag::DateTimeOriginal, "2001:02:03 04:05:06"),
+            ],
+            false,
+        );
+        let edits = MetadataEdits {
+            artist: "New artist".to_string(),
+            copyright: "New copyright".to_string(),
+            date_time: "2024:05:06 07:08:09".to_string(),
+        };
+        let edited =
+            apply_metadata_policy(sample_jpeg(exif), &MetadataPolicy::Edit(edits)).unwrap();
+
+        let exif = read_exif(&edited);
+        assert!(!exif.little_endian());
+        assert_eq!(ascii_value(&exif, Tag::Make), "Canon");
+        assert_eq!(ascii_value(&exif, Tag::Artist), "New artist");
+        assert_eq!(ascii_value(&exif, Tag::Copyright), "New copyright");
+        assert_eq!(
+            ascii_value(&exif, Tag::DateTimeOriginal),
+            "2024:05:06 07:08:09"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use crate::exif_info::{self, ExifInfo};
+use crate::xmp_iptc::{self, DescriptiveMetadata};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// 一张图片可用于搜索的全部元数据
+#[derive(Debug, Clone, Default)]
+pub struct ImageMetadata {
+    pub exif: Option<ExifInfo>,
+    pub descriptive: Option<DescriptiveMetadata>,
+}
+
+impl ImageMetadata {
+    pub fn load(path: &Path) -> Self {
+        ImageMetadata {
+            exif: exif_info::read_exif(path),
+            descriptive: xmp_iptc::read_descriptive_metadata(path),
+        }
+    }
+}
+
+// 按需填充的元数据缓存，只有搜索用到的图片才会被读取
+#[derive(Debug, Default)]
+pub struct MetadataCache {
+    entries: HashMap<PathBuf, ImageMetadata>,
+}
+
+impl MetadataCache {
+    pub fn get(&self, path: &Path) -> Option<&ImageMetadata> {
+        self.entries.get(path)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, metadata: ImageMetadata) {
+        self.entries.insert(path, metadata);
+    }
+
+    // 文件或侧车文件被修改后重新读取
+    pub fn remove(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    // 文件改名后缓存的元数据跟着换到新的路径
+    pub fn rename(&mut self, old: &Path, new: PathBuf) {
+        if let Some(metadata) = self.entries.remove(old) {
+            self.entries.insert(new, metadata);
+        }
+    }
+
+    // 返回尚未缓存的图片
+    pub fn missing(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        paths
+            .iter()
+            .filter(|p| !self.entries.contains_key(*p))
+            .cloned()
+            .collect()
+    }
+}
+
+// 元数据搜索条件，输入框中的原始文本，空字符串表示不限
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataQuery {
+    pub camera: String,
+    pub iso_min: String,
+    pub iso_max: String,
+    pub focal_min: String,
+    pub focal_max: String,
+    pub keyword: String,
+}
+
+impl MetadataQuery {
+    pub fn keyword(keyword: String) -> Self {
+        MetadataQuery {
+            keyword,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.summary().is_empty()
+    }
+
+    // 用于显示在筛选标签上的简短描述
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.camera.trim().is_empty() {
+            parts.push(format!("📷 {}", self.camera.trim()));
+        }
+        if let Some(range) = range_label(&self.iso_min, &self.iso_max) {
+            parts.push(format!("ISO {}", range));
+        }
+        if let Some(range) = range_label(&self.focal_min, &self.focal_max) {
+            parts.push(format!("{} mm", range));
+        }
+        if !self.keyword.trim().is_empty() {
+            parts.push(format!("🏷 {}", self.keyword.trim()));
+        }
+        parts.join(" · ")
+    }
+
+    pub fn matches(&self, metadata: &ImageMetadata) -> bool {
+        let exif = metadata.exif.as_ref();
+
+        let camera = self.camera.trim().to_lowercase();
+        if !camera.is_empty() {
+            let name = exif
+                .map(|e| {
+                    [e.camera_make.as_deref(), e.camera_model.as_deref()]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .to_lowercase()
+                })
+                .unwrap_or_default();
+            if !name.contains(&camera) {
+                return false;
+            }
+        }
+
+        let iso = exif.and_then(|e| e.iso).map(|iso| iso as f64);
+        if !in_range(iso, &self.iso_min, &self.iso_max) {
+            return false;
+        }
+
+        let focal = exif.and_then(|e| e.focal_length_mm);
+        if !in_range(focal, &self.focal_min, &self.focal_max) {
+            return false;
+        }
+
+        let keyword = self.keyword.trim();
+        if !keyword.is_empty()
+            && !metadata
+                .descriptive
+                .as_ref()
+                .is_some_and(|d| d.has_keyword(keyword))
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+pub fn parse_bound(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok()
+}
+
+// 设置了上下限时，缺少该字段的图片视为不匹配
+fn in_range(value: Option<f64>, min: &str, max: &str) -> bool {
+    let (min, max) = (parse_bound(min), parse_bound(max));
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    let Some(value) = value else {
+        return false;
+    };
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+fn range_label(min: &str, max: &str) -> Option<String> {
+    match (parse_bound(min), parse_bound(max)) {
+        (None, None) => None,
+        (Some(min), None) => Some(format!("≥ {}", min)),
+        (None, Some(max)) => Some(format!("≤ {}", max)),
+        (Some(min), Some(max)) => Some(format!("{}–{}", min, max)),
+    }
+}
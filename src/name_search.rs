@@ -0,0 +1,59 @@
+use regex::{Regex, RegexBuilder};
+use std::path::{Path, PathBuf};
+
+use crate::catalog::{self, Catalog};
+
+/// 文件名搜索条件：不区分大小写的子串，或正则表达式
+pub enum NamePattern {
+    Substring(String), // 已经转为小写
+    Regex(Regex),
+}
+
+impl NamePattern {
+    pub fn parse(text: &str, regex: bool) -> Result<NamePattern, String> {
+        if regex {
+            RegexBuilder::new(text)
+                .case_insensitive(true)
+                .build()
+                .map(NamePattern::Regex)
+                .map_err(|e| e.to_string())
+        } else {
+            Ok(NamePattern::Substring(text.to_lowercase()))
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Substring(text) => name.to_lowercase().contains(text),
+            NamePattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// 在后台按文件名搜索：有图片目录时查询目录，否则遍历 roots 中的文件夹
+pub async fn find(
+    pattern: NamePattern,
+    db: Option<PathBuf>,
+    roots: Vec<PathBuf>,
+) -> Result<Vec<PathBuf>, String> {
+    tokio::task::spawn_blocking(move || match db {
+        Some(db) => Catalog::open(&db)?.find_names(&pattern),
+        None => {
+            let mut files = Vec::new();
+            for root in &roots {
+                catalog::walk(root, &mut files);
+            }
+            files.retain(|path| file_name(path).is_some_and(|name| pattern.matches(name)));
+            // 文件夹之间可能互相包含
+            files.sort();
+            files.dedup();
+            Ok(files)
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()
+}
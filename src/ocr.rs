@@ -0,0 +1,75 @@
+use image::{DynamicImage, ImageFormat, RgbImage};
+use std::io::{Cursor, ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+// 短边小于这个尺寸的图片（多为截图）放大两倍再识别，Tesseract 对小字号的识别率较低
+const UPSCALE_BELOW: u32 = 1000;
+
+/// 调用系统安装的 Tesseract 识别图片中的文字，languages 为 Tesseract 的语言代码，多个用 + 连接
+pub fn recognize(img: &RgbImage, languages: &str) -> Result<String, String> {
+    let mut img = DynamicImage::ImageRgb8(img.clone());
+    if img.width().min(img.height()) < UPSCALE_BELOW {
+        img = img.resize(
+            img.width() * 2,
+            img.height() * 2,
+            image::imageops::FilterType::CatmullRom,
+        );
+    }
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout", "-l", languages])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(not_installed)?;
+    // Tesseract 读完整张图片后才开始输出，先写完再读取不会阻塞
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&png).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(message
+            .lines()
+            .last()
+            .unwrap_or_default()
+            .trim()
+            .to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 已安装的语言数据，不包括只用于判断方向的 osd
+pub fn languages() -> Result<Vec<String>, String> {
+    let output = Command::new("tesseract")
+        .arg("--list-langs")
+        .output()
+        .map_err(not_installed)?;
+    // 第一行是说明，之后每行一个语言代码；旧版本输出到 stderr
+    let listing = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    let mut languages: Vec<String> = String::from_utf8_lossy(&listing)
+        .lines()
+        .skip(1)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty() && line != "osd")
+        .collect();
+    languages.sort();
+    Ok(languages)
+}
+
+// 找不到命令时提示安装 Tesseract
+fn not_installed(e: std::io::Error) -> String {
+    if e.kind() == ErrorKind::NotFound {
+        "Tesseract is not installed".to_string()
+    } else {
+        e.to_string()
+    }
+}
@@ -0,0 +1,235 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha1::Sha1;
+use std::path::PathBuf;
+
+use crate::cloud_storage::{self, CloudStorage, ObjectListing, OssConfig};
+use crate::s3_client::uri_encode;
+use crate::throttle;
+
+/// 阿里云 OSS，请求使用 OSS 的 HMAC-SHA1 签名
+pub struct OssClient {
+    config: OssConfig,
+    scheme: String, // http 或 https
+    host: String,   // bucket.endpoint
+    http: reqwest::Client,
+}
+
+impl OssClient {
+    pub fn new(config: OssConfig) -> Result<Self, String> {
+        let endpoint = config.endpoint.trim().trim_end_matches('/');
+        let (scheme, endpoint) = match endpoint.split_once("://") {
+            Some((scheme, endpoint)) => (scheme.to_string(), endpoint),
+            None => ("https".to_string(), endpoint),
+        };
+        if endpoint.is_empty() {
+            return Err("Invalid OSS endpoint".to_string());
+        }
+        let host = format!("{}.{}", config.bucket.trim(), endpoint);
+        Ok(OssClient {
+            config,
+            scheme,
+            host,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// 发送签名后的请求，失败的状态码也原样返回
+    async fn execute(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        headers: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<(StatusCode, reqwest::header::HeaderMap, Vec<u8>), String> {
+        let date = chrono::Utc::now()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        let content_type = headers
+            .iter()
+            .find(|(name, _)| *name == "content-type")
+            .map_or("", |(_, value)| value.as_str());
+        // x-oss- 开头的请求头按名称排序后参与签名
+        let mut oss_headers: Vec<_> = headers
+            .iter()
+            .filter(|(name, _)| name.starts_with("x-oss-"))
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        oss_headers.sort();
+        // 签名的资源为 /bucket/key，列表请求的参数都不是子资源，不参与签名
+        let string_to_sign = format!(
+            "{}\n\n{}\n{}\n{}/{}/{}",
+            method.as_str(),
+            content_type,
+            date,
+            oss_headers.concat(),
+            self.config.bucket.trim(),
+            key
+        );
+        let mut mac = Hmac::<Sha1>::new_from_slice(self.config.access_key_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(string_to_sign.as_bytes());
+        let signature = BASE64.encode(mac.finalize().into_bytes());
+        let authorization = format!("OSS {}:{}", self.config.access_key_id, signature);
+
+        let mut url = format!("{}://{}/{}", self.scheme, self.host, uri_encode(key, false));
+        if !query.is_empty() {
+            let query = query
+                .iter()
+                .map(|(name, value)| {
+                    format!("{}={}", uri_encode(name, true), uri_encode(value, true))
+                })
+                .collect::<Vec<_>>()
+                .join("&");
+            url.push('?');
+            url.push_str(&query);
+        }
+        let mut request = self
+            .http
+            .request(method, url)
+            .header("date", date)
+            .header("authorization", authorization);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        // 分块发送的请求体没有长度，需要显式设置 Content-Length
+        if !body.is_empty() {
+            request = request.header("content-length", body.len());
+        }
+        let response = request
+            .body(throttle::upload_body(body))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let response_headers = response.headers().clone();
+        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        Ok((status, response_headers, bytes.to_vec()))
+    }
+
+    /// 发送签名后的请求，返回响应头和响应体
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        headers: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<(reqwest::header::HeaderMap, Vec<u8>), String> {
+        let (status, headers, body) = self.execute(method, key, query, headers, body).await?;
+        if !status.is_success() {
+            return Err(cloud_storage::http_error(status.as_u16(), &body));
+        }
+        Ok((headers, body))
+    }
+}
+
+impl CloudStorage for OssClient {
+    fn presigned_url(&self, key: &str, expires: u64) -> Result<String, String> {
+        let expires = chrono::Utc::now().timestamp() as u64 + expires;
+        let string_to_sign = format!(
+            "GET\n\n\n{}\n/{}/{}",
+            expires,
+            self.config.bucket.trim(),
+            key
+        );
+        let mut mac = Hmac::<Sha1>::new_from_slice(self.config.access_key_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(string_to_sign.as_bytes());
+        let signature = BASE64.encode(mac.finalize().into_bytes());
+        Ok(format!(
+            "{}://{}/{}?OSSAccessKeyId={}&Expires={}&Signature={}",
+            self.scheme,
+            self.host,
+            uri_encode(key, false),
+            uri_encode(&self.config.access_key_id, true),
+            expires,
+            uri_encode(&signature, true)
+        ))
+    }
+
+    async fn upload_object(&self, path: PathBuf, key: String) -> Result<(), String> {
+        let body = tokio::fs::read(&path).await.map_err(|e| e.to_string())?;
+        let content_type = cloud_storage::content_type(&key).to_string();
+        self.send(
+            Method::PUT,
+            &key,
+            &[],
+            &[("content-type", content_type)],
+            body,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn list_objects(&self, prefix: String) -> Result<ObjectListing, String> {
+        let mut listing = ObjectListing::default();
+        let mut marker = String::new();
+        // 每次最多返回 1000 个对象，被截断时从 NextMarker 继续
+        loop {
+            let query = [
+                ("delimiter", "/"),
+                ("marker", marker.as_str()),
+                ("max-keys", "1000"),
+                ("prefix", prefix.as_str()),
+            ];
+            let (_, body) = self.send(Method::GET, "", &query, &[], Vec::new()).await?;
+            let xml = String::from_utf8(body).map_err(|e| e.to_string())?;
+            match cloud_storage::parse_listing(&xml, &prefix, &mut listing, "NextMarker") {
+                Some(next) => marker = next,
+                None => break,
+            }
+        }
+        Ok(listing)
+    }
+
+    async fn object_size(&self, key: String) -> Result<u64, String> {
+        let (headers, _) = self.send(Method::HEAD, &key, &[], &[], Vec::new()).await?;
+        headers
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| "Missing Content-Length".to_string())
+    }
+
+    async fn download_range(&self, key: String, start: u64, end: u64) -> Result<Vec<u8>, String> {
+        let range = format!("bytes={}-{}", start, end);
+        let (_, body) = self
+            .send(Method::GET, &key, &[], &[("range", range)], Vec::new())
+            .await?;
+        Ok(body)
+    }
+
+    async fn object_etag(&self, key: String) -> Result<Option<String>, String> {
+        let (status, headers, body) = self
+            .execute(Method::HEAD, &key, &[], &[], Vec::new())
+            .await?;
+        cloud_storage::etag_from_head(status.as_u16(), &headers, &body)
+    }
+
+    async fn copy_object(&self, source: String, key: String) -> Result<(), String> {
+        let source = format!(
+            "/{}/{}",
+            self.config.bucket.trim(),
+            uri_encode(&source, false)
+        );
+        self.send(
+            Method::PUT,
+            &key,
+            &[],
+            &[("x-oss-copy-source", source)],
+            Vec::new(),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn delete_object(&self, key: String) -> Result<(), String> {
+        self.send(Method::DELETE, &key, &[], &[], Vec::new())
+            .await
+            .map(|_| ())
+    }
+}
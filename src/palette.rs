@@ -0,0 +1,105 @@
+use iced::{Color, Theme};
+
+/// 界面使用的颜色，浅色和深色主题各一套
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub surface: Color,     // 面板、对话框和标题栏的背景
+    pub surface_alt: Color, // 按钮和占位图的背景
+    pub hover: Color,       // 按钮悬停时的背景
+    pub pressed: Color,     // 按钮按下时的背景
+    pub canvas: Color,      // 图片区域的默认背景
+    pub border: Color,
+    pub text: Color,
+    pub heading: Color,  // 标题文字
+    pub muted: Color,    // 说明文字和标签
+    pub disabled: Color, // 不可用的文字
+    pub accent: Color,   // 主按钮、选中项和滑块
+    pub accent_hover: Color,
+    pub accent_pressed: Color,
+    pub danger: Color,
+    pub success: Color,
+    pub marked: Color, // 多选中的缩略图边框
+    pub shadow: Color,
+}
+
+impl Palette {
+    fn light() -> Palette {
+        Palette {
+            surface: Color::WHITE,
+            surface_alt: Color::from_rgb8(248, 249, 250),
+            hover: Color::from_rgb8(233, 236, 239),
+            pressed: Color::from_rgb8(222, 226, 230),
+            canvas: Color::from_rgb8(250, 250, 250),
+            border: Color::from_rgb8(222, 226, 230),
+            text: Color::from_rgb8(33, 37, 41),
+            heading: Color::from_rgb8(52, 58, 64),
+            muted: Color::from_rgb8(108, 117, 125),
+            disabled: Color::from_rgb8(173, 181, 189),
+            accent: Color::from_rgb8(13, 110, 253),
+            accent_hover: Color::from_rgb8(0, 86, 179),
+            accent_pressed: Color::from_rgb8(10, 88, 202),
+            danger: Color::from_rgb8(220, 53, 69),
+            success: Color::from_rgb8(25, 135, 84),
+            marked: Color::from_rgb8(253, 126, 20),
+            shadow: Color::from_rgba8(0, 0, 0, 0.15),
+        }
+    }
+
+    /// 按主题的明暗选择一套颜色，并换上给定的强调色
+    /// 悬停和按下的颜色由强调色推算：深色主题中提亮，浅色主题中加深
+    pub fn new(dark: bool, accent: Color) -> Palette {
+        if dark {
+            Palette {
+                accent: mix(accent, Color::WHITE, 0.2),
+                accent_hover: mix(accent, Color::WHITE, 0.4),
+                accent_pressed: accent,
+                ..Palette::dark()
+            }
+        } else {
+            Palette {
+                accent,
+                accent_hover: mix(accent, Color::BLACK, 0.3),
+                accent_pressed: mix(accent, Color::BLACK, 0.2),
+                ..Palette::light()
+            }
+        }
+    }
+
+    fn dark() -> Palette {
+        Palette {
+            surface: Color::from_rgb8(33, 37, 41),
+            surface_alt: Color::from_rgb8(43, 48, 53),
+            hover: Color::from_rgb8(52, 58, 64),
+            pressed: Color::from_rgb8(73, 80, 87),
+            canvas: Color::from_rgb8(24, 26, 29),
+            border: Color::from_rgb8(73, 80, 87),
+            text: Color::from_rgb8(222, 226, 230),
+            heading: Color::from_rgb8(206, 212, 218),
+            muted: Color::from_rgb8(173, 181, 189),
+            disabled: Color::from_rgb8(108, 117, 125),
+            accent: Color::from_rgb8(61, 139, 253),
+            accent_hover: Color::from_rgb8(110, 168, 254),
+            accent_pressed: Color::from_rgb8(13, 110, 253),
+            danger: Color::from_rgb8(234, 134, 143),
+            success: Color::from_rgb8(117, 183, 152),
+            marked: Color::from_rgb8(253, 126, 20),
+            shadow: Color::from_rgba8(0, 0, 0, 0.5),
+        }
+    }
+}
+
+// 按比例 t 把颜色 a 向 b 混合
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    Color {
+        r: a.r + (b.r - a.r) * t,
+        g: a.g + (b.g - a.g) * t,
+        b: a.b + (b.b - a.b) * t,
+        a: a.a,
+    }
+}
+
+/// 按主题取颜色，样式函数通过这里适配浅色和深色主题
+pub fn of(theme: &Theme) -> Palette {
+    // 主题的 primary 就是用户选择的强调色
+    Palette::new(theme.extended_palette().is_dark, theme.palette().primary)
+}
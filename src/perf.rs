@@ -0,0 +1,182 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// 图片处理流程中计时的环节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Decode,
+    Thumbnail,
+    Resize,
+    Adjust,
+    Encode,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Decode,
+        Stage::Thumbnail,
+        Stage::Resize,
+        Stage::Adjust,
+        Stage::Encode,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Decode => "decode",
+            Stage::Thumbnail => "thumbnail",
+            Stage::Resize => "resize",
+            Stage::Adjust => "adjust",
+            Stage::Encode => "encode",
+        }
+    }
+}
+
+/// 统计命中率的缓存
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cache {
+    Decoded,
+    Thumbnail,
+}
+
+/// 某个环节的耗时统计
+#[derive(Debug, Clone, Copy)]
+pub struct Timing {
+    pub count: u64,
+    pub last: Duration,
+    pub max: Duration,
+    total: Duration,
+}
+
+impl Timing {
+    const fn new() -> Self {
+        Timing {
+            count: 0,
+            last: Duration::ZERO,
+            max: Duration::ZERO,
+            total: Duration::ZERO,
+        }
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.count as u32
+        }
+    }
+}
+
+/// 缓存的命中次数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HitRate {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl HitRate {
+    /// 命中率，还没有查询过时返回 None
+    pub fn ratio(&self) -> Option<f32> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f32 / total as f32)
+    }
+}
+
+/// 性能统计的快照
+#[derive(Debug, Clone, Copy)]
+pub struct PerfStats {
+    timings: [Timing; Stage::ALL.len()],
+    pub decoded_cache: HitRate,
+    pub thumbnail_cache: HitRate,
+    pub updates: u64, // 处理过的消息数
+    pub views: u64,   // 重新生成界面的次数
+}
+
+impl PerfStats {
+    const fn new() -> Self {
+        PerfStats {
+            timings: [Timing::new(); Stage::ALL.len()],
+            decoded_cache: HitRate { hits: 0, misses: 0 },
+            thumbnail_cache: HitRate { hits: 0, misses: 0 },
+            updates: 0,
+            views: 0,
+        }
+    }
+
+    pub fn timing(&self, stage: Stage) -> &Timing {
+        &self.timings[stage as usize]
+    }
+}
+
+// 后台线程也会记录，统一放在全局的统计中
+static STATS: Mutex<PerfStats> = Mutex::new(PerfStats::new());
+
+fn stats() -> MutexGuard<'static, PerfStats> {
+    STATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 计时器，离开作用域时把经过的时间记到对应的环节
+pub struct Timer {
+    stage: Stage,
+    start: Instant,
+}
+
+impl Timer {
+    pub fn start(stage: Stage) -> Self {
+        Timer {
+            stage,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        record(self.stage, self.start.elapsed());
+    }
+}
+
+/// 执行 f 并把耗时记到对应的环节
+pub fn time<T>(stage: Stage, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    record(stage, start.elapsed());
+    result
+}
+
+pub fn record(stage: Stage, elapsed: Duration) {
+    let mut stats = stats();
+    let timing = &mut stats.timings[stage as usize];
+    timing.count += 1;
+    timing.last = elapsed;
+    timing.total += elapsed;
+    timing.max = timing.max.max(elapsed);
+}
+
+pub fn record_cache(cache: Cache, hit: bool) {
+    let mut stats = stats();
+    let rate = match cache {
+        Cache::Decoded => &mut stats.decoded_cache,
+        Cache::Thumbnail => &mut stats.thumbnail_cache,
+    };
+    if hit {
+        rate.hits += 1;
+    } else {
+        rate.misses += 1;
+    }
+}
+
+pub fn count_update() {
+    stats().updates += 1;
+}
+
+pub fn count_view() {
+    stats().views += 1;
+}
+
+pub fn snapshot() -> PerfStats {
+    *stats()
+}
+
+pub fn reset() {
+    *stats() = PerfStats::new();
+}
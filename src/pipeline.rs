@@ -0,0 +1,252 @@
+use iced::Vector;
+use iced::widget::image::Handle;
+use image::error::{DecodingError, ImageFormatHint};
+use image::metadata::Orientation;
+use image::{
+    DynamicImage, GenericImageView, ImageBuffer, ImageDecoder, ImageError, ImageReader, Rgb,
+    RgbImage,
+};
+use resize::Type::{Catrom, Lanczos3, Mitchell, Point, Triangle};
+use rgb::FromSlice;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::image_cache::Decoded;
+use crate::perf::Stage;
+use crate::{jpeg_scale, perf, psd, textures};
+
+// 定义缩放算法类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResamplingType {
+    Point,
+    Triangle,
+    Catrom,
+    Mitchell,
+    Lanczos3,
+}
+
+impl ResamplingType {
+    // 获取算法名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResamplingType::Point => "Point",
+            ResamplingType::Triangle => "Triangle",
+            ResamplingType::Catrom => "Catrom",
+            ResamplingType::Mitchell => "Mitchell",
+            ResamplingType::Lanczos3 => "Lanczos3",
+        }
+    }
+
+    // 获取对应的resize库算法类型
+    pub fn to_resize_type(self) -> resize::Type {
+        match self {
+            ResamplingType::Point => Point,
+            ResamplingType::Triangle => Triangle,
+            ResamplingType::Catrom => Catrom,
+            ResamplingType::Mitchell => Mitchell,
+            ResamplingType::Lanczos3 => Lanczos3,
+        }
+    }
+
+    // 获取所有可用算法
+    pub fn all() -> Vec<ResamplingType> {
+        vec![
+            ResamplingType::Point,
+            ResamplingType::Triangle,
+            ResamplingType::Catrom,
+            ResamplingType::Mitchell,
+            ResamplingType::Lanczos3,
+        ]
+    }
+}
+
+/// 把解码后的 RGB 图像转换为显示用的句柄
+pub fn rgb_handle(img: &image::RgbImage) -> Handle {
+    let rgba = DynamicImage::ImageRgb8(img.clone()).to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Handle::from_rgba(width, height, rgba.into_raw())
+}
+
+/// 解码图片并返回EXIF方向，apply_orientation 为 true 时按方向旋转/翻转
+pub fn decode_image(
+    path: &Path,
+    apply_orientation: bool,
+) -> image::ImageResult<(DynamicImage, Orientation)> {
+    let _timer = perf::Timer::start(Stage::Decode);
+    // GPU 纹理由 textures 解码，显示原尺寸的一层
+    if textures::is_texture(path) {
+        let (img, _) = textures::decode(path, 0)
+            .map_err(|e| ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, e)))?;
+        return Ok((img, Orientation::NoTransforms));
+    }
+    // Photoshop 文件只显示合并图像
+    if psd::is_psd(path) {
+        let img = psd::decode(path)
+            .map_err(|e| ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, e)))?;
+        return Ok((img, Orientation::NoTransforms));
+    }
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    if apply_orientation {
+        img.apply_orientation(orientation);
+    }
+    Ok((img, orientation))
+}
+
+// 浏览时使用的解码：长边远大于屏幕的 JPEG 按缩小的尺寸解码，同时记录原图尺寸
+pub fn decode_for_display(path: &Path, apply_orientation: bool) -> image::ImageResult<Decoded> {
+    // GPU 纹理同时取出信息面板中显示的格式和层数
+    if textures::is_texture(path) {
+        let (img, info) = perf::time(Stage::Decode, || textures::decode(path, 0))
+            .map_err(|e| ImageError::Decoding(DecodingError::new(ImageFormatHint::Unknown, e)))?;
+        return Ok(Decoded {
+            image: Arc::new(img.to_rgb8()),
+            orientation: Orientation::NoTransforms,
+            full_size: None,
+            texture: Some(info),
+            psd_layers: Vec::new(),
+        });
+    }
+    let reduced = perf::time(Stage::Decode, || {
+        jpeg_scale::decode_reduced(path, jpeg_scale::DISPLAY_TARGET)
+    });
+    let Some((mut img, (width, height))) = reduced else {
+        let (img, orientation) = decode_image(path, apply_orientation)?;
+        // Photoshop 文件同时读取图层名称
+        let psd_layers = if psd::is_psd(path) {
+            psd::layer_names(path).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        return Ok(Decoded {
+            image: Arc::new(img.to_rgb8()),
+            orientation,
+            full_size: None,
+            texture: None,
+            psd_layers,
+        });
+    };
+    // 只读取文件头中的方向信息
+    let orientation = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?
+        .orientation()
+        .unwrap_or(Orientation::NoTransforms);
+    let before = img.width();
+    if apply_orientation {
+        img.apply_orientation(orientation);
+    }
+    // 旋转 90° 后原图尺寸也要交换宽高
+    let full_size = if img.width() == before {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    Ok(Decoded {
+        image: Arc::new(img.to_rgb8()),
+        orientation,
+        full_size: Some(full_size),
+        texture: None,
+        psd_layers: Vec::new(),
+    })
+}
+
+pub fn scale_image_async(
+    ori_img: Option<Arc<RgbImage>>,
+    slider_value: u8,
+    resampling_type: ResamplingType,
+) -> Result<Vec<u8>, String> {
+    let _timer = perf::Timer::start(Stage::Resize);
+    if let Some(img) = &ori_img {
+        let (w0, h0) = img.dimensions();
+        // 缩放倍率：1.0 = 原始大小，2.0 = 放大两倍
+        let scale = slider_value as f32 / 50.0;
+
+        // 显示区域大小（你可以替换为实际显示区域大小）
+        let display_width = w0;
+        let display_height = h0;
+
+        // 计算裁剪区域大小（原始图像中的区域）
+        let crop_width = (display_width as f32 / scale) as u32;
+        let crop_height = (display_height as f32 / scale) as u32;
+
+        let crop_x = (w0.saturating_sub(crop_width)) / 2;
+        let crop_y = (h0.saturating_sub(crop_height)) / 2;
+
+        // 裁剪图像
+        let cropped = img.view(crop_x, crop_y, crop_width, crop_height).to_image();
+
+        // 将裁剪区域放大到显示区域大小
+        return resize_to_png(&cropped, display_width, display_height, resampling_type);
+    }
+    Ok(Vec::new())
+}
+
+/// 根据当前缩放倍数 + 平移偏移量，从原图裁一块并放大到显示尺寸
+pub fn crop_and_scale(
+    ori: &image::RgbImage,
+    scale: f32,     // slider_value / 50.0
+    offset: Vector, // 用户拖动的像素偏移（相对于显示窗口）
+    resample: ResamplingType,
+) -> Result<Vec<u8>, String> {
+    let _timer = perf::Timer::start(Stage::Resize);
+    let (full_w, full_h) = ori.dimensions();
+
+    // 1. 计算"窗口"在放大后图片上的逻辑大小
+    let view_w = (full_w as f32 / scale).max(1.0); // 逻辑宽
+    let view_h = (full_h as f32 / scale).max(1.0); // 逻辑高
+
+    // 2. 计算裁剪起点（左上角）
+    let center_x = full_w as f32 / 2.0;
+    let center_y = full_h as f32 / 2.0;
+
+    // 修正偏移量计算：拖动方向与裁剪方向相反，且需要根据缩放比例调整
+    let crop_x = (center_x - view_w / 2.0 - offset.x / scale)
+        .max(0.0)
+        .min(full_w as f32 - view_w) as u32;
+    let crop_y = (center_y - view_h / 2.0 - offset.y / scale)
+        .max(0.0)
+        .min(full_h as f32 - view_h) as u32;
+
+    let crop_w = view_w.min(full_w as f32 - crop_x as f32) as u32;
+    let crop_h = view_h.min(full_h as f32 - crop_y as f32) as u32;
+
+    // 3. 裁剪
+    let cropped = ori.view(crop_x, crop_y, crop_w, crop_h).to_image();
+
+    // 4. 放大回显示尺寸
+    resize_to_png(&cropped, full_w, full_h, resample)
+}
+
+// 把裁剪出的区域缩放到给定尺寸并编码为 PNG
+pub fn resize_to_png(
+    cropped: &RgbImage,
+    width: u32,
+    height: u32,
+    resample: ResamplingType,
+) -> Result<Vec<u8>, String> {
+    let mut dst = vec![0; (width * height * 3) as usize];
+    let mut resizer = resize::new(
+        cropped.width() as usize,
+        cropped.height() as usize,
+        width as usize,
+        height as usize,
+        resize::Pixel::RGB8,
+        resample.to_resize_type(),
+    )
+    .map_err(|e| e.to_string())?;
+    resizer
+        .resize(cropped.as_raw().as_rgb(), dst.as_rgb_mut())
+        .map_err(|e| e.to_string())?;
+
+    let out = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(width, height, dst)
+        .ok_or("pixel buffer does not match the image size")?;
+    let mut buf = Vec::new();
+    out.write_to(&mut std::io::Cursor::new(&mut buf), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(buf)
+}
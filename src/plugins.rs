@@ -0,0 +1,362 @@
+use image::RgbImage;
+use libloading::Library;
+use std::ffi::{CStr, CString, c_char};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::config;
+
+/// 插件接口的版本，插件的 ib_plugin_abi_version 必须返回这个值
+pub const ABI_VERSION: u32 = 1;
+
+// 插件是插件目录中的动态库，导出以下 C 函数：
+//   必需  ib_plugin_abi_version() -> u32
+//         ib_plugin_name() -> *const c_char
+//   滤镜  ib_filter_count() -> u32
+//         ib_filter_name(index) -> *const c_char
+//         ib_filter_apply(index, rgb: *mut u8, width, height)，在原地修改 RGB8 像素，不能改变尺寸
+//   导出  ib_exporter_count() -> u32
+//         ib_exporter_name(index) -> *const c_char
+//         ib_exporter_export(index, path: *const c_char) -> i32，path 为图片文件，返回 0 表示成功
+//   元数据 ib_metadata(path: *const c_char) -> *const c_char，每行一项 "名称=值"，没有时返回 NULL
+// 返回的字符串由插件持有，在下一次调用前有效；同一个插件的函数不会被同时调用
+type VersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type CountFn = unsafe extern "C" fn() -> u32;
+type ItemNameFn = unsafe extern "C" fn(u32) -> *const c_char;
+type FilterFn = unsafe extern "C" fn(u32, *mut u8, u32, u32);
+type ExportFn = unsafe extern "C" fn(u32, *const c_char) -> i32;
+type MetadataFn = unsafe extern "C" fn(*const c_char) -> *const c_char;
+
+/// 设置页中显示的插件信息
+#[derive(Debug, Clone)]
+pub struct PluginInfo {
+    pub name: &'static str,
+    pub file: PathBuf,
+    pub enabled: bool,
+    pub filters: usize,
+    pub exporters: usize,
+    pub metadata: bool,
+}
+
+impl PluginInfo {
+    /// 配置中记录停用插件时使用的名称
+    pub fn key(&self) -> String {
+        plugin_key(&self.file)
+    }
+}
+
+// 已加载的插件；动态库在程序退出前不会卸载，函数指针和名称一直有效
+struct Plugin {
+    info: PluginInfo,
+    filter_names: Vec<&'static str>,
+    exporter_names: Vec<&'static str>,
+    filter_apply: Option<FilterFn>,
+    export: Option<ExportFn>,
+    metadata: Option<MetadataFn>,
+    lock: Mutex<()>, // 同一个插件的调用依次进行
+    _library: Library,
+}
+
+#[derive(Default)]
+struct Registry {
+    plugins: Vec<Plugin>,
+    failed: Vec<(PathBuf, String)>, // 无法加载的文件和原因
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(RwLock::default);
+
+/// 插件提供的滤镜，plugin 为插件在已加载列表中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginFilter {
+    plugin: usize,
+    index: u32,
+}
+
+impl PluginFilter {
+    pub fn name(self) -> &'static str {
+        let registry = REGISTRY.read().unwrap();
+        registry
+            .plugins
+            .get(self.plugin)
+            .and_then(|plugin| plugin.filter_names.get(self.index as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 在原地应用滤镜，插件已停用时不做处理
+    pub fn apply(self, img: &mut RgbImage) {
+        let registry = REGISTRY.read().unwrap();
+        let Some(plugin) = registry.plugins.get(self.plugin) else {
+            return;
+        };
+        let Some(apply) = plugin.filter_apply.filter(|_| plugin.info.enabled) else {
+            return;
+        };
+        let (width, height) = img.dimensions();
+        let _guard = plugin.lock.lock().unwrap();
+        unsafe { apply(self.index, img.as_mut_ptr(), width, height) };
+    }
+}
+
+/// 插件提供的导出目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exporter {
+    plugin: usize,
+    index: u32,
+}
+
+impl Exporter {
+    pub fn name(self) -> &'static str {
+        let registry = REGISTRY.read().unwrap();
+        registry
+            .plugins
+            .get(self.plugin)
+            .and_then(|plugin| plugin.exporter_names.get(self.index as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 把图片文件交给插件导出，会阻塞到插件返回；插件已停用时返回错误
+    pub fn export(self, path: &Path) -> Result<(), String> {
+        let registry = REGISTRY.read().unwrap();
+        let Some((plugin, export)) = registry
+            .plugins
+            .get(self.plugin)
+            .and_then(|plugin| Some((plugin, plugin.export?)))
+        else {
+            return Err("plugin is not loaded".to_string());
+        };
+        if !plugin.info.enabled {
+            return Err("plugin is disabled".to_string());
+        }
+        let path = CString::new(path.to_string_lossy().into_owned()).map_err(|e| e.to_string())?;
+        let _guard = plugin.lock.lock().unwrap();
+        match unsafe { export(self.index, path.as_ptr()) } {
+            0 => Ok(()),
+            code => Err(format!("plugin returned error {}", code)),
+        }
+    }
+}
+
+/// 插件目录，放入其中的动态库在启动或重新加载时被发现
+pub fn plugins_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("plugins"))
+}
+
+/// 加载插件目录中还没有加载的动态库，disabled 中的插件加载后处于停用状态
+pub fn load(disabled: &[String]) {
+    let Some(dir) = plugins_dir() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+    let mut registry = REGISTRY.write().unwrap();
+    registry.failed.clear();
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+        })
+        .collect();
+    files.sort();
+    for file in files {
+        if registry
+            .plugins
+            .iter()
+            .any(|plugin| plugin.info.file == file)
+        {
+            continue;
+        }
+        match unsafe { load_plugin(&file) } {
+            Ok(mut plugin) => {
+                plugin.info.enabled = !disabled.contains(&plugin.info.key());
+                info!("Loaded plugin {} from {}", plugin.info.name, file.display());
+                registry.plugins.push(plugin);
+            }
+            Err(e) => {
+                warn!("Failed to load plugin {}: {}", file.display(), e);
+                registry.failed.push((file, e));
+            }
+        }
+    }
+}
+
+// 读取插件的名称和提供的功能
+unsafe fn load_plugin(file: &Path) -> Result<Plugin, String> {
+    unsafe {
+        let library = Library::new(file).map_err(|e| e.to_string())?;
+        let version = *library
+            .get::<VersionFn>(b"ib_plugin_abi_version\0")
+            .map_err(|e| e.to_string())?;
+        if version() != ABI_VERSION {
+            return Err(format!(
+                "unsupported plugin interface version {}",
+                version()
+            ));
+        }
+        let name = *library
+            .get::<NameFn>(b"ib_plugin_name\0")
+            .map_err(|e| e.to_string())?;
+        let name = leak(name()).unwrap_or_else(|| leak_str(plugin_key(file)));
+
+        let filter_names = item_names(&library, b"ib_filter_count\0", b"ib_filter_name\0");
+        let exporter_names = item_names(&library, b"ib_exporter_count\0", b"ib_exporter_name\0");
+        let filter_apply = library
+            .get::<FilterFn>(b"ib_filter_apply\0")
+            .ok()
+            .map(|f| *f);
+        let export = library
+            .get::<ExportFn>(b"ib_exporter_export\0")
+            .ok()
+            .map(|f| *f);
+        let metadata = library.get::<MetadataFn>(b"ib_metadata\0").ok().map(|f| *f);
+        Ok(Plugin {
+            info: PluginInfo {
+                name,
+                file: file.to_path_buf(),
+                enabled: true,
+                filters: if filter_apply.is_some() {
+                    filter_names.len()
+                } else {
+                    0
+                },
+                exporters: if export.is_some() {
+                    exporter_names.len()
+                } else {
+                    0
+                },
+                metadata: metadata.is_some(),
+            },
+            filter_names,
+            exporter_names,
+            filter_apply,
+            export,
+            metadata,
+            lock: Mutex::new(()),
+            _library: library,
+        })
+    }
+}
+
+// 按 count / name 函数读出滤镜或导出目标的名称，插件没有提供时为空
+unsafe fn item_names(library: &Library, count: &[u8], name: &[u8]) -> Vec<&'static str> {
+    unsafe {
+        let (Ok(count), Ok(name)) = (
+            library.get::<CountFn>(count),
+            library.get::<ItemNameFn>(name),
+        ) else {
+            return Vec::new();
+        };
+        (0..count())
+            .map(|index| leak(name(index)).unwrap_or("?"))
+            .collect()
+    }
+}
+
+// 复制插件返回的字符串；插件不会卸载，名称保存为静态字符串
+unsafe fn leak(text: *const c_char) -> Option<&'static str> {
+    if text.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(text) }
+        .to_string_lossy()
+        .into_owned();
+    Some(leak_str(text))
+}
+
+fn leak_str(text: String) -> &'static str {
+    Box::leak(text.into_boxed_str())
+}
+
+fn plugin_key(file: &Path) -> String {
+    file.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// 已加载的插件和无法加载的文件
+pub fn list() -> (Vec<PluginInfo>, Vec<(PathBuf, String)>) {
+    let registry = REGISTRY.read().unwrap();
+    (
+        registry
+            .plugins
+            .iter()
+            .map(|plugin| plugin.info.clone())
+            .collect(),
+        registry.failed.clone(),
+    )
+}
+
+/// 启用或停用插件，停用后它的滤镜、导出目标和元数据不再出现
+pub fn set_enabled(key: &str, enabled: bool) {
+    let mut registry = REGISTRY.write().unwrap();
+    for plugin in &mut registry.plugins {
+        if plugin.info.key() == key {
+            plugin.info.enabled = enabled;
+        }
+    }
+}
+
+/// 已启用插件提供的滤镜
+pub fn filters() -> Vec<PluginFilter> {
+    let registry = REGISTRY.read().unwrap();
+    registry
+        .plugins
+        .iter()
+        .enumerate()
+        .filter(|(_, plugin)| plugin.info.enabled)
+        .flat_map(|(index, plugin)| {
+            (0..plugin.info.filters as u32).map(move |filter| PluginFilter {
+                plugin: index,
+                index: filter,
+            })
+        })
+        .collect()
+}
+
+/// 已启用插件提供的导出目标
+pub fn exporters() -> Vec<Exporter> {
+    let registry = REGISTRY.read().unwrap();
+    registry
+        .plugins
+        .iter()
+        .enumerate()
+        .filter(|(_, plugin)| plugin.info.enabled)
+        .flat_map(|(index, plugin)| {
+            (0..plugin.info.exporters as u32).map(move |exporter| Exporter {
+                plugin: index,
+                index: exporter,
+            })
+        })
+        .collect()
+}
+
+/// 向已启用的元数据插件查询图片的附加信息，返回 (名称, 值)
+pub fn metadata(path: &Path) -> Vec<(String, String)> {
+    let registry = REGISTRY.read().unwrap();
+    let Ok(c_path) = CString::new(path.to_string_lossy().into_owned()) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for plugin in registry.plugins.iter().filter(|plugin| plugin.info.enabled) {
+        let Some(metadata) = plugin.metadata else {
+            continue;
+        };
+        let _guard = plugin.lock.lock().unwrap();
+        let text = unsafe { metadata(c_path.as_ptr()) };
+        if text.is_null() {
+            continue;
+        }
+        let text = unsafe { CStr::from_ptr(text) }.to_string_lossy();
+        entries.extend(text.lines().filter_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        }));
+    }
+    entries
+}
@@ -0,0 +1,408 @@
+use image::{DynamicImage, GrayImage, RgbImage};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Photoshop 的颜色模式
+const BITMAP: u16 = 0;
+const GRAYSCALE: u16 = 1;
+const INDEXED: u16 = 2;
+const RGB: u16 = 3;
+const CMYK: u16 = 4;
+
+// PSB 中长度为 8 字节的附加图层信息
+const LONG_KEYS: [&[u8; 4]; 13] = [
+    b"LMsk", b"Lr16", b"Lr32", b"Layr", b"Mt16", b"Mt32", b"Mtrn", b"Alph", b"FMsk", b"lnk2",
+    b"FEid", b"FXid", b"PxSD",
+];
+
+/// 按扩展名判断是否是 Photoshop 文件（PSD 和大文档格式 PSB），不区分大小写
+pub fn is_psd(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "psd" | "psb"))
+}
+
+// 文件头中的图片参数
+struct Header {
+    large: bool, // PSB，部分长度字段为 8 字节
+    channels: usize,
+    width: u32,
+    height: u32,
+    depth: u16,
+    mode: u16,
+}
+
+struct Reader<R> {
+    inner: R,
+    large: bool,
+    size: u64, // 文件大小，文件中记录的长度超出时视为损坏
+}
+
+impl<R: Read + Seek> Reader<R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut buf = [0u8; N];
+        self.inner.read_exact(&mut buf).map_err(|e| e.to_string())?;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.bytes()?))
+    }
+
+    // PSB 中为 8 字节的长度
+    fn length(&mut self) -> Result<u64, String> {
+        if self.large {
+            Ok(u64::from_be_bytes(self.bytes()?))
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+
+    fn vec(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        self.check_len(len as u64)?;
+        let mut buf = vec![0u8; len];
+        self.inner.read_exact(&mut buf).map_err(|e| e.to_string())?;
+        Ok(buf)
+    }
+
+    fn skip(&mut self, len: u64) -> Result<(), String> {
+        self.check_len(len)?;
+        self.inner
+            .seek(SeekFrom::Current(len as i64))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    // 读取或跳过 len 字节前检查文件中是否还有这么多数据，避免按损坏的长度分配内存
+    fn check_len(&mut self, len: u64) -> Result<(), String> {
+        let position = self.position()?;
+        if len > self.size.saturating_sub(position) {
+            return Err("PSD data is truncated".to_string());
+        }
+        Ok(())
+    }
+
+    fn position(&mut self) -> Result<u64, String> {
+        self.inner.stream_position().map_err(|e| e.to_string())
+    }
+
+    fn seek(&mut self, position: u64) -> Result<(), String> {
+        self.inner
+            .seek(SeekFrom::Start(position))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn open(path: &Path) -> Result<(Reader<BufReader<File>>, Header), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut reader = Reader {
+        inner: BufReader::new(file),
+        large: false,
+        size,
+    };
+    if &reader.bytes::<4>()? != b"8BPS" {
+        return Err("Not a Photoshop file".to_string());
+    }
+    let large = match reader.u16()? {
+        1 => false,
+        2 => true,
+        version => return Err(format!("Unsupported PSD version {}", version)),
+    };
+    reader.large = large;
+    reader.skip(6)?;
+    let channels = reader.u16()? as usize;
+    let height = reader.u32()?;
+    let width = reader.u32()?;
+    let depth = reader.u16()?;
+    let mode = reader.u16()?;
+    let header = Header {
+        large,
+        channels,
+        width,
+        height,
+        depth,
+        mode,
+    };
+    Ok((reader, header))
+}
+
+/// 解码文件末尾的合并图像。保存时关闭了“最大兼容”的文件没有完整的合并图像，显示为空白
+pub fn decode(path: &Path) -> Result<DynamicImage, String> {
+    let (mut reader, header) = open(path)?;
+    if !matches!(header.depth, 8 | 16) {
+        return Err(format!("Unsupported PSD bit depth {}", header.depth));
+    }
+    let planes = match header.mode {
+        GRAYSCALE | INDEXED => 1,
+        RGB => 3,
+        CMYK => 4,
+        BITMAP => return Err("Unsupported PSD color mode: bitmap".to_string()),
+        mode => return Err(format!("Unsupported PSD color mode {}", mode)),
+    };
+    if header.channels < planes || header.width == 0 || header.height == 0 {
+        return Err("PSD has no image data".to_string());
+    }
+
+    // 颜色模式数据中是索引颜色的调色板：256 个红色、256 个绿色、256 个蓝色
+    let color_data_len = reader.u32()? as usize;
+    let palette = if header.mode == INDEXED {
+        reader.vec(color_data_len)?
+    } else {
+        reader.skip(color_data_len as u64)?;
+        Vec::new()
+    };
+    let resources_len = reader.u32()?;
+    reader.skip(resources_len as u64)?;
+    let layers_len = reader.length()?;
+    reader.skip(layers_len)?;
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let bytes_per_sample = header.depth as usize / 8;
+    let row_len = width * bytes_per_sample;
+    let mut channels = Vec::with_capacity(planes);
+    match reader.u16()? {
+        0 => {
+            for _ in 0..planes {
+                channels.push(reader.vec(row_len * height)?);
+            }
+        }
+        1 => {
+            // 所有通道所有行压缩后的字节数在前，之后依次是每行的 PackBits 数据
+            let mut counts = Vec::with_capacity(planes * height);
+            for _ in 0..header.channels * height {
+                counts.push(if header.large {
+                    reader.u32()? as usize
+                } else {
+                    reader.u16()? as usize
+                });
+            }
+            for channel in counts.chunks(height).take(planes) {
+                let mut plane = Vec::with_capacity(row_len * height);
+                for &count in channel {
+                    let packed = reader.vec(count)?;
+                    unpack_bits(&packed, row_len, &mut plane);
+                }
+                channels.push(plane);
+            }
+        }
+        compression => {
+            return Err(format!("Unsupported PSD compression {}", compression));
+        }
+    }
+
+    // 16 位的样本取高字节
+    let sample = |channel: usize, index: usize| channels[channel][index * bytes_per_sample];
+    let (w, h) = (header.width, header.height);
+    let img = match header.mode {
+        GRAYSCALE => DynamicImage::ImageLuma8(GrayImage::from_fn(w, h, |x, y| {
+            image::Luma([sample(0, (y * w + x) as usize)])
+        })),
+        INDEXED => {
+            if palette.len() < 768 {
+                return Err("PSD color table is missing".to_string());
+            }
+            DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, y| {
+                let i = sample(0, (y * w + x) as usize) as usize;
+                image::Rgb([palette[i], palette[256 + i], palette[512 + i]])
+            }))
+        }
+        RGB => DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, y| {
+            let i = (y * w + x) as usize;
+            image::Rgb([sample(0, i), sample(1, i), sample(2, i)])
+        })),
+        // 保存的是反相的油墨量，按简单的换算显示，不做色彩管理
+        _ => DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, y| {
+            let i = (y * w + x) as usize;
+            let k = sample(3, i) as u16;
+            let value = |c: usize| (sample(c, i) as u16 * k / 255) as u8;
+            image::Rgb([value(0), value(1), value(2)])
+        })),
+    };
+    Ok(img)
+}
+
+// PackBits 解压一行，不足的部分补 0，多余的丢弃
+fn unpack_bits(packed: &[u8], row_len: usize, out: &mut Vec<u8>) {
+    let end = out.len() + row_len;
+    let mut i = 0;
+    while i < packed.len() && out.len() < end {
+        let n = packed[i] as i8;
+        i += 1;
+        if n >= 0 {
+            let literal = &packed[i.min(packed.len())..(i + n as usize + 1).min(packed.len())];
+            out.extend_from_slice(literal);
+            i += n as usize + 1;
+        } else if n != -128 {
+            if let Some(&value) = packed.get(i) {
+                out.extend(std::iter::repeat_n(value, (1 - n as isize) as usize));
+            }
+            i += 1;
+        }
+    }
+    out.resize(end, 0);
+}
+
+/// 图层名称，按图层面板中的顺序从上到下排列，不包括图层组的结束标记
+pub fn layer_names(path: &Path) -> Result<Vec<String>, String> {
+    let (mut reader, _) = open(path)?;
+    let color_data_len = reader.u32()?;
+    reader.skip(color_data_len as u64)?;
+    let resources_len = reader.u32()?;
+    reader.skip(resources_len as u64)?;
+    // 图层和蒙版信息段，其中第一部分是图层信息；只有背景的文件两者都为空
+    if reader.length()? == 0 || reader.length()? == 0 {
+        return Ok(Vec::new());
+    }
+    // 图层数为负数时表示第一个透明通道是合并图像的透明度
+    let count = (reader.u16()? as i16).unsigned_abs() as usize;
+
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        reader.skip(16)?; // 图层范围
+        let channels = reader.u16()? as u64;
+        reader.skip(channels * if reader.large { 10 } else { 6 })?;
+        reader.skip(12)?; // 混合模式签名和模式、不透明度、剪贴、标志和填充
+        let extra_len = reader.u32()? as u64;
+        let extra_start = reader.position()?;
+        let mask_len = reader.u32()?;
+        reader.skip(mask_len as u64)?;
+        let ranges_len = reader.u32()?;
+        reader.skip(ranges_len as u64)?;
+        // 帕斯卡字符串，连同长度字节补齐到 4 的倍数
+        let name_len = reader.u8()? as usize;
+        let name = reader.vec(name_len)?;
+        reader.skip(((name_len + 1).next_multiple_of(4) - name_len - 1) as u64)?;
+        let mut name = String::from_utf8_lossy(&name).into_owned();
+
+        // 附加图层信息中的 Unicode 名称和图层组标记
+        let mut divider = false;
+        while reader.position()? + 12 <= extra_start + extra_len {
+            let signature = reader.bytes::<4>()?;
+            if &signature != b"8BIM" && &signature != b"8B64" {
+                break;
+            }
+            let key = reader.bytes::<4>()?;
+            let len = if reader.large && LONG_KEYS.contains(&&key) {
+                u64::from_be_bytes(reader.bytes()?)
+            } else {
+                reader.u32()? as u64
+            };
+            let data_start = reader.position()?;
+            match &key {
+                b"luni" => {
+                    let chars = reader.u32()? as usize;
+                    let units = (0..chars)
+                        .map(|_| reader.u16())
+                        .collect::<Result<Vec<u16>, String>>()?;
+                    name = String::from_utf16_lossy(&units)
+                        .trim_end_matches('\0')
+                        .to_string();
+                }
+                b"lsct" => divider = reader.u32()? == 3,
+                _ => {}
+            }
+            reader.seek(data_start + len)?;
+        }
+        reader.seek(extra_start + extra_len)?;
+        if !divider {
+            names.push(name);
+        }
+    }
+    names.reverse();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // 8 位、不压缩、没有图层的 PSD，color_data 为颜色模式数据，planes 为按通道依次存放的像素
+    fn psd_bytes(
+        width: u32,
+        height: u32,
+        mode: u16,
+        color_data: &[u8],
+        planes: &[&[u8]],
+    ) -> Vec<u8> {
+        let mut data = b"8BPS".to_vec();
+        data.extend(1u16.to_be_bytes());
+        data.extend([0; 6]);
+        data.extend((planes.len() as u16).to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend(width.to_be_bytes());
+        data.extend(8u16.to_be_bytes());
+        data.extend(mode.to_be_bytes());
+        data.extend((color_data.len() as u32).to_be_bytes());
+        data.extend(color_data);
+        data.extend(0u32.to_be_bytes()); // 图像资源
+        data.extend(0u32.to_be_bytes()); // 图层和蒙版信息
+        data.extend(0u16.to_be_bytes()); // 不压缩
+        for plane in planes {
+            data.extend(*plane);
+        }
+        data
+    }
+
+    fn write_temp(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.psd", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn decodes_rgb_and_indexed() {
+        let data = psd_bytes(2, 1, RGB, &[], &[&[255, 0], &[0, 128], &[10, 255]]);
+        let path = write_temp("psd-rgb", &data);
+        let img = decode(&path).unwrap().to_rgb8();
+        assert_eq!(img.dimensions(), (2, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 10]);
+        assert_eq!(img.get_pixel(1, 0).0, [0, 128, 255]);
+        let _ = std::fs::remove_file(&path);
+
+        // 调色板中 1 号颜色为 (1, 2, 3)
+        let mut palette = vec![0; 768];
+        palette[1] = 1;
+        palette[257] = 2;
+        palette[513] = 3;
+        let data = psd_bytes(1, 2, INDEXED, &palette, &[&[0, 1]]);
+        let path = write_temp("psd-indexed", &data);
+        let img = decode(&path).unwrap().to_rgb8();
+        assert_eq!(img.get_pixel(0, 0).0, [0, 0, 0]);
+        assert_eq!(img.get_pixel(0, 1).0, [1, 2, 3]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn unpacks_packbits_rows() {
+        let mut row = Vec::new();
+        // 重复 3 次的 7，之后是 2 个原样的字节
+        unpack_bits(&[0xFE, 7, 0x01, 1, 2], 5, &mut row);
+        assert_eq!(row, [7, 7, 7, 1, 2]);
+        // 数据不足时补 0
+        unpack_bits(&[0x00, 9], 3, &mut row);
+        assert_eq!(row[5..], [9, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_color_data_longer_than_the_file() {
+        let mut data = psd_bytes(1, 1, INDEXED, &[], &[&[0]]);
+        // 颜色模式数据的长度改为接近 4 GB
+        data[26..30].copy_from_slice(&u32::MAX.to_be_bytes());
+        let path = write_temp("psd-color-data", &data);
+        assert_eq!(decode(&path).unwrap_err(), "PSD data is truncated");
+        assert_eq!(layer_names(&path).unwrap_err(), "PSD data is truncated");
+        let _ = std::fs::remove_file(&path);
+    }
+}
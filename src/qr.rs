@@ -0,0 +1,1067 @@
+use iced::Point;
+
+use crate::codes::Bits;
+
+/// 识别出的二维码：内容和四个角（图片坐标，依次为左上、右上、右下、左下）
+#[derive(Debug, Clone)]
+pub struct QrCode {
+    pub text: String,
+    pub corners: [Point; 4],
+}
+
+// 一个定位图案（三个角上的“回”字）的中心和模块大小
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    x: f32,
+    y: f32,
+    module: f32,
+    count: u32, // 在多少行中被找到
+}
+
+/// 在二值图像中查找并解码所有二维码
+pub fn detect(bits: &Bits) -> Vec<QrCode> {
+    let mut finders = find_finders(bits);
+    finders.retain(|finder| finder.count >= 2);
+    finders.sort_by_key(|finder| std::cmp::Reverse(finder.count));
+    finders.truncate(30);
+
+    let mut triples = Vec::new();
+    for i in 0..finders.len() {
+        for j in i + 1..finders.len() {
+            for k in j + 1..finders.len() {
+                if let Some((score, corners)) = arrange([finders[i], finders[j], finders[k]]) {
+                    triples.push((score, [i, j, k], corners));
+                }
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // 每个定位图案只属于一个二维码
+    let mut used = vec![false; finders.len()];
+    let mut codes = Vec::new();
+    for (_, indices, corners) in triples {
+        if indices.iter().any(|&i| used[i]) {
+            continue;
+        }
+        if let Some(code) = decode_at(bits, corners) {
+            indices.iter().for_each(|&i| used[i] = true);
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+// 黑白黑白黑的宽度是否符合 1:1:3:1:1
+fn finder_ratio(counts: &[usize; 5]) -> bool {
+    let total: usize = counts.iter().sum();
+    if total < 7 || counts.contains(&0) {
+        return false;
+    }
+    let module = total as f32 / 7.0;
+    let max_variance = module / 2.0;
+    counts.iter().enumerate().all(|(i, &count)| {
+        let expected = if i == 2 { 3.0 } else { 1.0 };
+        (count as f32 - module * expected).abs() < max_variance * expected
+    })
+}
+
+// 逐行寻找 1:1:3:1:1 的黑白序列，再在竖直和水平方向上确认
+fn find_finders(bits: &Bits) -> Vec<Finder> {
+    let mut finders: Vec<Finder> = Vec::new();
+    for y in 0..bits.height {
+        let mut counts = [0usize; 5];
+        let mut state = 0;
+        for x in 0..=bits.width {
+            // 行尾当作白色，以便检查紧贴右边缘的图案
+            let dark = x < bits.width && bits.get(x, y);
+            if dark {
+                if state % 2 == 1 {
+                    state += 1;
+                }
+                counts[state] += 1;
+            } else if state % 2 == 1 {
+                counts[state] += 1;
+            } else if state == 0 && counts[0] == 0 {
+                continue;
+            } else if state == 4 {
+                if finder_ratio(&counts)
+                    && let Some(finder) = confirm_finder(bits, &counts, x, y)
+                {
+                    add_finder(&mut finders, finder);
+                }
+                counts = [counts[2], counts[3], counts[4], 1, 0];
+                state = 3;
+            } else {
+                state += 1;
+                counts[state] += 1;
+            }
+        }
+    }
+    finders
+}
+
+fn confirm_finder(bits: &Bits, counts: &[usize; 5], end: usize, y: usize) -> Option<Finder> {
+    let total: usize = counts.iter().sum();
+    let center_x = end - counts[4] - counts[3] - counts[2] / 2 - 1;
+    let (center_y, vertical) =
+        cross_check(bits.height, y, counts[2], total, |i| bits.get(center_x, i))?;
+    let (center_x, horizontal) = cross_check(bits.width, center_x, counts[2], total, |i| {
+        bits.get(i, center_y)
+    })?;
+    Some(Finder {
+        x: center_x as f32 + 0.5,
+        y: center_y as f32 + 0.5,
+        module: (vertical + horizontal) as f32 / 14.0,
+        count: 1,
+    })
+}
+
+// 从中心沿一条线向两边数出五段黑白，符合比例时返回中心位置和总宽度
+fn cross_check(
+    len: usize,
+    center: usize,
+    max_count: usize,
+    original_total: usize,
+    dark: impl Fn(usize) -> bool,
+) -> Option<(usize, usize)> {
+    let mut counts = [0usize; 5];
+    let mut i = center as isize;
+    for (state, want_dark) in [(2, true), (1, false), (0, true)] {
+        while i >= 0 && dark(i as usize) == want_dark && (state == 2 || counts[state] <= max_count)
+        {
+            counts[state] += 1;
+            i -= 1;
+        }
+        if (state != 0 && i < 0) || counts[state] > max_count && state != 2 {
+            return None;
+        }
+    }
+    let mut i = center + 1;
+    for (state, want_dark) in [(2, true), (3, false), (4, true)] {
+        while i < len && dark(i) == want_dark && (state == 2 || counts[state] <= max_count) {
+            counts[state] += 1;
+            i += 1;
+        }
+        if (state != 4 && i == len) || counts[state] > max_count && state != 2 {
+            return None;
+        }
+    }
+    let total: usize = counts.iter().sum();
+    if 5 * total.abs_diff(original_total) >= 2 * original_total || !finder_ratio(&counts) {
+        return None;
+    }
+    Some((i - counts[4] - counts[3] - counts[2].div_ceil(2), total))
+}
+
+// 与已找到的定位图案位置相同时合并，否则加入列表
+fn add_finder(finders: &mut Vec<Finder>, finder: Finder) {
+    let existing = finders.iter_mut().find(|other| {
+        (other.x - finder.x).abs() <= other.module
+            && (other.y - finder.y).abs() <= other.module
+            && (other.module - finder.module).abs() <= other.module.max(1.0)
+    });
+    match existing {
+        Some(other) => {
+            let n = other.count as f32;
+            other.x = (other.x * n + finder.x) / (n + 1.0);
+            other.y = (other.y * n + finder.y) / (n + 1.0);
+            other.module = (other.module * n + finder.module) / (n + 1.0);
+            other.count += 1;
+        }
+        None => finders.push(finder),
+    }
+}
+
+// 三个定位图案能否构成二维码的三个角：大小相近、两边等长且接近直角。
+// 返回偏差（越小越好）和按左上、右上、左下排列的定位图案
+fn arrange(finders: [Finder; 3]) -> Option<(f32, [Finder; 3])> {
+    let modules = finders.map(|f| f.module);
+    let (min, max) = (
+        modules.iter().cloned().fold(f32::MAX, f32::min),
+        modules.iter().cloned().fold(0.0, f32::max),
+    );
+    if max > min * 1.5 {
+        return None;
+    }
+    let distance = |a: Finder, b: Finder| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+    // 最长的边所对的是左上角
+    let corner = (0..3)
+        .max_by(|&a, &b| {
+            let opposite = |i: usize| distance(finders[(i + 1) % 3], finders[(i + 2) % 3]);
+            opposite(a).total_cmp(&opposite(b))
+        })
+        .unwrap();
+    let top_left = finders[corner];
+    let (mut b, mut c) = (finders[(corner + 1) % 3], finders[(corner + 2) % 3]);
+    let cross = (b.x - top_left.x) * (c.y - top_left.y) - (b.y - top_left.y) * (c.x - top_left.x);
+    if cross < 0.0 {
+        std::mem::swap(&mut b, &mut c);
+    }
+    let (legs_a, legs_b, hypotenuse) =
+        (distance(top_left, b), distance(top_left, c), distance(b, c));
+    let leg_ratio = legs_a.min(legs_b) / legs_a.max(legs_b);
+    let pythagoras = hypotenuse.powi(2) / (legs_a.powi(2) + legs_b.powi(2));
+    let module = modules.iter().sum::<f32>() / 3.0;
+    if leg_ratio < 0.7 || (pythagoras - 1.0).abs() > 0.3 || legs_a.min(legs_b) < module * 12.0 {
+        return None;
+    }
+    Some((
+        (1.0 - leg_ratio) + (pythagoras - 1.0).abs(),
+        [top_left, b, c],
+    ))
+}
+
+// 估计边长（模块数），再按可能的几种边长采样并解码
+fn decode_at(bits: &Bits, [top_left, top_right, bottom_left]: [Finder; 3]) -> Option<QrCode> {
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let distance = |a: Finder, b: Finder| ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt();
+    let across = ((distance(top_left, top_right) / module).round()
+        + (distance(top_left, bottom_left) / module).round())
+        / 2.0;
+    let estimate = across.round() as usize + 7;
+    let nearest = match estimate % 4 {
+        0 => vec![estimate + 1],
+        2 => vec![estimate - 1],
+        3 => vec![estimate - 2, estimate + 2],
+        _ => vec![estimate],
+    };
+    let mut dimensions: Vec<usize> = nearest
+        .iter()
+        .flat_map(|&d| [d, d + 4, d.saturating_sub(4)])
+        .filter(|d| (21..=177).contains(d))
+        .collect();
+    dimensions.dedup();
+
+    let mut tried = Vec::new();
+    while let Some(dimension) = dimensions.first().copied() {
+        dimensions.remove(0);
+        if tried.contains(&dimension) {
+            continue;
+        }
+        tried.push(dimension);
+        let Some(transform) = locate(bits, [top_left, top_right, bottom_left], dimension) else {
+            continue;
+        };
+        let grid = Grid::sample(bits, &transform, dimension);
+        // 版本信息与估计的边长不一致时按版本信息重新采样
+        if let Some(version) = grid.version_info()
+            && 17 + 4 * version != dimension
+        {
+            dimensions.insert(0, 17 + 4 * version);
+            continue;
+        }
+        let text = grid.decode().or_else(|| grid.transposed().decode());
+        if let Some(text) = text {
+            let d = dimension as f32;
+            let corners =
+                [(0.0, 0.0), (d, 0.0), (d, d), (0.0, d)].map(|(u, v)| transform.apply(u, v));
+            return Some(QrCode { text, corners });
+        }
+    }
+    None
+}
+
+// 从模块坐标到图片坐标的透视变换
+struct Homography([f32; 8]);
+
+impl Homography {
+    // 由四对对应点求解
+    fn from_points(pairs: [((f32, f32), (f32, f32)); 4]) -> Option<Homography> {
+        let mut m = [[0f64; 9]; 8];
+        for (k, ((u, v), (x, y))) in pairs.into_iter().enumerate() {
+            let (u, v, x, y) = (u as f64, v as f64, x as f64, y as f64);
+            m[2 * k] = [u, v, 1.0, 0.0, 0.0, 0.0, -u * x, -v * x, x];
+            m[2 * k + 1] = [0.0, 0.0, 0.0, u, v, 1.0, -u * y, -v * y, y];
+        }
+        // 高斯消元
+        for col in 0..8 {
+            let pivot = (col..8).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+            if m[pivot][col].abs() < 1e-9 {
+                return None;
+            }
+            m.swap(col, pivot);
+            let pivot_row = m[col];
+            for (row, values) in m.iter_mut().enumerate() {
+                if row != col {
+                    let factor = values[col] / pivot_row[col];
+                    for (value, pivot_value) in values.iter_mut().zip(pivot_row).skip(col) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+        Some(Homography(std::array::from_fn(|i| {
+            (m[i][8] / m[i][i]) as f32
+        })))
+    }
+
+    fn apply(&self, u: f32, v: f32) -> Point {
+        let h = &self.0;
+        let w = h[6] * u + h[7] * v + 1.0;
+        Point::new(
+            (h[0] * u + h[1] * v + h[2]) / w,
+            (h[3] * u + h[4] * v + h[5]) / w,
+        )
+    }
+}
+
+// 由三个定位图案和右下角的校正图案（版本 1 没有，按平行四边形估计）确定透视变换
+fn locate(bits: &Bits, [tl, tr, bl]: [Finder; 3], dimension: usize) -> Option<Homography> {
+    let d = dimension as f32;
+    let span = d - 7.0;
+    let (ex, ey) = (
+        ((tr.x - tl.x) / span, (tr.y - tl.y) / span),
+        ((bl.x - tl.x) / span, (bl.y - tl.y) / span),
+    );
+    let affine = |u: f32, v: f32| {
+        (
+            tl.x + (u - 3.5) * ex.0 + (v - 3.5) * ey.0,
+            tl.y + (u - 3.5) * ex.1 + (v - 3.5) * ey.1,
+        )
+    };
+    let fourth = if dimension > 21 {
+        let estimate = affine(d - 6.5, d - 6.5);
+        find_alignment(bits, estimate, ex, ey)
+            .map(|point| ((d - 6.5, d - 6.5), point))
+            .unwrap_or(((d - 3.5, d - 3.5), affine(d - 3.5, d - 3.5)))
+    } else {
+        ((d - 3.5, d - 3.5), affine(d - 3.5, d - 3.5))
+    };
+    Homography::from_points([
+        ((3.5, 3.5), (tl.x, tl.y)),
+        ((d - 3.5, 3.5), (tr.x, tr.y)),
+        ((3.5, d - 3.5), (bl.x, bl.y)),
+        fourth,
+    ])
+}
+
+// 在估计位置附近寻找 5×5 的校正图案：外圈黑、中圈白、中心黑
+fn find_alignment(
+    bits: &Bits,
+    estimate: (f32, f32),
+    ex: (f32, f32),
+    ey: (f32, f32),
+) -> Option<(f32, f32)> {
+    let module = (ex.0.hypot(ex.1) + ey.0.hypot(ey.1)) / 2.0;
+    let radius = (module * 4.0).ceil() as isize;
+    // 得分最高的位置都在中心模块内，取它们的平均
+    let mut best = (0, Vec::new());
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (cx, cy) = (estimate.0 + dx as f32, estimate.1 + dy as f32);
+            let mut score = 0;
+            for v in -2i32..=2 {
+                for u in -2i32..=2 {
+                    let expected = u.abs().max(v.abs()) != 1;
+                    let (x, y) = (
+                        cx + u as f32 * ex.0 + v as f32 * ey.0,
+                        cy + u as f32 * ex.1 + v as f32 * ey.1,
+                    );
+                    if bits.get_f(x, y) == expected {
+                        score += 1;
+                    }
+                }
+            }
+            if score > best.0 {
+                best = (score, Vec::new());
+            }
+            if score == best.0 {
+                best.1.push((cx, cy));
+            }
+        }
+    }
+    let (score, points) = best;
+    if score < 23 {
+        return None;
+    }
+    let n = points.len() as f32;
+    let sum = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), point| (x + point.0, y + point.1));
+    Some((sum.0 / n, sum.1 / n))
+}
+
+// 采样得到的模块矩阵
+struct Grid {
+    size: usize,
+    cells: Vec<bool>, // 按行存放，true 为深色
+}
+
+impl Grid {
+    fn sample(bits: &Bits, transform: &Homography, size: usize) -> Grid {
+        let mut cells = Vec::with_capacity(size * size);
+        for row in 0..size {
+            for col in 0..size {
+                let point = transform.apply(col as f32 + 0.5, row as f32 + 0.5);
+                cells.push(bits.get_f(point.x, point.y));
+            }
+        }
+        Grid { size, cells }
+    }
+
+    // 镜像的二维码沿对角线翻转后才能读取
+    fn transposed(&self) -> Grid {
+        let n = self.size;
+        Grid {
+            size: n,
+            cells: (0..n * n)
+                .map(|i| self.cells[(i % n) * n + i / n])
+                .collect(),
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[y * self.size + x]
+    }
+
+    // 左上角周围和右上、左下两处的格式信息（纠错等级和掩码），允许 3 位错误
+    fn format_info(&self) -> Option<(usize, usize)> {
+        let n = self.size;
+        let read = |positions: &[(usize, usize)]| {
+            positions
+                .iter()
+                .fold(0u32, |bits, &(x, y)| bits << 1 | self.get(x, y) as u32)
+        };
+        let mut first: Vec<(usize, usize)> = (0..6).map(|i| (i, 8)).collect();
+        first.extend([(7, 8), (8, 8), (8, 7)]);
+        first.extend((0..6).rev().map(|j| (8, j)));
+        let mut second: Vec<(usize, usize)> = (n - 7..n).rev().map(|j| (8, j)).collect();
+        second.extend((n - 8..n).map(|i| (i, 8)));
+        let (a, b) = (read(&first), read(&second));
+
+        (0..32u32)
+            .map(|data| {
+                let code = bch(data, 10, 0x537) ^ 0x5412;
+                let distance = (code ^ a).count_ones().min((code ^ b).count_ones());
+                (distance, data)
+            })
+            .min()
+            .filter(|&(distance, _)| distance <= 3)
+            .map(|(_, data)| {
+                // 格式信息中的纠错等级：01 L、00 M、11 Q、10 H，转为 L、M、Q、H 的顺序
+                let level = match data >> 3 {
+                    1 => 0,
+                    0 => 1,
+                    3 => 2,
+                    _ => 3,
+                };
+                (level, (data & 7) as usize)
+            })
+    }
+
+    // 版本 7 以上在右上和左下有 18 位的版本信息，允许 3 位错误
+    fn version_info(&self) -> Option<usize> {
+        let n = self.size;
+        if n < 45 {
+            return None;
+        }
+        let mut a = 0u32;
+        let mut b = 0u32;
+        for j in (0..6).rev() {
+            for i in (n - 11..=n - 9).rev() {
+                a = a << 1 | self.get(i, j) as u32;
+                b = b << 1 | self.get(j, i) as u32;
+            }
+        }
+        (7..=40u32)
+            .map(|version| {
+                let code = bch(version, 12, 0x1F25);
+                (
+                    (code ^ a).count_ones().min((code ^ b).count_ones()),
+                    version,
+                )
+            })
+            .min()
+            .filter(|&(distance, _)| distance <= 3)
+            .map(|(_, version)| version as usize)
+    }
+
+    fn decode(&self) -> Option<String> {
+        let size = self.size;
+        let version = (size - 17) / 4;
+        let (level, mask) = self.format_info()?;
+        let function = function_modules(version);
+
+        // 从右下角开始，每两列一组交替向上、向下读取
+        let mut codewords = Vec::new();
+        let (mut byte, mut count) = (0u8, 0);
+        let mut upward = true;
+        let mut right = size - 1;
+        while right > 0 {
+            if right == 6 {
+                right -= 1;
+            }
+            for step in 0..size {
+                let row = if upward { size - 1 - step } else { step };
+                for col in [right, right - 1] {
+                    if function[row * size + col] {
+                        continue;
+                    }
+                    byte = byte << 1 | (self.get(col, row) ^ masked(mask, row, col)) as u8;
+                    count += 1;
+                    if count == 8 {
+                        codewords.push(byte);
+                        (byte, count) = (0, 0);
+                    }
+                }
+            }
+            upward = !upward;
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+
+        let data = correct_blocks(&codewords, version, level)?;
+        decode_segments(&data, version)
+    }
+}
+
+// 格式信息和版本信息使用的 BCH 码
+fn bch(data: u32, ecc_bits: u32, generator: u32) -> u32 {
+    let degree = 31 - generator.leading_zeros();
+    let mut value = data << ecc_bits;
+    for i in (degree..32).rev() {
+        if value & (1 << i) != 0 {
+            value ^= generator << (i - degree);
+        }
+    }
+    data << ecc_bits | value
+}
+
+// 八种数据掩码，row 和 col 为模块坐标
+fn masked(mask: usize, row: usize, col: usize) -> bool {
+    let (i, j) = (row, col);
+    match mask {
+        0 => (i + j) % 2 == 0,
+        1 => i % 2 == 0,
+        2 => j % 3 == 0,
+        3 => (i + j) % 3 == 0,
+        4 => (i / 2 + j / 3) % 2 == 0,
+        5 => (i * j) % 2 + (i * j) % 3 == 0,
+        6 => ((i * j) % 2 + (i * j) % 3) % 2 == 0,
+        _ => ((i + j) % 2 + (i * j) % 3) % 2 == 0,
+    }
+}
+
+// 校正图案中心的行列坐标
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions = vec![6];
+    let mut position = version * 4 + 10;
+    for _ in 1..count {
+        positions.insert(1, position);
+        position -= step;
+    }
+    positions
+}
+
+// 定位、分隔、时序、校正图案以及格式和版本信息占用的模块，不含数据
+fn function_modules(version: usize) -> Vec<bool> {
+    let size = version * 4 + 17;
+    let mut function = vec![false; size * size];
+    let mut region = |x: usize, y: usize, w: usize, h: usize| {
+        for row in y..y + h {
+            for col in x..x + w {
+                function[row * size + col] = true;
+            }
+        }
+    };
+    region(0, 0, 9, 9);
+    region(size - 8, 0, 8, 9);
+    region(0, size - 8, 9, 8);
+    let positions = alignment_positions(version);
+    let last = positions.len().saturating_sub(1);
+    for (a, &x) in positions.iter().enumerate() {
+        for (b, &y) in positions.iter().enumerate() {
+            // 与定位图案重叠的三个位置没有校正图案
+            if [(0, 0), (0, last), (last, 0)].contains(&(a, b)) {
+                continue;
+            }
+            region(x - 2, y - 2, 5, 5);
+        }
+    }
+    region(6, 9, 1, size - 17);
+    region(9, 6, size - 17, 1);
+    if version >= 7 {
+        region(size - 11, 0, 3, 6);
+        region(0, size - 11, 6, 3);
+    }
+    function
+}
+
+// 每块的纠错码字数，按 L、M、Q、H 和版本排列
+const ECC_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+// 纠错块数，按 L、M、Q、H 和版本排列
+const BLOCK_COUNT: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+// 数据区的模块数，除以 8 即码字总数
+fn raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        result -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+// 把交错存放的码字拆回各块，逐块纠错后拼接数据码字
+fn correct_blocks(codewords: &[u8], version: usize, level: usize) -> Option<Vec<u8>> {
+    let blocks = BLOCK_COUNT[level][version] as usize;
+    let ecc = ECC_PER_BLOCK[level][version] as usize;
+    let total = raw_data_modules(version) / 8;
+    if codewords.len() < total {
+        return None;
+    }
+    let short_blocks = blocks - total % blocks;
+    let short_len = total / blocks;
+    let short_data = short_len - ecc;
+
+    let mut split: Vec<Vec<u8>> = (0..blocks)
+        .map(|j| Vec::with_capacity(short_len + (j >= short_blocks) as usize))
+        .collect();
+    let mut next = codewords.iter();
+    for i in 0..=short_len {
+        for (j, block) in split.iter_mut().enumerate() {
+            // 短块在数据的最后一个位置上没有码字
+            if i == short_data && j < short_blocks {
+                continue;
+            }
+            block.push(*next.next()?);
+        }
+    }
+
+    let gf = Galois::new();
+    let mut data = Vec::new();
+    for mut block in split {
+        gf.correct(&mut block, ecc)?;
+        let len = block.len() - ecc;
+        data.extend_from_slice(&block[..len]);
+    }
+    Some(data)
+}
+
+// GF(256) 上的运算，本原多项式 x^8 + x^4 + x^3 + x^2 + 1
+struct Galois {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+impl Galois {
+    fn new() -> Galois {
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut x = 1u16;
+        for (i, value) in exp.iter_mut().take(255).enumerate() {
+            *value = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11D;
+            }
+        }
+        for i in 255..512 {
+            exp[i] = exp[i - 255];
+        }
+        Galois { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize]
+        }
+    }
+
+    // α^power
+    fn pow(&self, power: usize) -> u8 {
+        self.exp[power % 255]
+    }
+
+    // 多项式（低次在前）在 x 处的值
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    // Reed-Solomon 纠错：Berlekamp-Massey 求错误位置多项式，Chien 搜索定位，Forney 算法求错误值。
+    // block 的第一个码字是最高次项，无法纠正时返回 None
+    fn correct(&self, block: &mut [u8], ecc: usize) -> Option<()> {
+        let n = block.len();
+        let syndromes: Vec<u8> = (0..ecc)
+            .map(|j| {
+                let x = self.pow(j);
+                block.iter().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+            })
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(());
+        }
+
+        let mut locator = vec![1u8];
+        let mut previous = vec![1u8];
+        let (mut errors, mut shift, mut last_discrepancy) = (0, 1, 1u8);
+        for step in 0..ecc {
+            let mut discrepancy = syndromes[step];
+            for i in 1..=errors.min(locator.len() - 1) {
+                discrepancy ^= self.mul(locator[i], syndromes[step - i]);
+            }
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let factor = self.div(discrepancy, last_discrepancy);
+            let mut updated = locator.clone();
+            if updated.len() < previous.len() + shift {
+                updated.resize(previous.len() + shift, 0);
+            }
+            for (i, &c) in previous.iter().enumerate() {
+                updated[i + shift] ^= self.mul(factor, c);
+            }
+            if 2 * errors <= step {
+                previous = std::mem::replace(&mut locator, updated);
+                errors = step + 1 - errors;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                locator = updated;
+                shift += 1;
+            }
+        }
+        while locator.last() == Some(&0) {
+            locator.pop();
+        }
+        if errors * 2 > ecc || locator.len() != errors + 1 {
+            return None;
+        }
+
+        // 错误值计算用的 Ω(x) = S(x)Λ(x) mod x^ecc
+        let mut omega = vec![0u8; ecc];
+        for (i, &s) in syndromes.iter().enumerate() {
+            for (j, &l) in locator.iter().enumerate() {
+                if i + j < ecc {
+                    omega[i + j] ^= self.mul(s, l);
+                }
+            }
+        }
+        // Λ 的形式导数只保留奇次项
+        let derivative: Vec<u8> = (1..locator.len())
+            .map(|i| if i % 2 == 1 { locator[i] } else { 0 })
+            .collect();
+
+        let mut found = 0;
+        for (index, value) in block.iter_mut().enumerate() {
+            let power = n - 1 - index;
+            let inverse = self.pow(255 - power % 255);
+            if self.eval(&locator, inverse) != 0 {
+                continue;
+            }
+            let denominator = self.eval(&derivative, inverse);
+            if denominator == 0 {
+                return None;
+            }
+            let magnitude = self.mul(
+                self.pow(power),
+                self.div(self.eval(&omega, inverse), denominator),
+            );
+            *value ^= magnitude;
+            found += 1;
+        }
+        (found == errors).then_some(())
+    }
+}
+
+// 解析数据码字中的各段：数字、字母数字、字节和汉字（日文）模式，以及 ECI 等标记
+fn decode_segments(data: &[u8], version: usize) -> Option<String> {
+    const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+    let size_class = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let mut reader = BitReader { data, position: 0 };
+    let mut bytes = Vec::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0 => break,
+            1 => {
+                let mut count = reader.read([10, 12, 14][size_class])? as usize;
+                while count > 0 {
+                    let (digits, bits) = match count {
+                        1 => (1, 4),
+                        2 => (2, 7),
+                        _ => (3, 10),
+                    };
+                    let value = reader.read(bits)?;
+                    bytes.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            2 => {
+                let mut count = reader.read([9, 11, 13][size_class])? as usize;
+                while count >= 2 {
+                    let value = reader.read(11)? as usize;
+                    bytes.push(*ALPHANUMERIC.get(value / 45)?);
+                    bytes.push(ALPHANUMERIC[value % 45]);
+                    count -= 2;
+                }
+                if count == 1 {
+                    bytes.push(*ALPHANUMERIC.get(reader.read(6)? as usize)?);
+                }
+            }
+            4 => {
+                let count = reader.read([8, 16, 16][size_class])?;
+                for _ in 0..count {
+                    bytes.push(reader.read(8)? as u8);
+                }
+            }
+            // 汉字模式需要 Shift JIS 码表，用替换字符代替
+            8 => {
+                let count = reader.read([8, 10, 12][size_class])?;
+                for _ in 0..count {
+                    reader.read(13)?;
+                    bytes.extend("\u{FFFD}".bytes());
+                }
+            }
+            // ECI 指定的字符集只影响字节模式，统一按 UTF-8 尝试
+            7 => {
+                let first = reader.read(8)?;
+                if first & 0x80 != 0 {
+                    reader.read(if first & 0x40 == 0 { 8 } else { 16 })?;
+                }
+            }
+            3 => {
+                reader.read(16)?;
+            }
+            5 => {}
+            9 => {
+                reader.read(8)?;
+            }
+            _ => return None,
+        }
+    }
+    Some(match String::from_utf8(bytes) {
+        Ok(text) => text,
+        // 不是 UTF-8 时按 ISO-8859-1 解释
+        Err(e) => e.into_bytes().into_iter().map(char::from).collect(),
+    })
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn read(&mut self, count: usize) -> Option<u32> {
+        if count > self.remaining() {
+            return None;
+        }
+        let mut value = 0u32;
+        for _ in 0..count {
+            let bit = self.data[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = value << 1 | bit as u32;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes::{self, CodeKind};
+    use image::{Rgb, RgbImage};
+
+    // 用 qrcode 编码器生成的样例，# 为深色模块
+    const EXAMPLE_URL: [&str; 25] = [
+        "#######.###.#.#...#######",
+        "#.....#..######.#.#.....#",
+        "#.###.#.##..##..#.#.###.#",
+        "#.###.#....####...#.###.#",
+        "#.###.#..##...#...#.###.#",
+        "#.....#.##..#.#...#.....#",
+        "#######.#.#.#.#.#.#######",
+        "..........###.###........",
+        "#.#...##..#...#.#..#..#.#",
+        ".###.#.#.#.###.#.###.#.##",
+        ".###.###.#.#...#.#..###.#",
+        "#.###..#.#....#.#..#.#...",
+        "####.##.####.####.##....#",
+        "..#....#.##..#.##.##...##",
+        "###.#.#.##.#...####..##.#",
+        "..##.#.#.#...#..##.###...",
+        "###.###.#..###..#####..#.",
+        "........#...#...#...#...#",
+        "#######.#.#.###.#.#.#...#",
+        "#.....#...###.###...#....",
+        "#.###.#......########...#",
+        "#.###.#...#..##..#..#.##.",
+        "#.###.#.####...###.###.##",
+        "#.....#..##..#.######....",
+        "#######.#..###..#.#..#..#",
+    ];
+
+    const HELLO_WORLD: [&str; 21] = [
+        "#######.##....#######",
+        "#.....#.#..#..#.....#",
+        "#.###.#.#..##.#.###.#",
+        "#.###.#.#.....#.###.#",
+        "#.###.#.#.#...#.###.#",
+        "#.....#...#...#.....#",
+        "#######.#.#.#.#######",
+        "........#............",
+        ".##.#.##....#.#.#####",
+        ".#......####....#...#",
+        "..##.###.##...#.##...",
+        ".##.##.#..##.#.#.###.",
+        "#...#.#.#.###.###.#.#",
+        "........##.#..#...#.#",
+        "#######.#.#....#.##..",
+        "#.....#..#.##.##.#...",
+        "#.###.#.#.#...#######",
+        "#.###.#..#.#.#.#...#.",
+        "#.###.#.#..#.###.#..#",
+        "#.....#.#.####...#.##",
+        "#######....#.###....#",
+    ];
+
+    // 每个模块画成 4×4 像素，四周留 4 个模块的空白
+    fn render(rows: &[&str]) -> RgbImage {
+        let size = (rows.len() as u32 + 8) * 4;
+        RgbImage::from_fn(size, size, |x, y| {
+            let (col, row) = (
+                (x / 4).wrapping_sub(4) as usize,
+                (y / 4).wrapping_sub(4) as usize,
+            );
+            let dark = rows
+                .get(row)
+                .and_then(|line| line.as_bytes().get(col))
+                .is_some_and(|&c| c == b'#');
+            if dark {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        })
+    }
+
+    fn qr_texts(img: &RgbImage) -> Vec<String> {
+        codes::scan(img)
+            .into_iter()
+            .filter(|code| code.kind == CodeKind::Qr)
+            .map(|code| code.text)
+            .collect()
+    }
+
+    #[test]
+    fn decodes_known_samples() {
+        assert_eq!(qr_texts(&render(&EXAMPLE_URL)), ["https://example.com/"]);
+        assert_eq!(qr_texts(&render(&HELLO_WORLD)), ["HELLO WORLD"]);
+    }
+
+    #[test]
+    fn damaged_code_is_not_decoded() {
+        // 保留定位图案，把右下角的数据区全部反色，超出纠错能力
+        let rows: Vec<String> = HELLO_WORLD
+            .iter()
+            .enumerate()
+            .map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(x, c)| match (x >= 9 && y >= 9, c) {
+                        (true, '#') => '.',
+                        (true, _) => '#',
+                        (false, c) => c,
+                    })
+                    .collect()
+            })
+            .collect();
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        assert!(qr_texts(&render(&rows)).is_empty());
+    }
+
+    #[test]
+    fn noise_finds_nothing() {
+        let mut seed = 12345u32;
+        let img = RgbImage::from_fn(200, 200, |_, _| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let v = if seed >> 16 & 1 == 0 { 0 } else { 255 };
+            Rgb([v, v, v])
+        });
+        assert!(qr_texts(&img).is_empty());
+        assert!(qr_texts(&RgbImage::new(1, 1)).is_empty());
+    }
+
+    #[test]
+    fn malformed_data_returns_none() {
+        // 未定义的模式
+        assert_eq!(decode_segments(&[0xF0], 1), None);
+        // 字节模式声明 5 个字节，实际只有 1 个
+        assert_eq!(decode_segments(&[0x40, 0x54, 0x10], 1), None);
+        // 码字数量不足
+        assert_eq!(correct_blocks(&[0; 10], 1, 0), None);
+        // 错误超过纠错能力
+        let garbage: Vec<u8> = (0..26u8).map(|i| i.wrapping_mul(97) ^ 0x5A).collect();
+        assert_eq!(correct_blocks(&garbage, 1, 0), None);
+    }
+}
@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use crate::cloud;
+use crate::cloud_storage::{self, CloudConfig};
+use crate::config;
+use crate::i18n::tr;
+
+/// 远程缓存的大小上限，超过后删除最久没有打开的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CacheLimit {
+    Mb256,
+    #[default]
+    Gb1,
+    Gb4,
+    Unlimited,
+}
+
+impl CacheLimit {
+    pub const ALL: [CacheLimit; 4] = [
+        CacheLimit::Mb256,
+        CacheLimit::Gb1,
+        CacheLimit::Gb4,
+        CacheLimit::Unlimited,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CacheLimit::Mb256 => "256 MB",
+            CacheLimit::Gb1 => "1 GB",
+            CacheLimit::Gb4 => "4 GB",
+            CacheLimit::Unlimited => tr("Unlimited"),
+        }
+    }
+
+    fn bytes(self) -> Option<u64> {
+        match self {
+            CacheLimit::Mb256 => Some(256 << 20),
+            CacheLimit::Gb1 => Some(1 << 30),
+            CacheLimit::Gb4 => Some(4 << 30),
+            CacheLimit::Unlimited => None,
+        }
+    }
+}
+
+// 缓存中的一个对象
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    path: PathBuf,        // 本地副本
+    etag: Option<String>, // 下载时对象的 ETag，不知道时为 None
+    size: u64,
+    accessed: i64,        // 最后一次打开的时间（Unix 秒）
+}
+
+/// 下载到本地缓存的云端对象，键为“存储桶标识/对象键”
+/// ETag 变化后需要重新下载，离线时直接使用缓存中的副本
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl RemoteCache {
+    pub fn load_from_file(path: PathBuf) -> Result<RemoteCache, Box<dyn Error>> {
+        if !path.is_file() {
+            return Ok(RemoteCache::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 缓存中的副本；etag 为 None 时（离线或还不知道最新版本）只要有完整的副本就返回
+    pub fn get(&mut self, config: &CloudConfig, key: &str, etag: Option<&str>) -> Option<PathBuf> {
+        let id = entry_id(config, key)?;
+        let entry = self.entries.get(&id)?;
+        // 副本被删掉或不完整时忘掉这一项
+        let complete =
+            fs::metadata(&entry.path).is_ok_and(|meta| meta.is_file() && meta.len() == entry.size);
+        if !complete {
+            self.entries.remove(&id);
+            return None;
+        }
+        if let Some(etag) = etag
+            && !entry
+                .etag
+                .as_deref()
+                .is_some_and(|cached| cached.eq_ignore_ascii_case(etag))
+        {
+            return None;
+        }
+        let entry = self.entries.get_mut(&id)?;
+        entry.accessed = chrono::Utc::now().timestamp();
+        Some(entry.path.clone())
+    }
+
+    /// 记录下载完成的副本
+    pub fn record(&mut self, config: &CloudConfig, key: &str, path: PathBuf, etag: Option<String>) {
+        let (Some(id), Ok(meta)) = (entry_id(config, key), fs::metadata(&path)) else {
+            return;
+        };
+        self.entries.insert(
+            id,
+            CacheEntry {
+                path,
+                etag,
+                size: meta.len(),
+                accessed: chrono::Utc::now().timestamp(),
+            },
+        );
+    }
+
+    /// 云端对象被删除时一起删除副本
+    pub fn remove(&mut self, config: &CloudConfig, key: &str) {
+        if let Some(id) = entry_id(config, key) {
+            self.entries.remove(&id);
+        }
+        if let Some(path) = cloud::cache_path(config, key) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// 云端对象改名时副本跟着改名，不用重新下载
+    pub fn rename(&mut self, config: &CloudConfig, from: &str, to: &str) {
+        let (Some(from_id), Some(to_id), Some(source), Some(target)) = (
+            entry_id(config, from),
+            entry_id(config, to),
+            cloud::cache_path(config, from),
+            cloud::cache_path(config, to),
+        ) else {
+            return;
+        };
+        if let Some(parent) = target.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let moved = fs::rename(source, &target).is_ok();
+        match self.entries.remove(&from_id) {
+            Some(mut entry) if moved => {
+                entry.path = target;
+                self.entries.insert(to_id, entry);
+            }
+            _ => {
+                self.entries.remove(&to_id);
+            }
+        }
+    }
+
+    /// 缓存中副本的总大小
+    pub fn total_size(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// 超过上限时删除最久没有打开的副本，返回索引是否变化
+    pub fn evict(&mut self, limit: CacheLimit) -> bool {
+        let Some(limit) = limit.bytes() else {
+            return false;
+        };
+        let mut total = self.total_size();
+        if total <= limit {
+            return false;
+        }
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| (entry.accessed, id.clone()))
+            .collect();
+        entries.sort();
+        // 最近打开的一张即使单独超过上限也保留
+        entries.pop();
+        for (_, id) in entries {
+            if total <= limit {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&id) {
+                match fs::remove_file(&entry.path) {
+                    Ok(()) => debug!(path = %entry.path.display(), "Evicted cached download"),
+                    Err(e) => warn!(
+                        path = %entry.path.display(),
+                        error = %e,
+                        "Failed to evict cached download"
+                    ),
+                }
+                total -= entry.size;
+            }
+        }
+        true
+    }
+
+    /// 删除所有存储桶的缓存（包括没有记录的文件和下载到一半的文件），返回释放的字节数
+    pub fn clear(&mut self) -> Result<u64, String> {
+        let freed = self.total_size();
+        self.entries.clear();
+        if let Some(root) = cloud_storage::cache_root()
+            && root.exists()
+        {
+            fs::remove_dir_all(root).map_err(|e| e.to_string())?;
+        }
+        Ok(freed)
+    }
+}
+
+// 索引中的键：存储桶标识/对象键
+fn entry_id(config: &CloudConfig, key: &str) -> Option<String> {
+    Some(format!("{}/{}", config.bucket_id()?, key))
+}
+
+/// 缓存索引文件的位置
+pub fn index_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("remote_cache.json"))
+}
@@ -0,0 +1,297 @@
+use iced::futures::Stream;
+use std::path::PathBuf;
+use tracing::warn;
+
+// 控制接口：Linux 上为会话总线上的 DBus 服务，其他系统为本机的命名管道（Unix 上为套接字文件），
+// 每个连接发送一行命令，例如 "next"、"open /path/to/image.jpg"，回复 "ok" 或 "error 原因"
+#[cfg(target_os = "linux")]
+const DBUS_NAME: &str = "io.github.dew667.ImageBrowser";
+#[cfg(target_os = "linux")]
+const DBUS_PATH: &str = "/io/github/dew667/ImageBrowser";
+#[cfg(target_os = "linux")]
+const DBUS_INTERFACE: &str = "io.github.dew667.ImageBrowser.Control";
+
+/// 可以从脚本或媒体键发送的控制命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Next,
+    Previous,
+    Open(PathBuf),
+    PauseSlideshow, // 暂停或继续幻灯片放映
+    StopSlideshow,
+    Fullscreen, // 切换全屏
+}
+
+impl Command {
+    /// 解析一行命令；命令行的 --remote 也使用这些名称
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match (name, argument.trim()) {
+            ("next", "") => Ok(Command::Next),
+            ("previous", "") => Ok(Command::Previous),
+            ("open", "") => Err("open needs a path".to_string()),
+            ("open", path) => Ok(Command::Open(PathBuf::from(path))),
+            ("pause-slideshow", "") => Ok(Command::PauseSlideshow),
+            ("stop-slideshow", "") => Ok(Command::StopSlideshow),
+            ("fullscreen", "") => Ok(Command::Fullscreen),
+            _ => Err(format!(
+                "unknown command '{}', expected next, previous, open PATH, \
+                 pause-slideshow, stop-slideshow or fullscreen",
+                line
+            )),
+        }
+    }
+
+    /// 发送时使用的一行文字，与 parse 对应
+    pub fn line(&self) -> String {
+        match self {
+            Command::Next => "next".to_string(),
+            Command::Previous => "previous".to_string(),
+            Command::Open(path) => format!("open {}", path.display()),
+            Command::PauseSlideshow => "pause-slideshow".to_string(),
+            Command::StopSlideshow => "stop-slideshow".to_string(),
+            Command::Fullscreen => "fullscreen".to_string(),
+        }
+    }
+}
+
+/// 把命令发给正在运行的实例，没有实例在运行时返回错误
+pub fn send(command: &Command) -> Result<(), String> {
+    let command = match command {
+        Command::Open(path) => Command::Open(std::path::absolute(path).map_err(|e| e.to_string())?),
+        command => command.clone(),
+    };
+    platform::send(&command)
+}
+
+/// 收到的控制命令；无法注册服务时记录警告，不返回任何命令
+pub fn commands() -> impl Stream<Item = Command> {
+    iced::stream::channel(4, move |output| async move {
+        if let Err(e) = platform::serve(output).await {
+            warn!("Failed to start the remote control interface: {}", e);
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{Command, DBUS_INTERFACE, DBUS_NAME, DBUS_PATH};
+    use iced::futures::SinkExt;
+    use iced::futures::channel::mpsc::Sender;
+    use tracing::debug;
+    use zbus::fdo;
+
+    // 对象上的 io.github.dew667.ImageBrowser.Control 接口，方法与命令一一对应
+    struct Control {
+        output: Sender<Command>,
+    }
+
+    #[zbus::interface(name = "io.github.dew667.ImageBrowser.Control")]
+    impl Control {
+        async fn next(&self) -> fdo::Result<()> {
+            self.forward(Command::Next).await
+        }
+
+        async fn previous(&self) -> fdo::Result<()> {
+            self.forward(Command::Previous).await
+        }
+
+        async fn open(&self, path: String) -> fdo::Result<()> {
+            self.forward(Command::Open(path.into())).await
+        }
+
+        async fn pause_slideshow(&self) -> fdo::Result<()> {
+            self.forward(Command::PauseSlideshow).await
+        }
+
+        async fn stop_slideshow(&self) -> fdo::Result<()> {
+            self.forward(Command::StopSlideshow).await
+        }
+
+        async fn toggle_fullscreen(&self) -> fdo::Result<()> {
+            self.forward(Command::Fullscreen).await
+        }
+    }
+
+    impl Control {
+        async fn forward(&self, command: Command) -> fdo::Result<()> {
+            debug!(?command, "Remote control command");
+            self.output
+                .clone()
+                .send(command)
+                .await
+                .map_err(|e| fdo::Error::Failed(e.to_string()))
+        }
+    }
+
+    // 在会话总线上注册服务名，之后一直保持连接
+    pub async fn serve(output: Sender<Command>) -> Result<(), String> {
+        let _connection = zbus::connection::Builder::session()
+            .and_then(|builder| builder.name(DBUS_NAME))
+            .and_then(|builder| builder.serve_at(DBUS_PATH, Control { output }))
+            .map_err(|e| e.to_string())?
+            .build()
+            .await
+            .map_err(|e| e.to_string())?;
+        std::future::pending::<()>().await;
+        Ok(())
+    }
+
+    pub fn send(command: &Command) -> Result<(), String> {
+        let connection = zbus::blocking::Connection::session().map_err(|e| e.to_string())?;
+        let interface = Some(DBUS_INTERFACE);
+        let reply = match command {
+            Command::Open(path) => connection.call_method(
+                Some(DBUS_NAME),
+                DBUS_PATH,
+                interface,
+                "Open",
+                &(path.to_string_lossy().as_ref(),),
+            ),
+            command => {
+                let method = match command {
+                    Command::Next => "Next",
+                    Command::Previous => "Previous",
+                    Command::PauseSlideshow => "PauseSlideshow",
+                    Command::StopSlideshow => "StopSlideshow",
+                    _ => "ToggleFullscreen",
+                };
+                connection.call_method(Some(DBUS_NAME), DBUS_PATH, interface, method, &())
+            }
+        };
+        reply.map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::Command;
+    use iced::futures::SinkExt;
+    use iced::futures::channel::mpsc::Sender;
+    use std::io::{BufRead, BufReader, Write};
+    use tracing::debug;
+
+    // 每个连接在自己的任务中处理，不发送命令的客户端等待这么久后断开，不会挡住之后的连接
+    #[cfg(any(unix, windows))]
+    const READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+    // 当前用户的套接字文件，放在数据目录中
+    #[cfg(unix)]
+    fn socket_path() -> Result<std::path::PathBuf, String> {
+        crate::config::data_dir()
+            .map(|dir| dir.join("control.sock"))
+            .ok_or_else(|| "no data directory".to_string())
+    }
+
+    #[cfg(unix)]
+    pub async fn serve(output: Sender<Command>) -> Result<(), String> {
+        use std::os::unix::net::UnixStream;
+        let path = socket_path()?;
+        // 能连上时已有实例在监听；连不上的文件是上次异常退出时留下的
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!("{} is in use by another instance", path.display()));
+        }
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).map_err(|e| e.to_string())?;
+        loop {
+            if let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle(stream, output.clone()));
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn send(command: &Command) -> Result<(), String> {
+        let stream =
+            std::os::unix::net::UnixStream::connect(socket_path()?).map_err(|e| e.to_string())?;
+        exchange(stream, command)
+    }
+
+    // 命名管道按用户区分，不同用户的实例互不影响
+    #[cfg(windows)]
+    fn pipe_name() -> String {
+        format!(
+            r"\\.\pipe\image-browser-{}",
+            std::env::var("USERNAME").unwrap_or_default()
+        )
+    }
+
+    #[cfg(windows)]
+    pub async fn serve(output: Sender<Command>) -> Result<(), String> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+        let name = pipe_name();
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&name)
+            .map_err(|e| e.to_string())?;
+        loop {
+            server.connect().await.map_err(|e| e.to_string())?;
+            // 处理这个连接前先创建下一个管道实例，避免客户端连不上
+            let client = server;
+            server = ServerOptions::new()
+                .create(&name)
+                .map_err(|e| e.to_string())?;
+            tokio::spawn(handle(client, output.clone()));
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn send(command: &Command) -> Result<(), String> {
+        let pipe = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(pipe_name())
+            .map_err(|e| e.to_string())?;
+        exchange(pipe, command)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub async fn serve(_output: Sender<Command>) -> Result<(), String> {
+        Err("remote control is not supported on this system".to_string())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn send(_command: &Command) -> Result<(), String> {
+        Err("remote control is not supported on this system".to_string())
+    }
+
+    // 发送一行命令并等待回复
+    #[cfg(any(unix, windows))]
+    fn exchange<S: std::io::Read + Write>(mut stream: S, command: &Command) -> Result<(), String> {
+        writeln!(stream, "{}", command.line()).map_err(|e| e.to_string())?;
+        let mut reply = String::new();
+        BufReader::new(stream)
+            .read_line(&mut reply)
+            .map_err(|e| e.to_string())?;
+        match reply.trim() {
+            "ok" => Ok(()),
+            reply => Err(reply.strip_prefix("error ").unwrap_or(reply).to_string()),
+        }
+    }
+
+    // 读取一行命令并回复，命令交给界面处理
+    #[cfg(any(unix, windows))]
+    async fn handle<S>(stream: S, mut output: Sender<Command>)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+        let mut stream = tokio::io::BufReader::new(stream);
+        let mut line = String::new();
+        let read = tokio::time::timeout(READ_TIMEOUT, stream.read_line(&mut line)).await;
+        if !matches!(read, Ok(Ok(_))) {
+            debug!("Remote control client sent no command");
+            return;
+        }
+        let reply = match Command::parse(&line) {
+            Ok(command) => {
+                debug!(?command, "Remote control command");
+                let _ = output.send(command).await;
+                "ok\n".to_string()
+            }
+            Err(e) => format!("error {}\n", e),
+        };
+        let _ = stream.get_mut().write_all(reply.as_bytes()).await;
+    }
+}
@@ -0,0 +1,210 @@
+use iced::futures::{SinkExt, Stream};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+
+use crate::file_tree::FileTreeEntry;
+use crate::formats;
+
+// 每扫描这么多目录项发送一批结果
+const BATCH_SIZE: usize = 64;
+
+/// 一批扫描结果
+#[derive(Debug, Clone, Default)]
+pub struct ScanBatch {
+    pub children: Vec<FileTreeEntry>, // 目录树中显示的子目录和图片
+    pub images: Vec<PathBuf>,         // 加入图片库的图片
+    pub scanned: usize,               // 到目前为止扫描过的目录项数量
+}
+
+#[derive(Debug, Clone)]
+pub enum ScanEvent {
+    Batch(ScanBatch),
+    Failed(std::io::ErrorKind, String), // 无法读取目录，之后仍会发送 Finished
+    Finished,
+}
+
+/// 正在进行的目录扫描，被替换或丢弃时自动取消后台任务
+pub struct ActiveScan {
+    pub id: u64,
+    pub dir: PathBuf,
+    pub fill_tree: bool, // 目录刚展开且子项还没加载，需要把结果填入目录树
+    pub started: bool,   // 是否已经收到第一批结果
+    pub scanned: usize,
+    pub stamp: FolderStamp, // 开始扫描时文件夹的状态
+    _handle: iced::task::Handle,
+}
+
+impl ActiveScan {
+    pub fn new(id: u64, dir: PathBuf, fill_tree: bool, handle: iced::task::Handle) -> Self {
+        ActiveScan {
+            id,
+            stamp: FolderStamp::new(dir.clone()),
+            dir,
+            fill_tree,
+            started: false,
+            scanned: 0,
+            _handle: handle.abort_on_drop(),
+        }
+    }
+}
+
+/// 读取文件夹时记下它的修改时间，之后比较就能知道其中是否增删过文件，不需要监视文件系统
+#[derive(Debug, Clone)]
+pub struct FolderStamp {
+    pub dir: PathBuf,
+    pub scanned_at: SystemTime, // 在这之后修改过的图片需要重新生成缩略图
+    modified: Option<SystemTime>,
+}
+
+impl FolderStamp {
+    pub fn new(dir: PathBuf) -> Self {
+        FolderStamp {
+            modified: modified(&dir),
+            dir,
+            scanned_at: SystemTime::now(),
+        }
+    }
+
+    /// 文件夹中增加、删除或重命名过文件
+    pub fn is_stale(&self) -> bool {
+        modified(&self.dir) != self.modified
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+/// 手动刷新时重新读取的文件夹内容
+#[derive(Debug, Clone)]
+pub struct Rescan {
+    pub stamp: FolderStamp,
+    pub children: Vec<FileTreeEntry>,
+    pub images: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>, // 上次读取之后修改过的图片
+}
+
+/// 在后台重新读取文件夹；since 为上次读取的时间，之后修改过的图片记入 modified
+pub async fn rescan(
+    dir: PathBuf,
+    show_hidden: bool,
+    since: Option<SystemTime>,
+) -> Result<Rescan, String> {
+    tokio::task::spawn_blocking(move || {
+        let stamp = FolderStamp::new(dir.clone());
+        let mut batch = ScanBatch::default();
+        for entry in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+            add_entry(&mut batch, entry.path(), show_hidden);
+        }
+        let modified = match since {
+            Some(since) => batch
+                .images
+                .iter()
+                .filter(|path| modified(path).is_some_and(|time| time > since))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(Rescan {
+            stamp,
+            children: batch.children,
+            images: batch.images,
+            modified,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 在阻塞线程池中读取目录，分批返回结果，避免网络盘上的大目录卡住界面
+/// show_hidden 为 false 时跳过隐藏的文件和文件夹
+pub fn scan_directory(dir: PathBuf, show_hidden: bool) -> impl Stream<Item = ScanEvent> {
+    iced::stream::channel(4, move |mut output| async move {
+        let (sender, mut receiver) = mpsc::channel(4);
+        let worker = tokio::task::spawn_blocking(move || read_entries(&dir, show_hidden, &sender));
+        while let Some(batch) = receiver.recv().await {
+            let _ = output.send(ScanEvent::Batch(batch)).await;
+        }
+        if let Ok(Err(e)) = worker.await {
+            let _ = output
+                .send(ScanEvent::Failed(e.kind(), e.to_string()))
+                .await;
+        }
+        let _ = output.send(ScanEvent::Finished).await;
+    })
+}
+
+// 读取失败时不发送任何结果；接收端被丢弃（扫描已取消）时提前结束
+fn read_entries(
+    dir: &Path,
+    show_hidden: bool,
+    sender: &mpsc::Sender<ScanBatch>,
+) -> std::io::Result<()> {
+    let entries = fs::read_dir(dir)?;
+    let mut batch = ScanBatch::default();
+    let mut scanned = 0;
+    for entry in entries.flatten() {
+        scanned += 1;
+        add_entry(&mut batch, entry.path(), show_hidden);
+        if scanned % BATCH_SIZE == 0 {
+            batch.scanned = scanned;
+            if sender.blocking_send(std::mem::take(&mut batch)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    batch.scanned = scanned;
+    let _ = sender.blocking_send(batch);
+    Ok(())
+}
+
+/// 同步读取目录树中显示的子项，用于启动时加载主目录
+pub fn read_children(dir: &Path, show_hidden: bool) -> Vec<FileTreeEntry> {
+    let mut batch = ScanBatch::default();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            add_entry(&mut batch, entry.path(), show_hidden);
+        }
+    }
+    batch.children
+}
+
+/// 以 . 开头的文件和文件夹，以及 Windows 上带隐藏属性的
+pub fn is_hidden(path: &Path) -> bool {
+    if path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if fs::symlink_metadata(path)
+            .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+// 非图片文件不出现在目录树中，隐藏的文件和文件夹按设置跳过
+fn add_entry(batch: &mut ScanBatch, child_path: PathBuf, show_hidden: bool) {
+    if !show_hidden && is_hidden(&child_path) {
+        return;
+    }
+    let child_entry = FileTreeEntry::default(child_path.clone());
+    if child_entry.is_directory() {
+        batch.children.push(child_entry);
+        return;
+    }
+
+    if formats::is_image(&child_path) {
+        batch.images.push(child_path);
+        batch.children.push(child_entry);
+    }
+}
@@ -0,0 +1,202 @@
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+use crate::batch::BatchOptions;
+use crate::config;
+use crate::export::ExportFormat;
+use crate::file_tree::file_name;
+use crate::xmp_iptc;
+
+// 脚本最多执行的操作数，避免死循环一直占用后台线程
+const MAX_OPERATIONS: u64 = 100_000_000;
+
+/// 示例脚本，还没有运行过脚本时显示
+pub const EXAMPLE: &str = r#"// 把评了星的图片缩小到 2048 像素以内，导出到当前文件夹下的 out
+let starred = images().filter(|path| rating(path) > 0);
+print(`${starred.len()} starred images`);
+export_images(starred, #{ size: 2048, format: "jpeg", quality: 90, dir: "./out" });
+"#;
+
+/// 脚本运行时可以读取的内容
+#[derive(Debug, Clone)]
+pub struct ScriptContext {
+    pub images: Vec<PathBuf>,    // 当前图片库
+    pub selection: Vec<PathBuf>, // 选中的图片
+    pub folder: PathBuf,         // 当前文件夹，导出目录的相对路径从这里算起
+}
+
+/// 脚本运行的结果：输出的文字、加入任务队列的导出和要选中的图片
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOutput {
+    pub log: Vec<String>,
+    pub exports: Vec<(BatchOptions, Vec<PathBuf>)>,
+    pub select: Option<Vec<PathBuf>>,
+    pub error: Option<String>,
+}
+
+/// 上次运行的脚本，没有时返回示例脚本
+pub fn load_last() -> String {
+    last_script_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_else(|| EXAMPLE.to_string())
+}
+
+/// 保存运行的脚本，下次启动时恢复
+pub fn save_last(source: &str) {
+    if let Some(path) = last_script_path()
+        && let Err(e) = fs::write(&path, source)
+    {
+        warn!("Failed to save script to {}: {}", path.display(), e);
+    }
+}
+
+fn last_script_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("script.rhai"))
+}
+
+/// 运行脚本，会阻塞到脚本结束；导出只记录下来，由调用方加入任务队列
+pub fn run(source: &str, context: ScriptContext) -> ScriptOutput {
+    let output = Arc::new(Mutex::new(ScriptOutput::default()));
+    let context = Arc::new(context);
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let log = output.clone();
+    engine.on_print(move |text| log.lock().unwrap().log.push(text.to_string()));
+    let log = output.clone();
+    engine.on_debug(move |text, _, pos| {
+        log.lock().unwrap().log.push(format!("[{}] {}", pos, text));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("images", move || paths_to_array(&ctx.images));
+    let ctx = context.clone();
+    engine.register_fn("selection", move || paths_to_array(&ctx.selection));
+    let ctx = context.clone();
+    engine.register_fn("folder", move || ctx.folder.display().to_string());
+    engine.register_fn("name", |path: &str| file_name(Path::new(path)));
+    engine.register_fn("rating", |path: &str| {
+        xmp_iptc::read_descriptive_metadata(Path::new(path))
+            .and_then(|metadata| metadata.rating)
+            .map_or(0, i64::from)
+    });
+    engine.register_fn("keywords", |path: &str| -> Array {
+        xmp_iptc::read_descriptive_metadata(Path::new(path))
+            .map(|metadata| metadata.keywords.into_iter().map(Dynamic::from).collect())
+            .unwrap_or_default()
+    });
+
+    let exports = output.clone();
+    let ctx = context.clone();
+    engine.register_fn(
+        "export_images",
+        move |paths: Array, options: Map| -> Result<(), Box<EvalAltResult>> {
+            let options = batch_options(&options, &ctx.folder)?;
+            options.plan()?;
+            let paths = array_to_paths(paths)?;
+            exports.lock().unwrap().exports.push((options, paths));
+            Ok(())
+        },
+    );
+    let select = output.clone();
+    engine.register_fn(
+        "select",
+        move |paths: Array| -> Result<(), Box<EvalAltResult>> {
+            select.lock().unwrap().select = Some(array_to_paths(paths)?);
+            Ok(())
+        },
+    );
+
+    let result = engine.run(source);
+    drop(engine);
+    let mut output = Arc::try_unwrap(output)
+        .map(|output| output.into_inner().unwrap())
+        .unwrap_or_else(|output| output.lock().unwrap().clone());
+    if let Err(e) = result {
+        // 出错时不执行已经记录的导出和选择
+        output.exports.clear();
+        output.select = None;
+        output.error = Some(e.to_string());
+    }
+    output
+}
+
+fn paths_to_array(paths: &[PathBuf]) -> Array {
+    paths
+        .iter()
+        .map(|path| Dynamic::from(path.display().to_string()))
+        .collect()
+}
+
+fn array_to_paths(paths: Array) -> Result<Vec<PathBuf>, Box<EvalAltResult>> {
+    paths
+        .into_iter()
+        .map(|path| {
+            path.into_string()
+                .map(PathBuf::from)
+                .map_err(|kind| format!("expected a path string, got {}", kind).into())
+        })
+        .collect()
+}
+
+// 把脚本中的选项表转换为批处理选项：
+// size / width / height 为最大尺寸，format 和 quality 为输出格式，
+// dir 为输出目录（相对于当前文件夹），rename 为重命名模板，watermark 为水印图片
+fn batch_options(options: &Map, folder: &Path) -> Result<BatchOptions, Box<EvalAltResult>> {
+    let mut batch = BatchOptions::default();
+    let (mut width, mut height) = (None, None);
+    for (key, value) in options {
+        match key.as_str() {
+            "size" | "width" | "height" => {
+                let size = value
+                    .as_int()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| format!("{} must be a positive number", key))?;
+                if key != "height" {
+                    width = Some(size);
+                }
+                if key != "width" {
+                    height = Some(size);
+                }
+            }
+            "format" => {
+                let format = value.to_string();
+                batch.encode.format = ExportFormat::from_extension(&format)
+                    .ok_or_else(|| format!("unsupported format {}", format))?;
+                batch.convert_enabled = true;
+            }
+            "quality" => {
+                batch.encode.quality = value
+                    .as_int()
+                    .ok()
+                    .filter(|quality| (1..=100).contains(quality))
+                    .ok_or("quality must be between 1 and 100")?
+                    as u8;
+            }
+            "dir" => batch.output_dir = Some(resolve(folder, &value.to_string())),
+            "rename" => {
+                batch.rename_enabled = true;
+                batch.rename_pattern = value.to_string();
+            }
+            "watermark" => batch.watermark = Some(resolve(folder, &value.to_string())),
+            _ => return Err(format!("unknown export option {}", key).into()),
+        }
+    }
+    // 只限制宽或高时另一边不限制
+    if width.is_some() || height.is_some() {
+        batch.resize_enabled = true;
+        batch.max_width = width.map_or(u32::MAX.to_string(), |w| w.to_string());
+        batch.max_height = height.map_or(u32::MAX.to_string(), |h| h.to_string());
+    }
+    Ok(batch)
+}
+
+// 相对路径从当前文件夹算起，去掉开头的 ./
+fn resolve(folder: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    folder.join(path.strip_prefix(".").unwrap_or(path))
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::catalog::AddedWithin;
+use crate::cloud_storage::{CloudConfig, ShareExpiry};
+use crate::config;
+use crate::remote_cache::CacheLimit;
+use crate::sync::{ConflictPolicy, SyncFolder};
+
+/// 保存在数据目录中的用户设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub auto_upload: bool,                 // 是否自动上传指定文件夹中打开的图片
+    pub auto_upload_folders: Vec<PathBuf>, // 自动上传的文件夹（包括子文件夹）
+    pub cloud: CloudConfig,                // 使用的云存储及连接参数
+    pub share_expiry: ShareExpiry,         // 分享链接的有效期
+    pub sync_folders: Vec<SyncFolder>,     // 与存储桶双向同步的文件夹
+    pub sync_conflict: ConflictPolicy,     // 两边都修改过时保留哪一边
+    pub sync_interval: u64,                // 定时同步的间隔（分钟），0 表示只手动同步
+    pub upload_limit: u64,                 // 上传限速（KB/s），0 表示不限速
+    pub download_limit: u64,               // 下载限速（KB/s），0 表示不限速
+    pub remote_cache_limit: CacheLimit,    // 云端图片本地缓存的大小上限
+    pub catalog_folders: Vec<PathBuf>, // 加入图片目录的文件夹（包括子文件夹），为空时不使用图片目录
+    pub recently_added: AddedWithin,   // “最近添加”中列出多少天内修改过的图片
+    pub remember_view: bool,           // 再次打开图片时恢复上次的缩放和平移
+    pub wheel_navigation: bool,        // 在图片上滚动滚轮切换上一张/下一张，Ctrl+滚轮缩放
+}
+
+impl Settings {
+    pub fn load_from_file(path: PathBuf) -> Result<Settings, Box<dyn Error>> {
+        if !path.is_file() {
+            return Ok(Settings::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 打开这张图片时是否应该自动上传
+    pub fn should_auto_upload(&self, path: &Path) -> bool {
+        self.auto_upload
+            && self
+                .auto_upload_folders
+                .iter()
+                .any(|folder| path.starts_with(folder))
+    }
+}
+
+/// 设置文件的位置
+pub fn settings_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("settings.json"))
+}
@@ -0,0 +1,166 @@
+use iced::Task;
+use iced::window;
+use std::path::PathBuf;
+
+/// 调用系统分享的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Shown,       // 已经打开系统的分享界面
+    Unsupported, // 系统没有分享功能，由调用方改用云存储的分享链接
+}
+
+/// 用系统的分享界面分享图片：Linux 通过 xdg-desktop-portal 发送邮件；
+/// Windows 和 macOS 的系统分享面板需要打开 native-share 功能，否则返回 Unsupported
+pub fn share(path: PathBuf, window: window::Id) -> Task<Result<Outcome, String>> {
+    let path = match std::path::absolute(&path) {
+        Ok(path) => path,
+        Err(e) => return Task::done(Err(e.to_string())),
+    };
+    run(path, window)
+}
+
+#[cfg(target_os = "linux")]
+fn run(path: PathBuf, _window: window::Id) -> Task<Result<Outcome, String>> {
+    Task::perform(portal::share(path), |result| result)
+}
+
+// 分享界面需要在窗口所在的主线程上打开
+#[cfg(all(
+    feature = "native-share",
+    any(target_os = "macos", target_os = "windows")
+))]
+fn run(path: PathBuf, window: window::Id) -> Task<Result<Outcome, String>> {
+    use iced::window::raw_window_handle::RawWindowHandle;
+    window::run_with_handle(window, move |handle| match handle.as_raw() {
+        #[cfg(target_os = "macos")]
+        RawWindowHandle::AppKit(handle) => unsafe { appkit::share(&path, handle.ns_view.as_ptr()) },
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Win32(handle) => winrt::share(&path, handle.hwnd.get()),
+        _ => Ok(Outcome::Unsupported),
+    })
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    all(
+        feature = "native-share",
+        any(target_os = "macos", target_os = "windows")
+    )
+)))]
+fn run(_path: PathBuf, _window: window::Id) -> Task<Result<Outcome, String>> {
+    Task::done(Ok(Outcome::Unsupported))
+}
+
+// 桌面环境没有通用的分享门户，使用 Email 门户把图片作为附件交给邮件程序
+#[cfg(target_os = "linux")]
+mod portal {
+    use super::Outcome;
+    use ashpd::desktop::email::EmailRequest;
+    use std::fs::File;
+    use std::os::fd::OwnedFd;
+    use std::path::PathBuf;
+
+    pub async fn share(path: PathBuf) -> Result<Outcome, String> {
+        let file = File::open(&path).map_err(|e| e.to_string())?;
+        let subject = crate::file_tree::file_name(&path);
+        let request = EmailRequest::default()
+            .subject(subject.as_str())
+            .attach(OwnedFd::from(file))
+            .send()
+            .await;
+        match request {
+            Ok(_) => Ok(Outcome::Shown),
+            // 没有会话总线或门户不支持 Email 接口
+            Err(ashpd::Error::PortalNotFound(_) | ashpd::Error::Zbus(_)) => {
+                Ok(Outcome::Unsupported)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(all(feature = "native-share", target_os = "macos"))]
+mod appkit {
+    use super::Outcome;
+    use objc2::ClassType;
+    use objc2::rc::Retained;
+    use objc2_app_kit::{NSSharingServicePicker, NSView};
+    use objc2_foundation::{NSArray, NSRectEdge, NSString, NSURL};
+    use std::cell::RefCell;
+    use std::ffi::c_void;
+    use std::path::Path;
+
+    thread_local! {
+        // 分享面板关闭前保留选择器
+        static PICKER: RefCell<Option<Retained<NSSharingServicePicker>>> = const { RefCell::new(None) };
+    }
+
+    // ns_view 为窗口的内容视图，在它的底边弹出 NSSharingServicePicker
+    pub unsafe fn share(path: &Path, ns_view: *mut c_void) -> Result<Outcome, String> {
+        unsafe {
+            let view = &*(ns_view as *const NSView);
+            let url = NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+            let items: Retained<NSArray> = Retained::cast(NSArray::from_vec(vec![url]));
+            let picker =
+                NSSharingServicePicker::initWithItems(NSSharingServicePicker::alloc(), &items);
+            picker.showRelativeToRect_ofView_preferredEdge(view.bounds(), view, NSRectEdge::MinY);
+            PICKER.with(|cell| cell.replace(Some(picker)));
+        }
+        Ok(Outcome::Shown)
+    }
+}
+
+#[cfg(all(feature = "native-share", target_os = "windows"))]
+mod winrt {
+    use super::Outcome;
+    use std::cell::Cell;
+    use std::path::Path;
+    use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
+    use windows::Foundation::Collections::IIterable;
+    use windows::Foundation::{EventRegistrationToken, TypedEventHandler};
+    use windows::Storage::{IStorageItem, StorageFile};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+    use windows::core::{ComInterface, HSTRING};
+
+    thread_local! {
+        // 上一次注册的 DataRequested 处理函数，再次分享时先移除
+        static TOKEN: Cell<Option<EventRegistrationToken>> = const { Cell::new(None) };
+    }
+
+    // 通过 IDataTransferManagerInterop 为桌面窗口打开分享面板，面板请求数据时再提供文件
+    pub fn share(path: &Path, hwnd: isize) -> Result<Outcome, String> {
+        let hwnd = HWND(hwnd);
+        // 早于 Windows 10 的系统没有分享面板
+        let Ok(interop) =
+            windows::core::factory::<DataTransferManager, IDataTransferManagerInterop>()
+        else {
+            return Ok(Outcome::Unsupported);
+        };
+        let manager: DataTransferManager =
+            unsafe { interop.GetForWindow(hwnd) }.map_err(|e| e.to_string())?;
+        if let Some(token) = TOKEN.take() {
+            let _ = manager.RemoveDataRequested(token);
+        }
+        let title = HSTRING::from(crate::file_tree::file_name(path));
+        let path = HSTRING::from(path.as_os_str());
+        let handler = TypedEventHandler::new(
+            move |_: &Option<DataTransferManager>, args: &Option<DataRequestedEventArgs>| {
+                let Some(args) = args else {
+                    return Ok(());
+                };
+                let request = args.Request()?;
+                let data = request.Data()?;
+                data.Properties()?.SetTitle(&title)?;
+                // 打开 native-share 之前需要改为 GetDeferral 加异步完成回调，get() 会阻塞界面线程
+                let file = StorageFile::GetFileFromPathAsync(&path)?.get()?;
+                let items = IIterable::<IStorageItem>::try_from(vec![Some(file.cast()?)])?;
+                data.SetStorageItemsReadOnly(&items)
+            },
+        );
+        let token = manager.DataRequested(&handler).map_err(|e| e.to_string())?;
+        TOKEN.set(Some(token));
+        unsafe { interop.ShowShareUIForWindow(hwnd) }.map_err(|e| e.to_string())?;
+        Ok(Outcome::Shown)
+    }
+}
@@ -0,0 +1,73 @@
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::path::{Path, PathBuf};
+
+use crate::catalog::Catalog;
+use crate::i18n::tr;
+use crate::{jpeg_scale, pipeline};
+
+// 大 JPEG 按这个长边缩小解码，计算感知哈希不需要更多细节
+const HASH_DECODE_SIZE: u32 = 256;
+
+/// 哈希中不同的位数不超过这个值的图片视为相似
+pub const MAX_DISTANCE: u32 = 12;
+
+/// 差值哈希（dHash）：缩小为 9×8 的灰度图，每行相邻像素比较亮度得到 64 位，
+/// 缩放、重新压缩和轻微调色后基本不变
+pub fn dhash(img: &DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y).0[0] > small.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// 计算图片文件的感知哈希，按 EXIF 方向摆正后再计算；无法解码时返回 None
+pub fn hash_file(path: &Path) -> Option<u64> {
+    let img = match jpeg_scale::decode_reduced(path, HASH_DECODE_SIZE) {
+        Some((mut img, _)) => {
+            let orientation = ImageReader::open(path)
+                .ok()?
+                .with_guessed_format()
+                .ok()?
+                .into_decoder()
+                .ok()?
+                .orientation()
+                .unwrap_or(Orientation::NoTransforms);
+            img.apply_orientation(orientation);
+            img
+        }
+        None => pipeline::decode_image(path, true).ok()?.0,
+    };
+    Some(dhash(&img))
+}
+
+/// 在后台从图片目录中查找与 path 相似的图片，最相似的在前，path 本身排在第一个
+pub async fn find(path: PathBuf, db: PathBuf) -> Result<Vec<PathBuf>, String> {
+    tokio::task::spawn_blocking(move || {
+        let catalog = Catalog::open(&db)?;
+        // 不在图片目录中的图片（或还没有索引到）直接计算
+        let hash = match catalog.phash(&path) {
+            Some(hash) => hash,
+            None => hash_file(&path).ok_or(tr("Failed to read the image"))?,
+        };
+        let mut images = vec![path.clone()];
+        images.extend(
+            catalog
+                .similar(hash, MAX_DISTANCE)?
+                .into_iter()
+                .filter(|other| *other != path),
+        );
+        Ok(images)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,9 +1,30 @@
 use chrono::prelude::*;
-use serde;
-use serde_json;
 use std::fs;
+use std::path::Path;
 use std::{error::Error, path::PathBuf};
 
+// 目录树中智能文件夹的虚拟路径
+pub const RECENTS: &str = "__RECENTS__";
+pub const MOST_VIEWED: &str = "__MOST_VIEWED__";
+pub const RECENTLY_ADDED: &str = "__RECENTLY_ADDED__";
+
+// 浏览次数的权重每过这么多天减半，很久以前看得多的图片逐渐排到后面
+const HALF_LIFE_DAYS: f64 = 14.0;
+
+pub fn is_smart_folder(path: &Path) -> bool {
+    path.as_os_str() == RECENTS
+        || path.as_os_str() == MOST_VIEWED
+        || path.as_os_str() == RECENTLY_ADDED
+}
+
+/// 离开图片时的缩放条值和平移偏移，再次打开时恢复
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct ViewState {
+    pub zoom: u8,
+    pub pan_x: f32,
+    pub pan_y: f32,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct RecentItem {
     path: PathBuf,
@@ -11,6 +32,10 @@ pub struct RecentItem {
     view_count: u32,
     file_size: u64,
     last_modified: DateTime<Local>,
+    #[serde(default)]
+    pinned: bool, // 固定在 Recents 顶部，不会被新的记录挤掉
+    #[serde(default)]
+    view: Option<ViewState>, // 上次的缩放和平移，默认视图时为 None
 }
 
 impl RecentItem {
@@ -20,8 +45,10 @@ impl RecentItem {
             path,
             last_viewed: Local::now(),
             view_count: 1,
-            file_size: file_size,
+            file_size,
             last_modified: Local::now(),
+            pinned: false,
+            view: None,
         }
     }
 
@@ -36,6 +63,28 @@ impl RecentItem {
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+
+    pub fn view_count(&self) -> u32 {
+        self.view_count
+    }
+
+    pub fn last_viewed(&self) -> DateTime<Local> {
+        self.last_viewed
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    pub fn view(&self) -> Option<ViewState> {
+        self.view
+    }
+
+    // 按最后浏览时间衰减后的浏览次数
+    fn score(&self, now: DateTime<Local>) -> f64 {
+        let days = (now - self.last_viewed).num_seconds().max(0) as f64 / 86400.0;
+        self.view_count as f64 * 0.5f64.powf(days / HALF_LIFE_DAYS)
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
@@ -59,8 +108,11 @@ impl RecentManager {
             self.recent_items[index].last_modified = Local::now();
         } else {
             self.recent_items.push(RecentItem::new(path));
-            if self.recent_items.len() > self.max_items {
-                self.recent_items.remove(0);
+            // 超出数量时挤掉最早的未固定记录
+            if self.recent_items.len() > self.max_items
+                && let Some(index) = self.recent_items.iter().position(|item| !item.pinned)
+            {
+                self.recent_items.remove(index);
             }
         }
     }
@@ -69,6 +121,18 @@ impl RecentManager {
         &self.recent_items
     }
 
+    /// 按衰减后的浏览次数从多到少排列
+    pub fn most_viewed(&self) -> Vec<&RecentItem> {
+        let now = Local::now();
+        let mut items: Vec<_> = self.recent_items.iter().collect();
+        items.sort_by(|a, b| b.score(now).total_cmp(&a.score(now)));
+        items
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&RecentItem> {
+        self.recent_items.iter().find(|item| item.path == path)
+    }
+
     pub fn delete_item(&mut self, path: &PathBuf) -> Result<(), Box<dyn Error>> {
         if let Some(index) = self.recent_items.iter().position(|item| item.path == *path) {
             self.recent_items.remove(index);
@@ -76,18 +140,61 @@ impl RecentManager {
         Ok(())
     }
 
+    /// 记录这张图片的缩放和平移
+    pub fn set_view(&mut self, path: &Path, view: Option<ViewState>) {
+        if let Some(item) = self.recent_items.iter_mut().find(|item| item.path == path) {
+            item.view = view;
+        }
+    }
+
+    /// 固定或取消固定，固定的记录排在最前面
+    pub fn toggle_pin(&mut self, path: &Path) {
+        if let Some(item) = self.recent_items.iter_mut().find(|item| item.path == path) {
+            item.pinned = !item.pinned;
+        }
+        self.recent_items.sort_by_key(|item| !item.pinned);
+    }
+
     pub fn load_from_file(path: PathBuf) -> Result<RecentManager, Box<dyn Error>> {
         if !path.is_file() {
             return Ok(RecentManager::new(10));
         }
         let content = fs::read_to_string(path)?;
         let mut manager: RecentManager = serde_json::from_str(&content)?;
-        for item in manager.clone().recent_items.iter() {
-            if !item.path().exists() {
-                manager.delete_item(&item.path)?;
+        manager.prune();
+        Ok(manager)
+    }
+
+    /// 去掉已经不存在的文件，并合并指向同一个文件的重复记录
+    pub fn prune(&mut self) {
+        self.recent_items.retain(|item| item.path.exists());
+        let mut items: Vec<RecentItem> = Vec::with_capacity(self.recent_items.len());
+        let mut seen: Vec<PathBuf> = Vec::new();
+        for item in self.recent_items.drain(..) {
+            // 同一个文件可能以不同的路径（符号链接、相对路径）记录过
+            let canonical = fs::canonicalize(&item.path).unwrap_or_else(|_| item.path.clone());
+            match seen.iter().position(|path| *path == canonical) {
+                Some(index) => {
+                    let existing = &mut items[index];
+                    existing.view_count += item.view_count;
+                    existing.pinned |= item.pinned;
+                    if item.last_viewed > existing.last_viewed {
+                        existing.last_viewed = item.last_viewed;
+                        existing.view = item.view.or(existing.view);
+                    }
+                }
+                None => {
+                    seen.push(canonical);
+                    items.push(item);
+                }
             }
         }
-        Ok(manager)
+        self.recent_items = items;
+    }
+
+    /// 清空浏览记录，固定的记录保留
+    pub fn clear(&mut self) {
+        self.recent_items.retain(|item| item.pinned);
     }
 
     pub fn save_to_file(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
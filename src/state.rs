@@ -0,0 +1,6413 @@
+use iced::Theme;
+use iced::widget::image::Handle;
+use iced::widget::{container, text_editor, text_input};
+use iced::{Subscription, Task, Vector, keyboard, window};
+use image::RgbImage;
+use image::metadata::Orientation;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use crate::adjustments::Adjustments;
+use crate::batch::{BatchEvent, BatchJob, BatchOptions, BatchScope, ItemStatus};
+use crate::blink::Blink;
+use crate::catalog::{Catalog, IndexEvent, SmartAlbum};
+use crate::channels::Channel;
+use crate::cli::Args;
+use crate::cloud::RemoteEdit;
+use crate::cloud_storage::CloudStorage;
+use crate::codes::CodeScan;
+use crate::config::{Config, SortOrder, ThemeMode, ThumbnailStrip};
+use crate::contact_sheet::{SheetOptions, SheetProgress};
+use crate::convert::{ConvertProgress, ConvertSettings};
+use crate::crop::{AspectRatio, CropRect};
+use crate::exif_info::ExifInfo;
+use crate::export::{ExportProgress, ExportSettings};
+use crate::gallery::{GalleryProgress, GalleryWizard};
+use crate::gestures::{Gesture, TemporaryPan, TouchTracker, WheelSteps};
+use crate::frames::Animation;
+use crate::file_tree::{
+    Access, FileTreeEntry, NamePrompt, file_name, find_entry_by_path, load_directory_children,
+    reload_children, replace_children, smart_folder, smart_folder_child,
+};
+use crate::history::{EditHistory, EditOp, Rotation};
+use crate::i18n::{tr, trf};
+use crate::image_cache::{Decoded, DecodedCache};
+use crate::keymap::{Action, Keymap};
+use crate::map::MapView;
+use crate::memory::MemoryUsage;
+use crate::messages::{Message, RejectAction, SettingsPage};
+use crate::metadata::{MetadataEdits, MetadataPolicy};
+use crate::metadata_search::{ImageMetadata, MetadataCache, MetadataQuery};
+use crate::palette::Palette;
+use crate::perf::Stage;
+use crate::pipeline::{ResamplingType, crop_and_scale, decode_for_display, decode_image, rgb_handle};
+use crate::remote_cache::RemoteCache;
+use crate::remote_control::Command;
+use crate::scan::{ActiveScan, FolderStamp, ScanEvent};
+use crate::script::{ScriptContext, ScriptOutput};
+use crate::settings::Settings;
+use crate::smart_directory::{RecentManager, ViewState};
+use crate::sync::{SyncEvent, SyncFolder};
+use crate::tabs::Tab;
+use crate::textures::TextureInfo;
+use crate::thumbnails::{FileInfo, ThumbnailCache};
+use crate::timeline::{Timeline, TimelineSource};
+use crate::toast::{Toast, ToastKind};
+use crate::transfer_queue::{TransferDirection, TransferEvent, TransferQueue, TransferStatus};
+use crate::upload_manifest::UploadManifest;
+use crate::view::{FIND_INPUT, VIEWER};
+use crate::viewer::Viewer;
+use crate::xmp_iptc::{DescriptiveMetadata, Flag};
+use crate::{
+    batch, blink, catalog, channels, cloud, cloud_storage, codes, color_picker, config,
+    contact_sheet, convert, crop, exif_info, export, formats, frames, gallery, gestures, i18n,
+    image_cache, logging, map, memory, metadata, name_search, ocr, perf, plugins, remote_cache,
+    remote_control, scan, script, share, similar, smart_directory, sync, textures, throttle,
+    thumbnails, timeline, toast, transfer_queue, upload_manifest, wallpaper, xmp_iptc,
+};
+
+// 拖动缩放条时停顿多久才生成预览
+const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(80);
+
+// 松开缩放条后等待多久再做高质量渲染
+const FINALIZE_DELAY: Duration = Duration::from_millis(300);
+
+// 鼠标在缩略图上停留多久才显示悬停预览
+const HOVER_PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+// 悬停预览缓存的上限（字节），每张预览约 640 KB
+const HOVER_PREVIEW_CACHE: usize = 32 * 1024 * 1024;
+
+// 多久检查一次图片库所在的文件夹是否有变化
+const FOLDER_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+// 缩放快捷键每次改变的缩放条值
+const ZOOM_STEP: u8 = 10;
+
+// 两次单击间隔小于这个时间算作双击
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+pub struct State {
+    pub current_path: PathBuf,
+    pub current_image: Option<PathBuf>,
+    pub root_file_tree_entry: Vec<FileTreeEntry>,
+    pub image_collection: Vec<PathBuf>, // 用于存储图片库
+    pub current_image_index: usize,
+    pub resampling_bar_opened: bool,     // 是否打开缩放条
+    pub slider_value: u8,                // 用于缩放条的值
+    pub resampling_type: ResamplingType, // 当前选择的缩放算法
+    pub original: Option<Arc<RgbImage>>, // 用于存储原始图片，后台任务共享同一份像素
+    pub alpha: Option<Arc<RgbImage>>, // 原始图片的透明通道，显示透明通道时才在后台解码
+    pub alpha_pending: bool,          // 是否正在后台解码透明通道
+    pub scaled_bytes: Vec<u8>,           // 用于存储缩放后的图片字节
+    pub thumbnail_cache: ThumbnailCache, // 缓存缩略图
+    pub thumbnails_pending: std::collections::HashSet<PathBuf>, // 正在生成的缩略图
+    pub hovered_thumbnail: Option<PathBuf>, // 鼠标所在的缩略图
+    pub hover_preview: Option<PathBuf>,     // 正在显示悬停预览的图片
+    pub hover_previews: ThumbnailCache,     // 已生成的悬停预览
+    pub is_dragging: bool,               // 是否正在拖动滑块
+    pub resize_generation: u64,          // 每次缩放请求加一，用于丢弃过期的结果
+    pub preview_scaled_bytes: Vec<u8>,   // 用于存储预览缩放后的图片字节
+    pub final_scaled_bytes: Vec<u8>,     // 用于存储最终高质量缩放后的图片字节
+    pub is_resampling_mode: bool,
+    pub hand_tool_active: bool,                  // 是否启用手型工具
+    pub view_locked: bool,                       // 切换图片时保持缩放和平移
+    pub channel: Channel,                        // 只显示的通道
+    pub channel_view: Option<Arc<RgbImage>>,     // 只显示一个通道时的灰度图
+    pub blink: Option<Blink>,                    // 闪烁比较
+    pub texture: Option<TextureInfo>,            // 当前图片是 DDS 或 KTX 纹理时的格式和层数
+    pub mip_level: usize,                        // 显示纹理的哪一层
+    pub psd_layers: Vec<String>,                 // 当前图片是 PSD 时的图层名称，从上到下
+    pub animation: Option<Animation>,            // 当前图片是动画时的各帧
+    pub code_scan: Option<CodeScan>,             // 识别到的二维码和条形码
+    pub color_picking: bool,                     // 是否处于取色模式
+    pub hovered_color: Option<[u8; 3]>,          // 取色模式下光标处的颜色
+    pub extracting_text: bool,                   // 正在识别当前图片中的文字
+    pub ocr_languages: Option<Result<Vec<String>, String>>, // 已安装的 Tesseract 语言
+    pub is_panning: bool,                        // 是否正在拖动画布
+    pub pan_start_position: Option<iced::Point>, // 拖动开始位置
+    pub pan_offset: iced::Vector,                // 拖动偏移量
+    pub touches: TouchTracker,                   // 触摸屏上按下的手指
+    pub wheel_steps: WheelSteps,                 // 滚轮切换图片时累计的滚动量
+    pub last_click: Option<Instant>,             // 上一次在图片上单击的时间，用于判断双击
+    pub temporary_pan: Option<TemporaryPan>,     // 按住空格或中键临时平移
+    pub gesture_zoom_remainder: f32,             // 手势缩放中不足缩放条一格的部分
+    pub recent_manager: RecentManager,
+    pub is_fullscreen: bool,
+    pub sort: SortOrder,             // 图片库当前的排列顺序，命令行指定时不写入配置
+    pub slideshow: Option<Duration>, // 幻灯片放映中每张图片显示的时间
+    pub slideshow_paused: bool,      // 幻灯片放映是否暂停
+    pub system_dark: bool,           // 启动时系统是否使用深色模式
+    pub exif_info: Option<ExifInfo>, // 当前图片的EXIF信息
+    pub info_panel_opened: bool,     // 是否打开信息面板
+    pub descriptive_metadata: Option<DescriptiveMetadata>, // 当前图片的XMP/IPTC信息
+    pub plugin_metadata: Vec<(String, String)>, // 插件提供的当前图片信息
+    pub search_panel_opened: bool,   // 是否打开元数据搜索面板
+    pub search_query: MetadataQuery, // 搜索面板中编辑的条件
+    pub active_search: Option<MetadataQuery>, // 当前生效的搜索条件
+    pub unfiltered_collection: Vec<PathBuf>, // 筛选前的图片库
+    pub metadata_cache: MetadataCache, // 搜索用的元数据缓存
+    pub strip_metadata: bool,        // 导出和上传时去除全部元数据
+    pub metadata_edits: MetadataEdits, // 导出和上传时修改的EXIF字段
+    pub orientation: Orientation,    // 当前图片EXIF中的方向
+    pub show_raw_orientation: bool,  // 是否忽略EXIF方向显示原始图像
+    pub display_handle: Option<Handle>, // 原始方向或调整后图像的显示句柄
+    pub crop_mode: bool,             // 是否处于裁剪模式
+    pub crop_aspect: AspectRatio,    // 裁剪框比例
+    pub crop_selection: Option<CropRect>, // 当前裁剪框（原图像素坐标）
+    pub overlay_handle: Option<Handle>, // 裁剪/拉直模式下画布显示的图像
+    pub export_dialog_opened: bool,  // 是否打开导出对话框
+    pub export_settings: ExportSettings, // 导出尺寸、格式和质量
+    pub export_progress: Option<f32>, // 导出进度，None 表示没有进行中的导出
+    pub export_status: Option<String>, // 上一次导出的结果
+    pub modifiers: keyboard::Modifiers, // 当前按下的修饰键
+    pub selected_images: Vec<PathBuf>, // 缩略图多选（Ctrl/Shift+点击）
+    pub convert_dialog_opened: bool, // 是否打开格式转换对话框
+    pub convert_settings: ConvertSettings, // 转换格式、质量和输出目录
+    pub convert_progress: Option<(usize, usize)>, // 转换进度（已完成, 总数）
+    pub convert_failures: Vec<(PathBuf, String)>, // 转换失败的图片及原因
+    pub convert_status: Option<String>, // 上一次转换的结果
+    pub adjust_panel_opened: bool,   // 是否打开调整面板
+    pub adjustments: Adjustments,    // 当前的亮度/对比度/饱和度/gamma
+    pub adjusted: Option<Arc<RgbImage>>, // 应用调整后的图片，无调整时为 None
+    pub adjusting: bool,             // 是否有调整正在后台计算
+    pub batch_dialog_opened: bool,   // 是否打开批处理对话框
+    pub batch_options: BatchOptions, // 批处理对话框中的选项
+    pub batch_scope: BatchScope,     // 批处理选中的图片还是整个文件夹
+    pub batch_error: Option<String>, // 批处理选项校验失败的原因
+    pub batch_tag: String,           // 多选工具栏中输入的关键词
+    pub jobs: Vec<BatchJob>,         // 批处理任务队列
+    pub jobs_panel_opened: bool,     // 是否打开任务面板
+    pub map: Option<MapView>,        // 地图面板，关闭时为 None
+    pub timeline: Option<Timeline>,  // 时间线视图，关闭时为 None
+    pub next_job_id: u64,            // 下一个任务的编号
+    pub script_dialog_opened: bool,  // 是否打开脚本对话框
+    pub script: text_editor::Content, // 脚本编辑器中的内容
+    pub script_running: bool,        // 脚本是否正在后台运行
+    pub script_output: Vec<String>,  // 上次运行脚本的输出
+    pub contact_sheet_dialog_opened: bool, // 是否打开联系表对话框
+    pub contact_sheet: SheetOptions, // 联系表的版式和格式
+    pub contact_sheet_progress: Option<(usize, usize)>, // 生成进度（已放入, 总数）
+    pub contact_sheet_status: Option<String>, // 上一次生成的结果
+    pub gallery_wizard: Option<GalleryWizard>, // 打开着的网页相册向导
+    pub edited: Option<Arc<RgbImage>>, // 裁剪/旋转后的图片，没有几何编辑时为 None
+    pub history: EditHistory,        // 当前图片的编辑历史
+    pub straighten_mode: bool,       // 是否处于拉直模式
+    pub straighten_angle: f32,       // 拉直角度（度，顺时针为正）
+    pub decoded_cache: DecodedCache, // 最近解码的图片
+    pub loading: Option<PathBuf>,    // 正在后台解码的图片
+    pub reorienting: bool,           // 正在按新的显示方向重新解码当前图片，完成后保留缩放和平移
+    pub load_error: Option<String>,  // 当前图片无法打开的原因
+    pub spinner_frame: usize,        // 加载动画的当前帧
+    pub thumbnail_tasks: Vec<iced::task::Handle>, // 正在进行的缩略图批量任务
+    pub thumbnail_generation: u64,   // 单张缩略图请求的代数
+    pub collection_generation: u64,  // 取消缩略图生成时增加，之前的批量结果被丢弃
+    pub collection_folder: Option<FolderStamp>, // 图片库来自的本地文件夹，按 F5 时重新读取
+    pub folder_stale: bool,          // 图片库所在的文件夹在读取之后有变化
+    pub file_info: std::collections::HashMap<PathBuf, FileInfo>, // 图片尺寸和文件大小
+    pub resize_task: Option<iced::task::Handle>, // 正在进行的缩放任务
+    pub memory_budget: usize,        // 图片和缓存合计的内存上限（字节）
+    pub scan: Option<ActiveScan>,    // 正在后台扫描的目录
+    pub next_scan_id: u64,           // 下一次目录扫描的编号
+    pub full_size: Option<(u32, u32)>, // 原图按缩小的尺寸解码时的原始尺寸
+    pub full_resolution_generation: u64, // 每次在后台解码原尺寸时加一，用于丢弃过期的结果
+    pub full_resolution_pending: bool, // 是否正在后台解码原尺寸
+    pub after_full_resolution: Option<Message>, // 等原尺寸的像素就绪后再执行的操作
+    pub perf_hud_opened: bool,       // 是否显示性能面板
+    pub log_panel_opened: bool,      // 是否显示诊断日志面板
+    pub transfers: TransferQueue,    // 上传和下载队列
+    pub transfers_panel_opened: bool, // 是否打开传输面板
+    pub toasts: Vec<Toast>,          // 当前显示的提示
+    pub next_toast_id: u64,          // 下一条提示的编号
+    pub settings: Settings,          // 用户设置
+    pub config: Config,              // config.toml 中的界面偏好
+    pub keymap: Keymap,              // 动作和快捷键的对应关系
+    pub settings_dialog_opened: bool, // 是否打开设置对话框
+    pub settings_page: SettingsPage, // 设置对话框中显示的分页
+    pub cloud_folder: Option<String>, // 图片库当前显示的云端文件夹前缀
+    pub cloud_open: Option<u64>,     // 下载完成后要打开的传输编号
+    pub share_pending: Option<PathBuf>, // 上传完成后要复制分享链接的图片
+    pub cloud_selected: Option<PathBuf>, // 目录树中选中的云端文件（虚拟路径）
+    pub remote_edit: Option<RemoteEdit>, // 等待确认的删除或重命名
+    pub sync_progress: Option<(usize, usize)>, // 同步进度（已处理, 总数），None 表示没有在同步
+    pub upload_manifest: UploadManifest, // 已上传文件的 MD5
+    pub remote_cache: RemoteCache,   // 下载到本地的云端对象
+    pub recent_menu: Option<(PathBuf, PathBuf)>, // 打开了右键菜单的浏览记录（智能文件夹, 图片）
+    pub catalog: Option<Catalog>,    // 图片目录，没有加入任何文件夹时为 None
+    pub catalog_progress: Option<(usize, usize)>, // 索引进度（已处理, 总数），None 表示没有在索引
+    pub catalog_indexed: bool,       // 这次运行中是否已经索引过
+    pub search_library: bool,        // 在整个图片目录中搜索，而不是只筛选当前图片库
+    pub find_bar_opened: bool,       // 是否打开文件名搜索栏（Ctrl+F）
+    pub find_query: String,          // 搜索栏中输入的文件名或正则表达式
+    pub find_regex: bool,            // 按正则表达式匹配文件名
+    pub finding: bool,               // 正在后台搜索
+    pub find_results: Option<String>, // 图片库当前显示的是哪次搜索的结果
+    pub find_backup: Vec<PathBuf>,   // 显示搜索结果之前的图片库，关闭结果时恢复
+    pub similar_to: Option<String>,  // 图片库当前显示的是与哪张图片（文件名）相似的图片
+    pub flag_filter: Option<Flag>,   // 图片库当前只显示选中或排除的图片
+    // 单张重新生成的缩略图对应的最新请求代数
+    pub thumbnail_requests: std::collections::HashMap<PathBuf, u64>,
+    pub main_window: window::Id,
+    // 另外打开的查看窗口，各自浏览自己的图片
+    pub viewers: std::collections::HashMap<window::Id, Viewer>,
+    pub name_prompt: Option<NamePrompt>, // 正在输入名称的重命名或新建文件夹
+    pub tabs: Vec<Tab>,                  // 所有标签页，当前标签页的内容以 State 中的字段为准
+    pub active_tab: usize,               // 当前标签页在 tabs 中的位置
+}
+
+impl State {
+    /// 按配置和命令行参数创建初始状态，返回打开命令行中指定路径的任务
+    pub fn new(args: Args) -> (Self, Task<Message>) {
+        let config = config::config_path()
+            .and_then(|path| Config::load_or_create(&path).ok())
+            .unwrap_or_default();
+        i18n::set_language(config.language);
+        plugins::load(&config.disabled_plugins);
+        // 配置中指定了起始文件夹时用它代替主目录
+        let home_dir = if let Some(dir) = config.start_folder.clone().filter(|dir| dir.is_dir()) {
+            dir
+        } else if let Some(dir) = dirs::home_dir() {
+            dir
+        } else {
+            PathBuf::from("/")
+        };
+        thumbnails::set_size(config.thumbnail_size);
+        let sort = args.sort.unwrap_or(config.sort);
+        let (main_window, open_main) = window::open(window::Settings::default());
+        let rencents = if let Some(dir) = config::data_dir() {
+            if dir.join("recent.json").exists() {
+                RecentManager::load_from_file(dir.join("recent.json"))
+                    .unwrap_or(RecentManager::new(20))
+            } else {
+                RecentManager::new(20)
+            }
+        } else {
+            RecentManager::new(20)
+        };
+        let mut state = State {
+            current_path: home_dir.clone(),
+            current_image: None,
+            root_file_tree_entry: vec![
+                smart_folder(
+                    "Recents",
+                    smart_directory::RECENTS,
+                    rencents.get_recent_items(),
+                ),
+                smart_folder(
+                    "Most viewed",
+                    smart_directory::MOST_VIEWED,
+                    rencents.most_viewed(),
+                ),
+                smart_folder("Recently added", smart_directory::RECENTLY_ADDED, []),
+                FileTreeEntry::Directory {
+                    name: home_dir
+                        .clone()
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    path: home_dir.clone(),
+                    children: vec![],
+                    expanded: false,
+                    children_loaded: false, // 初始状态未加载子节点
+                    link: None,
+                    access: Access::Readable,
+                },
+            ],
+            image_collection: Vec::new(), // 初始化图片库为空
+            current_image_index: 0,       // 初始图片索引为 0
+            resampling_bar_opened: false,
+            slider_value: 50,                           // 初始缩放条值为 50
+            resampling_type: config.resampling,         // 配置中的默认算法
+            original: None,                             // 用于存储原始图片
+            alpha: None,
+            alpha_pending: false,
+            scaled_bytes: Vec::new(),                   // 用于存储缩放后的图片字节
+            thumbnail_cache: ThumbnailCache::default(), // 初始化缩略图缓存
+            is_dragging: false,                         // 初始状态不是拖动
+            resize_generation: 0,                       // 初始缩放请求代数
+            preview_scaled_bytes: Vec::new(),           // 初始化预览缩放字节
+            final_scaled_bytes: Vec::new(),             // 初始化最终缩放字节
+            is_resampling_mode: false,                  // 初始状态不是缩放模式
+            hand_tool_active: false,                    // 初始状态未启用手型工具
+            view_locked: false,
+            channel: Channel::All,
+            channel_view: None,
+            blink: None,
+            texture: None,
+            mip_level: 0,
+            psd_layers: Vec::new(),
+            animation: None,
+            code_scan: None,
+            color_picking: false,
+            hovered_color: None,
+            extracting_text: false,
+            ocr_languages: None,
+            is_panning: false,                          // 初始状态未拖动画布
+            pan_start_position: None,                   // 初始拖动开始位置
+            pan_offset: iced::Vector::new(0.0, 0.0),    // 初始拖动偏移量
+            touches: TouchTracker::default(),
+            wheel_steps: WheelSteps::default(),
+            last_click: None,
+            temporary_pan: None,
+            gesture_zoom_remainder: 0.0,
+            thumbnails_pending: std::collections::HashSet::new(),
+            hovered_thumbnail: None,
+            hover_preview: None,
+            hover_previews: ThumbnailCache::default(),
+            thumbnail_tasks: Vec::new(),
+            thumbnail_requests: std::collections::HashMap::new(),
+            thumbnail_generation: 0,
+            collection_generation: 0,
+            collection_folder: None,
+            folder_stale: false,
+            file_info: std::collections::HashMap::new(),
+            resize_task: None,
+            memory_budget: config.memory_budget_bytes(),
+            scan: None,
+            next_scan_id: 1,
+            full_size: None,
+            full_resolution_generation: 0,
+            full_resolution_pending: false,
+            after_full_resolution: None,
+            perf_hud_opened: false,
+            log_panel_opened: false,
+            transfers: TransferQueue::default(),
+            transfers_panel_opened: false,
+            toasts: Vec::new(),
+            next_toast_id: 1,
+            settings: config
+                .settings_path()
+                .and_then(|path| Settings::load_from_file(path).ok())
+                .unwrap_or_default(),
+            keymap: Keymap::from_config(&config.keys),
+            config,
+            settings_dialog_opened: false,
+            settings_page: SettingsPage::General,
+            cloud_folder: None,
+            cloud_open: None,
+            share_pending: None,
+            cloud_selected: None,
+            remote_edit: None,
+            sync_progress: None,
+            upload_manifest: upload_manifest::manifest_path()
+                .and_then(|path| UploadManifest::load_from_file(path).ok())
+                .unwrap_or_default(),
+            recent_menu: None,
+            catalog: None,
+            catalog_progress: None,
+            catalog_indexed: false,
+            search_library: false,
+            find_bar_opened: false,
+            find_query: String::new(),
+            find_regex: false,
+            finding: false,
+            find_results: None,
+            find_backup: Vec::new(),
+            similar_to: None,
+            flag_filter: None,
+            remote_cache: remote_cache::index_path()
+                .and_then(|path| RemoteCache::load_from_file(path).ok())
+                .unwrap_or_default(),
+            recent_manager: rencents,
+            is_fullscreen: args.fullscreen,
+            sort,
+            slideshow: args.slideshow.map(Duration::from_secs),
+            slideshow_paused: false,
+            system_dark: dark_light::detect() == dark_light::Mode::Dark,
+            exif_info: None,
+            info_panel_opened: false,
+            descriptive_metadata: None,
+            plugin_metadata: Vec::new(),
+            search_panel_opened: false,
+            search_query: MetadataQuery::default(),
+            active_search: None,
+            unfiltered_collection: Vec::new(),
+            metadata_cache: MetadataCache::default(),
+            strip_metadata: false,
+            metadata_edits: MetadataEdits::default(),
+            orientation: Orientation::NoTransforms,
+            show_raw_orientation: false,
+            display_handle: None,
+            crop_mode: false,
+            crop_aspect: AspectRatio::Free,
+            crop_selection: None,
+            overlay_handle: None,
+            export_dialog_opened: false,
+            export_settings: ExportSettings::default(),
+            export_progress: None,
+            export_status: None,
+            modifiers: keyboard::Modifiers::default(),
+            selected_images: Vec::new(),
+            convert_dialog_opened: false,
+            convert_settings: ConvertSettings::default(),
+            convert_progress: None,
+            convert_failures: Vec::new(),
+            convert_status: None,
+            adjust_panel_opened: false,
+            adjustments: Adjustments::default(),
+            adjusted: None,
+            adjusting: false,
+            batch_dialog_opened: false,
+            batch_options: BatchOptions::default(),
+            batch_scope: BatchScope::Selection,
+            batch_error: None,
+            batch_tag: String::new(),
+            jobs: Vec::new(),
+            jobs_panel_opened: false,
+            map: None,
+            timeline: None,
+            next_job_id: 1,
+            script_dialog_opened: false,
+            contact_sheet_dialog_opened: false,
+            contact_sheet: SheetOptions::default(),
+            contact_sheet_progress: None,
+            contact_sheet_status: None,
+            gallery_wizard: None,
+            script: text_editor::Content::with_text(&script::load_last()),
+            script_running: false,
+            script_output: Vec::new(),
+            edited: None,
+            history: EditHistory::default(),
+            straighten_mode: false,
+            straighten_angle: 0.0,
+            decoded_cache: DecodedCache::new(image_cache::DEFAULT_BUDGET),
+            loading: None,
+            reorienting: false,
+            load_error: None,
+            spinner_frame: 0,
+            main_window,
+            viewers: std::collections::HashMap::new(),
+            name_prompt: None,
+            tabs: vec![Tab::new(home_dir.clone())],
+            active_tab: 0,
+        };
+        let show_hidden = state.config.show_hidden;
+        load_directory_children(
+            &mut state.root_file_tree_entry[3],
+            home_dir.clone(),
+            show_hidden,
+        );
+        // 固定的文件夹放在主目录后面
+        let pinned: Vec<_> = state
+            .config
+            .pinned_folders
+            .iter()
+            .filter(|dir| dir.is_dir() && **dir != home_dir)
+            .map(|dir| FileTreeEntry::default(dir.clone()))
+            .collect();
+        state.root_file_tree_entry.extend(pinned);
+        // 加入了文件夹时打开图片目录
+        state.refresh_library_root();
+        // 配置了云存储时在目录树中显示存储桶
+        state.refresh_cloud_root();
+        throttle::set_limits(state.settings.upload_limit, state.settings.download_limit);
+        // 恢复上次没有完成的上传
+        if let Some(path) = transfer_queue::uploads_path()
+            && let Err(e) = state.transfers.load_uploads(path)
+        {
+            error!("Failed to load upload queue: {}", e);
+        }
+        let task = match args.path {
+            Some(path) => state.open_path(path),
+            None => Task::none(),
+        };
+        // 窗口打开后才能切换到全屏
+        let open_main = open_main.then(move |id| {
+            if args.fullscreen {
+                window::change_mode(id, window::Mode::Fullscreen)
+            } else {
+                Task::none()
+            }
+        });
+        (state, Task::batch([open_main, task]))
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        perf::count_update();
+        match message {
+            Message::SelectImage => {
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                // 取消选择时保持当前的图片
+                let Some(path) = FileDialog::new()
+                    .add_filter("image", &formats::IMAGE_EXTENSIONS)
+                    .set_directory("/")
+                    .pick_file()
+                else {
+                    return saved;
+                };
+                self.current_image = Some(path.clone());
+                self.current_path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+                self.image_collection.clear(); // 清空图片库
+                self.collection_folder = None;
+                self.forget_find_results();
+                self.current_image_index = 0; // 重置图片索引
+                Task::batch([
+                    saved,
+                    Task::perform(async move { Message::LoadImage(path) }, |msg| msg),
+                ])
+            }
+            Message::NoOp => Task::none(),
+            Message::ExpandDirectory(path) => {
+                // 没有权限或循环链接的目录不展开，提示原因
+                let access = self
+                    .root_file_tree_entry
+                    .iter_mut()
+                    .find_map(|root| find_entry_by_path(root, &path))
+                    .and_then(|entry| match entry {
+                        FileTreeEntry::Directory { access, .. } => Some(*access),
+                        FileTreeEntry::File { .. } => None,
+                    });
+                match access {
+                    Some(Access::Denied) => {
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Permission denied: {}", &[&path.display()]),
+                        );
+                    }
+                    Some(Access::Loop) => {
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("{} links to one of its parent folders", &[&path.display()]),
+                        );
+                    }
+                    _ => {}
+                }
+                // 检查是否是 Recents 或 Most viewed 智能文件夹
+                let is_recents = smart_directory::is_smart_folder(&path);
+                // 图片目录和智能相册的子项也不需要扫描
+                let is_virtual = is_recents || catalog::is_library(&path);
+
+                // 现在目录树根节点有recent和home目录两个，需要遍历查找
+                let needs_load = {
+                    let mut found = false;
+                    // 遍历所有根节点（包括recent和home）
+                    for root in self.root_file_tree_entry.iter_mut() {
+                        if let Some(entry) = find_entry_by_path(root, &path) {
+                            if let FileTreeEntry::Directory {
+                                expanded,
+                                children_loaded,
+                                children,
+                                ..
+                            } = entry
+                            {
+                                *expanded = !*expanded;
+                                if *expanded {
+                                    if is_virtual {
+                                        // Recents 目录的子项在初始化时已加载，标记为已加载
+                                        *children_loaded = true;
+                                        found = false;
+                                    } else {
+                                        // 展开且未加载 → 需要加载，子项由后台扫描分批填入
+                                        found = !*children_loaded;
+                                        if found {
+                                            children.clear();
+                                        }
+                                    }
+                                } else {
+                                    if !is_virtual {
+                                        // 折叠 → 清空缓存，无需加载（Recents目录保持子项）
+                                        children.clear();
+                                        *children_loaded = false;
+                                    }
+                                    found = false;
+                                }
+                            }
+                            break;
+                        }
+                    }
+                    found
+                };
+
+                if path.as_os_str() == smart_directory::RECENTLY_ADDED {
+                    // 在图片目录和固定的最近浏览图片所在的文件夹中查找最近修改过的图片
+                    let folders = self.pinned_folders();
+                    let db = self.catalog.as_ref().and_then(|_| catalog::catalog_path());
+                    let within = self.settings.recently_added;
+                    return Task::perform(
+                        catalog::recently_added(db, folders, within),
+                        Message::RecentlyAddedLoaded,
+                    );
+                }
+
+                if is_recents {
+                    // 处理 Recents 目录 - 更新图片集合为最近浏览的图片
+                    let recent_items = if path.as_os_str() == smart_directory::MOST_VIEWED {
+                        self.recent_manager.most_viewed()
+                    } else {
+                        self.recent_manager.get_recent_items().iter().collect()
+                    };
+                    let images = recent_items
+                        .into_iter()
+                        .map(|item| item.path().clone())
+                        .collect();
+                    return self.replace_collection(images);
+                }
+
+                if catalog::is_library(&path) {
+                    // 图片库换成智能相册中的图片；第一次展开时在后台更新索引
+                    let Some(album) = SmartAlbum::from_path(&path) else {
+                        return if self.catalog_indexed {
+                            Task::none()
+                        } else {
+                            self.update(Message::ReindexCatalog)
+                        };
+                    };
+                    let images = match self.catalog.as_ref().map(|catalog| catalog.album(album)) {
+                        Some(Ok(images)) => images,
+                        Some(Err(e)) => {
+                            return self.show_toast(
+                                ToastKind::Error,
+                                trf("Failed to read the catalog: {}", &[&e]),
+                            );
+                        }
+                        None => Vec::new(),
+                    };
+                    return self.replace_collection(images);
+                }
+
+                if cloud::is_remote(&path) {
+                    // 云端文件夹只在展开时列出对象
+                    if !needs_load {
+                        return Task::none();
+                    }
+                    let prefix = cloud::folder_prefix(&path);
+                    let config = self.settings.cloud.clone();
+                    return Task::perform(cloud::list(config, prefix), move |result| {
+                        Message::CloudListed(path.clone(), result)
+                    });
+                }
+
+                // 在后台扫描目录，分批填入目录树（需要加载时）和图片库
+                // 新的扫描会取消上一次还没完成的扫描，并立即停止生成旧文件夹的缩略图
+                self.leave_cloud_folder();
+                self.cancel_thumbnails();
+                let id = self.next_scan_id;
+                self.next_scan_id += 1;
+                let (task, handle) = Task::run(
+                    scan::scan_directory(path.clone(), self.config.show_hidden),
+                    move |event| Message::DirectoryScanned(id, event),
+                )
+                .abortable();
+                self.scan = Some(ActiveScan::new(id, path, needs_load, handle));
+                task
+            }
+            Message::DirectoryScanned(id, event) => {
+                let Some(scan) = self.scan.as_mut().filter(|scan| scan.id == id) else {
+                    return Task::none();
+                };
+                match event {
+                    ScanEvent::Batch(batch) => {
+                        let first = !scan.started;
+                        scan.started = true;
+                        scan.scanned = batch.scanned;
+                        if scan.fill_tree {
+                            let dir = scan.dir.clone();
+                            if let Some(FileTreeEntry::Directory {
+                                expanded: true,
+                                children,
+                                ..
+                            }) = self
+                                .root_file_tree_entry
+                                .iter_mut()
+                                .find_map(|root| find_entry_by_path(root, &dir))
+                            {
+                                children.extend(batch.children);
+                            }
+                        }
+                        // 收到第一批结果时才替换图片库，目录读取失败时保留原来的图片
+                        if first {
+                            self.image_collection.clear();
+                            self.active_search = None;
+                            self.forget_find_results();
+                            self.selected_images.clear();
+                            self.cancel_thumbnails();
+                        }
+                        self.record_thumbnail_lookups(&batch.images);
+                        self.image_collection.extend(batch.images);
+                        self.load_missing_thumbnails()
+                    }
+                    ScanEvent::Failed(kind, e) => {
+                        warn!("Failed to read {}: {}", scan.dir.display(), e);
+                        // 读取失败的目录收起并加锁，不再显示为空文件夹
+                        let dir = scan.dir.clone();
+                        if kind == std::io::ErrorKind::PermissionDenied
+                            && let Some(FileTreeEntry::Directory {
+                                expanded, access, ..
+                            }) = self
+                                .root_file_tree_entry
+                                .iter_mut()
+                                .find_map(|root| find_entry_by_path(root, &dir))
+                        {
+                            *expanded = false;
+                            *access = Access::Denied;
+                        }
+                        self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to open {}: {}", &[&dir.display(), &e]),
+                        )
+                    }
+                    ScanEvent::Finished => {
+                        let Some(scan) = self.scan.take() else {
+                            return Task::none();
+                        };
+                        // 图片全部到齐后按配置的顺序排列
+                        let resume = if scan.started {
+                            Task::none()
+                        } else {
+                            // 没有读到图片时仍显示原来的图片库，继续生成它的缩略图
+                            self.load_missing_thumbnails()
+                        };
+                        if scan.started {
+                            self.collection_folder = Some(scan.stamp.clone());
+                            self.folder_stale = false;
+                            self.sort.sort(&mut self.image_collection);
+                            self.current_image_index = self
+                                .current_image
+                                .as_ref()
+                                .and_then(|current| {
+                                    self.image_collection.iter().position(|p| p == current)
+                                })
+                                .unwrap_or(0);
+                        }
+                        // 扫描完整结束后才标记为已加载，中途取消的目录下次展开时重新扫描
+                        if scan.fill_tree
+                            && let Some(FileTreeEntry::Directory {
+                                children_loaded, ..
+                            }) = self
+                                .root_file_tree_entry
+                                .iter_mut()
+                                .find_map(|root| find_entry_by_path(root, &scan.dir))
+                        {
+                            *children_loaded = true;
+                        }
+                        // 地图面板和时间线换成新文件夹中的图片
+                        let timeline = match &self.timeline {
+                            Some(timeline) if timeline.source == TimelineSource::Collection => {
+                                self.timeline = Some(Timeline::new(TimelineSource::Collection));
+                                self.build_timeline()
+                            }
+                            _ => Task::none(),
+                        };
+                        Task::batch([self.locate_map_photos(), timeline, resume])
+                    }
+                }
+            }
+            Message::PickImage(path) if cloud::is_remote(&path) => {
+                // 云端图片先下载到本地缓存，再按本地文件打开
+                let key = cloud::object_key(&path).unwrap_or_default();
+                self.cloud_selected = Some(path.clone());
+                // 已缓存时直接打开，离线时也可以
+                if let Some(local) = self.remote_cache.get(&self.settings.cloud, &key, None) {
+                    self.save_remote_cache();
+                    return self.update(Message::PickImage(local));
+                }
+                let Some(local) = cloud::cache_path(&self.settings.cloud, &key) else {
+                    return Task::none();
+                };
+                let id = self
+                    .transfers
+                    .push(TransferDirection::Download, local, key, 0);
+                self.cloud_open = Some(id);
+                self.pump_transfers()
+            }
+            Message::PickImage(path) => {
+                // Ctrl/Cmd+点击切换选中，Shift+点击选中一段范围，不切换当前图片
+                if self.modifiers.command() {
+                    if let Some(pos) = self.selected_images.iter().position(|p| p == &path) {
+                        self.selected_images.remove(pos);
+                    } else {
+                        self.selected_images.push(path);
+                    }
+                    return Task::none();
+                }
+                if self.modifiers.shift()
+                    && let Some(end) = self.image_collection.iter().position(|p| p == &path)
+                {
+                    let start = self
+                        .current_image_index
+                        .min(self.image_collection.len() - 1);
+                    let (from, to) = (start.min(end), start.max(end));
+                    self.selected_images = self.image_collection[from..=to].to_vec();
+                    return Task::none();
+                }
+                self.selected_images.clear();
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                self.current_path = path.parent().unwrap_or(&path).to_path_buf();
+                self.current_image = Some(path.clone());
+                self.current_image_index = self
+                    .image_collection
+                    .iter()
+                    .position(|p| p == &path.clone())
+                    .unwrap_or(0);
+                let path = self.current_image.clone();
+                Task::batch([
+                    saved,
+                    Task::perform(
+                        async move { Message::LoadImage(path.unwrap_or_default()) },
+                        |msg| msg,
+                    ),
+                ])
+            }
+            Message::PickNextImage => {
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                if !self.image_collection.is_empty() {
+                    self.current_image_index =
+                        (self.current_image_index + 1) % self.image_collection.len();
+                    self.current_image =
+                        Some(self.image_collection[self.current_image_index].clone());
+                }
+                let path = self.current_image.clone();
+                Task::batch([
+                    saved,
+                    Task::perform(
+                        async move { Message::LoadImage(path.unwrap_or_default()) },
+                        |msg| msg,
+                    ),
+                ])
+            }
+            Message::PickPreviousImage => {
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                if !self.image_collection.is_empty() {
+                    if self.current_image_index == 0 {
+                        self.current_image_index = self.image_collection.len() - 1;
+                    } else {
+                        self.current_image_index -= 1;
+                    }
+                    self.current_image =
+                        Some(self.image_collection[self.current_image_index].clone());
+                }
+                let path = self.current_image.clone();
+                Task::batch([
+                    saved,
+                    Task::perform(
+                        async move { Message::LoadImage(path.unwrap_or_default()) },
+                        |msg| msg,
+                    ),
+                ])
+            }
+            Message::OpenResamplingBar => {
+                // 这里可以添加打开缩放条的逻辑
+                self.resampling_bar_opened = !self.resampling_bar_opened; // 切换缩放条状态
+                Task::none()
+            }
+            Message::SliderChanged(value) => {
+                let full_resolution = self.ensure_full_resolution();
+                self.slider_value = value;
+                self.is_dragging = true;
+                self.is_resampling_mode = true; // 进入缩放模式
+                // 防抖：滑块停顿一小段时间后才生成预览，期间的新变化会让旧的计时失效
+                self.resize_generation += 1;
+                let generation = self.resize_generation;
+                let preview = Task::perform(
+                    async move {
+                        tokio::time::sleep(PREVIEW_DEBOUNCE).await;
+                        Message::PreviewResize(generation)
+                    },
+                    |msg| msg,
+                );
+                Task::batch([full_resolution, preview])
+            }
+            Message::PreviewResize(generation) => {
+                if generation != self.resize_generation {
+                    return Task::none();
+                }
+
+                // 在拖动过程中使用Point算法（最快的算法）进行快速预览
+                self.spawn_resize(ResamplingType::Point, false) // false表示这是预览质量
+            }
+
+            Message::SliderReleased => {
+                // 滑块释放时，安排一个延迟任务来执行高质量渲染
+                // 不立即设置is_dragging = false，让FinalizeDragging来处理
+                // 释放后如果又拖动了滑块，代数会变化，这次的FinalizeDragging被丢弃
+                self.resize_generation += 1;
+                let generation = self.resize_generation;
+                Task::perform(
+                    async move {
+                        tokio::time::sleep(FINALIZE_DELAY).await;
+                        Message::FinalizeDragging(generation)
+                    },
+                    |msg| msg,
+                )
+            }
+
+            Message::FinalizeDragging(generation) => {
+                if !self.is_dragging || generation != self.resize_generation {
+                    return Task::none(); // 如果已经不在拖动状态或有更新的请求，不执行操作
+                }
+
+                self.is_dragging = false;
+                self.remember_view();
+
+                // 使用高质量算法进行最终渲染
+                self.spawn_resize(self.resampling_type, true) // true表示这是高质量渲染
+            }
+            Message::ResamplingTypeChanged(scale_type) => {
+                self.resampling_type = scale_type;
+                // 记住选择的算法，下次启动时作为默认算法
+                self.config.resampling = scale_type;
+                self.save_config();
+
+                // 如果有原始图片，立即应用新算法重新缩放
+                if self.original.is_some() {
+                    self.resize_generation += 1;
+                    return self.spawn_resize(scale_type, true); // 添加true表示这是高质量渲染
+                }
+                Task::none()
+            }
+            Message::ImageResized(scaled_bytes, is_high_quality, generation) => {
+                // 之后又有新的缩放请求，丢弃过期的结果
+                if generation != self.resize_generation {
+                    return Task::none();
+                }
+                self.resize_task = None;
+                if is_high_quality {
+                    // 高质量渲染结果，更新最终图像
+                    self.final_scaled_bytes = scaled_bytes;
+                    self.scaled_bytes = self.final_scaled_bytes.clone();
+                    self.render_blink();
+                } else if self.is_dragging {
+                    // 如果仍在拖动，更新预览图像
+                    self.preview_scaled_bytes = scaled_bytes;
+                    self.scaled_bytes = self.preview_scaled_bytes.clone();
+                }
+                self.enforce_memory_budget();
+                Task::none()
+            }
+            Message::LoadImage(path) => {
+                // Recent Image
+                self.recent_manager.add_item(path.clone());
+                self.save_recents();
+                self.refresh_smart_folders();
+                // 1. 加载图片
+                self.blink = None; // 切换图片时结束闪烁比较
+                let locked_view = self
+                    .view_locked
+                    .then_some((self.slider_value, self.pan_offset * self.reduction()));
+                self.is_dragging = false; // 重置拖动状态
+                self.slider_value = 50; // 重置缩放条值
+                self.is_resampling_mode = false; // 重置缩放模式
+                self.preview_scaled_bytes.clear(); // 清空预览缓存
+                self.final_scaled_bytes.clear(); // 清空最终缓存
+                self.cancel_resize(); // 上一张图片还没完成的缩放不再需要
+                self.pan_offset = iced::Vector::new(0.0, 0.0); // 重置拖动偏移量
+                self.is_panning = false; // 重置拖动状态
+                self.pan_start_position = None; // 重置拖动开始位置
+                self.gesture_zoom_remainder = 0.0;
+                // 上一张图片还在解码的原尺寸和等待它的操作不再需要
+                self.full_resolution_generation += 1;
+                self.full_resolution_pending = false;
+                self.after_full_resolution = None;
+                self.alpha = None;
+                self.alpha_pending = false;
+                self.reorienting = false;
+                // 锁定视图时沿用上一张图片的缩放和平移，否则恢复这张图片上次的视图，解码完成后按这个视图渲染
+                if let Some((zoom, offset)) = locked_view {
+                    self.slider_value = zoom;
+                    self.pan_offset = offset;
+                    self.is_resampling_mode = zoom != 50 || offset != iced::Vector::ZERO;
+                } else if self.settings.remember_view
+                    && let Some(view) = self.recent_manager.get(&path).and_then(|item| item.view())
+                {
+                    self.slider_value = view.zoom;
+                    self.pan_offset = iced::Vector::new(view.pan_x, view.pan_y);
+                    self.is_resampling_mode = true;
+                }
+                self.exif_info = None; // EXIF信息在后台读取
+                self.descriptive_metadata = None; // XMP/IPTC信息在后台读取
+                self.plugin_metadata.clear(); // 插件提供的信息在后台查询
+                self.texture = None; // 纹理信息随解码结果一起返回
+                self.mip_level = 0;
+                self.animation = None;
+                self.code_scan = None;
+                self.color_picking = false;
+                self.psd_layers.clear(); // 图层名称随解码结果一起返回
+                self.crop_mode = false; // 切换图片时退出裁剪模式
+                self.straighten_mode = false;
+                self.crop_selection = None;
+                self.overlay_handle = None;
+                self.load_error = None;
+
+                self.adjustments = Adjustments::default(); // 切换图片时重置调整
+                self.adjusted = None;
+                self.edited = None; // 编辑历史只对当前图片有效
+                self.history.clear();
+
+                // 最近看过的图片直接从缓存中取，不再重新解码
+                let raw_orientation = self.show_raw_orientation;
+                let cached = self.decoded_cache.get(&path, raw_orientation);
+                perf::record_cache(perf::Cache::Decoded, cached.is_some());
+                let metadata = Task::batch([
+                    load_exif_info(path.clone()),
+                    load_descriptive_metadata(path.clone()),
+                    load_plugin_metadata(path.clone()),
+                ]);
+                if let Some(decoded) = cached {
+                    debug!(path = %path.display(), "Decoded image cache hit");
+                    self.loading = None;
+                    return Task::batch([metadata, self.show_decoded(path, decoded)]);
+                }
+
+                // 解码期间先清空旧图，界面直接按路径显示新图片
+                self.original = None;
+                self.display_handle = None;
+                self.channel_view = None;
+                self.loading = Some(path.clone());
+                Task::batch([metadata, decode_in_background(path, raw_orientation)])
+            }
+            Message::ImageDecoded(path, raw_orientation, decoded) => {
+                let decoded = match decoded {
+                    Ok(decoded) => decoded,
+                    Err(e) => {
+                        warn!("Failed to load image {}: {}", path.display(), e);
+                        // 已经切换到其他图片或显示方向时不再提示
+                        if self.loading.as_ref() != Some(&path)
+                            || self.show_raw_orientation != raw_orientation
+                        {
+                            return Task::none();
+                        }
+                        self.loading = None;
+                        // 重新解码失败时继续显示原来方向的图片
+                        if std::mem::take(&mut self.reorienting) {
+                            return self.show_toast(
+                                ToastKind::Error,
+                                trf("Cannot open {}: {}", &[&file_name(&path), &e]),
+                            );
+                        }
+                        self.load_error = Some(e.clone());
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Cannot open {}: {}", &[&file_name(&path), &e]),
+                        );
+                    }
+                };
+                debug!(
+                    path = %path.display(),
+                    width = decoded.image.width(),
+                    height = decoded.image.height(),
+                    "Decoded image"
+                );
+                self.decoded_cache
+                    .insert(path.clone(), raw_orientation, decoded.clone());
+                // 解码期间已经切换到了其他图片或显示方向，只保留在缓存中
+                if self.loading.as_ref() != Some(&path)
+                    || self.show_raw_orientation != raw_orientation
+                {
+                    return Task::none();
+                }
+                self.loading = None;
+                if std::mem::take(&mut self.reorienting) {
+                    return self.show_reoriented(decoded);
+                }
+                self.show_decoded(path, decoded)
+            }
+            Message::SpinnerTick => {
+                self.spinner_frame = self.spinner_frame.wrapping_add(1);
+                Task::none()
+            }
+            Message::TogglePerfHud => {
+                self.perf_hud_opened = !self.perf_hud_opened;
+                Task::none()
+            }
+            // 统计数据在全局中更新，这里只需要触发界面刷新
+            Message::PerfHudTick => Task::none(),
+            Message::ResetPerfStats => {
+                perf::reset();
+                Task::none()
+            }
+            Message::LoadThumbnail(path) => {
+                // 单独重新生成一张缩略图（例如文件被覆盖后）
+                // 同一张图片连续请求时只接受最后一次的结果，避免旧结果覆盖新缩略图
+                self.thumbnail_generation += 1;
+                let generation = self.thumbnail_generation;
+                self.thumbnail_requests.insert(path.clone(), generation);
+                Task::perform(
+                    async move {
+                        let result = tokio::task::spawn_blocking(move || {
+                            let handle = thumbnails::generate_thumbnail(&path);
+                            let info = FileInfo::read(&path);
+                            (path, handle, info)
+                        })
+                        .await;
+                        match result {
+                            Ok((path, handle, info)) => {
+                                Message::ThumbnailLoaded(path, handle, info, generation)
+                            }
+                            Err(_) => Message::NoOp,
+                        }
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::ThumbnailLoaded(path, handle, info, generation) => {
+                if self.thumbnail_requests.get(&path) != Some(&generation) {
+                    return Task::none();
+                }
+                // 缩略图加载完成，保存到缓存
+                self.thumbnail_requests.remove(&path);
+                self.thumbnails_pending.remove(&path);
+                if let Some(info) = info {
+                    self.file_info.insert(path.clone(), info);
+                }
+                self.thumbnail_cache.insert(path, handle);
+                self.enforce_memory_budget();
+                Task::none()
+            }
+            Message::ThumbnailsLoaded(generation, batch) => {
+                // 上一个文件夹的结果可能在取消之前已经发出
+                if generation != self.collection_generation {
+                    return Task::none();
+                }
+                for (path, handle, info) in batch {
+                    // 已经取消的任务（例如切换了文件夹）或之后又单独请求过的缩略图不再写入
+                    if self.thumbnails_pending.remove(&path)
+                        && !self.thumbnail_requests.contains_key(&path)
+                    {
+                        if let Some(info) = info {
+                            self.file_info.insert(path.clone(), info);
+                        }
+                        self.thumbnail_cache.insert(path, handle);
+                    }
+                }
+                self.enforce_memory_budget();
+                Task::none()
+            }
+            Message::ToggleHandTool => {
+                self.hand_tool_active = !self.hand_tool_active;
+                self.ensure_full_resolution()
+            }
+            Message::MousePressed(event) => {
+                if self.double_clicked() {
+                    return self.update(Message::ToggleFullscreen);
+                }
+                if self.hand_tool_active
+                    && let iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left) = event
+                {
+                    self.is_panning = true;
+                    self.pan_start_position = None; // 重置开始位置，等待第一个MouseMoved事件
+                }
+                Task::none()
+            }
+            Message::MouseReleased(event) => {
+                if self.hand_tool_active
+                    && let iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left) = event
+                {
+                    self.is_panning = false;
+                    self.pan_start_position = None;
+
+                    // 拖动结束后，使用高质量算法重新渲染
+                    if let Some(ori) = self.shown_source() {
+                        let scale = self.slider_value as f32 / 50.0;
+                        match crop_and_scale(ori, scale, self.pan_offset, self.resampling_type) {
+                            Ok(final_image) => {
+                                self.scaled_bytes = final_image.clone();
+                                self.final_scaled_bytes = final_image;
+                            }
+                            Err(e) => error!("Failed to scale image: {}", e),
+                        }
+                    }
+                    self.render_blink();
+                    self.remember_view();
+                }
+                Task::none()
+            }
+            Message::MouseMoved(position) => {
+                if self.hand_tool_active && self.is_panning {
+                    if let Some(last) = self.pan_start_position {
+                        let delta = Vector::new(position.x - last.x, position.y - last.y);
+                        self.pan_offset = self.pan_offset + delta;
+                        self.pan_start_position = Some(position);
+                        if let Some(pan) = &mut self.temporary_pan {
+                            pan.moved = true;
+                        }
+
+                        // 重新裁剪+缩放
+                        if let Some(ori) = self.shown_source() {
+                            let scale = self.slider_value as f32 / 50.0;
+                            let preview = crop_and_scale(
+                                ori,
+                                scale,
+                                self.pan_offset,
+                                ResamplingType::Point, // 拖动时用最快的算法
+                            );
+                            match preview {
+                                Ok(preview) => {
+                                    self.scaled_bytes = preview.clone();
+                                    self.preview_scaled_bytes = preview;
+                                }
+                                Err(e) => error!("Failed to scale image: {}", e),
+                            }
+                        }
+                    } else {
+                        self.pan_start_position = Some(position);
+                    }
+                }
+                Task::none()
+            }
+            // 开启滚轮切换时，不按修饰键的滚动切换图片，Ctrl+滚动仍然缩放
+            Message::ViewerScrolled(delta)
+                if self.settings.wheel_navigation && self.modifiers.is_empty() =>
+            {
+                match self.wheel_steps.update(delta) {
+                    1 => self.update(Message::PickPreviousImage),
+                    -1 => self.update(Message::PickNextImage),
+                    _ => Task::none(),
+                }
+            }
+            Message::ViewerScrolled(delta) => {
+                self.apply_gesture(gestures::from_scroll(delta, self.modifiers))
+            }
+            Message::Touched(event) => match self.touches.update(event) {
+                Some(gesture) => self.apply_gesture(gesture),
+                None => Task::none(),
+            },
+            Message::ViewerPressed => {
+                if self.double_clicked() {
+                    return self.update(Message::ToggleFullscreen);
+                }
+                Task::none()
+            }
+            Message::ViewerMiddlePressed => self.start_temporary_pan(true),
+            Message::ViewerMeasured(bounds) => {
+                // 100% 按原图的像素计算
+                if let Some(task) = self.with_full_resolution(Message::ViewerMeasured(bounds)) {
+                    return task;
+                }
+                let (Some(bounds), Some((width, height))) =
+                    (bounds, self.display_source().map(|img| img.dimensions()))
+                else {
+                    return Task::none();
+                };
+                // 图片按 Contain 缩放到查看区域，100% 时一个图片像素对应一个屏幕像素
+                let padding = if self.is_fullscreen { 0.0 } else { 40.0 };
+                let fit = ((bounds.width - padding) / width as f32)
+                    .min((bounds.height - padding) / height as f32);
+                if fit <= 0.0 {
+                    return Task::none();
+                }
+                self.zoom_view((50.0 / fit).round().clamp(50.0, 150.0) as u8)
+            }
+            Message::ViewLockToggled(locked) => {
+                self.view_locked = locked;
+                Task::none()
+            }
+            Message::ChannelChanged(channel) => {
+                self.channel = channel;
+                self.refresh_display();
+                self.rerender_scaled();
+                self.load_alpha()
+            }
+            Message::ToggleBlink => {
+                if self.blink.take().is_some() || self.crop_mode || self.straighten_mode {
+                    return Task::none();
+                }
+                let Some(paths) = self.blink_pair() else {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        tr("Select two images to compare").to_string(),
+                    );
+                };
+                self.blink = Some(Blink::new(paths.clone()));
+                let apply_orientation = !self.show_raw_orientation;
+                Task::perform(
+                    async move {
+                        let decoded = tokio::task::spawn_blocking({
+                            let paths = paths.clone();
+                            move || blink::decode(paths, apply_orientation)
+                        })
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| r);
+                        Message::BlinkDecoded(paths, decoded)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::BlinkDecoded(paths, decoded) => {
+                // 解码期间已经结束或换了图片
+                let Some(blink) = self.blink.as_mut().filter(|blink| blink.paths == paths) else {
+                    return Task::none();
+                };
+                match decoded {
+                    Ok(images) => {
+                        blink.images = Some(images);
+                        self.render_blink();
+                        Task::none()
+                    }
+                    Err(e) => {
+                        self.blink = None;
+                        self.show_toast(ToastKind::Error, trf("Cannot compare images: {}", &[&e]))
+                    }
+                }
+            }
+            Message::BlinkTick => {
+                if let Some(blink) = &mut self.blink {
+                    blink.toggle();
+                }
+                Task::none()
+            }
+            Message::PauseBlink => {
+                if let Some(blink) = &mut self.blink {
+                    blink.paused = !blink.paused;
+                }
+                Task::none()
+            }
+            Message::BlinkIntervalChanged(interval) => {
+                self.config.blink_interval = interval;
+                self.save_config();
+                Task::none()
+            }
+            Message::KeyReleased(key, modifiers) => {
+                if self.temporary_pan.is_some_and(|pan| !pan.middle_button)
+                    && self.keymap.action(&key, modifiers) == Some(Action::HoldToPan)
+                {
+                    return self.end_temporary_pan();
+                }
+                Task::none()
+            }
+            Message::EndTemporaryPan => {
+                if self.temporary_pan.is_some_and(|pan| pan.middle_button) {
+                    return self.end_temporary_pan();
+                }
+                Task::none()
+            }
+            Message::ViewSettled(generation) => {
+                if !self.is_dragging || generation != self.resize_generation {
+                    return Task::none();
+                }
+                self.is_dragging = false;
+                self.remember_view();
+                self.spawn_resize(self.resampling_type, true)
+            }
+            Message::ToggleFullscreen => self.set_fullscreen(!self.is_fullscreen),
+            Message::EscPressed => {
+                if self.recent_menu.take().is_some() {
+                    return Task::none();
+                }
+                if self.find_bar_opened {
+                    self.find_bar_opened = false;
+                    return Task::none();
+                }
+                if self.remote_edit.is_some() {
+                    return self.update(Message::CancelRemoteEdit);
+                }
+                if self.name_prompt.take().is_some() {
+                    return Task::none();
+                }
+                if self.script_dialog_opened {
+                    self.script_dialog_opened = false;
+                    return Task::none();
+                }
+                if self.contact_sheet_dialog_opened {
+                    self.contact_sheet_dialog_opened = false;
+                    return Task::none();
+                }
+                if self.gallery_wizard.take().is_some() {
+                    return Task::none();
+                }
+                if self.timeline.take().is_some() {
+                    return Task::none();
+                }
+                if self.crop_mode {
+                    return self.update(Message::ToggleCropMode);
+                }
+                if self.straighten_mode {
+                    return self.update(Message::ToggleStraightenMode);
+                }
+                if self.code_scan.is_some() {
+                    return self.update(Message::ScanCodes);
+                }
+                if self.color_picking {
+                    return self.update(Message::ToggleColorPicker);
+                }
+                if self.blink.take().is_some() {
+                    return Task::none();
+                }
+                if self.slideshow.take().is_some() {
+                    self.slideshow_paused = false;
+                    return Task::none();
+                }
+                if self.is_fullscreen {
+                    return self.set_fullscreen(false);
+                }
+                Task::none()
+            }
+            Message::UploadToCloud(path) => {
+                if !self.settings.cloud.is_configured() {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        tr("Cloud storage is not configured").to_string(),
+                    );
+                }
+                // 同一个文件正在上传时不重复提交
+                if self.transfers.is_uploading(&path) {
+                    return Task::none();
+                }
+                let size = std::fs::metadata(&path).map_or(0, |meta| meta.len());
+                let key = cloud_storage::upload_key(&path);
+                self.transfers
+                    .push(TransferDirection::Upload, path, key, size);
+                self.pump_transfers()
+            }
+            Message::UploadSelected => {
+                let targets = self.convert_targets();
+                Task::batch(
+                    targets
+                        .into_iter()
+                        .map(|path| self.update(Message::UploadToCloud(path))),
+                )
+            }
+            Message::ToggleSettingsDialog => {
+                self.settings_dialog_opened = !self.settings_dialog_opened;
+                if !self.settings_dialog_opened || self.ocr_languages.is_some() {
+                    return Task::none();
+                }
+                Task::perform(
+                    async {
+                        tokio::task::spawn_blocking(ocr::languages)
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|r| r)
+                    },
+                    Message::OcrLanguagesListed,
+                )
+            }
+            Message::OcrLanguagesListed(languages) => {
+                self.ocr_languages = Some(languages);
+                Task::none()
+            }
+            Message::OcrLanguageToggled(language) => {
+                let mut selected: Vec<&str> = self
+                    .config
+                    .ocr_languages
+                    .split('+')
+                    .filter(|code| !code.is_empty())
+                    .collect();
+                if let Some(index) = selected.iter().position(|&code| code == language) {
+                    // 至少保留一种语言
+                    if selected.len() > 1 {
+                        selected.remove(index);
+                    }
+                } else {
+                    selected.push(&language);
+                }
+                self.config.ocr_languages = selected.join("+");
+                self.save_config();
+                Task::none()
+            }
+            Message::AutoUploadToggled(enabled) => {
+                self.settings.auto_upload = enabled;
+                self.save_settings();
+                Task::none()
+            }
+            Message::AddAutoUploadFolder => {
+                let mut dialog = FileDialog::new();
+                if let Some(parent) = self.current_image.as_ref().and_then(|p| p.parent()) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(dir) = dialog.pick_folder()
+                    && !self.settings.auto_upload_folders.contains(&dir)
+                {
+                    self.settings.auto_upload_folders.push(dir);
+                    self.save_settings();
+                }
+                Task::none()
+            }
+            Message::RemoveAutoUploadFolder(dir) => {
+                self.settings
+                    .auto_upload_folders
+                    .retain(|folder| *folder != dir);
+                self.save_settings();
+                Task::none()
+            }
+            Message::CloudProviderChanged(provider) => {
+                self.settings.cloud.provider = provider;
+                self.save_settings();
+                self.refresh_cloud_root();
+                Task::none()
+            }
+            Message::OssConfigChanged(config) => {
+                self.settings.cloud.oss = config;
+                self.save_settings();
+                self.refresh_cloud_root();
+                Task::none()
+            }
+            Message::S3ConfigChanged(config) => {
+                self.settings.cloud.s3 = config;
+                self.save_settings();
+                self.refresh_cloud_root();
+                Task::none()
+            }
+            Message::UploadFinished(path, result) => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                match result {
+                    Ok(()) => self.show_toast(ToastKind::Success, trf("Uploaded {}", &[&name])),
+                    Err(e) => {
+                        warn!("Failed to upload {}: {}", path.display(), e);
+                        self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to upload {}: {}", &[&name, &e]),
+                        )
+                    }
+                }
+            }
+            Message::DismissToast(id) => {
+                self.toasts.retain(|toast| toast.id != id);
+                Task::none()
+            }
+            Message::CloudListed(path, result) => {
+                let entry = self
+                    .root_file_tree_entry
+                    .iter_mut()
+                    .find_map(|root| find_entry_by_path(root, &path));
+                let listing = match result {
+                    Ok(listing) => listing,
+                    Err(e) => {
+                        // 折叠起来，下次点击时重试
+                        if let Some(FileTreeEntry::Directory { expanded, .. }) = entry {
+                            *expanded = false;
+                        }
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to list cloud folder: {}", &[&e]),
+                        );
+                    }
+                };
+                if let Some(FileTreeEntry::Directory {
+                    expanded: true,
+                    children,
+                    children_loaded,
+                    ..
+                }) = entry
+                {
+                    *children = cloud::tree_children(&listing);
+                    *children_loaded = true;
+                }
+
+                // 图片库换成这个文件夹中的图片：已缓存的直接加入，其余排队下载，完成一张加入一张
+                self.scan = None;
+                self.leave_cloud_folder();
+                self.image_collection.clear();
+                self.collection_folder = None;
+                self.active_search = None;
+                self.forget_find_results();
+                self.selected_images.clear();
+                self.cancel_thumbnails();
+                for object in listing.objects {
+                    if !cloud::is_image(&object.key) {
+                        continue;
+                    }
+                    // 缓存的版本与存储桶中的 ETag 不同时重新下载
+                    if let Some(local) =
+                        self.remote_cache
+                            .get(&self.settings.cloud, &object.key, Some(&object.etag))
+                    {
+                        self.image_collection.push(local);
+                    } else if let Some(local) = cloud::cache_path(&self.settings.cloud, &object.key)
+                    {
+                        let id = self.transfers.push(
+                            TransferDirection::Download,
+                            local,
+                            object.key,
+                            object.size,
+                        );
+                        self.transfers.set_etag(id, object.etag);
+                    }
+                }
+                self.save_remote_cache();
+                self.cloud_folder = Some(cloud::folder_prefix(&path));
+                self.record_thumbnail_lookups(&self.image_collection);
+                Task::batch([self.load_missing_thumbnails(), self.pump_transfers()])
+            }
+            Message::AddSyncFolder => {
+                if let Some(dir) = FileDialog::new().pick_folder()
+                    && !self.settings.sync_folders.iter().any(|f| f.local == dir)
+                {
+                    self.settings.sync_folders.push(SyncFolder::new(dir));
+                    self.save_settings();
+                }
+                Task::none()
+            }
+            Message::RemoveSyncFolder(dir) => {
+                self.settings.sync_folders.retain(|f| f.local != dir);
+                self.save_settings();
+                Task::none()
+            }
+            Message::SyncConflictChanged(policy) => {
+                self.settings.sync_conflict = policy;
+                self.save_settings();
+                Task::none()
+            }
+            Message::SyncIntervalChanged(minutes) => {
+                self.settings.sync_interval = minutes;
+                self.save_settings();
+                Task::none()
+            }
+            Message::SyncNow => {
+                if self.sync_progress.is_some() || self.settings.sync_folders.is_empty() {
+                    return Task::none();
+                }
+                if !self.settings.cloud.is_configured() {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        tr("Cloud storage is not configured").to_string(),
+                    );
+                }
+                self.sync_progress = Some((0, 0));
+                Task::run(
+                    sync::run(
+                        self.settings.sync_folders.clone(),
+                        self.settings.sync_conflict,
+                        self.settings.cloud.clone(),
+                    ),
+                    Message::SyncProgress,
+                )
+            }
+            Message::SyncProgress(SyncEvent::Progress(done, total)) => {
+                self.sync_progress = Some((done, total));
+                Task::none()
+            }
+            Message::SyncProgress(SyncEvent::Finished(summary)) => {
+                self.sync_progress = None;
+                for (name, error) in &summary.failures {
+                    warn!("Sync failed: {} {}", name, error);
+                }
+                let kind = if summary.failures.is_empty() {
+                    ToastKind::Success
+                } else {
+                    ToastKind::Error
+                };
+                self.show_toast(kind, summary.describe())
+            }
+            Message::TransferProgress(id, TransferEvent::Progress(transferred, size)) => {
+                self.transfers.progress(id, transferred, size);
+                Task::none()
+            }
+            Message::TransferProgress(id, TransferEvent::Hashed(md5)) => {
+                self.transfers.set_md5(id, md5);
+                Task::none()
+            }
+            Message::TransferProgress(id, TransferEvent::Skipped) => {
+                self.transfers.skip(id);
+                Task::none()
+            }
+            Message::TransferProgress(id, TransferEvent::Tagged(etag)) => {
+                self.transfers.set_etag(id, etag);
+                Task::none()
+            }
+            Message::TransferProgress(id, TransferEvent::Finished(result)) => {
+                let Some(transfer) = self.transfers.finish(id, result.clone()) else {
+                    return self.pump_transfers();
+                };
+                let (direction, local, key) = (
+                    transfer.direction,
+                    transfer.local.clone(),
+                    transfer.key.clone(),
+                );
+                let (md5, skipped, etag) = (
+                    transfer.md5.clone(),
+                    transfer.skipped,
+                    transfer.etag.clone(),
+                );
+                let waiting = match &transfer.status {
+                    TransferStatus::Waiting(e) => Some(e.clone()),
+                    _ => None,
+                };
+                match &result {
+                    Ok(()) => {
+                        info!(key = %key, direction = ?direction, skipped, "Transfer finished")
+                    }
+                    Err(e) => {
+                        warn!(key = %key, direction = ?direction, error = %e, "Transfer failed")
+                    }
+                }
+                // 记下上传成功的内容，离线时也能判断是否需要重新上传
+                if direction == TransferDirection::Upload
+                    && result.is_ok()
+                    && let (Some(md5), Some(bucket)) = (md5, self.settings.cloud.bucket_id())
+                {
+                    self.upload_manifest.record(&bucket, &key, md5);
+                    if let Some(path) = upload_manifest::manifest_path()
+                        && let Err(e) = self.upload_manifest.save_to_file(path)
+                    {
+                        error!("Failed to save upload manifest: {}", e);
+                    }
+                }
+                // 下载的副本记入远程缓存，超过上限时删除最久没有打开的
+                if direction == TransferDirection::Download && result.is_ok() {
+                    self.remote_cache
+                        .record(&self.settings.cloud, &key, local.clone(), etag);
+                    self.remote_cache.evict(self.settings.remote_cache_limit);
+                    self.save_remote_cache();
+                }
+                let task = match direction {
+                    // 还会自动重试，只在次数用完后提示
+                    TransferDirection::Upload if waiting.is_some() => {
+                        warn!(
+                            "Upload of {} failed, will retry: {}",
+                            local.display(),
+                            waiting.unwrap_or_default()
+                        );
+                        Task::none()
+                    }
+                    // 因为分享而上传的图片，上传完成后复制分享链接
+                    TransferDirection::Upload
+                        if result.is_ok() && self.share_pending.as_ref() == Some(&local) =>
+                    {
+                        self.share_pending = None;
+                        self.update(Message::CopyShareLink(key))
+                    }
+                    TransferDirection::Upload if skipped => {
+                        let name = local.file_name().unwrap_or_default().to_string_lossy();
+                        self.show_toast(ToastKind::Success, trf("{} is already uploaded", &[&name]))
+                    }
+                    TransferDirection::Upload => {
+                        self.update(Message::UploadFinished(local, result))
+                    }
+                    TransferDirection::Download if self.cloud_open == Some(id) => {
+                        self.cloud_open = None;
+                        match result {
+                            Ok(()) => self.update(Message::PickImage(local)),
+                            Err(e) => self
+                                .show_toast(ToastKind::Error, trf("Failed to download: {}", &[&e])),
+                        }
+                    }
+                    TransferDirection::Download => {
+                        // 仍在浏览这个云端文件夹时加入图片库
+                        if result.is_ok()
+                            && self.cloud_folder.as_deref() == Some(cloud::parent_prefix(&key))
+                            && !self.image_collection.contains(&local)
+                        {
+                            self.record_thumbnail_lookups(std::slice::from_ref(&local));
+                            self.image_collection.push(local);
+                            self.load_missing_thumbnails()
+                        } else {
+                            Task::none()
+                        }
+                    }
+                };
+                Task::batch([task, self.pump_transfers()])
+            }
+            Message::ToggleTransfersPanel => {
+                self.transfers_panel_opened = !self.transfers_panel_opened;
+                Task::none()
+            }
+            Message::PauseTransfer(id) => {
+                self.transfers.pause(id);
+                self.pump_transfers()
+            }
+            Message::ResumeTransfer(id) => {
+                self.transfers.resume(id);
+                self.pump_transfers()
+            }
+            Message::CancelTransfer(id) => {
+                self.transfers.cancel(id);
+                self.pump_transfers()
+            }
+            Message::RetryTransfer(id) => {
+                self.transfers.retry(id);
+                self.pump_transfers()
+            }
+            Message::ClearFinishedTransfers => {
+                self.transfers.clear_finished();
+                self.save_uploads();
+                Task::none()
+            }
+            Message::CopyShareLink(key) => {
+                let expiry = self.settings.share_expiry;
+                let url = cloud_storage::connect(&self.settings.cloud)
+                    .and_then(|storage| storage.presigned_url(&key, expiry.seconds()));
+                match url {
+                    Ok(url) => Task::batch([
+                        iced::clipboard::write(url),
+                        self.show_toast(
+                            ToastKind::Success,
+                            trf("Share link copied (valid for {})", &[&expiry.name()]),
+                        ),
+                    ]),
+                    Err(e) => self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to create share link: {}", &[&e]),
+                    ),
+                }
+            }
+            Message::ShareExpiryChanged(expiry) => {
+                self.settings.share_expiry = expiry;
+                self.save_settings();
+                Task::none()
+            }
+            Message::UploadLimitChanged(limit) => {
+                self.settings.upload_limit = limit;
+                throttle::set_limits(limit, self.settings.download_limit);
+                self.save_settings();
+                Task::none()
+            }
+            Message::DownloadLimitChanged(limit) => {
+                self.settings.download_limit = limit;
+                throttle::set_limits(self.settings.upload_limit, limit);
+                self.save_settings();
+                Task::none()
+            }
+            Message::CacheLimitChanged(limit) => {
+                self.settings.remote_cache_limit = limit;
+                self.save_settings();
+                if self.remote_cache.evict(limit) {
+                    self.save_remote_cache();
+                }
+                Task::none()
+            }
+            Message::ClearRemoteCache => {
+                let result = self.remote_cache.clear();
+                self.save_remote_cache();
+                match result {
+                    Ok(freed) => self.show_toast(
+                        ToastKind::Success,
+                        trf(
+                            "Cleared remote cache ({})",
+                            &[&memory::format_bytes(freed as usize)],
+                        ),
+                    ),
+                    Err(e) => self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to clear remote cache: {}", &[&e]),
+                    ),
+                }
+            }
+            Message::DeleteRemoteObject(key) => {
+                self.remote_edit = Some(RemoteEdit::Delete(key));
+                Task::none()
+            }
+            Message::RenameRemoteObject(key) => {
+                self.remote_edit = Some(RemoteEdit::Rename {
+                    target: key.clone(),
+                    key,
+                });
+                Task::none()
+            }
+            Message::RemoteRenameChanged(value) => {
+                if let Some(RemoteEdit::Rename { target, .. }) = &mut self.remote_edit {
+                    *target = value;
+                }
+                Task::none()
+            }
+            Message::CancelRemoteEdit => {
+                self.remote_edit = None;
+                Task::none()
+            }
+            Message::ConfirmRemoteEdit => {
+                let Some(edit) = self.remote_edit.take() else {
+                    return Task::none();
+                };
+                let config = self.settings.cloud.clone();
+                match edit.clone() {
+                    RemoteEdit::Delete(key) => {
+                        Task::perform(cloud::delete(config, key), move |result| {
+                            Message::RemoteEdited(edit.clone(), result)
+                        })
+                    }
+                    RemoteEdit::Rename { key, target } => Task::perform(
+                        cloud::rename(config, key, target.trim().to_string()),
+                        move |result| Message::RemoteEdited(edit.clone(), result),
+                    ),
+                }
+            }
+            Message::RemoteEdited(edit, result) => {
+                let (key, failed) = match &edit {
+                    RemoteEdit::Delete(key) => (key.clone(), "Failed to delete {}: {}"),
+                    RemoteEdit::Rename { key, .. } => (key.clone(), "Failed to rename {}: {}"),
+                };
+                if let Err(e) = result {
+                    return self.show_toast(ToastKind::Error, trf(failed, &[&key, &e]));
+                }
+                // 本地缓存跟着删除或改名
+                let message = match &edit {
+                    RemoteEdit::Delete(_) => {
+                        self.remote_cache.remove(&self.settings.cloud, &key);
+                        self.save_remote_cache();
+                        self.cloud_selected = None;
+                        trf("Deleted {}", &[&key])
+                    }
+                    RemoteEdit::Rename { target, .. } => {
+                        let target = target.trim();
+                        self.remote_cache.rename(&self.settings.cloud, &key, target);
+                        self.save_remote_cache();
+                        self.cloud_selected = Some(cloud::remote_path(target));
+                        // 目标文件夹折叠起来，下次展开时重新列出
+                        let target_folder = cloud::remote_path(cloud::parent_prefix(target));
+                        if cloud::parent_prefix(target) != cloud::parent_prefix(&key)
+                            && let Some(FileTreeEntry::Directory {
+                                expanded,
+                                children_loaded,
+                                ..
+                            }) = self
+                                .root_file_tree_entry
+                                .iter_mut()
+                                .find_map(|root| find_entry_by_path(root, &target_folder))
+                        {
+                            *expanded = false;
+                            *children_loaded = false;
+                        }
+                        trf("Renamed {} to {}", &[&key, &target])
+                    }
+                };
+                // 重新列出原来所在的文件夹
+                let folder = cloud::remote_path(cloud::parent_prefix(&key));
+                let prefix = cloud::parent_prefix(&key).to_string();
+                let config = self.settings.cloud.clone();
+                Task::batch([
+                    self.show_toast(ToastKind::Success, message),
+                    Task::perform(cloud::list(config, prefix), move |result| {
+                        Message::CloudListed(folder.clone(), result)
+                    }),
+                ])
+            }
+            Message::RetryTransfersTick => {
+                if self.transfers.requeue_due(chrono::Utc::now().timestamp()) {
+                    self.pump_transfers()
+                } else {
+                    Task::none()
+                }
+            }
+            Message::ToggleInfoPanel => {
+                self.info_panel_opened = !self.info_panel_opened;
+                Task::none()
+            }
+            Message::ToggleRecentMenu(folder, path) => {
+                let menu = Some((folder, path));
+                self.recent_menu = if self.recent_menu == menu { None } else { menu };
+                Task::none()
+            }
+            Message::RemoveRecent(path) => {
+                self.recent_menu = None;
+                let _ = self.recent_manager.delete_item(&path);
+                self.save_recents();
+                self.refresh_smart_folders();
+                Task::none()
+            }
+            Message::AddCatalogFolder => {
+                if let Some(dir) = FileDialog::new().pick_folder()
+                    && !self.settings.catalog_folders.contains(&dir)
+                {
+                    self.settings.catalog_folders.push(dir);
+                    self.save_settings();
+                    self.refresh_library_root();
+                    return self.update(Message::ReindexCatalog);
+                }
+                Task::none()
+            }
+            Message::RemoveCatalogFolder(dir) => {
+                self.settings
+                    .catalog_folders
+                    .retain(|folder| *folder != dir);
+                self.save_settings();
+                self.refresh_library_root();
+                // 重新索引时删除这个文件夹中的图片
+                if self.catalog.is_some() {
+                    return self.update(Message::ReindexCatalog);
+                }
+                Task::none()
+            }
+            Message::ReindexCatalog => {
+                if self.catalog_progress.is_some() || self.catalog.is_none() {
+                    return Task::none();
+                }
+                let Some(db) = catalog::catalog_path() else {
+                    return Task::none();
+                };
+                self.catalog_indexed = true;
+                self.catalog_progress = Some((0, 0));
+                Task::run(
+                    catalog::index(self.settings.catalog_folders.clone(), db),
+                    Message::CatalogIndexed,
+                )
+            }
+            Message::CatalogIndexed(IndexEvent::Progress(done, total)) => {
+                self.catalog_progress = Some((done, total));
+                Task::none()
+            }
+            Message::CatalogIndexed(IndexEvent::Finished(result)) => {
+                self.catalog_progress = None;
+                match result {
+                    Ok(_) => Task::none(),
+                    Err(e) => {
+                        self.show_toast(ToastKind::Error, trf("Failed to index catalog: {}", &[&e]))
+                    }
+                }
+            }
+            Message::SearchLibraryToggled(enabled) => {
+                self.search_library = enabled;
+                Task::none()
+            }
+            Message::ToggleFindBar => {
+                self.find_bar_opened = !self.find_bar_opened;
+                if self.find_bar_opened {
+                    text_input::focus(text_input::Id::new(FIND_INPUT))
+                } else {
+                    Task::none()
+                }
+            }
+            Message::FindQueryChanged(query) => {
+                self.find_query = query;
+                Task::none()
+            }
+            Message::FindRegexToggled(regex) => {
+                self.find_regex = regex;
+                Task::none()
+            }
+            Message::Find => {
+                let query = self.find_query.trim().to_string();
+                if query.is_empty() || self.finding {
+                    return Task::none();
+                }
+                let pattern = match name_search::NamePattern::parse(&query, self.find_regex) {
+                    Ok(pattern) => pattern,
+                    Err(e) => {
+                        return self
+                            .show_toast(ToastKind::Error, trf("Invalid pattern: {}", &[&e]));
+                    }
+                };
+                // 没有图片目录时遍历加入图片目录和固定的文件夹，都没有设置时遍历当前文件夹
+                let db = self.catalog.as_ref().and_then(|_| catalog::catalog_path());
+                let mut roots: Vec<PathBuf> = self
+                    .settings
+                    .catalog_folders
+                    .iter()
+                    .chain(&self.config.pinned_folders)
+                    .filter(|folder| !cloud::is_remote(folder))
+                    .cloned()
+                    .collect();
+                if roots.is_empty() {
+                    roots.push(self.current_path.clone());
+                }
+                self.finding = true;
+                Task::perform(name_search::find(pattern, db, roots), move |result| {
+                    Message::FindFinished(query.clone(), result)
+                })
+            }
+            Message::FindFinished(query, result) => {
+                self.finding = false;
+                let images = match result {
+                    Ok(images) => images,
+                    Err(e) => {
+                        return self.show_toast(ToastKind::Error, trf("Search failed: {}", &[&e]));
+                    }
+                };
+                let task = self.show_results(images);
+                self.find_results = Some(query);
+                task
+            }
+            Message::ClearFindResults => {
+                if self.find_results.take().is_some()
+                    | self.similar_to.take().is_some()
+                    | self.flag_filter.take().is_some()
+                {
+                    self.image_collection = std::mem::take(&mut self.find_backup);
+                    self.current_image_index = self
+                        .current_image
+                        .as_ref()
+                        .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                        .unwrap_or(0);
+                    return self.load_missing_thumbnails();
+                }
+                Task::none()
+            }
+            Message::ThemeChanged(theme) => {
+                self.config.theme = theme;
+                self.save_config();
+                Task::none()
+            }
+            Message::ToggleTheme => {
+                self.config.theme = if self.is_dark() {
+                    ThemeMode::Light
+                } else {
+                    ThemeMode::Dark
+                };
+                self.save_config();
+                Task::none()
+            }
+            Message::AccentChanged(accent) => {
+                self.config.accent = accent;
+                self.save_config();
+                Task::none()
+            }
+            Message::LanguageChanged(language) => {
+                self.config.language = language;
+                i18n::set_language(language);
+                self.save_config();
+                Task::none()
+            }
+            Message::KeyPressed(window, key, modifiers) => {
+                // 用手型工具查看放大的图片时，方向键平移图片而不是切换图片
+                if window == self.main_window
+                    && self.hand_tool_active
+                    && self.slider_value > 50
+                    && modifiers.is_empty()
+                    && let Some(pan) = gestures::arrow_pan(&key)
+                {
+                    return self.set_view(self.slider_value, self.pan_offset + pan);
+                }
+                match self.keymap.action(&key, modifiers) {
+                    Some(action) if window == self.main_window => self.run_action(action),
+                    Some(action) => self.run_viewer_action(window, action),
+                    None => Task::none(),
+                }
+            }
+            Message::DeleteImage => {
+                let Some(path) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                let answer = MessageDialog::new()
+                    .set_level(MessageLevel::Warning)
+                    .set_title(tr("Delete image"))
+                    .set_description(trf(
+                        "Delete {} from disk? This cannot be undone.",
+                        &[&path.display()],
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                if !matches!(answer, MessageDialogResult::Yes) {
+                    return Task::none();
+                }
+                if let Err(e) = delete_file(&path) {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to delete {}: {}", &[&path.display(), &e]),
+                    );
+                }
+                self.forget_path(&path);
+                self.history.clear();
+                self.show_next_after_removal()
+            }
+            Message::DeleteSelected => {
+                let paths = self.selected_images.clone();
+                if paths.is_empty() {
+                    return Task::none();
+                }
+                self.delete_images(paths)
+            }
+            Message::MoveSelected => {
+                let paths = self.selected_images.clone();
+                if paths.is_empty() {
+                    return Task::none();
+                }
+                self.move_images(paths)
+            }
+            Message::SelectionMoved(results) => {
+                let mut failed = Vec::new();
+                let mut moved = 0;
+                for (path, result) in &results {
+                    match result {
+                        Ok(_) => {
+                            moved += 1;
+                            self.forget_path(path);
+                        }
+                        Err(e) => {
+                            warn!("Failed to move {}: {}", path.display(), e);
+                            failed.push(file_name(path));
+                        }
+                    }
+                }
+                let toast = if failed.is_empty() {
+                    self.show_toast(ToastKind::Success, trf("Moved {} images", &[&moved]))
+                } else {
+                    self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to move {}", &[&failed.join(", ")]),
+                    )
+                };
+                self.finish_removal(toast)
+            }
+            Message::ExportSelection => {
+                self.batch_dialog_opened = true;
+                self.batch_scope = BatchScope::Selection;
+                self.batch_error = None;
+                Task::none()
+            }
+            Message::BatchTagChanged(tag) => {
+                self.batch_tag = tag;
+                Task::none()
+            }
+            Message::TagSelected => {
+                let tag = self.batch_tag.trim().to_string();
+                if tag.is_empty() || self.selected_images.is_empty() {
+                    return Task::none();
+                }
+                let targets = self.selected_images.clone();
+                // 在后台写入侧车文件
+                Task::perform(
+                    async move {
+                        let written = tokio::task::spawn_blocking({
+                            let tag = tag.clone();
+                            move || {
+                                targets
+                                    .into_iter()
+                                    .map(|path| {
+                                        let written = xmp_iptc::add_sidecar_keyword(&path, &tag);
+                                        (path, written)
+                                    })
+                                    .collect()
+                            }
+                        })
+                        .await
+                        .unwrap_or_default();
+                        Message::TagsWritten(tag, written)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::TagsWritten(tag, written) => {
+                let mut tagged = 0;
+                let mut failed = Vec::new();
+                let mut reload = Task::none();
+                for (path, result) in written {
+                    match result {
+                        Ok(()) => {
+                            tagged += 1;
+                            self.metadata_cache.remove(&path);
+                            if self.current_image.as_ref() == Some(&path) {
+                                reload = load_descriptive_metadata(path);
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Failed to tag {}: {}", path.display(), e);
+                            failed.push(file_name(&path));
+                        }
+                    }
+                }
+                if !failed.is_empty() {
+                    return Task::batch([
+                        reload,
+                        self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to tag {}", &[&failed.join(", ")]),
+                        ),
+                    ]);
+                }
+                self.batch_tag.clear();
+                Task::batch([
+                    reload,
+                    self.show_toast(
+                        ToastKind::Success,
+                        trf("Tagged {} images with “{}”", &[&tagged, &tag]),
+                    ),
+                ])
+            }
+            Message::ToggleLogPanel => {
+                self.log_panel_opened = !self.log_panel_opened;
+                Task::none()
+            }
+            // 日志在全局中记录，这里只需要触发界面刷新
+            Message::LogTick => Task::none(),
+            Message::CopyLog => {
+                let log = logging::recent()
+                    .iter()
+                    .map(|entry| entry.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Task::batch([
+                    iced::clipboard::write(log),
+                    self.show_toast(
+                        ToastKind::Success,
+                        tr("Log copied to clipboard").to_string(),
+                    ),
+                ])
+            }
+            Message::ClearLog => {
+                logging::clear();
+                Task::none()
+            }
+            Message::SlideshowTick => {
+                // 上一张还在解码时不切换
+                if self.loading.is_some() || self.image_collection.len() < 2 {
+                    return Task::none();
+                }
+                self.update(Message::PickNextImage)
+            }
+            Message::OpenFromOtherInstance(path) => {
+                // 打开路径后把窗口切换到前台
+                let opened = self.open_path(path);
+                Task::batch([
+                    opened,
+                    window::minimize(self.main_window, false),
+                    window::gain_focus(self.main_window),
+                ])
+            }
+            Message::RemoteCommand(command) => match command {
+                Command::Next => self.update(Message::PickNextImage),
+                Command::Previous => self.update(Message::PickPreviousImage),
+                Command::Open(path) => self.update(Message::OpenFromOtherInstance(path)),
+                Command::PauseSlideshow if self.slideshow.is_some() => {
+                    self.slideshow_paused = !self.slideshow_paused;
+                    let text = if self.slideshow_paused {
+                        tr("Slideshow paused")
+                    } else {
+                        tr("Slideshow resumed")
+                    };
+                    self.show_toast(ToastKind::Success, text.to_string())
+                }
+                Command::PauseSlideshow => Task::none(),
+                Command::StopSlideshow => {
+                    self.slideshow = None;
+                    self.slideshow_paused = false;
+                    Task::none()
+                }
+                Command::Fullscreen => self.update(Message::ToggleFullscreen),
+            },
+            Message::ToggleContactSheetDialog => {
+                self.contact_sheet_dialog_opened =
+                    !self.contact_sheet_dialog_opened && !self.contact_sheet_sources().is_empty();
+                self.contact_sheet_status = None;
+                Task::none()
+            }
+            Message::ContactSheetChanged(options) => {
+                self.contact_sheet = options;
+                Task::none()
+            }
+            Message::ExportContactSheet => {
+                let sources = self.contact_sheet_sources();
+                if sources.is_empty() || self.contact_sheet_progress.is_some() {
+                    return Task::none();
+                }
+                let format = self.contact_sheet.format;
+                let Some(mut output) = FileDialog::new()
+                    .set_directory(&self.current_path)
+                    .set_file_name(contact_sheet::default_file_name(format))
+                    .add_filter(format.name(), &[format.extension()])
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                if output.extension().is_none() {
+                    output.set_extension(format.extension());
+                }
+                self.contact_sheet_progress = Some((0, sources.len()));
+                self.contact_sheet_status = None;
+                Task::run(
+                    contact_sheet::export(sources, self.contact_sheet, output),
+                    Message::ContactSheetProgressed,
+                )
+            }
+            Message::OpenGalleryWizard(folder) => {
+                self.gallery_wizard = Some(GalleryWizard::new(folder));
+                Task::none()
+            }
+            Message::CloseGalleryWizard => {
+                self.gallery_wizard = None;
+                Task::none()
+            }
+            Message::GalleryStepChanged(step) => {
+                if let Some(wizard) = &mut self.gallery_wizard {
+                    wizard.step = step;
+                }
+                Task::none()
+            }
+            Message::GalleryOptionsChanged(options) => {
+                if let Some(wizard) = &mut self.gallery_wizard {
+                    wizard.options = options;
+                }
+                Task::none()
+            }
+            Message::GalleryEncodeChanged(encode) => {
+                if let Some(wizard) = &mut self.gallery_wizard {
+                    wizard.options.encode = encode;
+                }
+                Task::none()
+            }
+            Message::ChooseGalleryFolder => {
+                let Some(wizard) = &mut self.gallery_wizard else {
+                    return Task::none();
+                };
+                let start = wizard.output.as_deref().unwrap_or(&wizard.folder);
+                if let Some(dir) = FileDialog::new().set_directory(start).pick_folder() {
+                    wizard.output = Some(dir);
+                }
+                Task::none()
+            }
+            Message::ExportGallery => {
+                let Some(wizard) = &mut self.gallery_wizard else {
+                    return Task::none();
+                };
+                let Some(output) = wizard.output.clone() else {
+                    return Task::none();
+                };
+                if wizard.progress.is_some() {
+                    return Task::none();
+                }
+                wizard.progress = Some((0, wizard.count));
+                wizard.failures.clear();
+                wizard.index = None;
+                wizard.error = None;
+                Task::run(
+                    gallery::export(wizard.folder.clone(), wizard.options.clone(), output),
+                    Message::GalleryProgressed,
+                )
+            }
+            Message::GalleryProgressed(progress) => {
+                // 向导关闭后导出仍在继续，完成时用提示告知结果
+                let Some(wizard) = self
+                    .gallery_wizard
+                    .as_mut()
+                    .filter(|w| w.progress.is_some())
+                else {
+                    return match progress {
+                        GalleryProgress::Finished(Ok(index)) => self.show_toast(
+                            ToastKind::Success,
+                            trf("Gallery saved to {}", &[&index.display()]),
+                        ),
+                        GalleryProgress::Finished(Err(e)) => {
+                            self.show_toast(ToastKind::Error, trf("Export failed: {}", &[&e]))
+                        }
+                        _ => Task::none(),
+                    };
+                };
+                match progress {
+                    GalleryProgress::Started(total) => {
+                        wizard.progress = Some((0, total));
+                    }
+                    GalleryProgress::Exported(src, result) => {
+                        if let Some((done, _)) = &mut wizard.progress {
+                            *done += 1;
+                        }
+                        if let Err(e) = result {
+                            error!("Failed to export {} to the gallery: {}", src.display(), e);
+                            wizard.failures.push((src, e));
+                        }
+                    }
+                    GalleryProgress::Finished(result) => {
+                        wizard.progress = None;
+                        match result {
+                            Ok(index) => wizard.index = Some(index),
+                            Err(e) => {
+                                error!("Failed to export gallery: {}", e);
+                                wizard.error = Some(e);
+                            }
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::OpenGallery => {
+                let Some(index) = self.gallery_wizard.as_ref().and_then(|w| w.index.clone()) else {
+                    return Task::none();
+                };
+                if let Err(e) = open::that_detached(&index) {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to open {}: {}", &[&index.display(), &e]),
+                    );
+                }
+                Task::none()
+            }
+            Message::ContactSheetProgressed(progress) => {
+                match progress {
+                    SheetProgress::Placed => {
+                        if let Some((done, _)) = &mut self.contact_sheet_progress {
+                            *done += 1;
+                        }
+                    }
+                    SheetProgress::Finished(result) => {
+                        self.contact_sheet_progress = None;
+                        self.contact_sheet_status = Some(match result {
+                            Ok(files) => match files.as_slice() {
+                                [file] => trf("Saved {}", &[&file.display()]),
+                                files => trf("Saved {} pages", &[&files.len()]),
+                            },
+                            Err(e) => {
+                                error!("Failed to export contact sheet: {}", e);
+                                trf("Export failed: {}", &[&e])
+                            }
+                        });
+                    }
+                }
+                Task::none()
+            }
+            Message::OpenInNewWindow(path) => {
+                self.recent_menu = None;
+                let (id, opened) = window::open(window::Settings {
+                    size: iced::Size::new(900.0, 700.0),
+                    ..window::Settings::default()
+                });
+                if path.is_dir() {
+                    // 文件夹在后台扫描，结果填入这个窗口
+                    let (scan, handle) = Task::run(
+                        scan::scan_directory(path.clone(), self.config.show_hidden),
+                        move |event| Message::ViewerScanned(id, event),
+                    )
+                    .abortable();
+                    self.viewers.insert(id, Viewer::with_folder(path, handle));
+                    return Task::batch([opened.discard(), scan]);
+                }
+                // 图片在当前图片库中时可以继续浏览图片库中的其他图片
+                let viewer = match self.image_collection.iter().position(|p| p == &path) {
+                    Some(index) => Viewer::with_images(self.image_collection.clone(), index),
+                    None => Viewer::with_images(vec![path], 0),
+                };
+                self.viewers.insert(id, viewer);
+                opened.discard()
+            }
+            Message::ViewerNext(id) => {
+                if let Some(viewer) = self.viewers.get_mut(&id) {
+                    viewer.next();
+                }
+                Task::none()
+            }
+            Message::ViewerPrevious(id) => {
+                if let Some(viewer) = self.viewers.get_mut(&id) {
+                    viewer.previous();
+                }
+                Task::none()
+            }
+            Message::ViewerScanned(id, event) => {
+                let Some(viewer) = self.viewers.get_mut(&id) else {
+                    return Task::none();
+                };
+                match event {
+                    ScanEvent::Batch(batch) => viewer.images.extend(batch.images),
+                    ScanEvent::Failed(_, e) => warn!("Failed to read folder: {}", e),
+                    ScanEvent::Finished => {
+                        self.sort.sort(&mut viewer.images);
+                        viewer.index = 0;
+                        viewer.scan = None;
+                    }
+                }
+                Task::none()
+            }
+            Message::NewTab => {
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                // 新标签页从当前的文件夹和图片开始
+                self.store_tab();
+                let tab = self.tabs[self.active_tab].clone();
+                self.tabs.insert(self.active_tab + 1, tab);
+                Task::batch([saved, self.restore_tab(self.active_tab + 1)])
+            }
+            Message::CloseTab(index) => {
+                if self.tabs.len() < 2 || index >= self.tabs.len() {
+                    return Task::none();
+                }
+                if index != self.active_tab {
+                    self.tabs.remove(index);
+                    if index < self.active_tab {
+                        self.active_tab -= 1;
+                    }
+                    return Task::none();
+                }
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                self.tabs.remove(index);
+                let next = index.min(self.tabs.len() - 1);
+                Task::batch([saved, self.restore_tab(next)])
+            }
+            Message::SelectTab(index) => {
+                if index == self.active_tab || index >= self.tabs.len() {
+                    return Task::none();
+                }
+                let saved = match self.confirm_leave() {
+                    Ok(saved) => saved,
+                    Err(cancelled) => return cancelled,
+                };
+                self.store_tab();
+                Task::batch([saved, self.restore_tab(index)])
+            }
+            Message::ThumbnailStripChanged(strip) => {
+                self.config.thumbnail_strip = strip;
+                self.save_config();
+                Task::none()
+            }
+            Message::SetWallpaper(path) => Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || wallpaper::set(&path))
+                        .await
+                        .map_err(|e| e.to_string())?
+                },
+                Message::WallpaperSet,
+            ),
+            Message::WallpaperSet(result) => match result {
+                Ok(()) => self.show_toast(ToastKind::Success, tr("Wallpaper set").to_string()),
+                Err(e) => {
+                    warn!("Failed to set wallpaper: {}", e);
+                    self.show_toast(ToastKind::Error, trf("Failed to set wallpaper: {}", &[&e]))
+                }
+            },
+            Message::ShareImage(path) => share::share(path.clone(), self.main_window)
+                .map(move |result| Message::ShareFinished(path.clone(), result)),
+            Message::ShareFinished(path, result) => match result {
+                Ok(share::Outcome::Shown) => Task::none(),
+                // 系统不支持时改为上传到云存储，完成后复制分享链接
+                Ok(share::Outcome::Unsupported) => {
+                    if !self.settings.cloud.is_configured() {
+                        return self.show_toast(
+                            ToastKind::Error,
+                            tr("Sharing is not supported on this system and cloud storage is not configured")
+                                .to_string(),
+                        );
+                    }
+                    self.share_pending = Some(path.clone());
+                    self.update(Message::UploadToCloud(path))
+                }
+                Err(e) => {
+                    warn!("Failed to share {}: {}", path.display(), e);
+                    self.show_toast(ToastKind::Error, trf("Failed to share: {}", &[&e]))
+                }
+            },
+            Message::RemoveFromLibrary(path) => {
+                // 只从图片库中移除，不删除文件
+                self.image_collection.retain(|p| p != &path);
+                self.unfiltered_collection.retain(|p| p != &path);
+                self.selected_images.retain(|p| p != &path);
+                if let Some(index) = self
+                    .current_image
+                    .as_ref()
+                    .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                {
+                    self.current_image_index = index;
+                }
+                Task::none()
+            }
+            Message::RenameFile(path) => {
+                let name = file_name(&path);
+                self.name_prompt = Some(NamePrompt::Rename { path, name });
+                Task::none()
+            }
+            Message::NewFolder(parent) => {
+                self.name_prompt = Some(NamePrompt::NewFolder {
+                    parent,
+                    name: tr("New folder").to_string(),
+                });
+                Task::none()
+            }
+            Message::NamePromptChanged(value) => {
+                match &mut self.name_prompt {
+                    Some(NamePrompt::Rename { name, .. } | NamePrompt::NewFolder { name, .. }) => {
+                        *name = value;
+                    }
+                    None => {}
+                }
+                Task::none()
+            }
+            Message::CancelNamePrompt => {
+                self.name_prompt = None;
+                Task::none()
+            }
+            Message::ConfirmNamePrompt => match self.name_prompt.take() {
+                Some(NamePrompt::Rename { path, name }) => {
+                    if !is_valid_name(&name) {
+                        let toast = self.show_toast(
+                            ToastKind::Error,
+                            trf("Invalid name: {}", &[&name.trim()]),
+                        );
+                        self.name_prompt = Some(NamePrompt::Rename { path, name });
+                        return toast;
+                    }
+                    let target = path.with_file_name(name.trim());
+                    // 在后台改名，侧车文件一起改名
+                    Task::perform(
+                        async move {
+                            let renamed = tokio::task::spawn_blocking({
+                                let (path, target) = (path.clone(), target.clone());
+                                move || rename_file(&path, &target)
+                            })
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|r| r);
+                            Message::FileRenamed(path, target, renamed)
+                        },
+                        |msg| msg,
+                    )
+                }
+                Some(NamePrompt::NewFolder { parent, name }) => {
+                    if !is_valid_name(&name) {
+                        let toast = self.show_toast(
+                            ToastKind::Error,
+                            trf("Invalid name: {}", &[&name.trim()]),
+                        );
+                        self.name_prompt = Some(NamePrompt::NewFolder { parent, name });
+                        return toast;
+                    }
+                    let folder = parent.join(name.trim());
+                    if let Err(e) = std::fs::create_dir(&folder) {
+                        warn!("Failed to create {}: {}", folder.display(), e);
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to create {}: {}", &[&file_name(&folder), &e]),
+                        );
+                    }
+                    self.refresh_tree_folder(&parent)
+                }
+                None => Task::none(),
+            },
+            Message::FileRenamed(path, target, renamed) => {
+                if let Err(e) = renamed {
+                    warn!("Failed to rename {}: {}", path.display(), e);
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to rename {}: {}", &[&file_name(&path), &e]),
+                    );
+                }
+                self.replace_path(&path, target.clone());
+                let _ = self.recent_manager.delete_item(&path);
+                self.save_recents();
+                self.refresh_smart_folders();
+                self.refresh_tree_folder(target.parent().unwrap_or(&target))
+            }
+            Message::TogglePinFolder(path) => {
+                if let Some(pos) = self.config.pinned_folders.iter().position(|p| p == &path) {
+                    self.config.pinned_folders.remove(pos);
+                    self.root_file_tree_entry
+                        .retain(|entry| entry.path() != &path);
+                } else if !self
+                    .root_file_tree_entry
+                    .iter()
+                    .any(|entry| entry.path() == &path)
+                {
+                    // 放在云端根节点前面
+                    let position = self
+                        .root_file_tree_entry
+                        .iter()
+                        .position(|entry| cloud::is_remote(entry.path()))
+                        .unwrap_or(self.root_file_tree_entry.len());
+                    self.root_file_tree_entry
+                        .insert(position, FileTreeEntry::default(path.clone()));
+                    self.config.pinned_folders.push(path);
+                }
+                self.save_config();
+                Task::none()
+            }
+            Message::ShowHiddenToggled(show_hidden) => {
+                self.config.show_hidden = show_hidden;
+                self.save_config();
+                for root in self.root_file_tree_entry.iter_mut() {
+                    reload_children(root, show_hidden);
+                }
+                // 重新读取图片库所在的文件夹
+                self.update(Message::RefreshCollection)
+            }
+            Message::RefreshFolder(path) => {
+                // 在后台重新读取，再与目录树和图片库中已有的内容合并
+                let since = self
+                    .collection_folder
+                    .as_ref()
+                    .filter(|stamp| stamp.dir == path)
+                    .map(|stamp| stamp.scanned_at);
+                Task::perform(
+                    scan::rescan(path.clone(), self.config.show_hidden, since),
+                    move |result| Message::FolderRescanned(path.clone(), result),
+                )
+            }
+            Message::RefreshCollection => match &self.collection_folder {
+                Some(stamp) => self.update(Message::RefreshFolder(stamp.dir.clone())),
+                None => Task::none(),
+            },
+            Message::FolderRescanned(dir, result) => {
+                let rescan = match result {
+                    Ok(rescan) => rescan,
+                    Err(e) => {
+                        warn!("Failed to refresh {}: {}", dir.display(), e);
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to open {}: {}", &[&dir.display(), &e]),
+                        );
+                    }
+                };
+                if let Some(FileTreeEntry::Directory {
+                    children,
+                    children_loaded: true,
+                    ..
+                }) = self
+                    .root_file_tree_entry
+                    .iter_mut()
+                    .find_map(|root| find_entry_by_path(root, &dir))
+                {
+                    replace_children(children, rescan.children);
+                }
+                if self
+                    .collection_folder
+                    .as_ref()
+                    .is_none_or(|stamp| stamp.dir != dir)
+                {
+                    return Task::none();
+                }
+                self.collection_folder = Some(rescan.stamp);
+                self.folder_stale = false;
+                self.reconcile_collection(rescan.images, &rescan.modified)
+            }
+            Message::CheckFolder => {
+                self.folder_stale = self
+                    .collection_folder
+                    .as_ref()
+                    .is_some_and(FolderStamp::is_stale);
+                Task::none()
+            }
+            Message::SetFlag(flag) => {
+                let targets: Vec<PathBuf> = self
+                    .convert_targets()
+                    .into_iter()
+                    .filter(|path| !cloud::is_remote(path))
+                    .collect();
+                // 在后台写入侧车文件
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            targets
+                                .into_iter()
+                                .map(|path| {
+                                    let written = xmp_iptc::set_sidecar_flag(&path, flag);
+                                    (path, written)
+                                })
+                                .collect()
+                        })
+                        .await
+                        .unwrap_or_default()
+                    },
+                    move |written| Message::FlagsWritten(flag, written),
+                )
+            }
+            Message::ShowFlagged(flag) => {
+                // 再次点击同一个筛选时关闭
+                if self.flag_filter == Some(flag) {
+                    return self.update(Message::ClearFindResults);
+                }
+                Task::perform(
+                    find_flagged(self.base_collection().to_vec(), flag),
+                    move |images| Message::FlaggedFound(flag, images),
+                )
+            }
+            Message::FlaggedFound(flag, images) => {
+                if images.is_empty() {
+                    let message = match flag {
+                        Flag::Pick => tr("No picked images"),
+                        Flag::Reject => tr("No rejected images"),
+                    };
+                    return self.show_toast(ToastKind::Error, message.to_string());
+                }
+                let task = self.show_results(images);
+                self.flag_filter = Some(flag);
+                task
+            }
+            Message::MoveRejects => Task::perform(
+                find_flagged(self.base_collection().to_vec(), Flag::Reject),
+                |rejects| Message::RejectsFound(RejectAction::Move, rejects),
+            ),
+            Message::DeleteRejects => Task::perform(
+                find_flagged(self.base_collection().to_vec(), Flag::Reject),
+                |rejects| Message::RejectsFound(RejectAction::Delete, rejects),
+            ),
+            Message::RejectsFound(action, rejects) => {
+                if rejects.is_empty() {
+                    return self.show_toast(ToastKind::Error, tr("No rejected images").to_string());
+                }
+                match action {
+                    RejectAction::Move => self.move_images(rejects),
+                    RejectAction::Delete => self.delete_images(rejects),
+                }
+            }
+            Message::FlagsWritten(flag, written) => {
+                let mut failed = Vec::new();
+                let mut reload = Task::none();
+                for (path, result) in written {
+                    match result {
+                        Ok(()) => {
+                            if let Some(info) = self.file_info.get_mut(&path) {
+                                info.flag = flag;
+                            }
+                            self.metadata_cache.remove(&path);
+                            // 排除时星级改为 -1，重新读取当前图片的星级
+                            if self.current_image.as_ref() == Some(&path) {
+                                reload = load_descriptive_metadata(path);
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Failed to flag {}: {}", path.display(), e);
+                            failed.push(file_name(&path));
+                        }
+                    }
+                }
+                if failed.is_empty() {
+                    return reload;
+                }
+                Task::batch([
+                    reload,
+                    self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to flag {}", &[&failed.join(", ")]),
+                    ),
+                ])
+            }
+            Message::ExifInfoLoaded(path, info) => {
+                // 读取期间已经切换到了其他图片
+                if self.current_image.as_ref() == Some(&path) {
+                    self.exif_info = info;
+                }
+                Task::none()
+            }
+            Message::DescriptiveMetadataLoaded(path, metadata) => {
+                // 读取期间已经切换到了其他图片
+                if self.current_image.as_ref() == Some(&path) {
+                    self.descriptive_metadata = metadata;
+                }
+                Task::none()
+            }
+            Message::FullResolutionDecoded(path, generation, decoded) => {
+                // 解码期间已经切换了图片或重新解码过
+                if generation != self.full_resolution_generation {
+                    return Task::none();
+                }
+                self.full_resolution_pending = false;
+                if self.full_size.is_none() || self.current_image.as_ref() != Some(&path) {
+                    return Task::none();
+                }
+                let decoded = match decoded {
+                    Ok(decoded) => decoded,
+                    Err(e) => {
+                        warn!("Failed to load image {}: {}", path.display(), e);
+                        // 等待的操作不能在缩小的图片上进行
+                        if self.after_full_resolution.take().is_none() {
+                            return Task::none();
+                        }
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Cannot open {}: {}", &[&file_name(&path), &e]),
+                        );
+                    }
+                };
+                self.decoded_cache
+                    .insert(path, self.show_raw_orientation, decoded.clone());
+                // 平移量和裁剪框换算到原图的像素
+                let reduction = self.reduction();
+                self.pan_offset = self.pan_offset * reduction;
+                self.crop_selection = self.crop_selection.map(|rect| rect.scaled(reduction));
+                // 裁剪按比例记录，已有的几何编辑可以直接在原图上重放
+                self.edited = self.history.apply_geometry(&decoded.image).map(Arc::new);
+                self.original = Some(decoded.image);
+                self.orientation = decoded.orientation;
+                self.full_size = None;
+                // 调整结果还是按缩小的图片计算的，按原图重新计算
+                let adjusting = !self.adjustments.is_identity();
+                if adjusting {
+                    self.adjusted = None;
+                }
+                self.refresh_display();
+                self.rerender_scaled();
+                if self.crop_mode || self.straighten_mode || self.color_picking {
+                    self.overlay_handle = self.display_source().map(|img| rgb_handle(img));
+                }
+                self.enforce_memory_budget();
+                if adjusting {
+                    return self.start_adjusting();
+                }
+                self.run_after_full_resolution()
+            }
+            Message::AlphaDecoded(path, raw_orientation, alpha) => {
+                // 解码期间已经切换了图片或显示方向
+                if self.current_image.as_ref() != Some(&path)
+                    || self.show_raw_orientation != raw_orientation
+                {
+                    return Task::none();
+                }
+                self.alpha_pending = false;
+                match alpha {
+                    Ok(alpha) => self.alpha = Some(Arc::new(alpha)),
+                    Err(e) => {
+                        warn!("Failed to read alpha channel of {}: {}", path.display(), e);
+                        return Task::none();
+                    }
+                }
+                self.refresh_display();
+                self.rerender_scaled();
+                self.enforce_memory_budget();
+                Task::none()
+            }
+            Message::PluginMetadataLoaded(path, metadata) => {
+                if self.current_image.as_ref() == Some(&path) {
+                    self.plugin_metadata = metadata;
+                }
+                Task::none()
+            }
+            Message::WindowClosed(id) => {
+                if id == self.main_window {
+                    return iced::exit();
+                }
+                self.viewers.remove(&id);
+                Task::none()
+            }
+            Message::PluginToggled(key, enabled) => {
+                plugins::set_enabled(&key, enabled);
+                self.config
+                    .disabled_plugins
+                    .retain(|disabled| *disabled != key);
+                if !enabled {
+                    self.config.disabled_plugins.push(key);
+                }
+                self.save_config();
+                self.refresh_plugin_metadata()
+            }
+            Message::ReloadPlugins => {
+                plugins::load(&self.config.disabled_plugins);
+                self.refresh_plugin_metadata()
+            }
+            Message::OpenPluginsFolder => {
+                let Some(dir) = plugins::plugins_dir() else {
+                    return Task::none();
+                };
+                if let Err(e) =
+                    std::fs::create_dir_all(&dir).and_then(|_| open::that_detached(&dir))
+                {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to open {}: {}", &[&dir.display(), &e]),
+                    );
+                }
+                Task::none()
+            }
+            Message::PluginExport(exporter) => {
+                let Some(path) = self
+                    .current_image
+                    .clone()
+                    .filter(|path| !cloud::is_remote(path))
+                else {
+                    return Task::none();
+                };
+                self.export_status = Some(trf("Sending to {}…", &[&exporter.name()]));
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || exporter.export(&path))
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    move |result| Message::PluginExported(exporter.name(), result),
+                )
+            }
+            Message::ToggleScriptDialog => {
+                self.script_dialog_opened = !self.script_dialog_opened;
+                Task::none()
+            }
+            Message::ScriptEdited(action) => {
+                self.script.perform(action);
+                Task::none()
+            }
+            Message::RunScript => {
+                if self.script_running {
+                    return Task::none();
+                }
+                let source = self.script.text();
+                script::save_last(&source);
+                let context = ScriptContext {
+                    images: self.image_collection.clone(),
+                    selection: self.selected_images.clone(),
+                    folder: self.current_path.clone(),
+                };
+                self.script_running = true;
+                self.script_output.clear();
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || script::run(&source, context))
+                            .await
+                            .unwrap_or_else(|e| ScriptOutput {
+                                error: Some(e.to_string()),
+                                ..Default::default()
+                            })
+                    },
+                    Message::ScriptFinished,
+                )
+            }
+            Message::ScriptFinished(output) => {
+                self.script_running = false;
+                self.script_output = output.log;
+                if let Some(error) = output.error {
+                    self.script_output.push(trf("Error: {}", &[&error]));
+                    return Task::none();
+                }
+                if let Some(paths) = output.select {
+                    self.selected_images = paths
+                        .into_iter()
+                        .filter(|path| self.image_collection.contains(path))
+                        .collect();
+                }
+                // 脚本中的导出加入批处理任务队列
+                let mut queued = 0;
+                for (options, sources) in output.exports {
+                    if sources.is_empty() {
+                        continue;
+                    }
+                    if let Some(dir) = &options.output_dir
+                        && let Err(e) = std::fs::create_dir_all(dir)
+                    {
+                        self.script_output
+                            .push(trf("Failed to create {}: {}", &[&dir.display(), &e]));
+                        continue;
+                    }
+                    let Ok(plan) = options.plan() else {
+                        continue;
+                    };
+                    self.jobs
+                        .push(BatchJob::new(self.next_job_id, plan, sources));
+                    self.next_job_id += 1;
+                    queued += 1;
+                }
+                if queued == 0 {
+                    return Task::none();
+                }
+                self.script_output
+                    .push(trf("Queued {} export jobs", &[&queued]));
+                self.jobs_panel_opened = true;
+                self.start_next_job()
+            }
+            Message::OpenScript => {
+                let Some(path) = FileDialog::new().add_filter("Rhai", &["rhai"]).pick_file() else {
+                    return Task::none();
+                };
+                match std::fs::read_to_string(&path) {
+                    Ok(source) => self.script = text_editor::Content::with_text(&source),
+                    Err(e) => {
+                        return self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to open {}: {}", &[&path.display(), &e]),
+                        );
+                    }
+                }
+                Task::none()
+            }
+            Message::SaveScript => {
+                let Some(path) = FileDialog::new()
+                    .add_filter("Rhai", &["rhai"])
+                    .set_file_name("script.rhai")
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                if let Err(e) = std::fs::write(&path, self.script.text()) {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to save {}: {}", &[&path.display(), &e]),
+                    );
+                }
+                Task::none()
+            }
+            Message::PluginExported(name, result) => {
+                self.export_status = Some(match result {
+                    Ok(()) => trf("Sent to {}", &[&name]),
+                    Err(e) => {
+                        error!("Plugin export to {} failed: {}", name, e);
+                        trf("Export failed: {}", &[&e])
+                    }
+                });
+                Task::none()
+            }
+            Message::SettingsPageChanged(page) => {
+                self.settings_page = page;
+                Task::none()
+            }
+            Message::BackdropChanged(backdrop) => {
+                self.config.backdrop = backdrop;
+                self.save_config();
+                Task::none()
+            }
+            Message::ThumbnailSizeChanged(size) => {
+                self.config.thumbnail_size = size;
+                self.save_config();
+                thumbnails::set_size(size);
+                // 已有的缩略图按新的尺寸重新生成
+                self.cancel_thumbnails();
+                self.thumbnail_cache.shrink_to(0);
+                self.load_missing_thumbnails()
+            }
+            Message::ThumbnailSizeDragged(size) => {
+                // 拖动时只改变格子大小，已有的缩略图缩放显示
+                self.config.thumbnail_size = size;
+                Task::none()
+            }
+            Message::ThumbnailSizeReleased => {
+                let size = self.config.thumbnail_size;
+                // 变大时重新生成，否则缩小显示已有的缩略图即可
+                if size > thumbnails::size() {
+                    return self.update(Message::ThumbnailSizeChanged(size));
+                }
+                self.save_config();
+                thumbnails::set_size(size);
+                Task::none()
+            }
+            Message::SortOrderChanged(sort) => {
+                self.sort = sort;
+                self.config.sort = sort;
+                self.save_config();
+                // 搜索结果保持自己的顺序，关闭后恢复的图片库按新的顺序排列
+                if self.results_shown() {
+                    sort.sort(&mut self.find_backup);
+                } else {
+                    sort.sort(&mut self.image_collection);
+                    sort.sort(&mut self.unfiltered_collection);
+                    self.current_image_index = self
+                        .current_image
+                        .as_ref()
+                        .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                        .unwrap_or(0);
+                }
+                Task::none()
+            }
+            Message::MemoryBudgetChanged(budget) => {
+                self.config.memory_budget = budget;
+                self.save_config();
+                self.memory_budget = self.config.memory_budget_bytes();
+                self.enforce_memory_budget();
+                Task::none()
+            }
+            Message::PickStartFolder => {
+                if let Some(dir) = FileDialog::new().pick_folder() {
+                    self.config.start_folder = Some(dir);
+                    self.save_config();
+                }
+                Task::none()
+            }
+            Message::ResetStartFolder => {
+                self.config.start_folder = None;
+                self.save_config();
+                Task::none()
+            }
+            Message::RememberViewToggled(enabled) => {
+                self.settings.remember_view = enabled;
+                self.save_settings();
+                Task::none()
+            }
+            Message::WheelNavigationToggled(enabled) => {
+                self.settings.wheel_navigation = enabled;
+                self.save_settings();
+                Task::none()
+            }
+            Message::RecentlyAddedChanged(within) => {
+                self.settings.recently_added = within;
+                self.save_settings();
+                Task::none()
+            }
+            Message::RecentlyAddedLoaded(result) => match result {
+                Ok(images) => self.replace_collection(images),
+                Err(e) => self.show_toast(
+                    ToastKind::Error,
+                    trf("Failed to find recently added images: {}", &[&e]),
+                ),
+            },
+            Message::ClearRecents => {
+                self.recent_menu = None;
+                self.recent_manager.clear();
+                self.save_recents();
+                self.refresh_smart_folders();
+                Task::none()
+            }
+            Message::TogglePinRecent(path) => {
+                self.recent_menu = None;
+                self.recent_manager.toggle_pin(&path);
+                self.save_recents();
+                self.refresh_smart_folders();
+                Task::none()
+            }
+            Message::OpenContainingFolder(path) => {
+                self.recent_menu = None;
+                let folder = path.parent().unwrap_or(&path);
+                if let Err(e) = open::that_detached(folder) {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf("Failed to open {}: {}", &[&folder.display(), &e]),
+                    );
+                }
+                Task::none()
+            }
+            Message::CopyPath(path) => {
+                self.recent_menu = None;
+                Task::batch([
+                    iced::clipboard::write(path.display().to_string()),
+                    self.show_toast(ToastKind::Success, tr("Path copied").to_string()),
+                ])
+            }
+            Message::CopyCode(code) => Task::batch([
+                iced::clipboard::write(code),
+                self.show_toast(ToastKind::Success, tr("Copied to clipboard").to_string()),
+            ]),
+            Message::ToggleColorPicker => {
+                self.hovered_color = None;
+                if self.color_picking {
+                    self.color_picking = false;
+                    self.overlay_handle = None;
+                    return Task::none();
+                }
+                // 取显示的像素，已有的裁剪和调整同样生效
+                let Some(img) = self.display_source() else {
+                    return Task::none();
+                };
+                self.overlay_handle = Some(rgb_handle(img));
+                self.crop_mode = false;
+                self.straighten_mode = false;
+                self.crop_selection = None;
+                self.blink = None;
+                self.code_scan = None;
+                self.color_picking = true;
+                self.ensure_full_resolution()
+            }
+            Message::ColorHovered(color) => {
+                self.hovered_color = color;
+                Task::none()
+            }
+            Message::ColorPicked(color) => {
+                let hex = color_picker::to_hex(color);
+                self.config.picked_colors.retain(|other| *other != hex);
+                self.config.picked_colors.insert(0, hex);
+                self.config
+                    .picked_colors
+                    .truncate(color_picker::HISTORY_SIZE);
+                self.save_config();
+                self.update(Message::CopyColor(color))
+            }
+            Message::CopyColor(color) => {
+                let value = self.config.color_format.format(color);
+                Task::batch([
+                    iced::clipboard::write(value.clone()),
+                    self.show_toast(ToastKind::Success, trf("Copied {}", &[&value])),
+                ])
+            }
+            Message::ColorFormatChanged(format) => {
+                self.config.color_format = format;
+                self.save_config();
+                Task::none()
+            }
+            Message::ClearPickedColors => {
+                self.config.picked_colors.clear();
+                self.save_config();
+                Task::none()
+            }
+            Message::ExportPalette => {
+                let colors: Vec<[u8; 3]> = self
+                    .config
+                    .picked_colors
+                    .iter()
+                    .filter_map(|hex| color_picker::parse_hex(hex))
+                    .collect();
+                if colors.is_empty() {
+                    return Task::none();
+                }
+                let Some(dst) = FileDialog::new()
+                    .set_file_name("palette.gpl")
+                    .add_filter("GIMP palette", &["gpl"])
+                    .add_filter("Adobe Swatch Exchange", &["ase"])
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                match color_picker::export(&colors, &dst) {
+                    Ok(()) => {
+                        self.show_toast(ToastKind::Success, trf("Saved to {}", &[&dst.display()]))
+                    }
+                    Err(e) => {
+                        error!("Failed to export palette: {}", e);
+                        self.show_toast(ToastKind::Error, trf("Export failed: {}", &[&e]))
+                    }
+                }
+            }
+            Message::ThumbnailHovered(path) => {
+                self.hover_preview = None;
+                self.hovered_thumbnail = Some(path.clone());
+                Task::perform(tokio::time::sleep(HOVER_PREVIEW_DELAY), move |_| {
+                    Message::HoverPreviewDelayed(path.clone())
+                })
+            }
+            Message::ThumbnailUnhovered(path) => {
+                if self.hovered_thumbnail.as_ref() == Some(&path) {
+                    self.hovered_thumbnail = None;
+                    self.hover_preview = None;
+                }
+                Task::none()
+            }
+            Message::HoverPreviewDelayed(path) => {
+                // 鼠标已经移开
+                if self.hovered_thumbnail.as_ref() != Some(&path) {
+                    return Task::none();
+                }
+                if self.hover_previews.contains_key(&path) {
+                    self.hover_previews.touch([&path]);
+                    self.hover_preview = Some(path);
+                    return Task::none();
+                }
+                if cloud::is_remote(&path) {
+                    return Task::none();
+                }
+                Task::perform(
+                    async move {
+                        let preview = {
+                            let path = path.clone();
+                            tokio::task::spawn_blocking(move || thumbnails::generate_preview(&path))
+                                .await
+                                .ok()
+                                .flatten()
+                        };
+                        (path, preview)
+                    },
+                    |(path, preview)| Message::HoverPreviewLoaded(path, preview),
+                )
+            }
+            Message::HoverPreviewLoaded(path, preview) => {
+                let Some(handle) = preview else {
+                    return Task::none();
+                };
+                self.hover_previews.insert(path.clone(), handle);
+                self.hover_previews.shrink_to(HOVER_PREVIEW_CACHE);
+                if self.hovered_thumbnail.as_ref() == Some(&path) {
+                    self.hover_preview = Some(path);
+                }
+                Task::none()
+            }
+            Message::OpenUrl(url) => {
+                if let Err(e) = open::that_detached(&url) {
+                    warn!("Failed to open {}: {}", url, e);
+                }
+                Task::none()
+            }
+            Message::FilterByKeyword(keyword) => {
+                self.search_query = MetadataQuery::keyword(keyword);
+                self.update(Message::ApplySearch)
+            }
+            Message::ToggleSearchPanel => {
+                self.search_panel_opened = !self.search_panel_opened;
+                Task::none()
+            }
+            Message::SearchCameraChanged(value) => {
+                self.search_query.camera = value;
+                Task::none()
+            }
+            Message::SearchIsoMinChanged(value) => {
+                self.search_query.iso_min = value;
+                Task::none()
+            }
+            Message::SearchIsoMaxChanged(value) => {
+                self.search_query.iso_max = value;
+                Task::none()
+            }
+            Message::SearchFocalMinChanged(value) => {
+                self.search_query.focal_min = value;
+                Task::none()
+            }
+            Message::SearchFocalMaxChanged(value) => {
+                self.search_query.focal_max = value;
+                Task::none()
+            }
+            Message::SearchKeywordChanged(value) => {
+                self.search_query.keyword = value;
+                Task::none()
+            }
+            Message::ApplySearch => {
+                if self.search_query.is_empty() {
+                    return self.update(Message::ClearSearch);
+                }
+                // 在整个图片目录中搜索，结果替换图片库，清除搜索后恢复
+                if self.search_library
+                    && let Some(catalog) = &self.catalog
+                {
+                    let images = match catalog.search(&self.search_query) {
+                        Ok(images) => images,
+                        Err(e) => {
+                            return self
+                                .show_toast(ToastKind::Error, trf("Search failed: {}", &[&e]));
+                        }
+                    };
+                    if self.active_search.is_none() {
+                        self.unfiltered_collection = std::mem::take(&mut self.image_collection);
+                    }
+                    self.image_collection = images;
+                    self.active_search = Some(self.search_query.clone());
+                    self.current_image_index = self
+                        .current_image
+                        .as_ref()
+                        .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                        .unwrap_or(0);
+                    self.record_thumbnail_lookups(&self.image_collection);
+                    return self.load_missing_thumbnails();
+                }
+                // 已经在筛选时，基于筛选前的完整图片库重新搜索
+                let images = if self.active_search.is_some() {
+                    &self.unfiltered_collection
+                } else {
+                    &self.image_collection
+                };
+                // 只在后台读取还没有缓存的图片元数据
+                let missing = self.metadata_cache.missing(images);
+                let query = self.search_query.clone();
+                Task::perform(
+                    async move {
+                        let loaded = tokio::task::spawn_blocking(move || {
+                            missing
+                                .into_iter()
+                                .map(|p| {
+                                    let metadata = ImageMetadata::load(&p);
+                                    (p, metadata)
+                                })
+                                .collect()
+                        })
+                        .await
+                        .unwrap_or_default();
+                        Message::SearchMetadataLoaded(query, loaded)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::SearchMetadataLoaded(query, loaded) => {
+                for (path, metadata) in loaded {
+                    self.metadata_cache.insert(path, metadata);
+                }
+                if self.active_search.is_none() {
+                    self.unfiltered_collection = self.image_collection.clone();
+                }
+                self.image_collection = self
+                    .unfiltered_collection
+                    .iter()
+                    .filter(|p| {
+                        self.metadata_cache
+                            .get(p)
+                            .is_some_and(|metadata| query.matches(metadata))
+                    })
+                    .cloned()
+                    .collect();
+                self.active_search = Some(query);
+                self.current_image_index = self
+                    .current_image
+                    .as_ref()
+                    .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                    .unwrap_or(0);
+
+                self.load_missing_thumbnails()
+            }
+            Message::StripMetadataToggled(strip) => {
+                self.strip_metadata = strip;
+                Task::none()
+            }
+            Message::MetadataArtistChanged(value) => {
+                self.metadata_edits.artist = value;
+                Task::none()
+            }
+            Message::MetadataCopyrightChanged(value) => {
+                self.metadata_edits.copyright = value;
+                Task::none()
+            }
+            Message::MetadataDateChanged(value) => {
+                self.metadata_edits.date_time = value;
+                Task::none()
+            }
+            Message::ExportCopy => {
+                let Some(src) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                let date_time = self.metadata_edits.date_time.trim();
+                if !self.strip_metadata
+                    && !date_time.is_empty()
+                    && !metadata::is_valid_exif_date(date_time)
+                {
+                    warn!(
+                        "Invalid capture date: {} (expected YYYY:MM:DD HH:MM:SS)",
+                        date_time
+                    );
+                    return self.show_toast(
+                        ToastKind::Error,
+                        trf(
+                            "Invalid capture date: {} (expected YYYY:MM:DD HH:MM:SS)",
+                            &[&date_time],
+                        ),
+                    );
+                }
+                let file_name = src.file_name().unwrap_or_default().to_string_lossy();
+                let Some(dst) = FileDialog::new()
+                    .set_directory(src.parent().unwrap_or(&src))
+                    .set_file_name(format!("copy_{}", file_name))
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                let policy = self.metadata_policy();
+                Task::perform(
+                    async move {
+                        // 读取和写入整个文件，放到阻塞线程池中执行
+                        let result = tokio::task::spawn_blocking(move || {
+                            metadata::export_with_metadata(&src, &dst, &policy)
+                                .map(|()| dst)
+                                .map_err(|e| e.to_string())
+                        })
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| r);
+                        Message::CopyExported(result)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::CopyExported(result) => match result {
+                Ok(path) => {
+                    self.show_toast(ToastKind::Success, trf("Saved to {}", &[&path.display()]))
+                }
+                Err(e) => {
+                    error!("Failed to export copy: {}", e);
+                    self.show_toast(ToastKind::Error, trf("Export failed: {}", &[&e]))
+                }
+            },
+            Message::ToggleRawOrientation => {
+                self.show_raw_orientation = !self.show_raw_orientation;
+                let Some(path) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                let raw_orientation = self.show_raw_orientation;
+                if let Some(decoded) = self.decoded_cache.get(&path, raw_orientation) {
+                    self.loading = None;
+                    self.reorienting = false;
+                    if self.original.is_some() {
+                        return self.show_reoriented(decoded);
+                    }
+                    return self.show_decoded(path, decoded);
+                }
+                // 在后台重新解码当前图片，期间继续显示原来方向的图片，完成后保留缩放和平移
+                self.reorienting = self.original.is_some();
+                self.loading = Some(path.clone());
+                decode_in_background(path, raw_orientation)
+            }
+            Message::MipLevelChanged(level) => {
+                let Some(path) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                // 先切换选中的层，连续切换时只显示最后选中的一层
+                self.mip_level = level;
+                Task::perform(
+                    async move {
+                        let decoded = tokio::task::spawn_blocking({
+                            let path = path.clone();
+                            move || {
+                                textures::decode(&path, level)
+                                    .map(|(img, _)| Arc::new(img.to_rgb8()))
+                            }
+                        })
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| r);
+                        Message::MipLevelDecoded(path, level, decoded)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::MipLevelDecoded(path, level, decoded) => {
+                // 解码期间已经切换了图片或选中了另一层
+                if self.current_image.as_ref() != Some(&path) || self.mip_level != level {
+                    return Task::none();
+                }
+                match decoded {
+                    Ok(img) => {
+                        self.original = Some(img);
+                        // 各层尺寸不同，裁剪和旋转不再适用，平移也从中间开始
+                        self.edited = None;
+                        self.history.clear();
+                        self.pan_offset = iced::Vector::ZERO;
+                        if !self.adjustments.is_identity() {
+                            self.adjusted = None;
+                            return self.start_adjusting();
+                        }
+                        self.refresh_display();
+                        self.rerender_scaled();
+                        Task::none()
+                    }
+                    Err(e) => self.show_toast(
+                        ToastKind::Error,
+                        trf("Cannot open {}: {}", &[&file_name(&path), &e]),
+                    ),
+                }
+            }
+            Message::FramesDecoded(path, decoded) => {
+                // 解码期间已经切换到了其他图片
+                if self.current_image.as_ref() != Some(&path) {
+                    return Task::none();
+                }
+                match decoded {
+                    Ok(frames) if !frames.is_empty() => {
+                        self.animation = Some(Animation {
+                            path,
+                            frames,
+                            current: 0,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to decode frames of {}: {}", path.display(), e),
+                }
+                Task::none()
+            }
+            Message::FrameSelected(index) => {
+                let Some(frame) = self
+                    .animation
+                    .as_mut()
+                    .and_then(|animation| {
+                        animation.current = index.min(animation.frames.len() - 1);
+                        animation.frames.get(animation.current)
+                    })
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+                self.original = Some(Arc::new(
+                    image::DynamicImage::ImageRgba8((*frame).clone()).into_rgb8(),
+                ));
+                // 编辑只对一帧有效，换帧后重新开始
+                self.edited = None;
+                self.history.clear();
+                if !self.adjustments.is_identity() {
+                    self.adjusted = None;
+                    return self.start_adjusting();
+                }
+                self.refresh_display();
+                self.rerender_scaled();
+                Task::none()
+            }
+            Message::ExportFrame => {
+                let Some(animation) = &self.animation else {
+                    return Task::none();
+                };
+                let stem = animation
+                    .path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let Some(dst) = FileDialog::new()
+                    .set_directory(animation.path.parent().unwrap_or(&animation.path))
+                    .set_file_name(frames::frame_name(&stem, animation.current))
+                    .add_filter("PNG", &["png"])
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                let frame = animation.frames[animation.current].clone();
+                Task::perform(
+                    async move {
+                        let result = tokio::task::spawn_blocking(move || {
+                            frames::write_frame(&frame, &dst).map(|()| dst)
+                        })
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| r);
+                        Message::FramesExported(result)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::ExportAllFrames => {
+                let Some(animation) = &self.animation else {
+                    return Task::none();
+                };
+                let Some(dir) = FileDialog::new()
+                    .set_directory(animation.path.parent().unwrap_or(&animation.path))
+                    .pick_folder()
+                else {
+                    return Task::none();
+                };
+                let (source, frames) = (animation.path.clone(), animation.frames.clone());
+                Task::perform(
+                    async move {
+                        let result = tokio::task::spawn_blocking(move || {
+                            frames::write_all(&frames, &source, &dir).map(|()| dir)
+                        })
+                        .await
+                        .map_err(|e| e.to_string())
+                        .and_then(|r| r);
+                        Message::FramesExported(result)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::FramesExported(result) => match result {
+                Ok(path) => {
+                    self.show_toast(ToastKind::Success, trf("Saved to {}", &[&path.display()]))
+                }
+                Err(e) => {
+                    error!("Failed to export frames: {}", e);
+                    self.show_toast(ToastKind::Error, trf("Export failed: {}", &[&e]))
+                }
+            },
+            Message::ScanCodes => {
+                if self.code_scan.take().is_some() {
+                    self.overlay_handle = None;
+                    return Task::none();
+                }
+                let Some(path) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                // 按原图的像素识别
+                if let Some(task) = self.with_full_resolution(Message::ScanCodes) {
+                    return task;
+                }
+                let Some(img) = self.display_source().cloned() else {
+                    return Task::none();
+                };
+                self.crop_mode = false;
+                self.straighten_mode = false;
+                self.crop_selection = None;
+                self.blink = None;
+                self.color_picking = false;
+                // 在识别的像素上标出位置，与裁剪一样用画布显示
+                self.overlay_handle = Some(rgb_handle(&img));
+                self.code_scan = Some(CodeScan {
+                    path: path.clone(),
+                    codes: None,
+                });
+                Task::perform(
+                    async move {
+                        let codes = tokio::task::spawn_blocking(move || codes::scan(&img))
+                            .await
+                            .unwrap_or_default();
+                        Message::CodesScanned(path, codes)
+                    },
+                    |msg| msg,
+                )
+            }
+            Message::ExtractText => {
+                if self.extracting_text {
+                    return Task::none();
+                }
+                if let Some(task) = self.with_full_resolution(Message::ExtractText) {
+                    return task;
+                }
+                // 按显示的像素识别，已有的裁剪和旋转同样生效
+                let Some(img) = self.display_source().cloned() else {
+                    return Task::none();
+                };
+                self.extracting_text = true;
+                let languages = self.config.ocr_languages.clone();
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || ocr::recognize(&img, &languages))
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|r| r)
+                    },
+                    Message::TextExtracted,
+                )
+            }
+            Message::FindSimilar => {
+                let Some(path) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                if self.finding || cloud::is_remote(&path) {
+                    return Task::none();
+                }
+                let Some(db) = self.catalog.as_ref().and_then(|_| catalog::catalog_path()) else {
+                    return self.show_toast(
+                        ToastKind::Error,
+                        tr("Add folders to the library catalog to find similar images").to_string(),
+                    );
+                };
+                self.finding = true;
+                Task::perform(similar::find(path.clone(), db), move |result| {
+                    Message::SimilarFound(path.clone(), result)
+                })
+            }
+            Message::SimilarFound(path, result) => {
+                self.finding = false;
+                let images = match result {
+                    Ok(images) => images,
+                    Err(e) => {
+                        return self.show_toast(ToastKind::Error, trf("Search failed: {}", &[&e]));
+                    }
+                };
+                // 只有图片本身时说明目录中没有相似的图片
+                if images.len() <= 1 {
+                    return self
+                        .show_toast(ToastKind::Error, tr("No similar images found").to_string());
+                }
+                let task = self.show_results(images);
+                self.similar_to = Some(
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                );
+                task
+            }
+            Message::TextExtracted(result) => {
+                self.extracting_text = false;
+                match result {
+                    Ok(text) if text.is_empty() => {
+                        self.show_toast(ToastKind::Error, tr("No text found").to_string())
+                    }
+                    Ok(text) => Task::batch([
+                        iced::clipboard::write(text),
+                        self.show_toast(
+                            ToastKind::Success,
+                            tr("Text copied to clipboard").to_string(),
+                        ),
+                    ]),
+                    Err(e) => {
+                        warn!("Text recognition failed: {}", e);
+                        self.show_toast(ToastKind::Error, trf("Text recognition failed: {}", &[&e]))
+                    }
+                }
+            }
+            Message::CodesScanned(path, codes) => {
+                // 识别期间已经关闭或换了图片
+                if let Some(scan) = self.code_scan.as_mut().filter(|scan| scan.path == path) {
+                    scan.codes = Some(codes);
+                }
+                Task::none()
+            }
+            Message::ToggleCropMode => {
+                self.crop_mode = !self.crop_mode && self.original.is_some();
+                self.straighten_mode = false;
+                self.blink = None;
+                self.code_scan = None;
+                self.color_picking = false;
+                self.crop_selection = None;
+                // 裁剪基于解码并调整后的像素，与显示的方向保持一致
+                self.overlay_handle = if self.crop_mode {
+                    self.display_source().map(|img| rgb_handle(img))
+                } else {
+                    None
+                };
+                self.ensure_full_resolution()
+            }
+            Message::CropAspectChanged(aspect) => {
+                self.crop_aspect = aspect;
+                self.crop_selection = None;
+                Task::none()
+            }
+            Message::CropSelectionChanged(selection) => {
+                self.crop_selection = selection;
+                Task::none()
+            }
+            Message::SaveCropAs => {
+                // 不能从缩小的图片中裁剪保存
+                if let Some(task) = self.with_full_resolution(Message::SaveCropAs) {
+                    return task;
+                }
+                let Some(src) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                let file_name = src.file_name().unwrap_or_default().to_string_lossy();
+                let Some(dst) = FileDialog::new()
+                    .set_directory(src.parent().unwrap_or(&src))
+                    .set_file_name(format!("cropped_{}", file_name))
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                self.save_crop(dst)
+            }
+            Message::OverwriteWithCrop => {
+                if let Some(task) = self.with_full_resolution(Message::OverwriteWithCrop) {
+                    return task;
+                }
+                let Some(src) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                let confirmed = MessageDialog::new()
+                    .set_level(MessageLevel::Warning)
+                    .set_title(tr("Overwrite image"))
+                    .set_description(trf(
+                        "Replace {} with the cropped image? This cannot be undone.",
+                        &[&src.display()],
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                if confirmed != MessageDialogResult::Yes {
+                    return Task::none();
+                }
+                self.save_crop(src)
+            }
+            Message::SaveFailed(path, e) => {
+                error!("Failed to save image {}: {}", path.display(), e);
+                self.show_toast(
+                    ToastKind::Error,
+                    trf("Failed to save {}: {}", &[&path.display(), &e]),
+                )
+            }
+            Message::ImageSaved(path) => {
+                self.crop_mode = false;
+                self.crop_selection = None;
+                self.overlay_handle = None;
+                // 覆盖原文件后重新加载图片和缩略图
+                if self.current_image.as_ref() == Some(&path) {
+                    self.thumbnail_cache.remove(&path);
+                    self.hover_previews.remove(&path);
+                    return Task::batch([
+                        Task::done(Message::LoadImage(path.clone())),
+                        Task::done(Message::LoadThumbnail(path)),
+                    ]);
+                }
+                Task::none()
+            }
+            Message::Save => {
+                // 不能用缩小的图片覆盖原图
+                if let Some(task) = self.with_full_resolution(Message::Save) {
+                    return task;
+                }
+                let Some(path) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                if !self.is_modified() {
+                    return Task::none();
+                }
+                let confirmed = MessageDialog::new()
+                    .set_level(MessageLevel::Warning)
+                    .set_title(tr("Overwrite image"))
+                    .set_description(trf(
+                        "Replace {} with the edited image? The original cannot be restored.",
+                        &[&path.display()],
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                if confirmed != MessageDialogResult::Yes {
+                    return Task::none();
+                }
+                self.save_edits(path, Message::ImageSaved)
+            }
+            Message::SaveAs => {
+                if let Some(task) = self.with_full_resolution(Message::SaveAs) {
+                    return task;
+                }
+                let Some(src) = self.current_image.clone() else {
+                    return Task::none();
+                };
+                let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+                let extension = src.extension().unwrap_or_default().to_string_lossy();
+                let Some(dst) = FileDialog::new()
+                    .set_directory(src.parent().unwrap_or(&src))
+                    .set_file_name(format!("{}_edited.{}", stem, extension))
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                self.save_edits(dst, Message::SavedAs)
+            }
+            Message::SavedAs(path) => {
+                if self.current_image.as_ref() == Some(&path) {
+                    return self.update(Message::ImageSaved(path));
+                }
+                // 新文件在当前目录时加入图片库，放在原图后面
+                let same_dir =
+                    self.current_image.as_ref().and_then(|p| p.parent()) == path.parent();
+                if same_dir && !self.image_collection.contains(&path) {
+                    let index = (self.current_image_index + 1).min(self.image_collection.len());
+                    self.image_collection.insert(index, path.clone());
+                }
+                self.current_image_index = self
+                    .image_collection
+                    .iter()
+                    .position(|p| p == &path)
+                    .unwrap_or(self.current_image_index);
+                self.current_image = Some(path.clone());
+                Task::batch([
+                    Task::done(Message::LoadImage(path.clone())),
+                    Task::done(Message::LoadThumbnail(path)),
+                ])
+            }
+            Message::ToggleExportDialog => {
+                self.export_dialog_opened = !self.export_dialog_opened && self.original.is_some();
+                self.export_status = None;
+                self.ensure_full_resolution()
+            }
+            Message::ExportWidthChanged(value) => {
+                let source = self.original_dimensions();
+                self.export_settings.set_width(value, source);
+                Task::none()
+            }
+            Message::ExportHeightChanged(value) => {
+                let source = self.original_dimensions();
+                self.export_settings.set_height(value, source);
+                Task::none()
+            }
+            Message::ExportUnitChanged(unit) => {
+                let source = self.original_dimensions();
+                self.export_settings.set_unit(unit, source);
+                Task::none()
+            }
+            Message::ExportKeepAspectToggled(keep_aspect) => {
+                self.export_settings.keep_aspect = keep_aspect;
+                // 重新按宽度计算高度
+                if keep_aspect {
+                    let source = self.original_dimensions();
+                    let width = self.export_settings.width.clone();
+                    self.export_settings.set_width(width, source);
+                }
+                Task::none()
+            }
+            Message::ExportResamplingChanged(resampling) => {
+                self.export_settings.resampling = resampling;
+                Task::none()
+            }
+            Message::ExportEncodeChanged(options) => {
+                self.export_settings.encode = options;
+                Task::none()
+            }
+            Message::StartExport => {
+                if let Some(task) = self.with_full_resolution(Message::StartExport) {
+                    return task;
+                }
+                // 导出时带上当前的调整
+                let (Some(src), Some(ori)) =
+                    (self.current_image.clone(), self.display_source().cloned())
+                else {
+                    return Task::none();
+                };
+                if self.export_progress.is_some() {
+                    return Task::none();
+                }
+                let format = self.export_settings.encode.format;
+                let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+                let Some(dst) = FileDialog::new()
+                    .set_directory(src.parent().unwrap_or(&src))
+                    .set_file_name(format!("{}_export.{}", stem, format.extension()))
+                    .add_filter(format.name(), &[format.extension()])
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                self.export_progress = Some(0.0);
+                self.export_status = None;
+                Task::run(
+                    export::export_resized(
+                        ori,
+                        self.export_settings.clone(),
+                        self.metadata_policy(),
+                        dst,
+                    ),
+                    Message::ExportProgressed,
+                )
+            }
+            Message::ExportProgressed(progress) => {
+                match progress {
+                    ExportProgress::Progress(value) => self.export_progress = Some(value),
+                    ExportProgress::Finished(result) => {
+                        self.export_progress = None;
+                        self.export_status = Some(match result {
+                            Ok(path) => trf("Saved to {}", &[&path.display()]),
+                            Err(e) => {
+                                error!("Failed to export image: {}", e);
+                                trf("Export failed: {}", &[&e])
+                            }
+                        });
+                    }
+                }
+                Task::none()
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                Task::none()
+            }
+            Message::ClearSelection => {
+                self.selected_images.clear();
+                Task::none()
+            }
+            Message::ToggleConvertDialog => {
+                self.convert_dialog_opened =
+                    !self.convert_dialog_opened && !self.convert_targets().is_empty();
+                self.convert_status = None;
+                self.convert_failures.clear();
+                Task::none()
+            }
+            Message::ConvertEncodeChanged(options) => {
+                self.convert_settings.encode = options;
+                Task::none()
+            }
+            Message::ChooseConvertFolder => {
+                let mut dialog = FileDialog::new();
+                if let Some(dir) = &self.convert_settings.output_dir {
+                    dialog = dialog.set_directory(dir);
+                } else if let Some(parent) = self.current_image.as_ref().and_then(|p| p.parent()) {
+                    dialog = dialog.set_directory(parent);
+                }
+                if let Some(dir) = dialog.pick_folder() {
+                    self.convert_settings.output_dir = Some(dir);
+                }
+                Task::none()
+            }
+            Message::ConvertNextToOriginals => {
+                self.convert_settings.output_dir = None;
+                Task::none()
+            }
+            Message::StartConvert => {
+                let targets = self.convert_targets();
+                if targets.is_empty() || self.convert_progress.is_some() {
+                    return Task::none();
+                }
+                self.convert_progress = Some((0, targets.len()));
+                self.convert_failures.clear();
+                self.convert_status = None;
+                Task::run(
+                    convert::convert_all(targets, self.convert_settings.clone()),
+                    Message::ConvertProgressed,
+                )
+            }
+            Message::ConvertProgressed(progress) => {
+                match progress {
+                    ConvertProgress::Converted(src, result) => {
+                        if let Some((done, _)) = &mut self.convert_progress {
+                            *done += 1;
+                        }
+                        if let Err(e) = result {
+                            error!("Failed to convert {}: {}", src.display(), e);
+                            self.convert_failures.push((src, e));
+                        }
+                    }
+                    ConvertProgress::Finished => {
+                        let total = self.convert_progress.take().map_or(0, |(_, total)| total);
+                        let failed = self.convert_failures.len();
+                        self.convert_status = Some(if failed == 0 {
+                            trf("Converted {} image(s)", &[&total])
+                        } else {
+                            trf(
+                                "Converted {} of {} image(s), {} failed",
+                                &[&(total - failed), &total, &failed],
+                            )
+                        });
+                    }
+                }
+                Task::none()
+            }
+            Message::ToggleAdjustPanel => {
+                self.adjust_panel_opened = !self.adjust_panel_opened;
+                Task::none()
+            }
+            Message::AdjustmentsChanged(adjustments) => {
+                self.history.push(EditOp::Adjust {
+                    before: self.adjustments,
+                    after: adjustments,
+                });
+                self.adjustments = adjustments;
+                Task::batch([self.ensure_full_resolution(), self.start_adjusting()])
+            }
+            Message::ResetAdjustments => {
+                self.history.push(EditOp::Adjust {
+                    before: self.adjustments,
+                    after: Adjustments::default(),
+                });
+                self.adjustments = Adjustments::default();
+                self.start_adjusting()
+            }
+            Message::AdjustmentsApplied(adjustments, revision, adjusted) => {
+                self.adjusting = false;
+                // 计算期间参数、裁剪/旋转或原图的尺寸又变了，丢弃旧结果重新计算
+                let resized = adjusted.as_ref().is_some_and(|img| {
+                    Some(img.dimensions()) != self.edited_source().map(|src| src.dimensions())
+                });
+                if adjustments != self.adjustments
+                    || revision != self.history.geometry_revision()
+                    || resized
+                {
+                    return self.start_adjusting();
+                }
+                self.adjusted = adjusted.map(Arc::new);
+                self.refresh_display();
+                self.rerender_scaled();
+                self.enforce_memory_budget();
+                self.run_after_full_resolution()
+            }
+            Message::ApplyCrop => {
+                let Some(selection) = self.crop_selection else {
+                    return Task::none();
+                };
+                self.history.push(EditOp::Crop(selection));
+                self.rebuild_edited()
+            }
+            Message::Rotate(rotation) => {
+                if self.original.is_none() {
+                    return Task::none();
+                }
+                self.history.push(EditOp::Rotate(rotation));
+                Task::batch([self.ensure_full_resolution(), self.rebuild_edited()])
+            }
+            Message::ToggleStraightenMode => {
+                self.straighten_mode = !self.straighten_mode && self.original.is_some();
+                self.crop_mode = false;
+                self.blink = None;
+                self.code_scan = None;
+                self.color_picking = false;
+                self.crop_selection = None;
+                self.straighten_angle = 0.0;
+                self.overlay_handle = if self.straighten_mode {
+                    self.display_source().map(|img| rgb_handle(img))
+                } else {
+                    None
+                };
+                self.ensure_full_resolution()
+            }
+            Message::StraightenAngleChanged(angle) => {
+                self.straighten_angle = angle;
+                Task::none()
+            }
+            Message::ApplyStraighten => {
+                if self.straighten_angle != 0.0 {
+                    self.history.push(EditOp::Straighten(self.straighten_angle));
+                }
+                self.rebuild_edited()
+            }
+            Message::Undo => match self.history.undo() {
+                Some(EditOp::Adjust { before, .. }) => {
+                    self.adjustments = before;
+                    self.start_adjusting()
+                }
+                Some(_) => self.rebuild_edited(),
+                None => Task::none(),
+            },
+            Message::Redo => match self.history.redo() {
+                Some(EditOp::Adjust { after, .. }) => {
+                    self.adjustments = after;
+                    self.start_adjusting()
+                }
+                Some(_) => self.rebuild_edited(),
+                None => Task::none(),
+            },
+            Message::ToggleBatchDialog => {
+                self.batch_dialog_opened = !self.batch_dialog_opened;
+                self.batch_error = None;
+                // 没有多选时默认处理整个文件夹
+                if self.batch_dialog_opened && self.selected_images.is_empty() {
+                    self.batch_scope = BatchScope::Folder;
+                }
+                Task::none()
+            }
+            Message::BatchOptionsChanged(options) => {
+                self.batch_options = options;
+                self.batch_error = None;
+                Task::none()
+            }
+            Message::BatchEncodeChanged(encode) => {
+                self.batch_options.encode = encode;
+                Task::none()
+            }
+            Message::BatchScopeChanged(scope) => {
+                self.batch_scope = scope;
+                Task::none()
+            }
+            Message::ChooseBatchWatermark => {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("image", &["png", "jpg", "jpeg", "webp"])
+                    .pick_file()
+                {
+                    self.batch_options.watermark = Some(path);
+                }
+                Task::none()
+            }
+            Message::RemoveBatchWatermark => {
+                self.batch_options.watermark = None;
+                Task::none()
+            }
+            Message::ChooseBatchFolder => {
+                let mut dialog = FileDialog::new();
+                if let Some(dir) = &self.batch_options.output_dir {
+                    dialog = dialog.set_directory(dir);
+                }
+                if let Some(dir) = dialog.pick_folder() {
+                    self.batch_options.output_dir = Some(dir);
+                }
+                Task::none()
+            }
+            Message::BatchNextToOriginals => {
+                self.batch_options.output_dir = None;
+                Task::none()
+            }
+            Message::QueueBatchJob => {
+                let sources = self.batch_sources();
+                if sources.is_empty() {
+                    self.batch_error = Some(tr("No images to process").to_string());
+                    return Task::none();
+                }
+                match self.batch_options.plan() {
+                    Ok(plan) => {
+                        self.jobs
+                            .push(BatchJob::new(self.next_job_id, plan, sources));
+                        self.next_job_id += 1;
+                        self.batch_dialog_opened = false;
+                        self.jobs_panel_opened = true;
+                        self.start_next_job()
+                    }
+                    Err(e) => {
+                        self.batch_error = Some(e);
+                        Task::none()
+                    }
+                }
+            }
+            Message::BatchJobEvent(id, event) => {
+                let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+                    return Task::none();
+                };
+                match event {
+                    BatchEvent::Item(index, status) => {
+                        let renamed = match &status {
+                            ItemStatus::Done(dst) if job.plan.renames_in_place() => {
+                                Some((job.items[index].0.clone(), dst.clone()))
+                            }
+                            _ => None,
+                        };
+                        job.items[index].1 = status;
+                        if let ItemStatus::Failed(e) = &job.items[index].1 {
+                            warn!(
+                                "Batch job {} failed on {}: {}",
+                                id,
+                                job.items[index].0.display(),
+                                e
+                            );
+                        }
+                        // 原位重命名后更新图片库中的路径
+                        if let Some((old, new)) = renamed {
+                            self.replace_path(&old, new);
+                        }
+                        Task::none()
+                    }
+                    BatchEvent::Finished => {
+                        job.finished = true;
+                        for (_, status) in job.items.iter_mut() {
+                            if matches!(status, ItemStatus::Pending | ItemStatus::Running) {
+                                *status = ItemStatus::Cancelled;
+                            }
+                        }
+                        self.start_next_job()
+                    }
+                }
+            }
+            Message::CancelBatchJob(id) => {
+                if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+                    job.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                    // 还没开始的任务直接结束
+                    if !job.started {
+                        job.started = true;
+                        job.finished = true;
+                        for (_, status) in job.items.iter_mut() {
+                            *status = ItemStatus::Cancelled;
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::ToggleMap => {
+                if self.map.take().is_some() {
+                    return Task::none();
+                }
+                self.map = Some(MapView::new());
+                Task::batch([self.locate_map_photos(), self.load_map_tiles()])
+            }
+            Message::MapLocated(photos) => {
+                let Some(map) = &mut self.map else {
+                    return Task::none();
+                };
+                map.set_photos(photos);
+                self.load_map_tiles()
+            }
+            Message::MapPanned(delta) => {
+                let Some(map) = &mut self.map else {
+                    return Task::none();
+                };
+                map.pan(delta);
+                self.load_map_tiles()
+            }
+            Message::MapZoomed(steps, position) => {
+                let Some(map) = &mut self.map else {
+                    return Task::none();
+                };
+                map.zoom_at(steps, position);
+                self.load_map_tiles()
+            }
+            Message::MapTileLoaded(tile, result) => {
+                if let Err(e) = &result {
+                    warn!("Failed to load map tile {:?}: {}", tile, e);
+                }
+                if let Some(map) = &mut self.map {
+                    map.tile_loaded(tile, result.ok());
+                }
+                Task::none()
+            }
+            Message::ToggleTimeline => {
+                if self.timeline.take().is_some() {
+                    return Task::none();
+                }
+                self.timeline = Some(Timeline::new(TimelineSource::Collection));
+                self.build_timeline()
+            }
+            Message::TimelineSourceChanged(source) => {
+                if self.timeline.as_ref().is_some_and(|t| t.source == source) {
+                    return Task::none();
+                }
+                self.timeline = Some(Timeline::new(source));
+                self.build_timeline()
+            }
+            Message::TimelineBuilt(source, result) => {
+                // 已经关闭或换了范围时丢弃
+                let Some(timeline) = self.timeline.as_mut().filter(|t| t.source == source) else {
+                    return Task::none();
+                };
+                match result {
+                    Ok(months) => {
+                        timeline.months = Some(months);
+                        timeline.selected = 0;
+                        self.load_timeline_thumbnails()
+                    }
+                    Err(e) => {
+                        self.timeline = None;
+                        self.show_toast(
+                            ToastKind::Error,
+                            trf("Failed to read the catalog: {}", &[&e]),
+                        )
+                    }
+                }
+            }
+            Message::TimelineMonthSelected(index) => {
+                if let Some(timeline) = &mut self.timeline {
+                    timeline.selected = index;
+                }
+                self.load_timeline_thumbnails()
+            }
+            Message::TimelineImagePicked(path) => {
+                self.timeline = None;
+                self.update(Message::PickImage(path))
+            }
+            Message::ToggleJobsPanel => {
+                self.jobs_panel_opened = !self.jobs_panel_opened;
+                Task::none()
+            }
+            Message::ClearFinishedJobs => {
+                self.jobs.retain(|job| !job.finished);
+                Task::none()
+            }
+            Message::ClearSearch => {
+                if self.active_search.take().is_some() {
+                    self.image_collection = std::mem::take(&mut self.unfiltered_collection);
+                    self.current_image_index = self
+                        .current_image
+                        .as_ref()
+                        .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                        .unwrap_or(0);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        // 只在解码、扫描目录或传输期间刷新加载动画
+        let spinner =
+            if self.loading.is_some() || self.scan.is_some() || self.transfers.pending() > 0 {
+                iced::time::every(Duration::from_millis(120)).map(|_| Message::SpinnerTick)
+            } else {
+                Subscription::none()
+            };
+        // 性能面板打开时定时刷新
+        let perf_hud = if self.perf_hud_opened {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::PerfHudTick)
+        } else {
+            Subscription::none()
+        };
+        // 有等待重试的上传时每秒检查一次
+        let retry = if self.transfers.is_waiting() {
+            iced::time::every(Duration::from_secs(1)).map(|_| Message::RetryTransfersTick)
+        } else {
+            Subscription::none()
+        };
+        // 设置了间隔时定时同步
+        let sync = if self.settings.sync_interval > 0 && !self.settings.sync_folders.is_empty() {
+            iced::time::every(Duration::from_secs(self.settings.sync_interval * 60))
+                .map(|_| Message::SyncNow)
+        } else {
+            Subscription::none()
+        };
+        // 幻灯片放映时定时切换到下一张
+        let slideshow = match self.slideshow {
+            Some(interval) if !self.slideshow_paused => {
+                iced::time::every(interval).map(|_| Message::SlideshowTick)
+            }
+            _ => Subscription::none(),
+        };
+        // 闪烁比较时定时切换显示的图片
+        let blink = match &self.blink {
+            Some(blink) if !blink.paused && blink.frames.is_some() => {
+                iced::time::every(Duration::from_millis(self.config.blink_interval))
+                    .map(|_| Message::BlinkTick)
+            }
+            _ => Subscription::none(),
+        };
+        // 临时平移时等待松开按住的按键或中键，鼠标移出图片后松开也能收到
+        let temporary_pan = if self.temporary_pan.is_some() {
+            iced::event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyReleased { key, modifiers, .. }) => {
+                    Some(Message::KeyReleased(key, modifiers))
+                }
+                iced::Event::Mouse(iced::mouse::Event::ButtonReleased(
+                    iced::mouse::Button::Middle,
+                )) => Some(Message::EndTemporaryPan),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+        // 不监视文件系统，只定时比较图片库所在文件夹的修改时间
+        let folder_check = if self.collection_folder.is_some() && !self.folder_stale {
+            iced::time::every(FOLDER_CHECK_INTERVAL).map(|_| Message::CheckFolder)
+        } else {
+            Subscription::none()
+        };
+        // 诊断面板打开时定时显示新的日志
+        let log = if self.log_panel_opened {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::LogTick)
+        } else {
+            Subscription::none()
+        };
+        Subscription::batch([
+            spinner,
+            folder_check,
+            // 脚本或媒体键发来的控制命令，之后启动的实例也通过它转发路径
+            Subscription::run(remote_control::commands).map(Message::RemoteCommand),
+            perf_hud,
+            log,
+            slideshow,
+            blink,
+            retry,
+            sync,
+            // 快捷键在 update 中按快捷键表处理，输入框中的按键不会传到这里
+            iced::event::listen_with(|event, status, window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+                    if status == iced::event::Status::Ignored =>
+                {
+                    Some(Message::KeyPressed(window, key, modifiers))
+                }
+                _ => None,
+            }),
+            window::close_events().map(Message::WindowClosed),
+            temporary_pan,
+            // 没有被其他控件处理的触摸事件用于图片的捏合缩放
+            iced::event::listen_with(|event, status, _window| match event {
+                iced::Event::Touch(event) if status == iced::event::Status::Ignored => {
+                    Some(Message::Touched(event))
+                }
+                _ => None,
+            }),
+            // 记录修饰键，用于缩略图的 Ctrl/Shift 多选
+            iced::event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
+                _ => None,
+            }),
+        ])
+    }
+
+    // 解码完成后显示图片
+    fn show_decoded(&mut self, path: PathBuf, decoded: Decoded) -> Task<Message> {
+        self.original = Some(decoded.image);
+        self.orientation = decoded.orientation;
+        self.full_size = decoded.full_size;
+        self.texture = decoded.texture;
+        self.psd_layers = decoded.psd_layers;
+        // 恢复的平移量按原图的像素记录，先换算到缩小的图片上
+        self.pan_offset = self.pan_offset * (1.0 / self.reduction());
+        self.refresh_display();
+        self.enforce_memory_budget();
+        let alpha = self.load_alpha();
+        let full_resolution =
+            if self.is_resampling_mode && self.current_image.as_ref() == Some(&path) {
+                // 恢复的视图先按缩小的图片显示，同时在后台解码原图
+                self.rerender_scaled();
+                self.ensure_full_resolution()
+            } else {
+                Task::none()
+            };
+
+        // GIF、PNG 和 WebP 在后台检查是否是动画，是动画时解码所有帧
+        let frames = if frames::may_be_animated(&path) {
+            let path = path.clone();
+            Task::perform(
+                async move {
+                    let decoded = tokio::task::spawn_blocking({
+                        let path = path.clone();
+                        move || frames::decode(&path).map_err(|e| e.to_string())
+                    })
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|r| r);
+                    Message::FramesDecoded(path, decoded)
+                },
+                |msg| msg,
+            )
+        } else {
+            Task::none()
+        };
+        // 只自动上传设置中指定文件夹里的图片
+        if self.settings.should_auto_upload(&path) && self.settings.cloud.is_configured() {
+            Task::batch([
+                alpha,
+                full_resolution,
+                frames,
+                Task::perform(async move { Message::UploadToCloud(path) }, |msg| msg),
+            ])
+        } else {
+            Task::batch([alpha, full_resolution, frames])
+        }
+    }
+
+    // 按新的显示方向解码完成后替换当前图片，保留缩放和平移
+    fn show_reoriented(&mut self, decoded: Decoded) -> Task<Message> {
+        // 平移量先换算到原图的像素，新旧图片缩小的比例可能不同
+        let pan_offset = self.pan_offset * self.reduction();
+        self.original = Some(decoded.image);
+        self.orientation = decoded.orientation;
+        self.full_size = decoded.full_size;
+        self.texture = decoded.texture;
+        self.psd_layers = decoded.psd_layers;
+        self.pan_offset = pan_offset * (1.0 / self.reduction());
+        // 原尺寸和透明通道也要按新的方向重新解码
+        self.full_resolution_generation += 1;
+        self.full_resolution_pending = false;
+        self.alpha = None;
+        self.alpha_pending = false;
+        self.mip_level = 0;
+        if let Some(animation) = &mut self.animation {
+            animation.current = 0;
+        }
+        // 裁剪和旋转基于旧的方向，不再适用
+        self.edited = None;
+        self.history.clear();
+        self.crop_selection = None;
+        if self.crop_mode || self.straighten_mode || self.color_picking {
+            self.overlay_handle = self.display_source().map(|img| rgb_handle(img));
+        }
+        self.enforce_memory_budget();
+        let full_resolution = if self.is_resampling_mode || self.after_full_resolution.is_some() {
+            self.ensure_full_resolution()
+        } else {
+            Task::none()
+        };
+        // 调整需要基于新的像素重新计算
+        if !self.adjustments.is_identity() {
+            self.adjusted = None;
+            return Task::batch([self.start_adjusting(), self.load_alpha(), full_resolution]);
+        }
+        self.refresh_display();
+        self.rerender_scaled();
+        Task::batch([self.load_alpha(), full_resolution])
+    }
+
+    // 插件启用状态变化后重新查询当前图片的附加信息
+    fn refresh_plugin_metadata(&mut self) -> Task<Message> {
+        match self.current_image.clone() {
+            Some(path) => load_plugin_metadata(path),
+            None => {
+                self.plugin_metadata.clear();
+                Task::none()
+            }
+        }
+    }
+
+    fn save_config(&self) {
+        if let Some(path) = config::config_path()
+            && let Err(e) = self.config.save_to_file(&path)
+        {
+            error!("Failed to save config: {}", e);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Some(path) = self.config.settings_path()
+            && let Err(e) = self.settings.save_to_file(path)
+        {
+            error!("Failed to save settings: {}", e);
+        }
+    }
+
+    fn save_remote_cache(&self) {
+        if let Some(path) = remote_cache::index_path()
+            && let Err(e) = self.remote_cache.save_to_file(path)
+        {
+            error!("Failed to save remote cache index: {}", e);
+        }
+    }
+
+    fn save_uploads(&self) {
+        if let Some(path) = transfer_queue::uploads_path()
+            && let Err(e) = self.transfers.save_uploads(path)
+        {
+            error!("Failed to save upload queue: {}", e);
+        }
+    }
+
+    /// 开始排队中的传输，直到同时进行的数量达到上限；队列变化后都会调用这里，顺便保存上传队列
+    fn pump_transfers(&mut self) -> Task<Message> {
+        self.save_uploads();
+        // 上传前按元数据设置去除或修改元数据
+        let policy = self.metadata_policy();
+        let mut tasks = Vec::new();
+        while let Some(transfer) = self.transfers.next_to_start() {
+            let id = transfer.id;
+            info!(key = %transfer.key, direction = ?transfer.direction, "Starting transfer");
+            let config = self.settings.cloud.clone();
+            let uploaded = config
+                .bucket_id()
+                .and_then(|bucket| self.upload_manifest.get(&bucket, &transfer.key))
+                .cloned();
+            let stream = transfer_queue::run(transfer, config, uploaded, policy.clone());
+            let (task, handle) =
+                Task::run(stream, move |event| Message::TransferProgress(id, event)).abortable();
+            transfer.start(handle);
+            tasks.push(task);
+        }
+        Task::batch(tasks)
+    }
+
+    // 图片库不再显示云端文件夹，还没开始的下载不再需要
+    fn leave_cloud_folder(&mut self) {
+        self.cloud_folder = None;
+        self.transfers.remove_queued_downloads();
+    }
+
+    // 在后台读取时间线中图片的拍摄日期并分组
+    fn build_timeline(&mut self) -> Task<Message> {
+        let Some(timeline) = &self.timeline else {
+            return Task::none();
+        };
+        let source = timeline.source;
+        match source {
+            TimelineSource::Collection => Task::perform(
+                timeline::from_paths(self.image_collection.clone()),
+                move |result| Message::TimelineBuilt(source, result),
+            ),
+            TimelineSource::Library => {
+                let Some(db) = self.catalog.as_ref().and_then(|_| catalog::catalog_path()) else {
+                    return Task::none();
+                };
+                Task::perform(timeline::from_catalog(db), move |result| {
+                    Message::TimelineBuilt(source, result)
+                })
+            }
+        }
+    }
+
+    // 生成时间线中选中月份的缩略图
+    fn load_timeline_thumbnails(&mut self) -> Task<Message> {
+        let images = self
+            .timeline
+            .as_ref()
+            .map(Timeline::selected_images)
+            .unwrap_or_default();
+        self.load_thumbnails(&images)
+    }
+
+    // 在后台读取图片库中图片的 GPS 位置
+    fn locate_map_photos(&mut self) -> Task<Message> {
+        let Some(map) = &mut self.map else {
+            return Task::none();
+        };
+        map.photos = None;
+        Task::perform(
+            map::locate(self.image_collection.clone()),
+            Message::MapLocated,
+        )
+    }
+
+    // 下载地图上能看到但还没有的瓦片
+    fn load_map_tiles(&mut self) -> Task<Message> {
+        let Some(map) = &mut self.map else {
+            return Task::none();
+        };
+        Task::batch(map.missing_tiles().into_iter().map(|tile| {
+            Task::perform(map::fetch_tile(tile), move |result| {
+                Message::MapTileLoaded(tile, result)
+            })
+        }))
+    }
+
+    // 搜索结果作为临时图片库显示，关闭后恢复原来的图片
+    fn show_results(&mut self, images: Vec<PathBuf>) -> Task<Message> {
+        let backup = if self.results_shown() {
+            std::mem::take(&mut self.find_backup)
+        } else if self.active_search.is_some() {
+            // 正在按元数据筛选时保存筛选前的完整图片库
+            std::mem::take(&mut self.unfiltered_collection)
+        } else {
+            std::mem::take(&mut self.image_collection)
+        };
+        let folder = self.collection_folder.take();
+        let task = self.replace_collection(images);
+        self.find_backup = backup;
+        // 关闭结果后恢复的仍是这个文件夹的图片
+        self.collection_folder = folder;
+        self.current_image_index = self
+            .current_image
+            .as_ref()
+            .and_then(|current| self.image_collection.iter().position(|p| p == current))
+            .unwrap_or(0);
+        task
+    }
+
+    // 图片库换成给定的图片，取消还在进行的目录扫描和缩略图生成
+    fn replace_collection(&mut self, images: Vec<PathBuf>) -> Task<Message> {
+        self.scan = None;
+        self.leave_cloud_folder();
+        self.active_search = None;
+        self.forget_find_results();
+        self.selected_images.clear();
+        self.cancel_thumbnails();
+        self.image_collection = images;
+        self.collection_folder = None;
+        self.folder_stale = false;
+        self.record_thumbnail_lookups(&self.image_collection);
+
+        // 为每个图片异步加载缩略图
+        self.load_missing_thumbnails()
+    }
+
+    // 图片库换成别的文件夹后不再恢复搜索之前的图片
+    fn forget_find_results(&mut self) {
+        self.find_results = None;
+        self.similar_to = None;
+        self.flag_filter = None;
+        self.find_backup.clear();
+    }
+
+    // 图片库是否显示的是临时结果（文件名搜索、相似图片或挑片标记）
+    fn results_shown(&self) -> bool {
+        self.find_results.is_some() || self.similar_to.is_some() || self.flag_filter.is_some()
+    }
+
+    // 不含临时结果和元数据筛选的完整图片库
+    fn base_collection(&self) -> &[PathBuf] {
+        if self.results_shown() {
+            &self.find_backup
+        } else if self.active_search.is_some() {
+            &self.unfiltered_collection
+        } else {
+            &self.image_collection
+        }
+    }
+
+    // 按设置打开或关闭图片目录，并在目录树中显示或移除 Library 节点
+    fn refresh_library_root(&mut self) {
+        self.root_file_tree_entry
+            .retain(|entry| !catalog::is_library(entry.path()));
+        if self.settings.catalog_folders.is_empty() {
+            self.catalog = None;
+            return;
+        }
+        if self.catalog.is_none() {
+            self.catalog = match catalog::catalog_path().map(|path| Catalog::open(&path)) {
+                Some(Ok(catalog)) => Some(catalog),
+                Some(Err(e)) => {
+                    error!("Failed to open catalog: {}", e);
+                    None
+                }
+                None => None,
+            };
+        }
+        let albums = SmartAlbum::ALL
+            .into_iter()
+            .map(|album| FileTreeEntry::Directory {
+                name: album.name().to_string(),
+                path: album.path(),
+                children: Vec::new(),
+                expanded: false,
+                children_loaded: true,
+                link: None,
+                access: Access::Readable,
+            })
+            .collect();
+        // 放在智能文件夹后面、主目录前面
+        let position = self
+            .root_file_tree_entry
+            .iter()
+            .take_while(|entry| smart_directory::is_smart_folder(entry.path()))
+            .count();
+        self.root_file_tree_entry.insert(
+            position,
+            FileTreeEntry::Directory {
+                name: "Library".to_string(),
+                path: PathBuf::from(catalog::LIBRARY_ROOT),
+                children: albums,
+                expanded: false,
+                children_loaded: true,
+                link: None,
+                access: Access::Readable,
+            },
+        );
+    }
+
+    // 固定在 Recents 中的图片所在的文件夹，已经在图片目录中的除外
+    fn pinned_folders(&self) -> Vec<PathBuf> {
+        let mut folders: Vec<PathBuf> = Vec::new();
+        for item in self.recent_manager.get_recent_items() {
+            let Some(folder) = item.path().parent() else {
+                continue;
+            };
+            let indexed = self.catalog.is_some()
+                && self
+                    .settings
+                    .catalog_folders
+                    .iter()
+                    .any(|root| folder.starts_with(root));
+            if item.is_pinned() && !indexed && !folders.iter().any(|f| f == folder) {
+                folders.push(folder.to_path_buf());
+            }
+        }
+        folders
+    }
+
+    // 在浏览记录中记下当前图片的缩放和平移，默认视图不记录
+    fn remember_view(&mut self) {
+        if !self.settings.remember_view {
+            return;
+        }
+        let Some(path) = self.current_image.clone() else {
+            return;
+        };
+        let pan = self.pan_offset * self.reduction();
+        let view = (self.slider_value != 50 || pan != Vector::ZERO).then_some(ViewState {
+            zoom: self.slider_value,
+            pan_x: pan.x,
+            pan_y: pan.y,
+        });
+        let recorded = self.recent_manager.get(&path).and_then(|item| item.view());
+        if recorded != view {
+            self.recent_manager.set_view(&path, view);
+            self.save_recents();
+        }
+    }
+
+    fn save_recents(&self) {
+        if let Some(dir) = config::data_dir()
+            && let Err(e) = self.recent_manager.save_to_file(dir.join("recent.json"))
+        {
+            error!("Failed to save recents: {}", e);
+        }
+    }
+
+    // 浏览记录变化后重新生成 Recents 和 Most viewed 的子项
+    fn refresh_smart_folders(&mut self) {
+        for entry in self.root_file_tree_entry.iter_mut() {
+            let FileTreeEntry::Directory { path, children, .. } = entry else {
+                continue;
+            };
+            let items = if path.as_os_str() == smart_directory::RECENTS {
+                self.recent_manager.get_recent_items().iter().collect()
+            } else if path.as_os_str() == smart_directory::MOST_VIEWED {
+                self.recent_manager.most_viewed()
+            } else {
+                continue;
+            };
+            *children = items.into_iter().map(smart_folder_child).collect();
+        }
+    }
+
+    // 云存储设置变化后重建目录树中的云端根节点，没有配置时不显示
+    fn refresh_cloud_root(&mut self) {
+        self.root_file_tree_entry.retain(|entry| {
+            !matches!(entry, FileTreeEntry::Directory { path, .. } if cloud::is_remote(path))
+        });
+        if self.cloud_folder.is_some() {
+            self.leave_cloud_folder();
+        }
+        if self.settings.cloud.is_configured() {
+            self.root_file_tree_entry.push(cloud::root_entry());
+        }
+    }
+
+    /// 显示一条提示，到时间后自动关闭
+    fn show_toast(&mut self, kind: ToastKind, message: String) -> Task<Message> {
+        let id = self.next_toast_id;
+        self.next_toast_id += 1;
+        self.toasts.push(Toast { id, kind, message });
+        Task::perform(tokio::time::sleep(toast::TOAST_DURATION), move |_| {
+            Message::DismissToast(id)
+        })
+    }
+
+    // 浏览时大 JPEG 只按缩小的尺寸解码，缩放、编辑、导出和保存前在后台换成原尺寸的像素，期间继续显示缩小的图片
+    fn ensure_full_resolution(&mut self) -> Task<Message> {
+        if self.full_size.is_none() || self.full_resolution_pending {
+            return Task::none();
+        }
+        let Some(path) = self.current_image.clone() else {
+            return Task::none();
+        };
+        self.full_resolution_generation += 1;
+        self.full_resolution_pending = true;
+        let generation = self.full_resolution_generation;
+        let apply_orientation = !self.show_raw_orientation;
+        Task::perform(
+            async move {
+                let decoded = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || {
+                        let (img, orientation) = decode_image(&path, apply_orientation)?;
+                        Ok(Decoded {
+                            image: Arc::new(img.to_rgb8()),
+                            orientation,
+                            full_size: None,
+                            texture: None,
+                            psd_layers: Vec::new(),
+                        })
+                    }
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r: image::ImageResult<Decoded>| r.map_err(|e| e.to_string()));
+                Message::FullResolutionDecoded(path, generation, decoded)
+            },
+            |msg| msg,
+        )
+    }
+
+    // 需要原尺寸像素的操作：原图和按原图计算的调整还没就绪时先记下 message，就绪后再执行
+    fn with_full_resolution(&mut self, message: Message) -> Option<Task<Message>> {
+        if self.full_size.is_none() && !self.adjusting {
+            return None;
+        }
+        self.after_full_resolution = Some(message);
+        Some(self.ensure_full_resolution())
+    }
+
+    fn run_after_full_resolution(&mut self) -> Task<Message> {
+        if self.full_size.is_some() || self.adjusting {
+            return Task::none();
+        }
+        match self.after_full_resolution.take() {
+            Some(message) => self.update(message),
+            None => Task::none(),
+        }
+    }
+
+    // 缩小解码时原图与显示的图片的比例；平移量按显示的图片的像素计算，记录时换算成原图的像素
+    fn reduction(&self) -> f32 {
+        match (self.full_size, &self.original) {
+            (Some((width, _)), Some(ori)) => width as f32 / ori.width() as f32,
+            _ => 1.0,
+        }
+    }
+
+    // 并发生成图片库中还没有缓存的缩略图
+    fn load_missing_thumbnails(&mut self) -> Task<Message> {
+        // 当前图片库里的缩略图最后才被淘汰
+        self.thumbnail_cache.touch(&self.image_collection);
+        let images = self.image_collection.clone();
+        self.load_thumbnails(&images)
+    }
+
+    // 在后台生成还没有缓存的缩略图
+    fn load_thumbnails(&mut self, images: &[PathBuf]) -> Task<Message> {
+        let missing: Vec<PathBuf> = images
+            .iter()
+            .filter(|p| {
+                !self.thumbnail_cache.contains_key(p) && !self.thumbnails_pending.contains(*p)
+            })
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            return Task::none();
+        }
+        self.thumbnails_pending.extend(missing.iter().cloned());
+        let generation = self.collection_generation;
+        let (task, handle) = Task::run(thumbnails::load_all(missing), move |batch| {
+            Message::ThumbnailsLoaded(generation, batch)
+        })
+        .abortable();
+        self.thumbnail_tasks.push(handle);
+        task
+    }
+
+    // 当前图片、缩放缓冲区和各个缓存大致占用的内存
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let images = [&self.original, &self.edited, &self.adjusted, &self.alpha];
+        let handles = [&self.display_handle, &self.overlay_handle];
+        MemoryUsage {
+            current: images
+                .iter()
+                .flat_map(|img| img.as_ref())
+                // 原图和解码缓存共享同一份像素，只计算一次
+                .filter(|img| !self.decoded_cache.holds(img))
+                .map(|img| img.as_raw().len())
+                .sum::<usize>()
+                + handles
+                    .iter()
+                    .flat_map(|h| h.as_ref())
+                    .map(memory::handle_bytes)
+                    .sum::<usize>(),
+            buffers: self.scaled_bytes.len()
+                + self.preview_scaled_bytes.len()
+                + self.final_scaled_bytes.len(),
+            decoded: self.decoded_cache.used(),
+            thumbnails: self.thumbnail_cache.used(),
+        }
+    }
+
+    // 超出内存上限时先淘汰解码缓存，再淘汰缩略图，正在显示的图片不会被释放
+    fn enforce_memory_budget(&mut self) {
+        let usage = self.memory_usage();
+        if usage.total() <= self.memory_budget {
+            return;
+        }
+        let available = self
+            .memory_budget
+            .saturating_sub(usage.current + usage.buffers);
+        self.decoded_cache
+            .shrink_to(available.saturating_sub(usage.thumbnails));
+        self.thumbnail_cache
+            .shrink_to(available.saturating_sub(self.decoded_cache.used()));
+    }
+
+    // 打开文件夹时统计缩略图缓存的命中情况
+    fn record_thumbnail_lookups(&self, paths: &[PathBuf]) {
+        for path in paths {
+            perf::record_cache(
+                perf::Cache::Thumbnail,
+                self.thumbnail_cache.contains_key(path),
+            );
+        }
+    }
+
+    // 取消上一个文件夹还在生成的缩略图，已经生成的缩略图保留在缓存中
+    fn cancel_thumbnails(&mut self) {
+        for handle in self.thumbnail_tasks.drain(..) {
+            handle.abort();
+        }
+        self.thumbnails_pending.clear();
+        self.collection_generation += 1;
+    }
+
+    // 在后台线程按当前的缩放值缩放显示的图片，并取消上一次还没完成的缩放
+    fn spawn_resize(&mut self, resampling: ResamplingType, high_quality: bool) -> Task<Message> {
+        if let Some(handle) = self.resize_task.take() {
+            handle.abort();
+        }
+        let img = self.shown_source().cloned();
+        let scale = self.slider_value as f32 / 50.0;
+        let offset = self.pan_offset;
+        let generation = self.resize_generation;
+        let (task, handle) = Task::perform(
+            async move {
+                // 缩放失败时结果为空，界面退回显示未缩放的图片
+                let scaled = tokio::task::spawn_blocking(move || match img {
+                    Some(img) => crop_and_scale(&img, scale, offset, resampling),
+                    None => Ok(Vec::new()),
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r)
+                .unwrap_or_else(|e| {
+                    error!("Failed to scale image: {}", e);
+                    Vec::new()
+                });
+                Message::ImageResized(scaled, high_quality, generation)
+            },
+            |msg| msg,
+        )
+        .abortable();
+        self.resize_task = Some(handle);
+        task
+    }
+
+    // 放弃正在进行的缩放，之前发出的结果也会因为代数变化被丢弃
+    fn cancel_resize(&mut self) {
+        self.resize_generation += 1;
+        if let Some(handle) = self.resize_task.take() {
+            handle.abort();
+        }
+    }
+
+    // 批处理的图片：选中的图片（没有多选时为当前图片）或整个图片库
+    fn batch_sources(&self) -> Vec<PathBuf> {
+        match self.batch_scope {
+            BatchScope::Selection => self.convert_targets(),
+            BatchScope::Folder => self.image_collection.clone(),
+        }
+    }
+
+    // 队列中没有正在运行的任务时，启动下一个等待中的任务
+    fn start_next_job(&mut self) -> Task<Message> {
+        if self.jobs.iter().any(|job| job.started && !job.finished) {
+            return Task::none();
+        }
+        let Some(job) = self.jobs.iter_mut().find(|job| !job.started) else {
+            return Task::none();
+        };
+        job.started = true;
+        let id = job.id;
+        Task::run(batch::run_job(job), move |event| {
+            Message::BatchJobEvent(id, event)
+        })
+    }
+
+    // 执行快捷键对应的动作
+    fn run_action(&mut self, action: Action) -> Task<Message> {
+        match action {
+            Action::Next => self.update(Message::PickNextImage),
+            Action::Previous => self.update(Message::PickPreviousImage),
+            Action::Delete => self.update(Message::DeleteImage),
+            Action::RotateLeft => self.update(Message::Rotate(Rotation::Left)),
+            Action::RotateRight => self.update(Message::Rotate(Rotation::Right)),
+            Action::Fullscreen => self.update(Message::ToggleFullscreen),
+            Action::ZoomIn => self.zoom_view(self.slider_value.saturating_add(ZOOM_STEP).min(150)),
+            Action::ZoomOut => self.zoom_view(self.slider_value.saturating_sub(ZOOM_STEP).max(50)),
+            Action::ZoomReset => self.set_view(50, Vector::ZERO),
+            Action::ActualSize => {
+                container::visible_bounds(container::Id::new(VIEWER)).map(Message::ViewerMeasured)
+            }
+            Action::Save => self.update(Message::Save),
+            Action::SaveAs => self.update(Message::SaveAs),
+            Action::Find => self.update(Message::ToggleFindBar),
+            Action::Undo => self.update(Message::Undo),
+            Action::Redo => self.update(Message::Redo),
+            Action::Cancel => self.update(Message::EscPressed),
+            Action::PerfHud => self.update(Message::TogglePerfHud),
+            Action::Diagnostics => self.update(Message::ToggleLogPanel),
+            Action::NewWindow => {
+                let path = self
+                    .current_image
+                    .clone()
+                    .unwrap_or_else(|| self.current_path.clone());
+                self.update(Message::OpenInNewWindow(path))
+            }
+            Action::NewTab => self.update(Message::NewTab),
+            Action::CloseTab => self.update(Message::CloseTab(self.active_tab)),
+            Action::HoldToPan => self.start_temporary_pan(false),
+            Action::LockView => self.update(Message::ViewLockToggled(!self.view_locked)),
+            Action::Blink => self.update(Message::ToggleBlink),
+            Action::ScanCodes => self.update(Message::ScanCodes),
+            Action::ExtractText => self.update(Message::ExtractText),
+            Action::FindSimilar => self.update(Message::FindSimilar),
+            Action::Map => self.update(Message::ToggleMap),
+            Action::Timeline => self.update(Message::ToggleTimeline),
+            Action::PickColor => self.update(Message::ToggleColorPicker),
+            Action::Refresh => self.update(Message::RefreshCollection),
+            Action::Pick => self.update(Message::SetFlag(Some(Flag::Pick))),
+            Action::Reject => self.update(Message::SetFlag(Some(Flag::Reject))),
+            Action::Unflag => self.update(Message::SetFlag(None)),
+            Action::Thumbnails => {
+                let strip = if self.config.thumbnail_strip == ThumbnailStrip::Shown {
+                    ThumbnailStrip::Hidden
+                } else {
+                    ThumbnailStrip::Shown
+                };
+                self.update(Message::ThumbnailStripChanged(strip))
+            }
+            Action::NextTab => {
+                let next = (self.active_tab + 1) % self.tabs.len();
+                self.update(Message::SelectTab(next))
+            }
+            Action::PreviousTab => {
+                let previous = (self.active_tab + self.tabs.len() - 1) % self.tabs.len();
+                self.update(Message::SelectTab(previous))
+            }
+        }
+    }
+
+    // 进入或退出全屏：隐藏界面元素，并把主窗口切换为系统的无边框全屏
+    fn set_fullscreen(&mut self, fullscreen: bool) -> Task<Message> {
+        self.is_fullscreen = fullscreen;
+        let mode = if fullscreen {
+            window::Mode::Fullscreen
+        } else {
+            window::Mode::Windowed
+        };
+        window::change_mode(self.main_window, mode)
+    }
+
+    // 文件夹中的文件变化后，目录树中已展开的文件夹重新扫描
+    fn refresh_tree_folder(&mut self, dir: &Path) -> Task<Message> {
+        let dir = dir.to_path_buf();
+        let expanded = self.root_file_tree_entry.iter_mut().any(|root| {
+            matches!(
+                find_entry_by_path(root, &dir),
+                Some(FileTreeEntry::Directory { expanded: true, .. })
+            )
+        });
+        if expanded {
+            self.update(Message::RefreshFolder(dir))
+        } else {
+            Task::none()
+        }
+    }
+
+    // 把当前标签页的图片库、当前图片和缩放状态存到 tabs 中
+    fn store_tab(&mut self) {
+        self.tabs[self.active_tab] = Tab {
+            folder: self.current_path.clone(),
+            images: self.image_collection.clone(),
+            index: self.current_image_index,
+            current: self.current_image.clone(),
+            zoom: self.slider_value,
+            pan: self.pan_offset * self.reduction(),
+            zoomed: self.is_resampling_mode,
+            source: self.collection_folder.clone(),
+        };
+    }
+
+    // 切换到另一个标签页，换上它的图片库并重新打开它的当前图片
+    fn restore_tab(&mut self, index: usize) -> Task<Message> {
+        self.active_tab = index;
+        let tab = self.tabs[index].clone();
+        self.current_path = tab.folder;
+        let thumbnails = self.replace_collection(tab.images);
+        self.collection_folder = tab.source;
+        self.current_image_index = tab.index;
+        self.current_image = tab.current.clone();
+        let Some(path) = tab.current else {
+            self.original = None;
+            self.display_handle = None;
+            self.channel_view = None;
+            self.loading = None;
+            self.load_error = None;
+            return thumbnails;
+        };
+        let opened = self.update(Message::LoadImage(path));
+        // LoadImage 按设置恢复上次的视图，这里换成标签页自己的缩放和平移
+        self.slider_value = tab.zoom;
+        self.pan_offset = tab.pan * (1.0 / self.reduction());
+        self.is_resampling_mode = tab.zoomed;
+        let full_resolution = if tab.zoomed && self.original.is_some() {
+            self.rerender_scaled();
+            self.ensure_full_resolution()
+        } else {
+            Task::none()
+        };
+        Task::batch([thumbnails, opened, full_resolution])
+    }
+
+    // 查看窗口中只处理切换图片和关闭窗口，其他动作交给主窗口
+    fn run_viewer_action(&mut self, window: window::Id, action: Action) -> Task<Message> {
+        match action {
+            Action::Next => self.update(Message::ViewerNext(window)),
+            Action::Previous => self.update(Message::ViewerPrevious(window)),
+            Action::Cancel => window::close(window),
+            Action::HoldToPan => Task::none(),
+            _ => self.run_action(action),
+        }
+    }
+
+    // 打开命令行中指定的路径：文件夹时显示其中的图片，图片时同时显示所在文件夹的其他图片
+    fn open_path(&mut self, path: PathBuf) -> Task<Message> {
+        let path = std::path::absolute(&path).unwrap_or(path);
+        if path.is_dir() {
+            self.current_path = path.clone();
+            return self.update(Message::ExpandDirectory(path));
+        }
+        if !path.is_file() {
+            return self.show_toast(
+                ToastKind::Error,
+                trf(
+                    "Cannot open {}: {}",
+                    &[&path.display(), &tr("No such file or directory")],
+                ),
+            );
+        }
+        let dir = path.parent().unwrap_or(&path).to_path_buf();
+        // 扫描完成时按当前图片定位它在图片库中的位置
+        self.current_path = dir.clone();
+        self.current_image = Some(path.clone());
+        Task::batch([
+            self.update(Message::ExpandDirectory(dir)),
+            self.update(Message::LoadImage(path)),
+        ])
+    }
+
+    // 按住空格或中键时临时启用手型工具，中键按下后直接开始拖动
+    fn start_temporary_pan(&mut self, middle_button: bool) -> Task<Message> {
+        if self.temporary_pan.is_some()
+            || self.original.is_none()
+            || self.crop_mode
+            || self.straighten_mode
+        {
+            return Task::none();
+        }
+        self.temporary_pan = Some(TemporaryPan {
+            middle_button,
+            hand_tool_was_active: self.hand_tool_active,
+            moved: false,
+        });
+        self.hand_tool_active = true;
+        if middle_button {
+            self.is_panning = true;
+            self.pan_start_position = None;
+        }
+        self.ensure_full_resolution()
+    }
+
+    // 松开后按手型工具的流程结束拖动，再回到原来的工具
+    fn end_temporary_pan(&mut self) -> Task<Message> {
+        let Some(pan) = self.temporary_pan.take() else {
+            return Task::none();
+        };
+        let released = if self.is_panning {
+            self.update(Message::MouseReleased(iced::mouse::Event::ButtonReleased(
+                iced::mouse::Button::Left,
+            )))
+        } else {
+            Task::none()
+        };
+        self.hand_tool_active = pan.hand_tool_was_active;
+        // 中键单击而没有拖动时恢复默认的缩放和平移
+        if pan.middle_button && !pan.moved {
+            return Task::batch([released, self.run_action(Action::ZoomReset)]);
+        }
+        released
+    }
+
+    // 记录图片上的单击，与上一次单击间隔足够短时为双击
+    fn double_clicked(&mut self) -> bool {
+        let now = Instant::now();
+        let double = self
+            .last_click
+            .is_some_and(|last| now.duration_since(last) < DOUBLE_CLICK_INTERVAL);
+        self.last_click = (!double).then_some(now);
+        double
+    }
+
+    // 手势缩放和平移，缩放条之间的小数部分累计到下一次手势
+    fn apply_gesture(&mut self, gesture: Gesture) -> Task<Message> {
+        if self.original.is_none() || self.crop_mode || self.straighten_mode {
+            return Task::none();
+        }
+        let value = ((self.slider_value as f32 + self.gesture_zoom_remainder) * gesture.zoom)
+            .clamp(50.0, 150.0);
+        let slider_value = value.round() as u8;
+        self.gesture_zoom_remainder = value - slider_value as f32;
+        let offset =
+            self.pan_offset * (slider_value as f32 / self.slider_value as f32) + gesture.pan;
+        self.set_view(slider_value, offset)
+    }
+
+    // 缩放到指定的缩放条值，保持视图中心的内容不动
+    fn zoom_view(&mut self, slider_value: u8) -> Task<Message> {
+        let offset = self.pan_offset * (slider_value as f32 / self.slider_value as f32);
+        self.set_view(slider_value, offset)
+    }
+
+    // 按缩放条值和平移量更新视图：平移不超过放大后图片的边缘，先用最快的算法预览，停顿后高质量渲染
+    fn set_view(&mut self, slider_value: u8, offset: Vector) -> Task<Message> {
+        if self.original.is_none() {
+            return Task::none();
+        }
+        let Some((width, height)) = self.display_source().map(|img| img.dimensions()) else {
+            return Task::none();
+        };
+        let scale = slider_value as f32 / 50.0;
+        let (max_x, max_y) = (
+            width as f32 * (scale - 1.0) / 2.0,
+            height as f32 * (scale - 1.0) / 2.0,
+        );
+        let offset = Vector::new(offset.x.clamp(-max_x, max_x), offset.y.clamp(-max_y, max_y));
+        if slider_value == self.slider_value && offset == self.pan_offset {
+            return Task::none();
+        }
+
+        self.slider_value = slider_value;
+        self.pan_offset = offset;
+        self.is_resampling_mode = true;
+        self.is_dragging = true;
+        // 先在后台用最快的算法预览，之前还没完成的缩放直接放弃
+        self.cancel_resize();
+        let preview = self.spawn_resize(ResamplingType::Point, false);
+        let generation = self.resize_generation;
+        Task::batch([
+            self.ensure_full_resolution(),
+            preview,
+            Task::perform(tokio::time::sleep(FINALIZE_DELAY), move |_| {
+                Message::ViewSettled(generation)
+            }),
+        ])
+    }
+
+    // 按重新读取的文件夹内容更新图片库：去掉已删除的图片，加入新图片，修改过的图片重新生成缩略图
+    // 显示搜索结果或筛选时新图片加入原来的图片库，关闭后才显示
+    fn reconcile_collection(
+        &mut self,
+        images: Vec<PathBuf>,
+        modified: &[PathBuf],
+    ) -> Task<Message> {
+        let present: std::collections::HashSet<&PathBuf> = images.iter().collect();
+        let base = if self.results_shown() {
+            &mut self.find_backup
+        } else if self.active_search.is_some() {
+            &mut self.unfiltered_collection
+        } else {
+            &mut self.image_collection
+        };
+        let removed: Vec<PathBuf> = base
+            .iter()
+            .filter(|path| !present.contains(path))
+            .cloned()
+            .collect();
+        let added: Vec<PathBuf> = images
+            .iter()
+            .filter(|path| !base.contains(path))
+            .cloned()
+            .collect();
+        base.extend(added);
+        self.sort.sort(base);
+        for path in &removed {
+            self.image_collection.retain(|p| p != path);
+            self.unfiltered_collection.retain(|p| p != path);
+            self.find_backup.retain(|p| p != path);
+            self.selected_images.retain(|p| p != path);
+        }
+        for path in removed.iter().chain(modified) {
+            self.thumbnail_cache.remove(path);
+            self.hover_previews.remove(path);
+            self.metadata_cache.remove(path);
+            self.file_info.remove(path);
+        }
+        let current_removed = self
+            .current_image
+            .as_ref()
+            .is_some_and(|current| removed.contains(current));
+        let shown = if current_removed {
+            self.history.clear();
+            self.show_next_after_removal()
+        } else {
+            self.current_image_index = self
+                .current_image
+                .as_ref()
+                .and_then(|current| self.image_collection.iter().position(|p| p == current))
+                .unwrap_or(0);
+            Task::none()
+        };
+        Task::batch([shown, self.load_missing_thumbnails()])
+    }
+
+    // 当前图片被删除或移走后，显示原来位置上的下一张图片
+    fn show_next_after_removal(&mut self) -> Task<Message> {
+        if self.image_collection.is_empty() {
+            self.current_image = None;
+            self.original = None;
+            self.edited = None;
+            self.adjusted = None;
+            self.display_handle = None;
+            self.channel_view = None;
+            self.full_size = None;
+            self.load_error = None;
+            return Task::none();
+        }
+        self.current_image_index = self
+            .current_image_index
+            .min(self.image_collection.len() - 1);
+        let next = self.image_collection[self.current_image_index].clone();
+        self.current_image = Some(next.clone());
+        self.update(Message::LoadImage(next))
+    }
+
+    // 确认后删除图片文件
+    fn delete_images(&mut self, paths: Vec<PathBuf>) -> Task<Message> {
+        let answer = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title(tr("Delete images"))
+            .set_description(trf(
+                "Delete {} images from disk? This cannot be undone.",
+                &[&paths.len()],
+            ))
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        if !matches!(answer, MessageDialogResult::Yes) {
+            return Task::none();
+        }
+        let mut failed = Vec::new();
+        for path in &paths {
+            match delete_file(path) {
+                Ok(()) => self.forget_path(path),
+                Err(e) => {
+                    warn!("Failed to delete {}: {}", path.display(), e);
+                    failed.push(file_name(path));
+                }
+            }
+        }
+        let toast = if failed.is_empty() {
+            self.show_toast(
+                ToastKind::Success,
+                trf("Deleted {} images", &[&paths.len()]),
+            )
+        } else {
+            self.show_toast(
+                ToastKind::Error,
+                trf("Failed to delete {}", &[&failed.join(", ")]),
+            )
+        };
+        self.finish_removal(toast)
+    }
+
+    // 选择文件夹后在后台移动图片和侧车文件
+    fn move_images(&mut self, paths: Vec<PathBuf>) -> Task<Message> {
+        let Some(folder) = FileDialog::new().pick_folder() else {
+            return Task::none();
+        };
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    paths
+                        .into_iter()
+                        .map(|path| {
+                            let moved = move_file(&path, &folder);
+                            (path, moved)
+                        })
+                        .collect()
+                })
+                .await
+                .unwrap_or_default()
+            },
+            Message::SelectionMoved,
+        )
+    }
+
+    // 图片被删除或移走之后：临时结果已经全部移走时恢复原来的图片库，当前图片不在了时显示下一张
+    fn finish_removal(&mut self, toast: Task<Message>) -> Task<Message> {
+        let mut tasks = vec![toast];
+        if self.results_shown() && self.image_collection.is_empty() {
+            tasks.push(self.update(Message::ClearFindResults));
+        }
+        let current_removed = self
+            .current_image
+            .as_ref()
+            .is_some_and(|current| !self.image_collection.contains(current));
+        if current_removed {
+            self.history.clear();
+            tasks.push(self.show_next_after_removal());
+        }
+        Task::batch(tasks)
+    }
+
+    // 文件被删除后，从图片库、缩略图缓存、选择和浏览记录中移除
+    fn forget_path(&mut self, path: &Path) {
+        self.image_collection.retain(|p| p != path);
+        self.unfiltered_collection.retain(|p| p != path);
+        self.find_backup.retain(|p| p != path);
+        self.selected_images.retain(|p| p != path);
+        self.thumbnail_cache.remove(path);
+        self.hover_previews.remove(path);
+        let _ = self.recent_manager.delete_item(&path.to_path_buf());
+        self.save_recents();
+        self.refresh_smart_folders();
+    }
+
+    // 文件被重命名后，更新图片库、缩略图缓存和选择中的路径
+    fn replace_path(&mut self, old: &Path, new: PathBuf) {
+        for path in self
+            .image_collection
+            .iter_mut()
+            .chain(self.unfiltered_collection.iter_mut())
+            .chain(self.selected_images.iter_mut())
+        {
+            if path == old {
+                *path = new.clone();
+            }
+        }
+        if let Some(handle) = self.thumbnail_cache.remove(old) {
+            self.thumbnail_cache.insert(new.clone(), handle);
+        }
+        if let Some(handle) = self.hover_previews.remove(old) {
+            self.hover_previews.insert(new.clone(), handle);
+        }
+        if let Some(info) = self.file_info.remove(old) {
+            self.file_info.insert(new.clone(), info);
+        }
+        self.metadata_cache.rename(old, new.clone());
+        if self.current_image.as_deref() == Some(old) {
+            self.current_image = Some(new);
+        }
+    }
+
+    /// 联系表中的图片：多选时为选中的图片，否则为当前文件夹的全部图片
+    pub fn contact_sheet_sources(&self) -> Vec<PathBuf> {
+        let images = if self.selected_images.len() > 1 {
+            &self.selected_images
+        } else {
+            &self.image_collection
+        };
+        images
+            .iter()
+            .filter(|path| !cloud::is_remote(path))
+            .cloned()
+            .collect()
+    }
+
+    /// 闪烁比较的两张图片：选中的两张，或者当前图片和另一张选中的图片
+    pub fn blink_pair(&self) -> Option<[PathBuf; 2]> {
+        let pair = match self.selected_images.as_slice() {
+            [first, second] => [first.clone(), second.clone()],
+            [other] => [self.current_image.clone()?, other.clone()],
+            _ => return None,
+        };
+        (pair[0] != pair[1] && !pair.iter().any(|path| cloud::is_remote(path))).then_some(pair)
+    }
+
+    // 格式转换的目标：有多选时为选中的图片，否则为当前图片
+    pub fn convert_targets(&self) -> Vec<PathBuf> {
+        if !self.selected_images.is_empty() {
+            self.selected_images.clone()
+        } else {
+            self.current_image.iter().cloned().collect()
+        }
+    }
+
+    // 应用裁剪和旋转后、调整之前的图片
+    fn edited_source(&self) -> Option<&Arc<RgbImage>> {
+        self.edited.as_ref().or(self.original.as_ref())
+    }
+
+    // 缩放、平移、裁剪和导出使用的图像：有调整时为调整后的图片
+    pub fn display_source(&self) -> Option<&Arc<RgbImage>> {
+        self.adjusted.as_ref().or(self.edited_source())
+    }
+
+    // 画面上实际显示的图像：只显示一个通道时为对应的灰度图
+    fn shown_source(&self) -> Option<&Arc<RgbImage>> {
+        self.channel_view.as_ref().or(self.display_source())
+    }
+
+    // 显示的图像变化后重新生成通道视图和显示用的图像
+    fn refresh_display(&mut self) {
+        self.channel_view = self.isolate_channel().map(Arc::new);
+        self.display_handle = self.build_display_handle();
+    }
+
+    // 透明通道在后台从文件中读取，再重放裁剪和旋转，与显示的图像对齐
+    fn isolate_channel(&self) -> Option<RgbImage> {
+        let source = self.display_source()?;
+        if self.channel != Channel::Alpha {
+            return channels::isolate(source, self.channel);
+        }
+        // 透明通道还没有解码完成时先显示原图
+        let alpha = self.alpha.as_deref()?;
+        let alpha = self
+            .history
+            .apply_geometry(alpha)
+            .unwrap_or_else(|| alpha.clone());
+        let (width, height) = source.dimensions();
+        if alpha.dimensions() == (width, height) {
+            Some(alpha)
+        } else {
+            // 按缩小的尺寸解码的图片
+            Some(image::imageops::resize(
+                &alpha,
+                width,
+                height,
+                image::imageops::FilterType::Triangle,
+            ))
+        }
+    }
+
+    // 在后台解码当前图片的透明通道，只在显示透明通道时需要
+    fn load_alpha(&mut self) -> Task<Message> {
+        if self.channel != Channel::Alpha || self.alpha.is_some() || self.alpha_pending {
+            return Task::none();
+        }
+        let Some(path) = self.current_image.clone() else {
+            return Task::none();
+        };
+        self.alpha_pending = true;
+        let raw_orientation = self.show_raw_orientation;
+        Task::perform(
+            async move {
+                let alpha = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || channels::alpha(&path, !raw_orientation)
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r);
+                Message::AlphaDecoded(path, raw_orientation, alpha)
+            },
+            |msg| msg,
+        )
+    }
+
+    // 撤销/重做或新的裁剪、旋转后，从原图重放几何编辑并重新计算调整
+    fn rebuild_edited(&mut self) -> Task<Message> {
+        self.edited = match &self.original {
+            Some(ori) => self.history.apply_geometry(ori).map(Arc::new),
+            None => None,
+        };
+        self.crop_mode = false;
+        self.straighten_mode = false;
+        self.crop_selection = None;
+        self.code_scan = None; // 位置已不再对应
+        self.color_picking = false;
+        self.overlay_handle = None;
+        self.pan_offset = Vector::new(0.0, 0.0); // 尺寸可能变化，重置平移
+        self.adjusted = None;
+        self.refresh_display();
+        self.rerender_scaled();
+        if self.adjustments.is_identity() {
+            Task::none()
+        } else {
+            self.start_adjusting()
+        }
+    }
+
+    // 显示原始方向或有调整时无法使用 Handle::from_path（iced 会自动旋转且读取原文件），改用解码后的像素
+    fn build_display_handle(&self) -> Option<Handle> {
+        if let Some(channel) = &self.channel_view {
+            return Some(rgb_handle(channel));
+        }
+        let raw_orientation =
+            self.show_raw_orientation && self.orientation != Orientation::NoTransforms;
+        // 缩小解码的图片以及纹理和 PSD 也直接使用解码后的像素，避免 iced 再按原尺寸读取或无法解码文件
+        let decoded_by_app = self
+            .current_image
+            .as_deref()
+            .is_some_and(formats::decoded_by_app);
+        if !raw_orientation
+            && !decoded_by_app
+            && self.animation.is_none()
+            && self.adjusted.is_none()
+            && self.edited.is_none()
+            && self.full_size.is_none()
+        {
+            return None;
+        }
+        self.display_source().map(|img| rgb_handle(img))
+    }
+
+    // 缩放或手型工具模式下，按当前的显示图像重新生成高质量画面
+    fn rerender_scaled(&mut self) {
+        self.render_blink();
+        if !(self.is_resampling_mode || self.hand_tool_active) {
+            return;
+        }
+        let scale = self.slider_value as f32 / 50.0;
+        if let Some(ori) = self.shown_source() {
+            match crop_and_scale(ori, scale, self.pan_offset, self.resampling_type) {
+                Ok(bytes) => {
+                    self.scaled_bytes = bytes.clone();
+                    self.final_scaled_bytes = bytes;
+                }
+                Err(e) => error!("Failed to scale image: {}", e),
+            }
+        }
+    }
+
+    // 按当前的缩放和平移重新生成闪烁比较的画面
+    fn render_blink(&mut self) {
+        let (slider_value, offset) = (self.slider_value, self.pan_offset);
+        if let Some(blink) = &mut self.blink
+            && let Some(images) = &blink.images
+        {
+            blink.frames = Some(blink::render(
+                images,
+                slider_value,
+                offset,
+                self.resampling_type,
+            ));
+        }
+    }
+
+    // 在后台按当前参数计算调整后的图片，同一时间只运行一个计算
+    fn start_adjusting(&mut self) -> Task<Message> {
+        let Some(ori) = self.edited_source().cloned() else {
+            return Task::none();
+        };
+        if self.adjusting {
+            return Task::none();
+        }
+        self.adjusting = true;
+        let adjustments = self.adjustments;
+        let revision = self.history.geometry_revision();
+        // 模糊和锐化在大图上比较耗时，放到阻塞线程池中计算
+        Task::perform(
+            async move {
+                let adjusted = tokio::task::spawn_blocking(move || {
+                    (!adjustments.is_identity())
+                        .then(|| perf::time(Stage::Adjust, || adjustments.apply(&ori)))
+                })
+                .await
+                .unwrap_or_default();
+                Message::AdjustmentsApplied(adjustments, revision, adjusted)
+            },
+            |msg| msg,
+        )
+    }
+
+    pub fn original_dimensions(&self) -> (u32, u32) {
+        self.edited_source()
+            .map(|ori| ori.dimensions())
+            .unwrap_or((1, 1))
+    }
+
+    // 有裁剪、旋转或调整尚未保存
+    pub fn is_modified(&self) -> bool {
+        self.edited.is_some() || !self.adjustments.is_identity()
+    }
+
+    // 按当前编辑重新计算完整结果，不依赖可能还在后台计算中的 adjusted，没有图片时返回 None；
+    // 原尺寸还没有在后台解码完成时直接解码原图，不能用缩小的图片保存
+    fn render_edits(&self) -> Result<Option<image::RgbImage>, String> {
+        let full;
+        let img = match (&self.current_image, self.full_size) {
+            (Some(path), Some(_)) => {
+                let (img, _) =
+                    decode_image(path, !self.show_raw_orientation).map_err(|e| e.to_string())?;
+                let img = img.to_rgb8();
+                full = self.history.apply_geometry(&img).unwrap_or(img);
+                &full
+            }
+            _ => match self.edited_source() {
+                Some(img) => img,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(if self.adjustments.is_identity() {
+            RgbImage::clone(img)
+        } else {
+            self.adjustments.apply(img)
+        }))
+    }
+
+    // 在后台保存编辑结果，成功后发送 on_saved(dst)
+    fn save_edits(&self, dst: PathBuf, on_saved: fn(PathBuf) -> Message) -> Task<Message> {
+        let Some(img) = self.edited_source().cloned() else {
+            return Task::none();
+        };
+        let adjustments = self.adjustments;
+        Task::perform(
+            async move {
+                let target = dst.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    if adjustments.is_identity() {
+                        export::save_image(&img, &target)
+                    } else {
+                        export::save_image(&adjustments.apply(&img), &target)
+                    }
+                    .map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r);
+                match result {
+                    Ok(()) => on_saved(dst),
+                    Err(e) => Message::SaveFailed(dst, e),
+                }
+            },
+            |msg| msg,
+        )
+    }
+
+    // 离开有未保存修改的图片前询问是否保存，返回 Err 表示取消切换（保存失败时带上错误提示）
+    fn confirm_leave(&mut self) -> Result<Task<Message>, Task<Message>> {
+        let Some(path) = self.current_image.clone() else {
+            return Ok(Task::none());
+        };
+        if !self.is_modified() {
+            return Ok(Task::none());
+        }
+        let answer = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title(tr("Unsaved changes"))
+            .set_description(trf("Save changes to {}?", &[&path.display()]))
+            .set_buttons(MessageButtons::YesNoCancel)
+            .show();
+        match answer {
+            MessageDialogResult::Yes => {
+                // 切换前同步保存，保证重新加载时读到的是新内容；保存失败时留在这张图片上
+                let saved = self.render_edits().and_then(|img| match img {
+                    Some(img) => export::save_image(&img, &path).map_err(|e| e.to_string()),
+                    None => Ok(()),
+                });
+                if let Err(e) = saved {
+                    return Err(self.update(Message::SaveFailed(path, e)));
+                }
+                self.thumbnail_cache.remove(&path);
+                self.hover_previews.remove(&path);
+                Ok(Task::done(Message::LoadThumbnail(path)))
+            }
+            MessageDialogResult::No => Ok(Task::none()),
+            _ => Err(Task::none()),
+        }
+    }
+
+    // 按配置选择主题，System 时跟随系统的深浅色
+    pub fn theme(&self, _window: window::Id) -> Theme {
+        let base = if self.is_dark() {
+            Theme::Dark
+        } else {
+            Theme::Light
+        };
+        // 强调色作为主题的 primary，iced 自带的控件也跟着使用
+        Theme::custom(
+            base.to_string(),
+            iced::theme::Palette {
+                primary: self.config.accent.color(),
+                ..base.palette()
+            },
+        )
+    }
+
+    // 当前主题下界面使用的颜色
+    pub fn palette(&self) -> Palette {
+        Palette::new(self.is_dark(), self.config.accent.color())
+    }
+
+    // 当前是否使用深色主题
+    pub fn is_dark(&self) -> bool {
+        match self.config.theme {
+            ThemeMode::Light => false,
+            ThemeMode::Dark => true,
+            ThemeMode::System => self.system_dark,
+        }
+    }
+
+    // 窗口标题，有未保存的修改时在文件名后显示 •
+    pub fn title(&self, window: window::Id) -> String {
+        // 查看窗口显示自己的图片和位置
+        if let Some(viewer) = self.viewers.get(&window) {
+            return match viewer.current() {
+                Some(path) => format!(
+                    "{} — Image Browser ({}/{})",
+                    file_name(path),
+                    viewer.index + 1,
+                    viewer.images.len()
+                ),
+                None => match &viewer.folder {
+                    Some(folder) => format!("{} — Image Browser", file_name(folder)),
+                    None => "Image Browser".to_string(),
+                },
+            };
+        }
+        let Some(path) = &self.current_image else {
+            return "Image Browser".to_string();
+        };
+        let name = format!(
+            "{}{}",
+            file_name(path),
+            if self.is_modified() { " •" } else { "" }
+        );
+        // 图片不在当前图片库中时（例如另存后）不显示位置
+        let index = if self.image_collection.get(self.current_image_index) == Some(path) {
+            Some(self.current_image_index)
+        } else {
+            self.image_collection.iter().position(|p| p == path)
+        };
+        match index {
+            Some(index) => format!(
+                "{name} — Image Browser ({}/{})",
+                index + 1,
+                self.image_collection.len()
+            ),
+            None => format!("{name} — Image Browser"),
+        }
+    }
+
+    // 在后台保存裁剪结果，成功后发送 ImageSaved
+    fn save_crop(&self, dst: PathBuf) -> Task<Message> {
+        let (Some(ori), Some(selection)) = (self.display_source().cloned(), self.crop_selection)
+        else {
+            return Task::none();
+        };
+        Task::perform(
+            async move {
+                // 裁剪、编码和写入都比较耗时，放到阻塞线程池中执行
+                let target = dst.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    crop::save_cropped(&ori, selection, &target).map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r);
+                match result {
+                    Ok(()) => Message::ImageSaved(dst),
+                    Err(e) => Message::SaveFailed(dst, e),
+                }
+            },
+            |msg| msg,
+        )
+    }
+
+    // 导出和上传时使用的元数据处理方式
+    fn metadata_policy(&self) -> MetadataPolicy {
+        if self.strip_metadata {
+            MetadataPolicy::StripAll
+        } else if self.metadata_edits.is_empty() {
+            MetadataPolicy::Keep
+        } else {
+            MetadataPolicy::Edit(self.metadata_edits.clone())
+        }
+    }
+}
+
+// 把文件移动到 folder 中，跨磁盘时复制后删除原文件；
+// 侧车文件一起移动，与同名的其他文件共用的侧车文件复制一份，原处的保留
+fn move_file(path: &Path, folder: &Path) -> Result<PathBuf, String> {
+    let name = path.file_name().ok_or("Invalid file name")?;
+    let target = folder.join(name);
+    if target.exists() {
+        return Err(trf("{} already exists", &[&target.display()]));
+    }
+    // 移动前确定侧车文件，避免图片移走后留下无主的标记
+    let sidecar = xmp_iptc::find_sidecar(path).and_then(|sidecar| {
+        let sidecar_target = folder.join(sidecar.file_name()?);
+        let owned = xmp_iptc::own_sidecar(path).as_ref() == Some(&sidecar);
+        Some((sidecar, sidecar_target, owned))
+    });
+    if let Some((_, sidecar_target, _)) = &sidecar
+        && sidecar_target.exists()
+    {
+        return Err(trf("{} already exists", &[&sidecar_target.display()]));
+    }
+    rename_or_copy(path, &target).map_err(|e| e.to_string())?;
+    if let Some((sidecar, sidecar_target, owned)) = sidecar {
+        let moved = if owned {
+            rename_or_copy(&sidecar, &sidecar_target)
+        } else {
+            std::fs::copy(&sidecar, &sidecar_target).map(|_| ())
+        };
+        if let Err(e) = moved {
+            warn!("Failed to move {}: {}", sidecar.display(), e);
+        }
+    }
+    Ok(target)
+}
+
+// 在同一个文件夹中改名，只属于这张图片的侧车文件一起改名，与同名的其他文件共用的复制一份
+fn rename_file(path: &Path, target: &Path) -> Result<(), String> {
+    if target.exists() {
+        return Err(trf("{} already exists", &[&file_name(target)]));
+    }
+    // photo.jpg.xmp 改为 new.jpg.xmp，photo.xmp 改为 new.xmp
+    let sidecar = xmp_iptc::find_sidecar(path).map(|sidecar| {
+        let full = sidecar.file_name() == Some(format!("{}.xmp", file_name(path)).as_ref());
+        let sidecar_target = if full {
+            target.with_file_name(format!("{}.xmp", file_name(target)))
+        } else {
+            target.with_extension(sidecar.extension().unwrap_or_default())
+        };
+        let owned = xmp_iptc::own_sidecar(path).as_ref() == Some(&sidecar);
+        (sidecar, sidecar_target, owned)
+    });
+    if let Some((_, sidecar_target, _)) = &sidecar
+        && sidecar_target.exists()
+    {
+        return Err(trf("{} already exists", &[&file_name(sidecar_target)]));
+    }
+    std::fs::rename(path, target).map_err(|e| e.to_string())?;
+    if let Some((sidecar, sidecar_target, owned)) = sidecar {
+        let renamed = if owned {
+            std::fs::rename(&sidecar, &sidecar_target)
+        } else {
+            std::fs::copy(&sidecar, &sidecar_target).map(|_| ())
+        };
+        if let Err(e) = renamed {
+            warn!("Failed to rename {}: {}", sidecar.display(), e);
+        }
+    }
+    Ok(())
+}
+
+// 文件名或文件夹名是否可用：不能为空、. 或 ..，也不能包含路径分隔符
+fn is_valid_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && name != "." && name != ".." && !name.contains(std::path::is_separator)
+}
+
+// 重命名，跨磁盘时复制后删除原文件
+fn rename_or_copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+// 删除图片文件，只属于这张图片的侧车文件一起删除，与同名的其他文件共用的保留
+fn delete_file(path: &Path) -> std::io::Result<()> {
+    let sidecar = xmp_iptc::own_sidecar(path);
+    std::fs::remove_file(path)?;
+    if let Some(sidecar) = sidecar
+        && let Err(e) = std::fs::remove_file(&sidecar)
+    {
+        warn!("Failed to delete {}: {}", sidecar.display(), e);
+    }
+    Ok(())
+}
+
+// 在后台按显示的尺寸解码图片，raw_orientation 为 true 时忽略EXIF方向
+fn decode_in_background(path: PathBuf, raw_orientation: bool) -> Task<Message> {
+    Task::perform(
+        async move {
+            let decoded = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || decode_for_display(&path, !raw_orientation).map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+            Message::ImageDecoded(path, raw_orientation, decoded)
+        },
+        |msg| msg,
+    )
+}
+
+// 在后台读取侧车文件，找出 images 中标记为 flag 的本地图片
+async fn find_flagged(images: Vec<PathBuf>, flag: Flag) -> Vec<PathBuf> {
+    tokio::task::spawn_blocking(move || {
+        images
+            .into_iter()
+            .filter(|path| !cloud::is_remote(path))
+            .filter(|path| xmp_iptc::read_sidecar_flag(path) == Some(flag))
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+// 在后台读取图片的EXIF信息，TIFF 和 PSD 等格式可能需要扫描整个文件
+fn load_exif_info(path: PathBuf) -> Task<Message> {
+    Task::perform(
+        async move {
+            let info = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || exif_info::read_exif(&path)
+            })
+            .await
+            .ok()
+            .flatten();
+            Message::ExifInfoLoaded(path, info)
+        },
+        |msg| msg,
+    )
+}
+
+// 在后台读取图片的 XMP/IPTC 信息
+fn load_descriptive_metadata(path: PathBuf) -> Task<Message> {
+    Task::perform(
+        async move {
+            let metadata = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || xmp_iptc::read_descriptive_metadata(&path)
+            })
+            .await
+            .ok()
+            .flatten();
+            Message::DescriptiveMetadataLoaded(path, metadata)
+        },
+        |msg| msg,
+    )
+}
+
+// 在后台向插件查询图片的附加信息，插件的调用可能很慢
+fn load_plugin_metadata(path: PathBuf) -> Task<Message> {
+    Task::perform(
+        async move {
+            let metadata = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || plugins::metadata(&path)
+            })
+            .await
+            .unwrap_or_default();
+            Message::PluginMetadataLoaded(path, metadata)
+        },
+        |msg| msg,
+    )
+}
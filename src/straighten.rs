@@ -0,0 +1,161 @@
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Stroke};
+use iced::widget::image::Handle;
+use iced::{Color, Point, Radians, Rectangle, Renderer, Size, Theme};
+use image::{Rgb, RgbImage};
+
+use crate::messages::Message;
+
+// 拉直角度的范围（度）
+pub const MAX_ANGLE: f32 = 45.0;
+
+/// 旋转 w x h 的图片后，完全落在图片内部的最大矩形的尺寸
+pub fn inner_size(width: f32, height: f32, degrees: f32) -> (f32, f32) {
+    let radians = degrees.to_radians();
+    let (sin, cos) = (radians.sin().abs(), radians.cos().abs());
+    let (long, short) = if width >= height {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    // 较短的边先碰到边界时，矩形的两个角落在长边上
+    if short <= 2.0 * sin * cos * long || (sin - cos).abs() < 1e-6 {
+        let x = 0.5 * short;
+        if width >= height {
+            (x / sin, x / cos)
+        } else {
+            (x / cos, x / sin)
+        }
+    } else {
+        let cos_2a = cos * cos - sin * sin;
+        (
+            (width * cos - height * sin) / cos_2a,
+            (height * cos - width * sin) / cos_2a,
+        )
+    }
+}
+
+/// 按角度（顺时针为正）旋转图片，并裁掉旋转后产生的空白边
+pub fn straighten(img: &RgbImage, degrees: f32) -> RgbImage {
+    let (width, height) = (img.width() as f32, img.height() as f32);
+    let (inner_w, inner_h) = inner_size(width, height, degrees);
+    let out_w = (inner_w.floor() as u32).clamp(1, img.width());
+    let out_h = (inner_h.floor() as u32).clamp(1, img.height());
+    let (sin, cos) = degrees.to_radians().sin_cos();
+
+    RgbImage::from_fn(out_w, out_h, |x, y| {
+        // 输出像素相对中心的位置反向旋转，得到原图中的采样点
+        let dx = x as f32 + 0.5 - out_w as f32 / 2.0;
+        let dy = y as f32 + 0.5 - out_h as f32 / 2.0;
+        let sx = cos * dx + sin * dy + width / 2.0 - 0.5;
+        let sy = -sin * dx + cos * dy + height / 2.0 - 0.5;
+        sample_bilinear(img, sx, sy)
+    })
+}
+
+// 双线性插值，超出边界的坐标取最近的边缘像素
+fn sample_bilinear(img: &RgbImage, x: f32, y: f32) -> Rgb<u8> {
+    let max_x = (img.width() - 1) as f32;
+    let max_y = (img.height() - 1) as f32;
+    let (x, y) = (x.clamp(0.0, max_x), y.clamp(0.0, max_y));
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as u32, y0 as u32);
+    let x1 = (x0 + 1).min(img.width() - 1);
+    let y1 = (y0 + 1).min(img.height() - 1);
+
+    let (p00, p10) = (img.get_pixel(x0, y0), img.get_pixel(x1, y0));
+    let (p01, p11) = (img.get_pixel(x0, y1), img.get_pixel(x1, y1));
+    Rgb(std::array::from_fn(|c| {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        (top * (1.0 - fy) + bottom * fy).round() as u8
+    }))
+}
+
+/// 拉直模式下预览旋转后的图片，叠加水平参考网格并标出自动裁剪的范围
+pub struct StraightenOverlay<'a> {
+    pub handle: &'a Handle,
+    pub image_size: (u32, u32),
+    pub angle: f32,
+}
+
+impl canvas::Program<Message> for StraightenOverlay<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (w, h) = (self.image_size.0 as f32, self.image_size.1 as f32);
+        let scale = (bounds.width / w).min(bounds.height / h);
+        let size = Size::new(w * scale, h * scale);
+        let center = frame.center();
+        let fit = Rectangle::new(
+            Point::new(center.x - size.width / 2.0, center.y - size.height / 2.0),
+            size,
+        );
+        frame.draw_image(
+            fit,
+            canvas::Image::new(self.handle.clone()).rotation(Radians(self.angle.to_radians())),
+        );
+
+        // 自动裁剪后保留的区域，其余部分变暗
+        let (inner_w, inner_h) = inner_size(size.width, size.height, self.angle);
+        let inner = Rectangle::new(
+            Point::new(center.x - inner_w / 2.0, center.y - inner_h / 2.0),
+            Size::new(inner_w, inner_h),
+        );
+        let shade = Color::from_rgba8(0, 0, 0, 0.5);
+        frame.fill_rectangle(Point::ORIGIN, Size::new(bounds.width, inner.y), shade);
+        frame.fill_rectangle(
+            Point::new(0.0, inner.y + inner.height),
+            Size::new(bounds.width, bounds.height - inner.y - inner.height),
+            shade,
+        );
+        frame.fill_rectangle(
+            Point::new(0.0, inner.y),
+            Size::new(inner.x, inner.height),
+            shade,
+        );
+        frame.fill_rectangle(
+            Point::new(inner.x + inner.width, inner.y),
+            Size::new(bounds.width - inner.x - inner.width, inner.height),
+            shade,
+        );
+
+        // 水平/垂直参考网格，用来对齐地平线和建筑边缘
+        let grid = Stroke::default()
+            .with_width(1.0)
+            .with_color(Color::from_rgba8(255, 255, 255, 0.4));
+        let spacing = 40.0;
+        let mut x = fit.x + spacing;
+        while x < fit.x + fit.width {
+            frame.stroke(
+                &canvas::Path::line(Point::new(x, fit.y), Point::new(x, fit.y + fit.height)),
+                grid,
+            );
+            x += spacing;
+        }
+        let mut y = fit.y + spacing;
+        while y < fit.y + fit.height {
+            frame.stroke(
+                &canvas::Path::line(Point::new(fit.x, y), Point::new(fit.x + fit.width, y)),
+                grid,
+            );
+            y += spacing;
+        }
+
+        frame.stroke(
+            &canvas::Path::rectangle(inner.position(), inner.size()),
+            Stroke::default().with_width(2.0).with_color(Color::WHITE),
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
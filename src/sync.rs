@@ -0,0 +1,449 @@
+use iced::futures::{SinkExt, Stream};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tokio::io::AsyncWriteExt;
+
+use crate::cloud;
+use crate::cloud_storage::{self, CloudConfig, CloudStorage, RemoteObject, Storage};
+use crate::config;
+use crate::i18n::{tr, trf};
+use crate::upload_manifest;
+
+// 下载时每次请求的字节数
+const CHUNK_SIZE: u64 = 512 * 1024;
+
+/// 两边都修改过同一个文件时保留哪一边
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    #[default]
+    Newer,
+    Local,
+    Remote,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 3] = [
+        ConflictPolicy::Newer,
+        ConflictPolicy::Local,
+        ConflictPolicy::Remote,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ConflictPolicy::Newer => tr("Keep newer"),
+            ConflictPolicy::Local => tr("Keep local"),
+            ConflictPolicy::Remote => tr("Keep remote"),
+        }
+    }
+}
+
+/// 定时同步的间隔（分钟），0 表示只手动同步
+pub const INTERVALS: [u64; 4] = [0, 15, 60, 360];
+
+pub fn interval_name(minutes: u64) -> String {
+    match minutes {
+        0 => tr("Manual").to_string(),
+        m if m % 60 == 0 => trf("Every {} h", &[&(m / 60)]),
+        m => trf("Every {} min", &[&m]),
+    }
+}
+
+/// 一个本地文件夹和存储桶中前缀（以 / 结尾）的同步关系
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncFolder {
+    pub local: PathBuf,
+    pub prefix: String,
+}
+
+impl SyncFolder {
+    /// 默认同步到存储桶中与文件夹同名的前缀
+    pub fn new(local: PathBuf) -> Self {
+        let name = local
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let prefix = if name.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", name)
+        };
+        SyncFolder { local, prefix }
+    }
+}
+
+// 上次同步完成时两边都存在的文件，用来判断之后哪一边发生了变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ManifestEntry {
+    size: u64,
+    mtime: i64, // 本地文件的修改时间（Unix 秒）
+    etag: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    files: BTreeMap<String, ManifestEntry>, // 键为相对路径（用 / 分隔）
+}
+
+// 本地文件的大小和修改时间
+#[derive(Debug, Clone, Copy)]
+struct LocalFile {
+    size: u64,
+    mtime: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Upload,
+    Download,
+    DeleteLocal,
+    DeleteRemote,
+}
+
+/// 同步过程中发送的事件
+#[derive(Debug, Clone)]
+pub enum SyncEvent {
+    Progress(usize, usize), // 已处理的文件数、需要处理的文件数
+    Finished(SyncSummary),
+}
+
+/// 一次同步的结果
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub deleted: usize,
+    pub failures: Vec<(String, String)>, // 失败的文件（或文件夹）及原因
+}
+
+impl SyncSummary {
+    pub fn describe(&self) -> String {
+        let mut text = trf(
+            "Sync finished: {} uploaded, {} downloaded, {} deleted",
+            &[&self.uploaded, &self.downloaded, &self.deleted],
+        );
+        if !self.failures.is_empty() {
+            text.push_str(&trf(", {} failed", &[&self.failures.len()]));
+        }
+        text
+    }
+}
+
+/// 依次同步每个文件夹，单个文件失败时继续处理其余文件
+pub fn run(
+    folders: Vec<SyncFolder>,
+    policy: ConflictPolicy,
+    config: CloudConfig,
+) -> impl Stream<Item = SyncEvent> + use<> {
+    iced::stream::channel(4, move |mut output| async move {
+        let mut summary = SyncSummary::default();
+        match cloud_storage::connect(&config) {
+            Ok(storage) => {
+                for folder in &folders {
+                    if let Err(e) =
+                        sync_folder(&storage, folder, policy, &mut summary, &mut output).await
+                    {
+                        summary
+                            .failures
+                            .push((folder.local.display().to_string(), e));
+                    }
+                }
+            }
+            Err(e) => summary.failures.push((String::new(), e)),
+        }
+        let _ = output.send(SyncEvent::Finished(summary)).await;
+    })
+}
+
+async fn sync_folder(
+    storage: &Storage,
+    folder: &SyncFolder,
+    policy: ConflictPolicy,
+    summary: &mut SyncSummary,
+    output: &mut iced::futures::channel::mpsc::Sender<SyncEvent>,
+) -> Result<(), String> {
+    let manifest_path = manifest_path(folder).ok_or("No data directory")?;
+    let manifest = load_manifest(&manifest_path);
+    let local = scan_local(&folder.local)?;
+    let remote = list_remote(storage, &folder.prefix).await?;
+
+    // 对比上次同步后的状态，决定每个文件要做什么
+    let mut actions = Vec::new();
+    let names: BTreeSet<&String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(manifest.files.keys())
+        .collect();
+    for name in names {
+        let base = manifest.files.get(name);
+        let (file, object) = (local.get(name), remote.get(name));
+        // 第一次同步时两边都有的文件，内容的 MD5 与 ETag 相同才认为是同一个文件
+        let identical = match (base, file, object) {
+            (None, Some(file), Some(object)) if file.size == object.size => {
+                upload_manifest::file_md5(&local_path(&folder.local, name))
+                    .await
+                    .is_ok_and(|md5| md5.eq_ignore_ascii_case(&object.etag))
+            }
+            _ => false,
+        };
+        if let Some(action) = plan(file, object, base, identical, policy) {
+            actions.push((name.clone(), action));
+        }
+    }
+
+    let total = actions.len();
+    let mut failed = BTreeSet::new();
+    for (done, (name, action)) in actions.iter().enumerate() {
+        let _ = output.send(SyncEvent::Progress(done, total)).await;
+        let path = local_path(&folder.local, name);
+        let key = format!("{}{}", folder.prefix, name);
+        let result = match action {
+            Action::Upload => storage.upload_object(path, key).await,
+            Action::Download => {
+                let size = remote.get(name).map_or(0, |object| object.size);
+                download(storage, key, &path, size).await
+            }
+            Action::DeleteLocal => fs::remove_file(&path).map_err(|e| e.to_string()),
+            Action::DeleteRemote => storage.delete_object(key).await,
+        };
+        match result {
+            Ok(()) => match action {
+                Action::Upload => summary.uploaded += 1,
+                Action::Download => summary.downloaded += 1,
+                Action::DeleteLocal | Action::DeleteRemote => summary.deleted += 1,
+            },
+            Err(e) => {
+                summary.failures.push((name.clone(), e));
+                failed.insert(name.clone());
+            }
+        }
+    }
+    let _ = output.send(SyncEvent::Progress(total, total)).await;
+
+    // 重新读取两边的状态写入清单；失败的文件保留原来的记录，下次再处理
+    let local = scan_local(&folder.local)?;
+    let remote = list_remote(storage, &folder.prefix).await?;
+    let mut files: BTreeMap<_, _> = manifest
+        .files
+        .into_iter()
+        .filter(|(name, _)| failed.contains(name))
+        .collect();
+    for (name, file) in local {
+        if failed.contains(&name) {
+            continue;
+        }
+        if let Some(object) = remote.get(&name) {
+            let entry = ManifestEntry {
+                size: file.size,
+                mtime: file.mtime,
+                etag: object.etag.clone(),
+            };
+            files.insert(name, entry);
+        }
+    }
+    save_manifest(&manifest_path, &Manifest { files })
+}
+
+// 返回这个文件需要的操作，两边都没有变化时返回 None；identical 表示两边的内容已确认相同
+fn plan(
+    local: Option<&LocalFile>,
+    remote: Option<&RemoteObject>,
+    base: Option<&ManifestEntry>,
+    identical: bool,
+    policy: ConflictPolicy,
+) -> Option<Action> {
+    let local_changed = |file: &LocalFile| {
+        base.is_none_or(|base| base.size != file.size || base.mtime != file.mtime)
+    };
+    let remote_changed = |object: &RemoteObject| base.is_none_or(|base| base.etag != object.etag);
+    match (local, remote) {
+        (Some(file), Some(object)) => match (local_changed(file), remote_changed(object)) {
+            (false, false) => None,
+            (true, false) => Some(Action::Upload),
+            (false, true) => Some(Action::Download),
+            (true, true) if identical => None,
+            (true, true) => Some(match policy {
+                ConflictPolicy::Local => Action::Upload,
+                ConflictPolicy::Remote => Action::Download,
+                ConflictPolicy::Newer if file.mtime >= object.modified => Action::Upload,
+                ConflictPolicy::Newer => Action::Download,
+            }),
+        },
+        // 只有一边有：另一边删除了没有修改过的文件就跟着删除，否则补上
+        (Some(file), None) if base.is_some() && !local_changed(file) => Some(Action::DeleteLocal),
+        (Some(_), None) => Some(Action::Upload),
+        (None, Some(object)) if base.is_some() && !remote_changed(object) => {
+            Some(Action::DeleteRemote)
+        }
+        (None, Some(_)) => Some(Action::Download),
+        (None, None) => None,
+    }
+}
+
+// 文件夹（包括子文件夹）中的图片，键为相对路径。
+// 任何文件夹读取失败都返回错误，否则其中的文件会被当作已在本地删除，进而删除远端的对象
+fn scan_local(root: &Path) -> Result<BTreeMap<String, LocalFile>, String> {
+    if !root.is_dir() {
+        return Err(format!("Folder not found: {}", root.display()));
+    }
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let unreadable = |e: std::io::Error| format!("{}: {}", dir.display(), e);
+        for entry in fs::read_dir(&dir).map_err(unreadable)? {
+            let entry = entry.map_err(unreadable)?;
+            let path = entry.path();
+            let meta = entry
+                .metadata()
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            if meta.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if !cloud::is_image(&name) {
+                continue;
+            }
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |time| time.as_secs() as i64);
+            files.insert(
+                name,
+                LocalFile {
+                    size: meta.len(),
+                    mtime,
+                },
+            );
+        }
+    }
+    Ok(files)
+}
+
+// 前缀（包括下级文件夹）中的图片，键为去掉前缀后的路径
+async fn list_remote(
+    storage: &Storage,
+    prefix: &str,
+) -> Result<BTreeMap<String, RemoteObject>, String> {
+    let mut objects = BTreeMap::new();
+    let mut folders = vec![prefix.to_string()];
+    while let Some(folder) = folders.pop() {
+        let listing = storage.list_objects(folder).await?;
+        folders.extend(listing.folders);
+        for object in listing.objects {
+            if let Some(name) = object.key.strip_prefix(prefix)
+                && cloud::is_image(name)
+            {
+                objects.insert(name.to_string(), object);
+            }
+        }
+    }
+    Ok(objects)
+}
+
+fn local_path(root: &Path, name: &str) -> PathBuf {
+    let mut path = root.to_path_buf();
+    path.extend(
+        name.split('/')
+            .filter(|part| !part.is_empty() && *part != ".."),
+    );
+    path
+}
+
+// 先下载到 .part 文件，完成后再替换本地文件
+async fn download(storage: &Storage, key: String, local: &Path, size: u64) -> Result<(), String> {
+    if let Some(parent) = local.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let mut name = local.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    let partial = local.with_file_name(name);
+    let mut file = tokio::fs::File::create(&partial)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut offset = 0;
+    while offset < size {
+        let end = (offset + CHUNK_SIZE).min(size) - 1;
+        let bytes = storage.download_range(key.clone(), offset, end).await?;
+        if bytes.is_empty() {
+            return Err("Empty response".to_string());
+        }
+        file.write_all(&bytes).await.map_err(|e| e.to_string())?;
+        offset += bytes.len() as u64;
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+    drop(file);
+    tokio::fs::rename(&partial, local)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 每个同步关系一个清单文件，文件名取本地路径和前缀的哈希
+fn manifest_path(folder: &SyncFolder) -> Option<PathBuf> {
+    let mut hasher = Sha256::new();
+    hasher.update(folder.local.to_string_lossy().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(folder.prefix.as_bytes());
+    let name = format!("{}.json", &hex::encode(hasher.finalize())[..16]);
+    config::data_dir().map(|dir| dir.join("sync").join(name))
+}
+
+fn load_manifest(path: &Path) -> Manifest {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_manifest(path: &Path, manifest: &Manifest) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_sync_compares_content_not_size() {
+        let file = LocalFile {
+            size: 10,
+            mtime: 200,
+        };
+        let object = RemoteObject {
+            key: "a/b.bmp".to_string(),
+            size: 10,
+            etag: "0123".to_string(),
+            modified: 100,
+        };
+        let plan_first = |identical| {
+            plan(
+                Some(&file),
+                Some(&object),
+                None,
+                identical,
+                ConflictPolicy::Newer,
+            )
+        };
+        assert_eq!(plan_first(true), None);
+        // 大小相同但内容不同时按冲突处理，本地较新
+        assert_eq!(plan_first(false), Some(Action::Upload));
+    }
+}
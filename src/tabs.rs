@@ -0,0 +1,39 @@
+use iced::Vector;
+use std::path::PathBuf;
+
+use crate::file_tree::file_name;
+use crate::scan::FolderStamp;
+
+/// 一个标签页打开的文件夹、图片库、当前图片和缩放状态
+/// 当前标签页的内容在 State 的字段中，切换到别的标签页时才存到这里
+#[derive(Debug, Clone)]
+pub struct Tab {
+    pub folder: PathBuf,
+    pub images: Vec<PathBuf>,
+    pub index: usize,
+    pub current: Option<PathBuf>,
+    pub zoom: u8,
+    pub pan: Vector,
+    pub zoomed: bool,                // 是否处于缩放模式
+    pub source: Option<FolderStamp>, // 图片库来自的本地文件夹
+}
+
+impl Tab {
+    pub fn new(folder: PathBuf) -> Tab {
+        Tab {
+            folder,
+            images: Vec::new(),
+            index: 0,
+            current: None,
+            zoom: 50,
+            pan: Vector::new(0.0, 0.0),
+            zoomed: false,
+            source: None,
+        }
+    }
+
+    /// 标签上显示的文件夹名称
+    pub fn title(&self) -> String {
+        file_name(&self.folder)
+    }
+}
@@ -0,0 +1,511 @@
+use image::{DynamicImage, RgbaImage};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::bcn::{self, BlockFormat};
+
+/// GPU 纹理文件的扩展名（小写）
+pub const TEXTURE_EXTENSIONS: [&str; 3] = ["dds", "ktx", "ktx2"];
+
+const KTX1_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// 纹理的格式和多级渐远纹理（mipmap）层数，显示在信息面板中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureInfo {
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub levels: usize, // 文件中完整的层数，至少为 1
+}
+
+impl TextureInfo {
+    /// 第 level 层的尺寸，每层宽高减半，最小为 1
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        let shift = level.min(31) as u32;
+        ((self.width >> shift).max(1), (self.height >> shift).max(1))
+    }
+}
+
+// 像素的存储方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PixelFormat {
+    Block(BlockFormat),
+    // 未压缩的像素：每像素的字节数和 R、G、B、A 的位掩码，掩码为 0 的颜色通道为 0、透明通道为不透明
+    Masked { bytes: usize, masks: [u32; 4] },
+}
+
+impl PixelFormat {
+    const RGBA8: PixelFormat = PixelFormat::Masked {
+        bytes: 4,
+        masks: [0xFF, 0xFF00, 0xFF0000, 0xFF000000],
+    };
+    const BGRA8: PixelFormat = PixelFormat::Masked {
+        bytes: 4,
+        masks: [0xFF0000, 0xFF00, 0xFF, 0xFF000000],
+    };
+    const BGRX8: PixelFormat = PixelFormat::Masked {
+        bytes: 4,
+        masks: [0xFF0000, 0xFF00, 0xFF, 0],
+    };
+    const RGB8: PixelFormat = PixelFormat::Masked {
+        bytes: 3,
+        masks: [0xFF, 0xFF00, 0xFF0000, 0],
+    };
+    const BGR8: PixelFormat = PixelFormat::Masked {
+        bytes: 3,
+        masks: [0xFF0000, 0xFF00, 0xFF, 0],
+    };
+    const RG8: PixelFormat = PixelFormat::Masked {
+        bytes: 2,
+        masks: [0xFF, 0xFF00, 0, 0],
+    };
+    // 单通道显示为灰度
+    const R8: PixelFormat = PixelFormat::Masked {
+        bytes: 1,
+        masks: [0xFF, 0xFF, 0xFF, 0],
+    };
+
+    // 一层纹理的字节数，未压缩的行按 row_align 字节对齐
+    fn level_size(self, width: u32, height: u32, row_align: usize) -> usize {
+        match self {
+            PixelFormat::Block(format) => format.level_size(width, height),
+            PixelFormat::Masked { bytes, .. } => {
+                (width as usize * bytes).next_multiple_of(row_align) * height as usize
+            }
+        }
+    }
+}
+
+// 解析文件头得到的格式和各层数据在文件中的位置
+struct Layout {
+    name: String,
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    row_align: usize,
+    levels: Vec<Range<usize>>, // 每层第一张图片（数组的第一层、立方体贴图的第一个面）
+}
+
+impl Layout {
+    fn info(&self) -> TextureInfo {
+        TextureInfo {
+            format: self.name.clone(),
+            width: self.width,
+            height: self.height,
+            levels: self.levels.len(),
+        }
+    }
+}
+
+/// 按扩展名判断是否是 DDS 或 KTX 纹理，不区分大小写
+pub fn is_texture(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEXTURE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 解码纹理的第 level 层，0 为原尺寸，同时返回纹理的格式、尺寸和层数
+pub fn decode(path: &Path, level: usize) -> Result<(DynamicImage, TextureInfo), String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let layout = parse(&data)?;
+    let range = layout
+        .levels
+        .get(level)
+        .ok_or_else(|| format!("Mip level {} does not exist", level))?;
+    let (width, height) = layout.info().level_size(level);
+    let bytes = &data[range.clone()];
+    let img = match layout.format {
+        PixelFormat::Block(format) => bcn::decode(format, bytes, width, height),
+        PixelFormat::Masked { bytes: size, masks } => {
+            decode_masked(bytes, width, height, size, masks, layout.row_align)
+        }
+    };
+    let img = img.ok_or_else(|| "Texture data is truncated".to_string())?;
+    Ok((DynamicImage::ImageRgba8(img), layout.info()))
+}
+
+fn parse(data: &[u8]) -> Result<Layout, String> {
+    if data.starts_with(b"DDS ") {
+        parse_dds(data)
+    } else if data.starts_with(&KTX1_IDENTIFIER) {
+        parse_ktx1(data)
+    } else if data.starts_with(&KTX2_IDENTIFIER) {
+        parse_ktx2(data)
+    } else {
+        Err("Not a DDS or KTX texture".to_string())
+    }
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "Texture header is truncated".to_string())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Result<usize, String> {
+    Ok(u32_at(data, offset)? as usize | (u32_at(data, offset + 4)? as usize) << 32)
+}
+
+fn unsupported(format: impl std::fmt::Display) -> String {
+    format!("Unsupported texture format: {}", format)
+}
+
+// 依次排列的各层，只保留文件中完整的层
+fn sequential_levels(
+    format: PixelFormat,
+    (width, height): (u32, u32),
+    count: usize,
+    mut offset: usize,
+    data_len: usize,
+) -> Vec<Range<usize>> {
+    let mut levels = Vec::new();
+    for level in 0..count.min(32) {
+        let (w, h) = ((width >> level).max(1), (height >> level).max(1));
+        let end = offset + format.level_size(w, h, 1);
+        if end > data_len {
+            break;
+        }
+        levels.push(offset..end);
+        offset = end;
+    }
+    levels
+}
+
+fn finish(
+    name: String,
+    format: PixelFormat,
+    (width, height): (u32, u32),
+    row_align: usize,
+    levels: Vec<Range<usize>>,
+) -> Result<Layout, String> {
+    if width == 0 || height == 0 {
+        return Err("Texture has no pixels".to_string());
+    }
+    if levels.is_empty() {
+        return Err("Texture data is truncated".to_string());
+    }
+    Ok(Layout {
+        name,
+        format,
+        width,
+        height,
+        row_align,
+        levels,
+    })
+}
+
+// DDS：128 字节的文件头，FourCC 为 DX10 时另有 20 字节的扩展头；立方体贴图和数组依次存放各面的全部层
+fn parse_dds(data: &[u8]) -> Result<Layout, String> {
+    let flags = u32_at(data, 8)?;
+    let size = (u32_at(data, 16)?, u32_at(data, 12)?);
+    let mip_count = u32_at(data, 28)? as usize;
+    let pixel_flags = u32_at(data, 80)?;
+    let four_cc = data.get(84..88).unwrap_or_default();
+    if u32_at(data, 112)? & 0x200000 != 0 {
+        return Err(unsupported("volume texture"));
+    }
+    let levels = if flags & 0x20000 != 0 {
+        mip_count.max(1)
+    } else {
+        1
+    };
+
+    let (name, format, offset) = if pixel_flags & 0x4 != 0 && four_cc == b"DX10" {
+        if u32_at(data, 132)? == 4 {
+            return Err(unsupported("volume texture"));
+        }
+        let (name, format) = dxgi_format(u32_at(data, 128)?)?;
+        (name.to_string(), format, 148)
+    } else if pixel_flags & 0x4 != 0 {
+        let format = match four_cc {
+            b"DXT1" => BlockFormat::Bc1,
+            b"DXT2" | b"DXT3" => BlockFormat::Bc2,
+            b"DXT4" | b"DXT5" => BlockFormat::Bc3,
+            b"ATI1" | b"BC4U" => BlockFormat::Bc4,
+            b"ATI2" | b"BC5U" => BlockFormat::Bc5,
+            other => return Err(unsupported(String::from_utf8_lossy(other))),
+        };
+        let name = format!("{} ({})", format.name(), String::from_utf8_lossy(four_cc));
+        (name, PixelFormat::Block(format), 128)
+    } else {
+        // 未压缩的 RGB 或亮度，按掩码取出各通道
+        let bits = u32_at(data, 88)?;
+        if !matches!(bits, 8 | 16 | 24 | 32) {
+            return Err(unsupported(format!("{} bits per pixel", bits)));
+        }
+        let [r, g, b, a] = [92, 96, 100, 104].map(|offset| u32_at(data, offset).unwrap_or(0));
+        let a = if pixel_flags & 0x1 != 0 { a } else { 0 };
+        let masks = if pixel_flags & 0x20000 != 0 {
+            [r, r, r, a]
+        } else if pixel_flags & 0x40 != 0 {
+            [r, g, b, a]
+        } else {
+            return Err(unsupported("unknown pixel format"));
+        };
+        let name = format!("{}-bit uncompressed", bits);
+        let format = PixelFormat::Masked {
+            bytes: bits as usize / 8,
+            masks,
+        };
+        (name, format, 128)
+    };
+    let chain = sequential_levels(format, size, levels, offset, data.len());
+    finish(name, format, size, 1, chain)
+}
+
+fn dxgi_format(code: u32) -> Result<(&'static str, PixelFormat), String> {
+    let block = |name, format| Ok((name, PixelFormat::Block(format)));
+    match code {
+        28 | 29 => Ok(("RGBA8", PixelFormat::RGBA8)),
+        87 | 91 => Ok(("BGRA8", PixelFormat::BGRA8)),
+        88 | 93 => Ok(("BGRX8", PixelFormat::BGRX8)),
+        49 => Ok(("RG8", PixelFormat::RG8)),
+        61 => Ok(("R8", PixelFormat::R8)),
+        70..=72 => block("BC1", BlockFormat::Bc1),
+        73..=75 => block("BC2", BlockFormat::Bc2),
+        76..=78 => block("BC3", BlockFormat::Bc3),
+        79 | 80 => block("BC4", BlockFormat::Bc4),
+        82 | 83 => block("BC5", BlockFormat::Bc5),
+        97..=99 => block("BC7", BlockFormat::Bc7),
+        94..=96 => Err(unsupported("BC6H")),
+        other => Err(unsupported(format!("DXGI format {}", other))),
+    }
+}
+
+// KTX 1：64 字节的文件头和键值数据之后，每层以 4 字节的大小开头，未压缩的行按 4 字节对齐
+fn parse_ktx1(data: &[u8]) -> Result<Layout, String> {
+    if u32_at(data, 12)? != 0x04030201 {
+        return Err(unsupported("big-endian KTX"));
+    }
+    let gl_type = u32_at(data, 16)?;
+    let gl_format = u32_at(data, 24)?;
+    let internal_format = u32_at(data, 28)?;
+    let size = (u32_at(data, 36)?, u32_at(data, 40)?.max(1));
+    if u32_at(data, 44)? > 1 {
+        return Err(unsupported("volume texture"));
+    }
+    let array_elements = u32_at(data, 48)?;
+    let faces = u32_at(data, 52)?;
+    let levels = (u32_at(data, 56)? as usize).max(1);
+    let key_value_bytes = u32_at(data, 60)? as usize;
+
+    let (name, format) = if gl_type == 0 {
+        ktx1_compressed(internal_format)?
+    } else {
+        // GL_UNSIGNED_BYTE
+        if gl_type != 0x1401 {
+            return Err(unsupported(format!("GL type 0x{:04X}", gl_type)));
+        }
+        match gl_format {
+            0x1908 => ("RGBA8", PixelFormat::RGBA8),
+            0x80E1 => ("BGRA8", PixelFormat::BGRA8),
+            0x1907 => ("RGB8", PixelFormat::RGB8),
+            0x80E0 => ("BGR8", PixelFormat::BGR8),
+            0x8227 => ("RG8", PixelFormat::RG8),
+            0x1903 => ("R8", PixelFormat::R8),
+            other => return Err(unsupported(format!("GL format 0x{:04X}", other))),
+        }
+    };
+
+    // 非数组的立方体贴图中 imageSize 是一个面的大小，各面依次存放
+    let face_count = if faces == 6 && array_elements == 0 {
+        6
+    } else {
+        1
+    };
+    let mut offset = 64 + key_value_bytes;
+    let mut chain = Vec::new();
+    for level in 0..levels.min(32) {
+        let (w, h) = ((size.0 >> level).max(1), (size.1 >> level).max(1));
+        let Ok(image_size) = u32_at(data, offset) else {
+            break;
+        };
+        let start = offset + 4;
+        let end = start + format.level_size(w, h, 4);
+        if end > data.len() || end > start + image_size as usize {
+            break;
+        }
+        chain.push(start..end);
+        offset = start + (image_size as usize).next_multiple_of(4) * face_count;
+    }
+    finish(name.to_string(), format, size, 4, chain)
+}
+
+fn ktx1_compressed(internal_format: u32) -> Result<(&'static str, PixelFormat), String> {
+    let format = match internal_format {
+        0x83F0 | 0x83F1 | 0x8C4C | 0x8C4D => BlockFormat::Bc1,
+        0x83F2 | 0x8C4E => BlockFormat::Bc2,
+        0x83F3 | 0x8C4F => BlockFormat::Bc3,
+        0x8DBB => BlockFormat::Bc4,
+        0x8DBD => BlockFormat::Bc5,
+        0x8E8C | 0x8E8D => BlockFormat::Bc7,
+        0x8E8E | 0x8E8F => return Err(unsupported("BC6H")),
+        other => return Err(unsupported(format!("GL internal format 0x{:04X}", other))),
+    };
+    Ok((format.name(), PixelFormat::Block(format)))
+}
+
+// KTX 2：80 字节的文件头之后是每层 24 字节的索引（偏移、长度、未压缩长度），索引从原尺寸开始
+fn parse_ktx2(data: &[u8]) -> Result<Layout, String> {
+    let vk_format = u32_at(data, 12)?;
+    let size = (u32_at(data, 20)?, u32_at(data, 24)?.max(1));
+    if u32_at(data, 28)? > 1 {
+        return Err(unsupported("volume texture"));
+    }
+    let levels = (u32_at(data, 40)? as usize).max(1);
+    if u32_at(data, 44)? != 0 {
+        return Err(unsupported(
+            "supercompressed KTX2 (Basis Universal or Zstandard)",
+        ));
+    }
+    let (name, format) = vk_format_of(vk_format)?;
+
+    let mut chain = Vec::new();
+    for level in 0..levels.min(32) {
+        let entry = 80 + level * 24;
+        let (offset, length) = (u64_at(data, entry)?, u64_at(data, entry + 8)?);
+        let (w, h) = ((size.0 >> level).max(1), (size.1 >> level).max(1));
+        let end = offset + format.level_size(w, h, 1);
+        if end > data.len() || end > offset + length {
+            break;
+        }
+        chain.push(offset..end);
+    }
+    finish(name.to_string(), format, size, 1, chain)
+}
+
+fn vk_format_of(code: u32) -> Result<(&'static str, PixelFormat), String> {
+    let block = |name, format| Ok((name, PixelFormat::Block(format)));
+    match code {
+        9 | 15 => Ok(("R8", PixelFormat::R8)),
+        16 | 22 => Ok(("RG8", PixelFormat::RG8)),
+        23 | 29 => Ok(("RGB8", PixelFormat::RGB8)),
+        30 | 36 => Ok(("BGR8", PixelFormat::BGR8)),
+        37 | 43 => Ok(("RGBA8", PixelFormat::RGBA8)),
+        44 | 50 => Ok(("BGRA8", PixelFormat::BGRA8)),
+        131..=134 => block("BC1", BlockFormat::Bc1),
+        135 | 136 => block("BC2", BlockFormat::Bc2),
+        137 | 138 => block("BC3", BlockFormat::Bc3),
+        139 => block("BC4", BlockFormat::Bc4),
+        141 => block("BC5", BlockFormat::Bc5),
+        145 | 146 => block("BC7", BlockFormat::Bc7),
+        143 | 144 => Err(unsupported("BC6H")),
+        0 => Err(unsupported("undefined (Basis Universal)")),
+        other => Err(unsupported(format!("Vulkan format {}", other))),
+    }
+}
+
+// 未压缩的像素按掩码取出各通道并换算到 8 位
+fn decode_masked(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bytes: usize,
+    masks: [u32; 4],
+    row_align: usize,
+) -> Option<RgbaImage> {
+    let stride = (width as usize * bytes).next_multiple_of(row_align);
+    if data.len() < stride * (height as usize - 1) + width as usize * bytes {
+        return None;
+    }
+    let channels = masks.map(|mask| {
+        let shift = mask.trailing_zeros().min(31);
+        (mask, shift, (mask >> shift).max(1))
+    });
+    Some(RgbaImage::from_fn(width, height, |x, y| {
+        let offset = y as usize * stride + x as usize * bytes;
+        let mut value = [0u8; 4];
+        value[..bytes].copy_from_slice(&data[offset..offset + bytes]);
+        let value = u32::from_le_bytes(value);
+        let mut pixel = [0u8; 4];
+        for (i, (mask, shift, max)) in channels.into_iter().enumerate() {
+            pixel[i] = if mask == 0 {
+                if i == 3 { 255 } else { 0 }
+            } else {
+                (((value & mask) >> shift) as u64 * 255 / max as u64) as u8
+            };
+        }
+        image::Rgba(pixel)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // 4×4 的 DXT1 纹理，带 2×2 的第二层；每层一个 8 字节的块
+    fn dxt1_bytes(blocks: &[[u8; 8]]) -> Vec<u8> {
+        let mut data = vec![0; 128];
+        data[..4].copy_from_slice(b"DDS ");
+        let mut put = |offset: usize, value: u32| {
+            data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        put(4, 124);
+        put(8, 0x1007 | 0x20000); // 带 mipmap 数量
+        put(12, 4);
+        put(16, 4);
+        put(28, blocks.len() as u32);
+        put(76, 32);
+        put(80, 0x4);
+        data[84..88].copy_from_slice(b"DXT1");
+        for block in blocks {
+            data.extend(block);
+        }
+        data
+    }
+
+    fn write_temp(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.dds", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn decodes_dxt1_levels() {
+        // 红色和蓝色两个端点，第一行用红色，第二行用蓝色，之后两行中间是两个插值颜色
+        let top = [0x00, 0xF8, 0x1F, 0x00, 0x00, 0x55, 0x78, 0x78];
+        // 第一个端点不大于第二个时索引 3 为透明
+        let level1 = [0x1F, 0x00, 0x00, 0xF8, 0xFF, 0xFF, 0xFF, 0xFF];
+        let path = write_temp("texture-dxt1", &dxt1_bytes(&[top, level1]));
+
+        let (img, info) = decode(&path, 0).unwrap();
+        assert_eq!(
+            info,
+            TextureInfo {
+                format: "BC1 (DXT1)".to_string(),
+                width: 4,
+                height: 4,
+                levels: 2,
+            }
+        );
+        let img = img.to_rgba8();
+        assert_eq!(img.get_pixel(3, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(0, 1).0, [0, 0, 255, 255]);
+        let [r2, _, b2, _] = img.get_pixel(1, 2).0;
+        let [r3, _, b3, _] = img.get_pixel(2, 2).0;
+        assert!(r2 > b2 && r3 < b3);
+
+        let (img, _) = decode(&path, 1).unwrap();
+        assert_eq!(img.to_rgba8().get_pixel(1, 1).0[3], 0);
+        assert!(decode(&path, 2).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let data = dxt1_bytes(&[[0; 8]]);
+        let path = write_temp("texture-truncated", &data[..130]);
+        assert_eq!(
+            decode(&path, 0).map(|_| ()).unwrap_err(),
+            "Texture data is truncated"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
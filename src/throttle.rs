@@ -0,0 +1,86 @@
+use iced::futures::{StreamExt, stream};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::i18n::tr;
+
+// 限速时上传请求体按这个大小分块发送
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 设置中可选的限速（KB/s），0 表示不限速
+pub const LIMITS: [u64; 5] = [0, 256, 1024, 4096, 16384];
+
+pub fn limit_name(kib: u64) -> String {
+    match kib {
+        0 => tr("Unlimited").to_string(),
+        k if k >= 1024 => format!("{} MB/s", k / 1024),
+        k => format!("{} KB/s", k),
+    }
+}
+
+// 所有传输共用的令牌桶：按速率排出每块数据的发送时间
+struct Limiter {
+    rate: AtomicU64,              // 字节/秒，0 表示不限速
+    next: Mutex<Option<Instant>>, // 下一块数据最早可以发送完的时间
+}
+
+impl Limiter {
+    const fn new() -> Self {
+        Limiter {
+            rate: AtomicU64::new(0),
+            next: Mutex::new(None),
+        }
+    }
+
+    fn set(&self, kib: u64) {
+        self.rate.store(kib * 1024, Ordering::Relaxed);
+        *self.next.lock().unwrap() = None;
+    }
+
+    fn is_limited(&self) -> bool {
+        self.rate.load(Ordering::Relaxed) > 0
+    }
+
+    async fn consume(&self, bytes: u64) {
+        let rate = self.rate.load(Ordering::Relaxed);
+        if rate == 0 || bytes == 0 {
+            return;
+        }
+        let wait = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let start = next.filter(|next| *next > now).unwrap_or(now);
+            let end = start + Duration::from_secs_f64(bytes as f64 / rate as f64);
+            *next = Some(end);
+            end - now
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+static UPLOAD: Limiter = Limiter::new();
+static DOWNLOAD: Limiter = Limiter::new();
+
+/// 修改上传和下载的限速（KB/s），正在进行的传输从下一块开始生效
+pub fn set_limits(upload_kib: u64, download_kib: u64) {
+    UPLOAD.set(upload_kib);
+    DOWNLOAD.set(download_kib);
+}
+
+/// 收到一块下载的数据后调用，超过限速时等待
+pub async fn downloaded(bytes: usize) {
+    DOWNLOAD.consume(bytes as u64).await;
+}
+
+/// 上传的请求体，限速时分块发送（调用方需要自己设置 Content-Length）
+pub fn upload_body(bytes: Vec<u8>) -> reqwest::Body {
+    if bytes.is_empty() || !UPLOAD.is_limited() {
+        return bytes.into();
+    }
+    let chunks: Vec<Vec<u8>> = bytes.chunks(CHUNK_SIZE).map(<[u8]>::to_vec).collect();
+    reqwest::Body::wrap_stream(stream::iter(chunks).then(|chunk| async move {
+        UPLOAD.consume(chunk.len() as u64).await;
+        Ok::<_, std::io::Error>(chunk)
+    }))
+}
@@ -0,0 +1,265 @@
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::widget::image::Handle;
+use image::metadata::Orientation;
+use image::{ImageDecoder, ImageReader};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tracing::{debug, warn};
+
+use crate::perf::{self, Stage};
+use crate::pipeline::decode_image;
+use crate::xmp_iptc::{self, Flag};
+use crate::{exif_info, formats, memory};
+
+// 每批最多合并的缩略图数量，减少界面刷新次数
+const BATCH_SIZE: usize = 16;
+
+// 缩略图边长（像素），启动时按配置设置
+static SIZE: AtomicU32 = AtomicU32::new(80);
+
+pub fn set_size(size: u32) {
+    SIZE.store(size, Ordering::Relaxed);
+}
+
+pub fn size() -> u32 {
+    SIZE.load(Ordering::Relaxed)
+}
+
+/// 按配置的边长生成缩略图，失败时返回占位图
+pub fn generate_thumbnail(path: &Path) -> Handle {
+    let size = size();
+    let _timer = perf::Timer::start(Stage::Thumbnail);
+    // 检查文件是否存在且可读
+    if !path.exists() || !path.is_file() {
+        warn!("File does not exist or is not a file: {}", path.display());
+        // 返回默认占位符
+        return Handle::from_rgba(size, size, [200].repeat((size * size * 4) as usize));
+    }
+
+    // 检查文件扩展名
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    if !formats::IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        warn!("Unsupported image format: {}", path.display());
+        return Handle::from_rgba(size, size, [150].repeat((size * size * 4) as usize));
+    }
+
+    // JPEG 优先使用内嵌的 EXIF 缩略图，避免完整解码原图
+    if (ext == "jpg" || ext == "jpeg")
+        && let Some(handle) = load_embedded_thumbnail(path, size)
+    {
+        return handle;
+    }
+
+    // 尝试加载图片
+    match decode_image(path, true) {
+        Ok((img, _)) => {
+            // 缩放到缩略图尺寸
+            let thumbnail = img.resize(size, size, image::imageops::FilterType::Lanczos3);
+            let rgba = thumbnail.to_rgba8();
+            let (width, height) = rgba.dimensions();
+            Handle::from_rgba(width, height, rgba.into_raw())
+        }
+        Err(e) => {
+            warn!("Failed to load thumbnail for {}: {}", path.display(), e);
+            // 返回错误占位符
+            Handle::from_rgba(
+                size,
+                size,
+                [255, 100, 100, 255].repeat((size * size) as usize),
+            )
+        }
+    }
+}
+
+/// 悬停预览的长边（像素）
+pub const PREVIEW_SIZE: u32 = 400;
+
+/// 生成悬停预览，比缩略图大，解码失败时返回 None
+pub fn generate_preview(path: &Path) -> Option<Handle> {
+    if let Some(handle) = load_embedded_thumbnail(path, PREVIEW_SIZE) {
+        return Some(handle);
+    }
+    let (img, _) = decode_image(path, true).ok()?;
+    let preview = img.resize(
+        PREVIEW_SIZE,
+        PREVIEW_SIZE,
+        image::imageops::FilterType::Triangle,
+    );
+    let rgba = preview.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Some(Handle::from_rgba(width, height, rgba.into_raw()))
+}
+
+/// 图片的像素尺寸和文件大小，显示在悬停提示中；挑片标记显示在缩略图上
+#[derive(Debug, Clone, Copy)]
+pub struct FileInfo {
+    pub dimensions: Option<(u32, u32)>,
+    pub bytes: u64,
+    pub flag: Option<Flag>,
+}
+
+impl FileInfo {
+    /// 尺寸只读取文件头，不解码图片；文件不存在时（例如云端图片）返回 None
+    pub fn read(path: &Path) -> Option<FileInfo> {
+        let bytes = std::fs::metadata(path).ok()?.len();
+        Some(FileInfo {
+            dimensions: oriented_dimensions(path),
+            bytes,
+            flag: xmp_iptc::read_sidecar_flag(path),
+        })
+    }
+}
+
+// 文件头中的尺寸，EXIF 方向为旋转 90° 时交换宽高，与摆正后的缩略图一致
+fn oriented_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let mut decoder = ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let (width, height) = decoder.dimensions();
+    match decoder.orientation().unwrap_or(Orientation::NoTransforms) {
+        Orientation::Rotate90
+        | Orientation::Rotate270
+        | Orientation::Rotate90FlipH
+        | Orientation::Rotate270FlipH => Some((height, width)),
+        _ => Some((width, height)),
+    }
+}
+
+/// 从内嵌的 EXIF 缩略图生成缩略图，尺寸太小或解码失败时返回 None
+fn load_embedded_thumbnail(path: &Path, size: u32) -> Option<Handle> {
+    let (data, orientation) = exif_info::read_embedded_thumbnail(path)?;
+    let mut img = image::load_from_memory_with_format(&data, image::ImageFormat::Jpeg).ok()?;
+    // 内嵌缩略图太小时放大会很模糊，回退到完整解码
+    if img.width().max(img.height()) < size {
+        return None;
+    }
+    if let Some(orientation) = Orientation::from_exif(orientation as u8) {
+        img.apply_orientation(orientation);
+    }
+    let thumbnail = img.resize(size, size, image::imageops::FilterType::Triangle);
+    let rgba = thumbnail.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Some(Handle::from_rgba(width, height, rgba.into_raw()))
+}
+
+/// 在阻塞线程池中并发生成缩略图并读取文件信息，按完成顺序分批返回
+pub fn load_all(
+    paths: Vec<PathBuf>,
+) -> impl Stream<Item = Vec<(PathBuf, Handle, Option<FileInfo>)>> {
+    let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+    iced::stream::channel(4, move |mut output| async move {
+        let mut batches = iced::futures::stream::iter(paths)
+            .map(|path| async move {
+                tokio::task::spawn_blocking(move || {
+                    let handle = generate_thumbnail(&path);
+                    let info = FileInfo::read(&path);
+                    (path, handle, info)
+                })
+                .await
+            })
+            .buffer_unordered(workers)
+            .filter_map(|result| std::future::ready(result.ok()))
+            .ready_chunks(BATCH_SIZE);
+        while let Some(batch) = batches.next().await {
+            let _ = output.send(batch).await;
+        }
+    })
+}
+
+// 缩略图缓存条目：缩略图、最近使用的序号、占用的字节数
+struct CachedThumbnail {
+    handle: Handle,
+    last_used: u64,
+    bytes: usize,
+}
+
+/// 按路径缓存的缩略图，记录使用顺序，内存不足时淘汰最久未使用的
+#[derive(Default)]
+pub struct ThumbnailCache {
+    entries: HashMap<PathBuf, CachedThumbnail>,
+    clock: u64,
+    used: usize,
+}
+
+impl ThumbnailCache {
+    pub fn get(&self, path: &Path) -> Option<&Handle> {
+        self.entries.get(path).map(|entry| &entry.handle)
+    }
+
+    pub fn contains_key(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, handle: Handle) {
+        self.clock += 1;
+        let bytes = memory::handle_bytes(&handle);
+        let entry = CachedThumbnail {
+            handle,
+            last_used: self.clock,
+            bytes,
+        };
+        self.used += bytes;
+        if let Some(old) = self.entries.insert(path, entry) {
+            self.used -= old.bytes;
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Option<Handle> {
+        let entry = self.entries.remove(path)?;
+        self.used -= entry.bytes;
+        Some(entry.handle)
+    }
+
+    /// 把这些缩略图标记为最近使用（例如它们所在的文件夹刚被打开）
+    pub fn touch<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) {
+        self.clock += 1;
+        for path in paths {
+            if let Some(entry) = self.entries.get_mut(path) {
+                entry.last_used = self.clock;
+            }
+        }
+    }
+
+    /// 淘汰最久未使用的缩略图，直到占用不超过 limit 字节
+    pub fn shrink_to(&mut self, limit: usize) {
+        if self.used <= limit {
+            return;
+        }
+        let mut order: Vec<(u64, PathBuf)> = self
+            .entries
+            .iter()
+            .map(|(path, entry)| (entry.last_used, path.clone()))
+            .collect();
+        order.sort_unstable_by_key(|(last_used, _)| *last_used);
+        let before = self.entries.len();
+        for (_, path) in order {
+            if self.used <= limit {
+                break;
+            }
+            self.remove(&path);
+        }
+        debug!(
+            evicted = before - self.entries.len(),
+            used = self.used,
+            limit,
+            "Shrank thumbnail cache"
+        );
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use std::path::PathBuf;
+
+use crate::catalog::Catalog;
+use crate::exif_info;
+use crate::i18n::tr;
+
+/// 时间线中列出哪些图片
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineSource {
+    Collection, // 当前图片库
+    Library,    // 整个图片目录
+}
+
+impl TimelineSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            TimelineSource::Collection => tr("This folder"),
+            TimelineSource::Library => tr("Whole library"),
+        }
+    }
+}
+
+/// 同一天拍摄的图片
+#[derive(Debug, Clone)]
+pub struct Day {
+    pub date: Option<NaiveDate>, // 没有拍摄日期也读不到修改时间时为 None
+    pub images: Vec<PathBuf>,
+}
+
+/// 同一个月拍摄的图片，按日期分组
+#[derive(Debug, Clone)]
+pub struct Month {
+    pub month: Option<(i32, u32)>, // （年, 月），日期未知的图片为 None
+    pub days: Vec<Day>,
+}
+
+impl Month {
+    pub fn title(&self) -> String {
+        match self.month {
+            Some((year, month)) => format!("{}-{:02}", year, month),
+            None => tr("Unknown date").to_string(),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.days.iter().map(|day| day.images.len()).sum()
+    }
+}
+
+/// 时间线面板的状态
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    pub source: TimelineSource,
+    pub months: Option<Vec<Month>>, // None 时正在读取拍摄日期
+    pub selected: usize,            // 右侧显示的月份
+}
+
+impl Timeline {
+    pub fn new(source: TimelineSource) -> Timeline {
+        Timeline {
+            source,
+            months: None,
+            selected: 0,
+        }
+    }
+
+    /// 选中月份中的所有图片
+    pub fn selected_images(&self) -> Vec<PathBuf> {
+        self.months
+            .iter()
+            .flatten()
+            .nth(self.selected)
+            .map(|month| {
+                month
+                    .days
+                    .iter()
+                    .flat_map(|day| day.images.iter().cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// 在后台读取图片的拍摄日期，没有 EXIF 日期时使用文件的修改时间
+pub async fn from_paths(paths: Vec<PathBuf>) -> Result<Vec<Month>, String> {
+    tokio::task::spawn_blocking(move || {
+        let dated = paths
+            .into_iter()
+            .map(|path| {
+                let date = exif_info::read_exif(&path)
+                    .and_then(|exif| exif.date_time)
+                    .and_then(|taken| parse_taken(&taken))
+                    .or_else(|| {
+                        let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+                        Some(DateTime::<Local>::from(modified).date_naive())
+                    });
+                (path, date)
+            })
+            .collect();
+        group(dated)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 在后台按图片目录中记录的拍摄日期和修改时间分组
+pub async fn from_catalog(db: PathBuf) -> Result<Vec<Month>, String> {
+    tokio::task::spawn_blocking(move || {
+        let dated = Catalog::open(&db)?
+            .dates()?
+            .into_iter()
+            .map(|(path, taken, mtime)| {
+                let date = taken.as_deref().and_then(parse_taken).or_else(|| {
+                    DateTime::from_timestamp(mtime, 0)
+                        .map(|time| time.with_timezone(&Local).date_naive())
+                });
+                (path, date)
+            })
+            .collect();
+        Ok(group(dated))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// EXIF 中的日期时间，如 "2024:06:15 14:30:00"
+fn parse_taken(taken: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(taken.get(..10)?, "%Y:%m:%d").ok()
+}
+
+// 按月和日分组，最新的在前，日期未知的放在最后
+fn group(mut dated: Vec<(PathBuf, Option<NaiveDate>)>) -> Vec<Month> {
+    dated.sort_by(|(a_path, a), (b_path, b)| b.cmp(a).then_with(|| a_path.cmp(b_path)));
+    let mut months: Vec<Month> = Vec::new();
+    for (path, date) in dated {
+        let key = date.map(|date| (date.year(), date.month()));
+        if months.last().is_none_or(|month| month.month != key) {
+            months.push(Month {
+                month: key,
+                days: Vec::new(),
+            });
+        }
+        let Some(month) = months.last_mut() else {
+            continue;
+        };
+        match month.days.last_mut() {
+            Some(day) if day.date == date => day.images.push(path),
+            _ => month.days.push(Day {
+                date,
+                images: vec![path],
+            }),
+        }
+    }
+    months
+}
@@ -0,0 +1,59 @@
+use iced::widget::{column, container, text};
+use iced::{Alignment, Background, Border, Element, Length};
+use std::time::Duration;
+
+use crate::messages::Message;
+use crate::palette;
+
+// 提示显示多久后自动消失
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Success,
+    Error,
+}
+
+/// 窗口角落短暂显示的提示
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub message: String,
+}
+
+/// 从上到下排列的提示
+pub fn view(toasts: &[Toast]) -> Element<'_, Message> {
+    column(toasts.iter().map(|toast| {
+        let kind = toast.kind;
+        container(
+            text(&toast.message)
+                .shaping(text::Shaping::Advanced)
+                .size(13),
+        )
+        .padding([8, 12])
+        .max_width(360)
+        .style(move |theme| {
+            let p = palette::of(theme);
+            let accent = match kind {
+                ToastKind::Success => p.success,
+                ToastKind::Error => p.danger,
+            };
+            container::Style {
+                background: Some(Background::Color(p.surface)),
+                text_color: Some(p.text),
+                border: Border {
+                    radius: 6.0.into(),
+                    width: 1.0,
+                    color: accent,
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    }))
+    .spacing(8)
+    .align_x(Alignment::End)
+    .width(Length::Shrink)
+    .into()
+}
@@ -0,0 +1,535 @@
+use iced::futures::channel::mpsc::Sender;
+use iced::futures::{SinkExt, Stream};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+use crate::cloud_storage::{self, CloudConfig, CloudStorage, Storage};
+use crate::config;
+use crate::metadata::{self, MetadataPolicy};
+use crate::upload_manifest;
+
+// 同时进行的传输数量
+const MAX_ACTIVE: usize = 3;
+
+// 下载时每次请求的字节数，每收到一块更新一次进度
+const CHUNK_SIZE: u64 = 512 * 1024;
+
+// 上传失败后自动重试的次数上限，超过后需要手动重试
+const MAX_ATTEMPTS: u32 = 6;
+
+// 第一次重试前等待的秒数，之后每次翻倍，最多等待 MAX_RETRY_DELAY 秒
+const RETRY_DELAY: i64 = 5;
+const MAX_RETRY_DELAY: i64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferStatus {
+    Queued,
+    Active,
+    Paused,
+    Waiting(String), // 上传失败，等待自动重试（上次失败的原因）
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+/// 队列中的一项传输：上传时把 local 上传为 key，下载时把 key 保存到 local
+pub struct Transfer {
+    pub id: u64,
+    pub direction: TransferDirection,
+    pub local: PathBuf,
+    pub key: String,
+    pub size: u64,        // 总字节数，下载开始前可能还不知道（为 0）
+    pub transferred: u64, // 已传输的字节数
+    pub status: TransferStatus,
+    pub md5: Option<String>,  // 上传文件内容的 MD5，开始上传后才知道
+    pub skipped: bool,        // 存储桶中已有相同内容，没有重新上传
+    pub attempts: u32,        // 连续失败的次数
+    pub retry_at: i64,        // 等待重试时下次重试的时间（Unix 秒）
+    pub etag: Option<String>, // 下载的对象的 ETag，记入远程缓存
+    handle: Option<iced::task::Handle>, // 进行中的后台任务，丢弃时取消
+}
+
+impl Transfer {
+    pub fn name(&self) -> &str {
+        self.key.rsplit('/').next().unwrap_or(&self.key)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            TransferStatus::Completed | TransferStatus::Failed(_) | TransferStatus::Cancelled
+        )
+    }
+
+    /// 开始传输，handle 被丢弃时取消后台任务
+    pub fn start(&mut self, handle: iced::task::Handle) {
+        self.status = TransferStatus::Active;
+        self.handle = Some(handle.abort_on_drop());
+    }
+}
+
+// 传输过程中发送的事件
+#[derive(Debug, Clone)]
+pub enum TransferEvent {
+    Progress(u64, u64), // 已传输的字节数、总字节数
+    Hashed(String),     // 计算出了上传文件的 MD5
+    Skipped,            // 内容没有变化，跳过上传
+    Tagged(String),     // 查询到了要下载的对象的 ETag
+    Finished(Result<(), String>),
+}
+
+/// 上传和下载共用的传输队列，同时最多进行 MAX_ACTIVE 项
+#[derive(Default)]
+pub struct TransferQueue {
+    transfers: Vec<Transfer>,
+    next_id: u64,
+}
+
+impl TransferQueue {
+    pub fn push(
+        &mut self,
+        direction: TransferDirection,
+        local: PathBuf,
+        key: String,
+        size: u64,
+    ) -> u64 {
+        self.next_id += 1;
+        self.transfers.push(Transfer {
+            id: self.next_id,
+            direction,
+            local,
+            key,
+            size,
+            transferred: 0,
+            status: TransferStatus::Queued,
+            md5: None,
+            skipped: false,
+            attempts: 0,
+            retry_at: 0,
+            etag: None,
+            handle: None,
+        });
+        self.next_id
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Transfer> {
+        self.transfers.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+
+    /// 排队中和进行中的数量
+    pub fn pending(&self) -> usize {
+        self.transfers
+            .iter()
+            .filter(|t| matches!(t.status, TransferStatus::Queued | TransferStatus::Active))
+            .count()
+    }
+
+    /// 下一项可以开始的传输，进行中的数量已达上限时返回 None
+    pub fn next_to_start(&mut self) -> Option<&mut Transfer> {
+        let active = self
+            .transfers
+            .iter()
+            .filter(|t| t.status == TransferStatus::Active)
+            .count();
+        if active >= MAX_ACTIVE {
+            return None;
+        }
+        self.transfers
+            .iter_mut()
+            .find(|t| t.status == TransferStatus::Queued)
+    }
+
+    /// 是否有同一个文件还没完成的上传
+    pub fn is_uploading(&self, local: &Path) -> bool {
+        self.transfers.iter().any(|t| {
+            t.direction == TransferDirection::Upload && t.local == local && !t.is_finished()
+        })
+    }
+
+    pub fn progress(&mut self, id: u64, transferred: u64, size: u64) {
+        if let Some(transfer) = self.find(id) {
+            transfer.transferred = transferred;
+            transfer.size = size;
+        }
+    }
+
+    pub fn set_md5(&mut self, id: u64, md5: String) {
+        if let Some(transfer) = self.find(id) {
+            transfer.md5 = Some(md5);
+        }
+    }
+
+    pub fn set_etag(&mut self, id: u64, etag: String) {
+        if let Some(transfer) = self.find(id) {
+            transfer.etag = Some(etag);
+        }
+    }
+
+    pub fn skip(&mut self, id: u64) {
+        if let Some(transfer) = self.find(id) {
+            transfer.skipped = true;
+        }
+    }
+
+    /// 是否有等待自动重试的上传
+    pub fn is_waiting(&self) -> bool {
+        self.transfers
+            .iter()
+            .any(|t| matches!(t.status, TransferStatus::Waiting(_)))
+    }
+
+    /// 把到了重试时间的上传重新排队，返回是否有这样的上传
+    pub fn requeue_due(&mut self, now: i64) -> bool {
+        let mut requeued = false;
+        for transfer in self.transfers.iter_mut() {
+            if matches!(transfer.status, TransferStatus::Waiting(_)) && transfer.retry_at <= now {
+                transfer.status = TransferStatus::Queued;
+                requeued = true;
+            }
+        }
+        requeued
+    }
+
+    /// 记录传输结果，返回这一项；已经暂停或取消的传输忽略迟到的结果
+    /// 上传失败时按指数退避等待自动重试，次数用完后才算失败
+    pub fn finish(&mut self, id: u64, result: Result<(), String>) -> Option<&Transfer> {
+        let transfer = self.find(id)?;
+        if transfer.status != TransferStatus::Active {
+            return None;
+        }
+        transfer.handle = None;
+        transfer.status = match result {
+            Ok(()) => {
+                transfer.transferred = transfer.size;
+                transfer.attempts = 0;
+                TransferStatus::Completed
+            }
+            Err(e) if transfer.direction == TransferDirection::Upload => {
+                transfer.attempts += 1;
+                if transfer.attempts < MAX_ATTEMPTS {
+                    transfer.retry_at =
+                        chrono::Utc::now().timestamp() + retry_delay(transfer.attempts);
+                    TransferStatus::Waiting(e)
+                } else {
+                    TransferStatus::Failed(e)
+                }
+            }
+            Err(e) => TransferStatus::Failed(e),
+        };
+        Some(transfer)
+    }
+
+    /// 暂停后下载从中断的位置继续，上传重新开始
+    pub fn pause(&mut self, id: u64) {
+        if let Some(transfer) = self.find(id)
+            && matches!(
+                transfer.status,
+                TransferStatus::Queued | TransferStatus::Active | TransferStatus::Waiting(_)
+            )
+        {
+            transfer.handle = None;
+            transfer.status = TransferStatus::Paused;
+        }
+    }
+
+    pub fn resume(&mut self, id: u64) {
+        if let Some(transfer) = self.find(id)
+            && transfer.status == TransferStatus::Paused
+        {
+            transfer.status = TransferStatus::Queued;
+        }
+    }
+
+    pub fn cancel(&mut self, id: u64) {
+        if let Some(transfer) = self.find(id)
+            && !transfer.is_finished()
+        {
+            transfer.handle = None;
+            transfer.status = TransferStatus::Cancelled;
+            if transfer.direction == TransferDirection::Download {
+                let _ = std::fs::remove_file(partial_path(&transfer.local));
+            }
+        }
+    }
+
+    pub fn retry(&mut self, id: u64) {
+        if let Some(transfer) = self.find(id)
+            && matches!(
+                transfer.status,
+                TransferStatus::Failed(_) | TransferStatus::Cancelled | TransferStatus::Waiting(_)
+            )
+        {
+            transfer.transferred = 0;
+            transfer.skipped = false;
+            transfer.attempts = 0;
+            transfer.status = TransferStatus::Queued;
+        }
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.transfers.retain(|t| !t.is_finished());
+    }
+
+    /// 丢弃还没开始的下载（例如切换到了另一个文件夹）
+    pub fn remove_queued_downloads(&mut self) {
+        self.transfers.retain(|t| {
+            !(t.direction == TransferDirection::Download && t.status == TransferStatus::Queued)
+        });
+    }
+
+    /// 保存还没完成和失败的上传，下次启动时恢复
+    pub fn save_uploads(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let uploads: Vec<SavedUpload> = self
+            .transfers
+            .iter()
+            .filter(|t| t.direction == TransferDirection::Upload)
+            .filter(|t| {
+                !matches!(
+                    t.status,
+                    TransferStatus::Completed | TransferStatus::Cancelled
+                )
+            })
+            .map(|t| SavedUpload {
+                local: t.local.clone(),
+                key: t.key.clone(),
+                size: t.size,
+                attempts: t.attempts,
+                paused: t.status == TransferStatus::Paused,
+                error: match &t.status {
+                    TransferStatus::Waiting(e) | TransferStatus::Failed(e) => Some(e.clone()),
+                    _ => None,
+                },
+            })
+            .collect();
+        let content = serde_json::to_string_pretty(&uploads)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 恢复上次退出时保存的上传：失败的仍显示为失败，其余的马上开始重试
+    pub fn load_uploads(&mut self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        if !path.is_file() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(path)?;
+        let uploads: Vec<SavedUpload> = serde_json::from_str(&content)?;
+        for upload in uploads {
+            let id = self.push(
+                TransferDirection::Upload,
+                upload.local,
+                upload.key,
+                upload.size,
+            );
+            let Some(transfer) = self.find(id) else {
+                continue;
+            };
+            transfer.attempts = upload.attempts;
+            transfer.status = match upload.error {
+                _ if upload.paused => TransferStatus::Paused,
+                Some(e) if upload.attempts >= MAX_ATTEMPTS => TransferStatus::Failed(e),
+                error => TransferStatus::Waiting(error.unwrap_or_default()),
+            };
+        }
+        Ok(())
+    }
+
+    fn find(&mut self, id: u64) -> Option<&mut Transfer> {
+        self.transfers.iter_mut().find(|t| t.id == id)
+    }
+}
+
+// 保存到文件中的一项上传
+#[derive(Serialize, Deserialize)]
+struct SavedUpload {
+    local: PathBuf,
+    key: String,
+    size: u64,
+    attempts: u32,
+    paused: bool,
+    error: Option<String>,
+}
+
+// 第 attempts 次失败后等待的秒数
+fn retry_delay(attempts: u32) -> i64 {
+    (RETRY_DELAY << (attempts - 1).min(16)).min(MAX_RETRY_DELAY)
+}
+
+/// 上传队列文件的位置
+pub fn uploads_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("upload_queue.json"))
+}
+
+/// 执行一项传输；下载先写到 .part 文件，完成后再改名，暂停后从已下载的位置继续
+/// uploaded 为清单中记录的这个对象上次上传的 MD5，上传前按 policy 处理元数据
+pub fn run(
+    transfer: &Transfer,
+    config: CloudConfig,
+    uploaded: Option<String>,
+    policy: MetadataPolicy,
+) -> impl Stream<Item = TransferEvent> + use<> {
+    let direction = transfer.direction;
+    let local = transfer.local.clone();
+    let key = transfer.key.clone();
+    let size = transfer.size;
+    let etag = transfer.etag.clone();
+    iced::stream::channel(4, move |mut output| async move {
+        let result = match cloud_storage::connect(&config) {
+            Ok(storage) => match direction {
+                TransferDirection::Upload => {
+                    upload(&storage, local, key, size, uploaded, policy, &mut output).await
+                }
+                TransferDirection::Download => {
+                    download(&storage, key, &local, size, etag, &mut output).await
+                }
+            },
+            Err(e) => Err(e),
+        };
+        let _ = output.send(TransferEvent::Finished(result)).await;
+    })
+}
+
+// 存储桶中的对象与本地文件内容相同时跳过上传；无法访问存储桶时按清单判断
+async fn upload(
+    storage: &Storage,
+    local: PathBuf,
+    key: String,
+    size: u64,
+    uploaded: Option<String>,
+    policy: MetadataPolicy,
+    output: &mut Sender<TransferEvent>,
+) -> Result<(), String> {
+    let _ = output.send(TransferEvent::Progress(0, size)).await;
+    let staged = stage_upload(&local, policy).await?;
+    let path = staged.clone().unwrap_or(local);
+    let result = upload_file(storage, path, key, uploaded, output).await;
+    if let Some(staged) = staged {
+        let _ = tokio::fs::remove_file(staged).await;
+    }
+    result
+}
+
+async fn upload_file(
+    storage: &Storage,
+    path: PathBuf,
+    key: String,
+    uploaded: Option<String>,
+    output: &mut Sender<TransferEvent>,
+) -> Result<(), String> {
+    let md5 = upload_manifest::file_md5(&path).await?;
+    let _ = output.send(TransferEvent::Hashed(md5.clone())).await;
+    let unchanged = match storage.object_etag(key.clone()).await {
+        Ok(etag) => etag.is_some_and(|etag| etag.eq_ignore_ascii_case(&md5)),
+        Err(_) => uploaded.as_ref() == Some(&md5),
+    };
+    if unchanged {
+        let _ = output.send(TransferEvent::Skipped).await;
+        return Ok(());
+    }
+    storage.upload_object(path, key).await
+}
+
+// 需要去除或修改元数据时把处理后的副本写到临时文件夹，返回副本的位置；不需要处理时返回 None
+async fn stage_upload(local: &Path, policy: MetadataPolicy) -> Result<Option<PathBuf>, String> {
+    if policy == MetadataPolicy::Keep {
+        return Ok(None);
+    }
+    let local = local.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let bytes = std::fs::read(&local).map_err(|e| e.to_string())?;
+        let output = metadata::apply_metadata_policy(bytes, &policy).map_err(|e| e.to_string())?;
+        // 副本保留原来的文件名，上传时按扩展名判断类型
+        let folder = std::env::temp_dir().join(format!(
+            "image-browser-upload-{}",
+            upload_manifest::bytes_md5(&output)
+        ));
+        std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+        let staged = folder.join(local.file_name().unwrap_or_default());
+        std::fs::write(&staged, output).map_err(|e| e.to_string())?;
+        Ok(Some(staged))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+async fn download(
+    storage: &Storage,
+    key: String,
+    local: &Path,
+    size: u64,
+    etag: Option<String>,
+    output: &mut Sender<TransferEvent>,
+) -> Result<(), String> {
+    let size = if size > 0 {
+        size
+    } else {
+        storage.object_size(key.clone()).await?
+    };
+    // 不是从文件夹列表中排队的下载，先查询 ETag 用来标记缓存的版本
+    if etag.is_none()
+        && let Ok(Some(etag)) = storage.object_etag(key.clone()).await
+    {
+        let _ = output.send(TransferEvent::Tagged(etag)).await;
+    }
+    if let Some(parent) = local.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let partial = partial_path(local);
+    let mut offset = tokio::fs::metadata(&partial)
+        .await
+        .map_or(0, |meta| meta.len());
+    // 残留的 .part 比对象还大，说明对象已经变了，重新下载
+    if offset > size {
+        offset = 0;
+    }
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&partial)
+        .await
+        .map_err(|e| e.to_string())?;
+    file.set_len(offset).await.map_err(|e| e.to_string())?;
+    let mut file = tokio::io::BufWriter::new(file);
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = output.send(TransferEvent::Progress(offset, size)).await;
+    while offset < size {
+        let end = (offset + CHUNK_SIZE).min(size) - 1;
+        let bytes = storage.download_range(key.clone(), offset, end).await?;
+        if bytes.is_empty() {
+            return Err("Empty response".to_string());
+        }
+        file.write_all(&bytes).await.map_err(|e| e.to_string())?;
+        offset += bytes.len() as u64;
+        let _ = output.send(TransferEvent::Progress(offset, size)).await;
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+    drop(file);
+    tokio::fs::rename(&partial, local)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 下载中的临时文件
+fn partial_path(local: &Path) -> PathBuf {
+    let mut name = local.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    local.with_file_name(name)
+}
@@ -0,0 +1,55 @@
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+/// 已上传文件的 MD5，键为“存储桶标识/对象键”，离线时用来判断文件是否已经上传过
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadManifest {
+    hashes: HashMap<String, String>,
+}
+
+impl UploadManifest {
+    pub fn load_from_file(path: PathBuf) -> Result<UploadManifest, Box<dyn Error>> {
+        if !path.is_file() {
+            return Ok(UploadManifest::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, bucket: &str, key: &str) -> Option<&String> {
+        self.hashes.get(&format!("{}/{}", bucket, key))
+    }
+
+    pub fn record(&mut self, bucket: &str, key: &str, md5: String) {
+        self.hashes.insert(format!("{}/{}", bucket, key), md5);
+    }
+}
+
+/// 清单文件的位置
+pub fn manifest_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("uploads.json"))
+}
+
+/// 文件内容的 MD5（小写十六进制），与单次上传的对象 ETag 相同
+pub async fn file_md5(path: &Path) -> Result<String, String> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| e.to_string())?;
+    Ok(bytes_md5(&bytes))
+}
+
+/// 内存中的内容的 MD5（小写十六进制）
+pub fn bytes_md5(bytes: &[u8]) -> String {
+    hex::encode(Md5::digest(bytes))
+}
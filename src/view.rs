@@ -0,0 +1,5376 @@
+use iced::widget::image::Handle;
+use iced::widget::scrollable::Direction;
+use iced::widget::{
+    Stack, button, checkbox, progress_bar, slider, text_editor, text_input, tooltip,
+};
+use iced::{Background, Color, Vector, window};
+use iced::{
+    Element, Length, Theme,
+    alignment::Horizontal,
+    widget::{column, container, row, scrollable, text},
+};
+use image::metadata::Orientation;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::adjustments::Adjustments;
+use crate::batch::{BatchOptions, BatchScope, ItemStatus};
+use crate::blink::Blink;
+use crate::catalog::AddedWithin;
+use crate::channels::Channel;
+use crate::cloud::RemoteEdit;
+use crate::cloud_storage::{OssConfig, Provider, S3Config, ShareExpiry};
+use crate::codes::{CodeOverlay, CodeScan};
+use crate::color_picker::{self, ColorFormat, ColorPickerOverlay};
+use crate::config::{Accent, Backdrop, SortOrder, ThemeMode, ThumbnailStrip};
+use crate::contact_sheet::{CELL_SIZES, SheetFormat, SheetOptions};
+use crate::context_menu::context_menu;
+use crate::crop::{AspectRatio, CropOverlay};
+use crate::export::{EncodeOptions, ExportFormat, PngCompression, SizeUnit};
+use crate::file_tree::{Access, FileTreeEntry, NamePrompt, file_name, smart_folder_name};
+use crate::filters::Filter;
+use crate::frames::Animation;
+use crate::gallery::{GalleryOptions, GalleryStep, GalleryWizard, IMAGE_SIZES, THUMBNAIL_SIZES};
+use crate::history::Rotation;
+use crate::i18n::{Language, tr, trf};
+use crate::keymap::Action;
+use crate::map::{self, MapCanvas};
+use crate::messages::{Message, SettingsPage};
+use crate::palette::{self, Palette};
+use crate::perf::Stage;
+use crate::pipeline::ResamplingType;
+use crate::remote_cache::CacheLimit;
+use crate::state::State;
+use crate::straighten::StraightenOverlay;
+use crate::sync::ConflictPolicy;
+use crate::timeline::{Timeline, TimelineSource};
+use crate::transfer_queue::{TransferDirection, TransferStatus};
+use crate::viewer::Viewer;
+use crate::xmp_iptc::Flag;
+use crate::{
+    button_style, catalog, cloud, config, logging, memory, perf, plugins, smart_directory,
+    straighten, sync, throttle, toast,
+};
+
+// 加载动画的帧
+const SPINNER_FRAMES: [&str; 4] = ["◐", "◓", "◑", "◒"];
+
+// 文件名搜索框的标识，打开搜索栏时聚焦
+pub const FIND_INPUT: &str = "find";
+
+// 图片查看区域的容器，缩放到 100% 时按它的大小计算
+pub const VIEWER: &str = "viewer";
+
+impl State {
+    pub fn view(&self, window: window::Id) -> Element<'_, Message> {
+        match self.viewers.get(&window) {
+            Some(viewer) => self.view_viewer(window, viewer),
+            None => self.view_main(),
+        }
+    }
+
+    fn view_main(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        perf::count_view();
+        let top_bar = container(
+            row![
+                // Left: App logo and title
+                row![
+                    text("📷").size(24).shaping(text::Shaping::Advanced),
+                    text("Image Browser")
+                        .size(18)
+                        .color(palette.text)
+                        .font(iced::Font::MONOSPACE)
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Fill),
+                // Center: Navigation buttons
+                row![
+                    button(ui_text(tr("Open")).size(14))
+                        .on_press(Message::SelectImage)
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                    button(ui_text(tr("Next")).size(14))
+                        .on_press(Message::PickNextImage)
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                    button(ui_text(tr("Previous")).size(14))
+                        .on_press(Message::PickPreviousImage)
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                    button(ui_text(tr("Zoom")).size(14))
+                        .on_press(Message::OpenResamplingBar)
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                    button(text("🖐").shaping(text::Shaping::Advanced).size(14))
+                        .on_press(Message::ToggleHandTool)
+                        .style(move |theme, status| {
+                            if self.hand_tool_active {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Adjust")).size(14))
+                        .on_press(Message::ToggleAdjustPanel)
+                        .style(move |theme, status| {
+                            if self.adjust_panel_opened {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Crop")).size(14))
+                        .on_press_maybe(self.original.as_ref().map(|_| Message::ToggleCropMode))
+                        .style(move |theme, status| {
+                            if self.crop_mode {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Straighten")).size(14))
+                        .on_press_maybe(
+                            self.original
+                                .as_ref()
+                                .map(|_| Message::ToggleStraightenMode)
+                        )
+                        .style(move |theme, status| {
+                            if self.straighten_mode {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    hover_tip(
+                        button(ui_text(tr("Blink")).size(14))
+                            .on_press_maybe(
+                                (self.blink.is_some() || self.blink_pair().is_some())
+                                    .then_some(Message::ToggleBlink)
+                            )
+                            .style(move |theme, status| {
+                                if self.blink.is_some() {
+                                    button_style::primary(theme, status)
+                                } else {
+                                    button_style::default(theme, status)
+                                }
+                            })
+                            .padding([6, 12]),
+                        tr("Alternate between two selected images").to_string(),
+                        tooltip::Position::Bottom
+                    ),
+                    hover_tip(
+                        button(ui_text(tr("Scan codes")).size(14))
+                            .on_press_maybe(self.original.as_ref().map(|_| Message::ScanCodes))
+                            .style(move |theme, status| {
+                                if self.code_scan.is_some() {
+                                    button_style::primary(theme, status)
+                                } else {
+                                    button_style::default(theme, status)
+                                }
+                            })
+                            .padding([6, 12]),
+                        tr("Find QR codes and barcodes in the image").to_string(),
+                        tooltip::Position::Bottom
+                    ),
+                    hover_tip(
+                        button(ui_text(tr("Pick color")).size(14))
+                            .on_press_maybe(
+                                self.original.as_ref().map(|_| Message::ToggleColorPicker)
+                            )
+                            .style(move |theme, status| {
+                                if self.color_picking {
+                                    button_style::primary(theme, status)
+                                } else {
+                                    button_style::default(theme, status)
+                                }
+                            })
+                            .padding([6, 12]),
+                        tr("Copy the color under the cursor").to_string(),
+                        tooltip::Position::Bottom
+                    ),
+                    button(ui_text(tr("Export")).size(14))
+                        .on_press_maybe(self.original.as_ref().map(|_| Message::ToggleExportDialog))
+                        .style(move |theme, status| {
+                            if self.export_dialog_opened {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Save")).size(14))
+                        .on_press_maybe(self.is_modified().then_some(Message::Save))
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                    button(ui_text(tr("Save as…")).size(14))
+                        .on_press_maybe(self.original.as_ref().map(|_| Message::SaveAs))
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                    button(ui_text(tr("Search")).size(14))
+                        .on_press(Message::ToggleSearchPanel)
+                        .style(move |theme, status| {
+                            if self.search_panel_opened {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Info")).size(14))
+                        .on_press(Message::ToggleInfoPanel)
+                        .style(move |theme, status| {
+                            if self.info_panel_opened {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Timeline")).size(14))
+                        .on_press(Message::ToggleTimeline)
+                        .style(move |theme, status| {
+                            if self.timeline.is_some() {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                    button(ui_text(tr("Map")).size(14))
+                        .on_press(Message::ToggleMap)
+                        .style(move |theme, status| {
+                            if self.map.is_some() {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .padding([6, 12]),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .width(Length::Shrink),
+                // Right: Theme and fullscreen buttons
+                container(
+                    row![
+                        button(
+                            text(if self.is_dark() { "☀" } else { "☾" })
+                                .shaping(text::Shaping::Advanced)
+                                .size(14)
+                        )
+                        .on_press(Message::ToggleTheme)
+                        .style(button_style::default)
+                        .padding([6, 10]),
+                        button(ui_text(tr("Fullscreen")).size(14))
+                            .on_press(Message::ToggleFullscreen)
+                            .style(button_style::primary)
+                            .padding([8, 16]),
+                    ]
+                    .spacing(8)
+                    .align_y(iced::Alignment::Center)
+                )
+                .align_x(Horizontal::Right)
+                .width(Length::Fill),
+            ]
+            .align_y(iced::Alignment::Center)
+            .spacing(20),
+        )
+        .padding([12, 20])
+        .style(move |_theme| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            border: iced::Border {
+                radius: 0.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            shadow: iced::Shadow {
+                offset: Vector::new(0.0, 1.0),
+                blur_radius: 3.0,
+                color: Color::from_rgba8(0, 0, 0, 0.1),
+            },
+            ..Default::default()
+        });
+        let file_tree_content = self
+            .root_file_tree_entry
+            .iter()
+            .map(|root| self.view_file_tree(root, 0, None));
+
+        let file_tree = container(
+            scrollable(
+                column(file_tree_content)
+                    .spacing(8)
+                    .width(Length::Fill)
+                    .padding([8, 12]),
+            )
+            .style(move |_theme, _| iced::widget::scrollable::Style {
+                container: container::Style {
+                    background: Some(Background::Color(Color::TRANSPARENT)),
+                    ..Default::default()
+                },
+                vertical_rail: iced::widget::scrollable::Rail {
+                    background: Some(Background::Color(Color {
+                        a: 0.1,
+                        ..palette.text
+                    })),
+                    border: iced::Border {
+                        radius: 2.0.into(),
+                        width: 0.0,
+                        color: Color::TRANSPARENT,
+                    },
+                    scroller: iced::widget::scrollable::Scroller {
+                        color: Color {
+                            a: 0.3,
+                            ..palette.text
+                        },
+                        border: iced::Border {
+                            radius: 2.0.into(),
+                            width: 0.0,
+                            color: Color::TRANSPARENT,
+                        },
+                    },
+                },
+                horizontal_rail: iced::widget::scrollable::Rail {
+                    background: Some(Background::Color(Color::TRANSPARENT)),
+                    border: iced::Border::default(),
+                    scroller: iced::widget::scrollable::Scroller {
+                        color: Color::TRANSPARENT,
+                        border: iced::Border::default(),
+                    },
+                },
+                gap: Some(Background::Color(Color::TRANSPARENT)),
+            }),
+        )
+        .width(280)
+        .height(Length::Fill)
+        .padding([16, 0])
+        .style(move |_theme: &Theme| container::Style {
+            background: Some(Background::Color(palette.surface_alt)),
+            border: iced::Border {
+                radius: 0.0.into(),
+                width: 1.0,
+                color: palette.border,
+            },
+            ..Default::default()
+        });
+
+        let main_image_display: iced::Element<_> = {
+            let handle = if let Some(frame) = self.blink.as_ref().and_then(Blink::frame) {
+                // 闪烁比较中，显示两张图片之一
+                frame.clone()
+            } else if (self.is_resampling_mode || self.hand_tool_active)
+                && !self.scaled_bytes.is_empty()
+            {
+                // 使用当前的缩放图像（可能是预览质量或高质量）
+                if self.is_dragging || self.is_panning {
+                    // 如果正在拖动滑块或拖动图片，使用预览缩放后的图片
+                    iced::widget::image::Handle::from_bytes(self.preview_scaled_bytes.clone())
+                } else {
+                    // 如果不是拖动状态，使用最终高质量缩放后的图片
+                    iced::widget::image::Handle::from_bytes(self.final_scaled_bytes.clone())
+                }
+            } else {
+                // 如果没有缩放后的图片，使用原始图片
+                if let Some(handle) = &self.display_handle {
+                    handle.clone()
+                } else if let Some(path) = &self.current_image {
+                    iced::widget::image::Handle::from_path(path)
+                } else {
+                    // 创建一个空白占位符
+                    iced::widget::image::Handle::from_rgba(
+                        400,
+                        300,
+                        palette.surface_alt.into_rgba8().repeat(400 * 300),
+                    )
+                }
+            };
+
+            // 图片无法打开时显示错误占位
+            // 裁剪和拉直模式下用画布显示图片，以便拖拽裁剪框或叠加参考网格
+            let img: Element<_> = if let Some(error) = &self.load_error {
+                self.view_load_error(error, palette)
+            } else {
+                match (&self.overlay_handle, self.display_source()) {
+                    (Some(overlay_handle), Some(ori)) if self.crop_mode => {
+                        iced::widget::canvas(CropOverlay {
+                            handle: overlay_handle,
+                            image_size: ori.dimensions(),
+                            selection: self.crop_selection,
+                            aspect: self.crop_aspect,
+                        })
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into()
+                    }
+                    (Some(overlay_handle), Some(ori)) if self.straighten_mode => {
+                        iced::widget::canvas(StraightenOverlay {
+                            handle: overlay_handle,
+                            image_size: ori.dimensions(),
+                            angle: self.straighten_angle,
+                        })
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into()
+                    }
+                    (Some(overlay_handle), Some(ori)) if self.code_scan.is_some() => {
+                        let codes = self
+                            .code_scan
+                            .as_ref()
+                            .and_then(|scan| scan.codes.as_deref())
+                            .unwrap_or_default();
+                        iced::widget::canvas(CodeOverlay {
+                            handle: overlay_handle,
+                            image_size: ori.dimensions(),
+                            codes,
+                        })
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into()
+                    }
+                    (Some(overlay_handle), Some(ori)) if self.color_picking => {
+                        iced::widget::canvas(ColorPickerOverlay {
+                            handle: overlay_handle,
+                            image: ori,
+                        })
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into()
+                    }
+                    _ => iced::widget::image(handle)
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .content_fit(iced::ContentFit::Contain)
+                        .into(),
+                }
+            };
+
+            // 用现代化的容器包装图片
+            let backdrop = self.config.backdrop.color(&palette);
+            let positioned: Element<_> = if self.is_fullscreen {
+                // 全屏模式：去除所有装饰，让图片占满屏幕
+                container(img)
+                    .id(container::Id::new(VIEWER))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .padding(0)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill)
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(Color::BLACK)),
+                        border: iced::Border::default(),
+                        shadow: iced::Shadow::default(),
+                        ..Default::default()
+                    })
+                    .into()
+            } else {
+                // 非全屏模式：保持原有装饰样式
+                container(img)
+                    .id(container::Id::new(VIEWER))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .padding(20)
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(backdrop)),
+                        border: iced::Border {
+                            radius: 8.0.into(),
+                            width: 1.0,
+                            color: palette.border,
+                        },
+                        shadow: iced::Shadow {
+                            offset: Vector::new(0.0, 2.0),
+                            blur_radius: 8.0,
+                            color: Color::from_rgba8(0, 0, 0, 0.1),
+                        },
+                        ..Default::default()
+                    })
+                    .into()
+            };
+
+            // 只在打开时显示滑块和算法选择
+            let slider_layer = if self.resampling_bar_opened {
+                // 手动添加每个按钮，避免使用extend和map
+                let buttons = ResamplingType::all()
+                    .iter()
+                    .map(|&resampling_type| {
+                        let is_selected = resampling_type == self.resampling_type;
+                        let btn: Element<_> = button(text(resampling_type.name()).size(12))
+                            .padding([6, 12])
+                            .style(move |theme, status| {
+                                if is_selected {
+                                    button_style::primary(theme, status)
+                                } else {
+                                    button_style::default(theme, status)
+                                }
+                            })
+                            .on_press(Message::ResamplingTypeChanged(resampling_type))
+                            .into();
+                        btn
+                    })
+                    .collect::<Vec<_>>();
+
+                let algorithm_buttons = row(buttons).spacing(8).padding([8, 0]);
+
+                let channel_buttons = row(Channel::ALL.map(|channel| {
+                    let selected = channel == self.channel;
+                    let btn = button(text(channel.label()).size(12))
+                        .padding([6, 12])
+                        .style(move |theme, status| {
+                            if selected {
+                                button_style::primary(theme, status)
+                            } else {
+                                button_style::default(theme, status)
+                            }
+                        })
+                        .on_press(Message::ChannelChanged(channel));
+                    hover_tip(btn, channel.name().to_string(), tooltip::Position::Bottom)
+                }))
+                .spacing(8);
+
+                // 组合滑块和算法选择
+                container(
+                    column![
+                        ui_text(tr("Zoom Level")).size(14).color(palette.heading),
+                        slider(50..=150, self.slider_value, Message::SliderChanged)
+                            .default(50)
+                            .shift_step(5)
+                            .on_release(Message::SliderReleased)
+                            .style(move |_theme, _| iced::widget::slider::Style {
+                                rail: iced::widget::slider::Rail {
+                                    backgrounds: (
+                                        Background::Color(palette.border),
+                                        Background::Color(palette.accent),
+                                    ),
+                                    width: 4.0,
+                                    border: iced::Border {
+                                        color: Color::TRANSPARENT,
+                                        width: 2.0,
+                                        radius: 2.0.into()
+                                    },
+                                },
+                                handle: iced::widget::slider::Handle {
+                                    shape: iced::widget::slider::HandleShape::Circle {
+                                        radius: 8.0
+                                    },
+                                    background: Background::Color(palette.surface),
+                                    border_color: palette.accent,
+                                    border_width: 2.0,
+                                },
+                            }),
+                        checkbox(
+                            tr("Keep zoom and position when switching images"),
+                            self.view_locked
+                        )
+                        .text_shaping(text::Shaping::Advanced)
+                        .on_toggle(Message::ViewLockToggled)
+                        .size(14)
+                        .text_size(12),
+                        ui_text(tr("Resampling Algorithm"))
+                            .size(14)
+                            .color(palette.heading),
+                        algorithm_buttons,
+                        ui_text(tr("Channel")).size(14).color(palette.heading),
+                        channel_buttons
+                    ]
+                    .spacing(12),
+                )
+                .width(380)
+                .padding(16)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(palette.surface)),
+                    border: iced::Border {
+                        radius: 12.0.into(),
+                        width: 1.0,
+                        color: palette.border,
+                    },
+                    shadow: iced::Shadow {
+                        offset: Vector::new(0.0, 4.0),
+                        blur_radius: 12.0,
+                        color: palette.shadow,
+                    },
+                    ..Default::default()
+                })
+                .center_x(380)
+                .align_y(iced::alignment::Vertical::Top)
+                .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let info_layer: Element<_> = if self.info_panel_opened {
+                container(self.view_info_panel())
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Right)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let search_layer: Element<_> = if self.search_panel_opened {
+                container(self.view_search_panel()).padding(16).into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let export_layer: Element<_> = if self.export_dialog_opened {
+                container(self.view_export_dialog())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill)
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                        ..Default::default()
+                    })
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let adjust_layer: Element<_> = if self.adjust_panel_opened {
+                container(self.view_adjust_panel())
+                    .height(Length::Fill)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let loading_layer: Element<_> = if self.loading.is_some() {
+                container(
+                    container(
+                        row![
+                            text(SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()])
+                                .shaping(text::Shaping::Advanced)
+                                .size(16),
+                            ui_text(tr("Loading…")).size(13),
+                        ]
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center),
+                    )
+                    .padding([8, 16])
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.6))),
+                        text_color: Some(Color::WHITE),
+                        border: iced::Border {
+                            radius: 16.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let batch_layer: Element<_> = if self.batch_dialog_opened {
+                container(self.view_batch_dialog())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill)
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                        ..Default::default()
+                    })
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let perf_layer: Element<_> = if self.perf_hud_opened {
+                container(self.view_perf_hud())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Left)
+                    .align_y(iced::alignment::Vertical::Top)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let log_layer: Element<_> = if self.log_panel_opened {
+                container(self.view_log_panel())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Left)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let jobs_layer: Element<_> = if self.jobs_panel_opened {
+                container(self.view_jobs_panel())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Right)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let map_layer: Element<_> = if let Some(map) = &self.map {
+                container(self.view_map_panel(map))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Right)
+                    .align_y(iced::alignment::Vertical::Top)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let convert_layer: Element<_> = if self.convert_dialog_opened {
+                container(self.view_convert_dialog())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill)
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                        ..Default::default()
+                    })
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            let crop_layer: Element<_> = if self.crop_mode || self.straighten_mode {
+                let toolbar = if self.crop_mode {
+                    self.view_crop_toolbar()
+                } else {
+                    self.view_straighten_toolbar()
+                };
+                container(toolbar)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else if let Some(scan) = &self.code_scan {
+                container(self.view_codes_toolbar(scan))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else if self.color_picking {
+                container(self.view_color_toolbar())
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else if let Some(blink) = &self.blink {
+                container(self.view_blink_toolbar(blink))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else if let Some(animation) = &self.animation {
+                container(self.view_frame_toolbar(animation))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .padding(16)
+                    .into()
+            } else {
+                iced::Element::new(iced::widget::Space::new(0, 0))
+            };
+
+            // 如果启用了手型工具，包装图片在MouseArea中以捕获鼠标事件
+            let image_with_mouse_events: Element<_> = if self.hand_tool_active
+                && !self.crop_mode
+                && !self.straighten_mode
+                && self.code_scan.is_none()
+                && !self.color_picking
+            {
+                iced::widget::mouse_area(positioned)
+                    .on_press(Message::MousePressed(iced::mouse::Event::ButtonPressed(
+                        iced::mouse::Button::Left,
+                    )))
+                    .on_release(Message::MouseReleased(iced::mouse::Event::ButtonReleased(
+                        iced::mouse::Button::Left,
+                    )))
+                    .on_move(Message::MouseMoved)
+                    .into()
+            } else {
+                positioned
+            };
+            // 触控板双指滚动平移，捏合（Ctrl+滚动）缩放；双击切换全屏，中键恢复默认视图
+            let image_with_mouse_events: Element<_> = if self.crop_mode
+                || self.straighten_mode
+                || self.code_scan.is_some()
+                || self.color_picking
+            {
+                image_with_mouse_events
+            } else {
+                iced::widget::mouse_area(image_with_mouse_events)
+                    .on_scroll(Message::ViewerScrolled)
+                    .on_press(Message::ViewerPressed)
+                    .on_middle_press(Message::ViewerMiddlePressed)
+                    .into()
+            };
+            // 本地图片的右键菜单
+            let image_with_mouse_events = match self.current_image.clone() {
+                Some(path)
+                    if !cloud::is_remote(&path) && !self.crop_mode && !self.straighten_mode =>
+                {
+                    context_menu(image_with_mouse_events, move || {
+                        menu(vec![
+                            ("Copy path", Message::CopyPath(path.clone())),
+                            ("Rotate left", Message::Rotate(Rotation::Left)),
+                            ("Rotate right", Message::Rotate(Rotation::Right)),
+                            ("Upload to cloud", Message::UploadToCloud(path.clone())),
+                            ("Share…", Message::ShareImage(path.clone())),
+                            ("Set as wallpaper", Message::SetWallpaper(path.clone())),
+                            ("Extract text", Message::ExtractText),
+                            ("Find similar", Message::FindSimilar),
+                            ("Delete image", Message::DeleteImage),
+                        ])
+                    })
+                    .into()
+                }
+                _ => image_with_mouse_events,
+            };
+
+            if self.is_fullscreen {
+                // 全屏模式：简化布局，只显示图片和必要的滑块
+                container(
+                    Stack::new()
+                        .push(image_with_mouse_events) // 底层：带鼠标事件的图片
+                        .push(loading_layer) // 中层：加载提示
+                        .push(info_layer) // 中层：信息面板
+                        .push(search_layer) // 中层：搜索面板
+                        .push(adjust_layer) // 中层：调整面板
+                        .push(jobs_layer) // 中层：任务面板
+                        .push(map_layer) // 中层：地图面板
+                        .push(perf_layer) // 中层：性能面板
+                        .push(log_layer) // 中层：诊断日志
+                        .push(crop_layer) // 中层：裁剪/拉直工具栏
+                        .push(slider_layer) // 中层：滑块
+                        .push(export_layer) // 顶层：导出对话框
+                        .push(convert_layer) // 顶层：格式转换对话框
+                        .push(batch_layer), // 顶层：批处理对话框
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(0)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::BLACK)),
+                    ..Default::default()
+                })
+                .into()
+            } else {
+                // 非全屏模式：保持原有样式
+                container(
+                    Stack::new()
+                        .push(image_with_mouse_events) // 底层：带鼠标事件的图片
+                        .push(loading_layer) // 中层：加载提示
+                        .push(info_layer) // 中层：信息面板
+                        .push(search_layer) // 中层：搜索面板
+                        .push(adjust_layer) // 中层：调整面板
+                        .push(jobs_layer) // 中层：任务面板
+                        .push(map_layer) // 中层：地图面板
+                        .push(perf_layer) // 中层：性能面板
+                        .push(log_layer) // 中层：诊断日志
+                        .push(crop_layer) // 中层：裁剪/拉直工具栏
+                        .push(slider_layer) // 中层：滑块
+                        .push(export_layer) // 顶层：导出对话框
+                        .push(convert_layer) // 顶层：格式转换对话框
+                        .push(batch_layer), // 顶层：批处理对话框
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(16)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(palette.surface_alt)),
+                    ..Default::default()
+                })
+                .into()
+            }
+        };
+
+        let images = self.image_collection.clone();
+
+        // 元数据搜索标签，显示文件名搜索结果时换成搜索结果标签
+        let filter_chip: Element<_> = if let Some(query) = &self.find_results {
+            button(
+                ui_text(trf("Files matching “{}” ({})  ✕", &[&query, &images.len()]))
+                    .shaping(text::Shaping::Advanced)
+                    .size(12),
+            )
+            .style(button_style::highlighted)
+            .padding([4, 8])
+            .on_press(Message::ClearFindResults)
+            .into()
+        } else if let Some(name) = &self.similar_to {
+            button(
+                ui_text(trf(
+                    "Similar to “{}” ({})  ✕",
+                    &[&name, &images.len().saturating_sub(1)],
+                ))
+                .shaping(text::Shaping::Advanced)
+                .size(12),
+            )
+            .style(button_style::highlighted)
+            .padding([4, 8])
+            .on_press(Message::ClearFindResults)
+            .into()
+        } else if let Some(flag) = self.flag_filter {
+            let label = match flag {
+                Flag::Pick => trf("Picked ({})  ✕", &[&images.len()]),
+                Flag::Reject => trf("Rejected ({})  ✕", &[&images.len()]),
+            };
+            button(ui_text(label).shaping(text::Shaping::Advanced).size(12))
+                .style(button_style::highlighted)
+                .padding([4, 8])
+                .on_press(Message::ClearFindResults)
+                .into()
+        } else if let Some(query) = &self.active_search {
+            button(
+                text(format!("{}  ✕", query.summary()))
+                    .shaping(text::Shaping::Advanced)
+                    .size(12),
+            )
+            .style(button_style::highlighted)
+            .padding([4, 8])
+            .on_press(Message::ClearSearch)
+            .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        // 图片库来自本地文件夹时可以刷新，文件夹有变化时提示
+        let refresh_button: Element<_> = match &self.collection_folder {
+            Some(_) if self.folder_stale => button(
+                ui_text(tr("Folder changed · Refresh"))
+                    .shaping(text::Shaping::Advanced)
+                    .size(12),
+            )
+            .style(button_style::highlighted)
+            .padding([4, 8])
+            .on_press(Message::RefreshCollection)
+            .into(),
+            Some(_) => hover_tip(
+                button(text("⟳").shaping(text::Shaping::Advanced).size(12))
+                    .style(button_style::transparent)
+                    .padding([4, 8])
+                    .on_press(Message::RefreshCollection),
+                tr("Refresh folder").to_string(),
+                tooltip::Position::Top,
+            ),
+            None => iced::Element::new(iced::widget::Space::new(0, 0)),
+        };
+
+        // 挑片：只显示选中或排除的图片，显示排除的图片时可以一起移走或删除
+        let flag_button = |flag: Flag, label: &str, tip: &str| {
+            let active = self.flag_filter == Some(flag);
+            hover_tip(
+                button(
+                    text(label.to_string())
+                        .shaping(text::Shaping::Advanced)
+                        .size(12),
+                )
+                .style(move |theme, status| {
+                    if active {
+                        button_style::primary(theme, status)
+                    } else {
+                        button_style::transparent(theme, status)
+                    }
+                })
+                .padding([4, 8])
+                .on_press(Message::ShowFlagged(flag)),
+                tip.to_string(),
+                tooltip::Position::Top,
+            )
+        };
+        let mut flag_buttons = row![
+            flag_button(Flag::Pick, "✓", tr("Show picked")),
+            flag_button(Flag::Reject, "✕", tr("Show rejected")),
+        ]
+        .align_y(iced::Alignment::Center)
+        .spacing(4);
+        if self.flag_filter == Some(Flag::Reject) {
+            flag_buttons = flag_buttons
+                .push(
+                    button(ui_text(tr("Move rejects…")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press(Message::MoveRejects),
+                )
+                .push(
+                    button(ui_text(tr("Delete rejects")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press(Message::DeleteRejects),
+                );
+        }
+
+        // 多选时在缩略图上方显示批量操作
+        let selection_bar = (!self.selected_images.is_empty()).then(|| {
+            let tag_ready = !self.batch_tag.trim().is_empty();
+            container(
+                row![
+                    button(
+                        ui_text(trf("{} selected  ✕", &[&self.selected_images.len()]))
+                            .shaping(text::Shaping::Advanced)
+                            .size(12),
+                    )
+                    .style(button_style::highlighted)
+                    .padding([4, 8])
+                    .on_press(Message::ClearSelection),
+                    container(text("")).width(Length::Fill),
+                    button(ui_text(tr("Delete")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press(Message::DeleteSelected),
+                    button(ui_text(tr("Move to…")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press(Message::MoveSelected),
+                    button(ui_text(tr("Export…")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press(Message::ExportSelection),
+                    button(ui_text(tr("Upload")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press(Message::UploadSelected),
+                    text_input(tr("Keyword"), &self.batch_tag)
+                        .on_input(Message::BatchTagChanged)
+                        .on_submit(Message::TagSelected)
+                        .size(12)
+                        .padding([4, 8])
+                        .width(140),
+                    button(ui_text(tr("Tag")).size(12))
+                        .style(button_style::default)
+                        .padding([4, 8])
+                        .on_press_maybe(tag_ready.then_some(Message::TagSelected)),
+                ]
+                .align_y(iced::Alignment::Center)
+                .spacing(8),
+            )
+            .padding([4, 16])
+        });
+
+        let running_jobs = self.jobs.iter().filter(|job| !job.finished).count();
+        let jobs_label = if running_jobs > 0 {
+            trf("Jobs ({})", &[&running_jobs])
+        } else {
+            tr("Jobs").to_string()
+        };
+
+        let pending_transfers = self.transfers.pending();
+        let transfers_label = if pending_transfers > 0 {
+            trf("Transfers ({})", &[&pending_transfers])
+        } else {
+            tr("Transfers").to_string()
+        };
+
+        // 创建缩略图标题栏
+        let collapsed = self.config.thumbnail_strip == ThumbnailStrip::Collapsed;
+        let thumbnail_header = container(
+            row![
+                text("Tolores")
+                    .size(16)
+                    .color(palette.text)
+                    .font(iced::Font::MONOSPACE),
+                filter_chip,
+                refresh_button,
+                flag_buttons,
+                container(text("")).width(Length::Fill),
+                hover_tip(
+                    slider(
+                        config::THUMBNAIL_SIZE_MIN..=config::THUMBNAIL_SIZE_MAX,
+                        self.config.thumbnail_size,
+                        Message::ThumbnailSizeDragged,
+                    )
+                    .step(4u32)
+                    .on_release(Message::ThumbnailSizeReleased)
+                    .width(100),
+                    format!(
+                        "{} ({}px)",
+                        tr("Thumbnail size"),
+                        self.config.thumbnail_size
+                    ),
+                    tooltip::Position::Top,
+                ),
+                button(ui_text(tr("Batch…")).size(12))
+                    .style(button_style::default)
+                    .padding([4, 8])
+                    .on_press(Message::ToggleBatchDialog),
+                button(ui_text(tr("Script…")).size(12))
+                    .style(button_style::default)
+                    .padding([4, 8])
+                    .on_press(Message::ToggleScriptDialog),
+                button(ui_text(tr("Contact sheet…")).size(12))
+                    .style(button_style::default)
+                    .padding([4, 8])
+                    .on_press(Message::ToggleContactSheetDialog),
+                button(ui_text(jobs_label).size(12))
+                    .style(move |theme, status| {
+                        if self.jobs_panel_opened {
+                            button_style::primary(theme, status)
+                        } else {
+                            button_style::default(theme, status)
+                        }
+                    })
+                    .padding([4, 8])
+                    .on_press(Message::ToggleJobsPanel),
+                button(ui_text(transfers_label).size(12))
+                    .style(move |theme, status| {
+                        if self.transfers_panel_opened {
+                            button_style::primary(theme, status)
+                        } else {
+                            button_style::default(theme, status)
+                        }
+                    })
+                    .padding([4, 8])
+                    .on_press(Message::ToggleTransfersPanel),
+                button(ui_text(tr("Convert to…")).size(12))
+                    .style(button_style::default)
+                    .padding([4, 8])
+                    .on_press_maybe(
+                        (!self.convert_targets().is_empty())
+                            .then_some(Message::ToggleConvertDialog)
+                    ),
+                button(ui_text(tr("Upload")).size(12))
+                    .style(button_style::default)
+                    .padding([4, 8])
+                    .on_press_maybe(
+                        (!self.convert_targets().is_empty()).then_some(Message::UploadSelected)
+                    ),
+                button(text("⚙").shaping(text::Shaping::Advanced))
+                    .style(move |theme, status| {
+                        if self.settings_dialog_opened {
+                            button_style::primary(theme, status)
+                        } else {
+                            button_style::transparent(theme, status)
+                        }
+                    })
+                    .padding([4, 8])
+                    .on_press(Message::ToggleSettingsDialog),
+                // 折叠后只保留标题栏，隐藏后从状态栏或快捷键重新显示
+                button(text(if collapsed { "▴" } else { "▾" }).shaping(text::Shaping::Advanced))
+                    .style(button_style::transparent)
+                    .padding([4, 8])
+                    .on_press(Message::ThumbnailStripChanged(if collapsed {
+                        ThumbnailStrip::Shown
+                    } else {
+                        ThumbnailStrip::Collapsed
+                    })),
+                button(text("✕").shaping(text::Shaping::Advanced))
+                    .style(button_style::transparent)
+                    .padding([4, 8])
+                    .on_press(Message::ThumbnailStripChanged(ThumbnailStrip::Hidden)),
+            ]
+            .align_y(iced::Alignment::Center)
+            .spacing(8),
+        )
+        .padding([12, 16])
+        .style(move |_theme| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            border: iced::Border {
+                radius: 0.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            ..Default::default()
+        });
+
+        // 生成缩略图行
+        let thumbnail_size = self.config.thumbnail_size;
+        let thumbnails_row = row![]
+            .spacing(12)
+            .padding([0, 16])
+            .width(Length::Shrink)
+            .extend(images.into_iter().enumerate().map(|(idx, p)| {
+                let is_selected = idx == self.current_image_index;
+                let is_marked = self.selected_images.contains(&p);
+
+                let image_handle = if let Some(handle) = self.thumbnail_cache.get(&p) {
+                    handle.clone()
+                } else {
+                    Handle::from_rgba(
+                        thumbnail_size,
+                        thumbnail_size,
+                        palette
+                            .surface_alt
+                            .into_rgba8()
+                            .repeat((thumbnail_size * thumbnail_size) as usize),
+                    )
+                };
+
+                // 挑片标记显示在右上角，排除的图片变暗
+                let flag = self.file_info.get(&p).and_then(|info| info.flag);
+                let badge = flag.map(|flag| {
+                    let (mark, color) = match flag {
+                        Flag::Pick => ("✓", palette.success),
+                        Flag::Reject => ("✕", palette.danger),
+                    };
+                    container(
+                        container(
+                            text(mark)
+                                .shaping(text::Shaping::Advanced)
+                                .size(11)
+                                .color(Color::WHITE),
+                        )
+                        .padding([1, 5])
+                        .style(move |_theme| container::Style {
+                            background: Some(Background::Color(color)),
+                            border: iced::Border {
+                                radius: 8.0.into(),
+                                width: 0.0,
+                                color: Color::TRANSPARENT,
+                            },
+                            ..Default::default()
+                        }),
+                    )
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Right)
+                    .align_y(iced::alignment::Vertical::Top)
+                    .padding(4)
+                });
+                let thumbnail = button(
+                    Stack::new()
+                        .push(
+                            iced::widget::image(image_handle)
+                                .width(Length::Fixed(thumbnail_size as f32))
+                                .height(Length::Fixed(thumbnail_size as f32))
+                                .content_fit(iced::ContentFit::Cover)
+                                .opacity(if flag == Some(Flag::Reject) { 0.5 } else { 1.0 }),
+                        )
+                        .push_maybe(badge),
+                )
+                .style(move |theme, status| {
+                    if is_selected {
+                        button_style::thumbnail_selected(theme, status)
+                    } else if is_marked {
+                        button_style::thumbnail_marked(theme, status)
+                    } else {
+                        button_style::thumbnail(theme, status)
+                    }
+                })
+                .on_press(Message::PickImage(p.clone()));
+                // 停留一会儿后提示换成较大的预览
+                let preview = self
+                    .hover_preview
+                    .as_ref()
+                    .filter(|preview| **preview == p)
+                    .and_then(|preview| self.hover_previews.get(preview));
+                let thumbnail = match preview {
+                    Some(handle) => tooltip(
+                        thumbnail,
+                        self.view_hover_preview(&p, handle),
+                        tooltip::Position::Top,
+                    )
+                    .into(),
+                    None => hover_tip(thumbnail, self.file_tooltip(&p), tooltip::Position::Top),
+                };
+                let thumbnail: Element<_> = iced::widget::mouse_area(thumbnail)
+                    .on_enter(Message::ThumbnailHovered(p.clone()))
+                    .on_exit(Message::ThumbnailUnhovered(p.clone()))
+                    .into();
+                if cloud::is_remote(&p) {
+                    return thumbnail;
+                }
+                context_menu(thumbnail, move || {
+                    menu(vec![
+                        ("Open", Message::PickImage(p.clone())),
+                        ("Open in new window", Message::OpenInNewWindow(p.clone())),
+                        ("Rename…", Message::RenameFile(p.clone())),
+                        ("Remove from library", Message::RemoveFromLibrary(p.clone())),
+                    ])
+                })
+                .into()
+            }));
+
+        // 将行包装在水平滚动容器中
+        let thumbnails_scroll = scrollable(thumbnails_row)
+            .direction(Direction::Horizontal(scrollable::Scrollbar::new()))
+            .style(move |_theme, _| iced::widget::scrollable::Style {
+                container: container::Style {
+                    background: Some(Background::Color(palette.surface)),
+                    ..Default::default()
+                },
+                vertical_rail: iced::widget::scrollable::Rail {
+                    background: Some(Background::Color(Color::TRANSPARENT)),
+                    border: iced::Border::default(),
+                    scroller: iced::widget::scrollable::Scroller {
+                        color: Color::TRANSPARENT,
+                        border: iced::Border::default(),
+                    },
+                },
+                horizontal_rail: iced::widget::scrollable::Rail {
+                    background: Some(Background::Color(Color {
+                        a: 0.05,
+                        ..palette.text
+                    })),
+                    border: iced::Border {
+                        radius: 3.0.into(),
+                        width: 0.0,
+                        color: Color::TRANSPARENT,
+                    },
+                    scroller: iced::widget::scrollable::Scroller {
+                        color: Color {
+                            a: 0.3,
+                            ..palette.text
+                        },
+                        border: iced::Border {
+                            radius: 3.0.into(),
+                            width: 0.0,
+                            color: Color::TRANSPARENT,
+                        },
+                    },
+                },
+                gap: Some(Background::Color(Color::TRANSPARENT)),
+            });
+
+        // 折叠时只显示标题栏
+        let selection_height = if selection_bar.is_some() { 36.0 } else { 0.0 };
+        let (collection_display, strip_height) = if collapsed {
+            (
+                column![thumbnail_header].push_maybe(selection_bar),
+                Length::Shrink,
+            )
+        } else {
+            (
+                column![thumbnail_header]
+                    .push_maybe(selection_bar)
+                    .push(thumbnails_scroll),
+                Length::Fixed(thumbnail_size as f32 + 60.0 + selection_height),
+            )
+        };
+        let strip_hidden = self.config.thumbnail_strip == ThumbnailStrip::Hidden;
+
+        let content: Element<_> = if self.is_fullscreen {
+            // 全屏模式：只显示图片，隐藏其他UI元素
+            main_image_display
+        } else {
+            // 打开时间线时代替图片区域
+            let main_area = match &self.timeline {
+                Some(timeline) => self.view_timeline(timeline),
+                None => main_image_display,
+            };
+            // 非全屏模式：显示完整界面
+            let main_content = row![
+                file_tree,
+                column![self.view_tab_bar(), main_area]
+                    // 标题栏和滚动条占 60 像素，其余为缩略图
+                    .push_maybe((!strip_hidden).then(|| {
+                        container(collection_display)
+                            .height(strip_height)
+                            .width(Length::Fill)
+                            .style(move |_theme| container::Style {
+                                background: Some(Background::Color(palette.surface)),
+                                border: iced::Border {
+                                    radius: 0.0.into(),
+                                    width: 1.0,
+                                    color: palette.border,
+                                },
+                                shadow: iced::Shadow {
+                                    offset: Vector::new(0.0, -1.0),
+                                    blur_radius: 3.0,
+                                    color: Color::from_rgba8(0, 0, 0, 0.05),
+                                },
+                                ..Default::default()
+                            })
+                    }))
+                    .width(Length::FillPortion(4)) // This column takes the remaining space
+                    .height(Length::Fill) // Fill remaining height
+            ]
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+            column![top_bar, main_content, self.view_status_bar()]
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        };
+
+        let settings_layer: Element<_> = if self.settings_dialog_opened {
+            container(self.view_settings_dialog())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        let contact_sheet_layer: Element<_> = if self.contact_sheet_dialog_opened {
+            container(self.view_contact_sheet_dialog())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        let gallery_layer: Element<_> = if let Some(wizard) = &self.gallery_wizard {
+            container(self.view_gallery_wizard(wizard))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        let script_layer: Element<_> = if self.script_dialog_opened {
+            container(self.view_script_dialog())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        let transfers_layer: Element<_> = if self.transfers_panel_opened {
+            container(self.view_transfers_panel())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Horizontal::Left)
+                .align_y(iced::alignment::Vertical::Bottom)
+                .padding(16)
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        // 传输进度和提示浮在所有界面之上
+        let remote_edit_layer: Element<_> = if let Some(edit) = &self.remote_edit {
+            container(self.view_remote_edit_dialog(edit))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        let name_prompt_layer: Element<_> = if let Some(prompt) = &self.name_prompt {
+            container(self.view_name_prompt(prompt))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .style(move |_theme| container::Style {
+                    background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.3))),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        let find_layer: Element<_> = if self.find_bar_opened {
+            container(self.view_find_bar())
+                .width(Length::Fill)
+                .center_x(Length::Fill)
+                .padding(iced::Padding {
+                    top: 56.0,
+                    right: 16.0,
+                    bottom: 16.0,
+                    left: 16.0,
+                })
+                .into()
+        } else {
+            iced::Element::new(iced::widget::Space::new(0, 0))
+        };
+
+        Stack::new()
+            .push(content)
+            .push(find_layer)
+            .push(transfers_layer)
+            .push(settings_layer)
+            .push(script_layer)
+            .push(contact_sheet_layer)
+            .push(gallery_layer)
+            .push(remote_edit_layer)
+            .push(name_prompt_layer)
+            .push(self.view_notifications())
+            .into()
+    }
+
+    // 图片上方的标签页，只有一个标签页时不显示
+    fn view_tab_bar(&self) -> Element<'_, Message> {
+        if self.tabs.len() < 2 {
+            return iced::Element::new(iced::widget::Space::new(0, 0));
+        }
+        let palette = self.palette();
+        let tabs = self.tabs.iter().enumerate().map(|(index, tab)| {
+            let active = index == self.active_tab;
+            // 当前标签页的文件夹以 current_path 为准
+            let title = if active {
+                file_name(&self.current_path)
+            } else {
+                tab.title()
+            };
+            row![
+                button(ui_text(title).size(12))
+                    .on_press(Message::SelectTab(index))
+                    .style(move |theme, status| {
+                        if active {
+                            button_style::primary(theme, status)
+                        } else {
+                            button_style::default(theme, status)
+                        }
+                    })
+                    .padding([4, 10]),
+                button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                    .on_press(Message::CloseTab(index))
+                    .style(button_style::transparent)
+                    .padding([4, 6]),
+            ]
+            .align_y(iced::Alignment::Center)
+            .into()
+        });
+        container(
+            row(tabs)
+                .push(
+                    button(text("+").size(14))
+                        .on_press(Message::NewTab)
+                        .style(button_style::transparent)
+                        .padding([2, 8]),
+                )
+                .spacing(4)
+                .align_y(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .padding([4, 8])
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            ..container::Style::default()
+        })
+        .into()
+    }
+
+    // 查看窗口：图片占满窗口，下方是切换按钮和位置
+    fn view_viewer<'a>(&'a self, window: window::Id, viewer: &'a Viewer) -> Element<'a, Message> {
+        let palette = self.palette();
+        let img: Element<_> = match viewer.handle() {
+            Some(handle) => iced::widget::image(handle)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .content_fit(iced::ContentFit::Contain)
+                .into(),
+            None => ui_text(if viewer.scan.is_some() {
+                tr("Loading…")
+            } else {
+                tr("No images")
+            })
+            .size(14)
+            .color(palette.muted)
+            .into(),
+        };
+        let position = match viewer.current() {
+            Some(path) => format!(
+                "{} · {}/{}",
+                file_name(path),
+                viewer.index + 1,
+                viewer.images.len()
+            ),
+            None => String::new(),
+        };
+        let has_others = viewer.images.len() > 1;
+        let bar = container(
+            row![
+                button(text("‹").size(16))
+                    .on_press_maybe(has_others.then_some(Message::ViewerPrevious(window)))
+                    .style(button_style::default)
+                    .padding([4, 12]),
+                ui_text(position).size(13).color(palette.text),
+                button(text("›").size(16))
+                    .on_press_maybe(has_others.then_some(Message::ViewerNext(window)))
+                    .style(button_style::default)
+                    .padding([4, 12]),
+            ]
+            .spacing(12)
+            .align_y(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .center_x(Length::Fill)
+        .padding(8)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            ..container::Style::default()
+        });
+        let backdrop = self.config.backdrop.color(&palette);
+        column![
+            container(img)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center(Length::Fill)
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(backdrop)),
+                    ..container::Style::default()
+                }),
+            bar,
+        ]
+        .into()
+    }
+
+    // 图片无法打开时代替图片显示的占位
+    fn view_load_error(&self, error: &str, palette: Palette) -> Element<'_, Message> {
+        let name = self
+            .current_image
+            .as_deref()
+            .map(file_name)
+            .unwrap_or_default();
+        container(
+            column![
+                ui_text("⚠").size(40).color(palette.danger),
+                ui_text(tr("Cannot open this image"))
+                    .size(16)
+                    .color(palette.heading),
+                ui_text(name).size(13).color(palette.text),
+                ui_text(error.to_string()).size(12).color(palette.muted),
+            ]
+            .spacing(8)
+            .max_width(420)
+            .align_x(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into()
+    }
+
+    // 动画图片的帧滑块和导出按钮
+    fn view_frame_toolbar(&self, animation: &'_ Animation) -> Element<'_, Message> {
+        let palette = self.palette();
+        let last = animation.frames.len() as u32 - 1;
+        let content = row![
+            ui_text(trf(
+                "Frame {} / {}",
+                &[&(animation.current + 1), &animation.frames.len()]
+            ))
+            .size(12)
+            .color(palette.muted),
+            slider(0..=last, animation.current as u32, |index| {
+                Message::FrameSelected(index as usize)
+            })
+            .width(240),
+            button(ui_text(tr("Export frame")).size(12))
+                .on_press(Message::ExportFrame)
+                .style(button_style::default)
+                .padding([6, 12]),
+            button(ui_text(tr("Export all frames")).size(12))
+                .on_press(Message::ExportAllFrames)
+                .style(button_style::default)
+                .padding([6, 12]),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+
+        container(content)
+            .padding(12)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    // 识别结果列表：每个码的序号与画面上的标记对应
+    fn view_codes_toolbar(&self, scan: &'_ CodeScan) -> Element<'_, Message> {
+        let palette = self.palette();
+        let close = button(ui_text(tr("Close")).size(12))
+            .on_press(Message::ScanCodes)
+            .style(button_style::default)
+            .padding([6, 12]);
+        let content: Element<_> = match &scan.codes {
+            None => row![
+                ui_text(tr("Scanning…")).size(12).color(palette.muted),
+                close
+            ]
+            .spacing(12)
+            .align_y(iced::Alignment::Center)
+            .into(),
+            Some(codes) if codes.is_empty() => row![
+                ui_text(tr("No codes found")).size(12).color(palette.muted),
+                close
+            ]
+            .spacing(12)
+            .align_y(iced::Alignment::Center)
+            .into(),
+            Some(codes) => {
+                let rows = codes.iter().enumerate().map(|(index, code)| {
+                    let open: Element<_> = if code.is_link() {
+                        button(ui_text(tr("Open")).size(12))
+                            .on_press(Message::OpenUrl(code.text.trim().to_string()))
+                            .style(button_style::default)
+                            .padding([6, 12])
+                            .into()
+                    } else {
+                        iced::widget::Space::new(0, 0).into()
+                    };
+                    row![
+                        text(format!("{}", index + 1))
+                            .size(12)
+                            .color(palette.accent),
+                        text(code.kind.name()).size(12).color(palette.muted),
+                        text(code.text.clone())
+                            .size(12)
+                            .shaping(text::Shaping::Advanced)
+                            .width(Length::Fixed(320.0)),
+                        button(ui_text(tr("Copy")).size(12))
+                            .on_press(Message::CopyCode(code.text.clone()))
+                            .style(button_style::default)
+                            .padding([6, 12]),
+                        open,
+                    ]
+                    .spacing(12)
+                    .align_y(iced::Alignment::Center)
+                    .into()
+                });
+                column![
+                    scrollable(column(rows).spacing(6)).height(Length::Shrink),
+                    close
+                ]
+                .spacing(8)
+                .align_x(Horizontal::Right)
+                .into()
+            }
+        };
+
+        container(content)
+            .padding(12)
+            .max_height(240)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    // 取色工具栏：光标处的颜色、复制的写法和最近选取的颜色
+    fn view_color_toolbar(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let swatch = move |color: [u8; 3], size: f32| {
+            container(iced::widget::Space::new(size, size)).style(move |_theme| container::Style {
+                background: Some(Background::Color(Color::from_rgb8(
+                    color[0], color[1], color[2],
+                ))),
+                border: iced::Border {
+                    radius: 4.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                ..Default::default()
+            })
+        };
+
+        let hovered: Element<_> = match self.hovered_color {
+            Some(color) => row![
+                swatch(color, 24.0),
+                text(self.config.color_format.format(color))
+                    .size(12)
+                    .font(iced::Font::MONOSPACE)
+                    .width(Length::Fixed(150.0)),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into(),
+            None => ui_text(tr("Click the image to pick a color"))
+                .size(12)
+                .color(palette.muted)
+                .width(Length::Fixed(182.0))
+                .into(),
+        };
+        let formats = row(ColorFormat::ALL.map(|format| {
+            choice_button(
+                format.name(),
+                self.config.color_format == format,
+                Message::ColorFormatChanged(format),
+            )
+        }))
+        .spacing(4);
+
+        let history = row(self
+            .config
+            .picked_colors
+            .iter()
+            .filter_map(|hex| color_picker::parse_hex(hex))
+            .map(|color| {
+                hover_tip(
+                    button(swatch(color, 20.0))
+                        .on_press(Message::CopyColor(color))
+                        .style(button_style::transparent)
+                        .padding(2),
+                    color_picker::to_hex(color),
+                    tooltip::Position::Top,
+                )
+            }))
+        .spacing(2);
+        let has_history = !self.config.picked_colors.is_empty();
+
+        container(
+            row![
+                hovered,
+                formats,
+                history,
+                button(ui_text(tr("Export palette…")).size(12))
+                    .on_press_maybe(has_history.then_some(Message::ExportPalette))
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(tr("Clear")).size(12))
+                    .on_press_maybe(has_history.then_some(Message::ClearPickedColors))
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(tr("Close")).size(12))
+                    .on_press(Message::ToggleColorPicker)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+            ]
+            .spacing(12)
+            .align_y(iced::Alignment::Center),
+        )
+        .padding(12)
+        .style(move |_theme| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            border: iced::Border {
+                radius: 12.0.into(),
+                width: 1.0,
+                color: palette.border,
+            },
+            shadow: iced::Shadow {
+                offset: Vector::new(0.0, 4.0),
+                blur_radius: 12.0,
+                color: palette.shadow,
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn view_blink_toolbar(&self, blink: &'_ Blink) -> Element<'_, Message> {
+        let palette = self.palette();
+        // 正在显示的图片名称高亮
+        let names = blink.paths.iter().enumerate().map(|(index, path)| {
+            let color = if blink.frames.is_some() && index == blink.showing {
+                palette.accent
+            } else {
+                palette.muted
+            };
+            text(file_name(path)).size(12).color(color).into()
+        });
+        let intervals = config::BLINK_INTERVALS.map(|interval| {
+            choice_button(
+                format!("{} ms", interval),
+                interval == self.config.blink_interval,
+                Message::BlinkIntervalChanged(interval),
+            )
+        });
+        let status: Element<_> = if blink.frames.is_some() {
+            button(ui_text(tr(if blink.paused { "Resume" } else { "Pause" })).size(12))
+                .on_press(Message::PauseBlink)
+                .style(button_style::default)
+                .padding([6, 12])
+                .into()
+        } else {
+            ui_text(tr("Loading…")).size(12).color(palette.muted).into()
+        };
+        let content = row![
+            row(names).spacing(8),
+            ui_text(tr("Interval")).size(12).color(palette.muted),
+            row(intervals).spacing(4),
+            status,
+            button(ui_text(tr("Close")).size(12))
+                .on_press(Message::ToggleBlink)
+                .style(button_style::default)
+                .padding([6, 12]),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+
+        container(content)
+            .padding(12)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_straighten_toolbar(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let content = row![
+            ui_text(tr("Angle")).size(12).color(palette.muted),
+            slider(
+                -straighten::MAX_ANGLE..=straighten::MAX_ANGLE,
+                self.straighten_angle,
+                Message::StraightenAngleChanged
+            )
+            .step(0.1)
+            .width(320),
+            text(format!("{:+.1}°", self.straighten_angle))
+                .size(12)
+                .width(48),
+            button(ui_text(tr("Reset")).size(12))
+                .on_press_maybe(
+                    (self.straighten_angle != 0.0).then_some(Message::StraightenAngleChanged(0.0))
+                )
+                .style(button_style::default)
+                .padding([6, 12]),
+            button(ui_text(tr("Apply")).size(12))
+                .on_press_maybe((self.straighten_angle != 0.0).then_some(Message::ApplyStraighten))
+                .style(button_style::primary)
+                .padding([6, 12]),
+            button(ui_text(tr("Cancel")).size(12))
+                .on_press(Message::ToggleStraightenMode)
+                .style(button_style::default)
+                .padding([6, 12]),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+
+        container(content)
+            .padding(12)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_crop_toolbar(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let aspect_buttons = AspectRatio::all().into_iter().map(|aspect| {
+            let is_selected = aspect == self.crop_aspect;
+            button(text(aspect.name()).size(12))
+                .padding([6, 12])
+                .style(move |theme, status| {
+                    if is_selected {
+                        button_style::primary(theme, status)
+                    } else {
+                        button_style::default(theme, status)
+                    }
+                })
+                .on_press(Message::CropAspectChanged(aspect))
+                .into()
+        });
+
+        let size_label = match (self.crop_selection, self.display_source()) {
+            (Some(selection), Some(ori)) => {
+                let (_, _, w, h) = selection.to_pixels(ori.width(), ori.height());
+                format!("{} × {}", w, h)
+            }
+            _ => tr("Drag on the image to select").to_string(),
+        };
+        let save_message = |message: Message| self.crop_selection.map(|_| message);
+
+        let content = row![
+            row(aspect_buttons).spacing(8),
+            ui_text(size_label).size(12).color(palette.muted),
+            button(ui_text(tr("Apply")).size(12))
+                .on_press_maybe(save_message(Message::ApplyCrop))
+                .style(button_style::primary)
+                .padding([6, 12]),
+            button(ui_text(tr("Save as…")).size(12))
+                .on_press_maybe(save_message(Message::SaveCropAs))
+                .style(button_style::default)
+                .padding([6, 12]),
+            button(ui_text(tr("Overwrite…")).size(12))
+                .on_press_maybe(save_message(Message::OverwriteWithCrop))
+                .style(button_style::default)
+                .padding([6, 12]),
+            button(ui_text(tr("Cancel")).size(12))
+                .on_press(Message::ToggleCropMode)
+                .style(button_style::default)
+                .padding([6, 12]),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+
+        container(content)
+            .padding(12)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_export_dialog(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let settings = &self.export_settings;
+        let exporting = self.export_progress.is_some();
+
+        let source = self.original_dimensions();
+        let target_label = match settings.target_size(source) {
+            Some((w, h)) => format!("{} × {} → {} × {}", source.0, source.1, w, h),
+            None => tr("Invalid size").to_string(),
+        };
+
+        let units = [SizeUnit::Pixels, SizeUnit::Percent].map(|unit| {
+            choice_button(
+                unit.name(),
+                unit == settings.unit,
+                Message::ExportUnitChanged(unit),
+            )
+        });
+        let algorithms = ResamplingType::all().into_iter().map(|resampling| {
+            choice_button(
+                resampling.name(),
+                resampling == settings.resampling,
+                Message::ExportResamplingChanged(resampling),
+            )
+        });
+        let mut content = column![
+            ui_text(tr("Resize & export"))
+                .size(14)
+                .color(palette.heading),
+            column![
+                ui_text(tr("Size")).size(12).color(label_color),
+                row![
+                    text_input(tr("Width"), &settings.width)
+                        .on_input(Message::ExportWidthChanged)
+                        .size(12)
+                        .padding([4, 8]),
+                    text("×").size(12).color(label_color),
+                    text_input(tr("Height"), &settings.height)
+                        .on_input(Message::ExportHeightChanged)
+                        .size(12)
+                        .padding([4, 8]),
+                    row(units).spacing(4),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+                checkbox(tr("Keep aspect ratio"), settings.keep_aspect)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(Message::ExportKeepAspectToggled)
+                    .size(14)
+                    .text_size(12),
+                ui_text(target_label).size(12).color(label_color),
+            ]
+            .spacing(6),
+            column![
+                ui_text(tr("Resampling Algorithm"))
+                    .size(12)
+                    .color(label_color),
+                row(algorithms).spacing(4).wrap(),
+            ]
+            .spacing(6),
+            view_encode_options(
+                ExportFormat::all(),
+                settings.encode,
+                Message::ExportEncodeChanged,
+                palette
+            ),
+        ]
+        .spacing(12);
+
+        // 插件提供的导出目标直接接收磁盘上的原文件
+        let exporters = plugins::exporters();
+        let local = self
+            .current_image
+            .as_ref()
+            .is_some_and(|path| !cloud::is_remote(path));
+        if !exporters.is_empty() {
+            content = content.push(
+                column![
+                    ui_text(tr("Send original to")).size(12).color(label_color),
+                    row(exporters.into_iter().map(|exporter| {
+                        button(ui_text(exporter.name()).size(12))
+                            .on_press_maybe(local.then_some(Message::PluginExport(exporter)))
+                            .style(button_style::default)
+                            .padding([4, 8])
+                            .into()
+                    }))
+                    .spacing(4)
+                    .wrap(),
+                ]
+                .spacing(6),
+            );
+        }
+
+        if let Some(progress) = self.export_progress {
+            content = content.push(progress_bar(0.0..=1.0, progress).height(6));
+        } else if let Some(status) = &self.export_status {
+            content = content.push(text(status.clone()).size(12).color(label_color));
+        }
+
+        content = content.push(
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Close")).size(12))
+                    .on_press(Message::ToggleExportDialog)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(tr("Export…")).size(12))
+                    .on_press_maybe(
+                        (!exporting && settings.target_size(source).is_some())
+                            .then_some(Message::StartExport)
+                    )
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        );
+
+        container(content)
+            .width(420)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_batch_dialog(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let options = &self.batch_options;
+        let changed = |update: fn(&mut BatchOptions, String)| {
+            let options = options.clone();
+            move |value: String| {
+                let mut options = options.clone();
+                update(&mut options, value);
+                Message::BatchOptionsChanged(options)
+            }
+        };
+        let toggled = |update: fn(&mut BatchOptions, bool)| {
+            let options = options.clone();
+            move |value: bool| {
+                let mut options = options.clone();
+                update(&mut options, value);
+                Message::BatchOptionsChanged(options)
+            }
+        };
+
+        let selection_count = self.convert_targets().len();
+        let scope = row![
+            choice_button(
+                trf("Selection ({})", &[&selection_count]),
+                self.batch_scope == BatchScope::Selection,
+                Message::BatchScopeChanged(BatchScope::Selection)
+            ),
+            choice_button(
+                trf("Folder ({})", &[&self.image_collection.len()]),
+                self.batch_scope == BatchScope::Folder,
+                Message::BatchScopeChanged(BatchScope::Folder)
+            ),
+        ]
+        .spacing(4);
+
+        let mut content = column![
+            ui_text(tr("Batch processing"))
+                .size(14)
+                .color(palette.heading),
+            column![ui_text(tr("Apply to")).size(12).color(label_color), scope].spacing(6),
+            checkbox(tr("Resize"), options.resize_enabled)
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(toggled(|o, v| o.resize_enabled = v))
+                .size(14)
+                .text_size(12),
+        ]
+        .spacing(10);
+
+        if options.resize_enabled {
+            let algorithms = ResamplingType::all().into_iter().map(|resampling| {
+                let mut updated = options.clone();
+                updated.resampling = resampling;
+                choice_button(
+                    resampling.name(),
+                    resampling == options.resampling,
+                    Message::BatchOptionsChanged(updated),
+                )
+            });
+            content = content.push(
+                column![
+                    row![
+                        ui_text(tr("Max")).size(12).color(label_color),
+                        text_input(tr("Width"), &options.max_width)
+                            .on_input(changed(|o, v| o.max_width = v))
+                            .size(12)
+                            .padding([4, 8]),
+                        text("×").size(12).color(label_color),
+                        text_input(tr("Height"), &options.max_height)
+                            .on_input(changed(|o, v| o.max_height = v))
+                            .size(12)
+                            .padding([4, 8]),
+                    ]
+                    .spacing(8)
+                    .align_y(iced::Alignment::Center),
+                    row(algorithms).spacing(4).wrap(),
+                ]
+                .spacing(6),
+            );
+        }
+
+        let watermark_label = match &options.watermark {
+            Some(path) => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            None => tr("No watermark").to_string(),
+        };
+        content = content.push(
+            row![
+                ui_text(tr("Watermark")).size(12).color(label_color),
+                ui_text(watermark_label).size(12).width(Length::Fill),
+                button(ui_text(tr("Choose…")).size(12))
+                    .on_press(Message::ChooseBatchWatermark)
+                    .style(button_style::default)
+                    .padding([4, 8]),
+                button(ui_text(tr("Remove")).size(12))
+                    .on_press_maybe(
+                        options
+                            .watermark
+                            .as_ref()
+                            .map(|_| Message::RemoveBatchWatermark)
+                    )
+                    .style(button_style::default)
+                    .padding([4, 8]),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+        );
+
+        content = content.push(
+            checkbox(tr("Convert format"), options.convert_enabled)
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(toggled(|o, v| o.convert_enabled = v))
+                .size(14)
+                .text_size(12),
+        );
+        if options.convert_enabled {
+            content = content.push(view_encode_options(
+                ExportFormat::all(),
+                options.encode,
+                Message::BatchEncodeChanged,
+                palette,
+            ));
+        }
+
+        content = content.push(
+            checkbox(tr("Rename"), options.rename_enabled)
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(toggled(|o, v| o.rename_enabled = v))
+                .size(14)
+                .text_size(12),
+        );
+        if options.rename_enabled {
+            content = content.push(
+                column![
+                    text_input(tr("{name}_{n}"), &options.rename_pattern)
+                        .on_input(changed(|o, v| o.rename_pattern = v))
+                        .size(12)
+                        .padding([4, 8]),
+                    ui_text(tr("{name} = original name, {n} = 001, 002, …"))
+                        .size(11)
+                        .color(label_color),
+                ]
+                .spacing(4),
+            );
+        }
+
+        let destination = match &options.output_dir {
+            Some(dir) => dir.display().to_string(),
+            None => tr("Next to originals").to_string(),
+        };
+        content = content.push(
+            column![
+                ui_text(tr("Destination")).size(12).color(label_color),
+                ui_text(destination).size(12),
+                row![
+                    choice_button(
+                        tr("Next to originals"),
+                        options.output_dir.is_none(),
+                        Message::BatchNextToOriginals
+                    ),
+                    choice_button(
+                        tr("Choose folder…"),
+                        options.output_dir.is_some(),
+                        Message::ChooseBatchFolder
+                    ),
+                ]
+                .spacing(4),
+            ]
+            .spacing(6),
+        );
+
+        if let Some(error) = &self.batch_error {
+            content = content.push(text(error.clone()).size(12).color(palette.danger));
+        }
+
+        content = content.push(
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Close")).size(12))
+                    .on_press(Message::ToggleBatchDialog)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(tr("Add to queue")).size(12))
+                    .on_press(Message::QueueBatchJob)
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        );
+
+        container(scrollable(content))
+            .width(460)
+            .max_height(640)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    // 底部状态栏：当前图片的位置和尺寸，以及内存占用
+    fn view_status_bar(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let position = if let Some(scan) = &self.scan {
+            trf(
+                "{} Scanning {}… {} items",
+                &[
+                    &SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()],
+                    &scan
+                        .dir
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    &scan.scanned,
+                ],
+            )
+        } else if self.image_collection.is_empty() {
+            tr("No images").to_string()
+        } else {
+            format!(
+                "{} / {}",
+                self.current_image_index + 1,
+                self.image_collection.len()
+            )
+        };
+        let dimensions = match (self.full_size, self.display_source()) {
+            (Some((w, h)), Some(img)) => trf(
+                "{} × {} (preview {} × {})",
+                &[&w, &h, &img.width(), &img.height()],
+            ),
+            (None, Some(img)) => format!("{} × {}", img.width(), img.height()),
+            _ => String::new(),
+        };
+
+        let usage = self.memory_usage();
+        let memory = trf(
+            "Memory {} / {}  ·  image {}  ·  buffers {}  ·  {} cached ({})  ·  {} thumbnails ({})",
+            &[
+                &memory::format_bytes(usage.total()),
+                &memory::format_bytes(self.memory_budget),
+                &memory::format_bytes(usage.current),
+                &memory::format_bytes(usage.buffers),
+                &self.decoded_cache.count(),
+                &memory::format_bytes(usage.decoded),
+                &self.thumbnail_cache.count(),
+                &memory::format_bytes(usage.thumbnails),
+            ],
+        );
+        let ratio = usage.total() as f32 / self.memory_budget.max(1) as f32;
+
+        container(
+            row![
+                ui_text(position)
+                    .size(12)
+                    .color(label_color)
+                    .shaping(text::Shaping::Advanced),
+                ui_text(dimensions).size(12).color(label_color),
+                container(text("")).width(Length::Fill),
+            ]
+            .push_maybe(
+                (self.config.thumbnail_strip == ThumbnailStrip::Hidden).then(|| {
+                    button(ui_text(tr("Show thumbnails")).size(12))
+                        .style(button_style::transparent)
+                        .padding([2, 6])
+                        .on_press(Message::ThumbnailStripChanged(ThumbnailStrip::Shown))
+                }),
+            )
+            .push(
+                ui_text(memory)
+                    .size(12)
+                    .color(label_color)
+                    .shaping(text::Shaping::Advanced),
+            )
+            .push(
+                progress_bar(0.0..=1.0, ratio.min(1.0))
+                    .width(Length::Fixed(80.0))
+                    .height(Length::Fixed(6.0)),
+            )
+            .spacing(16)
+            .align_y(iced::Alignment::Center),
+        )
+        .padding([4, 16])
+        .width(Length::Fill)
+        .style(move |_theme| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            border: iced::Border {
+                radius: 0.0.into(),
+                width: 1.0,
+                color: palette.border,
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    // 右上角：正在进行的传输和提示
+    fn view_notifications(&self) -> Element<'_, Message> {
+        let mut content = column![].spacing(8).align_x(Horizontal::Right);
+        let pending = self.transfers.pending();
+        if pending > 0 && !self.transfers_panel_opened {
+            let spinner = SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()];
+            content = content.push(
+                button(
+                    row![
+                        text(spinner).shaping(text::Shaping::Advanced).size(13),
+                        ui_text(trf("Transferring {} file(s)", &[&pending])).size(12),
+                    ]
+                    .spacing(6)
+                    .align_y(iced::Alignment::Center),
+                )
+                .style(button_style::default)
+                .padding([6, 12])
+                .on_press(Message::ToggleTransfersPanel),
+            );
+        }
+        content = content.push(toast::view(&self.toasts));
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Right)
+            .align_y(iced::alignment::Vertical::Top)
+            .padding(16)
+            .into()
+    }
+
+    // 性能面板：各环节的耗时、缓存命中率和界面刷新次数
+    fn view_perf_hud(&self) -> Element<'_, Message> {
+        let stats = perf::snapshot();
+        let ms = |d: Duration| format!("{:.1} ms", d.as_secs_f64() * 1000.0);
+        let rate = |rate: perf::HitRate| match rate.ratio() {
+            Some(ratio) => format!(
+                "{:.0}% ({}/{})",
+                ratio * 100.0,
+                rate.hits,
+                rate.hits + rate.misses
+            ),
+            None => "-".to_string(),
+        };
+        let line = |content: String| text(content).size(12).font(iced::Font::MONOSPACE);
+
+        let mut content = column![
+            row![
+                ui_text(tr("Performance")).size(13).width(Length::Fill),
+                button(ui_text(tr("Reset")).size(11))
+                    .on_press(Message::ResetPerfStats)
+                    .style(button_style::default)
+                    .padding([2, 6]),
+            ]
+            .spacing(12)
+            .align_y(iced::Alignment::Center)
+        ]
+        .spacing(4);
+        for stage in Stage::ALL {
+            let timing = stats.timing(stage);
+            content = content.push(line(format!(
+                "{:<9} last {:>9}  avg {:>9}  max {:>9}  ×{}",
+                stage.name(),
+                ms(timing.last),
+                ms(timing.average()),
+                ms(timing.max),
+                timing.count
+            )));
+        }
+        content = content
+            .push(line(format!(
+                "image cache      {}",
+                rate(stats.decoded_cache)
+            )))
+            .push(line(format!(
+                "thumbnail cache  {}",
+                rate(stats.thumbnail_cache)
+            )))
+            .push(line(format!(
+                "updates {}  views {}",
+                stats.updates, stats.views
+            )));
+
+        container(content)
+            .padding(12)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.75))),
+                text_color: Some(Color::WHITE),
+                border: iced::Border {
+                    radius: 8.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    // 诊断面板：最近的日志，复制后可以附在问题报告中
+    fn view_log_panel(&self) -> Element<'_, Message> {
+        let entries = logging::recent();
+        let small_button = |label: &'static str, message: Message| {
+            button(ui_text(tr(label)).size(11))
+                .on_press(message)
+                .style(button_style::default)
+                .padding([2, 6])
+        };
+        let header = row![
+            ui_text(tr("Diagnostics log")).size(13).width(Length::Fill),
+            small_button("Copy", Message::CopyLog),
+            small_button("Clear", Message::ClearLog),
+            button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                .on_press(Message::ToggleLogPanel)
+                .style(button_style::transparent)
+                .padding([2, 6]),
+        ]
+        .spacing(6)
+        .align_y(iced::Alignment::Center);
+
+        let lines: Element<_> = if entries.is_empty() {
+            ui_text(tr("No log events yet")).size(12).into()
+        } else {
+            column(entries.iter().map(|entry| {
+                let color = match entry.level {
+                    tracing::Level::ERROR => Color::from_rgb8(255, 107, 107),
+                    tracing::Level::WARN => Color::from_rgb8(255, 212, 59),
+                    tracing::Level::INFO => Color::WHITE,
+                    _ => Color::from_rgb8(173, 181, 189),
+                };
+                text(entry.to_string())
+                    .size(11)
+                    .font(iced::Font::MONOSPACE)
+                    .shaping(text::Shaping::Advanced)
+                    .color(color)
+                    .into()
+            }))
+            .spacing(2)
+            .into()
+        };
+
+        container(
+            column![
+                header,
+                // 新的日志在底部，默认滚动到最后
+                scrollable(lines)
+                    .height(240)
+                    .anchor_bottom()
+                    .width(Length::Fill),
+            ]
+            .spacing(8),
+        )
+        .width(640)
+        .padding(12)
+        .style(move |_theme| container::Style {
+            background: Some(Background::Color(Color::from_rgba8(0, 0, 0, 0.75))),
+            text_color: Some(Color::WHITE),
+            border: iced::Border {
+                radius: 8.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn view_transfers_panel(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let error_color = palette.danger;
+
+        let mut content = column![
+            row![
+                ui_text(tr("Transfers"))
+                    .size(14)
+                    .color(palette.heading)
+                    .width(Length::Fill),
+                button(ui_text(tr("Clear finished")).size(12))
+                    .on_press_maybe(
+                        self.transfers
+                            .iter()
+                            .any(|transfer| transfer.is_finished())
+                            .then_some(Message::ClearFinishedTransfers)
+                    )
+                    .style(button_style::default)
+                    .padding([4, 8]),
+                button(text("✕").shaping(text::Shaping::Advanced).size(12))
+                    .on_press(Message::ToggleTransfersPanel)
+                    .style(button_style::transparent)
+                    .padding([4, 8]),
+            ]
+            .spacing(4)
+            .align_y(iced::Alignment::Center)
+        ]
+        .spacing(12);
+
+        if self.transfers.is_empty() {
+            content = content.push(ui_text(tr("No transfers")).size(12).color(label_color));
+        }
+
+        for transfer in self.transfers.iter() {
+            let arrow = match transfer.direction {
+                TransferDirection::Upload => "↑",
+                TransferDirection::Download => "↓",
+            };
+            let state = match &transfer.status {
+                TransferStatus::Queued => tr("Queued").to_string(),
+                TransferStatus::Active if transfer.size > 0 => format!(
+                    "{} / {}",
+                    memory::format_bytes(transfer.transferred as usize),
+                    memory::format_bytes(transfer.size as usize)
+                ),
+                TransferStatus::Active => tr("Starting…").to_string(),
+                TransferStatus::Paused => tr("Paused").to_string(),
+                TransferStatus::Waiting(_) => trf(
+                    "Retry in {}s",
+                    &[&(transfer.retry_at - chrono::Utc::now().timestamp()).max(0)],
+                ),
+                TransferStatus::Completed if transfer.skipped => tr("Unchanged").to_string(),
+                TransferStatus::Completed => tr("Done").to_string(),
+                TransferStatus::Failed(_) => tr("Failed").to_string(),
+                TransferStatus::Cancelled => tr("Cancelled").to_string(),
+            };
+
+            let action = |label: &'static str, message: Message| {
+                button(ui_text(tr(label)).size(11))
+                    .on_press(message)
+                    .style(button_style::default)
+                    .padding([2, 6])
+            };
+            let mut actions = row![].spacing(4);
+            match transfer.status {
+                TransferStatus::Queued | TransferStatus::Active => {
+                    actions = actions.push(action("Pause", Message::PauseTransfer(transfer.id)));
+                }
+                TransferStatus::Paused => {
+                    actions = actions.push(action("Resume", Message::ResumeTransfer(transfer.id)));
+                }
+                TransferStatus::Failed(_) | TransferStatus::Cancelled => {
+                    actions = actions.push(action("Retry", Message::RetryTransfer(transfer.id)));
+                }
+                TransferStatus::Waiting(_) => {
+                    actions =
+                        actions.push(action("Retry now", Message::RetryTransfer(transfer.id)));
+                }
+                TransferStatus::Completed => {
+                    if transfer.direction == TransferDirection::Upload {
+                        actions = actions.push(action(
+                            "Copy link",
+                            Message::CopyShareLink(transfer.key.clone()),
+                        ));
+                    }
+                }
+            }
+            if !transfer.is_finished() {
+                actions = actions.push(action("Cancel", Message::CancelTransfer(transfer.id)));
+            }
+
+            let mut item = column![
+                row![
+                    text(arrow).shaping(text::Shaping::Advanced).size(12),
+                    text(transfer.name()).size(12).width(Length::Fill),
+                    ui_text(state).size(11).color(label_color),
+                    actions,
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+                progress_bar(
+                    0.0..=transfer.size.max(1) as f32,
+                    transfer.transferred as f32
+                )
+                .height(6),
+            ]
+            .spacing(6);
+            if let TransferStatus::Failed(error) | TransferStatus::Waiting(error) = &transfer.status
+            {
+                item = item.push(text(error.clone()).size(11).color(error_color));
+            }
+            content = content.push(item);
+        }
+
+        container(scrollable(content))
+            .width(380)
+            .max_height(480)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_jobs_panel(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let error_color = palette.danger;
+
+        let mut content = column![
+            row![
+                ui_text(tr("Jobs"))
+                    .size(14)
+                    .color(palette.heading)
+                    .width(Length::Fill),
+                button(ui_text(tr("Clear finished")).size(12))
+                    .on_press_maybe(
+                        self.jobs
+                            .iter()
+                            .any(|job| job.finished)
+                            .then_some(Message::ClearFinishedJobs)
+                    )
+                    .style(button_style::default)
+                    .padding([4, 8]),
+            ]
+            .align_y(iced::Alignment::Center)
+        ]
+        .spacing(12);
+
+        if self.jobs.is_empty() {
+            content = content.push(ui_text(tr("No jobs")).size(12).color(label_color));
+        }
+
+        for job in &self.jobs {
+            let total = job.items.len();
+            let completed = job.completed();
+            let state = if !job.started {
+                tr("Queued")
+            } else if !job.finished {
+                if job.is_cancelled() {
+                    tr("Cancelling…")
+                } else {
+                    tr("Running")
+                }
+            } else if job.is_cancelled() {
+                tr("Cancelled")
+            } else {
+                tr("Done")
+            };
+
+            let mut job_column = column![
+                row![
+                    ui_text(format!("#{} {}", job.id, job.plan.summary()))
+                        .size(12)
+                        .width(Length::Fill),
+                    ui_text(format!("{} · {}/{}", state, completed, total))
+                        .size(12)
+                        .color(label_color),
+                    button(ui_text(tr("Cancel")).size(11))
+                        .on_press_maybe(
+                            (!job.finished && !job.is_cancelled())
+                                .then_some(Message::CancelBatchJob(job.id))
+                        )
+                        .style(button_style::default)
+                        .padding([2, 6]),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+                progress_bar(0.0..=total.max(1) as f32, completed as f32).height(6),
+            ]
+            .spacing(6);
+
+            // 每张图片的状态
+            let items = job.items.iter().map(|(path, status)| {
+                let (icon, color) = match status {
+                    ItemStatus::Pending => ("·", label_color),
+                    ItemStatus::Running => ("…", palette.accent),
+                    ItemStatus::Done(_) => ("✓", palette.success),
+                    ItemStatus::Failed(_) => ("✕", error_color),
+                    ItemStatus::Cancelled => ("–", label_color),
+                };
+                row![
+                    text(icon)
+                        .shaping(text::Shaping::Advanced)
+                        .size(11)
+                        .color(color)
+                        .width(14),
+                    text(path.file_name().unwrap_or_default().to_string_lossy())
+                        .size(11)
+                        .color(label_color),
+                ]
+                .spacing(4)
+                .into()
+            });
+            job_column = job_column.push(
+                container(scrollable(column(items).spacing(2).width(Length::Fill))).max_height(120),
+            );
+
+            // 失败汇总
+            let failed = job.failures().count();
+            if job.finished && failed > 0 {
+                job_column = job_column.push(
+                    ui_text(trf("{} of {} failed", &[&failed, &total]))
+                        .size(12)
+                        .color(error_color),
+                );
+                for (path, error) in job.failures().take(5) {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    job_column = job_column.push(
+                        text(format!("{}: {}", name, error))
+                            .size(11)
+                            .color(error_color),
+                    );
+                }
+                if failed > 5 {
+                    job_column = job_column.push(
+                        ui_text(trf("and {} more", &[&(failed - 5)]))
+                            .size(11)
+                            .color(error_color),
+                    );
+                }
+            }
+            content = content.push(job_column);
+        }
+
+        container(scrollable(content))
+            .width(340)
+            .max_height(480)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    // 时间线：左侧是月份列表，右侧是选中月份按日期分组的缩略图
+    fn view_timeline<'a>(&'a self, timeline: &'a Timeline) -> Element<'a, Message> {
+        let palette = self.palette();
+        let mut sources = vec![TimelineSource::Collection];
+        if self.catalog.is_some() {
+            sources.push(TimelineSource::Library);
+        }
+        let header = row![
+            ui_text(tr("Timeline")).size(14).color(palette.heading),
+            row(sources.into_iter().map(|source| {
+                choice_button(
+                    source.name(),
+                    timeline.source == source,
+                    Message::TimelineSourceChanged(source),
+                )
+            }))
+            .spacing(6),
+            container(text("")).width(Length::Fill),
+            button(text("✕").shaping(text::Shaping::Advanced).size(12))
+                .on_press(Message::ToggleTimeline)
+                .style(button_style::transparent)
+                .padding([4, 8]),
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+
+        let body: Element<_> = match &timeline.months {
+            None => ui_text(tr("Reading dates…"))
+                .size(12)
+                .color(palette.muted)
+                .into(),
+            Some(months) if months.is_empty() => ui_text(tr("No images"))
+                .size(12)
+                .color(palette.muted)
+                .into(),
+            Some(months) => {
+                let month_list = column(months.iter().enumerate().map(|(index, month)| {
+                    let selected = index == timeline.selected;
+                    button(
+                        row![
+                            ui_text(month.title()).size(12).width(Length::Fill),
+                            text(month.count().to_string())
+                                .size(11)
+                                .color(palette.muted),
+                        ]
+                        .spacing(8),
+                    )
+                    .width(Length::Fill)
+                    .padding([4, 8])
+                    .style(move |theme, status| {
+                        if selected {
+                            button_style::primary(theme, status)
+                        } else {
+                            button_style::transparent(theme, status)
+                        }
+                    })
+                    .on_press(Message::TimelineMonthSelected(index))
+                    .into()
+                }))
+                .spacing(2);
+
+                let thumbnail_size = self.config.thumbnail_size;
+                let days = months
+                    .get(timeline.selected)
+                    .map_or(&[][..], |m| &m.days[..]);
+                let day_sections = column(days.iter().map(|day| {
+                    let title = match day.date {
+                        Some(date) => date.format("%Y-%m-%d").to_string(),
+                        None => tr("Unknown date").to_string(),
+                    };
+                    let thumbnails = day.images.iter().map(|path| {
+                        let handle = self.thumbnail_cache.get(path).cloned().unwrap_or_else(|| {
+                            Handle::from_rgba(
+                                thumbnail_size,
+                                thumbnail_size,
+                                palette
+                                    .surface_alt
+                                    .into_rgba8()
+                                    .repeat((thumbnail_size * thumbnail_size) as usize),
+                            )
+                        });
+                        let selected = self.current_image.as_ref() == Some(path);
+                        let thumbnail = button(
+                            iced::widget::image(handle)
+                                .width(Length::Fixed(thumbnail_size as f32))
+                                .height(Length::Fixed(thumbnail_size as f32))
+                                .content_fit(iced::ContentFit::Cover),
+                        )
+                        .style(move |theme, status| {
+                            if selected {
+                                button_style::thumbnail_selected(theme, status)
+                            } else {
+                                button_style::thumbnail(theme, status)
+                            }
+                        })
+                        .on_press(Message::TimelineImagePicked(path.clone()));
+                        hover_tip(thumbnail, self.file_tooltip(path), tooltip::Position::Top)
+                    });
+                    column![
+                        row![
+                            ui_text(title).size(13).color(palette.heading),
+                            ui_text(trf("{} images", &[&day.images.len()]))
+                                .size(11)
+                                .color(palette.muted),
+                        ]
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center),
+                        row(thumbnails).spacing(8).wrap(),
+                    ]
+                    .spacing(8)
+                    .into()
+                }))
+                .spacing(20)
+                .padding([0, 16]);
+
+                row![
+                    scrollable(month_list).width(180),
+                    scrollable(day_sections)
+                        .width(Length::Fill)
+                        .height(Length::Fill),
+                ]
+                .spacing(12)
+                .into()
+            }
+        };
+
+        container(column![header, body].spacing(12))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.canvas)),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_map_panel<'a>(&'a self, map: &'a map::MapView) -> Element<'a, Message> {
+        let palette = self.palette();
+        let status = match &map.photos {
+            None => tr("Reading locations…").to_string(),
+            Some(photos) if photos.is_empty() => {
+                tr("No geotagged images in this folder").to_string()
+            }
+            Some(photos) => trf("{} geotagged images", &[&photos.len()]),
+        };
+
+        container(
+            column![
+                row![
+                    ui_text(tr("Map")).size(14).color(palette.heading),
+                    ui_text(status)
+                        .size(12)
+                        .color(palette.muted)
+                        .width(Length::Fill),
+                    button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                        .on_press(Message::ToggleMap)
+                        .style(button_style::transparent)
+                        .padding([2, 6]),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+                iced::widget::canvas(MapCanvas {
+                    map,
+                    current: self.current_image.as_deref(),
+                })
+                .width(map::WIDTH)
+                .height(map::HEIGHT),
+            ]
+            .spacing(8),
+        )
+        .padding(12)
+        .style(move |_theme| container::Style {
+            background: Some(Background::Color(palette.surface)),
+            border: iced::Border {
+                radius: 12.0.into(),
+                width: 1.0,
+                color: palette.border,
+            },
+            shadow: iced::Shadow {
+                offset: Vector::new(0.0, 4.0),
+                blur_radius: 12.0,
+                color: palette.shadow,
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    // 重命名图片或新建文件夹时输入名称
+    // 脚本编辑器，下面是上次运行的输出
+    fn view_script_dialog(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let output = column(self.script_output.iter().map(|line| {
+            text(line.as_str())
+                .size(11)
+                .font(iced::Font::MONOSPACE)
+                .color(label_color)
+                .into()
+        }))
+        .spacing(2);
+        let small_button = |label: &'static str, message: Message| {
+            button(ui_text(tr(label)).size(12))
+                .on_press(message)
+                .style(button_style::default)
+                .padding([6, 12])
+        };
+        let run_label = if self.script_running {
+            tr("Running…")
+        } else {
+            tr("Run")
+        };
+        let run_button = button(ui_text(run_label).size(12))
+            .on_press_maybe((!self.script_running).then_some(Message::RunScript))
+            .style(button_style::primary)
+            .padding([6, 12]);
+
+        let content = column![
+            ui_text(tr("Script")).size(14).color(palette.heading),
+            ui_text(tr(
+                "Rhai script. Available: images(), selection(), folder(), name(path), rating(path), keywords(path), select(paths) and export_images(paths, #{ size, width, height, format, quality, dir, rename, watermark })."
+            ))
+            .size(11)
+            .color(label_color),
+            text_editor(&self.script)
+                .on_action(Message::ScriptEdited)
+                .font(iced::Font::MONOSPACE)
+                .size(12)
+                .height(260),
+            container(scrollable(output).width(Length::Fill)).max_height(120),
+            row![
+                small_button("Open…", Message::OpenScript),
+                small_button("Save…", Message::SaveScript),
+                container(text("")).width(Length::Fill),
+                small_button("Close", Message::ToggleScriptDialog),
+                run_button,
+            ]
+            .spacing(8),
+        ]
+        .spacing(12);
+
+        container(content)
+            .width(560)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_name_prompt<'a>(&'a self, prompt: &'a NamePrompt) -> Element<'a, Message> {
+        let palette = self.palette();
+        let (title, name, confirm) = match prompt {
+            NamePrompt::Rename { name, .. } => (tr("Rename"), name, tr("Rename")),
+            NamePrompt::NewFolder { name, .. } => (tr("New folder"), name, tr("Create")),
+        };
+        let name = name.trim();
+        let valid = !name.is_empty() && !name.contains(['/', '\\']);
+        let content = column![
+            ui_text(title).size(14).color(palette.heading),
+            text_input("", name)
+                .on_input(Message::NamePromptChanged)
+                .on_submit_maybe(valid.then_some(Message::ConfirmNamePrompt))
+                .size(12)
+                .padding([4, 8]),
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Cancel")).size(12))
+                    .on_press(Message::CancelNamePrompt)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(confirm).size(12))
+                    .on_press_maybe(valid.then_some(Message::ConfirmNamePrompt))
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        ]
+        .spacing(12);
+
+        container(content)
+            .width(380)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_remote_edit_dialog<'a>(&'a self, edit: &'a RemoteEdit) -> Element<'a, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let (title, body, confirm, enabled): (_, Element<_>, _, _) = match edit {
+            RemoteEdit::Delete(key) => (
+                tr("Delete from cloud"),
+                ui_text(trf(
+                    "Delete {} from the bucket? This cannot be undone.",
+                    &[&key],
+                ))
+                .size(12)
+                .color(label_color)
+                .into(),
+                tr("Delete"),
+                true,
+            ),
+            RemoteEdit::Rename { key, target } => {
+                let target = target.trim();
+                let valid = !target.is_empty() && !target.ends_with('/') && target != key;
+                (
+                    tr("Rename or move"),
+                    column![
+                        ui_text(tr("New path in the bucket (use / to move into a folder)"))
+                            .size(12)
+                            .color(label_color),
+                        text_input(key, target)
+                            .on_input(Message::RemoteRenameChanged)
+                            .on_submit_maybe(valid.then_some(Message::ConfirmRemoteEdit))
+                            .size(12)
+                            .padding([4, 8]),
+                    ]
+                    .spacing(6)
+                    .into(),
+                    tr("Rename"),
+                    valid,
+                )
+            }
+        };
+
+        let content = column![
+            ui_text(title).size(14).color(palette.heading),
+            body,
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Cancel")).size(12))
+                    .on_press(Message::CancelRemoteEdit)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(confirm).size(12))
+                    .on_press_maybe(enabled.then_some(Message::ConfirmRemoteEdit))
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        ]
+        .spacing(12);
+
+        container(content)
+            .width(380)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_settings_dialog(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let settings = &self.settings;
+
+        let cloud = &settings.cloud;
+        let status = match (cloud.provider, cloud.is_configured()) {
+            (_, true) => tr("Cloud storage is configured"),
+            (Provider::Cos, false) => tr(
+                "Cloud storage is not configured (set COS_SECRET_ID, COS_SECRET_KEY, COS_REGION and COS_BUCKET)",
+            ),
+            (Provider::Oss, false) => {
+                tr("Cloud storage is not configured (fill in all OSS fields)")
+            }
+            (Provider::S3, false) => tr("Cloud storage is not configured (fill in all S3 fields)"),
+        };
+        let providers = Provider::ALL.map(|provider| {
+            choice_button(
+                provider.name(),
+                provider == cloud.provider,
+                Message::CloudProviderChanged(provider),
+            )
+        });
+        // OSS 和 S3 的连接参数，每项修改后都整体替换
+        let oss = &cloud.oss;
+        let oss_input =
+            |placeholder: &'static str, value: &str, update: fn(&mut OssConfig, String)| {
+                text_input(tr(placeholder), value)
+                    .on_input(move |value| {
+                        let mut config = oss.clone();
+                        update(&mut config, value);
+                        Message::OssConfigChanged(config)
+                    })
+                    .size(12)
+                    .padding([4, 8])
+            };
+        let s3 = &cloud.s3;
+        let s3_input =
+            |placeholder: &'static str, value: &str, update: fn(&mut S3Config, String)| {
+                text_input(tr(placeholder), value)
+                    .on_input(move |value| {
+                        let mut config = s3.clone();
+                        update(&mut config, value);
+                        Message::S3ConfigChanged(config)
+                    })
+                    .size(12)
+                    .padding([4, 8])
+            };
+        let mut provider_options = column![row(providers).spacing(8)].spacing(6);
+        if cloud.provider == Provider::Oss {
+            provider_options = provider_options.push(
+                column![
+                    row![
+                        oss_input(
+                            "Endpoint (oss-cn-hangzhou.aliyuncs.com)",
+                            &oss.endpoint,
+                            |c, v| c.endpoint = v
+                        ),
+                        oss_input("Bucket", &oss.bucket, |c, v| c.bucket = v),
+                    ]
+                    .spacing(8),
+                    oss_input("AccessKey ID", &oss.access_key_id, |c, v| c.access_key_id =
+                        v),
+                    oss_input("AccessKey secret", &oss.access_key_secret, |c, v| {
+                        c.access_key_secret = v
+                    })
+                    .secure(true),
+                ]
+                .spacing(6),
+            );
+        }
+        if cloud.provider == Provider::S3 {
+            provider_options = provider_options.push(
+                column![
+                    s3_input(
+                        "Endpoint (https://s3.us-east-1.amazonaws.com)",
+                        &s3.endpoint,
+                        |c, v| { c.endpoint = v }
+                    ),
+                    row![
+                        s3_input("Region", &s3.region, |c, v| c.region = v),
+                        s3_input("Bucket", &s3.bucket, |c, v| c.bucket = v),
+                    ]
+                    .spacing(8),
+                    s3_input("Access key", &s3.access_key, |c, v| c.access_key = v),
+                    s3_input("Secret key", &s3.secret_key, |c, v| c.secret_key = v).secure(true),
+                    checkbox(tr("Path-style addressing (MinIO)"), s3.path_style)
+                        .text_shaping(text::Shaping::Advanced)
+                        .on_toggle(|path_style| Message::S3ConfigChanged(S3Config {
+                            path_style,
+                            ..s3.clone()
+                        }))
+                        .size(14)
+                        .text_size(12),
+                ]
+                .spacing(6),
+            );
+        }
+        // 双向同步的文件夹：本地路径 ⇄ 存储桶前缀
+        let sync_folders = column(settings.sync_folders.iter().map(|folder| {
+            row![
+                text(format!("{} ⇄ /{}", folder.local.display(), folder.prefix))
+                    .shaping(text::Shaping::Advanced)
+                    .size(12)
+                    .width(Length::Fill),
+                button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                    .style(button_style::transparent)
+                    .padding([2, 6])
+                    .on_press(Message::RemoveSyncFolder(folder.local.clone())),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into()
+        }))
+        .spacing(4);
+        let sync_button = button(ui_text(tr("Sync now")).size(12))
+            .on_press_maybe(
+                (self.sync_progress.is_none() && !settings.sync_folders.is_empty())
+                    .then_some(Message::SyncNow),
+            )
+            .style(button_style::default)
+            .padding([4, 8]);
+        let sync_status = match self.sync_progress {
+            Some((done, total)) if total > 0 => trf("Syncing {}/{}…", &[&done, &total]),
+            Some(_) => tr("Comparing…").to_string(),
+            None => String::new(),
+        };
+        // 图片目录中索引的文件夹
+        let catalog_folders = column(settings.catalog_folders.iter().map(|folder| {
+            row![
+                text(folder.display().to_string())
+                    .size(12)
+                    .width(Length::Fill),
+                button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                    .style(button_style::transparent)
+                    .padding([2, 6])
+                    .on_press(Message::RemoveCatalogFolder(folder.clone())),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into()
+        }))
+        .spacing(4);
+        let catalog_status = match (&self.catalog, self.catalog_progress) {
+            (_, Some((done, total))) if total > 0 => trf("Indexing {}/{}…", &[&done, &total]),
+            (_, Some(_)) => tr("Scanning folders…").to_string(),
+            (Some(catalog), None) => trf("{} images indexed", &[&catalog.count()]),
+            (None, None) => String::new(),
+        };
+        let share_expiries = ShareExpiry::ALL.map(|expiry| {
+            choice_button(
+                expiry.name(),
+                expiry == settings.share_expiry,
+                Message::ShareExpiryChanged(expiry),
+            )
+        });
+        // 传输限速，同时作用于传输队列和文件夹同步
+        let upload_limits = throttle::LIMITS.map(|limit| {
+            choice_button(
+                throttle::limit_name(limit),
+                limit == settings.upload_limit,
+                Message::UploadLimitChanged(limit),
+            )
+        });
+        let download_limits = throttle::LIMITS.map(|limit| {
+            choice_button(
+                throttle::limit_name(limit),
+                limit == settings.download_limit,
+                Message::DownloadLimitChanged(limit),
+            )
+        });
+        let cache_limits = CacheLimit::ALL.map(|limit| {
+            choice_button(
+                limit.name(),
+                limit == settings.remote_cache_limit,
+                Message::CacheLimitChanged(limit),
+            )
+        });
+        let added_within = AddedWithin::ALL.map(|within| {
+            choice_button(
+                within.name(),
+                within == settings.recently_added,
+                Message::RecentlyAddedChanged(within),
+            )
+        });
+        let conflict_policies = ConflictPolicy::ALL.map(|policy| {
+            choice_button(
+                policy.name(),
+                policy == settings.sync_conflict,
+                Message::SyncConflictChanged(policy),
+            )
+        });
+        let intervals = sync::INTERVALS.map(|minutes| {
+            choice_button(
+                sync::interval_name(minutes),
+                minutes == settings.sync_interval,
+                Message::SyncIntervalChanged(minutes),
+            )
+        });
+        let resamplings = ResamplingType::all().into_iter().map(|resampling| {
+            choice_button(
+                resampling.name(),
+                resampling == self.config.resampling,
+                Message::ResamplingTypeChanged(resampling),
+            )
+        });
+        let languages = Language::ALL.map(|language| {
+            choice_button(
+                language.name(),
+                language == self.config.language,
+                Message::LanguageChanged(language),
+            )
+        });
+        let themes = ThemeMode::ALL.map(|theme| {
+            choice_button(
+                theme.name(),
+                theme == self.config.theme,
+                Message::ThemeChanged(theme),
+            )
+        });
+        // 强调色的按钮用各自的颜色显示，当前选择的加上边框
+        let accents = Accent::ALL.map(|accent| {
+            let selected = accent == self.config.accent;
+            button(text(accent.name()).size(12))
+                .padding([6, 12])
+                .style(move |theme, status| {
+                    let style = button_style::primary(theme, status);
+                    button::Style {
+                        background: Some(Background::Color(accent.color())),
+                        border: iced::Border {
+                            color: if selected {
+                                palette.text
+                            } else {
+                                Color::TRANSPARENT
+                            },
+                            width: 2.0,
+                            ..style.border
+                        },
+                        ..style
+                    }
+                })
+                .on_press(Message::AccentChanged(accent))
+                .into()
+        });
+        let backdrops = Backdrop::ALL.map(|backdrop| {
+            choice_button(
+                backdrop.name(),
+                backdrop == self.config.backdrop,
+                Message::BackdropChanged(backdrop),
+            )
+        });
+        let thumbnail_sizes = config::THUMBNAIL_SIZES.map(|size| {
+            choice_button(
+                format!("{} px", size),
+                size == self.config.thumbnail_size,
+                Message::ThumbnailSizeChanged(size),
+            )
+        });
+        let sort_orders = SortOrder::ALL.map(|sort| {
+            choice_button(
+                sort.name(),
+                sort == self.sort,
+                Message::SortOrderChanged(sort),
+            )
+        });
+        let memory_budgets = config::MEMORY_BUDGETS.map(|budget| {
+            choice_button(
+                memory::format_bytes(budget << 20),
+                budget == self.config.memory_budget,
+                Message::MemoryBudgetChanged(budget),
+            )
+        });
+        // 文字识别的语言：列出已安装的 Tesseract 语言数据，可以多选
+        let ocr_languages: Element<_> = match &self.ocr_languages {
+            None => ui_text(tr("Loading…")).size(11).color(label_color).into(),
+            Some(Ok(languages)) if !languages.is_empty() => {
+                let selected: Vec<&str> = self.config.ocr_languages.split('+').collect();
+                row(languages.iter().map(|language| {
+                    choice_button(
+                        language.clone(),
+                        selected.contains(&language.as_str()),
+                        Message::OcrLanguageToggled(language.clone()),
+                    )
+                }))
+                .spacing(8)
+                .wrap()
+                .into()
+            }
+            Some(_) => ui_text(tr("Install Tesseract to extract text from images"))
+                .size(11)
+                .color(label_color)
+                .into(),
+        };
+        let shortcuts = column(Action::ALL.map(|action| {
+            row![
+                ui_text(action.name()).size(12).width(Length::Fill),
+                text(self.keymap.describe(action))
+                    .size(12)
+                    .color(label_color),
+            ]
+            .into()
+        }))
+        .spacing(2);
+        let start_folder = match &self.config.start_folder {
+            Some(dir) => dir.display().to_string(),
+            None => tr("Home folder").to_string(),
+        };
+        let folders = column(settings.auto_upload_folders.iter().map(|folder| {
+            row![
+                text(folder.display().to_string())
+                    .size(12)
+                    .width(Length::Fill),
+                button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                    .style(button_style::transparent)
+                    .padding([2, 6])
+                    .on_press(Message::RemoveAutoUploadFolder(folder.clone())),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into()
+        }))
+        .spacing(4);
+
+        let general = column![
+            column![
+                ui_text(tr("Viewing")).size(12).color(label_color),
+                checkbox(
+                    tr("Remember zoom and pan for each image"),
+                    settings.remember_view
+                )
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(Message::RememberViewToggled)
+                .size(14)
+                .text_size(12),
+                checkbox(
+                    tr("Mouse wheel switches images, Ctrl+wheel zooms"),
+                    settings.wheel_navigation
+                )
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(Message::WheelNavigationToggled)
+                .size(14)
+                .text_size(12),
+                checkbox(tr("Show hidden files and folders"), self.config.show_hidden)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(Message::ShowHiddenToggled)
+                    .size(14)
+                    .text_size(12),
+                ui_text(tr("Language")).size(11).color(label_color),
+                row(languages).spacing(8),
+                ui_text(tr("Theme")).size(11).color(label_color),
+                row(themes).spacing(8),
+                ui_text(tr("Accent color")).size(11).color(label_color),
+                row(accents).spacing(8),
+                ui_text(tr("Default resampling"))
+                    .size(11)
+                    .color(label_color),
+                row(resamplings).spacing(8),
+                ui_text(tr("Background")).size(11).color(label_color),
+                row(backdrops).spacing(8),
+            ]
+            .spacing(6),
+            column![
+                ui_text(tr("Browsing")).size(12).color(label_color),
+                ui_text(tr("Thumbnail size")).size(11).color(label_color),
+                row(thumbnail_sizes).spacing(8),
+                ui_text(tr("Sort folders by")).size(11).color(label_color),
+                row(sort_orders).spacing(8),
+                ui_text(tr("Start folder (used on next launch)"))
+                    .size(11)
+                    .color(label_color),
+                row![
+                    ui_text(start_folder).size(12).width(Length::Fill),
+                    button(ui_text(tr("Choose…")).size(12))
+                        .on_press(Message::PickStartFolder)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                    button(ui_text(tr("Use home")).size(12))
+                        .on_press_maybe(
+                            self.config
+                                .start_folder
+                                .is_some()
+                                .then_some(Message::ResetStartFolder),
+                        )
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            ]
+            .spacing(6),
+            column![
+                ui_text(tr("Text recognition")).size(12).color(label_color),
+                ui_text(tr("Languages")).size(11).color(label_color),
+                ocr_languages,
+            ]
+            .spacing(6),
+            column![
+                ui_text(tr("Memory")).size(12).color(label_color),
+                ui_text(tr("Limit for the open image and caches"))
+                    .size(11)
+                    .color(label_color),
+                row(memory_budgets).spacing(8),
+            ]
+            .spacing(6),
+            column![
+                ui_text(tr("Keyboard shortcuts"))
+                    .size(12)
+                    .color(label_color),
+                ui_text(tr("Change them in the [keys] section of config.toml"))
+                    .size(11)
+                    .color(label_color),
+                shortcuts,
+            ]
+            .spacing(6),
+        ]
+        .spacing(12);
+        // 插件：名称和启用开关，下面是文件名和提供的功能
+        let (loaded_plugins, failed_plugins) = plugins::list();
+        let no_plugins = loaded_plugins.is_empty() && failed_plugins.is_empty();
+        let plugin_list = column(loaded_plugins.into_iter().map(|plugin| {
+            let key = plugin.key();
+            let mut features = vec![file_name(&plugin.file)];
+            if plugin.filters > 0 {
+                features.push(trf("{} filters", &[&plugin.filters]));
+            }
+            if plugin.exporters > 0 {
+                features.push(trf("{} export targets", &[&plugin.exporters]));
+            }
+            if plugin.metadata {
+                features.push(tr("metadata").to_string());
+            }
+            column![
+                checkbox(plugin.name, plugin.enabled)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(move |enabled| Message::PluginToggled(key.clone(), enabled))
+                    .size(14)
+                    .text_size(12),
+                ui_text(features.join(" · ")).size(11).color(label_color),
+            ]
+            .spacing(2)
+            .into()
+        }))
+        .spacing(8)
+        .extend(failed_plugins.into_iter().map(|(file, error)| {
+            ui_text(trf("Could not load {}: {}", &[&file_name(&file), &error]))
+                .size(11)
+                .color(palette.danger)
+                .into()
+        }));
+        let page: Element<'_, Message> = match self.settings_page {
+            SettingsPage::General => general.into(),
+            SettingsPage::Cloud => column![
+                ui_text(tr("Cloud upload")).size(12).color(label_color),
+                provider_options,
+                ui_text(status).size(11).color(label_color),
+                checkbox(
+                    tr("Automatically upload images opened from these folders"),
+                    settings.auto_upload
+                )
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(Message::AutoUploadToggled)
+                .size(14)
+                .text_size(12),
+                folders,
+                button(ui_text(tr("Add folder…")).size(12))
+                    .on_press(Message::AddAutoUploadFolder)
+                    .style(button_style::default)
+                    .padding([4, 8]),
+                ui_text(tr("Share links are valid for"))
+                    .size(11)
+                    .color(label_color),
+                row(share_expiries).spacing(8),
+                ui_text(tr("Upload speed limit"))
+                    .size(11)
+                    .color(label_color),
+                row(upload_limits).spacing(8),
+                ui_text(tr("Download speed limit"))
+                    .size(11)
+                    .color(label_color),
+                row(download_limits).spacing(8),
+                ui_text(trf(
+                    "Remote cache ({} used)",
+                    &[&memory::format_bytes(
+                        self.remote_cache.total_size() as usize
+                    )]
+                ))
+                .size(11)
+                .color(label_color),
+                row![
+                    row(cache_limits).spacing(8),
+                    button(ui_text(tr("Clear remote cache")).size(12))
+                        .on_press(Message::ClearRemoteCache)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                ]
+                .spacing(16)
+                .align_y(iced::Alignment::Center),
+            ]
+            .spacing(6)
+            .into(),
+            SettingsPage::Sync => column![
+                ui_text(tr("Folder sync")).size(12).color(label_color),
+                sync_folders,
+                row![
+                    button(ui_text(tr("Add folder…")).size(12))
+                        .on_press(Message::AddSyncFolder)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                    sync_button,
+                    ui_text(sync_status).size(11).color(label_color),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+                ui_text(tr("When both sides changed"))
+                    .size(11)
+                    .color(label_color),
+                row(conflict_policies).spacing(8),
+                ui_text(tr("Schedule")).size(11).color(label_color),
+                row(intervals).spacing(8),
+            ]
+            .spacing(6)
+            .into(),
+            SettingsPage::Library => column![
+                ui_text(tr("Library catalog")).size(12).color(label_color),
+                catalog_folders,
+                row![
+                    button(ui_text(tr("Add folder…")).size(12))
+                        .on_press(Message::AddCatalogFolder)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                    button(ui_text(tr("Reindex")).size(12))
+                        .on_press_maybe(
+                            (self.catalog.is_some() && self.catalog_progress.is_none())
+                                .then_some(Message::ReindexCatalog),
+                        )
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                    ui_text(catalog_status).size(11).color(label_color),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+                ui_text(tr("Recently added")).size(11).color(label_color),
+                row(added_within).spacing(8),
+            ]
+            .spacing(6)
+            .into(),
+            SettingsPage::Plugins => column![
+                ui_text(tr("Plugins")).size(12).color(label_color),
+                ui_text(tr(
+                    "Plugins are dynamic libraries in the plugins folder. They can add filters, export targets and image information."
+                ))
+                .size(11)
+                .color(label_color),
+                plugin_list,
+            ]
+            .push_maybe(
+                no_plugins.then(|| ui_text(tr("No plugins found")).size(12).color(label_color)),
+            )
+            .push(
+                row![
+                    button(ui_text(tr("Open plugins folder")).size(12))
+                        .on_press(Message::OpenPluginsFolder)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                    button(ui_text(tr("Reload")).size(12))
+                        .on_press(Message::ReloadPlugins)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                ]
+                .spacing(8),
+            )
+            .spacing(6)
+            .into(),
+        };
+        let pages = SettingsPage::ALL.map(|page| {
+            choice_button(
+                page.name(),
+                page == self.settings_page,
+                Message::SettingsPageChanged(page),
+            )
+        });
+
+        let content = column![
+            ui_text(tr("Settings")).size(14).color(palette.heading),
+            row(pages).spacing(8),
+            // 内容较多的分页在对话框内滚动
+            container(scrollable(
+                container(page).padding(iced::padding::right(12))
+            ))
+            .max_height(460),
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Close")).size(12))
+                    .on_press(Message::ToggleSettingsDialog)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        ]
+        .spacing(12);
+
+        container(content)
+            .width(420)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_contact_sheet_dialog(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let options = self.contact_sheet;
+        let count = self.contact_sheet_sources().len();
+        let exporting = self.contact_sheet_progress.is_some();
+        let formats = SheetFormat::ALL.into_iter().map(|format| {
+            choice_button(
+                format.name(),
+                options.format == format,
+                Message::ContactSheetChanged(SheetOptions { format, ..options }),
+            )
+        });
+        let cell_sizes = CELL_SIZES.into_iter().map(|cell_size| {
+            choice_button(
+                format!("{} px", cell_size),
+                options.cell_size == cell_size,
+                Message::ContactSheetChanged(SheetOptions {
+                    cell_size,
+                    ..options
+                }),
+            )
+        });
+
+        let mut content = column![
+            ui_text(trf(
+                "Contact sheet of {} images on {} page(s)",
+                &[&count, &options.pages(count)]
+            ))
+            .size(14)
+            .color(palette.heading),
+            column![
+                ui_text(tr("Format")).size(12).color(label_color),
+                row(formats).spacing(4),
+            ]
+            .spacing(6),
+            row![
+                ui_text(tr("Columns")).size(12).color(label_color).width(96),
+                slider(1..=12, options.columns, move |columns| {
+                    Message::ContactSheetChanged(SheetOptions { columns, ..options })
+                }),
+                text(options.columns.to_string()).size(12).width(24),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                ui_text(tr("Rows per page"))
+                    .size(12)
+                    .color(label_color)
+                    .width(96),
+                slider(1..=20, options.rows, move |rows| {
+                    Message::ContactSheetChanged(SheetOptions { rows, ..options })
+                }),
+                text(options.rows.to_string()).size(12).width(24),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            column![
+                ui_text(tr("Cell size")).size(12).color(label_color),
+                row(cell_sizes).spacing(4),
+            ]
+            .spacing(6),
+            checkbox(tr("Show file names"), options.labels)
+                .text_shaping(text::Shaping::Advanced)
+                .on_toggle(move |labels| Message::ContactSheetChanged(SheetOptions {
+                    labels,
+                    ..options
+                }))
+                .size(14)
+                .text_size(12),
+        ]
+        .spacing(12);
+
+        if let Some((done, total)) = self.contact_sheet_progress {
+            content = content.push(
+                column![
+                    text(format!("{} / {}", done, total))
+                        .size(12)
+                        .color(label_color),
+                    progress_bar(0.0..=total as f32, done as f32).height(6),
+                ]
+                .spacing(4),
+            );
+        } else if let Some(status) = &self.contact_sheet_status {
+            content = content.push(ui_text(status.clone()).size(12).color(label_color));
+        }
+
+        content = content.push(
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Close")).size(12))
+                    .on_press(Message::ToggleContactSheetDialog)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(tr("Export…")).size(12))
+                    .on_press_maybe((!exporting).then_some(Message::ExportContactSheet))
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        );
+
+        container(content)
+            .width(420)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_gallery_wizard<'a>(&self, wizard: &'a GalleryWizard) -> Element<'a, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let options = &wizard.options;
+        let exporting = wizard.progress.is_some();
+        let step = match wizard.step {
+            GalleryStep::Content => 1,
+            GalleryStep::Output => 2,
+        };
+
+        let mut content = column![
+            row![
+                ui_text(trf("HTML gallery of {} images", &[&wizard.count]))
+                    .size(14)
+                    .color(palette.heading)
+                    .width(Length::Fill),
+                ui_text(trf("Step {} of {}", &[&step, &2]))
+                    .size(12)
+                    .color(label_color),
+            ]
+            .align_y(iced::Alignment::Center),
+        ]
+        .spacing(12);
+
+        match wizard.step {
+            GalleryStep::Content => {
+                let image_sizes = IMAGE_SIZES.into_iter().map(|image_size| {
+                    choice_button(
+                        format!("{} px", image_size),
+                        options.image_size == image_size,
+                        Message::GalleryOptionsChanged(GalleryOptions {
+                            image_size,
+                            ..options.clone()
+                        }),
+                    )
+                });
+                let thumbnail_sizes = THUMBNAIL_SIZES.into_iter().map(|thumbnail_size| {
+                    choice_button(
+                        format!("{} px", thumbnail_size),
+                        options.thumbnail_size == thumbnail_size,
+                        Message::GalleryOptionsChanged(GalleryOptions {
+                            thumbnail_size,
+                            ..options.clone()
+                        }),
+                    )
+                });
+                content = content.push(
+                    column![
+                        ui_text(tr("Title")).size(12).color(label_color),
+                        text_input(tr("Title"), &options.title)
+                            .on_input(move |title| {
+                                Message::GalleryOptionsChanged(GalleryOptions {
+                                    title,
+                                    ..options.clone()
+                                })
+                            })
+                            .size(12)
+                            .padding([4, 8]),
+                    ]
+                    .spacing(6),
+                );
+                content = content.push(
+                    column![
+                        ui_text(tr("Image size")).size(12).color(label_color),
+                        row(image_sizes).spacing(4),
+                    ]
+                    .spacing(6),
+                );
+                content = content.push(
+                    column![
+                        ui_text(tr("Thumbnail size")).size(12).color(label_color),
+                        row(thumbnail_sizes).spacing(4),
+                    ]
+                    .spacing(6),
+                );
+                content = content.push(
+                    checkbox(
+                        tr("Captions from titles and descriptions"),
+                        options.captions,
+                    )
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(move |captions| {
+                        Message::GalleryOptionsChanged(GalleryOptions {
+                            captions,
+                            ..options.clone()
+                        })
+                    })
+                    .size(14)
+                    .text_size(12),
+                );
+            }
+            GalleryStep::Output => {
+                let destination = match &wizard.output {
+                    Some(dir) => dir.display().to_string(),
+                    None => tr("No folder chosen").to_string(),
+                };
+                content = content.push(view_encode_options(
+                    ExportFormat::for_web(),
+                    options.encode,
+                    Message::GalleryEncodeChanged,
+                    palette,
+                ));
+                content = content.push(
+                    column![
+                        ui_text(tr("Destination")).size(12).color(label_color),
+                        row![
+                            ui_text(destination).size(12).width(Length::Fill),
+                            button(ui_text(tr("Choose folder…")).size(12))
+                                .on_press_maybe(
+                                    (!exporting).then_some(Message::ChooseGalleryFolder)
+                                )
+                                .style(button_style::default)
+                                .padding([4, 8]),
+                        ]
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center),
+                    ]
+                    .spacing(6),
+                );
+            }
+        }
+
+        if let Some((done, total)) = wizard.progress {
+            content = content.push(
+                column![
+                    text(format!("{} / {}", done, total))
+                        .size(12)
+                        .color(label_color),
+                    progress_bar(0.0..=total as f32, done as f32).height(6),
+                ]
+                .spacing(4),
+            );
+        } else if let Some(index) = &wizard.index {
+            content = content.push(
+                row![
+                    ui_text(trf("Gallery saved to {}", &[&index.display()]))
+                        .size(12)
+                        .color(label_color)
+                        .width(Length::Fill),
+                    button(ui_text(tr("Open in browser")).size(12))
+                        .on_press(Message::OpenGallery)
+                        .style(button_style::default)
+                        .padding([4, 8]),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            );
+        } else if let Some(error) = &wizard.error {
+            content = content.push(
+                ui_text(trf("Export failed: {}", &[error]))
+                    .size(12)
+                    .color(palette.danger),
+            );
+        }
+        // 导出失败的图片及原因
+        for (path, error) in &wizard.failures {
+            content = content.push(
+                text(format!("✕ {}: {}", file_name(path), error))
+                    .shaping(text::Shaping::Advanced)
+                    .size(11)
+                    .color(palette.danger),
+            );
+        }
+
+        let mut buttons = row![
+            container(text("")).width(Length::Fill),
+            button(ui_text(tr("Close")).size(12))
+                .on_press(Message::CloseGalleryWizard)
+                .style(button_style::default)
+                .padding([6, 12]),
+        ]
+        .spacing(8);
+        buttons = match wizard.step {
+            GalleryStep::Content => buttons.push(
+                button(ui_text(tr("Continue")).size(12))
+                    .on_press(Message::GalleryStepChanged(GalleryStep::Output))
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ),
+            GalleryStep::Output => buttons
+                .push(
+                    button(ui_text(tr("Back")).size(12))
+                        .on_press_maybe(
+                            (!exporting)
+                                .then_some(Message::GalleryStepChanged(GalleryStep::Content)),
+                        )
+                        .style(button_style::default)
+                        .padding([6, 12]),
+                )
+                .push(
+                    button(ui_text(tr("Export")).size(12))
+                        .on_press_maybe(
+                            (!exporting && wizard.output.is_some() && wizard.count > 0)
+                                .then_some(Message::ExportGallery),
+                        )
+                        .style(button_style::primary)
+                        .padding([6, 12]),
+                ),
+        };
+        content = content.push(buttons);
+
+        container(content)
+            .width(420)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_convert_dialog(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let settings = &self.convert_settings;
+        let targets = self.convert_targets();
+        let converting = self.convert_progress.is_some();
+
+        let title = if targets.len() == 1 {
+            let name = targets[0].file_name().unwrap_or_default().to_string_lossy();
+            trf("Convert {}", &[&name])
+        } else {
+            trf("Convert {} images", &[&targets.len()])
+        };
+        let destination = match &settings.output_dir {
+            Some(dir) => dir.display().to_string(),
+            None => tr("Next to originals").to_string(),
+        };
+
+        let mut content = column![
+            ui_text(title).size(14).color(palette.heading),
+            view_encode_options(
+                ExportFormat::all(),
+                settings.encode,
+                Message::ConvertEncodeChanged,
+                palette
+            ),
+            column![
+                ui_text(tr("Destination")).size(12).color(label_color),
+                ui_text(destination).size(12),
+                row![
+                    choice_button(
+                        tr("Next to originals"),
+                        settings.output_dir.is_none(),
+                        Message::ConvertNextToOriginals
+                    ),
+                    choice_button(
+                        tr("Choose folder…"),
+                        settings.output_dir.is_some(),
+                        Message::ChooseConvertFolder
+                    ),
+                ]
+                .spacing(4),
+            ]
+            .spacing(6),
+        ]
+        .spacing(12);
+
+        if let Some((done, total)) = self.convert_progress {
+            content = content.push(
+                column![
+                    text(format!("{} / {}", done, total))
+                        .size(12)
+                        .color(label_color),
+                    progress_bar(0.0..=total as f32, done as f32).height(6),
+                ]
+                .spacing(4),
+            );
+        } else if let Some(status) = &self.convert_status {
+            content = content.push(text(status.clone()).size(12).color(label_color));
+        }
+        // 失败的图片及原因
+        for (path, error) in &self.convert_failures {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            content = content.push(
+                text(format!("✕ {}: {}", name, error))
+                    .shaping(text::Shaping::Advanced)
+                    .size(11)
+                    .color(palette.danger),
+            );
+        }
+
+        content = content.push(
+            row![
+                container(text("")).width(Length::Fill),
+                button(ui_text(tr("Close")).size(12))
+                    .on_press(Message::ToggleConvertDialog)
+                    .style(button_style::default)
+                    .padding([6, 12]),
+                button(ui_text(tr("Convert")).size(12))
+                    .on_press_maybe((!converting).then_some(Message::StartConvert))
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        );
+
+        container(content)
+            .width(420)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_adjust_panel(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let adjustments = self.adjustments;
+        let adjust_slider =
+            |label: &'static str,
+             range: std::ops::RangeInclusive<f32>,
+             step: f32,
+             value: f32,
+             on_change: fn(Adjustments, f32) -> Adjustments| {
+                column![
+                    row![
+                        ui_text(tr(label)).size(12).color(label_color),
+                        container(text("")).width(Length::Fill),
+                        text(format!("{:.2}", value)).size(12).color(label_color),
+                    ],
+                    slider(range, value, move |v| {
+                        Message::AdjustmentsChanged(on_change(adjustments, v))
+                    })
+                    .step(step),
+                ]
+                .spacing(4)
+            };
+
+        let filter_buttons = std::iter::once(None)
+            .chain(Filter::all().into_iter().map(Some))
+            .map(|filter| {
+                choice_button(
+                    filter.map_or(tr("None"), Filter::name),
+                    filter == adjustments.filter,
+                    Message::AdjustmentsChanged(Adjustments {
+                        filter,
+                        ..adjustments
+                    }),
+                )
+            });
+
+        let small_button = |label: &'static str, message: Option<Message>| {
+            button(ui_text(tr(label)).size(12))
+                .on_press_maybe(message)
+                .style(button_style::default)
+                .padding([4, 8])
+        };
+        let has_image = self.original.is_some();
+
+        let content = column![
+            row![
+                ui_text(tr("Adjust"))
+                    .size(14)
+                    .color(palette.heading)
+                    .width(Length::Fill),
+                small_button("↶ Undo", self.history.can_undo().then_some(Message::Undo)),
+                small_button("↷ Redo", self.history.can_redo().then_some(Message::Redo)),
+            ]
+            .spacing(4)
+            .align_y(iced::Alignment::Center),
+            row![
+                ui_text(tr("Rotate"))
+                    .size(12)
+                    .color(label_color)
+                    .width(Length::Fill),
+                small_button("⟲ Left", has_image.then_some(Message::Rotate(Rotation::Left))),
+                small_button(
+                    "⟳ Right",
+                    has_image.then_some(Message::Rotate(Rotation::Right))
+                ),
+            ]
+            .spacing(4)
+            .align_y(iced::Alignment::Center),
+            adjust_slider(
+                "Brightness",
+                -100.0..=100.0,
+                1.0,
+                adjustments.brightness,
+                |a, brightness| Adjustments { brightness, ..a }
+            ),
+            adjust_slider(
+                "Contrast",
+                -100.0..=100.0,
+                1.0,
+                adjustments.contrast,
+                |a, contrast| Adjustments { contrast, ..a }
+            ),
+            adjust_slider(
+                "Saturation",
+                -100.0..=100.0,
+                1.0,
+                adjustments.saturation,
+                |a, saturation| Adjustments { saturation, ..a }
+            ),
+            adjust_slider("Gamma", 0.2..=3.0, 0.05, adjustments.gamma, |a, gamma| {
+                Adjustments { gamma, ..a }
+            }),
+            adjust_slider("Blur", 0.0..=20.0, 0.5, adjustments.blur, |a, blur| {
+                Adjustments { blur, ..a }
+            }),
+            adjust_slider(
+                "Sharpen",
+                0.0..=100.0,
+                1.0,
+                adjustments.sharpen,
+                |a, sharpen| Adjustments { sharpen, ..a }
+            ),
+            column![
+                ui_text(tr("Filter")).size(12).color(label_color),
+                row(filter_buttons).spacing(4).wrap(),
+            ]
+            .spacing(6),
+            ui_text(tr("Edits are applied to exports and crops, the file on disk is unchanged. Ctrl+Z to undo."))
+                .size(11)
+                .color(label_color),
+            row![
+                choice_button(
+                    tr("Auto enhance"),
+                    adjustments.auto_levels,
+                    Message::AdjustmentsChanged(Adjustments {
+                        auto_levels: !adjustments.auto_levels,
+                        ..adjustments
+                    })
+                ),
+                button(ui_text(tr("Reset")).size(12))
+                    .on_press_maybe(
+                        (!adjustments.is_identity()).then_some(Message::ResetAdjustments)
+                    )
+                    .style(button_style::default)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        ]
+        .spacing(10);
+
+        container(content)
+            .width(260)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_find_bar(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let scope = if self.catalog.is_some() {
+            tr("Searching all indexed folders")
+        } else {
+            tr("Searching the current folder and its subfolders")
+        };
+        let content = column![
+            row![
+                text_input(tr("Find files by name…"), &self.find_query)
+                    .id(text_input::Id::new(FIND_INPUT))
+                    .on_input(Message::FindQueryChanged)
+                    .on_submit(Message::Find)
+                    .size(12)
+                    .padding([4, 8])
+                    .width(Length::Fill),
+                checkbox(tr("Regex"), self.find_regex)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(Message::FindRegexToggled)
+                    .size(14)
+                    .text_size(12),
+                button(ui_text(tr(if self.finding { "Searching…" } else { "Find" })).size(12))
+                    .on_press_maybe((!self.finding).then_some(Message::Find))
+                    .style(button_style::primary)
+                    .padding([4, 12]),
+                button(text("✕").shaping(text::Shaping::Advanced).size(11))
+                    .style(button_style::transparent)
+                    .padding([4, 6])
+                    .on_press(Message::ToggleFindBar),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            ui_text(scope).size(11).color(palette.muted),
+        ]
+        .spacing(6);
+
+        container(content)
+            .width(480)
+            .padding(12)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_search_panel(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let input = |placeholder: &'static str, value: &str, on_input: fn(String) -> Message| {
+            text_input(tr(placeholder), value)
+                .on_input(on_input)
+                .on_submit(Message::ApplySearch)
+                .size(12)
+                .padding([4, 8])
+        };
+        let range_row = |label: &'static str, min, max| {
+            column![
+                ui_text(tr(label)).size(12).color(label_color),
+                row![min, max].spacing(8)
+            ]
+            .spacing(4)
+        };
+
+        let query = &self.search_query;
+        let content = column![
+            ui_text(tr("Search by metadata"))
+                .size(14)
+                .color(palette.heading),
+            column![
+                ui_text(tr("Camera")).size(12).color(label_color),
+                input(
+                    "e.g. Canon EOS R5",
+                    &query.camera,
+                    Message::SearchCameraChanged
+                )
+            ]
+            .spacing(4),
+            range_row(
+                "ISO",
+                input("Min", &query.iso_min, Message::SearchIsoMinChanged),
+                input("Max", &query.iso_max, Message::SearchIsoMaxChanged)
+            ),
+            range_row(
+                "Focal length (mm)",
+                input("Min", &query.focal_min, Message::SearchFocalMinChanged),
+                input("Max", &query.focal_max, Message::SearchFocalMaxChanged)
+            ),
+            column![
+                ui_text(tr("Keyword")).size(12).color(label_color),
+                input("Keyword", &query.keyword, Message::SearchKeywordChanged)
+            ]
+            .spacing(4),
+        ]
+        .spacing(10);
+        // 加入了图片目录时可以搜索整个图片库
+        let content = if self.catalog.is_some() {
+            content.push(
+                checkbox(tr("Search the whole library"), self.search_library)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(Message::SearchLibraryToggled)
+                    .size(14)
+                    .text_size(12),
+            )
+        } else {
+            content
+        };
+        let content = content.push(
+            row![
+                button(ui_text(tr("Search")).size(12))
+                    .on_press(Message::ApplySearch)
+                    .style(button_style::primary)
+                    .padding([6, 12]),
+                button(ui_text(tr("Clear")).size(12))
+                    .on_press_maybe(self.active_search.as_ref().map(|_| Message::ClearSearch))
+                    .style(button_style::default)
+                    .padding([6, 12]),
+            ]
+            .spacing(8),
+        );
+
+        container(content)
+            .width(280)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    fn view_info_panel(&self) -> Element<'_, Message> {
+        let palette = self.palette();
+        let label_color = palette.muted;
+        let value_color = palette.text;
+        let info_row = |label: &'static str, value: String| -> Element<'_, Message> {
+            row![
+                ui_text(tr(label)).size(12).color(label_color).width(90),
+                ui_text(value).size(12).color(value_color)
+            ]
+            .spacing(8)
+            .into()
+        };
+
+        let mut content =
+            column![ui_text(tr("Image Info")).size(14).color(palette.heading)].spacing(8);
+
+        if let Some(path) = &self.current_image {
+            content = content.push(info_row(
+                "File",
+                path.file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ));
+        }
+        if let Some(ori) = &self.original {
+            let (w, h) = self.full_size.unwrap_or(ori.dimensions());
+            content = content.push(info_row("Dimensions", format!("{} × {}", w, h)));
+        }
+        if let Some(texture) = &self.texture {
+            content = content.push(info_row("Format", texture.format.clone()));
+            content = content.push(info_row("Mip levels", texture.levels.to_string()));
+            if texture.levels > 1 {
+                let levels = (0..texture.levels).map(|level| {
+                    let (w, h) = texture.level_size(level);
+                    choice_button(
+                        format!("{}: {} × {}", level, w, h),
+                        level == self.mip_level,
+                        Message::MipLevelChanged(level),
+                    )
+                });
+                content = content.push(row(levels).spacing(4).wrap());
+            }
+        }
+        if !self.psd_layers.is_empty() {
+            let layers = self
+                .psd_layers
+                .iter()
+                .map(|name| ui_text(name.as_str()).size(12).color(value_color).into());
+            content = content.push(
+                row![
+                    ui_text(tr("Layers")).size(12).color(label_color).width(90),
+                    column(layers).spacing(2)
+                ]
+                .spacing(8),
+            );
+        }
+
+        match &self.exif_info {
+            Some(exif) if !exif.is_empty() => {
+                let camera = [exif.camera_make.clone(), exif.camera_model.clone()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !camera.is_empty() {
+                    content = content.push(info_row("Camera", camera));
+                }
+                if let Some(date_time) = &exif.date_time {
+                    content = content.push(info_row("Taken", date_time.clone()));
+                }
+                if let Some(exposure) = &exif.exposure_time {
+                    content = content.push(info_row("Exposure", exposure.clone()));
+                }
+                if let Some(f_number) = &exif.f_number {
+                    content = content.push(info_row("Aperture", f_number.clone()));
+                }
+                if let Some(iso) = exif.iso {
+                    content = content.push(info_row("ISO", iso.to_string()));
+                }
+                if let Some(focal_length) = &exif.focal_length {
+                    content = content.push(info_row("Focal length", focal_length.clone()));
+                }
+                if self.orientation != Orientation::NoTransforms {
+                    content = content.push(info_row(
+                        "Orientation",
+                        orientation_name(self.orientation).to_string(),
+                    ));
+                }
+                if let Some(gps) = exif.gps {
+                    content = content.push(info_row("Location", gps.display()));
+                    if let Some(altitude) = gps.altitude {
+                        content = content.push(info_row("Altitude", format!("{:.1} m", altitude)));
+                    }
+                    content = content.push(
+                        button(ui_text(tr("Open in maps")).size(12))
+                            .on_press(Message::OpenUrl(gps.map_url()))
+                            .style(button_style::highlighted)
+                            .padding([6, 12]),
+                    );
+                }
+            }
+            _ => {
+                content = content.push(ui_text(tr("No EXIF data")).size(12).color(label_color));
+            }
+        }
+
+        if self.orientation != Orientation::NoTransforms {
+            content = content.push(
+                checkbox(tr("Show raw orientation"), self.show_raw_orientation)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(|_| Message::ToggleRawOrientation)
+                    .size(14)
+                    .text_size(12),
+            );
+        }
+
+        // 插件提供的信息，名称由插件决定，不翻译
+        for (name, value) in &self.plugin_metadata {
+            content = content.push(
+                row![
+                    ui_text(name.as_str()).size(12).color(label_color).width(90),
+                    ui_text(value.as_str()).size(12).color(value_color)
+                ]
+                .spacing(8),
+            );
+        }
+
+        if let Some(metadata) = &self.descriptive_metadata {
+            if let Some(title) = &metadata.title {
+                content = content.push(info_row("Title", title.clone()));
+            }
+            if let Some(description) = &metadata.description {
+                content = content.push(info_row("Description", description.clone()));
+            }
+            if let Some(copyright) = &metadata.copyright {
+                content = content.push(info_row("Copyright", copyright.clone()));
+            }
+            if let Some(rating) = metadata.rating {
+                content = content.push(info_row("Rating", format!("{} / 5", rating)));
+            }
+            if !metadata.keywords.is_empty() {
+                // 点击关键词即按该关键词筛选图片库
+                let keywords = metadata.keywords.iter().map(|keyword| {
+                    button(text(keyword.clone()).size(11))
+                        .on_press(Message::FilterByKeyword(keyword.clone()))
+                        .style(button_style::default)
+                        .padding([2, 6])
+                        .into()
+                });
+                content = content.push(
+                    column![
+                        ui_text(tr("Keywords")).size(12).color(label_color),
+                        row(keywords).spacing(4).wrap()
+                    ]
+                    .spacing(4),
+                );
+            }
+        }
+
+        // 导出副本：去除元数据或修改作者/版权/拍摄时间
+        let edit_input =
+            |placeholder: &'static str, value: &str, on_input: fn(String) -> Message| {
+                let input = text_input(tr(placeholder), value).size(12).padding([4, 8]);
+                if self.strip_metadata {
+                    input
+                } else {
+                    input.on_input(on_input)
+                }
+            };
+        content = content.push(
+            column![
+                ui_text(tr("Export copy")).size(14).color(palette.heading),
+                checkbox(tr("Strip all metadata"), self.strip_metadata)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(Message::StripMetadataToggled)
+                    .size(14)
+                    .text_size(12),
+                edit_input(
+                    "Artist",
+                    &self.metadata_edits.artist,
+                    Message::MetadataArtistChanged
+                ),
+                edit_input(
+                    "Copyright",
+                    &self.metadata_edits.copyright,
+                    Message::MetadataCopyrightChanged
+                ),
+                edit_input(
+                    "Capture date (YYYY:MM:DD HH:MM:SS)",
+                    &self.metadata_edits.date_time,
+                    Message::MetadataDateChanged
+                ),
+                button(ui_text(tr("Export copy…")).size(12))
+                    .on_press_maybe(self.current_image.as_ref().map(|_| Message::ExportCopy))
+                    .style(button_style::default)
+                    .padding([6, 12]),
+            ]
+            .spacing(6),
+        );
+
+        container(content)
+            .width(280)
+            .padding(16)
+            .style(move |_theme| container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 4.0),
+                    blur_radius: 12.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    // 图片的悬停提示：完整路径，生成过缩略图的再加上尺寸和文件大小
+    fn view_hover_preview<'a>(&self, path: &Path, handle: &Handle) -> Element<'a, Message> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dimensions = self
+            .file_info
+            .get(path)
+            .and_then(|info| info.dimensions)
+            .map(|(width, height)| format!("{width} × {height}"));
+        container(
+            column![iced::widget::image(handle.clone()), ui_text(name).size(12),]
+                .push_maybe(dimensions.map(|dimensions| ui_text(dimensions).size(11)))
+                .spacing(4)
+                .align_x(iced::Alignment::Center),
+        )
+        .padding(6)
+        .style(container::rounded_box)
+        .into()
+    }
+
+    fn file_tooltip(&self, path: &Path) -> String {
+        let mut tip = path.display().to_string();
+        if let Some(info) = self.file_info.get(path) {
+            let size = memory::format_bytes(info.bytes as usize);
+            tip.push('\n');
+            match info.dimensions {
+                Some((width, height)) => tip.push_str(&format!("{width} × {height} · {size}")),
+                None => tip.push_str(&size),
+            }
+        }
+        tip
+    }
+
+    /// folder 为这一项所在的智能文件夹，其中的图片可以右键打开菜单
+    fn view_file_tree<'a>(
+        &'a self,
+        entry: &'a FileTreeEntry,
+        level: usize,
+        folder: Option<&'a Path>,
+    ) -> Element<'a, Message> {
+        let palette = self.palette();
+        let indent = (level as f32) * 16.0;
+
+        let (icon, name, on_press_msg) = match entry {
+            FileTreeEntry::Directory {
+                path,
+                name,
+                expanded,
+                link,
+                access,
+                ..
+            } => {
+                let folder_icon = match (access, link) {
+                    (Access::Denied, _) => "🔒",
+                    (_, Some(_)) => "🔗",
+                    _ if *expanded => "📂",
+                    _ => "📁",
+                };
+                // 智能文件夹的名称按当前语言显示
+                let name = smart_folder_name(path).map_or_else(|| name.clone(), str::to_string);
+                (folder_icon, name, Message::ExpandDirectory(path.clone()))
+            }
+            FileTreeEntry::File { path, name } => {
+                ("🖼", name.clone(), Message::PickImage(path.clone()))
+            }
+        };
+
+        let item = button(
+            row![
+                text(icon).shaping(text::Shaping::Advanced).size(14),
+                text(name)
+                    .shaping(text::Shaping::Advanced)
+                    .size(13)
+                    .color(palette.heading)
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press(on_press_msg)
+        .width(Length::Fill)
+        .style(button_style::sidebar_item)
+        .padding([6, 8]);
+        // 悬停时显示完整路径，看过的图片再加上浏览次数
+        let item: Element<_> = match entry {
+            FileTreeEntry::File { path, .. } => {
+                let mut tip = self.file_tooltip(path);
+                if let Some(recent) = self.recent_manager.get(path) {
+                    let last_viewed = recent.last_viewed().format("%Y-%m-%d %H:%M");
+                    tip.push('\n');
+                    tip.push_str(&match recent.view_count() {
+                        1 => trf("Viewed once · last {}", &[&last_viewed]),
+                        count => trf("Viewed {} times · last {}", &[&count, &last_viewed]),
+                    });
+                }
+                hover_tip(item, tip, tooltip::Position::Right)
+            }
+            FileTreeEntry::Directory {
+                path, link, access, ..
+            } if smart_folder_name(path).is_none() => {
+                let mut tip = path.display().to_string();
+                if let Some(target) = link {
+                    tip.push_str(&format!("\n→ {}", target.display()));
+                }
+                match access {
+                    Access::Denied => tip.push_str(&format!("\n{}", tr("Permission denied"))),
+                    Access::Loop => tip.push_str(&format!("\n{}", tr("Links to a parent folder"))),
+                    Access::Readable => {}
+                }
+                hover_tip(item, tip, tooltip::Position::Right)
+            }
+            _ => item.into(),
+        };
+        // 选中的云端文件后面显示重命名和删除按钮
+        let item = match entry {
+            FileTreeEntry::File { path, .. } if self.cloud_selected.as_ref() == Some(path) => {
+                let key = cloud::object_key(path).unwrap_or_default();
+                let action = |icon: &'static str, message: Message| {
+                    button(text(icon).shaping(text::Shaping::Advanced).size(12))
+                        .style(button_style::transparent)
+                        .padding([4, 6])
+                        .on_press(message)
+                };
+                row![
+                    item,
+                    action("✎", Message::RenameRemoteObject(key.clone())),
+                    action("🗑", Message::DeleteRemoteObject(key)),
+                ]
+                .spacing(2)
+                .align_y(iced::Alignment::Center)
+                .into()
+            }
+            // Recents 后面显示清空按钮
+            FileTreeEntry::Directory { path, .. }
+                if path.as_os_str() == smart_directory::RECENTS
+                    && !self.recent_manager.get_recent_items().is_empty() =>
+            {
+                row![
+                    item,
+                    button(ui_text(tr("Clear")).size(11))
+                        .style(button_style::transparent)
+                        .padding([4, 6])
+                        .on_press(Message::ClearRecents),
+                ]
+                .spacing(2)
+                .align_y(iced::Alignment::Center)
+                .into()
+            }
+            _ => item,
+        };
+        let item = match (entry, folder) {
+            // 本地文件夹的右键菜单
+            (FileTreeEntry::Directory { path, .. }, _)
+                if !smart_directory::is_smart_folder(path)
+                    && !catalog::is_library(path)
+                    && !cloud::is_remote(path) =>
+            {
+                let pinned = self.config.pinned_folders.contains(path);
+                let path = path.clone();
+                context_menu(item, move || {
+                    menu(vec![
+                        (
+                            if pinned { "Unpin folder" } else { "Pin folder" },
+                            Message::TogglePinFolder(path.clone()),
+                        ),
+                        ("Refresh", Message::RefreshFolder(path.clone())),
+                        ("New folder…", Message::NewFolder(path.clone())),
+                        ("Open in new window", Message::OpenInNewWindow(path.clone())),
+                        (
+                            "Export HTML gallery…",
+                            Message::OpenGalleryWizard(path.clone()),
+                        ),
+                    ])
+                })
+                .into()
+            }
+            (FileTreeEntry::File { path, .. }, Some(folder)) => iced::widget::mouse_area(item)
+                .on_right_press(Message::ToggleRecentMenu(
+                    folder.to_path_buf(),
+                    path.clone(),
+                ))
+                .into(),
+            _ => item,
+        };
+
+        let item_button = container(item).padding(iced::Padding {
+            top: 1.0,
+            right: 0.0,
+            bottom: 1.0,
+            left: indent,
+        });
+
+        let mut item_column = column![item_button];
+
+        // 浏览记录的右键菜单显示在这一项下面
+        if let (FileTreeEntry::File { path, .. }, Some(folder)) = (entry, folder)
+            && self
+                .recent_menu
+                .as_ref()
+                .is_some_and(|(menu_folder, menu_path)| menu_folder == folder && menu_path == path)
+        {
+            let pinned = self
+                .recent_manager
+                .get(path)
+                .is_some_and(|item| item.is_pinned());
+            let action = |label: &'static str, message: Message| {
+                button(ui_text(tr(label)).size(12))
+                    .width(Length::Fill)
+                    .style(button_style::transparent)
+                    .padding([4, 8])
+                    .on_press(message)
+            };
+            let menu = column![
+                action("Remove from Recents", Message::RemoveRecent(path.clone())),
+                action(
+                    if pinned { "Unpin" } else { "Pin to top" },
+                    Message::TogglePinRecent(path.clone()),
+                ),
+                action(
+                    "Open containing folder",
+                    Message::OpenContainingFolder(path.clone()),
+                ),
+                action("Copy path", Message::CopyPath(path.clone())),
+                action("Open in new window", Message::OpenInNewWindow(path.clone())),
+            ];
+            item_column = item_column.push(
+                container(container(menu).padding(4).style(container::rounded_box)).padding(
+                    iced::Padding {
+                        top: 0.0,
+                        right: 0.0,
+                        bottom: 4.0,
+                        left: indent + 16.0,
+                    },
+                ),
+            );
+        }
+
+        if let FileTreeEntry::Directory {
+            path,
+            expanded,
+            children,
+            ..
+        } = entry
+            && *expanded
+        {
+            let folder = smart_directory::is_smart_folder(path).then_some(path.as_path());
+            for child_entry in children.iter() {
+                item_column = item_column.push(self.view_file_tree(child_entry, level + 1, folder));
+            }
+        }
+
+        item_column.into()
+    }
+}
+
+// 界面文字使用 Advanced 排版，中文等译文才能回退到系统中带有这些字形的字体
+fn ui_text<'a>(content: impl text::IntoFragment<'a>) -> text::Text<'a> {
+    text(content).shaping(text::Shaping::Advanced)
+}
+
+// 悬停时显示的文字提示
+fn hover_tip<'a>(
+    content: impl Into<Element<'a, Message>>,
+    tip: String,
+    position: tooltip::Position,
+) -> Element<'a, Message> {
+    tooltip(
+        content,
+        container(ui_text(tip).size(11))
+            .padding([4, 8])
+            .style(container::rounded_box),
+        position,
+    )
+    .into()
+}
+
+// 右键菜单的内容，每一项是要翻译的文字和点击后的消息
+fn menu<'a>(items: Vec<(&'static str, Message)>) -> Element<'a, Message> {
+    let items = items.into_iter().map(|(label, message)| {
+        button(ui_text(tr(label)).size(12))
+            .width(Length::Fill)
+            .style(button_style::transparent)
+            .padding([4, 8])
+            .on_press(message)
+            .into()
+    });
+    container(column(items))
+        .width(200)
+        .padding(4)
+        .style(|theme| {
+            let palette = palette::of(theme);
+            container::Style {
+                background: Some(Background::Color(palette.surface)),
+                border: iced::Border {
+                    radius: 6.0.into(),
+                    width: 1.0,
+                    color: palette.border,
+                },
+                shadow: iced::Shadow {
+                    offset: Vector::new(0.0, 2.0),
+                    blur_radius: 8.0,
+                    color: palette.shadow,
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+}
+
+// 单选按钮组中的一项，选中时高亮
+fn choice_button<'a>(
+    label: impl text::IntoFragment<'a>,
+    selected: bool,
+    message: Message,
+) -> Element<'a, Message> {
+    button(ui_text(label).size(12))
+        .padding([6, 12])
+        .style(move |theme, status| {
+            if selected {
+                button_style::primary(theme, status)
+            } else {
+                button_style::default(theme, status)
+            }
+        })
+        .on_press(message)
+        .into()
+}
+
+// 导出和格式转换共用的编码参数设置
+fn view_encode_options(
+    formats: Vec<ExportFormat>,
+    options: EncodeOptions,
+    on_change: fn(EncodeOptions) -> Message,
+    palette: Palette,
+) -> Element<'static, Message> {
+    let label_color = palette.muted;
+    let formats = formats.into_iter().map(|format| {
+        choice_button(
+            format.name(),
+            format == options.format,
+            on_change(EncodeOptions { format, ..options }),
+        )
+    });
+    let mut content = column![
+        ui_text(tr("Format")).size(12).color(label_color),
+        row(formats).spacing(4),
+    ]
+    .spacing(6);
+
+    match options.format {
+        ExportFormat::Png => {
+            let levels = PngCompression::all().into_iter().map(|png_compression| {
+                choice_button(
+                    png_compression.name(),
+                    png_compression == options.png_compression,
+                    on_change(EncodeOptions {
+                        png_compression,
+                        ..options
+                    }),
+                )
+            });
+            content = content.push(ui_text(tr("Compression")).size(12).color(label_color));
+            content = content.push(row(levels).spacing(4));
+        }
+        ExportFormat::WebP => {
+            content = content.push(
+                checkbox(tr("Lossless"), options.webp_lossless)
+                    .text_shaping(text::Shaping::Advanced)
+                    .on_toggle(move |webp_lossless| {
+                        on_change(EncodeOptions {
+                            webp_lossless,
+                            ..options
+                        })
+                    })
+                    .size(14)
+                    .text_size(12),
+            );
+        }
+        ExportFormat::Jpeg | ExportFormat::Qoi => {}
+    }
+
+    if options.has_quality() {
+        content = content.push(
+            ui_text(trf("Quality: {}", &[&options.quality]))
+                .size(12)
+                .color(label_color),
+        );
+        content = content.push(slider(1..=100, options.quality, move |quality| {
+            on_change(EncodeOptions { quality, ..options })
+        }));
+    }
+
+    content.into()
+}
+
+fn orientation_name(orientation: Orientation) -> &'static str {
+    match orientation {
+        Orientation::NoTransforms => tr("Normal"),
+        Orientation::Rotate90 => tr("Rotated 90° CW"),
+        Orientation::Rotate180 => tr("Rotated 180°"),
+        Orientation::Rotate270 => tr("Rotated 90° CCW"),
+        Orientation::FlipHorizontal => tr("Mirrored horizontally"),
+        Orientation::FlipVertical => tr("Mirrored vertically"),
+        Orientation::Rotate90FlipH => tr("Rotated 90° CW, mirrored"),
+        Orientation::Rotate270FlipH => tr("Rotated 90° CCW, mirrored"),
+    }
+}
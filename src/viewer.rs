@@ -0,0 +1,52 @@
+use iced::widget::image::Handle;
+use std::path::PathBuf;
+
+/// 另外打开的查看窗口的状态，只用来浏览图片，编辑和面板都在主窗口中
+#[derive(Debug, Clone, Default)]
+pub struct Viewer {
+    pub images: Vec<PathBuf>,             // 这个窗口中浏览的图片
+    pub index: usize,                     // 当前图片在 images 中的位置
+    pub folder: Option<PathBuf>,          // 打开的是文件夹时，正在扫描或已经扫描的文件夹
+    pub scan: Option<iced::task::Handle>, // 文件夹扫描任务，窗口关闭时取消
+}
+
+impl Viewer {
+    /// 浏览一组图片，从 index 开始
+    pub fn with_images(images: Vec<PathBuf>, index: usize) -> Viewer {
+        Viewer {
+            index: index.min(images.len().saturating_sub(1)),
+            images,
+            ..Viewer::default()
+        }
+    }
+
+    /// 浏览文件夹中的图片，扫描结果到达后填入
+    pub fn with_folder(folder: PathBuf, scan: iced::task::Handle) -> Viewer {
+        Viewer {
+            folder: Some(folder),
+            scan: Some(scan.abort_on_drop()),
+            ..Viewer::default()
+        }
+    }
+
+    pub fn current(&self) -> Option<&PathBuf> {
+        self.images.get(self.index)
+    }
+
+    /// 当前图片，由 iced 按路径解码
+    pub fn handle(&self) -> Option<Handle> {
+        self.current().map(Handle::from_path)
+    }
+
+    pub fn next(&mut self) {
+        if !self.images.is_empty() {
+            self.index = (self.index + 1) % self.images.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.images.is_empty() {
+            self.index = (self.index + self.images.len() - 1) % self.images.len();
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+use std::process::Command;
+
+/// 把图片设为桌面壁纸，调用各个系统自带的命令
+pub fn set(path: &Path) -> Result<(), String> {
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    run(&path)
+}
+
+// GNOME 桌面，深色模式使用另一个键
+#[cfg(target_os = "linux")]
+fn run(path: &Path) -> Result<(), String> {
+    let uri = format!("file://{}", path.display());
+    gsettings("picture-uri", &uri)?;
+    let _ = gsettings("picture-uri-dark", &uri);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn gsettings(key: &str, value: &str) -> Result<(), String> {
+    check(
+        Command::new("gsettings")
+            .args(["set", "org.gnome.desktop.background", key, value])
+            .output(),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn run(path: &Path) -> Result<(), String> {
+    let script = format!(
+        "tell application \"System Events\" to tell every desktop to set picture to \"{}\"",
+        path.display().to_string().replace('"', "\\\"")
+    );
+    check(Command::new("osascript").args(["-e", &script]).output())
+}
+
+// 通过 PowerShell 调用 SystemParametersInfo(SPI_SETDESKWALLPAPER)
+#[cfg(target_os = "windows")]
+fn run(path: &Path) -> Result<(), String> {
+    let script = format!(
+        "Add-Type -TypeDefinition 'using System.Runtime.InteropServices; \
+         public class Wallpaper {{ [DllImport(\"user32.dll\", CharSet = CharSet.Unicode)] \
+         public static extern int SystemParametersInfo(int action, int param, string value, int flags); }}'; \
+         if ([Wallpaper]::SystemParametersInfo(20, 0, '{}', 3) -eq 0) {{ exit 1 }}",
+        path.display().to_string().replace('\'', "''")
+    );
+    check(
+        Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .output(),
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn run(_path: &Path) -> Result<(), String> {
+    Err("Setting the wallpaper is not supported on this system".to_string())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn check(output: std::io::Result<std::process::Output>) -> Result<(), String> {
+    let output = output.map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
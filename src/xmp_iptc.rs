@@ -0,0 +1,507 @@
+use std::fs;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+// 描述性元数据：来自 XMP（侧车文件或内嵌）以及 JPEG 内嵌的 IPTC
+#[derive(Debug, Clone, Default)]
+pub struct DescriptiveMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub copyright: Option<String>,
+    pub rating: Option<u8>, // XMP 中的星级（0-5）
+}
+
+impl DescriptiveMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.keywords.is_empty()
+            && self.copyright.is_none()
+            && self.rating.is_none()
+    }
+
+    pub fn has_keyword(&self, keyword: &str) -> bool {
+        self.keywords
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(keyword))
+    }
+
+    // 用 other 补全缺失的字段，关键词取并集
+    fn merge(&mut self, other: DescriptiveMetadata) {
+        if self.title.is_none() {
+            self.title = other.title;
+        }
+        if self.description.is_none() {
+            self.description = other.description;
+        }
+        if self.copyright.is_none() {
+            self.copyright = other.copyright;
+        }
+        if self.rating.is_none() {
+            self.rating = other.rating;
+        }
+        for keyword in other.keywords {
+            if !self.has_keyword(&keyword) {
+                self.keywords.push(keyword);
+            }
+        }
+    }
+}
+
+const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+
+/// 读取图片的标题、描述、关键词和版权信息
+/// 优先级：XMP 侧车文件 > 内嵌 XMP > 内嵌 IPTC
+pub fn read_descriptive_metadata(path: &Path) -> Option<DescriptiveMetadata> {
+    let mut metadata = DescriptiveMetadata::default();
+
+    if let Some(sidecar) = find_sidecar(path)
+        && let Ok(xml) = fs::read_to_string(sidecar)
+    {
+        metadata.merge(parse_xmp(&xml));
+    }
+
+    for (marker, payload) in jpeg_app_segments(path) {
+        match marker {
+            // APP1: XMP
+            0xE1 if payload.starts_with(XMP_SIGNATURE) => {
+                let xml = String::from_utf8_lossy(&payload[XMP_SIGNATURE.len()..]);
+                metadata.merge(parse_xmp(&xml));
+            }
+            // APP13: Photoshop 资源块，其中包含 IPTC
+            0xED if payload.starts_with(PHOTOSHOP_SIGNATURE) => {
+                if let Some(iptc) = find_iptc_block(&payload[PHOTOSHOP_SIGNATURE.len()..]) {
+                    metadata.merge(parse_iptc(iptc));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if metadata.is_empty() {
+        None
+    } else {
+        Some(metadata)
+    }
+}
+
+// 查找 XMP 侧车文件：photo.jpg.xmp 或 photo.xmp（darktable 与 Lightroom 两种命名），
+// 两者都有时 photo.jpg.xmp 只属于这张图片，优先使用
+pub fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    let candidates = [
+        full_sidecar(path),
+        path.with_extension("xmp"),
+        path.with_extension("XMP"),
+    ];
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+/// 只属于这张图片的侧车文件，删除或移动图片时一起处理；
+/// 有主文件名相同的其他文件时（例如 photo.cr2 和 photo.jpg）photo.xmp 是共用的，不算在内
+pub fn own_sidecar(path: &Path) -> Option<PathBuf> {
+    let sidecar = find_sidecar(path)?;
+    (sidecar == full_sidecar(path) || !has_namesake(path)).then_some(sidecar)
+}
+
+// photo.jpg.xmp
+fn full_sidecar(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".xmp");
+    PathBuf::from(name)
+}
+
+// 同一文件夹中是否有主文件名相同的其他文件（侧车文件除外）
+fn has_namesake(path: &Path) -> bool {
+    let (Some(folder), Some(stem)) = (path.parent(), path.file_stem()) else {
+        return false;
+    };
+    let Ok(entries) = fs::read_dir(folder) else {
+        return false;
+    };
+    entries.flatten().map(|entry| entry.path()).any(|other| {
+        other != path
+            && other.file_stem() == Some(stem)
+            && !other
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xmp"))
+    })
+}
+
+/// 逐段读取 JPEG 文件头中的 APPn 段，返回 (marker, payload)，遇到图像数据（SOS）时停止，不读取整个文件
+pub fn jpeg_app_segments(path: &Path) -> Vec<(u8, Vec<u8>)> {
+    let mut segments = Vec::new();
+    let Ok(file) = fs::File::open(path) else {
+        return segments;
+    };
+    let mut reader = BufReader::new(file);
+    let mut header = [0u8; 2];
+    if reader.read_exact(&mut header).is_err() || header != [0xFF, 0xD8] {
+        return segments;
+    }
+
+    let mut byte = [0u8; 1];
+    loop {
+        if reader.read_exact(&mut byte).is_err() || byte[0] != 0xFF {
+            break;
+        }
+        // 跳过填充字节
+        let marker = loop {
+            if reader.read_exact(&mut byte).is_err() {
+                return segments;
+            }
+            if byte[0] != 0xFF {
+                break byte[0];
+            }
+        };
+        // SOS 或 EOI 之后不再有元数据
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let mut len = [0u8; 2];
+        if reader.read_exact(&mut len).is_err() {
+            break;
+        }
+        let len = u16::from_be_bytes(len) as usize;
+        if len < 2 {
+            break;
+        }
+        // 其他段（量化表、霍夫曼表等）直接跳过
+        if !(0xE0..=0xEF).contains(&marker) {
+            if reader.seek_relative(len as i64 - 2).is_err() {
+                break;
+            }
+            continue;
+        }
+        let mut payload = vec![0; len - 2];
+        if reader.read_exact(&mut payload).is_err() {
+            break;
+        }
+        segments.push((marker, payload));
+    }
+    segments
+}
+
+// 在 Photoshop 资源块（8BIM）中查找 ID 为 0x0404 的 IPTC-NAA 记录
+fn find_iptc_block(mut data: &[u8]) -> Option<&[u8]> {
+    while data.len() >= 12 && &data[0..4] == b"8BIM" {
+        let id = u16::from_be_bytes([data[4], data[5]]);
+        // Pascal 字符串名称，总长度补齐到偶数
+        let name_len = data[6] as usize;
+        let mut pos = 6 + ((name_len + 2) & !1);
+        if pos + 4 > data.len() {
+            return None;
+        }
+        let size =
+            u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        pos += 4;
+        if pos + size > data.len() {
+            return None;
+        }
+        if id == 0x0404 {
+            return Some(&data[pos..pos + size]);
+        }
+        pos += size + (size & 1);
+        data = data.get(pos..)?;
+    }
+    None
+}
+
+fn parse_iptc(mut data: &[u8]) -> DescriptiveMetadata {
+    let mut metadata = DescriptiveMetadata::default();
+    while data.len() >= 5 && data[0] == 0x1C {
+        let record = data[1];
+        let dataset = data[2];
+        let len = u16::from_be_bytes([data[3], data[4]]) as usize;
+        // 不支持扩展长度（最高位为 1）的数据集
+        if len & 0x8000 != 0 || 5 + len > data.len() {
+            break;
+        }
+        let value = String::from_utf8_lossy(&data[5..5 + len])
+            .trim()
+            .to_string();
+        if record == 2 && !value.is_empty() {
+            match dataset {
+                5 => metadata.title = Some(value),
+                25 if !metadata.has_keyword(&value) => metadata.keywords.push(value),
+                116 => metadata.copyright = Some(value),
+                120 => metadata.description = Some(value),
+                _ => {}
+            }
+        }
+        data = &data[5 + len..];
+    }
+    metadata
+}
+
+fn parse_xmp(xml: &str) -> DescriptiveMetadata {
+    DescriptiveMetadata {
+        title: xmp_list(xml, "dc:title").into_iter().next(),
+        description: xmp_list(xml, "dc:description").into_iter().next(),
+        keywords: xmp_list(xml, "dc:subject"),
+        copyright: xmp_list(xml, "dc:rights").into_iter().next(),
+        rating: xmp_rating(xml),
+    }
+}
+
+// 星级可能是属性 xmp:Rating="3"，也可能是元素 <xmp:Rating>3</xmp:Rating>
+fn xmp_rating(xml: &str) -> Option<u8> {
+    // -1 表示拒绝，按没有星级处理
+    xmp_value(xml, "xmp:Rating")?
+        .trim()
+        .parse::<i8>()
+        .ok()
+        .and_then(|r| u8::try_from(r).ok())
+}
+
+// 简单属性的值：属性 name="v" 或元素 <name>v</name>
+fn xmp_value<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let attribute = format!("{}=\"", name);
+    if let Some(start) = xml.find(&attribute) {
+        let rest = &xml[start + attribute.len()..];
+        return Some(&rest[..rest.find('"')?]);
+    }
+    let open = format!("<{}>", name);
+    let start = xml.find(&open)?;
+    let rest = &xml[start + open.len()..];
+    Some(&rest[..rest.find('<')?])
+}
+
+// 读取 <dc:xxx><rdf:Alt|Bag|Seq><rdf:li>...</rdf:li></...></dc:xxx> 中的各项
+fn xmp_list(xml: &str, element: &str) -> Vec<String> {
+    let open = format!("<{}>", element);
+    let close = format!("</{}>", element);
+    let Some(start) = xml.find(&open) else {
+        return Vec::new();
+    };
+    let body = &xml[start + open.len()..];
+    let body = &body[..body.find(&close).unwrap_or(body.len())];
+
+    let mut items = Vec::new();
+    let mut rest = body;
+    while let Some(li_start) = rest.find("<rdf:li") {
+        rest = &rest[li_start..];
+        let Some(content_start) = rest.find('>') else {
+            break;
+        };
+        // 自闭合的空项
+        if rest[..content_start].ends_with('/') {
+            rest = &rest[content_start + 1..];
+            continue;
+        }
+        rest = &rest[content_start + 1..];
+        let Some(content_end) = rest.find("</rdf:li>") else {
+            break;
+        };
+        let value = unescape_xml(rest[..content_end].trim());
+        if !value.is_empty() {
+            items.push(value);
+        }
+        rest = &rest[content_end..];
+    }
+    items
+}
+
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+const XMP_NAMESPACE: &str = "http://ns.adobe.com/xap/1.0/";
+const PHOTO_MECHANIC_NAMESPACE: &str = "http://ns.camerabits.com/photomechanic/1.0/";
+
+/// 挑片标记：选中的图片按 Photo Mechanic 的方式记为 photomechanic:Tagged，
+/// 排除的图片与 Lightroom、Bridge 相同，星级记为 -1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Pick,
+    Reject,
+}
+
+// 新建侧车文件时使用的空白 XMP
+const EMPTY_SIDECAR: &str = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\">
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+";
+
+/// 给图片添加关键词，写入 XMP 侧车文件（位置见 load_sidecar），不修改图片本身
+pub fn add_sidecar_keyword(path: &Path, keyword: &str) -> Result<(), String> {
+    let (sidecar, mut xml) = load_sidecar(path)?;
+    if parse_xmp(&xml).has_keyword(keyword) {
+        return Ok(());
+    }
+    let item = format!("<rdf:li>{}</rdf:li>", escape_xml(keyword));
+    // 已有关键词列表时加到列表末尾，否则在 rdf:Description 中新建
+    let bag_end = xml
+        .find("<dc:subject>")
+        .and_then(|start| Some(start + xml[start..].find("</rdf:Bag>")?));
+    match bag_end {
+        Some(end) => xml.insert_str(end, &item),
+        None => {
+            let end = description_end(&mut xml)?;
+            xml.insert_str(
+                end,
+                &format!("<dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>\n  ", item),
+            );
+            declare_namespace(&mut xml, "dc", DC_NAMESPACE);
+        }
+    }
+    fs::write(&sidecar, xml).map_err(|e| e.to_string())
+}
+
+/// 侧车文件中的挑片标记，只读取侧车文件，不读取图片
+pub fn read_sidecar_flag(path: &Path) -> Option<Flag> {
+    let xml = fs::read_to_string(find_sidecar(path)?).ok()?;
+    if xmp_value(&xml, "xmp:Rating").is_some_and(|rating| rating.trim() == "-1") {
+        return Some(Flag::Reject);
+    }
+    xmp_value(&xml, "photomechanic:Tagged")
+        .is_some_and(|tagged| tagged.trim().eq_ignore_ascii_case("true"))
+        .then_some(Flag::Pick)
+}
+
+/// 把挑片标记写入侧车文件，None 表示清除；清除或选中时不改动已有的星级
+pub fn set_sidecar_flag(path: &Path, flag: Option<Flag>) -> Result<(), String> {
+    let (sidecar, mut xml) = load_sidecar(path)?;
+    remove_property(&mut xml, "photomechanic:Tagged");
+    if xmp_value(&xml, "xmp:Rating").is_some_and(|rating| rating.trim() == "-1") {
+        remove_property(&mut xml, "xmp:Rating");
+    }
+    let property = match flag {
+        Some(Flag::Pick) => Some((
+            "photomechanic",
+            PHOTO_MECHANIC_NAMESPACE,
+            "<photomechanic:Tagged>True</photomechanic:Tagged>",
+        )),
+        Some(Flag::Reject) => {
+            // 排除时替换原来的星级
+            remove_property(&mut xml, "xmp:Rating");
+            Some(("xmp", XMP_NAMESPACE, "<xmp:Rating>-1</xmp:Rating>"))
+        }
+        None => None,
+    };
+    if let Some((prefix, uri, element)) = property {
+        let end = description_end(&mut xml)?;
+        xml.insert_str(end, &format!("{}\n  ", element));
+        declare_namespace(&mut xml, prefix, uri);
+    }
+    fs::write(&sidecar, xml).map_err(|e| e.to_string())
+}
+
+// 读取要修改的侧车文件和写入的位置：共用的 photo.xmp 修改后写入 photo.jpg.xmp，不影响同名的其他文件；
+// 没有侧车文件时从空白的 XMP 开始，有同名的其他文件时新建 photo.jpg.xmp，否则新建 photo.xmp
+fn load_sidecar(path: &Path) -> Result<(PathBuf, String), String> {
+    let target = own_sidecar(path).unwrap_or_else(|| {
+        if has_namesake(path) {
+            full_sidecar(path)
+        } else {
+            path.with_extension("xmp")
+        }
+    });
+    let xml = match find_sidecar(path) {
+        Some(sidecar) => fs::read_to_string(sidecar).map_err(|e| e.to_string())?,
+        None => EMPTY_SIDECAR.to_string(),
+    };
+    Ok((target, xml))
+}
+
+// </rdf:Description> 的位置，新的属性插入到这里；自闭合的 rdf:Description 先展开
+fn description_end(xml: &mut String) -> Result<usize, String> {
+    if let Some(end) = xml.find("</rdf:Description>") {
+        return Ok(end);
+    }
+    let start = xml
+        .find("<rdf:Description")
+        .ok_or("Unsupported XMP sidecar")?;
+    let close = start + xml[start..].find("/>").ok_or("Unsupported XMP sidecar")?;
+    xml.replace_range(close..close + 2, ">\n  </rdf:Description>");
+    Ok(close + 4)
+}
+
+// 删除简单属性，属性和元素两种写法都删除
+fn remove_property(xml: &mut String, name: &str) {
+    let attribute = format!("{}=\"", name);
+    let mut from = 0;
+    while let Some(offset) = xml[from..].find(&attribute) {
+        let start = from + offset;
+        let value_start = start + attribute.len();
+        let Some(length) = xml[value_start..].find('"') else {
+            break;
+        };
+        // 前面必须是空白，否则是另一个名称的一部分
+        if xml[..start].ends_with(char::is_whitespace) {
+            xml.replace_range(start - 1..value_start + length + 1, "");
+            from = start - 1;
+        } else {
+            from = value_start;
+        }
+    }
+    let (open, close) = (format!("<{}>", name), format!("</{}>", name));
+    while let Some(start) = xml.find(&open) {
+        let Some(end) = xml[start..].find(&close) else {
+            break;
+        };
+        // 连同后面的换行和缩进一起删除，不留下空行
+        let end = start + end + close.len();
+        let blank = xml[end..].len() - xml[end..].trim_start().len();
+        xml.replace_range(start..end + blank, "");
+    }
+}
+
+// 在 rdf:Description 上声明还没有声明的命名空间前缀
+fn declare_namespace(xml: &mut String, prefix: &str, uri: &str) {
+    if xml.contains(&format!("xmlns:{}=", prefix)) {
+        return;
+    }
+    if let Some(start) = xml.find("<rdf:Description") {
+        xml.insert_str(
+            start + "<rdf:Description".len(),
+            &format!(" xmlns:{}=\"{}\"", prefix, uri),
+        );
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_stem_images_keep_separate_flags() {
+        let folder = std::env::temp_dir().join(format!("sidecars-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        let jpg = folder.join("photo.jpg");
+        let raw = folder.join("photo.cr2");
+        fs::write(&raw, b"").unwrap();
+        add_sidecar_keyword(&raw, "beach").unwrap();
+        assert_eq!(own_sidecar(&raw), Some(folder.join("photo.xmp")));
+        fs::write(&jpg, b"").unwrap();
+
+        // 共用的 photo.xmp 不属于任何一张，标记写入 photo.jpg.xmp
+        assert_eq!(own_sidecar(&jpg), None);
+        set_sidecar_flag(&jpg, Some(Flag::Reject)).unwrap();
+        assert_eq!(own_sidecar(&jpg), Some(folder.join("photo.jpg.xmp")));
+        assert_eq!(read_sidecar_flag(&jpg), Some(Flag::Reject));
+        assert_eq!(read_sidecar_flag(&raw), None);
+        // 分出的侧车文件保留共用文件中原有的信息
+        let metadata = read_descriptive_metadata(&jpg).unwrap();
+        assert!(metadata.has_keyword("beach"));
+
+        let _ = fs::remove_dir_all(&folder);
+    }
+}